@@ -6,7 +6,7 @@ use criterion::{black_box, criterion_group, criterion_main, Criterion, Benchmark
 use wasma_client::{
     WindowHandler, WindowGeometry, ResourceLimits, ResourceMode, ExecutionMode,
 };
-use wbackend::{Assignment, WBackend};
+use wbackend::{Assignment, CorePlacement, WBackend};
 use std::time::Duration;
 
 fn benchmark_assignment_creation(c: &mut Criterion) {
@@ -120,9 +120,12 @@ fn benchmark_resource_limits_creation(c: &mut Criterion) {
                 max_gpu_memory_mb: 1024,
                 cpu_cores: vec![0, 1, 2, 3],
                 execution_mode: Some(ExecutionMode::Hybrid),
+                core_placement: CorePlacement::Spread,
+                core_class: None,
                 lease_duration: Duration::from_secs(60),
                 renderer: "glx_renderer".to_string(),
                 pixel_load_limit: 75,
+                quota_group: None,
             };
             black_box(limits)
         });
@@ -148,9 +151,12 @@ fn benchmark_adjust_window_resources(c: &mut Criterion) {
                 max_gpu_memory_mb: 512,
                 cpu_cores: vec![0, 1],
                 execution_mode: Some(ExecutionMode::GpuPreferred),
+                core_placement: CorePlacement::Spread,
+                core_class: None,
                 lease_duration: Duration::from_secs(30),
                 renderer: "cpu_renderer".to_string(),
                 pixel_load_limit: 50,
+                quota_group: None,
             };
             handler.adjust_window_resources(black_box(window_id), black_box(new_limits)).ok();
         });
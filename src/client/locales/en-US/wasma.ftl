@@ -0,0 +1,70 @@
+# WASMA - English (United States)
+# Reference catalog: every other locale falls back to these messages.
+
+-brand = WASMA
+
+## Errors
+
+error-window-not-found = Window { $id } not found
+
+## GUI
+
+gui-title = { -brand } Window Manager
+gui-new-window = + New Window
+gui-update-resources = ⟳ Update Resources
+gui-hotkeys = ⌨ Hotkeys
+gui-preview-heavy = ◔ Preview Heavy Window
+gui-protocols = ⇄ Protocols
+gui-no-windows = No active windows. Create one with 'New Window'.
+gui-no-resources = No resource information
+gui-monitor-summary = { $running }/{ $total } running · { $expired } lease(s) expired
+gui-window-focus = Focus
+gui-window-minimize = Min
+gui-window-maximize = Max
+gui-window-fullscreen = FS
+gui-window-hide = Hide
+gui-window-next-workspace = Next Workspace →
+gui-workspace = Workspace { $number } ({ $count })
+gui-window-child-of = ↳ Child of { $title } (#{ $id })
+gui-window-modal-for = 🔒 Modal dialog of { $title } (#{ $id })
+gui-window-blocked-by = ⛔ Input blocked by { $title } (#{ $id })
+palette-placeholder = Type a command or window name…
+palette-no-match = No matching command
+hotkeys-title = Registered Global Hotkeys
+hotkeys-empty = No application has registered a global hotkey.
+protocols-title = Protocol Endpoints
+protocols-empty = No protocol_def endpoint is configured.
+protocols-reconnect = Reconnect
+protocols-disable = Disable
+protocols-enable = Enable
+preview-title = Next resource cycle with a heavy window (dry run)
+preview-totals = RAM { $ram } MiB | VRAM { $vram } MiB
+preview-no-changes = Nothing would change.
+osd-wants-attention = { $title } wants attention
+
+## CLI
+
+cli-init-start = 🔧 Initializing { -brand } configuration...
+cli-init-done = ✅ Configuration file created: { $path }
+cli-init-hint = Edit this file to customize your { -brand } setup.
+cli-init-failed = ❌ Failed to initialize config: { $error }
+cli-validate-start = 🔍 Validating configuration...
+cli-validate-ok = ✅ Configuration is valid!
+cli-validate-failed = ❌ Configuration validation failed: { $error }
+cli-config-read-failed = ❌ Failed to read config: { $error }
+cli-core-failed = ❌ Failed to initialize { -brand } Core: { $error }
+cli-gui-launch = 🖥️  Launching { -brand } GUI Window Manager...
+cli-gui-failed = ❌ GUI failed: { $error }
+cli-window-created = ✅ Window created successfully!
+cli-window-create-failed = ❌ Failed to create window: { $error }
+cli-no-windows = ℹ️  No active windows.
+cli-windows-total = { $count ->
+    [one] Total: 1 window
+   *[other] Total: { $count } windows
+}
+cli-window-closing = 🗑️  Closing window { $id }...
+cli-window-closed = ✅ Window { $id } closed successfully
+cli-window-close-failed = ❌ Failed to close window: { $error }
+cli-window-focusing = 👁️  Focusing window { $id }...
+cli-window-focused = ✅ Window { $id } is now focused
+cli-window-focus-failed = ❌ Failed to focus window: { $error }
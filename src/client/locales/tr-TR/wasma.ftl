@@ -0,0 +1,66 @@
+# WASMA - Türkçe (Türkiye)
+
+-brand = WASMA
+
+## Hatalar
+
+error-window-not-found = { $id } numaralı pencere bulunamadı
+
+## Arayüz
+
+gui-title = { -brand } Pencere Yöneticisi
+gui-new-window = + Yeni Pencere
+gui-update-resources = ⟳ Kaynakları Güncelle
+gui-hotkeys = ⌨ Kısayollar
+gui-preview-heavy = ◔ Ağır Pencere Önizle
+gui-protocols = ⇄ Protokoller
+gui-no-windows = Etkin pencere yok. 'Yeni Pencere' ile bir tane oluşturun.
+gui-no-resources = Kaynak bilgisi yok
+gui-monitor-summary = { $running }/{ $total } çalışıyor · { $expired } lease süresi doldu
+gui-window-focus = Odakla
+gui-window-minimize = Küçült
+gui-window-maximize = Büyüt
+gui-window-fullscreen = TE
+gui-window-hide = Gizle
+gui-window-next-workspace = Sonraki Alan →
+gui-workspace = Çalışma Alanı { $number } ({ $count })
+gui-window-child-of = ↳ { $title } penceresinin alt penceresi (#{ $id })
+gui-window-modal-for = 🔒 { $title } için kalıcı iletişim kutusu (#{ $id })
+gui-window-blocked-by = ⛔ Girdi { $title } tarafından engellendi (#{ $id })
+palette-placeholder = Bir komut veya pencere adı yazın…
+palette-no-match = Eşleşen komut yok
+hotkeys-title = Kayıtlı Genel Kısayollar
+hotkeys-empty = Hiçbir uygulama genel kısayol kaydetmedi.
+protocols-title = Protokol Uç Noktaları
+protocols-empty = Yapılandırılmış protocol_def uç noktası yok.
+protocols-reconnect = Yeniden Bağlan
+protocols-disable = Devre Dışı Bırak
+protocols-enable = Etkinleştir
+preview-title = Ağır bir pencereyle sonraki kaynak döngüsü (deneme)
+preview-totals = RAM { $ram } MiB | VRAM { $vram } MiB
+preview-no-changes = Hiçbir şey değişmeyecek.
+osd-wants-attention = { $title } dikkat istiyor
+
+## Komut satırı
+
+cli-init-start = 🔧 { -brand } yapılandırması oluşturuluyor...
+cli-init-done = ✅ Yapılandırma dosyası oluşturuldu: { $path }
+cli-init-hint = { -brand } kurulumunuzu özelleştirmek için bu dosyayı düzenleyin.
+cli-init-failed = ❌ Yapılandırma oluşturulamadı: { $error }
+cli-validate-start = 🔍 Yapılandırma doğrulanıyor...
+cli-validate-ok = ✅ Yapılandırma geçerli!
+cli-validate-failed = ❌ Yapılandırma doğrulaması başarısız: { $error }
+cli-config-read-failed = ❌ Yapılandırma okunamadı: { $error }
+cli-core-failed = ❌ { -brand } Core başlatılamadı: { $error }
+cli-gui-launch = 🖥️  { -brand } Pencere Yöneticisi başlatılıyor...
+cli-gui-failed = ❌ Arayüz hatası: { $error }
+cli-window-created = ✅ Pencere oluşturuldu!
+cli-window-create-failed = ❌ Pencere oluşturulamadı: { $error }
+cli-no-windows = ℹ️  Etkin pencere yok.
+cli-windows-total = Toplam: { $count } pencere
+cli-window-closing = 🗑️  { $id } numaralı pencere kapatılıyor...
+cli-window-closed = ✅ { $id } numaralı pencere kapatıldı
+cli-window-close-failed = ❌ Pencere kapatılamadı: { $error }
+cli-window-focusing = 👁️  { $id } numaralı pencere odaklanıyor...
+cli-window-focused = ✅ { $id } numaralı pencere odaklandı
+cli-window-focus-failed = ❌ Pencere odaklanamadı: { $error }
@@ -0,0 +1,183 @@
+// accessibility.rs
+// WASMA - Accessibility themes for the GUI manager
+// Maps the shared wbackend theme tokens (standard / high-contrast /
+// deuteranopia-safe) onto iced colors and themes, and applies the
+// reduce-motion preference to GUI transitions. With the standard variant the
+// manager follows settings.conf: [theme] dark_mode and accent_color pick the
+// palette, [font] size scales text; the window manager re-applies them through
+// WsdgSettingsManager::enable_wasma_sync whenever the file changes.
+
+use std::sync::RwLock;
+use iced::theme::Palette;
+use iced::{Color, Theme};
+use wbackend::theme::{self, A11yTheme, ThemeTokens};
+use wsdg_xdg::{WsdgEnv, WsdgSettings, WsdgSettingsManager};
+
+/// Theme/settings color → iced color
+pub fn color(color: wbackend::Color) -> Color {
+    let [r, g, b, a] = color.to_f32();
+    Color { r, g, b, a }
+}
+
+/// Linear mix, `t` = 0 → a, 1 → b
+pub fn blend(a: wbackend::Color, b: wbackend::Color, t: f32) -> Color {
+    color(a.mix(b, t))
+}
+
+/// Variant selected in [theme] a11y_variant; unknown names fall back to standard
+pub fn variant_from_settings(settings: &WsdgSettings) -> A11yTheme {
+    A11yTheme::from_name(&settings.theme.a11y_variant).unwrap_or_default()
+}
+
+/// Pick the active variant (CLI flag, then WSDG settings, then WASMA_A11Y_THEME)
+/// and the reduce-motion preference (WSDG setting or GTK system preference)
+pub fn init(cli: Option<A11yTheme>) -> A11yTheme {
+    let mut manager = WsdgSettingsManager::new(WsdgEnv::new());
+    let loaded = manager.load().is_ok();
+
+    let variant = cli.unwrap_or_else(|| {
+        let configured = variant_from_settings(manager.settings());
+        if loaded && configured != A11yTheme::Standard {
+            configured
+        } else {
+            theme::active()
+        }
+    });
+    theme::set_active(variant);
+
+    if manager.reduce_motion() {
+        theme::set_reduce_motion(true);
+    }
+
+    variant
+}
+
+/// Whether animations and fades should be skipped
+pub fn reduce_motion() -> bool {
+    theme::reduce_motion()
+}
+
+/// [font] size the GUI text sizes are designed for
+pub const DEFAULT_FONT_POINTS: u32 = 11;
+
+/// Look of the manager window from settings.conf
+#[derive(Debug, Clone, PartialEq)]
+pub struct Appearance {
+    pub dark_mode: bool,
+    pub accent: wbackend::Color,
+    /// Light mode only; dark mode keeps the dark theme tokens
+    pub background: wbackend::Color,
+    pub foreground: wbackend::Color,
+    /// [font] size in points
+    pub font_size: u32,
+}
+
+impl Appearance {
+    pub fn from_settings(settings: &WsdgSettings) -> Self {
+        Appearance {
+            dark_mode: settings.theme.dark_mode,
+            accent: settings.theme.accent_color,
+            background: settings.theme.background_color,
+            foreground: settings.theme.foreground_color,
+            font_size: settings.font.size,
+        }
+    }
+
+    /// Standard tokens recolored with the accent and, in light mode, the settings colors
+    pub fn apply(&self, tokens: ThemeTokens) -> ThemeTokens {
+        let mut tokens = ThemeTokens { accent: self.accent, ..tokens };
+        if !self.dark_mode {
+            tokens.background = self.background;
+            tokens.foreground = self.foreground;
+            tokens.surface = self.background.mix(self.foreground, 0.06);
+            tokens.muted = self.background.mix(self.foreground, 0.5);
+        }
+        tokens
+    }
+
+    /// `base` (designed for DEFAULT_FONT_POINTS) at the configured font size
+    pub fn text_size(&self, base: u16) -> u16 {
+        let scaled = base as u32 * self.font_size.max(1) / DEFAULT_FONT_POINTS;
+        scaled.clamp(1, u16::MAX as u32) as u16
+    }
+}
+
+// None until the window manager applies settings.conf
+static APPEARANCE: RwLock<Option<Appearance>> = RwLock::new(None);
+
+/// Apply settings.conf to the manager window; the next redraw picks it up
+pub fn set_appearance(appearance: Appearance) {
+    *APPEARANCE.write().unwrap() = Some(appearance);
+}
+
+pub fn appearance() -> Option<Appearance> {
+    APPEARANCE.read().unwrap().clone()
+}
+
+/// Tokens of the active variant; the standard one follows settings.conf
+pub fn tokens() -> ThemeTokens {
+    let tokens = theme::active_tokens();
+    match appearance() {
+        Some(appearance) if theme::active() == A11yTheme::Standard => appearance.apply(tokens),
+        _ => tokens,
+    }
+}
+
+/// Text size for `base` at the configured font size
+pub fn text_size(base: u16) -> u16 {
+    appearance().map_or(base, |appearance| appearance.text_size(base))
+}
+
+/// iced theme for the manager window; without settings the standard variant
+/// keeps iced's own
+pub fn iced_theme() -> Theme {
+    let variant = theme::active();
+    let appearance = appearance();
+    let name = match (variant, &appearance) {
+        (A11yTheme::Standard, None) => return Theme::default(),
+        (A11yTheme::Standard, Some(appearance)) if appearance.dark_mode => "dark",
+        (A11yTheme::Standard, Some(_)) => "light",
+        _ => variant.name(),
+    };
+
+    let tokens = tokens();
+    Theme::custom(
+        format!("WASMA {}", name),
+        Palette {
+            background: color(tokens.background),
+            text: color(tokens.foreground),
+            primary: color(tokens.accent),
+            success: color(tokens.success),
+            danger: color(tokens.danger),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_appearance() {
+        let mut settings = WsdgSettings::default();
+        settings.theme.accent_color = wbackend::Color::rgb(0xe0, 0x1b, 0x24);
+        settings.font.size = 22;
+        let light = Appearance::from_settings(&settings);
+        let standard = A11yTheme::Standard.tokens();
+
+        // Light mode takes the settings colors, both modes take the accent
+        let tokens = light.apply(standard.clone());
+        assert_eq!(tokens.accent, settings.theme.accent_color);
+        assert_eq!((tokens.background, tokens.foreground), (wbackend::Color::WHITE, wbackend::Color::BLACK));
+        assert_ne!(tokens.surface, standard.surface);
+
+        let dark = Appearance { dark_mode: true, ..light.clone() }.apply(standard.clone());
+        assert_eq!(dark.background, standard.background);
+        assert_eq!(dark.accent, settings.theme.accent_color);
+
+        // Text sizes scale with [font] size relative to 11pt
+        assert_eq!(light.text_size(16), 32);
+        assert_eq!(Appearance { font_size: DEFAULT_FONT_POINTS, ..light.clone() }.text_size(14), 14);
+        assert_eq!(Appearance { font_size: 0, ..light }.text_size(14), 1);
+    }
+}
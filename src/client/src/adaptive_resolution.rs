@@ -0,0 +1,220 @@
+// WASMA - Adaptive Resolution Scaling
+// When end-to-end frame latency stays above a threshold the stream steps down
+// a resolution level: the remote is asked for smaller frames
+// (ProtocolStream::request_resize) or, if it cannot resize, frames are
+// downscaled before upload. Sustained headroom steps back up to full size.
+
+use std::time::Duration;
+
+/// Resolution levels, full size first
+pub const SCALE_LEVELS: [f32; 4] = [1.0, 0.75, 0.5, 0.33];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScalerConfig {
+    /// Smoothed latency above this steps down
+    pub high_latency: Duration,
+    /// Smoothed latency below this steps back up
+    pub low_latency: Duration,
+    /// Consecutive frames beyond a threshold before changing level
+    pub settle_frames: u32,
+    /// Weight of the newest sample in the moving average
+    pub smoothing: f32,
+}
+
+impl Default for ScalerConfig {
+    fn default() -> Self {
+        Self {
+            high_latency: Duration::from_millis(50),
+            low_latency: Duration::from_millis(25),
+            settle_frames: 10,
+            smoothing: 0.2,
+        }
+    }
+}
+
+/// How a lower resolution is obtained
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleMethod {
+    /// The remote renders smaller frames
+    RemoteResize,
+    /// Full-size frames are downscaled before upload
+    Downscale,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScaleChange {
+    pub from: f32,
+    pub to: f32,
+}
+
+/// Per-stream latency tracker and level controller
+#[derive(Debug, Clone)]
+pub struct ResolutionScaler {
+    config: ScalerConfig,
+    level: usize,
+    method: ScaleMethod,
+    smoothed_ms: Option<f32>,
+    // Frames in a row above high_latency (positive) or below low_latency (negative)
+    streak: i64,
+}
+
+impl Default for ResolutionScaler {
+    fn default() -> Self {
+        Self::new(ScalerConfig::default())
+    }
+}
+
+impl ResolutionScaler {
+    pub fn new(config: ScalerConfig) -> Self {
+        Self { config, level: 0, method: ScaleMethod::Downscale, smoothed_ms: None, streak: 0 }
+    }
+
+    pub fn scale(&self) -> f32 {
+        SCALE_LEVELS[self.level]
+    }
+
+    pub fn method(&self) -> ScaleMethod {
+        self.method
+    }
+
+    /// Set once the remote has accepted (or refused) a resize request
+    pub fn set_method(&mut self, method: ScaleMethod) {
+        self.method = method;
+    }
+
+    pub fn smoothed_latency(&self) -> Option<Duration> {
+        self.smoothed_ms.map(|ms| Duration::from_secs_f32(ms / 1000.0))
+    }
+
+    /// Feed one frame's end-to-end latency; returns the level change, if any
+    pub fn record(&mut self, latency: Duration) -> Option<ScaleChange> {
+        let sample = latency.as_secs_f32() * 1000.0;
+        let alpha = self.config.smoothing.clamp(0.0, 1.0);
+        let smoothed = match self.smoothed_ms {
+            Some(previous) => previous + alpha * (sample - previous),
+            None => sample,
+        };
+        self.smoothed_ms = Some(smoothed);
+
+        let high = self.config.high_latency.as_secs_f32() * 1000.0;
+        let low = self.config.low_latency.as_secs_f32() * 1000.0;
+        self.streak = if smoothed > high {
+            self.streak.max(0) + 1
+        } else if smoothed < low {
+            self.streak.min(0) - 1
+        } else {
+            0
+        };
+
+        let settle = i64::from(self.config.settle_frames.max(1));
+        let from = self.scale();
+        if self.streak >= settle && self.level + 1 < SCALE_LEVELS.len() {
+            self.level += 1;
+        } else if self.streak <= -settle && self.level > 0 {
+            self.level -= 1;
+        } else {
+            return None;
+        }
+        self.streak = 0;
+        Some(ScaleChange { from, to: self.scale() })
+    }
+
+    /// Frame size to request from the remote for a viewport of `width`x`height`
+    pub fn target_size(&self, width: u32, height: u32) -> (u32, u32) {
+        scaled_size(width, height, self.scale())
+    }
+}
+
+pub fn scaled_size(width: u32, height: u32, scale: f32) -> (u32, u32) {
+    (
+        ((width as f32 * scale).round() as u32).max(1),
+        ((height as f32 * scale).round() as u32).max(1),
+    )
+}
+
+/// Box-filter an RGBA frame down by `scale`; returns the pixels and new size
+pub fn downscale_rgba(data: &[u8], width: u32, height: u32, scale: f32) -> (Vec<u8>, u32, u32) {
+    let (dst_w, dst_h) = scaled_size(width, height, scale.min(1.0));
+    let (w, h) = (width as usize, height as usize);
+    if dst_w as usize >= w || data.len() < w * h * 4 {
+        return (data.to_vec(), width, height);
+    }
+
+    let mut out = vec![0u8; dst_w as usize * dst_h as usize * 4];
+    for dy in 0..dst_h as usize {
+        let y0 = dy * h / dst_h as usize;
+        let y1 = ((dy + 1) * h / dst_h as usize).max(y0 + 1);
+        for dx in 0..dst_w as usize {
+            let x0 = dx * w / dst_w as usize;
+            let x1 = ((dx + 1) * w / dst_w as usize).max(x0 + 1);
+
+            let mut sum = [0u32; 4];
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let src = (y * w + x) * 4;
+                    for (acc, &value) in sum.iter_mut().zip(&data[src..src + 4]) {
+                        *acc += u32::from(value);
+                    }
+                }
+            }
+            let count = ((y1 - y0) * (x1 - x0)) as u32;
+            let dst = (dy * dst_w as usize + dx) * 4;
+            for (value, acc) in out[dst..dst + 4].iter_mut().zip(sum) {
+                *value = (acc / count) as u8;
+            }
+        }
+    }
+    (out, dst_w, dst_h)
+}
+
+/// Nearest-neighbour stretch of an RGBA frame to `dst_w`x`dst_h` (HiDPI
+/// outputs: integer factors stay crisp); short frames come back unchanged
+pub fn upscale_rgba(data: &[u8], width: u32, height: u32, dst_w: u32, dst_h: u32) -> Vec<u8> {
+    let (w, h) = (width as usize, height as usize);
+    if w == 0 || h == 0 || data.len() < w * h * 4 {
+        return data.to_vec();
+    }
+
+    let (dst_w, dst_h) = (dst_w as usize, dst_h as usize);
+    let mut out = Vec::with_capacity(dst_w * dst_h * 4);
+    for dy in 0..dst_h {
+        let row = &data[(dy * h / dst_h) * w * 4..][..w * 4];
+        for dx in 0..dst_w {
+            let src = (dx * w / dst_w) * 4;
+            out.extend_from_slice(&row[src..src + 4]);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaler_steps_down_and_recovers() {
+        let mut scaler = ResolutionScaler::new(ScalerConfig { settle_frames: 3, smoothing: 1.0, ..Default::default() });
+        let slow = Duration::from_millis(80);
+        let fast = Duration::from_millis(10);
+
+        // A single spike does not change the level
+        assert_eq!(scaler.record(slow), None);
+        assert_eq!(scaler.record(fast), None);
+
+        for _ in 0..2 {
+            assert_eq!(scaler.record(slow), None);
+        }
+        assert_eq!(scaler.record(slow), Some(ScaleChange { from: 1.0, to: 0.75 }));
+        assert_eq!(scaler.target_size(1280, 720), (960, 540));
+
+        for _ in 0..2 {
+            scaler.record(fast);
+        }
+        assert_eq!(scaler.record(fast), Some(ScaleChange { from: 0.75, to: 1.0 }));
+
+        let frame = vec![200u8; 4 * 4 * 4];
+        let (half, w, h) = downscale_rgba(&frame, 4, 4, 0.5);
+        assert_eq!((w, h), (2, 2));
+        assert_eq!(half, vec![200u8; 2 * 2 * 4]);
+    }
+}
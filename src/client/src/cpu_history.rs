@@ -0,0 +1,187 @@
+// WASMA - Per-window CPU history
+// A sampler thread records each assignment's CPU utilization into a ring
+// buffer per window (see also resource_graphs). Utilization comes
+// from the assignment's cgroup (cpu.stat usage_usec) when it has one, otherwise
+// from /proc/stat busy time of the cores it is bound to.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Samples kept per window
+pub const CPU_HISTORY_LEN: usize = 120;
+pub const CPU_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Ring buffer of utilization samples in 0.0..=1.0, oldest first
+#[derive(Debug, Clone)]
+pub struct CpuHistory {
+    samples: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl Default for CpuHistory {
+    fn default() -> Self {
+        Self::new(CPU_HISTORY_LEN)
+    }
+}
+
+impl CpuHistory {
+    pub fn new(capacity: usize) -> Self {
+        CpuHistory { samples: VecDeque::with_capacity(capacity), capacity: capacity.max(1) }
+    }
+
+    pub fn push(&mut self, utilization: f32) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(utilization.clamp(0.0, 1.0));
+    }
+
+    /// Newest `count` samples, oldest first
+    pub fn recent(&self, count: usize) -> Vec<f32> {
+        let skip = self.samples.len().saturating_sub(count);
+        self.samples.iter().skip(skip).copied().collect()
+    }
+
+    pub fn latest(&self) -> Option<f32> {
+        self.samples.back().copied()
+    }
+}
+
+/// One-line block-character graph of `samples`
+pub fn sparkline(samples: &[f32]) -> String {
+    samples
+        .iter()
+        .map(|s| SPARK_LEVELS[((s.clamp(0.0, 1.0) * (SPARK_LEVELS.len() - 1) as f32).round()) as usize])
+        .collect()
+}
+
+/// What the sampler needs to know about an assignment
+#[derive(Debug, Clone)]
+pub struct CpuSource {
+    pub assignment_id: u32,
+    pub cpu_cores: Vec<usize>,
+    /// cgroup cpu.stat usage_usec, when the assignment has a cgroup
+    pub cgroup_usage_usec: Option<u64>,
+}
+
+/// (busy, total) jiffies; `None` key is the aggregate "cpu" line
+type CoreTimes = HashMap<Option<usize>, (u64, u64)>;
+
+/// Turns successive counters into utilization; the first sample of a source only primes it
+#[derive(Debug, Default)]
+pub struct CpuSampler {
+    last_cores: CoreTimes,
+    last_cgroup: HashMap<u32, (u64, Instant)>,
+}
+
+impl CpuSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sample(&mut self, sources: &[CpuSource]) -> HashMap<u32, f32> {
+        let cores = std::fs::read_to_string("/proc/stat").map(|s| parse_proc_stat(&s)).unwrap_or_default();
+        self.sample_with(sources, cores, Instant::now())
+    }
+
+    fn sample_with(&mut self, sources: &[CpuSource], cores: CoreTimes, now: Instant) -> HashMap<u32, f32> {
+        let core_busy = |key: Option<usize>| -> Option<f32> {
+            let (busy, total) = cores.get(&key)?;
+            let (last_busy, last_total) = self.last_cores.get(&key)?;
+            let total = total.checked_sub(*last_total).filter(|&t| t > 0)?;
+            Some(busy.saturating_sub(*last_busy) as f32 / total as f32)
+        };
+
+        let mut utilization = HashMap::new();
+        for source in sources {
+            let value = match source.cgroup_usage_usec {
+                Some(usec) => {
+                    let previous = self.last_cgroup.insert(source.assignment_id, (usec, now));
+                    previous.and_then(|(last_usec, at)| {
+                        let elapsed = now.saturating_duration_since(at).as_micros() as f32;
+                        let capacity = elapsed * source.cpu_cores.len().max(1) as f32;
+                        (capacity > 0.0).then(|| usec.saturating_sub(last_usec) as f32 / capacity)
+                    })
+                }
+                None if source.cpu_cores.is_empty() => core_busy(None),
+                None => {
+                    let busy: Vec<f32> = source.cpu_cores.iter().filter_map(|&c| core_busy(Some(c))).collect();
+                    (!busy.is_empty()).then(|| busy.iter().sum::<f32>() / busy.len() as f32)
+                }
+            };
+            if let Some(value) = value {
+                utilization.insert(source.assignment_id, value.clamp(0.0, 1.0));
+            }
+        }
+
+        self.last_cgroup.retain(|id, _| sources.iter().any(|s| s.assignment_id == *id));
+        self.last_cores = cores;
+        utilization
+    }
+}
+
+/// /proc/stat "cpu" / "cpuN" lines → (busy, total); idle and iowait are not busy
+pub fn parse_proc_stat(content: &str) -> HashMap<Option<usize>, (u64, u64)> {
+    let mut times = HashMap::new();
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let Some(name) = fields.next().and_then(|n| n.strip_prefix("cpu")) else { continue };
+        let key = if name.is_empty() {
+            None
+        } else {
+            match name.parse() {
+                Ok(core) => Some(core),
+                Err(_) => continue,
+            }
+        };
+        let values: Vec<u64> = fields.filter_map(|v| v.parse().ok()).collect();
+        if values.len() < 4 {
+            continue;
+        }
+        // user nice system idle iowait irq softirq steal (guest is already in user)
+        let total: u64 = values.iter().take(8).sum();
+        let idle = values[3] + values.get(4).copied().unwrap_or(0);
+        times.insert(key, (total - idle, total));
+    }
+    times
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_sampling_and_history() {
+        let stat = |busy0: u64, idle0: u64| {
+            parse_proc_stat(&format!(
+                "cpu  {} 0 0 {} 0 0 0 0 0 0\ncpu0 {} 0 0 {} 0 0 0 0 0 0\nintr 1 2 3\n",
+                busy0, idle0, busy0, idle0
+            ))
+        };
+        let start = Instant::now();
+        let sources = [
+            CpuSource { assignment_id: 1, cpu_cores: vec![0], cgroup_usage_usec: None },
+            CpuSource { assignment_id: 2, cpu_cores: vec![0, 1], cgroup_usage_usec: Some(1_000_000) },
+        ];
+
+        let mut sampler = CpuSampler::new();
+        assert!(sampler.sample_with(&sources, stat(100, 100), start).is_empty());
+
+        let mut later = sources.clone();
+        later[1].cgroup_usage_usec = Some(1_500_000);
+        let usage = sampler.sample_with(&later, stat(175, 125), start + Duration::from_secs(1));
+        assert_eq!(usage[&1], 0.75);
+        // 0.5s of CPU over 1s on two cores
+        assert_eq!(usage[&2], 0.25);
+
+        let mut history = CpuHistory::new(3);
+        for value in [0.0, 0.25, 0.5, 1.0] {
+            history.push(value);
+        }
+        assert_eq!(history.recent(10), vec![0.25, 0.5, 1.0]);
+        assert_eq!(history.recent(2), vec![0.5, 1.0]);
+        assert_eq!(sparkline(&history.recent(3)), "▃▅█");
+    }
+}
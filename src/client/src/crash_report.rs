@@ -0,0 +1,200 @@
+// WASMA - Crash reports
+// On a panic or a fatal renderer error a report directory is written under
+// $STATE/wasma/crashes/<unix time>-<pid>-<kind>/:
+//   report.txt  - message, thread, backtrace
+//   config.json - the wasma.in.conf in effect (when one was loaded)
+//   log.txt     - the last CRASH_LOG_LINES log records (see logging::CrashLayer)
+// `wasma report last` prints the newest one or packages it into a single file.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Log records kept in memory for the next report
+pub const CRASH_LOG_LINES: usize = 200;
+
+/// Older report directories are removed beyond this many
+pub const MAX_CRASH_REPORTS: usize = 20;
+
+static LOG_LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static CONFIG_SNAPSHOT: Mutex<Option<String>> = Mutex::new(None);
+
+const REPORT_FILE: &str = "report.txt";
+const CONFIG_FILE: &str = "config.json";
+const LOG_FILE: &str = "log.txt";
+
+/// `$STATE/wasma/crashes` for the current WSDG environment
+pub fn crash_dir() -> PathBuf {
+    wsdg_xdg::WsdgEnv::new()
+        .state_dir()
+        .unwrap_or_else(|_| std::env::temp_dir())
+        .join("wasma")
+        .join("crashes")
+}
+
+/// Keep `line` for the next report; the oldest line goes once CRASH_LOG_LINES are held
+pub fn remember_line(line: impl Into<String>) {
+    let mut lines = LOG_LINES.lock().unwrap_or_else(|e| e.into_inner());
+    if lines.len() == CRASH_LOG_LINES {
+        lines.pop_front();
+    }
+    lines.push_back(line.into());
+}
+
+/// Config attached to later reports
+pub fn set_config_snapshot<T: serde::Serialize>(config: &T) {
+    let snapshot = serde_json::to_string_pretty(config).ok();
+    *CONFIG_SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()) = snapshot;
+}
+
+/// Write a report for every panic, then run the previous hook
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = match info.payload().downcast_ref::<&str>() {
+            Some(s) => s.to_string(),
+            None => info.payload().downcast_ref::<String>().cloned().unwrap_or_else(|| "Box<dyn Any>".to_string()),
+        };
+        let location = info.location().map(|l| format!(" at {}:{}:{}", l.file(), l.line(), l.column())).unwrap_or_default();
+        let backtrace = std::backtrace::Backtrace::force_capture();
+
+        match write_report(&crash_dir(), "panic", &format!("{}{}", message, location), &backtrace.to_string()) {
+            Ok(dir) => eprintln!("💥 Crash report written to {}", dir.display()),
+            Err(e) => eprintln!("⚠️  Crash report could not be written: {}", e),
+        }
+        previous(info);
+    }));
+}
+
+/// Report an error the process cannot continue from (e.g. a renderer failing to start)
+pub fn report_fatal(context: &str, error: &dyn fmt::Display) -> Option<PathBuf> {
+    let backtrace = std::backtrace::Backtrace::force_capture();
+    match write_report(&crash_dir(), "fatal", &format!("{}: {}", context, error), &backtrace.to_string()) {
+        Ok(dir) => {
+            eprintln!("💥 Crash report written to {}", dir.display());
+            Some(dir)
+        }
+        Err(e) => {
+            eprintln!("⚠️  Crash report could not be written: {}", e);
+            None
+        }
+    }
+}
+
+/// Create a report directory under `crashes`; returns its path
+pub fn write_report(crashes: &Path, kind: &str, message: &str, backtrace: &str) -> io::Result<PathBuf> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let dir = crashes.join(format!("{}-{}-{}", now.as_secs(), std::process::id(), kind));
+    fs::create_dir_all(&dir)?;
+
+    let thread = std::thread::current();
+    let report = format!(
+        "WASMA {} crash report\nkind: {}\ntime: {}\nos: {} {}\nthread: {}\n\n{}\n\nbacktrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        kind,
+        now.as_secs(),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        thread.name().unwrap_or("<unnamed>"),
+        message,
+        backtrace,
+    );
+    fs::write(dir.join(REPORT_FILE), report)?;
+
+    if let Some(config) = CONFIG_SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        fs::write(dir.join(CONFIG_FILE), config)?;
+    }
+    let lines: Vec<String> = LOG_LINES.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect();
+    fs::write(dir.join(LOG_FILE), lines.join("\n"))?;
+
+    prune(crashes, MAX_CRASH_REPORTS);
+    Ok(dir)
+}
+
+/// Report directories, oldest first
+pub fn list_reports(crashes: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(crashes) else { return Vec::new() };
+    let mut reports: Vec<(u64, PathBuf)> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.join(REPORT_FILE).is_file())
+        .filter_map(|p| {
+            let secs = p.file_name()?.to_str()?.split('-').next()?.parse().ok()?;
+            Some((secs, p))
+        })
+        .collect();
+    reports.sort();
+    reports.into_iter().map(|(_, p)| p).collect()
+}
+
+pub fn latest_report(crashes: &Path) -> Option<PathBuf> {
+    list_reports(crashes).pop()
+}
+
+fn prune(crashes: &Path, keep: usize) {
+    let reports = list_reports(crashes);
+    for old in reports.iter().take(reports.len().saturating_sub(keep)) {
+        let _ = fs::remove_dir_all(old);
+    }
+}
+
+/// The whole report as one text document, for printing or attaching to a bug
+pub fn render_report(report: &Path) -> io::Result<String> {
+    let mut out = fs::read_to_string(report.join(REPORT_FILE))?;
+    for (title, file) in [("config", CONFIG_FILE), ("log", LOG_FILE)] {
+        if let Ok(content) = fs::read_to_string(report.join(file)) {
+            out.push_str(&format!("\n===== {} =====\n{}\n", title, content));
+        }
+    }
+    Ok(out)
+}
+
+/// Write render_report() to `output`
+pub fn package_report(report: &Path, output: &Path) -> io::Result<()> {
+    fs::write(output, render_report(report)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crash_report_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let crashes = dir.path().join("crashes");
+        assert_eq!(latest_report(&crashes), None);
+
+        set_config_snapshot(&serde_json::json!({ "renderer": "cpu_renderer" }));
+        for i in 0..CRASH_LOG_LINES + 5 {
+            remember_line(format!("line {}", i));
+        }
+        let report = write_report(&crashes, "fatal", "renderer: device lost", "0: main").unwrap();
+        assert_eq!(latest_report(&crashes), Some(report.clone()));
+
+        let text = render_report(&report).unwrap();
+        assert!(text.contains("kind: fatal"));
+        assert!(text.contains("renderer: device lost"));
+        assert!(text.contains("\"renderer\": \"cpu_renderer\""));
+        assert!(text.contains(&format!("line {}", CRASH_LOG_LINES + 4)));
+        assert!(!text.contains("line 4\n"));
+
+        let packaged = dir.path().join("bug.txt");
+        package_report(&report, &packaged).unwrap();
+        assert_eq!(fs::read_to_string(&packaged).unwrap(), text);
+
+        // Only the newest reports are kept
+        for secs in 0..MAX_CRASH_REPORTS as u64 + 3 {
+            let old = crashes.join(format!("{}-1-panic", secs));
+            fs::create_dir_all(&old).unwrap();
+            fs::write(old.join(REPORT_FILE), "old").unwrap();
+        }
+        prune(&crashes, MAX_CRASH_REPORTS);
+        let reports = list_reports(&crashes);
+        assert_eq!(reports.len(), MAX_CRASH_REPORTS);
+        assert_eq!(reports.last(), Some(&report));
+    }
+}
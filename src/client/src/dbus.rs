@@ -0,0 +1,209 @@
+// WASMA - D-Bus control interface
+// `wasma daemon` (and the GUI) own org.wasma.WindowManager on the session bus
+// and serve the control operations at /org/wasma/WindowManager, so status
+// bars, desktop tooling and scripts can drive WASMA with busctl/gdbus or any
+// D-Bus binding instead of the control socket:
+//   ListWindows()                           -> a(tsss)  id, title, app_id, state
+//   CreateWindow(s title, s app_id, u w, u h) -> t     window id
+//   CloseWindow(t id), FocusWindow(t id)
+//   SetWindowState(t id, s state)           normal|minimized|maximized|fullscreen|hidden
+//   GetResources(t id)                      -> a{sv}  ResourceUsage fields; unmeasured ones are left out
+//   WindowCount                             property, u
+// Rust clients can use WindowManagerProxy.
+
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::sync::Arc;
+use wbackend::ResourceMode;
+use zbus::fdo;
+use zbus::zvariant::Value;
+
+use crate::control;
+use crate::window_handling::{WindowGeometry, WindowHandler};
+
+pub const BUS_NAME: &str = "org.wasma.WindowManager";
+pub const OBJECT_PATH: &str = "/org/wasma/WindowManager";
+
+/// org.wasma.WindowManager, backed by the live WindowHandler
+pub struct WindowManagerInterface {
+    handler: Arc<WindowHandler>,
+    resource_mode: ResourceMode,
+}
+
+impl WindowManagerInterface {
+    pub fn new(handler: Arc<WindowHandler>, resource_mode: ResourceMode) -> Self {
+        Self { handler, resource_mode }
+    }
+}
+
+#[zbus::interface(name = "org.wasma.WindowManager")]
+impl WindowManagerInterface {
+    fn list_windows(&self) -> Vec<(u64, String, String, String)> {
+        self.handler
+            .list_windows()
+            .into_iter()
+            .map(|w| (w.id, w.title, w.app_id, control::state_name(&w.state).to_string()))
+            .collect()
+    }
+
+    fn create_window(&self, title: String, app_id: String, width: u32, height: u32) -> fdo::Result<u64> {
+        if width == 0 || height == 0 {
+            return Err(fdo::Error::InvalidArgs(format!("invalid size '{}x{}'", width, height)));
+        }
+        let geometry = WindowGeometry { x: 100, y: 100, width, height };
+        self.handler
+            .create_window(title, app_id, geometry, None, self.resource_mode)
+            .map_err(fdo::Error::Failed)
+    }
+
+    fn close_window(&self, id: u64) -> fdo::Result<()> {
+        self.handler.close_window(id).map_err(fdo::Error::Failed)
+    }
+
+    fn focus_window(&self, id: u64) -> fdo::Result<()> {
+        self.handler.focus_window(id).map_err(fdo::Error::Failed)
+    }
+
+    fn set_window_state(&self, id: u64, state: &str) -> fdo::Result<()> {
+        let state = control::parse_state(state).map_err(fdo::Error::InvalidArgs)?;
+        self.handler.set_window_state(id, state).map_err(fdo::Error::Failed)
+    }
+
+    fn get_resources(&self, id: u64) -> fdo::Result<HashMap<String, Value<'static>>> {
+        let usage = self.handler.get_window_resource_usage(id).map_err(fdo::Error::Failed)?;
+        let mut resources: HashMap<String, Value<'static>> = HashMap::from([
+            ("assignment_id".to_string(), Value::from(usage.assignment_id)),
+            ("ram_allocated_mb".to_string(), Value::from(usage.ram_allocated_mb)),
+            ("vram_allocated_mb".to_string(), Value::from(usage.vram_allocated_mb)),
+            ("cpu_cores".to_string(), Value::from(usage.cpu_cores.iter().map(|&c| c as u32).collect::<Vec<u32>>())),
+            ("task_active".to_string(), Value::from(usage.task_active)),
+            ("gpu_active".to_string(), Value::from(usage.gpu_active)),
+            ("remaining_lease_secs".to_string(), Value::from(usage.remaining_lease_secs)),
+            ("execution_mode".to_string(), Value::from(format!("{:?}", usage.execution_mode))),
+        ]);
+        if let Some(gpu) = usage.gpu_device {
+            resources.insert("gpu_device".to_string(), Value::from(gpu));
+        }
+        if let Some(used) = usage.ram_used_mb {
+            resources.insert("ram_used_mb".to_string(), Value::from(used));
+        }
+        if let Some(usec) = usage.cpu_usage_usec {
+            resources.insert("cpu_usage_usec".to_string(), Value::from(usec));
+        }
+        Ok(resources)
+    }
+
+    #[zbus(property)]
+    fn window_count(&self) -> u32 {
+        self.handler.list_windows().len() as u32
+    }
+}
+
+#[zbus::proxy(
+    interface = "org.wasma.WindowManager",
+    default_service = "org.wasma.WindowManager",
+    default_path = "/org/wasma/WindowManager"
+)]
+pub trait WindowManager {
+    fn list_windows(&self) -> zbus::Result<Vec<(u64, String, String, String)>>;
+    fn create_window(&self, title: &str, app_id: &str, width: u32, height: u32) -> zbus::Result<u64>;
+    fn close_window(&self, id: u64) -> zbus::Result<()>;
+    fn focus_window(&self, id: u64) -> zbus::Result<()>;
+    fn set_window_state(&self, id: u64, state: &str) -> zbus::Result<()>;
+    fn get_resources(&self, id: u64) -> zbus::Result<HashMap<String, zbus::zvariant::OwnedValue>>;
+    #[zbus(property)]
+    fn window_count(&self) -> zbus::Result<u32>;
+}
+
+/// Session bus service; runs on its own thread like the gRPC control service
+pub struct DbusService {
+    handler: Arc<WindowHandler>,
+    resource_mode: ResourceMode,
+}
+
+impl DbusService {
+    pub fn new(handler: Arc<WindowHandler>, resource_mode: ResourceMode) -> Self {
+        Self { handler, resource_mode }
+    }
+
+    /// Claim BUS_NAME on the session bus and serve until the process exits.
+    /// Returns once the name is owned, or with the reason it could not be.
+    pub fn start(&self) -> zbus::Result<()> {
+        let interface = WindowManagerInterface::new(self.handler.clone(), self.resource_mode);
+        let (ready, started) = mpsc::channel();
+        std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime,
+                Err(e) => return drop(ready.send(Err(e.into()))),
+            };
+            runtime.block_on(async move {
+                let connection = zbus::connection::Builder::session()
+                    .and_then(|builder| builder.name(BUS_NAME))
+                    .and_then(|builder| builder.serve_at(OBJECT_PATH, interface));
+                let connection = match connection {
+                    Ok(builder) => builder.build().await,
+                    Err(e) => Err(e),
+                };
+                match connection {
+                    Ok(_connection) => {
+                        let _ = ready.send(Ok(()));
+                        // The connection serves requests from its own tasks
+                        std::future::pending::<()>().await
+                    }
+                    Err(e) => drop(ready.send(Err(e))),
+                }
+            });
+        });
+        started
+            .recv()
+            .unwrap_or_else(|_| Err(zbus::Error::Failure("D-Bus service thread stopped".to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dbus_window_manager() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let handler = Arc::new(WindowHandler::new(ResourceMode::Manual));
+            let interface = WindowManagerInterface::new(handler.clone(), ResourceMode::Manual);
+
+            // Peer-to-peer connection in place of the session bus
+            let (server, client) = tokio::net::UnixStream::pair().unwrap();
+            let server = zbus::connection::Builder::unix_stream(server)
+                .server(zbus::Guid::generate())
+                .unwrap()
+                .p2p()
+                .serve_at(OBJECT_PATH, interface)
+                .unwrap()
+                .build();
+            let client = zbus::connection::Builder::unix_stream(client).p2p().build();
+            let (_server, client) = tokio::try_join!(server, client).unwrap();
+            let proxy = WindowManagerProxy::new(&client).await.unwrap();
+
+            let id = proxy.create_window("Status bar", "test.dbus", 640, 480).await.unwrap();
+            assert_eq!(proxy.window_count().await.unwrap(), 1);
+            assert_eq!(
+                proxy.list_windows().await.unwrap(),
+                vec![(id, "Status bar".to_string(), "test.dbus".to_string(), "normal".to_string())]
+            );
+
+            proxy.set_window_state(id, "maximized").await.unwrap();
+            assert_eq!(proxy.list_windows().await.unwrap()[0].3, "maximized");
+            assert!(proxy.set_window_state(id, "rolled-up").await.is_err());
+            proxy.focus_window(id).await.unwrap();
+
+            let resources = proxy.get_resources(id).await.unwrap();
+            assert_eq!(u32::try_from(&resources["assignment_id"]).unwrap(), id as u32);
+            assert!(resources.contains_key("ram_allocated_mb"));
+
+            proxy.close_window(id).await.unwrap();
+            assert!(proxy.list_windows().await.unwrap().is_empty());
+            assert!(proxy.close_window(id).await.is_err());
+            assert!(proxy.create_window("Empty", "test.dbus", 0, 480).await.is_err());
+        });
+    }
+}
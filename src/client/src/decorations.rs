@@ -0,0 +1,350 @@
+// decorations.rs
+// WASMA - Server-side window decorations
+// Titlebar (icon, title, min/max/close) drawn by WASMA around viewports
+// whose stream cannot draw its own client-side decorations (CSD)
+
+use iced::{
+    widget::{button, container, row, text, Space},
+    Alignment, Background, Color, Element, Length, Theme,
+};
+use wbackend::theme::{self, A11yTheme};
+use wsdg_xdg::{WsdgEnv, WsdgSettings, WsdgSettingsManager};
+
+use crate::accessibility;
+use crate::placeholder::{Canvas, GLYPH_HEIGHT};
+use crate::window_handling::{Message, Window, WindowType};
+
+/// (x, y, width, height) – same layout as the blit bounds in WindowClient
+pub type Bounds = (i32, i32, u32, u32);
+
+// ============================================================================
+// THEME
+// ============================================================================
+
+#[derive(Debug, Clone)]
+pub struct DecorationTheme {
+    /// WsdgSettings.window.decorations – master switch for SSD
+    pub enabled: bool,
+    pub titlebar_height: u32,
+    pub border_width: u32,
+    pub font_size: u16,
+    pub background: Color,
+    pub foreground: Color,
+    pub accent: Color,
+    pub close: Color,
+}
+
+impl Default for DecorationTheme {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            titlebar_height: 28,
+            border_width: 1,
+            font_size: 14,
+            background: Color::from_rgb(0.15, 0.15, 0.15),
+            foreground: Color::from_rgb(0.9, 0.9, 0.9),
+            accent: Color::from_rgb(0.2, 0.6, 1.0),
+            close: Color::from_rgb(0.85, 0.25, 0.25),
+        }
+    }
+}
+
+impl DecorationTheme {
+    pub fn from_settings(settings: &WsdgSettings) -> Self {
+        let default = Self::default();
+        let theme = &settings.theme;
+
+        // Titlebar scales with the UI font: 11pt → 28px
+        let font_size = (settings.font.size as u16).saturating_add(3).max(10);
+        let titlebar_height = (settings.font.size * 28 / 11).clamp(20, 64);
+
+        Self {
+            enabled: settings.window.decorations,
+            titlebar_height,
+            border_width: default.border_width,
+            font_size,
+            background: accessibility::color(theme.background_color),
+            foreground: accessibility::color(theme.foreground_color),
+            accent: accessibility::color(theme.accent_color),
+            close: default.close,
+        }
+        .with_a11y(accessibility::variant_from_settings(settings))
+    }
+
+    /// Replace colors and border with an accessibility palette (standard keeps the theme)
+    pub fn with_a11y(mut self, variant: A11yTheme) -> Self {
+        if variant == A11yTheme::Standard {
+            return self;
+        }
+
+        let tokens = variant.tokens();
+        self.background = accessibility::color(tokens.surface);
+        self.foreground = accessibility::color(tokens.foreground);
+        self.accent = accessibility::color(tokens.accent);
+        self.close = accessibility::color(tokens.danger);
+        self.border_width = tokens.border_width;
+        self
+    }
+
+    /// Load the user's WSDG settings, falling back to the built-in theme
+    pub fn from_wsdg() -> Self {
+        Self::from_env(&WsdgEnv::new())
+    }
+
+    /// Theme from the settings of a specific WSDG environment (e.g. a seat's user)
+    pub fn from_env(env: &WsdgEnv) -> Self {
+        let mut manager = WsdgSettingsManager::new(env.clone());
+        match manager.load() {
+            Ok(()) => Self::from_settings(manager.settings()),
+            Err(_) => Self::default(),
+        }
+        .with_a11y(theme::active())
+    }
+}
+
+// ============================================================================
+// GEOMETRY
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecorationButton {
+    Minimize,
+    Maximize,
+    Close,
+}
+
+impl DecorationButton {
+    /// Right-to-left order on the titlebar
+    pub const ALL: [DecorationButton; 3] = [
+        DecorationButton::Close,
+        DecorationButton::Maximize,
+        DecorationButton::Minimize,
+    ];
+
+    pub fn glyph(&self) -> &'static str {
+        match self {
+            DecorationButton::Minimize => "—",
+            DecorationButton::Maximize => "▢",
+            DecorationButton::Close => "✕",
+        }
+    }
+
+    pub fn message(&self, window_id: u64) -> Message {
+        match self {
+            DecorationButton::Minimize => Message::MinimizeWindow(window_id),
+            DecorationButton::Maximize => Message::MaximizeWindow(window_id),
+            DecorationButton::Close => Message::CloseWindow(window_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecorationHit {
+    /// Drag area
+    Titlebar,
+    Button(DecorationButton),
+    Border,
+}
+
+/// Whether a window of this type gets a titlebar at all
+pub fn wants_decorations(window_type: &WindowType) -> bool {
+    matches!(window_type, WindowType::Normal | WindowType::Dialog | WindowType::Utility)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Decorations {
+    theme: DecorationTheme,
+}
+
+impl Decorations {
+    pub fn new(theme: DecorationTheme) -> Self {
+        Self { theme }
+    }
+
+    pub fn theme(&self) -> &DecorationTheme {
+        &self.theme
+    }
+
+    pub fn titlebar_bounds(&self, frame: Bounds) -> Bounds {
+        let (x, y, w, h) = frame;
+        (x, y, w, self.theme.titlebar_height.min(h))
+    }
+
+    /// Area left for the stream's own pixels inside the decorated frame
+    pub fn content_bounds(&self, frame: Bounds) -> Bounds {
+        let (x, y, w, h) = frame;
+        let b = self.theme.border_width;
+        let top = self.theme.titlebar_height;
+        (
+            x + b as i32,
+            y + top as i32,
+            w.saturating_sub(2 * b),
+            h.saturating_sub(top + b),
+        )
+    }
+
+    pub fn button_bounds(&self, frame: Bounds, which: DecorationButton) -> Bounds {
+        let (x, y, w, _) = frame;
+        let pad = 4;
+        let size = self.theme.titlebar_height.saturating_sub(2 * pad);
+        let slot = DecorationButton::ALL.iter().position(|b| *b == which).unwrap_or(0) as u32;
+        let right = w.saturating_sub(pad + (slot + 1) * (size + pad));
+        (x + right as i32, y + pad as i32, size, size)
+    }
+
+    /// Pointer → decoration element, None when inside the content area
+    pub fn hit_test(&self, frame: Bounds, px: i32, py: i32) -> Option<DecorationHit> {
+        if !contains(frame, px, py) {
+            return None;
+        }
+
+        for which in DecorationButton::ALL {
+            if contains(self.button_bounds(frame, which), px, py) {
+                return Some(DecorationHit::Button(which));
+            }
+        }
+
+        if contains(self.titlebar_bounds(frame), px, py) {
+            Some(DecorationHit::Titlebar)
+        } else if contains(self.content_bounds(frame), px, py) {
+            None
+        } else {
+            Some(DecorationHit::Border)
+        }
+    }
+
+    /// RGBA pixels of the titlebar strip for raw framebuffer blits
+    pub fn paint_titlebar(&self, frame: Bounds, focused: bool, title: &str) -> Vec<u8> {
+        let (_, _, w, h) = self.titlebar_bounds(frame);
+        let bar = if focused { self.theme.accent } else { self.theme.background };
+        let mut pixels = Vec::with_capacity((w * h * 4) as usize);
+
+        for row in 0..h {
+            for col in 0..w {
+                let color = DecorationButton::ALL
+                    .iter()
+                    .find(|b| contains(self.button_bounds((0, 0, w, h), **b), col as i32, row as i32))
+                    .map(|b| match b {
+                        DecorationButton::Close => self.theme.close,
+                        _ => self.theme.foreground,
+                    })
+                    .unwrap_or(bar);
+                pixels.extend_from_slice(&color.into_rgba8());
+            }
+        }
+
+        // Title in the built-in font, left of the buttons
+        let scale = (h / 2 / GLYPH_HEIGHT).max(1);
+        let pad = h / 4;
+        let buttons = DecorationButton::ALL
+            .iter()
+            .map(|b| self.button_bounds((0, 0, w, h), *b).0.max(0) as u32)
+            .min()
+            .unwrap_or(w);
+        let mut canvas = Canvas::from_rgba(w, h, pixels);
+        canvas.draw_label(
+            title,
+            pad,
+            h.saturating_sub(GLYPH_HEIGHT * scale) / 2,
+            buttons.saturating_sub(2 * pad),
+            scale,
+            self.theme.foreground,
+        );
+        canvas.into_rgba()
+    }
+
+    /// iced titlebar for the manager GUI
+    pub fn titlebar<'a>(&self, window: &Window) -> Element<'a, Message> {
+        let theme = self.theme.clone();
+        let glyph = match window.window_type {
+            WindowType::Dialog => "💬",
+            WindowType::Utility => "🛠",
+            _ => "🪟",
+        };
+
+        let mut bar = row![
+            text(glyph).size(theme.font_size),
+            Space::with_width(8),
+            text(window.title.clone())
+                .size(theme.font_size)
+                .style(theme.foreground),
+            Space::with_width(Length::Fill),
+        ]
+        .align_items(Alignment::Center)
+        .spacing(4);
+
+        for which in DecorationButton::ALL.iter().rev() {
+            bar = bar.push(
+                button(text(which.glyph()).size(theme.font_size))
+                    .on_press(which.message(window.id))
+                    .style(iced::theme::Button::Text),
+            );
+        }
+
+        let background = if window.focused { theme.accent } else { theme.background };
+
+        container(bar)
+            .width(Length::Fill)
+            .height(theme.titlebar_height as u16)
+            .padding([0, 8])
+            .center_y()
+            .style(move |_theme: &Theme| container::Appearance {
+                background: Some(Background::Color(background)),
+                text_color: Some(theme.foreground),
+                ..Default::default()
+            })
+            .into()
+    }
+}
+
+fn contains(bounds: Bounds, px: i32, py: i32) -> bool {
+    let (x, y, w, h) = bounds;
+    px >= x && py >= y && px < x + w as i32 && py < y + h as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_geometry() {
+        let deco = Decorations::default();
+        let frame = (100, 50, 800, 600);
+
+        let (cx, cy, cw, ch) = deco.content_bounds(frame);
+        assert_eq!((cx, cy), (101, 78));
+        assert_eq!((cw, ch), (798, 571));
+
+        let (bx, by, _, _) = deco.button_bounds(frame, DecorationButton::Close);
+        assert_eq!(deco.hit_test(frame, bx + 2, by + 2), Some(DecorationHit::Button(DecorationButton::Close)));
+        assert_eq!(deco.hit_test(frame, 110, 55), Some(DecorationHit::Titlebar));
+        assert_eq!(deco.hit_test(frame, 400, 300), None);
+        assert_eq!(deco.hit_test(frame, 100, 300), Some(DecorationHit::Border));
+
+        let pixels = deco.paint_titlebar(frame, false, "");
+        assert_eq!(pixels.len(), 800 * 28 * 4);
+
+        // Focus switches the bar colour, the title is drawn into it
+        let background = deco.theme().background.into_rgba8();
+        assert_eq!(pixels[..4], background);
+        assert_ne!(deco.paint_titlebar(frame, true, "")[..4], background);
+        assert_ne!(deco.paint_titlebar(frame, false, "Files"), pixels);
+    }
+
+    #[test]
+    fn test_theme_from_settings() {
+        let mut settings = WsdgSettings::default();
+        settings.window.decorations = false;
+        settings.theme.accent_color = "#ff0000".parse().unwrap();
+
+        let theme = DecorationTheme::from_settings(&settings);
+        assert!(!theme.enabled);
+        assert_eq!(theme.accent, Color::from_rgb(1.0, 0.0, 0.0));
+        assert_eq!(theme.titlebar_height, 28);
+
+        settings.theme.a11y_variant = "high-contrast".to_string();
+        let theme = DecorationTheme::from_settings(&settings);
+        assert_eq!(theme.foreground, Color::WHITE);
+        assert_eq!(theme.border_width, 3);
+    }
+}
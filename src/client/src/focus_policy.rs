@@ -0,0 +1,296 @@
+// focus_policy.rs
+// WASMA - Window focus policies
+// Click-to-focus, focus-follows-mouse (with hover delay), sloppy focus
+// and focus-stealing prevention based on window creation timestamps
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use wbackend::osd::{self, OsdIcon};
+use wsdg_xdg::{WsdgEnv, WsdgSettings, WsdgSettingsManager};
+
+use crate::window_handling::{Window, WindowHandler, WindowState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusPolicy {
+    /// Focus changes only on click
+    ClickToFocus,
+    /// Focus follows the pointer; hovering the desktop drops focus
+    FocusFollowsMouse,
+    /// Focus follows the pointer but stays put over the desktop
+    Sloppy,
+}
+
+impl FocusPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "click" | "click-to-focus" => Some(FocusPolicy::ClickToFocus),
+            "mouse" | "focus-follows-mouse" | "ffm" => Some(FocusPolicy::FocusFollowsMouse),
+            "sloppy" => Some(FocusPolicy::Sloppy),
+            _ => None,
+        }
+    }
+
+    fn follows_pointer(&self) -> bool {
+        !matches!(self, FocusPolicy::ClickToFocus)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FocusConfig {
+    pub policy: FocusPolicy,
+    pub delay: Duration,
+    pub prevent_stealing: bool,
+}
+
+impl Default for FocusConfig {
+    fn default() -> Self {
+        Self {
+            policy: FocusPolicy::ClickToFocus,
+            delay: Duration::from_millis(150),
+            prevent_stealing: true,
+        }
+    }
+}
+
+impl FocusConfig {
+    pub fn from_settings(settings: &WsdgSettings) -> Self {
+        let window = &settings.window;
+        Self {
+            policy: FocusPolicy::from_name(&window.focus_policy).unwrap_or(FocusPolicy::ClickToFocus),
+            delay: Duration::from_millis(window.focus_delay_ms as u64),
+            prevent_stealing: window.focus_stealing_prevention,
+        }
+    }
+
+    /// [window] section of the user's WSDG settings
+    pub fn from_wsdg() -> Self {
+        let mut manager = WsdgSettingsManager::new(WsdgEnv::new());
+        match manager.load() {
+            Ok(()) => Self::from_settings(manager.settings()),
+            Err(_) => Self::default(),
+        }
+    }
+}
+
+/// Pointer input as delivered by the backend's input routing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PointerEvent {
+    Motion { x: i32, y: i32 },
+    ButtonPress { x: i32, y: i32, button: u32 },
+    ButtonRelease { x: i32, y: i32, button: u32 },
+    /// Pointer left every output
+    Leave,
+}
+
+pub struct FocusEngine {
+    handler: Arc<WindowHandler>,
+    config: FocusConfig,
+    // window under the pointer and since when
+    hovered: Option<(Option<u64>, Instant)>,
+    last_user_input: Option<SystemTime>,
+    attention: HashSet<u64>,
+}
+
+impl FocusEngine {
+    pub fn new(handler: Arc<WindowHandler>, config: FocusConfig) -> Self {
+        Self {
+            handler,
+            config,
+            hovered: None,
+            last_user_input: None,
+            attention: HashSet::new(),
+        }
+    }
+
+    pub fn config(&self) -> &FocusConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: FocusConfig) {
+        self.config = config;
+        self.hovered = None;
+    }
+
+    /// Topmost focusable window at a point (focused first, then most recently active)
+    pub fn window_at(&self, x: i32, y: i32) -> Option<u64> {
+        let mut candidates: Vec<Window> = self.handler.list_windows()
+            .into_iter()
+            .filter(|w| w.visible && !matches!(w.state, WindowState::Minimized | WindowState::Hidden))
+            .filter(|w| {
+                let g = w.geometry;
+                x >= g.x && y >= g.y && x < g.x + g.width as i32 && y < g.y + g.height as i32
+            })
+            .collect();
+
+        candidates.sort_by_key(|w| (w.focused, w.last_activity));
+        candidates.last().map(|w| w.id)
+    }
+
+    /// Feed a pointer event; returns the window that received focus, if any
+    pub fn handle_pointer(&mut self, event: PointerEvent, now: Instant) -> Option<u64> {
+        match event {
+            PointerEvent::ButtonPress { x, y, .. } => {
+                self.note_user_input();
+                // A click focuses under every policy
+                let target = self.window_at(x, y)?;
+                self.focus(target)
+            }
+            PointerEvent::ButtonRelease { .. } => None,
+            PointerEvent::Motion { x, y } => {
+                if !self.config.policy.follows_pointer() {
+                    return None;
+                }
+                let target = self.window_at(x, y);
+                if self.hovered.map(|(h, _)| h) != Some(target) {
+                    self.hovered = Some((target, now));
+                }
+                self.tick(now)
+            }
+            PointerEvent::Leave => {
+                self.hovered = None;
+                None
+            }
+        }
+    }
+
+    /// Apply focus-follows-mouse once the hover delay has elapsed
+    pub fn tick(&mut self, now: Instant) -> Option<u64> {
+        let (target, since) = self.hovered?;
+        if now.saturating_duration_since(since) < self.config.delay {
+            return None;
+        }
+
+        match target {
+            Some(id) if self.handler.get_focused_window() != Some(id) => self.focus(id),
+            None if self.config.policy == FocusPolicy::FocusFollowsMouse => {
+                self.handler.clear_focus();
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether a hover is waiting for its delay to pass
+    pub fn is_pending(&self) -> bool {
+        self.hovered.is_some_and(|(target, _)| match target {
+            Some(id) => self.handler.get_focused_window() != Some(id),
+            None => self.config.policy == FocusPolicy::FocusFollowsMouse
+                && self.handler.get_focused_window().is_some(),
+        })
+    }
+
+    /// Keyboard or pointer input from the user
+    pub fn note_user_input(&mut self) {
+        self.last_user_input = Some(SystemTime::now());
+    }
+
+    /// Focus requested by an application (new window mapped, activation request).
+    /// With stealing prevention a window created before the user's last input
+    /// does not take focus from another window; it is marked as needing attention.
+    pub fn request_focus(&mut self, id: u64) -> Result<bool, String> {
+        let window = self.handler.get_window(id)
+            .ok_or_else(|| crate::t!("error-window-not-found", id = id))?;
+
+        let focused = self.handler.get_focused_window();
+        let stale = self.last_user_input.is_some_and(|input| window.created_at < input);
+
+        if self.config.prevent_stealing && stale && focused.is_some() && focused != Some(id) {
+            if self.attention.insert(id) {
+                osd::show(crate::t!("osd-wants-attention", title = window.title), OsdIcon::Info, Duration::from_secs(2));
+            }
+            return Ok(false);
+        }
+
+        self.focus(id);
+        Ok(true)
+    }
+
+    pub fn needs_attention(&self, id: u64) -> bool {
+        self.attention.contains(&id)
+    }
+
+    pub fn forget_window(&mut self, id: u64) {
+        self.attention.remove(&id);
+        if self.hovered.is_some_and(|(target, _)| target == Some(id)) {
+            self.hovered = None;
+        }
+    }
+
+    fn focus(&mut self, id: u64) -> Option<u64> {
+        match self.handler.focus_window(id) {
+            Ok(()) => {
+                self.attention.remove(&id);
+                Some(id)
+            }
+            Err(e) => {
+                eprintln!("❌ Could not focus {}: {}", id, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window_handling::WindowGeometry;
+    use wbackend::ResourceMode;
+
+    fn window(handler: &WindowHandler, x: i32) -> u64 {
+        handler.create_window(
+            format!("W{}", x),
+            "test.focus".to_string(),
+            WindowGeometry { x, y: 0, width: 100, height: 100 },
+            None,
+            ResourceMode::Manual,
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_follows_mouse_with_delay() {
+        let handler = Arc::new(WindowHandler::new(ResourceMode::Manual));
+        let a = window(&handler, 0);
+        let b = window(&handler, 200);
+
+        let mut engine = FocusEngine::new(handler.clone(), FocusConfig {
+            policy: FocusPolicy::FocusFollowsMouse,
+            delay: Duration::from_millis(100),
+            prevent_stealing: true,
+        });
+
+        let t0 = Instant::now();
+        assert_eq!(engine.handle_pointer(PointerEvent::Motion { x: 50, y: 50 }, t0), None);
+        assert_eq!(engine.tick(t0 + Duration::from_millis(150)), Some(a));
+
+        engine.handle_pointer(PointerEvent::Motion { x: 250, y: 50 }, t0 + Duration::from_millis(200));
+        assert_eq!(engine.tick(t0 + Duration::from_millis(400)), Some(b));
+
+        // Desktop: strict FFM drops focus
+        engine.handle_pointer(PointerEvent::Motion { x: 900, y: 900 }, t0 + Duration::from_millis(500));
+        engine.tick(t0 + Duration::from_millis(700));
+        assert_eq!(handler.get_focused_window(), None);
+    }
+
+    #[test]
+    fn test_click_to_focus_and_stealing_prevention() {
+        let handler = Arc::new(WindowHandler::new(ResourceMode::Manual));
+        let a = window(&handler, 0);
+        let background = window(&handler, 200);
+
+        let mut engine = FocusEngine::new(handler.clone(), FocusConfig::default());
+        assert_eq!(engine.handle_pointer(PointerEvent::Motion { x: 50, y: 50 }, Instant::now()), None);
+        assert_eq!(engine.handle_pointer(PointerEvent::ButtonPress { x: 50, y: 50, button: 1 }, Instant::now()), Some(a));
+
+        // Created before the user's click → may not steal focus
+        assert!(!engine.request_focus(background).unwrap());
+        assert!(engine.needs_attention(background));
+        assert_eq!(handler.get_focused_window(), Some(a));
+
+        // Created after the last input → allowed
+        let fresh = window(&handler, 400);
+        assert!(engine.request_focus(fresh).unwrap());
+        assert_eq!(handler.get_focused_window(), Some(fresh));
+    }
+}
@@ -0,0 +1,604 @@
+// WASMA - Global Hotkey Daemon
+// Launched/streamed applications request global shortcuts over a Unix socket.
+// Registration is gated by the window's PermissionScope, conflicting combos
+// across apps are rejected and activations are pushed back to the owner.
+//
+// Protocol (one line per request):
+//   REGISTER <window_id> <combo> <action> [description...]  -> OK <binding_id> | ERR <reason>
+//   UNREGISTER <window_id> <combo>                          -> OK | ERR <reason>
+//   TRIGGER <combo>                                          -> OK <action> | ERR <reason>
+//   LIST                                                     -> BINDING ... lines, then END
+// Owners receive `ACTIVATED <binding_id> <action>` when their combo fires.
+//
+// Peers are identified by SO_PEERCRED: only processes of our own user may
+// connect, REGISTER/UNREGISTER only name windows the peer process (or its
+// parent) owns, and TRIGGER is only accepted from the WASMA binary itself.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+use crate::control::peer_credentials;
+use crate::window_handling::WindowHandler;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum HotkeyError {
+    #[error("Invalid key combination: {0}")]
+    InvalidCombo(String),
+
+    #[error("Window {0} is not allowed to register global hotkeys")]
+    PermissionDenied(u64),
+
+    #[error("{combo} is already bound by {owner}")]
+    Conflict { combo: String, owner: String },
+
+    #[error("Window {0} not found")]
+    WindowNotFound(u64),
+
+    #[error("No binding for {0}")]
+    NotBound(String),
+
+    #[error("Window {0} does not belong to the requesting process")]
+    NotOwner(u64),
+
+    #[error("Only WASMA itself may trigger hotkeys")]
+    TriggerDenied,
+
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+}
+
+// ============================================================================
+// KEY COMBINATIONS
+// ============================================================================
+
+/// Modifier set of a key combination
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub super_key: bool,
+}
+
+/// Normalized key combination ("Ctrl+Alt+T", "super+left", ...)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    pub modifiers: Modifiers,
+    pub key: String,
+}
+
+impl KeyCombo {
+    /// Parse "Mod+Mod+Key" – modifiers are case-insensitive, the key is normalized
+    pub fn parse(input: &str) -> Result<Self, HotkeyError> {
+        let mut modifiers = Modifiers::default();
+        let mut key = None;
+
+        for part in input.split('+').map(str::trim) {
+            if part.is_empty() {
+                return Err(HotkeyError::InvalidCombo(input.to_string()));
+            }
+
+            match part.to_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "alt" | "mod1" => modifiers.alt = true,
+                "shift" => modifiers.shift = true,
+                "super" | "meta" | "logo" | "win" | "mod4" => modifiers.super_key = true,
+                _ if key.is_none() => key = Some(normalize_key(part)),
+                _ => return Err(HotkeyError::InvalidCombo(input.to_string())),
+            }
+        }
+
+        let key = key.ok_or_else(|| HotkeyError::InvalidCombo(input.to_string()))?;
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.super_key {
+            write!(f, "Super+")?;
+        }
+        if self.modifiers.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.shift {
+            write!(f, "Shift+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Single characters upper-case, named keys capitalized ("left" → "Left")
+fn normalize_key(key: &str) -> String {
+    if key.chars().count() == 1 {
+        return key.to_uppercase();
+    }
+
+    let lower = key.to_lowercase();
+    let mut chars = lower.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+// ============================================================================
+// REGISTRY
+// ============================================================================
+
+/// A registered global shortcut
+#[derive(Debug, Clone)]
+pub struct HotkeyBinding {
+    pub id: u64,
+    pub window_id: u64,
+    pub app_id: String,
+    pub combo: KeyCombo,
+    pub action: String,
+    pub description: String,
+}
+
+/// Who owns a combo: an application binding or WASMA itself
+#[derive(Debug, Clone)]
+enum Owner {
+    App(HotkeyBinding),
+    System(String),
+}
+
+/// Registered bindings + activation subscribers
+pub struct HotkeyRegistry {
+    handler: Arc<WindowHandler>,
+    bindings: Mutex<HashMap<KeyCombo, Owner>>,
+    subscribers: Mutex<HashMap<u64, Sender<String>>>,
+    next_id: Mutex<u64>,
+}
+
+impl HotkeyRegistry {
+    pub fn new(handler: Arc<WindowHandler>) -> Self {
+        Self {
+            handler,
+            bindings: Mutex::new(HashMap::new()),
+            subscribers: Mutex::new(HashMap::new()),
+            next_id: Mutex::new(1),
+        }
+    }
+
+    /// Reserve a combo for WASMA's own shortcuts – apps can never take it
+    pub fn reserve(&self, combo: KeyCombo, name: &str) {
+        self.bindings.lock().unwrap().insert(combo, Owner::System(name.to_string()));
+    }
+
+    /// The window named in a request must belong to the connected process
+    pub fn authorize(&self, peer_pid: u32, window_id: u64) -> Result<(), HotkeyError> {
+        if self.handler.get_window(window_id).is_none() {
+            return Err(HotkeyError::WindowNotFound(window_id));
+        }
+        if !self.handler.windows_for_pid(peer_pid).contains(&window_id) {
+            return Err(HotkeyError::NotOwner(window_id));
+        }
+        Ok(())
+    }
+
+    /// Register a combo for a window (permission + conflict checked)
+    pub fn register(
+        &self,
+        window_id: u64,
+        combo: KeyCombo,
+        action: &str,
+        description: &str,
+    ) -> Result<u64, HotkeyError> {
+        let window = self.handler.get_window(window_id)
+            .ok_or(HotkeyError::WindowNotFound(window_id))?;
+
+        if !window.permissions.can_register_hotkeys {
+            return Err(HotkeyError::PermissionDenied(window_id));
+        }
+
+        let mut bindings = self.bindings.lock().unwrap();
+        match bindings.get(&combo) {
+            Some(Owner::System(name)) => {
+                return Err(HotkeyError::Conflict {
+                    combo: combo.to_string(),
+                    owner: format!("WASMA ({})", name),
+                });
+            }
+            Some(Owner::App(existing)) if existing.window_id != window_id => {
+                return Err(HotkeyError::Conflict {
+                    combo: combo.to_string(),
+                    owner: format!("{} (window {})", existing.app_id, existing.window_id),
+                });
+            }
+            // Re-registering its own combo replaces the binding and its subscriber
+            Some(Owner::App(previous)) => {
+                self.subscribers.lock().unwrap().remove(&previous.id);
+            }
+            None => {}
+        }
+
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            let id = *next_id;
+            *next_id += 1;
+            id
+        };
+
+        bindings.insert(combo.clone(), Owner::App(HotkeyBinding {
+            id,
+            window_id,
+            app_id: window.app_id.clone(),
+            combo,
+            action: action.to_string(),
+            description: description.to_string(),
+        }));
+
+        Ok(id)
+    }
+
+    pub fn unregister(&self, window_id: u64, combo: &KeyCombo) -> Result<(), HotkeyError> {
+        let mut bindings = self.bindings.lock().unwrap();
+        match bindings.get(combo) {
+            Some(Owner::App(b)) if b.window_id == window_id => {
+                let id = b.id;
+                bindings.remove(combo);
+                self.subscribers.lock().unwrap().remove(&id);
+                Ok(())
+            }
+            _ => Err(HotkeyError::NotBound(combo.to_string())),
+        }
+    }
+
+    /// Drop every binding of a window (window closed / client disconnected)
+    pub fn unregister_window(&self, window_id: u64) {
+        // Same lock order as register/unregister: bindings, then subscribers
+        let mut bindings = self.bindings.lock().unwrap();
+        let mut subscribers = self.subscribers.lock().unwrap();
+        bindings.retain(|_, owner| match owner {
+            Owner::App(b) if b.window_id == window_id => {
+                subscribers.remove(&b.id);
+                false
+            }
+            _ => true,
+        });
+    }
+
+    /// Deliver activations of a binding to a channel
+    pub fn subscribe(&self, binding_id: u64, sender: Sender<String>) {
+        self.subscribers.lock().unwrap().insert(binding_id, sender);
+    }
+
+    /// A combo was pressed: notify its owner, returns the fired binding
+    pub fn trigger(&self, combo: &KeyCombo) -> Option<HotkeyBinding> {
+        let binding = match self.bindings.lock().unwrap().get(combo) {
+            Some(Owner::App(b)) => b.clone(),
+            _ => return None,
+        };
+
+        if let Some(sender) = self.subscribers.lock().unwrap().get(&binding.id) {
+            sender.send(format!("ACTIVATED {} {}", binding.id, binding.action)).ok();
+        }
+        Some(binding)
+    }
+
+    /// All application bindings, sorted by combo
+    pub fn list(&self) -> Vec<HotkeyBinding> {
+        let mut list: Vec<HotkeyBinding> = self.bindings.lock().unwrap()
+            .values()
+            .filter_map(|owner| match owner {
+                Owner::App(b) => Some(b.clone()),
+                Owner::System(_) => None,
+            })
+            .collect();
+        list.sort_by_key(|b| b.combo.to_string());
+        list
+    }
+
+    /// Reserved (WASMA-owned) combos with their names
+    pub fn reserved(&self) -> Vec<(KeyCombo, String)> {
+        self.bindings.lock().unwrap()
+            .iter()
+            .filter_map(|(combo, owner)| match owner {
+                Owner::System(name) => Some((combo.clone(), name.clone())),
+                Owner::App(_) => None,
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// DAEMON
+// ============================================================================
+
+/// Default socket: <runtime dir>/hotkeys.sock, next to the control socket
+pub fn default_socket_path() -> PathBuf {
+    wbackend::rundir::runtime_dir().join("hotkeys.sock")
+}
+
+/// Hotkey daemon serving the registration protocol
+pub struct HotkeyDaemon {
+    registry: Arc<HotkeyRegistry>,
+    socket_path: PathBuf,
+}
+
+impl HotkeyDaemon {
+    pub fn new(registry: Arc<HotkeyRegistry>, socket_path: PathBuf) -> Self {
+        Self { registry, socket_path }
+    }
+
+    pub fn registry(&self) -> Arc<HotkeyRegistry> {
+        self.registry.clone()
+    }
+
+    /// Bind the socket and serve clients on background threads
+    pub fn start(&self) -> std::io::Result<()> {
+        if let Some(parent) = self.socket_path.parent() {
+            wbackend::rundir::ensure_private(parent)?;
+        }
+
+        // Never steal the socket from a live instance
+        if UnixStream::connect(&self.socket_path).is_ok() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!("another hotkey daemon is serving {}", self.socket_path.display()),
+            ));
+        }
+        std::fs::remove_file(&self.socket_path).ok();
+
+        let listener = UnixListener::bind(&self.socket_path)?;
+        let registry = self.registry.clone();
+
+        tracing::info!(socket = %self.socket_path.display(), "hotkey daemon listening");
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().map_while(Result::ok) {
+                let registry = registry.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = serve_client(registry, stream) {
+                        tracing::warn!(error = %e, "hotkey client error");
+                    }
+                });
+            }
+        });
+
+        Ok(())
+    }
+}
+
+fn serve_client(registry: Arc<HotkeyRegistry>, stream: UnixStream) -> std::io::Result<()> {
+    let peer = peer_credentials(&stream)?;
+    if peer.uid != unsafe { libc::geteuid() } {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("connection from uid {} refused", peer.uid),
+        ));
+    }
+    let (tx, rx) = mpsc::channel::<String>();
+
+    // Writer thread: replies and activation events share one ordered channel
+    let mut writer = stream.try_clone()?;
+    std::thread::spawn(move || {
+        for line in rx {
+            if writeln!(writer, "{}", line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut owned_windows = Vec::new();
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        let replies = handle_request(&registry, peer.pid as u32, &line, &tx, &mut owned_windows);
+        for reply in replies {
+            tx.send(reply).ok();
+        }
+    }
+
+    // Client gone: its shortcuts go with it
+    for window_id in owned_windows {
+        registry.unregister_window(window_id);
+    }
+    Ok(())
+}
+
+/// Execute one protocol line from process `peer_pid`, returns the reply lines
+pub fn handle_request(
+    registry: &HotkeyRegistry,
+    peer_pid: u32,
+    line: &str,
+    events: &Sender<String>,
+    owned_windows: &mut Vec<u64>,
+) -> Vec<String> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let result = match parts.as_slice() {
+        ["REGISTER", window_id, combo, action, description @ ..] => parse_window_id(window_id)
+            .and_then(|window_id| {
+                registry.authorize(peer_pid, window_id)?;
+                let combo = KeyCombo::parse(combo)?;
+                let id = registry.register(window_id, combo, action, &description.join(" "))?;
+                registry.subscribe(id, events.clone());
+                if !owned_windows.contains(&window_id) {
+                    owned_windows.push(window_id);
+                }
+                Ok(vec![format!("OK {}", id)])
+            }),
+        ["UNREGISTER", window_id, combo] => parse_window_id(window_id)
+            .and_then(|window_id| {
+                registry.authorize(peer_pid, window_id)?;
+                registry.unregister(window_id, &KeyCombo::parse(combo)?)?;
+                Ok(vec!["OK".to_string()])
+            }),
+        ["TRIGGER", _] if !is_wasma_process(peer_pid) => Err(HotkeyError::TriggerDenied),
+        ["TRIGGER", combo] => KeyCombo::parse(combo).and_then(|combo| {
+            registry.trigger(&combo)
+                .map(|b| vec![format!("OK {}", b.action)])
+                .ok_or_else(|| HotkeyError::NotBound(combo.to_string()))
+        }),
+        ["LIST"] => {
+            let mut lines: Vec<String> = registry.list()
+                .iter()
+                .map(|b| format!("BINDING {} {} {} {} {}", b.id, b.window_id, b.app_id, b.combo, b.action))
+                .collect();
+            lines.push("END".to_string());
+            Ok(lines)
+        }
+        _ => Err(HotkeyError::Protocol(format!("unknown request '{}'", line.trim()))),
+    };
+
+    result.unwrap_or_else(|e| vec![format!("ERR {}", e)])
+}
+
+/// Whether `pid` runs the same executable as this process (`wasma hotkeys --trigger`)
+fn is_wasma_process(pid: u32) -> bool {
+    let exe = std::fs::read_link(format!("/proc/{}/exe", pid));
+    match (exe, std::env::current_exe()) {
+        (Ok(exe), Ok(ours)) => exe == ours,
+        _ => false,
+    }
+}
+
+fn parse_window_id(raw: &str) -> Result<u64, HotkeyError> {
+    raw.parse().map_err(|_| HotkeyError::Protocol(format!("invalid window id '{}'", raw)))
+}
+
+/// Client side: send one request to a running daemon and collect the reply
+pub fn query_daemon(socket_path: &PathBuf, request: &str) -> std::io::Result<Vec<String>> {
+    let mut stream = UnixStream::connect(socket_path)?;
+    writeln!(stream, "{}", request)?;
+
+    let multi_line = request.trim() == "LIST";
+    let mut replies = Vec::new();
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        let done = !multi_line || line == "END";
+        replies.push(line);
+        if done {
+            break;
+        }
+    }
+    Ok(replies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window_handling::WindowGeometry;
+    use wbackend::ResourceMode;
+
+    fn handler_with_window(allowed: bool) -> (Arc<WindowHandler>, u64) {
+        let handler = Arc::new(WindowHandler::new(ResourceMode::Auto));
+        let id = handler.create_window(
+            "Hotkey App".to_string(),
+            "test.hotkeys".to_string(),
+            WindowGeometry { x: 0, y: 0, width: 100, height: 100 },
+            None,
+            ResourceMode::Auto,
+        ).unwrap();
+        allow_hotkeys(&handler, id, allowed);
+        (handler, id)
+    }
+
+    fn allow_hotkeys(handler: &WindowHandler, id: u64, allowed: bool) {
+        let mut permissions = handler.get_window(id).unwrap().permissions;
+        permissions.can_register_hotkeys = allowed;
+        handler.set_permissions(id, permissions).unwrap();
+    }
+
+    #[test]
+    fn test_combo_parsing() {
+        let combo = KeyCombo::parse("ctrl+ALT+t").unwrap();
+        assert!(combo.modifiers.ctrl && combo.modifiers.alt);
+        assert_eq!(combo.to_string(), "Ctrl+Alt+T");
+        assert_eq!(KeyCombo::parse("Super+left").unwrap().to_string(), "Super+Left");
+        assert_eq!(combo, KeyCombo::parse("Alt+Ctrl+t").unwrap());
+
+        assert!(KeyCombo::parse("Ctrl+").is_err());
+        assert!(KeyCombo::parse("Ctrl+Shift").is_err());
+        assert!(KeyCombo::parse("A+B").is_err());
+    }
+
+    #[test]
+    fn test_register_requires_permission() {
+        let (handler, id) = handler_with_window(false);
+        let registry = HotkeyRegistry::new(handler);
+
+        let err = registry.register(id, KeyCombo::parse("Ctrl+Alt+T").unwrap(), "open", "").unwrap_err();
+        assert_eq!(err, HotkeyError::PermissionDenied(id));
+    }
+
+    #[test]
+    fn test_conflicts_and_trigger() {
+        let (handler, first) = handler_with_window(true);
+        let second = handler.create_window(
+            "Other".to_string(),
+            "test.other".to_string(),
+            WindowGeometry { x: 0, y: 0, width: 100, height: 100 },
+            None,
+            ResourceMode::Auto,
+        ).unwrap();
+        allow_hotkeys(&handler, second, true);
+
+        let registry = HotkeyRegistry::new(handler);
+        let combo = KeyCombo::parse("Ctrl+Alt+T").unwrap();
+        let binding = registry.register(first, combo.clone(), "open", "Open terminal").unwrap();
+
+        assert!(matches!(
+            registry.register(second, combo.clone(), "other", ""),
+            Err(HotkeyError::Conflict { .. })
+        ));
+
+        registry.reserve(KeyCombo::parse("Super+Left").unwrap(), "snap-left");
+        assert!(registry.register(first, KeyCombo::parse("super+left").unwrap(), "x", "").is_err());
+
+        let (tx, rx) = mpsc::channel();
+        registry.subscribe(binding, tx);
+        assert_eq!(registry.trigger(&combo).unwrap().action, "open");
+        assert_eq!(rx.try_recv().unwrap(), format!("ACTIVATED {} open", binding));
+
+        // Binding the same combo again drops the replaced binding's subscriber
+        let rebound = registry.register(first, combo.clone(), "open", "Open terminal").unwrap();
+        assert!(registry.subscribers.lock().unwrap().is_empty());
+        registry.subscribe(rebound, mpsc::channel().0);
+
+        registry.unregister_window(first);
+        assert!(registry.list().is_empty());
+        assert!(registry.subscribers.lock().unwrap().is_empty());
+        assert!(registry.trigger(&combo).is_none());
+    }
+
+    #[test]
+    fn test_protocol_requests() {
+        let (handler, id) = handler_with_window(true);
+        let registry = HotkeyRegistry::new(handler.clone());
+        let (tx, _rx) = mpsc::channel();
+        let mut owned = Vec::new();
+        let me = std::process::id();
+        let register = format!("REGISTER {} Ctrl+Shift+P palette Show palette", id);
+
+        // The window is not ours until the kernel says so
+        let reply = handle_request(&registry, me, &register, &tx, &mut owned);
+        assert_eq!(reply, vec![format!("ERR {}", HotkeyError::NotOwner(id))]);
+        assert!(owned.is_empty());
+
+        handler.set_window_pid(id, me);
+        let reply = handle_request(&registry, me, &register, &tx, &mut owned);
+        assert_eq!(reply, vec!["OK 1".to_string()]);
+        assert_eq!(owned, vec![id]);
+
+        let listed = handle_request(&registry, me, "LIST", &tx, &mut owned);
+        assert_eq!(listed.len(), 2);
+        assert!(listed[0].contains("Ctrl+Shift+P"));
+
+        // Only the WASMA binary may fire bindings
+        assert_eq!(handle_request(&registry, me, "TRIGGER ctrl+shift+p", &tx, &mut owned), vec!["OK palette".to_string()]);
+        let denied = handle_request(&registry, 1, "TRIGGER ctrl+shift+p", &tx, &mut owned);
+        assert_eq!(denied, vec![format!("ERR {}", HotkeyError::TriggerDenied)]);
+        assert!(handle_request(&registry, me, "BOGUS", &tx, &mut owned)[0].starts_with("ERR"));
+    }
+}
@@ -0,0 +1,508 @@
+// WASMA - gRPC display stream
+// Client for wasma.display.Display/StreamFrames (proto/wasma_display.proto) over
+// HTTP/2 with prior knowledge (h2c). gRPC length-prefixed messages are reassembled
+// from DATA frames, decoded into typed frames (width, height, stride, pixel format)
+// and normalized to tightly packed BGRA for the display path. No grpc-encoding is
+// negotiated, so compressed messages are refused.
+
+use bytes::{Buf, Bytes, BytesMut};
+use h2::client::SendRequest;
+use h2::RecvStream;
+use http::{HeaderMap, Method, Request};
+use thiserror::Error;
+use tokio::net::TcpStream;
+
+use crate::pixel_format::{self, FrameHeader, PixelFormat};
+
+/// HTTP/2 path of the server-streaming frame call
+pub const STREAM_FRAMES_PATH: &str = "/wasma.display.Display/StreamFrames";
+/// Largest message accepted (a 4K RGBA frame is ~32 MiB)
+pub const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum GrpcError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("HTTP/2 error: {0}")]
+    Http2(#[from] h2::Error),
+
+    #[error("gRPC status {code}: {message}")]
+    Status { code: u32, message: String },
+
+    #[error("gRPC protocol error: {0}")]
+    Protocol(String),
+
+    #[error("Malformed frame message: {0}")]
+    Decode(String),
+}
+
+// ============================================================================
+// SCHEMA
+// ============================================================================
+
+/// `FrameRequest`: which stream, and the frame size the client would like
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameRequest {
+    pub stream_id: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FrameRequest {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        put_varint_field(&mut out, 1, self.stream_id as u64);
+        put_varint_field(&mut out, 2, self.width as u64);
+        put_varint_field(&mut out, 3, self.height as u64);
+        out
+    }
+
+    pub fn decode(buf: &[u8]) -> Result<Self, GrpcError> {
+        let mut request = Self::default();
+        for field in Fields::new(buf) {
+            match field? {
+                (1, Value::Varint(v)) => request.stream_id = v as u32,
+                (2, Value::Varint(v)) => request.width = v as u32,
+                (3, Value::Varint(v)) => request.height = v as u32,
+                _ => {}
+            }
+        }
+        Ok(request)
+    }
+}
+
+/// `Frame`: one picture of the display stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub sequence: u64,
+    pub width: u32,
+    pub height: u32,
+    pub stride: u32,
+    pub format: PixelFormat,
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.data.len() + 32);
+        put_varint_field(&mut out, 1, self.sequence);
+        put_varint_field(&mut out, 2, self.width as u64);
+        put_varint_field(&mut out, 3, self.height as u64);
+        put_varint_field(&mut out, 4, self.stride as u64);
+        put_varint_field(&mut out, 5, self.format.code() as u64);
+        put_bytes_field(&mut out, 6, &self.data);
+        out
+    }
+
+    /// Decode and check that `data` covers `height` rows of `stride` bytes
+    pub fn decode(buf: &[u8]) -> Result<Self, GrpcError> {
+        let (mut sequence, mut width, mut height, mut stride, mut format) = (0, 0, 0, 0, 0);
+        let mut data: &[u8] = &[];
+        for field in Fields::new(buf) {
+            match field? {
+                (1, Value::Varint(v)) => sequence = v,
+                (2, Value::Varint(v)) => width = v as u32,
+                (3, Value::Varint(v)) => height = v as u32,
+                (4, Value::Varint(v)) => stride = v as u32,
+                (5, Value::Varint(v)) => format = v,
+                (6, Value::Bytes(b)) => data = b,
+                _ => {}
+            }
+        }
+
+        let format = u8::try_from(format).ok().and_then(PixelFormat::from_code)
+            .ok_or_else(|| GrpcError::Decode(format!("unknown pixel format {}", format)))?;
+        let frame = Self { sequence, width, height, stride, format, data: data.to_vec() };
+        frame.header().validate(frame.data.len()).map_err(|e| GrpcError::Decode(e.to_string()))?;
+        Ok(frame)
+    }
+
+    pub fn header(&self) -> FrameHeader {
+        FrameHeader { width: self.width, height: self.height, stride: self.stride, format: self.format }
+    }
+
+    /// Tightly packed BGRA rows, the layout the display path writes out
+    pub fn to_bgra(&self) -> Vec<u8> {
+        pixel_format::to_bgra(&self.header(), &self.data)
+    }
+}
+
+// ============================================================================
+// PROTOBUF WIRE FORMAT
+// ============================================================================
+
+pub(crate) enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// (field number, value) pairs of a message; unknown fields are skipped by the caller
+pub(crate) struct Fields<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn varint(&mut self) -> Result<u64, GrpcError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = self.buf.split_first()
+                .ok_or_else(|| GrpcError::Decode("truncated varint".to_string()))?;
+            self.buf = rest;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(GrpcError::Decode("varint longer than 10 bytes".to_string()))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], GrpcError> {
+        if self.buf.len() < len {
+            return Err(GrpcError::Decode(format!("field of {} bytes, {} left", len, self.buf.len())));
+        }
+        let (taken, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(taken)
+    }
+
+    fn field(&mut self) -> Result<(u64, Value<'a>), GrpcError> {
+        let tag = self.varint()?;
+        let value = match tag & 7 {
+            0 => Value::Varint(self.varint()?),
+            1 => self.take(8).map(|_| Value::Fixed)?,
+            2 => {
+                let len = self.varint()? as usize;
+                Value::Bytes(self.take(len)?)
+            }
+            5 => self.take(4).map(|_| Value::Fixed)?,
+            wire => return Err(GrpcError::Decode(format!("unsupported wire type {}", wire))),
+        };
+        Ok((tag >> 3, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u64, Value<'a>), GrpcError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        let field = self.field();
+        if field.is_err() {
+            self.buf = &[];
+        }
+        Some(field)
+    }
+}
+
+pub(crate) fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// proto3 leaves zero scalars off the wire
+pub(crate) fn put_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    if value != 0 {
+        put_varint(out, field << 3);
+        put_varint(out, value);
+    }
+}
+
+/// Length-delimited field (string, bytes, embedded message); always written
+pub(crate) fn put_bytes_field(out: &mut Vec<u8>, field: u64, value: &[u8]) {
+    put_varint(out, (field << 3) | 2);
+    put_varint(out, value.len() as u64);
+    out.extend_from_slice(value);
+}
+
+// ============================================================================
+// gRPC MESSAGE FRAMING
+// ============================================================================
+
+/// gRPC message prefix: compressed flag + big-endian length
+pub fn encode_message(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 5);
+    out.push(0);
+    out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Reassembles length-prefixed messages that span (or share) HTTP/2 DATA frames
+#[derive(Debug, Default)]
+pub struct MessageDecoder {
+    buf: BytesMut,
+}
+
+impl MessageDecoder {
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.buf.extend_from_slice(chunk);
+    }
+
+    pub fn next_message(&mut self) -> Result<Option<Bytes>, GrpcError> {
+        if self.buf.len() < 5 {
+            return Ok(None);
+        }
+        if self.buf[0] != 0 {
+            return Err(GrpcError::Protocol("compressed message without a negotiated grpc-encoding".to_string()));
+        }
+        let len = u32::from_be_bytes([self.buf[1], self.buf[2], self.buf[3], self.buf[4]]) as usize;
+        if len > MAX_MESSAGE_SIZE {
+            return Err(GrpcError::Protocol(format!("{}-byte message exceeds {} bytes", len, MAX_MESSAGE_SIZE)));
+        }
+        if self.buf.len() < 5 + len {
+            return Ok(None);
+        }
+        self.buf.advance(5);
+        Ok(Some(self.buf.split_to(len).freeze()))
+    }
+
+    /// No partial message pending
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+}
+
+/// grpc-status / grpc-message of a trailer (or trailers-only header) block
+fn check_status(headers: &HeaderMap) -> Result<bool, GrpcError> {
+    let Some(status) = headers.get("grpc-status") else {
+        return Ok(false);
+    };
+    let code = status.to_str().ok().and_then(|s| s.trim().parse().ok())
+        .ok_or_else(|| GrpcError::Protocol(format!("invalid grpc-status {:?}", status)))?;
+    if code == 0 {
+        return Ok(true);
+    }
+    let message = headers.get("grpc-message")
+        .and_then(|m| m.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    Err(GrpcError::Status { code, message })
+}
+
+// ============================================================================
+// CLIENT
+// ============================================================================
+
+/// HTTP/2 connection to a display server; calls share the connection
+#[derive(Clone)]
+pub struct GrpcClient {
+    send_request: SendRequest<Bytes>,
+    authority: String,
+    token: Option<String>,
+}
+
+impl GrpcClient {
+    /// HTTP/2 handshake on an established connection to `authority` (host:port)
+    pub async fn handshake(io: TcpStream, authority: impl Into<String>) -> Result<Self, GrpcError> {
+        let (send_request, connection) = h2::client::handshake(io).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                eprintln!("⚠️  gRPC connection closed: {}", e);
+            }
+        });
+        Ok(Self { send_request, authority: authority.into(), token: None })
+    }
+
+    /// Send `authorization: Bearer <token>` with every call
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Start a StreamFrames call
+    pub async fn stream_frames(&self, request: &FrameRequest) -> Result<FrameStream, GrpcError> {
+        let messages = self.call(STREAM_FRAMES_PATH, &request.encode()).await?;
+        Ok(FrameStream { messages })
+    }
+
+    /// Unary call: one request message, exactly one reply message
+    pub async fn unary(&self, path: &str, request: &[u8]) -> Result<Bytes, GrpcError> {
+        let mut messages = self.call(path, request).await?;
+        let reply = messages.next_message().await?
+            .ok_or_else(|| GrpcError::Protocol(format!("{} returned no message", path)))?;
+        if messages.next_message().await?.is_some() {
+            return Err(GrpcError::Protocol(format!("{} returned more than one message", path)));
+        }
+        Ok(reply)
+    }
+
+    /// Send one request message to `path` and read the reply messages
+    pub async fn call(&self, path: &str, request: &[u8]) -> Result<MessageStream, GrpcError> {
+        let mut send_request = self.send_request.clone().ready().await?;
+        let mut http_request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("http://{}{}", self.authority, path))
+            .header("content-type", "application/grpc+proto")
+            .header("te", "trailers");
+        if let Some(token) = &self.token {
+            http_request = http_request.header("authorization", format!("Bearer {}", token));
+        }
+        let http_request = http_request
+            .body(())
+            .map_err(|e| GrpcError::Protocol(e.to_string()))?;
+
+        let (response, mut body) = send_request.send_request(http_request, false)?;
+        body.send_data(Bytes::from(encode_message(request)), true)?;
+
+        let (parts, body) = response.await?.into_parts();
+        if parts.status != http::StatusCode::OK {
+            return Err(GrpcError::Protocol(format!("HTTP status {}", parts.status)));
+        }
+        let content_type = parts.headers.get("content-type").and_then(|v| v.to_str().ok()).unwrap_or_default();
+        if !content_type.starts_with("application/grpc") {
+            return Err(GrpcError::Protocol(format!("unexpected content-type {:?}", content_type)));
+        }
+        // Trailers-only response: the call ended (or failed) before any message
+        let finished = check_status(&parts.headers)?;
+
+        Ok(MessageStream { body, decoder: MessageDecoder::default(), finished })
+    }
+}
+
+/// Frames of one StreamFrames call
+pub struct FrameStream {
+    messages: MessageStream,
+}
+
+impl FrameStream {
+    /// Next frame; Ok(None) once the server ends the call with status OK
+    pub async fn next_frame(&mut self) -> Result<Option<Frame>, GrpcError> {
+        match self.messages.next_message().await? {
+            Some(message) => Frame::decode(&message).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Reply messages of one call
+pub struct MessageStream {
+    body: RecvStream,
+    decoder: MessageDecoder,
+    finished: bool,
+}
+
+impl MessageStream {
+    /// Next message; Ok(None) once the server ends the call with status OK
+    pub async fn next_message(&mut self) -> Result<Option<Bytes>, GrpcError> {
+        loop {
+            if let Some(message) = self.decoder.next_message()? {
+                return Ok(Some(message));
+            }
+            if self.finished {
+                return Ok(None);
+            }
+
+            match self.body.data().await {
+                Some(chunk) => {
+                    let chunk = chunk?;
+                    let _ = self.body.flow_control().release_capacity(chunk.len());
+                    self.decoder.push(&chunk);
+                }
+                None => {
+                    self.finished = true;
+                    if !self.decoder.is_empty() {
+                        return Err(GrpcError::Protocol("call ended inside a message".to_string()));
+                    }
+                    let trailers = self.body.trailers().await?.unwrap_or_default();
+                    if !check_status(&trailers)? {
+                        return Err(GrpcError::Protocol("call ended without grpc-status".to_string()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serves one StreamFrames call: `frames`, split at awkward offsets, then `status`
+    async fn serve(listener: tokio::net::TcpListener, frames: Vec<Frame>, status: &'static str) -> FrameRequest {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut connection = h2::server::handshake(socket).await.unwrap();
+        let (request, mut respond) = connection.accept().await.unwrap().unwrap();
+        tokio::spawn(async move { while connection.accept().await.is_some() {} });
+        assert_eq!(request.uri().path(), STREAM_FRAMES_PATH);
+
+        let mut body = request.into_body();
+        let mut decoder = MessageDecoder::default();
+        while let Some(chunk) = body.data().await {
+            decoder.push(&chunk.unwrap());
+        }
+        let frame_request = FrameRequest::decode(&decoder.next_message().unwrap().unwrap()).unwrap();
+
+        let response = http::Response::builder().header("content-type", "application/grpc").body(()).unwrap();
+        let mut send = respond.send_response(response, false).unwrap();
+        let wire: Vec<u8> = frames.iter().flat_map(|f| encode_message(&f.encode())).collect();
+        for piece in wire.chunks(7) {
+            send.send_data(Bytes::copy_from_slice(piece), false).unwrap();
+        }
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", status.parse().unwrap());
+        trailers.insert("grpc-message", "bye".parse().unwrap());
+        send.send_trailers(trailers).unwrap();
+        frame_request
+    }
+
+    #[test]
+    fn test_grpc_frame_stream() {
+        let rgb = Frame {
+            sequence: 1,
+            width: 2,
+            height: 2,
+            stride: 8,
+            format: PixelFormat::Rgb888,
+            data: vec![1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11, 12],
+        };
+        let rgb565 = Frame { sequence: 2, width: 1, height: 1, stride: 2, format: PixelFormat::Rgb565, data: vec![0x00, 0xf8] };
+        assert_eq!(rgb.to_bgra(), [3, 2, 1, 255, 6, 5, 4, 255, 9, 8, 7, 255, 12, 11, 10, 255]);
+        assert_eq!(rgb565.to_bgra(), [0, 0, 255, 255]);
+
+        // Schema checks instead of trusting raw bytes
+        let short = Frame { data: vec![0; 13], ..rgb.clone() };
+        assert!(matches!(Frame::decode(&short.encode()), Err(GrpcError::Decode(_))));
+        let narrow = Frame { stride: 5, ..rgb.clone() };
+        assert!(matches!(Frame::decode(&narrow.encode()), Err(GrpcError::Decode(_))));
+        let mut compressed = MessageDecoder::default();
+        compressed.push(&[1, 0, 0, 0, 0]);
+        assert!(matches!(compressed.next_message(), Err(GrpcError::Protocol(_))));
+
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let server = tokio::spawn(serve(listener, vec![rgb.clone(), rgb565.clone()], "0"));
+
+            let client = GrpcClient::handshake(TcpStream::connect(addr).await.unwrap(), addr.to_string()).await.unwrap();
+            let request = FrameRequest { stream_id: 3, width: 1280, height: 720 };
+            let mut frames = client.stream_frames(&request).await.unwrap();
+            assert_eq!(frames.next_frame().await.unwrap(), Some(rgb.clone()));
+            assert_eq!(frames.next_frame().await.unwrap(), Some(rgb565.clone()));
+            assert_eq!(frames.next_frame().await.unwrap(), None);
+            assert_eq!(server.await.unwrap(), request);
+
+            // Non-OK grpc-status surfaces as a status error
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(serve(listener, Vec::new(), "14"));
+            let client = GrpcClient::handshake(TcpStream::connect(addr).await.unwrap(), addr.to_string()).await.unwrap();
+            let mut frames = client.stream_frames(&FrameRequest::default()).await.unwrap();
+            match frames.next_frame().await {
+                Err(GrpcError::Status { code, message }) => assert_eq!((code, message.as_str()), (14, "bye")),
+                other => panic!("expected UNAVAILABLE, got {:?}", other.map(|f| f.is_some())),
+            }
+        });
+    }
+}
@@ -0,0 +1,698 @@
+// WASMA - gRPC control service
+// `wasma daemon --grpc [addr]` serves the control protocol (see control.rs) as
+// wasma.control.Control/Execute over HTTP/2 with prior knowledge (h2c), next to
+// the standard services every gRPC host is expected to have:
+//   grpc.health.v1.Health                       Check / Watch (kubernetes probes)
+//   grpc.reflection.v1{,alpha}.ServerReflection  ServerReflectionInfo (grpcurl)
+// Reflection lists every service and returns file descriptors for the control
+// and health protos; the reflection protos themselves are not described.
+//
+// h2c is plaintext: without a token the service only binds loopback addresses.
+// With one, every call but the health checks needs `authorization: Bearer <token>`.
+
+use bytes::Bytes;
+use h2::server::SendResponse;
+use h2::{RecvStream, SendStream};
+use http::{HeaderMap, Request, Response};
+use std::collections::HashMap;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+use crate::control;
+use crate::grpc::{encode_message, put_bytes_field, put_varint_field, Fields, GrpcError, MessageDecoder, Value};
+use crate::window_handling::WindowHandler;
+use wbackend::ResourceMode;
+
+pub const CONTROL_SERVICE: &str = "wasma.control.Control";
+pub const HEALTH_SERVICE: &str = "grpc.health.v1.Health";
+pub const REFLECTION_SERVICE: &str = "grpc.reflection.v1.ServerReflection";
+pub const REFLECTION_SERVICE_V1ALPHA: &str = "grpc.reflection.v1alpha.ServerReflection";
+
+pub const EXECUTE_PATH: &str = "/wasma.control.Control/Execute";
+pub const HEALTH_CHECK_PATH: &str = "/grpc.health.v1.Health/Check";
+pub const HEALTH_WATCH_PATH: &str = "/grpc.health.v1.Health/Watch";
+const REFLECTION_PATH: &str = "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo";
+const REFLECTION_PATH_V1ALPHA: &str = "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo";
+
+// gRPC status codes
+const STATUS_OK: u32 = 0;
+const STATUS_INVALID_ARGUMENT: u32 = 3;
+const STATUS_NOT_FOUND: u32 = 5;
+const STATUS_UNIMPLEMENTED: u32 = 12;
+const STATUS_INTERNAL: u32 = 13;
+const STATUS_UNAUTHENTICATED: u32 = 16;
+
+// ============================================================================
+// HEALTH
+// ============================================================================
+
+/// grpc.health.v1.HealthCheckResponse.ServingStatus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthStatus {
+    Unknown = 0,
+    Serving = 1,
+    NotServing = 2,
+    /// Only sent by Watch, for services the server does not know
+    ServiceUnknown = 3,
+}
+
+/// Serving status per service; "" is the server as a whole
+pub struct HealthRegistry {
+    statuses: watch::Sender<HashMap<String, HealthStatus>>,
+}
+
+impl Default for HealthRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthRegistry {
+    /// The server and the control service start out SERVING
+    pub fn new() -> Self {
+        let statuses = [("", HealthStatus::Serving), (CONTROL_SERVICE, HealthStatus::Serving)]
+            .into_iter()
+            .map(|(service, status)| (service.to_string(), status))
+            .collect();
+        Self { statuses: watch::Sender::new(statuses) }
+    }
+
+    /// Watchers are only woken when the status actually changes
+    pub fn set(&self, service: &str, status: HealthStatus) {
+        self.statuses.send_if_modified(|statuses| statuses.insert(service.to_string(), status) != Some(status));
+    }
+
+    pub fn status(&self, service: &str) -> Option<HealthStatus> {
+        self.statuses.borrow().get(service).copied()
+    }
+
+    /// Every service NOT_SERVING, e.g. while shutting down
+    pub fn shutdown(&self) {
+        self.statuses.send_modify(|statuses| statuses.values_mut().for_each(|s| *s = HealthStatus::NotServing));
+    }
+}
+
+// ============================================================================
+// SCHEMA
+// ============================================================================
+
+#[derive(Clone, Copy)]
+enum FieldType {
+    Bool,
+    String,
+    /// Fully qualified enum name
+    Enum(&'static str),
+}
+
+struct MessageDef {
+    name: &'static str,
+    fields: &'static [(&'static str, u64, FieldType)],
+    enums: &'static [(&'static str, &'static [(&'static str, u64)])],
+}
+
+struct MethodDef {
+    name: &'static str,
+    input: &'static str,
+    output: &'static str,
+    server_streaming: bool,
+}
+
+/// Enough of a .proto for reflection clients to call its service
+struct FileDef {
+    name: &'static str,
+    package: &'static str,
+    messages: &'static [MessageDef],
+    service: &'static str,
+    methods: &'static [MethodDef],
+}
+
+const CONTROL_FILE: FileDef = FileDef {
+    name: "wasma_control.proto",
+    package: "wasma.control",
+    messages: &[
+        // One control protocol line, e.g. "LIST DETAILED" or "CLOSE 3"
+        MessageDef { name: "ControlRequest", fields: &[("request", 1, FieldType::String)], enums: &[] },
+        // `payload` is what follows OK / ERR on the Unix socket
+        MessageDef {
+            name: "ControlReply",
+            fields: &[("ok", 1, FieldType::Bool), ("payload", 2, FieldType::String)],
+            enums: &[],
+        },
+    ],
+    service: "Control",
+    methods: &[MethodDef { name: "Execute", input: "ControlRequest", output: "ControlReply", server_streaming: false }],
+};
+
+const HEALTH_FILE: FileDef = FileDef {
+    name: "grpc/health/v1/health.proto",
+    package: "grpc.health.v1",
+    messages: &[
+        MessageDef { name: "HealthCheckRequest", fields: &[("service", 1, FieldType::String)], enums: &[] },
+        MessageDef {
+            name: "HealthCheckResponse",
+            fields: &[("status", 1, FieldType::Enum(".grpc.health.v1.HealthCheckResponse.ServingStatus"))],
+            enums: &[("ServingStatus", &[("UNKNOWN", 0), ("SERVING", 1), ("NOT_SERVING", 2), ("SERVICE_UNKNOWN", 3)])],
+        },
+    ],
+    service: "Health",
+    methods: &[
+        MethodDef { name: "Check", input: "HealthCheckRequest", output: "HealthCheckResponse", server_streaming: false },
+        MethodDef { name: "Watch", input: "HealthCheckRequest", output: "HealthCheckResponse", server_streaming: true },
+    ],
+};
+
+const FILES: &[FileDef] = &[CONTROL_FILE, HEALTH_FILE];
+
+impl FileDef {
+    /// Serialized google.protobuf.FileDescriptorProto
+    fn descriptor(&self) -> Vec<u8> {
+        let mut file = Vec::new();
+        put_bytes_field(&mut file, 1, self.name.as_bytes());
+        put_bytes_field(&mut file, 2, self.package.as_bytes());
+        for message in self.messages {
+            let mut descriptor = Vec::new();
+            put_bytes_field(&mut descriptor, 1, message.name.as_bytes());
+            for &(name, number, kind) in message.fields {
+                let mut field = Vec::new();
+                put_bytes_field(&mut field, 1, name.as_bytes());
+                put_varint_field(&mut field, 3, number);
+                put_varint_field(&mut field, 4, 1); // LABEL_OPTIONAL
+                match kind {
+                    FieldType::Bool => put_varint_field(&mut field, 5, 8),
+                    FieldType::String => put_varint_field(&mut field, 5, 9),
+                    FieldType::Enum(type_name) => {
+                        put_varint_field(&mut field, 5, 14);
+                        put_bytes_field(&mut field, 6, type_name.as_bytes());
+                    }
+                }
+                put_bytes_field(&mut field, 10, name.as_bytes()); // json_name
+                put_bytes_field(&mut descriptor, 2, &field);
+            }
+            for &(name, values) in message.enums {
+                let mut enum_type = Vec::new();
+                put_bytes_field(&mut enum_type, 1, name.as_bytes());
+                for &(value_name, number) in values {
+                    let mut value = Vec::new();
+                    put_bytes_field(&mut value, 1, value_name.as_bytes());
+                    put_varint_field(&mut value, 2, number);
+                    put_bytes_field(&mut enum_type, 2, &value);
+                }
+                put_bytes_field(&mut descriptor, 4, &enum_type);
+            }
+            put_bytes_field(&mut file, 4, &descriptor);
+        }
+
+        let mut service = Vec::new();
+        put_bytes_field(&mut service, 1, self.service.as_bytes());
+        for method in self.methods {
+            let mut descriptor = Vec::new();
+            put_bytes_field(&mut descriptor, 1, method.name.as_bytes());
+            put_bytes_field(&mut descriptor, 2, format!(".{}.{}", self.package, method.input).as_bytes());
+            put_bytes_field(&mut descriptor, 3, format!(".{}.{}", self.package, method.output).as_bytes());
+            put_varint_field(&mut descriptor, 6, method.server_streaming as u64);
+            put_bytes_field(&mut service, 2, &descriptor);
+        }
+        put_bytes_field(&mut file, 6, &service);
+        put_bytes_field(&mut file, 12, b"proto3");
+        file
+    }
+
+    fn service_name(&self) -> String {
+        format!("{}.{}", self.package, self.service)
+    }
+
+    /// Fully qualified names reflection clients may ask for
+    fn symbols(&self) -> Vec<String> {
+        let service = self.service_name();
+        let mut symbols: Vec<String> = self.methods.iter().map(|m| format!("{}.{}", service, m.name)).collect();
+        for message in self.messages {
+            symbols.push(format!("{}.{}", self.package, message.name));
+            symbols.extend(message.enums.iter().map(|(e, _)| format!("{}.{}.{}", self.package, message.name, e)));
+        }
+        symbols.push(service);
+        symbols
+    }
+
+    fn defines(&self, symbol: &str) -> bool {
+        self.symbols().iter().any(|s| s == symbol)
+    }
+}
+
+/// Services the server answers, as listed by reflection
+pub fn services() -> Vec<String> {
+    let mut services: Vec<String> = FILES.iter().map(FileDef::service_name).collect();
+    services.push(REFLECTION_SERVICE.to_string());
+    services.push(REFLECTION_SERVICE_V1ALPHA.to_string());
+    services
+}
+
+fn string_field(message: &[u8], number: u64) -> Result<String, GrpcError> {
+    let mut value = String::new();
+    for field in Fields::new(message) {
+        if let (n, Value::Bytes(bytes)) = field? {
+            if n == number {
+                value = String::from_utf8(bytes.to_vec()).map_err(|e| GrpcError::Decode(e.to_string()))?;
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// ServerReflectionResponse for one ServerReflectionRequest
+fn reflect(request: &[u8]) -> Result<Vec<u8>, GrpcError> {
+    let mut host = String::new();
+    let mut answer: Option<(u64, Vec<u8>)> = None;
+    let error = |code: u32, message: String| {
+        let mut error = Vec::new();
+        put_varint_field(&mut error, 1, code as u64);
+        put_bytes_field(&mut error, 2, message.as_bytes());
+        (7, error)
+    };
+    let files = |files: Vec<&FileDef>| {
+        let mut response = Vec::new();
+        for file in files {
+            put_bytes_field(&mut response, 1, &file.descriptor());
+        }
+        (4, response)
+    };
+
+    for field in Fields::new(request) {
+        let (number, Value::Bytes(bytes)) = field? else { continue };
+        let value = String::from_utf8_lossy(bytes).into_owned();
+        answer = Some(match number {
+            1 => {
+                host = value;
+                continue;
+            }
+            3 => match FILES.iter().find(|f| f.name == value) {
+                Some(file) => files(vec![file]),
+                None => error(STATUS_NOT_FOUND, format!("file {} not found", value)),
+            },
+            4 => match FILES.iter().find(|f| f.defines(&value)) {
+                Some(file) => files(vec![file]),
+                None => error(STATUS_NOT_FOUND, format!("symbol {} not found", value)),
+            },
+            // proto3: no extensions anywhere
+            5 => error(STATUS_NOT_FOUND, "extensions are not supported".to_string()),
+            6 => {
+                let mut response = Vec::new();
+                put_bytes_field(&mut response, 1, bytes);
+                (5, response)
+            }
+            7 => {
+                let mut response = Vec::new();
+                for service in services() {
+                    let mut entry = Vec::new();
+                    put_bytes_field(&mut entry, 1, service.as_bytes());
+                    put_bytes_field(&mut response, 1, &entry);
+                }
+                (6, response)
+            }
+            _ => continue,
+        });
+    }
+
+    let (number, payload) = answer.unwrap_or_else(|| error(STATUS_INVALID_ARGUMENT, "empty reflection request".to_string()));
+    let mut response = Vec::new();
+    if !host.is_empty() {
+        put_bytes_field(&mut response, 1, host.as_bytes());
+    }
+    put_bytes_field(&mut response, 2, request);
+    put_bytes_field(&mut response, number, &payload);
+    Ok(response)
+}
+
+// ============================================================================
+// SERVER
+// ============================================================================
+
+/// One incoming call: request messages in, reply messages and status out
+struct Call {
+    body: RecvStream,
+    decoder: MessageDecoder,
+    respond: SendResponse<Bytes>,
+    send: Option<SendStream<Bytes>>,
+}
+
+impl Call {
+    async fn next_message(&mut self) -> Result<Option<Bytes>, GrpcError> {
+        loop {
+            if let Some(message) = self.decoder.next_message()? {
+                return Ok(Some(message));
+            }
+            match self.body.data().await {
+                Some(chunk) => {
+                    let chunk = chunk?;
+                    let _ = self.body.flow_control().release_capacity(chunk.len());
+                    self.decoder.push(&chunk);
+                }
+                None if self.decoder.is_empty() => return Ok(None),
+                None => return Err(GrpcError::Protocol("request ended inside a message".to_string())),
+            }
+        }
+    }
+
+    /// Exactly one request message (unary and server-streaming calls)
+    async fn single_message(&mut self) -> Result<Bytes, GrpcError> {
+        self.next_message().await?
+            .ok_or_else(|| GrpcError::Protocol("call without a request message".to_string()))
+    }
+
+    fn send_message(&mut self, payload: &[u8]) -> Result<(), GrpcError> {
+        let send = match &mut self.send {
+            Some(send) => send,
+            None => {
+                let response = Response::builder().header("content-type", "application/grpc").body(()).unwrap();
+                self.send.insert(self.respond.send_response(response, false)?)
+            }
+        };
+        send.send_data(Bytes::from(encode_message(payload)), false)?;
+        Ok(())
+    }
+
+    /// Client cancelled (RST_STREAM) or went away
+    async fn cancelled(&mut self) {
+        match &mut self.send {
+            Some(send) => {
+                let _ = std::future::poll_fn(|cx| send.poll_reset(cx)).await;
+            }
+            None => {
+                let _ = std::future::poll_fn(|cx| self.respond.poll_reset(cx)).await;
+            }
+        }
+    }
+
+    /// End the call; before any message this is a trailers-only response
+    fn finish(mut self, code: u32, message: &str) {
+        let mut status = HeaderMap::new();
+        status.insert("grpc-status", code.into());
+        if let Ok(message) = message.parse() {
+            status.insert("grpc-message", message);
+        }
+        match self.send.take() {
+            Some(mut send) => {
+                let _ = send.send_trailers(status);
+            }
+            None => {
+                let mut response = Response::builder().header("content-type", "application/grpc").body(()).unwrap();
+                response.headers_mut().extend(status);
+                let _ = self.respond.send_response(response, true);
+            }
+        }
+    }
+}
+
+/// Serves Execute, Health and reflection; cheap to clone
+#[derive(Clone)]
+pub struct GrpcControlServer {
+    handler: Arc<WindowHandler>,
+    resource_mode: ResourceMode,
+    health: Arc<HealthRegistry>,
+    token: Option<Arc<str>>,
+}
+
+impl GrpcControlServer {
+    pub fn new(handler: Arc<WindowHandler>, resource_mode: ResourceMode) -> Self {
+        Self { handler, resource_mode, health: Arc::new(HealthRegistry::new()), token: None }
+    }
+
+    /// Require `authorization: Bearer <token>`; needed to bind non-loopback addresses
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(Arc::from(token));
+        self
+    }
+
+    /// Statuses reported by grpc.health.v1.Health
+    pub fn health(&self) -> Arc<HealthRegistry> {
+        self.health.clone()
+    }
+
+    /// Bind `addr` and serve on a background thread; returns the bound address
+    pub fn start(&self, addr: &str) -> std::io::Result<SocketAddr> {
+        // Anyone on the network could drive the window manager otherwise
+        if self.token.is_none() && addr.to_socket_addrs()?.any(|a| !a.ip().is_loopback()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("refusing unauthenticated plaintext gRPC on non-loopback {} (set a token)", addr),
+            ));
+        }
+        let listener = std::net::TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let local = listener.local_addr()?;
+        let server = self.clone();
+
+        println!("🛰️  gRPC control service listening on {}", local);
+        std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime,
+                Err(e) => return eprintln!("❌ gRPC control service could not start: {}", e),
+            };
+            runtime.block_on(async move {
+                match TcpListener::from_std(listener) {
+                    Ok(listener) => server.serve(listener).await,
+                    Err(e) => eprintln!("❌ gRPC control service could not start: {}", e),
+                }
+            });
+        });
+        Ok(local)
+    }
+
+    /// Accept connections until the listener fails
+    pub async fn serve(self, listener: TcpListener) {
+        loop {
+            match listener.accept().await {
+                Ok((socket, _)) => {
+                    let server = self.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = server.serve_connection(socket).await {
+                            eprintln!("⚠️  gRPC control connection error: {}", e);
+                        }
+                    });
+                }
+                Err(e) => {
+                    eprintln!("❌ gRPC control service stopped: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn serve_connection(self, socket: TcpStream) -> Result<(), h2::Error> {
+        let mut connection = h2::server::handshake(socket).await?;
+        while let Some(accepted) = connection.accept().await {
+            let (request, respond) = accepted?;
+            tokio::spawn(self.clone().serve_call(request, respond));
+        }
+        Ok(())
+    }
+
+    async fn serve_call(self, request: Request<RecvStream>, respond: SendResponse<Bytes>) {
+        let path = request.uri().path().to_string();
+        // Probes stay open; everything else needs the token when one is set
+        let probe = path == HEALTH_CHECK_PATH || path == HEALTH_WATCH_PATH;
+        let authorized = probe || self.token.as_deref().map_or(true, |token| bearer_matches(request.headers(), token));
+        let mut call = Call { body: request.into_body(), decoder: MessageDecoder::default(), respond, send: None };
+
+        let result = match path.as_str() {
+            _ if !authorized => Err((STATUS_UNAUTHENTICATED, "missing or wrong bearer token".to_string())),
+            EXECUTE_PATH => self.execute(&mut call).await,
+            HEALTH_CHECK_PATH => self.check(&mut call).await,
+            HEALTH_WATCH_PATH => self.watch(&mut call).await,
+            REFLECTION_PATH | REFLECTION_PATH_V1ALPHA => reflection(&mut call).await,
+            _ => Err((STATUS_UNIMPLEMENTED, format!("unknown method {}", path))),
+        };
+        match result {
+            Ok(()) => call.finish(STATUS_OK, ""),
+            Err((code, message)) => call.finish(code, &message),
+        }
+    }
+
+    /// wasma.control.Control/Execute: one control protocol line
+    async fn execute(&self, call: &mut Call) -> Result<(), (u32, String)> {
+        let message = call.single_message().await.map_err(invalid)?;
+        let request = string_field(&message, 1).map_err(invalid)?;
+
+        let handler = self.handler.clone();
+        let resource_mode = self.resource_mode;
+        let reply = tokio::task::spawn_blocking(move || control::handle_request(&handler, resource_mode, &request))
+            .await
+            .map_err(|e| (STATUS_INTERNAL, e.to_string()))?;
+
+        let (ok, payload) = match reply.split_once(' ') {
+            Some((status, payload)) => (status == "OK", payload),
+            None => (reply == "OK", ""),
+        };
+        let mut response = Vec::new();
+        put_varint_field(&mut response, 1, ok as u64);
+        if !payload.is_empty() {
+            put_bytes_field(&mut response, 2, payload.as_bytes());
+        }
+        call.send_message(&response).map_err(internal)
+    }
+
+    async fn check(&self, call: &mut Call) -> Result<(), (u32, String)> {
+        let message = call.single_message().await.map_err(invalid)?;
+        let service = string_field(&message, 1).map_err(invalid)?;
+        let status = self.health.status(&service)
+            .ok_or_else(|| (STATUS_NOT_FOUND, format!("unknown service {}", service)))?;
+        call.send_message(&health_response(status)).map_err(internal)
+    }
+
+    /// Current status, then every change, until the client cancels
+    async fn watch(&self, call: &mut Call) -> Result<(), (u32, String)> {
+        let message = call.single_message().await.map_err(invalid)?;
+        let service = string_field(&message, 1).map_err(invalid)?;
+        let mut statuses = self.health.statuses.subscribe();
+
+        let mut last = None;
+        loop {
+            let status = statuses.borrow_and_update().get(&service).copied().unwrap_or(HealthStatus::ServiceUnknown);
+            if last != Some(status) {
+                call.send_message(&health_response(status)).map_err(internal)?;
+                last = Some(status);
+            }
+            tokio::select! {
+                changed = statuses.changed() => {
+                    if changed.is_err() {
+                        return Ok(());
+                    }
+                }
+                _ = call.cancelled() => return Ok(()),
+            }
+        }
+    }
+}
+
+/// ServerReflectionInfo: one response per request until the client half-closes
+async fn reflection(call: &mut Call) -> Result<(), (u32, String)> {
+    while let Some(request) = call.next_message().await.map_err(invalid)? {
+        let response = reflect(&request).map_err(invalid)?;
+        call.send_message(&response).map_err(internal)?;
+    }
+    Ok(())
+}
+
+/// `authorization: Bearer <token>`, compared in constant time
+fn bearer_matches(headers: &HeaderMap, token: &str) -> bool {
+    let Some(presented) = headers.get("authorization").and_then(|v| v.as_bytes().strip_prefix(b"Bearer ")) else {
+        return false;
+    };
+    presented.len() == token.len()
+        && presented.iter().zip(token.as_bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn health_response(status: HealthStatus) -> Vec<u8> {
+    let mut response = Vec::new();
+    put_varint_field(&mut response, 1, status as u64);
+    response
+}
+
+fn invalid(e: GrpcError) -> (u32, String) {
+    (STATUS_INVALID_ARGUMENT, e.to_string())
+}
+
+fn internal(e: GrpcError) -> (u32, String) {
+    (STATUS_INTERNAL, e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::GrpcClient;
+
+    fn request(field: u64, value: &str) -> Vec<u8> {
+        let mut message = Vec::new();
+        put_bytes_field(&mut message, field, value.as_bytes());
+        message
+    }
+
+    #[test]
+    fn test_grpc_control_service() {
+        let handler = Arc::new(WindowHandler::new(ResourceMode::Auto));
+        let server = GrpcControlServer::new(handler.clone(), ResourceMode::Auto);
+        let addr = server.start("127.0.0.1:0").unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let client = GrpcClient::handshake(TcpStream::connect(addr).await.unwrap(), addr.to_string()).await.unwrap();
+
+            // Execute forwards control protocol lines
+            let reply = client.unary(EXECUTE_PATH, &request(1, "CREATE 640 480 test.grpc gRPC Window")).await.unwrap();
+            let window = &handler.list_windows()[0];
+            assert_eq!(window.title, "gRPC Window");
+            assert_eq!(string_field(&reply, 2).unwrap(), window.id.to_string());
+            let reply = client.unary(EXECUTE_PATH, &request(1, "BOGUS")).await.unwrap();
+            assert!(!Fields::new(&reply).any(|f| matches!(f, Ok((1, Value::Varint(1))))));
+
+            // Health: known services answer, unknown ones are NOT_FOUND
+            let serving = health_response(HealthStatus::Serving);
+            assert_eq!(client.unary(HEALTH_CHECK_PATH, &request(1, "")).await.unwrap(), serving);
+            assert_eq!(client.unary(HEALTH_CHECK_PATH, &request(1, CONTROL_SERVICE)).await.unwrap(), serving);
+            match client.unary(HEALTH_CHECK_PATH, &request(1, "nope")).await {
+                Err(GrpcError::Status { code, .. }) => assert_eq!(code, STATUS_NOT_FOUND),
+                other => panic!("expected NOT_FOUND, got {:?}", other),
+            }
+
+            // Watch sends the current status, then changes
+            let mut watch = client.call(HEALTH_WATCH_PATH, &request(1, CONTROL_SERVICE)).await.unwrap();
+            assert_eq!(watch.next_message().await.unwrap().unwrap(), serving);
+            server.health().set(CONTROL_SERVICE, HealthStatus::NotServing);
+            assert_eq!(watch.next_message().await.unwrap().unwrap(), health_response(HealthStatus::NotServing));
+
+            match client.unary("/wasma.control.Control/Missing", &[]).await {
+                Err(GrpcError::Status { code, .. }) => assert_eq!(code, STATUS_UNIMPLEMENTED),
+                other => panic!("expected UNIMPLEMENTED, got {:?}", other),
+            }
+        });
+
+        // Reflection: list services, then resolve symbols to descriptors
+        let listing = reflect(&request(7, "")).unwrap();
+        let mut names = Vec::new();
+        for field in Fields::new(&listing) {
+            if let Ok((6, Value::Bytes(list))) = field {
+                for entry in Fields::new(list) {
+                    if let Ok((1, Value::Bytes(service))) = entry {
+                        names.push(string_field(service, 1).unwrap());
+                    }
+                }
+            }
+        }
+        assert_eq!(names, services());
+        assert!(names.contains(&HEALTH_SERVICE.to_string()));
+
+        for symbol in [HEALTH_SERVICE, "grpc.health.v1.Health.Watch", "grpc.health.v1.HealthCheckResponse.ServingStatus", "wasma.control.ControlReply"] {
+            let response = reflect(&request(4, symbol)).unwrap();
+            assert!(Fields::new(&response).any(|f| matches!(f, Ok((4, _)))), "{} not resolved", symbol);
+        }
+        let missing = reflect(&request(4, "wasma.control.Nope")).unwrap();
+        assert!(Fields::new(&missing).any(|f| matches!(f, Ok((7, _)))));
+        assert!(!HEALTH_FILE.defines("grpc.health.v1.HealthCheck"));
+        assert!(HEALTH_FILE.descriptor().windows(b"SERVICE_UNKNOWN".len()).any(|w| w == b"SERVICE_UNKNOWN"));
+    }
+
+    #[test]
+    fn test_grpc_token() {
+        let handler = Arc::new(WindowHandler::new(ResourceMode::Auto));
+        let open = GrpcControlServer::new(handler.clone(), ResourceMode::Auto);
+        let err = open.start("0.0.0.0:0").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+
+        let server = open.with_token("s3cret");
+        let addr = server.start("127.0.0.1:0").unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let client = GrpcClient::handshake(TcpStream::connect(addr).await.unwrap(), addr.to_string()).await.unwrap();
+            match client.unary(EXECUTE_PATH, &request(1, "LIST")).await {
+                Err(GrpcError::Status { code, .. }) => assert_eq!(code, STATUS_UNAUTHENTICATED),
+                other => panic!("expected UNAUTHENTICATED, got {:?}", other),
+            }
+            // Health probes need no token
+            assert!(client.unary(HEALTH_CHECK_PATH, &request(1, "")).await.is_ok());
+
+            let client = client.with_token("s3cret");
+            assert!(client.unary(EXECUTE_PATH, &request(1, "LIST")).await.is_ok());
+        });
+    }
+}
@@ -0,0 +1,274 @@
+// WASMA - Hook scripts
+// Shell-level extensibility for admins, run-parts style: on every event the
+// executables in /etc/wasma/hooks/<event>.d/ run one after another in name
+// order, with the event in WASMA_* environment variables. Names must be made
+// of letters, digits, '_' and '-' (so editor backups and package manager
+// leftovers are skipped). A hook that outlives HOOK_TIMEOUT is killed; its
+// exit status never affects WASMA.
+//   window-created    WASMA_WINDOW_ID WASMA_WINDOW_TITLE WASMA_APP_ID
+//   window-closed     WASMA_WINDOW_ID
+//   focus-changed     WASMA_PREVIOUS_WINDOW_ID WASMA_WINDOW_ID (unset: desktop)
+//   state-changed     WASMA_WINDOW_ID WASMA_STATE_FROM WASMA_STATE_TO
+//   lease-expired     WASMA_WINDOW_ID WASMA_ASSIGNMENT_ID
+//   session-detached  WASMA_DETACH_MODE
+//   session-attached
+//   workspace-changed WASMA_PREVIOUS_WORKSPACE WASMA_WORKSPACE
+//   endpoint-up       WASMA_ENDPOINT WASMA_PROTOCOL
+//   endpoint-down     WASMA_ENDPOINT WASMA_PROTOCOL WASMA_ERROR
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+use crate::protocol_control::{EndpointState, EndpointTransition};
+use crate::window_handling::WindowEvent;
+
+pub const DEFAULT_HOOKS_DIR: &str = "/etc/wasma/hooks";
+/// Longest a single hook may run
+pub const HOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Event name plus the environment its hooks get
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookEvent {
+    /// Hooks run from `<name>.d`
+    pub name: &'static str,
+    pub env: Vec<(&'static str, String)>,
+}
+
+impl HookEvent {
+    pub fn from_window_event(event: &WindowEvent) -> Self {
+        let (name, env) = match event {
+            WindowEvent::WindowCreated { window_id, title, app_id } => ("window-created", vec![
+                ("WASMA_WINDOW_ID", window_id.to_string()),
+                ("WASMA_WINDOW_TITLE", title.clone()),
+                ("WASMA_APP_ID", app_id.clone()),
+            ]),
+            WindowEvent::WindowClosed { window_id } => ("window-closed", vec![("WASMA_WINDOW_ID", window_id.to_string())]),
+            WindowEvent::FocusChanged { previous, current } => {
+                let mut env = Vec::new();
+                if let Some(previous) = previous {
+                    env.push(("WASMA_PREVIOUS_WINDOW_ID", previous.to_string()));
+                }
+                if let Some(current) = current {
+                    env.push(("WASMA_WINDOW_ID", current.to_string()));
+                }
+                ("focus-changed", env)
+            }
+            WindowEvent::StateChanged { window_id, from, to } => ("state-changed", vec![
+                ("WASMA_WINDOW_ID", window_id.to_string()),
+                ("WASMA_STATE_FROM", format!("{:?}", from).to_lowercase()),
+                ("WASMA_STATE_TO", format!("{:?}", to).to_lowercase()),
+            ]),
+            WindowEvent::ResourceLeaseExpired { window_id, assignment_id } => ("lease-expired", vec![
+                ("WASMA_WINDOW_ID", window_id.to_string()),
+                ("WASMA_ASSIGNMENT_ID", assignment_id.to_string()),
+            ]),
+            WindowEvent::SessionDetached { mode } => ("session-detached", vec![("WASMA_DETACH_MODE", format!("{:?}", mode).to_lowercase())]),
+            WindowEvent::SessionAttached => ("session-attached", Vec::new()),
+            WindowEvent::WorkspaceChanged { previous, current } => ("workspace-changed", vec![
+                ("WASMA_PREVIOUS_WORKSPACE", previous.to_string()),
+                ("WASMA_WORKSPACE", current.to_string()),
+            ]),
+        };
+        Self { name, env }
+    }
+
+    /// endpoint-up / endpoint-down; None for other state changes
+    pub fn from_transition(transition: &EndpointTransition) -> Option<Self> {
+        let stats = &transition.stats;
+        let mut env = vec![
+            ("WASMA_ENDPOINT", stats.endpoint.clone()),
+            ("WASMA_PROTOCOL", format!("{:?}", stats.protocol).to_lowercase()),
+        ];
+        let name = match (transition.previous, stats.state) {
+            (_, EndpointState::Connected) => "endpoint-up",
+            (EndpointState::Connected, EndpointState::Disconnected) => {
+                env.push(("WASMA_ERROR", stats.last_error.clone().unwrap_or_default()));
+                "endpoint-down"
+            }
+            _ => return None,
+        };
+        Some(Self { name, env })
+    }
+}
+
+/// How one hook ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookOutcome {
+    Exited(i32),
+    /// Killed by a signal
+    Signaled,
+    TimedOut,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookResult {
+    pub script: PathBuf,
+    pub outcome: HookOutcome,
+}
+
+/// run-parts names: letters, digits, '_' and '-'
+fn valid_hook_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[derive(Debug, Clone)]
+pub struct HookRunner {
+    dir: PathBuf,
+    timeout: Duration,
+}
+
+impl HookRunner {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), timeout: HOOK_TIMEOUT }
+    }
+
+    /// Hooks under /etc/wasma/hooks
+    pub fn system() -> Self {
+        Self::new(DEFAULT_HOOKS_DIR)
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Executables that would run for `event`, in order
+    pub fn scripts(&self, event: &str) -> Vec<PathBuf> {
+        let Ok(entries) = std::fs::read_dir(self.dir.join(format!("{}.d", event))) else {
+            return Vec::new();
+        };
+        let mut scripts: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|entry| entry.file_name().to_str().is_some_and(valid_hook_name))
+            .map(|entry| entry.path())
+            // Follows symlinks, like run-parts
+            .filter(|path| std::fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0))
+            .collect();
+        scripts.sort();
+        scripts
+    }
+
+    /// Run the event's hooks one after another
+    pub fn run(&self, event: &HookEvent) -> Vec<HookResult> {
+        self.scripts(event.name).into_iter().map(|script| {
+            let outcome = self.run_one(&script, event);
+            if outcome != HookOutcome::Exited(0) {
+                eprintln!("⚠️  Hook {} ({}): {:?}", script.display(), event.name, outcome);
+            }
+            HookResult { script, outcome }
+        }).collect()
+    }
+
+    fn run_one(&self, script: &Path, event: &HookEvent) -> HookOutcome {
+        let mut command = Command::new(script);
+        command.stdin(Stdio::null()).env("WASMA_EVENT", event.name);
+        for (key, value) in &event.env {
+            command.env(key, value);
+        }
+        // ETXTBSY: the script is still open for writing somewhere (being installed)
+        let mut attempts = 0;
+        let mut child = loop {
+            match command.spawn() {
+                Ok(child) => break child,
+                Err(e) if e.raw_os_error() == Some(libc::ETXTBSY) && attempts < 5 => {
+                    attempts += 1;
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                Err(e) => return HookOutcome::Failed(e.to_string()),
+            }
+        };
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => return status.code().map_or(HookOutcome::Signaled, HookOutcome::Exited),
+                Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+                Ok(None) => {
+                    child.kill().ok();
+                    child.wait().ok();
+                    return HookOutcome::TimedOut;
+                }
+                Err(e) => return HookOutcome::Failed(e.to_string()),
+            }
+        }
+    }
+
+    /// Run hooks for window events until the handler drops `events`
+    pub fn watch_windows(&self, events: Receiver<WindowEvent>) {
+        let runner = self.clone();
+        std::thread::spawn(move || {
+            for event in events {
+                runner.run(&HookEvent::from_window_event(&event));
+            }
+        });
+    }
+
+    /// Run endpoint-up / endpoint-down hooks from a ProtocolControl subscription
+    pub fn watch_endpoints(&self, transitions: Receiver<EndpointTransition>) {
+        let runner = self.clone();
+        std::thread::spawn(move || {
+            for transition in transitions {
+                if let Some(event) = HookEvent::from_transition(&transition) {
+                    runner.run(&event);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn script(dir: &Path, name: &str, body: &str, mode: u32) {
+        let path = dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[test]
+    fn test_run_parts_hooks() {
+        let root = tempfile::tempdir().unwrap();
+        let hooks = root.path().join("window-created.d");
+        fs::create_dir(&hooks).unwrap();
+        let log = root.path().join("log");
+
+        script(&hooks, "20-fail", "exit 3", 0o755);
+        script(&hooks, "10-log", &format!("echo \"$WASMA_EVENT $WASMA_WINDOW_ID $WASMA_APP_ID\" >> {}", log.display()), 0o755);
+        script(&hooks, "30-slow", "sleep 5", 0o755);
+        // Skipped: not executable, dotfile, editor backup
+        script(&hooks, "15-disabled", "exit 1", 0o644);
+        script(&hooks, ".hidden", "exit 1", 0o755);
+        script(&hooks, "10-log~", "exit 1", 0o755);
+
+        let runner = HookRunner::new(root.path()).with_timeout(Duration::from_millis(300));
+        let event = HookEvent::from_window_event(&WindowEvent::WindowCreated {
+            window_id: 7,
+            title: "Editor".to_string(),
+            app_id: "org.example.Editor".to_string(),
+        });
+        let results = runner.run(&event);
+        let outcomes: Vec<_> = results.iter()
+            .map(|r| (r.script.file_name().unwrap().to_str().unwrap().to_string(), r.outcome.clone()))
+            .collect();
+        assert_eq!(outcomes, [
+            ("10-log".to_string(), HookOutcome::Exited(0)),
+            ("20-fail".to_string(), HookOutcome::Exited(3)),
+            ("30-slow".to_string(), HookOutcome::TimedOut),
+        ]);
+        assert_eq!(fs::read_to_string(&log).unwrap(), "window-created 7 org.example.Editor\n");
+
+        // No directory for the event: nothing runs
+        let event = HookEvent::from_window_event(&WindowEvent::WindowClosed { window_id: 7 });
+        assert!(runner.run(&event).is_empty());
+    }
+}
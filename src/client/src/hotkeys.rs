@@ -0,0 +1,261 @@
+// WASMA - Window manager hotkeys
+// WASMA's own key bindings: cycle focus, close the focused window, toggle
+// singularity and switch the tiling layout. Each action is bound in the
+// [hotkeys] section of settings.conf; an empty value unbinds it:
+//
+//   [hotkeys]
+//   cycle_focus = "Alt+Tab"
+//   close_window = "Super+Q"
+//   next_layout = ""
+//
+// The iced manager turns a bound key press into the action's Message, the
+// streaming client acts on its viewports (WindowClient::route_key), and every
+// bound combo is reserved so applications cannot register it globally.
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use iced::keyboard::{self, key::Named};
+use wsdg_xdg::{WsdgEnv, WsdgSettings, WsdgSettingsManager};
+
+use crate::global_hotkeys::{HotkeyRegistry, KeyCombo};
+use crate::window_handling::Message;
+use crate::window_multitary::DEFAULT_SINGULARITY_SHORTCUT;
+
+static GLOBAL: OnceLock<RwLock<WmHotkeys>> = OnceLock::new();
+
+/// Something the window manager does on a key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum WmAction {
+    CycleFocus,
+    CycleFocusBack,
+    CloseWindow,
+    ToggleSingularity,
+    NextLayout,
+}
+
+impl WmAction {
+    pub const ALL: [WmAction; 5] = [
+        WmAction::CycleFocus,
+        WmAction::CycleFocusBack,
+        WmAction::CloseWindow,
+        WmAction::ToggleSingularity,
+        WmAction::NextLayout,
+    ];
+
+    /// Key in the [hotkeys] section of settings.conf
+    pub fn name(&self) -> &'static str {
+        match self {
+            WmAction::CycleFocus => "cycle_focus",
+            WmAction::CycleFocusBack => "cycle_focus_back",
+            WmAction::CloseWindow => "close_window",
+            WmAction::ToggleSingularity => "toggle_singularity",
+            WmAction::NextLayout => "next_layout",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            WmAction::CycleFocus => "Focus next window",
+            WmAction::CycleFocusBack => "Focus previous window",
+            WmAction::CloseWindow => "Close focused window",
+            WmAction::ToggleSingularity => "Toggle singularity",
+            WmAction::NextLayout => "Next layout",
+        }
+    }
+
+    pub fn default_combo(&self) -> &'static str {
+        match self {
+            WmAction::CycleFocus => "Alt+Tab",
+            WmAction::CycleFocusBack => "Alt+Shift+Tab",
+            WmAction::CloseWindow => "Alt+F4",
+            WmAction::ToggleSingularity => DEFAULT_SINGULARITY_SHORTCUT,
+            WmAction::NextLayout => "Super+Space",
+        }
+    }
+
+    /// What the iced manager does for it
+    pub fn message(&self) -> Message {
+        match self {
+            WmAction::CycleFocus => Message::CycleFocus(false),
+            WmAction::CycleFocusBack => Message::CycleFocus(true),
+            WmAction::CloseWindow => Message::CloseFocused,
+            WmAction::ToggleSingularity => Message::ToggleSingularity,
+            WmAction::NextLayout => Message::NextLayout,
+        }
+    }
+}
+
+/// Combo → action table of the window manager
+#[derive(Debug, Clone, PartialEq)]
+pub struct WmHotkeys {
+    bindings: HashMap<KeyCombo, WmAction>,
+}
+
+impl Default for WmHotkeys {
+    fn default() -> Self {
+        Self::from_settings(&WsdgSettings::default())
+    }
+}
+
+impl WmHotkeys {
+    /// Bindings from [hotkeys]; missing keys keep their default, invalid ones
+    /// fall back to it with a warning
+    pub fn from_settings(settings: &WsdgSettings) -> Self {
+        let mut bindings = HashMap::new();
+        for action in WmAction::ALL {
+            let configured = settings.custom.get(&format!("hotkeys.{}", action.name()));
+            let combo = match configured.map(|c| c.trim()) {
+                Some("") => continue,
+                Some(raw) => KeyCombo::parse(raw).unwrap_or_else(|e| {
+                    eprintln!("⚠️  hotkeys.{}: {}, using {}", action.name(), e, action.default_combo());
+                    KeyCombo::parse(action.default_combo()).expect("valid default hotkey")
+                }),
+                None => KeyCombo::parse(action.default_combo()).expect("valid default hotkey"),
+            };
+            if let Some(previous) = bindings.insert(combo.clone(), action) {
+                eprintln!("⚠️  {} is bound to both {} and {}, keeping {}", combo, previous.name(), action.name(), action.name());
+            }
+        }
+        Self { bindings }
+    }
+
+    pub fn from_wsdg() -> Self {
+        let mut manager = WsdgSettingsManager::new(WsdgEnv::new());
+        match manager.load() {
+            Ok(()) => Self::from_settings(manager.settings()),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Bindings the GUI key handler looks up
+    pub fn global() -> WmHotkeys {
+        GLOBAL.get_or_init(|| RwLock::new(WmHotkeys::default())).read().unwrap().clone()
+    }
+
+    pub fn set_global(hotkeys: WmHotkeys) {
+        *GLOBAL.get_or_init(|| RwLock::new(WmHotkeys::default())).write().unwrap() = hotkeys;
+    }
+
+    pub fn action(&self, combo: &KeyCombo) -> Option<WmAction> {
+        self.bindings.get(combo).copied()
+    }
+
+    pub fn combo(&self, action: WmAction) -> Option<&KeyCombo> {
+        self.bindings.iter().find(|(_, a)| **a == action).map(|(combo, _)| combo)
+    }
+
+    /// Bound actions in WmAction order
+    pub fn bindings(&self) -> Vec<(WmAction, KeyCombo)> {
+        let mut bindings: Vec<_> = self.bindings.iter().map(|(combo, action)| (*action, combo.clone())).collect();
+        bindings.sort_by_key(|(action, _)| *action);
+        bindings
+    }
+
+    /// Keep applications from registering these combos
+    pub fn reserve_in(&self, registry: &HotkeyRegistry) {
+        for (action, combo) in self.bindings() {
+            registry.reserve(combo, action.description());
+        }
+    }
+}
+
+/// An iced key press as a KeyCombo; None for keys a combo cannot name
+pub fn combo_from_iced(key: &keyboard::Key, modifiers: keyboard::Modifiers) -> Option<KeyCombo> {
+    let name = match key {
+        keyboard::Key::Character(c) => c.to_string(),
+        keyboard::Key::Named(Named::ArrowLeft) => "Left".to_string(),
+        keyboard::Key::Named(Named::ArrowRight) => "Right".to_string(),
+        keyboard::Key::Named(Named::ArrowUp) => "Up".to_string(),
+        keyboard::Key::Named(Named::ArrowDown) => "Down".to_string(),
+        keyboard::Key::Named(named) => format!("{:?}", named),
+        keyboard::Key::Unidentified => return None,
+    };
+    let mut combo = String::new();
+    for (held, modifier) in [
+        (modifiers.logo(), "Super+"),
+        (modifiers.control(), "Ctrl+"),
+        (modifiers.alt(), "Alt+"),
+        (modifiers.shift(), "Shift+"),
+    ] {
+        if held {
+            combo.push_str(modifier);
+        }
+    }
+    combo.push_str(&name);
+    KeyCombo::parse(&combo).ok()
+}
+
+/// Key press → Message of the bound window manager action
+pub fn wm_shortcut(key: keyboard::Key, modifiers: keyboard::Modifiers) -> Option<Message> {
+    let combo = combo_from_iced(&key, modifiers)?;
+    WmHotkeys::global().action(&combo).map(|action| action.message())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::window_handling::{WindowGeometry, WindowHandler, WindowState};
+    use crate::parser::ConfigParser;
+    use crate::window_client::WindowClient;
+    use crate::window_multitary::{InputAction, LayoutPolicy};
+    use wbackend::ResourceMode;
+
+    #[test]
+    fn test_wm_hotkeys() {
+        let mut settings = WsdgSettings::default();
+        settings.custom.insert("hotkeys.close_window".into(), "super+q".into());
+        settings.custom.insert("hotkeys.next_layout".into(), "".into());
+        settings.custom.insert("hotkeys.cycle_focus".into(), "Alt+".into());
+        let hotkeys = WmHotkeys::from_settings(&settings);
+
+        let combo = |raw: &str| KeyCombo::parse(raw).unwrap();
+        assert_eq!(hotkeys.action(&combo("Super+Q")), Some(WmAction::CloseWindow));
+        assert_eq!(hotkeys.action(&combo("Alt+F4")), None);
+        assert_eq!(hotkeys.combo(WmAction::NextLayout), None);
+        // Invalid combos fall back to the default
+        assert_eq!(hotkeys.action(&combo("Alt+Tab")), Some(WmAction::CycleFocus));
+        assert_eq!(hotkeys.bindings().len(), 4);
+
+        // iced key presses map to the same combos
+        let alt_shift = keyboard::Modifiers::ALT | keyboard::Modifiers::SHIFT;
+        assert_eq!(combo_from_iced(&keyboard::Key::Named(Named::Tab), alt_shift), Some(combo("Alt+Shift+Tab")));
+        assert_eq!(combo_from_iced(&keyboard::Key::Character("q".into()), keyboard::Modifiers::LOGO), Some(combo("Super+Q")));
+        assert_eq!(combo_from_iced(&keyboard::Key::Named(Named::ArrowLeft), keyboard::Modifiers::LOGO), Some(combo("Super+Left")));
+
+        // Bound combos are off limits for applications
+        let handler = Arc::new(WindowHandler::new(ResourceMode::Manual));
+        let registry = HotkeyRegistry::new(handler.clone());
+        hotkeys.reserve_in(&registry);
+        assert!(registry.reserved().iter().any(|(c, name)| *c == combo("Super+Q") && name == "Close focused window"));
+
+        // Focus cycles through the visible windows and skips minimized ones
+        let geometry = WindowGeometry { x: 0, y: 0, width: 400, height: 300 };
+        let create = |title: &str| handler.create_window(title.into(), "test.hotkeys".into(), geometry, None, ResourceMode::Manual).unwrap();
+        let (first, second, third) = (create("One"), create("Two"), create("Three"));
+        handler.set_window_state(second, WindowState::Minimized).unwrap();
+        assert_eq!(handler.cycle_focus(false), Some(first));
+        assert_eq!(handler.cycle_focus(false), Some(third));
+        assert_eq!(handler.cycle_focus(false), Some(first));
+        assert_eq!(handler.cycle_focus(true), Some(third));
+
+        // Layouts cycle and tile the visible windows over the output
+        assert_eq!(LayoutPolicy::Vertical.next(), LayoutPolicy::Horizontal);
+        assert_eq!(LayoutPolicy::Floating.next(), LayoutPolicy::Vertical);
+        assert_eq!(handler.arrange_windows(LayoutPolicy::Horizontal), 2);
+        let (width, height) = crate::DEFAULT_SCREEN_SIZE;
+        assert_eq!(handler.get_window(third).unwrap().geometry, WindowGeometry { x: width as i32 / 2, y: 0, width: width / 2, height });
+        assert_eq!(handler.get_window(second).unwrap().geometry, geometry);
+
+        // The streaming client acts on its viewports
+        let parser = ConfigParser::new(None);
+        let mut client = WindowClient::new(parser.parse(&parser.generate_default_config()).unwrap(), 640, 480);
+        client.set_hotkeys(hotkeys);
+        assert_eq!(client.route_key(&combo("Alt+Tab")), [InputAction::FocusChanged { previous: None, current: Some(0) }]);
+        assert_eq!(client.route_key(&combo("Super+Q")), [InputAction::CloseRequested { stream_id: 0 }]);
+        assert_eq!(client.route_key(&combo("Super+Space")), [InputAction::Key { stream_id: 0, combo: combo("Super+Space") }]);
+        client.set_hotkeys(WmHotkeys::default());
+        assert_eq!(client.route_key(&combo("Super+Space")), [InputAction::LayoutChanged(LayoutPolicy::Horizontal)]);
+        assert_eq!(client.layout_policy(), LayoutPolicy::Horizontal);
+    }
+}
@@ -0,0 +1,345 @@
+// i18n.rs
+// WASMA - Localization
+// Fluent (.ftl) message catalogs for GUI, CLI and error strings.
+// Locale comes from the WSDG translated environment (LANGUAGE / LC_ALL / LC_MESSAGES / LANG).
+// Catalogs are formatted by fluent-bundle; select expressions on numbers use
+// the CLDR plural rules of the catalog's locale.
+
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::types::FluentNumber;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use fluent_syntax::ast;
+use thiserror::Error;
+use unic_langid::LanguageIdentifier;
+use wsdg_xdg::WsdgEnv;
+
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// Catalogs compiled into the binary; files on disk override them
+const BUILTIN: &[(&str, &str)] = &[
+    ("en-US", include_str!("../locales/en-US/wasma.ftl")),
+    ("tr-TR", include_str!("../locales/tr-TR/wasma.ftl")),
+];
+
+#[derive(Debug, Error)]
+pub enum I18nError {
+    #[error("FTL syntax error on line {line}: {reason}")]
+    Syntax { line: usize, reason: String },
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+// ============================================================================
+// CATALOG
+// ============================================================================
+
+/// One parsed .ftl source
+pub struct Catalog(FluentResource);
+
+impl Catalog {
+    /// Parse Fluent source; the first syntax error is reported with its line
+    pub fn parse(source: &str) -> Result<Self, I18nError> {
+        FluentResource::try_new(source.to_string()).map(Catalog).map_err(|(_, errors)| {
+            let error = &errors[0];
+            let before = source.get(..error.pos.start).unwrap_or(source);
+            I18nError::Syntax { line: before.matches('\n').count() + 1, reason: error.to_string() }
+        })
+    }
+
+    fn message_ids(&self) -> impl Iterator<Item = &str> {
+        self.0.entries().filter_map(|entry| match entry {
+            ast::Entry::Message(message) => Some(message.id.name),
+            _ => None,
+        })
+    }
+
+    pub fn has(&self, id: &str) -> bool {
+        self.message_ids().any(|m| m == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.message_ids().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// ============================================================================
+// FORMATTING
+// ============================================================================
+
+/// Arguments that read back unchanged as numbers are passed as numbers, so
+/// select expressions use the locale's CLDR plural category ("2" → few in
+/// Polish, "1.0" → other in English); everything else stays a string
+fn fluent_value(value: &str) -> FluentValue<'_> {
+    let plain = !value.is_empty() && value.trim_start_matches('-').chars().all(|c| c.is_ascii_digit() || c == '.');
+    match value.parse::<FluentNumber>() {
+        Ok(number) if plain && number.as_string() == value => FluentValue::Number(number),
+        _ => FluentValue::from(value),
+    }
+}
+
+// ============================================================================
+// LOCALIZER
+// ============================================================================
+
+pub struct Localizer {
+    locale: String,
+    // Fallback chain: requested locale first, en-US last; each bundle selects
+    // plural variants with its own locale's rules
+    chain: Vec<FluentBundle<FluentResource>>,
+}
+
+impl Localizer {
+    pub fn new(locale: &str) -> Self {
+        let locale = normalize_locale(locale);
+        let mut chain = Vec::new();
+
+        for candidate in fallback_chain(&locale) {
+            let Ok(langid) = candidate.parse::<LanguageIdentifier>() else { continue };
+            let catalogs: Vec<Catalog> = builtin_catalog(&candidate).into_iter()
+                .chain(locale_dirs().iter().flat_map(|dir| load_dir(&dir.join(&candidate)).unwrap_or_default()))
+                .collect();
+            if catalogs.is_empty() {
+                continue;
+            }
+            chain.push(bundle(langid, catalogs));
+        }
+
+        Self { locale, chain }
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Format a message; an unknown id is returned as-is so missing strings stay visible
+    pub fn format(&self, id: &str, args: &[(&str, String)]) -> String {
+        let Some((bundle, pattern)) = self.chain.iter()
+            .find_map(|bundle| Some((bundle, bundle.get_message(id)?.value()?)))
+        else {
+            return id.to_string();
+        };
+
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, fluent_value(value));
+        }
+        // Unknown variables render as `{$name}`; nothing else to report
+        let mut errors = Vec::new();
+        bundle.format_pattern(pattern, Some(&fluent_args), &mut errors).into_owned()
+    }
+}
+
+/// Bundle of one locale's catalogs; later catalogs override earlier ids
+fn bundle(langid: LanguageIdentifier, catalogs: Vec<Catalog>) -> FluentBundle<FluentResource> {
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Terminal and GUI text: no bidi isolation marks around placeables
+    bundle.set_use_isolating(false);
+    for catalog in catalogs {
+        bundle.add_resource_overriding(catalog.0);
+    }
+    bundle
+}
+
+fn builtin_catalog(locale: &str) -> Option<Catalog> {
+    BUILTIN.iter()
+        .find(|(l, _)| *l == locale)
+        .and_then(|(_, src)| Catalog::parse(src).ok())
+}
+
+fn load_dir(dir: &PathBuf) -> Result<Vec<Catalog>, I18nError> {
+    let mut catalogs = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "ftl") {
+            catalogs.push(Catalog::parse(&std::fs::read_to_string(&path)?)?);
+        }
+    }
+    Ok(catalogs)
+}
+
+/// Directories searched for `<locale>/*.ftl` overrides (later wins)
+fn locale_dirs() -> Vec<PathBuf> {
+    let env = WsdgEnv::new();
+    let mut dirs = vec![PathBuf::from("/usr/share/wasma/locales")];
+    if let Ok(share) = env.share_dir() {
+        dirs.push(share.join("wasma/locales"));
+    }
+    dirs
+}
+
+/// "tr_TR.UTF-8@euro" → "tr-TR", "C"/"POSIX" → en-US
+pub fn normalize_locale(raw: &str) -> String {
+    let base = raw.split(['.', '@']).next().unwrap_or("").trim();
+    if base.is_empty() || base == "C" || base == "POSIX" {
+        return DEFAULT_LOCALE.to_string();
+    }
+
+    let mut parts = base.split(['_', '-']);
+    let lang = parts.next().unwrap_or("").to_lowercase();
+    match parts.next() {
+        Some(region) => format!("{}-{}", lang, region.to_uppercase()),
+        None => lang,
+    }
+}
+
+/// "tr-TR" → ["tr-TR", "tr-*" builtin with same language, "en-US"]
+fn fallback_chain(locale: &str) -> Vec<String> {
+    let mut chain = vec![locale.to_string()];
+    let lang = locale.split('-').next().unwrap_or(locale);
+
+    if let Some((builtin, _)) = BUILTIN.iter().find(|(l, _)| l.split('-').next() == Some(lang)) {
+        chain.push(builtin.to_string());
+    }
+    chain.push(DEFAULT_LOCALE.to_string());
+    chain.dedup();
+    chain
+}
+
+/// Locale from the WSDG translated environment (WSDG overrides, then system vars)
+pub fn detect_locale(env: &WsdgEnv) -> String {
+    if let Some(first) = env.get("LANGUAGE").and_then(|v| v.split(':').find(|s| !s.is_empty())) {
+        return normalize_locale(first);
+    }
+
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|key| env.get(key))
+        .find(|v| !v.is_empty())
+        .map(|v| normalize_locale(v))
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+}
+
+// ============================================================================
+// GLOBAL API
+// ============================================================================
+
+static LOCALIZER: OnceLock<RwLock<Localizer>> = OnceLock::new();
+
+fn localizer() -> &'static RwLock<Localizer> {
+    LOCALIZER.get_or_init(|| RwLock::new(Localizer::new(&detect_locale(&WsdgEnv::new()))))
+}
+
+/// Select the locale explicitly, or detect it when `None`
+pub fn init(locale: Option<&str>) {
+    let locale = locale
+        .map(str::to_string)
+        .unwrap_or_else(|| detect_locale(&WsdgEnv::new()));
+    set_locale(&locale);
+}
+
+pub fn set_locale(locale: &str) {
+    *localizer().write().unwrap() = Localizer::new(locale);
+}
+
+pub fn locale() -> String {
+    localizer().read().unwrap().locale().to_string()
+}
+
+pub fn tr(id: &str) -> String {
+    localizer().read().unwrap().format(id, &[])
+}
+
+pub fn tr_args(id: &str, args: &[(&str, String)]) -> String {
+    localizer().read().unwrap().format(id, args)
+}
+
+/// `t!("window-created", id = 3)` → localized string
+#[macro_export]
+macro_rules! t {
+    ($id:expr) => {
+        $crate::i18n::tr($id)
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::tr_args($id, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+# comment
+-brand = WASMA
+greeting = Hello from { -brand }, { $user }!
+windows-total = { $count ->
+    [one] One window
+   *[other] { $count } windows
+}
+multi =
+    first line
+    second line
+"#;
+
+    fn localizer(locale: &str, source: &str) -> Localizer {
+        let catalog = Catalog::parse(source).unwrap();
+        Localizer { locale: locale.to_string(), chain: vec![bundle(locale.parse().unwrap(), vec![catalog])] }
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        let localizer = localizer("en-US", SAMPLE);
+
+        assert_eq!(localizer.format("greeting", &[("user", "ada".to_string())]), "Hello from WASMA, ada!");
+        assert_eq!(localizer.format("windows-total", &[("count", "1".to_string())]), "One window");
+        assert_eq!(localizer.format("windows-total", &[("count", "4".to_string())]), "4 windows");
+        assert_eq!(localizer.format("multi", &[]), "first line\nsecond line");
+        assert_eq!(localizer.format("missing-id", &[]), "missing-id");
+        // A visible fraction digit makes "1.0" other in English; "007" stays a string
+        assert_eq!(localizer.format("windows-total", &[("count", "1.0".to_string())]), "1.0 windows");
+        assert_eq!(localizer.format("greeting", &[("user", "007".to_string())]), "Hello from WASMA, 007!");
+
+        assert!(matches!(Catalog::parse("ok = fine\nnot a message"), Err(I18nError::Syntax { line: 2, .. })));
+    }
+
+    #[test]
+    fn test_cldr_plural_categories() {
+        let polish = localizer("pl-PL", r#"
+files = { $count ->
+    [one] { $count } plik
+    [few] { $count } pliki
+   *[many] { $count } plików
+}
+"#);
+        let files = |n: &str| polish.format("files", &[("count", n.to_string())]);
+        assert_eq!(files("1"), "1 plik");
+        assert_eq!(files("3"), "3 pliki");
+        assert_eq!(files("5"), "5 plików");
+        assert_eq!(files("22"), "22 pliki");
+        assert_eq!(files("112"), "112 plików");
+
+        // An exact-number variant wins over the category
+        let english = localizer("en-US", "left = { $n ->\n    [0] none left\n    [one] one left\n   *[other] { $n } left\n}\n");
+        assert_eq!(english.format("left", &[("n", "0".to_string())]), "none left");
+        assert_eq!(english.format("left", &[("n", "1".to_string())]), "one left");
+    }
+
+    #[test]
+    fn test_locale_detection_and_builtins() {
+        assert_eq!(normalize_locale("tr_TR.UTF-8"), "tr-TR");
+        assert_eq!(normalize_locale("C"), "en-US");
+        assert_eq!(normalize_locale("de"), "de");
+
+        let env = wsdg_xdg::WsdgEnvBuilder::new()
+            .var("LANGUAGE", "tr_TR:en")
+            .system_fallback(false)
+            .build();
+        assert_eq!(detect_locale(&env), "tr-TR");
+
+        // Every built-in catalog parses and covers the English ids
+        let english = builtin_catalog("en-US").unwrap();
+        for (locale, _) in BUILTIN {
+            let catalog = builtin_catalog(locale).unwrap();
+            for id in english.message_ids() {
+                assert!(catalog.has(id), "{} is missing {}", locale, id);
+            }
+        }
+    }
+}
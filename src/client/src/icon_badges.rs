@@ -0,0 +1,315 @@
+// icon_badges.rs
+// WASMA - Status badges composed onto window icons
+// Notification count, recording dot and paused overlay drawn over a window's
+// IconInfo at render time; composed results are cached per icon/size/badge set
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tiny_skia::{Color, FillRule, Paint, PathBuilder, Pixmap, PixmapPaint, Rect, Transform};
+use wsdg_xdg::{IcoFormat, IconInfo};
+
+use crate::accessibility;
+
+/// Composed icons kept before the cache starts over
+pub const BADGE_CACHE_CAPACITY: usize = 256;
+
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+// 3x5 digit glyphs, one row per byte (bit 2 = left column)
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// Status drawn over a window icon
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IconBadge {
+    /// Unread notifications, top-right; shown up to 99
+    Count(u32),
+    /// Capturing screen or audio, bottom-left dot
+    Recording,
+    /// Assignment paused, dimmed icon with a pause sign
+    Paused,
+}
+
+impl IconBadge {
+    /// Text form for surfaces that cannot show pixels (GUI cards, CLI)
+    pub fn label(&self) -> String {
+        match self {
+            Self::Count(n) if *n > 99 => "🔔99+".to_string(),
+            Self::Count(n) => format!("🔔{}", n),
+            Self::Recording => "🔴".to_string(),
+            Self::Paused => "⏸".to_string(),
+        }
+    }
+}
+
+/// Square RGBA icon with badges applied
+#[derive(Debug, Clone)]
+pub struct ComposedIcon {
+    pixmap: Pixmap,
+}
+
+impl ComposedIcon {
+    pub fn size(&self) -> u32 {
+        self.pixmap.width()
+    }
+
+    /// Straight (non-premultiplied) RGBA rows
+    pub fn rgba(&self) -> Vec<u8> {
+        self.pixmap
+            .pixels()
+            .iter()
+            .flat_map(|p| {
+                let c = p.demultiply();
+                [c.red(), c.green(), c.blue(), c.alpha()]
+            })
+            .collect()
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        self.pixmap.encode_png().map_err(|e| e.to_string())
+    }
+
+    /// EWMH `_NET_WM_ICON` payload: width, height, then ARGB pixels
+    pub fn to_net_wm_icon(&self) -> Vec<u32> {
+        let mut data = vec![self.pixmap.width(), self.pixmap.height()];
+        data.extend(self.pixmap.pixels().iter().map(|p| {
+            let c = p.demultiply();
+            u32::from_be_bytes([c.alpha(), c.red(), c.green(), c.blue()])
+        }));
+        data
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    icon: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    size: u32,
+    badges: Vec<IconBadge>,
+}
+
+/// Composes badges onto icons, reusing earlier results
+#[derive(Debug, Default)]
+pub struct BadgeCompositor {
+    cache: Mutex<HashMap<CacheKey, Arc<ComposedIcon>>>,
+}
+
+impl BadgeCompositor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `icon` scaled to `size` with `badges` on top. Without a decodable icon
+    /// (missing, SVG, XPM, BMP-only ICO) the badges go on a themed tile.
+    pub fn compose(&self, icon: Option<&IconInfo>, badges: &[IconBadge], size: u32) -> Result<Arc<ComposedIcon>, String> {
+        let size = size.max(8);
+        let key = CacheKey {
+            icon: icon.map(|i| i.path.clone()),
+            // An icon edited on disk composes again
+            modified: icon.and_then(|i| std::fs::metadata(&i.path).and_then(|m| m.modified()).ok()),
+            size,
+            badges: badges.to_vec(),
+        };
+        if let Some(hit) = self.cache.lock().unwrap().get(&key) {
+            return Ok(hit.clone());
+        }
+
+        let mut canvas = Pixmap::new(size, size).ok_or_else(|| format!("Invalid icon size {}", size))?;
+        match icon.and_then(decode_icon) {
+            Some(source) => {
+                let transform = Transform::from_scale(
+                    size as f32 / source.width() as f32,
+                    size as f32 / source.height() as f32,
+                );
+                let paint = PixmapPaint { quality: tiny_skia::FilterQuality::Bicubic, ..Default::default() };
+                canvas.draw_pixmap(0, 0, source.as_ref(), &paint, transform, None);
+            }
+            None => draw_tile(&mut canvas),
+        }
+
+        // Overlay first, so the dot and the count stay bright on a paused icon
+        let mut ordered = badges.to_vec();
+        ordered.sort_by_key(|b| match b {
+            IconBadge::Paused => 0,
+            IconBadge::Recording => 1,
+            IconBadge::Count(_) => 2,
+        });
+        for badge in ordered {
+            match badge {
+                IconBadge::Paused => draw_paused(&mut canvas),
+                IconBadge::Recording => draw_recording(&mut canvas),
+                IconBadge::Count(0) => {}
+                IconBadge::Count(n) => draw_count(&mut canvas, n.min(99)),
+            }
+        }
+
+        let composed = Arc::new(ComposedIcon { pixmap: canvas });
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= BADGE_CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache.insert(key, composed.clone());
+        Ok(composed)
+    }
+
+    pub fn cached(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+}
+
+/// PNG files, or the largest PNG entry of an ICO
+fn decode_icon(icon: &IconInfo) -> Option<Pixmap> {
+    let data = std::fs::read(&icon.path).ok()?;
+    match icon.format {
+        IcoFormat::Png => Pixmap::decode_png(&data).ok(),
+        IcoFormat::Ico => {
+            let count = u16::from_le_bytes([*data.get(4)?, *data.get(5)?]) as usize;
+            (0..count)
+                .filter_map(|i| {
+                    let entry = data.get(6 + i * 16..6 + i * 16 + 16)?;
+                    let len = u32::from_le_bytes(entry[8..12].try_into().ok()?) as usize;
+                    let offset = u32::from_le_bytes(entry[12..16].try_into().ok()?) as usize;
+                    let image = data.get(offset..offset.checked_add(len)?)?;
+                    image.starts_with(PNG_MAGIC).then_some(image)
+                })
+                .filter_map(|image| Pixmap::decode_png(image).ok())
+                .max_by_key(|p| p.width())
+        }
+        _ => None,
+    }
+}
+
+fn paint_color(color: wbackend::Color) -> Color {
+    Color::from_rgba8(color.r, color.g, color.b, color.a)
+}
+
+fn fill_circle(canvas: &mut Pixmap, cx: f32, cy: f32, radius: f32, color: Color) {
+    if let Some(path) = PathBuilder::from_circle(cx, cy, radius) {
+        let mut paint = Paint::default();
+        paint.set_color(color);
+        paint.anti_alias = true;
+        canvas.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+    }
+}
+
+fn fill_rect(canvas: &mut Pixmap, x: f32, y: f32, w: f32, h: f32, color: Color) {
+    if let Some(rect) = Rect::from_xywh(x, y, w, h) {
+        let mut paint = Paint::default();
+        paint.set_color(color);
+        canvas.fill_rect(rect, &paint, Transform::identity(), None);
+    }
+}
+
+/// Stand-in for windows without a usable icon
+fn draw_tile(canvas: &mut Pixmap) {
+    let tokens = accessibility::tokens();
+    let size = canvas.width() as f32;
+    let inset = size * 0.08;
+    fill_rect(canvas, inset, inset, size - 2.0 * inset, size - 2.0 * inset, paint_color(tokens.surface));
+    fill_circle(canvas, size / 2.0, size / 2.0, size * 0.2, paint_color(tokens.accent));
+}
+
+fn draw_paused(canvas: &mut Pixmap) {
+    let size = canvas.width() as f32;
+    fill_rect(canvas, 0.0, 0.0, size, size, Color::from_rgba8(0, 0, 0, 115));
+    let (bar_w, bar_h) = (size * 0.12, size * 0.4);
+    let top = (size - bar_h) / 2.0;
+    fill_rect(canvas, size / 2.0 - bar_w * 1.5, top, bar_w, bar_h, Color::WHITE);
+    fill_rect(canvas, size / 2.0 + bar_w * 0.5, top, bar_w, bar_h, Color::WHITE);
+}
+
+fn draw_recording(canvas: &mut Pixmap) {
+    let danger = accessibility::tokens().danger;
+    let size = canvas.width() as f32;
+    let radius = size * 0.14;
+    let (cx, cy) = (radius * 1.3, size - radius * 1.3);
+    fill_circle(canvas, cx, cy, radius * 1.25, Color::WHITE);
+    fill_circle(canvas, cx, cy, radius, paint_color(danger));
+}
+
+fn draw_count(canvas: &mut Pixmap, count: u32) {
+    let danger = accessibility::tokens().danger;
+    let size = canvas.width() as f32;
+    let radius = size * 0.24;
+    let (cx, cy) = (size - radius, radius);
+    fill_circle(canvas, cx, cy, radius, paint_color(danger));
+
+    let digits: Vec<usize> = count.to_string().bytes().map(|b| (b - b'0') as usize).collect();
+    // Glyphs are 3x5 cells with one cell between digits
+    let columns = digits.len() as f32 * 4.0 - 1.0;
+    let cell = (radius * 1.3 / columns.max(5.0)).max(1.0);
+    let left = cx - columns * cell / 2.0;
+    let top = cy - 2.5 * cell;
+    for (i, digit) in digits.iter().enumerate() {
+        for (row, bits) in DIGITS[*digit].iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) != 0 {
+                    let x = left + (i as f32 * 4.0 + col as f32) * cell;
+                    fill_rect(canvas, x, top + row as f32 * cell, cell, cell, Color::WHITE);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wsdg_xdg::IconSize;
+
+    #[test]
+    fn test_badge_composition() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.png");
+        let mut source = Pixmap::new(16, 16).unwrap();
+        source.fill(Color::from_rgba8(0, 0, 255, 255));
+        std::fs::write(&path, source.encode_png().unwrap()).unwrap();
+        let icon = IconInfo { name: "app".into(), path, size: IconSize::Size16, format: IcoFormat::Png };
+
+        let compositor = BadgeCompositor::new();
+        let plain = compositor.compose(Some(&icon), &[], 32).unwrap();
+        assert_eq!(plain.size(), 32);
+        assert_eq!(&plain.rgba()[..4], &[0, 0, 255, 255]);
+
+        let badged = compositor.compose(Some(&icon), &[IconBadge::Count(3), IconBadge::Recording], 32).unwrap();
+        // Count badge covers the top-right corner, the icon shows elsewhere
+        let pixel = |icon: &ComposedIcon, x: usize, y: usize| icon.rgba()[(y * 32 + x) * 4..][..4].to_vec();
+        assert_ne!(pixel(&badged, 26, 2), vec![0, 0, 255, 255]);
+        assert_eq!(pixel(&badged, 16, 16), vec![0, 0, 255, 255]);
+
+        let paused = compositor.compose(Some(&icon), &[IconBadge::Paused], 32).unwrap();
+        assert!(pixel(&paused, 1, 1)[2] < 255);
+
+        // Same inputs come from the cache
+        let again = compositor.compose(Some(&icon), &[IconBadge::Count(3), IconBadge::Recording], 32).unwrap();
+        assert!(Arc::ptr_eq(&badged, &again));
+        assert_eq!(compositor.cached(), 3);
+
+        let net = badged.to_net_wm_icon();
+        assert_eq!(&net[..2], &[32, 32]);
+        assert_eq!(net.len(), 2 + 32 * 32);
+
+        // No icon: badges land on a themed tile
+        let tile = compositor.compose(None, &[IconBadge::Count(120)], 24).unwrap();
+        assert!(tile.to_png().unwrap().starts_with(PNG_MAGIC));
+        assert_eq!(IconBadge::Count(120).label(), "🔔99+");
+    }
+}
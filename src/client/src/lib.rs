@@ -10,27 +10,137 @@ pub use source_parser as source;
 pub mod window_handling;
 pub mod window_client;
 pub mod window_multitary;
+pub mod monitors;
+pub mod texture_atlas;
+pub mod adaptive_resolution;
+pub mod presentation_buffer;
+pub mod presenter;
+pub mod thumbnails;
 pub mod window_singularity;
 pub mod protocols;
+pub mod protocol_control;
 pub mod uclient;
+pub mod context_pool;
+pub mod cpu_history;
+pub mod resource_graphs;
+pub mod telemetry;
+pub mod metrics;
+pub mod webhooks;
+pub mod hooks;
+pub mod crash_report;
+pub mod logging;
+pub mod config_backup;
 pub mod wgclient;
+pub mod tor;
+pub mod grpc;
+pub mod grpc_control;
+pub mod pixel_format;
+pub mod seats;
 pub mod window_resourcer_engineering;
+pub mod session_lock;
+pub mod lock_surface;
+pub mod global_hotkeys;
+pub mod hotkeys;
+pub mod decorations;
+pub mod placeholder;
+pub mod icon_badges;
+pub mod permission_enforcement;
+pub mod window_snapping;
+pub mod window_placement;
+pub mod workspaces;
+pub mod window_rules;
+pub mod focus_policy;
+pub mod command_palette;
+pub mod i18n;
+pub mod accessibility;
+pub mod output;
+pub mod control;
+pub mod renderer_soft;
+#[cfg(feature = "vulkan")]
+pub mod renderer_vulkan;
+#[cfg(feature = "x11")]
+pub mod x11_adoption;
+#[cfg(feature = "x11")]
+pub mod x11_backend;
+#[cfg(feature = "wayland")]
+pub mod wayland_backend;
+#[cfg(feature = "wayland-compositor")]
+pub mod wayland_compositor;
+#[cfg(feature = "xwayland")]
+pub mod xwayland;
+#[cfg(feature = "dbus")]
+pub mod dbus;
 
 // Re-export commonly used types
-pub use parser::{ConfigParser, ParserError, Protocol, ProtocolConfig, WasmaConfig};
+pub use parser::{ConfigParser, LeaseExpiry, ParserError, Protocol, ProtocolConfig, WasmaConfig};
 pub use window_handling::{
-    Window, WindowHandler, WindowGeometry, WindowState, WindowType,
-    ResourceLimits, PermissionScope, BackendType, ResourceUsage,
+    Window, WindowHandler, WindowGeometry, WindowState, WindowType, ManifestConfig,
+    ResourceLimits, PermissionScope, BackendType, ResourceUsage, WindowEvent, DetachMode,
     WasmaWindowManager, launch_window_manager, Message,
 };
 pub use window_client::WindowClient;
-pub use window_multitary::{WindowMultitary, Viewport};
+pub use adaptive_resolution::{ResolutionScaler, ScalerConfig, ScaleChange, ScaleMethod};
+pub use presentation_buffer::{PresentationBuffer, PresentationConfig, PresentedFrame, FrameKind, PresentationStats};
+pub use presenter::{Presenter, DoubleBuffer, VsyncSource};
+pub use thumbnails::{FrameSource, RgbaImage};
+pub use window_multitary::{WindowMultitary, Viewport, LayoutPolicy, InputAction, DragMode, StreamFocusEvent};
+pub use monitors::{Monitor, MonitorMap, MonitorWatcher};
+pub use protocol_control::{ProtocolControl, EndpointState, EndpointStats, EndpointTransition};
+pub use wgclient::{WGClient, StreamMux, StreamSender, StreamStats, STREAM_QUEUE_CAPACITY};
+pub use texture_atlas::{TextureAtlas, AtlasSlot, CompositePlan, DrawCommand, TextureRef, UvRect};
 pub use window_singularity::{WindowSingularity, SINGULARITY_LOCK};
+pub use session_lock::{SessionLock, LockError, SESSION_LOCKED};
+pub use global_hotkeys::{HotkeyRegistry, HotkeyDaemon, HotkeyBinding, HotkeyError, KeyCombo, Modifiers};
+pub use hotkeys::{WmAction, WmHotkeys};
+pub use decorations::{Decorations, DecorationTheme, DecorationButton, DecorationHit};
+pub use placeholder::{Placeholder, PlaceholderMode};
+pub use icon_badges::{BadgeCompositor, ComposedIcon, IconBadge};
+pub use permission_enforcement::{PermissionEnforcer, EnforcementReport, Restriction, Outcome};
+pub use window_snapping::{WindowSnapper, SnapZone, SnapDirection, ResizeEdge};
+pub use window_placement::{TypeBehavior, Placement};
+pub use workspaces::Workspace;
+pub use window_rules::WindowRule;
+pub use focus_policy::{FocusEngine, FocusPolicy, FocusConfig, PointerEvent};
+pub use command_palette::{CommandRegistry, CommandProvider, PaletteCommand};
+pub use i18n::{Localizer, Catalog, I18nError};
+pub use output::{OutputFormat, OutputError};
+pub use control::{ControlDaemon, ControlClient, ControlError};
+pub use context_pool::{ContextPool, RendererContext};
+pub use cpu_history::{CpuHistory, CpuSampler, CpuSource};
+pub use config_backup::{ConfigLocations, BackupItem, BackupEntry, BackupReport, BackupError, BACKUP_FORMAT_VERSION};
+pub use grpc::{GrpcClient, GrpcError, FrameStream, MessageStream};
+pub use grpc_control::{GrpcControlServer, HealthRegistry, HealthStatus};
+pub use pixel_format::{PixelFormat, FrameHeader, FrameAssembler, FormatError, DamageRect};
+pub use uclient::{UClient, ConnectionEvent, ReconnectPolicy};
+pub use renderer_soft::{SoftRenderer, SoftFramebuffer, SoftPresenter};
+pub use seats::{Seat, SeatChange, SeatDevice, SeatDiscovery, SeatInstance, SeatManager, SeatUser, DEFAULT_SEAT};
+pub use telemetry::{Telemetry, TelemetrySink, TelemetryReport, ErrorCategory};
+pub use webhooks::{WebhookNotifier, WebhookEvent, WebhookPayload, WebhookStats};
+pub use hooks::{HookRunner, HookEvent, HookOutcome, HookResult};
+#[cfg(feature = "x11")]
+pub use x11_adoption::{X11Adopter, AdoptedClient};
+#[cfg(feature = "x11")]
+pub use x11_backend::{X11Backend, NetWmState};
+#[cfg(feature = "wayland")]
+pub use wayland_backend::{WaylandBackend, ToplevelConfigure};
+#[cfg(feature = "wayland-compositor")]
+pub use wayland_compositor::{WaylandCompositor, WaylandToplevel};
+#[cfg(feature = "xwayland")]
+pub use xwayland::{XWayland, XwaylandWindow, X11Metadata};
 
 // WBackend integration
-pub use wbackend::{Assignment, ExecutionMode, ResourceMode, WBackend};
+pub use wbackend::{Assignment, ExecutionMode, MonitorReport, ResourceMode, WBackend};
 
+use std::path::Path;
+use std::process::{Child, Command};
 use std::sync::Arc;
+use wsdg_app_manifest::manifest_parser::WindowPlacement;
+
+/// Size of a manifest window that does not declare width/height
+pub const DEFAULT_MANIFEST_WINDOW_SIZE: (u32, u32) = (800, 600);
+
+/// Output size assumed when centering manifest windows (the manager's snapping area)
+pub const DEFAULT_SCREEN_SIZE: (u32, u32) = (1920, 1080);
 
 /// WASMA Core - Main entry point for the architecture
 pub struct WasmaCore {
@@ -129,14 +239,94 @@ impl WasmaCore {
         Ok(window_id)
     }
 
+    /// Create a window laid out by the manifest's `[window]` section: size, position,
+    /// type, decorations, always-on-top and initial state
+    pub fn create_window_with_manifest(
+        &self,
+        title: String,
+        app_id: String,
+        manifest_path: String,
+    ) -> Result<u64, String> {
+        let config = self.window_handler.load_manifest_config(&manifest_path)?;
+        let spec = &config.manifest.window;
+
+        let width = spec.width.unwrap_or(DEFAULT_MANIFEST_WINDOW_SIZE.0);
+        let height = spec.height.unwrap_or(DEFAULT_MANIFEST_WINDOW_SIZE.1);
+        let (x, y) = match spec.position {
+            WindowPlacement::Auto => (100, 100),
+            WindowPlacement::Centered => {
+                let (screen_width, screen_height) = DEFAULT_SCREEN_SIZE;
+                (
+                    (screen_width.saturating_sub(width) / 2) as i32,
+                    (screen_height.saturating_sub(height) / 2) as i32,
+                )
+            }
+            WindowPlacement::At { x, y } => (x, y),
+        };
+        let geometry = WindowGeometry { x, y, width, height };
+
+        let window_id = self.window_handler.create_window(
+            title,
+            app_id,
+            geometry,
+            Some(manifest_path),
+            self.resource_mode,
+        )?;
+
+        if let Err(e) = self.window_handler.apply_manifest_window(window_id, spec) {
+            self.window_handler.close_window(window_id).ok();
+            return Err(e);
+        }
+        Ok(window_id)
+    }
+
+    /// Create the manifest's window and launch its `exec` with the window's
+    /// permission scope enforced; the report is kept on the window
+    pub fn launch_app(
+        &self,
+        title: String,
+        app_id: String,
+        manifest_path: String,
+        targets: &[&str],
+    ) -> Result<(u64, Child, EnforcementReport), String> {
+        let config = self.window_handler.load_manifest_config(&manifest_path)?;
+        let launch = config.manifest.app.launch_command(targets)
+            .ok_or_else(|| format!("Manifest {} has no exec", manifest_path))?;
+
+        let window_id = self.create_window_with_manifest(title, app_id.clone(), manifest_path)?;
+        let Some(window) = self.window_handler.get_window(window_id) else {
+            return Err(format!("Window {} closed during launch", window_id));
+        };
+
+        let mut command = Command::new(&launch.program);
+        command.args(&launch.args);
+        if let Some(cwd) = &launch.cwd {
+            command.current_dir(cwd);
+        }
+        let pending = PermissionEnforcer::new(&window.permissions)
+            .for_app(&app_id, Path::new(&launch.program))
+            .apply(&mut command);
+
+        let child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                self.window_handler.close_window(window_id).ok();
+                return Err(format!("{}: {}", launch.program, e));
+            }
+        };
+        let report = pending.finish(child.id());
+        self.window_handler.set_window_enforcement(window_id, report.clone())?;
+        Ok((window_id, child, report))
+    }
+
     /// Get window resource usage
     pub fn get_window_resources(&self, window_id: u64) -> Result<ResourceUsage, String> {
         self.window_handler.get_window_resource_usage(window_id)
     }
 
     /// Run resource management cycle
-    pub fn update(&self) {
-        self.window_handler.run_resource_cycle();
+    pub fn update(&self) -> MonitorReport {
+        self.window_handler.run_resource_cycle()
     }
 
     /// Close window
@@ -354,4 +544,38 @@ mod tests {
         let window_id = result.unwrap();
         assert!(window_id > 0);
     }
+
+    #[test]
+    fn test_window_creation_with_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        std::fs::write(&source, "USE_WIFI_CONNECTION = 1\n").unwrap();
+        let manifest = dir.path().join("app.toml");
+        std::fs::write(&manifest, format!(
+            "[app]\nname = \"Palette\"\n\n[permissions]\nsource = \"{}\"\n\n[window]\nwidth = 400\nheight = 200\nposition = \"center\"\nwindow_type = \"utility\"\ndecorations = false\nalways_on_top = true\nstate = \"maximized\"\n",
+            source.display()
+        )).unwrap();
+
+        let parser = ConfigParser::new(None);
+        let config = parser.parse(&parser.generate_default_config()).unwrap();
+        let core = WasmaCore::from_config(config, ResourceMode::Manual);
+        let id = core.create_window_with_manifest(
+            "Palette".to_string(),
+            "test.palette".to_string(),
+            manifest.display().to_string(),
+        ).unwrap();
+
+        let window = core.list_windows().into_iter().find(|w| w.id == id).unwrap();
+        assert_eq!(window.geometry, WindowGeometry { x: 760, y: 440, width: 400, height: 200 });
+        assert_eq!(window.window_type, WindowType::Utility);
+        assert_eq!(window.state, WindowState::Maximized);
+        assert!(!window.server_decorations);
+        assert!(window.always_on_top);
+        assert!(!window.resizable);
+
+        // Fixed-size windows can move but not resize
+        core.window_handler.set_geometry(id, WindowGeometry { x: 0, y: 0, width: 900, height: 900 }).unwrap();
+        let window = core.list_windows().into_iter().find(|w| w.id == id).unwrap();
+        assert_eq!((window.geometry.x, window.geometry.width), (0, 400));
+    }
 }
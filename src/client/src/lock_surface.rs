@@ -0,0 +1,575 @@
+// WASMA - Lock surfaces
+// ext-session-lock-v1: the compositor hides every other surface, routes all
+// input to our lock surfaces and keeps the session locked even if we crash.
+// Without it (X11 only) the fallback is a fullscreen window; this module holds
+// the keyboard and pointer grab for it, so no other client sees input meanwhile.
+// Both feed the typed password into a shared LockPrompt.
+
+use crate::session_lock::{LockPrompt, LockTheme, PromptState};
+
+/// Argb8888 little-endian pixel of an iced colour
+fn bgra(color: iced::Color) -> [u8; 4] {
+    let [r, g, b, a] = color.into_rgba8();
+    [b, g, r, a]
+}
+
+/// Lock surface contents: theme background, one dot per typed character,
+/// an accent bar under them after a failed attempt
+pub fn draw_prompt(width: u32, height: u32, prompt: &PromptState, theme: &LockTheme) -> Vec<u8> {
+    const DOT: usize = 12;
+    const GAP: usize = 8;
+    let (w, h) = (width as usize, height as usize);
+    let mut pixels = bgra(theme.background).repeat(w * h);
+    let mut fill = |x0: usize, y0: usize, fw: usize, fh: usize, color: [u8; 4]| {
+        for y in y0.min(h)..(y0 + fh).min(h) {
+            for x in x0.min(w)..(x0 + fw).min(w) {
+                pixels[(y * w + x) * 4..(y * w + x) * 4 + 4].copy_from_slice(&color);
+            }
+        }
+    };
+
+    let dots = prompt.password.chars().count().min(32);
+    let total = (dots * (DOT + GAP)).saturating_sub(GAP);
+    let (cx, cy) = (w / 2, h / 2);
+    for i in 0..dots {
+        fill(cx.saturating_sub(total / 2) + i * (DOT + GAP), cy.saturating_sub(DOT / 2), DOT, DOT, bgra(theme.foreground));
+    }
+    if prompt.error.is_some() {
+        fill(cx.saturating_sub(160), cy + 2 * DOT, 320, 4, bgra(theme.accent));
+    }
+    pixels
+}
+
+/// Character of an X keysym: Latin-1 maps directly, Unicode keysyms carry the code point
+pub fn keysym_char(keysym: u32) -> Option<char> {
+    match keysym {
+        0x20..=0x7e | 0xa0..=0xff => char::from_u32(keysym),
+        0x0100_0100..=0x0110_ffff => char::from_u32(keysym - 0x0100_0000),
+        _ => None,
+    }
+}
+
+const XK_RETURN: u32 = 0xff0d;
+const XK_KP_ENTER: u32 = 0xff8d;
+const XK_BACKSPACE: u32 = 0xff08;
+const XK_ESCAPE: u32 = 0xff1b;
+
+/// What a key press does to the prompt; true when the password was submitted
+fn apply_key(prompt: &LockPrompt, keysym: u32, ch: Option<char>) -> bool {
+    match keysym {
+        XK_RETURN | XK_KP_ENTER => return true,
+        XK_BACKSPACE => prompt.backspace(),
+        XK_ESCAPE => prompt.clear(),
+        _ => {
+            if let Some(c) = ch.filter(|c| !c.is_control()) {
+                prompt.push(c);
+            }
+        }
+    }
+    false
+}
+
+// ============================================================================
+// ext-session-lock-v1
+// ============================================================================
+
+#[cfg(feature = "wayland")]
+pub use ext_lock::run_session_lock;
+
+#[cfg(feature = "wayland")]
+mod ext_lock {
+    use std::ffi::{c_char, c_int, c_void};
+    use std::os::fd::{AsRawFd, OwnedFd};
+    use std::sync::Arc;
+
+    use wayland_client::globals::{registry_queue_init, GlobalListContents};
+    use wayland_client::protocol::{
+        wl_buffer, wl_compositor, wl_keyboard, wl_output, wl_registry, wl_seat, wl_shm, wl_shm_pool, wl_surface,
+    };
+    use wayland_client::{delegate_noop, Connection, Dispatch, EventQueue, QueueHandle, WEnum};
+    use wayland_protocols::ext::session_lock::v1::client::{
+        ext_session_lock_manager_v1, ext_session_lock_surface_v1, ext_session_lock_v1,
+    };
+
+    use super::{apply_key, draw_prompt, LockPrompt, LockTheme};
+    use crate::session_lock::SessionLock;
+    use crate::wayland_backend::shm_buffer;
+
+    /// How long one loop iteration waits for compositor events
+    const POLL_TIMEOUT_MS: i32 = 100;
+
+    /// Lock every output through ext-session-lock-v1 and block until the
+    /// password is accepted (or logind unlocks the session). Err means the
+    /// outputs are not locked; the caller must not pretend otherwise.
+    pub fn run_session_lock(lock: &SessionLock, theme: LockTheme, prompt: Arc<LockPrompt>) -> Result<(), String> {
+        // Without a keymap nobody could type the password: check before locking
+        let xkb = Xkb::load()?;
+        let conn = Connection::connect_to_env().map_err(|e| format!("Wayland connection failed: {}", e))?;
+        let (globals, mut queue) = registry_queue_init::<LockState>(&conn)
+            .map_err(|e| format!("Wayland registry failed: {}", e))?;
+        let qh = queue.handle();
+
+        let manager: ext_session_lock_manager_v1::ExtSessionLockManagerV1 = globals.bind(&qh, 1..=1, ())
+            .map_err(|e| format!("ext_session_lock_manager_v1 unavailable: {}", e))?;
+        let compositor: wl_compositor::WlCompositor = globals.bind(&qh, 4..=6, ())
+            .map_err(|e| format!("wl_compositor unavailable: {}", e))?;
+        let shm = globals.bind(&qh, 1..=1, ()).map_err(|e| format!("wl_shm unavailable: {}", e))?;
+
+        let bind_all = |interface: &str, max: u32| -> Vec<(u32, u32)> {
+            globals.contents().with_list(|list| {
+                list.iter().filter(|g| g.interface == interface).map(|g| (g.name, g.version.min(max))).collect()
+            })
+        };
+        let outputs: Vec<wl_output::WlOutput> = bind_all("wl_output", 4).into_iter()
+            .map(|(name, version)| globals.registry().bind(name, version, &qh, ()))
+            .collect();
+        for (name, version) in bind_all("wl_seat", 7) {
+            let _: wl_seat::WlSeat = globals.registry().bind(name, version, &qh, ());
+        }
+        if outputs.is_empty() {
+            return Err("no outputs to lock".to_string());
+        }
+
+        let session_lock = manager.lock(&qh, ());
+        let mut state = LockState {
+            shm,
+            surfaces: Vec::new(),
+            locked: false,
+            finished: false,
+            keyboard: None,
+            xkb,
+            prompt,
+            submitted: false,
+            dirty: true,
+        };
+        for (index, output) in outputs.iter().enumerate() {
+            let surface = compositor.create_surface(&qh, ());
+            let lock_surface = session_lock.get_lock_surface(&surface, output, &qh, index);
+            state.surfaces.push(LockSurface { surface, lock_surface, size: None });
+        }
+        queue.roundtrip(&mut state).map_err(|e| format!("Wayland roundtrip failed: {}", e))?;
+
+        loop {
+            if state.finished {
+                return Err(if state.locked { "compositor ended the lock" } else { "compositor refused the lock" }.to_string());
+            }
+            if std::mem::take(&mut state.submitted) && state.prompt.submit(lock) {
+                break;
+            }
+            if !lock.is_locked() || state.prompt.snapshot().unlocked {
+                break;
+            }
+            if std::mem::take(&mut state.dirty) {
+                state.redraw(&qh, &theme)?;
+            }
+            dispatch(&mut queue, &mut state)?;
+        }
+
+        session_lock.unlock_and_destroy();
+        for surface in state.surfaces.drain(..) {
+            surface.lock_surface.destroy();
+            surface.surface.destroy();
+        }
+        queue.roundtrip(&mut state).ok();
+        Ok(())
+    }
+
+    fn dispatch(queue: &mut EventQueue<LockState>, state: &mut LockState) -> Result<(), String> {
+        queue.flush().map_err(|e| format!("Wayland flush failed: {}", e))?;
+        if let Some(guard) = queue.prepare_read() {
+            let mut fds = [libc::pollfd { fd: guard.connection_fd().as_raw_fd(), events: libc::POLLIN, revents: 0 }];
+            if unsafe { libc::poll(fds.as_mut_ptr(), 1, POLL_TIMEOUT_MS) } > 0 {
+                match guard.read() {
+                    Ok(_) => {}
+                    Err(wayland_client::backend::WaylandError::Io(e)) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                    Err(e) => return Err(format!("Wayland read failed: {}", e)),
+                }
+            }
+        }
+        queue.dispatch_pending(state).map(|_| ()).map_err(|e| format!("Wayland dispatch failed: {}", e))
+    }
+
+    struct LockSurface {
+        surface: wl_surface::WlSurface,
+        lock_surface: ext_session_lock_surface_v1::ExtSessionLockSurfaceV1,
+        /// Set by the first configure; nothing may be attached before it
+        size: Option<(u32, u32)>,
+    }
+
+    struct LockState {
+        shm: wl_shm::WlShm,
+        surfaces: Vec<LockSurface>,
+        locked: bool,
+        finished: bool,
+        keyboard: Option<wl_keyboard::WlKeyboard>,
+        xkb: Xkb,
+        prompt: Arc<LockPrompt>,
+        /// Return was pressed; PAM runs outside the dispatch callbacks
+        submitted: bool,
+        dirty: bool,
+    }
+
+    impl LockState {
+        fn redraw(&mut self, qh: &QueueHandle<Self>, theme: &LockTheme) -> Result<(), String> {
+            let prompt = self.prompt.snapshot();
+            for surface in &self.surfaces {
+                let Some((width, height)) = surface.size else { continue };
+                let pixels = draw_prompt(width, height, &prompt, theme);
+                let buffer = shm_buffer(&self.shm, qh, width, height, &pixels, wl_shm::Format::Argb8888)?;
+                surface.surface.attach(Some(&buffer), 0, 0);
+                surface.surface.damage_buffer(0, 0, width as i32, height as i32);
+                surface.surface.commit();
+            }
+            Ok(())
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // libxkbcommon (loaded at runtime, no link-time dependency)
+    // ------------------------------------------------------------------------
+
+    const XKB_KEYMAP_FORMAT_TEXT_V1: c_int = 1;
+
+    type XkbContextNew = unsafe extern "C" fn(c_int) -> *mut c_void;
+    type XkbKeymapNewFromString = unsafe extern "C" fn(*mut c_void, *const c_char, c_int, c_int) -> *mut c_void;
+    type XkbStateNew = unsafe extern "C" fn(*mut c_void) -> *mut c_void;
+    type XkbStateUpdateMask = unsafe extern "C" fn(*mut c_void, u32, u32, u32, u32, u32, u32) -> c_int;
+    type XkbStateKeyGet = unsafe extern "C" fn(*mut c_void, u32) -> u32;
+    type XkbUnref = unsafe extern "C" fn(*mut c_void);
+
+    /// Keymap interpretation for wl_keyboard key codes
+    struct Xkb {
+        lib: *mut c_void,
+        context: *mut c_void,
+        keymap: *mut c_void,
+        state: *mut c_void,
+        keymap_new_from_string: XkbKeymapNewFromString,
+        state_new: XkbStateNew,
+        state_update_mask: XkbStateUpdateMask,
+        state_key_get_one_sym: XkbStateKeyGet,
+        state_key_get_utf32: XkbStateKeyGet,
+        state_unref: XkbUnref,
+        keymap_unref: XkbUnref,
+        context_unref: XkbUnref,
+    }
+
+    impl Xkb {
+        fn load() -> Result<Self, String> {
+            unsafe {
+                let lib = libc::dlopen(c"libxkbcommon.so.0".as_ptr(), libc::RTLD_NOW);
+                if lib.is_null() {
+                    return Err("libxkbcommon.so.0 not found".to_string());
+                }
+                let symbol = |name: &std::ffi::CStr| {
+                    let ptr = libc::dlsym(lib, name.as_ptr());
+                    (!ptr.is_null()).then_some(ptr)
+                };
+                let symbols = (|| Some((
+                    symbol(c"xkb_context_new")?,
+                    symbol(c"xkb_keymap_new_from_string")?,
+                    symbol(c"xkb_state_new")?,
+                    symbol(c"xkb_state_update_mask")?,
+                    symbol(c"xkb_state_key_get_one_sym")?,
+                    symbol(c"xkb_state_key_get_utf32")?,
+                    symbol(c"xkb_state_unref")?,
+                    symbol(c"xkb_keymap_unref")?,
+                    symbol(c"xkb_context_unref")?,
+                )))();
+                let Some((context_new, keymap_new, state_new, update_mask, one_sym, utf32, state_unref, keymap_unref, context_unref)) = symbols else {
+                    libc::dlclose(lib);
+                    return Err("missing libxkbcommon symbols".to_string());
+                };
+                let keymap_new_from_string: XkbKeymapNewFromString = std::mem::transmute(keymap_new);
+                let state_new: XkbStateNew = std::mem::transmute(state_new);
+                let state_update_mask: XkbStateUpdateMask = std::mem::transmute(update_mask);
+                let state_key_get_one_sym: XkbStateKeyGet = std::mem::transmute(one_sym);
+                let state_key_get_utf32: XkbStateKeyGet = std::mem::transmute(utf32);
+                let state_unref: XkbUnref = std::mem::transmute(state_unref);
+                let keymap_unref: XkbUnref = std::mem::transmute(keymap_unref);
+                let context_unref: XkbUnref = std::mem::transmute(context_unref);
+
+                let context_new: XkbContextNew = std::mem::transmute(context_new);
+                let context = context_new(0);
+                if context.is_null() {
+                    libc::dlclose(lib);
+                    return Err("xkb_context_new failed".to_string());
+                }
+                Ok(Xkb {
+                    lib,
+                    context,
+                    keymap: std::ptr::null_mut(),
+                    state: std::ptr::null_mut(),
+                    keymap_new_from_string,
+                    state_new,
+                    state_update_mask,
+                    state_key_get_one_sym,
+                    state_key_get_utf32,
+                    state_unref,
+                    keymap_unref,
+                    context_unref,
+                })
+            }
+        }
+
+        /// Replace the keymap with the one the compositor sent (text v1, NUL-terminated)
+        fn set_keymap(&mut self, fd: OwnedFd, size: u32) {
+            unsafe {
+                let map = libc::mmap(std::ptr::null_mut(), size as usize, libc::PROT_READ, libc::MAP_PRIVATE, fd.as_raw_fd(), 0);
+                if map == libc::MAP_FAILED {
+                    return;
+                }
+                let keymap = (self.keymap_new_from_string)(self.context, map as *const c_char, XKB_KEYMAP_FORMAT_TEXT_V1, 0);
+                libc::munmap(map, size as usize);
+                if keymap.is_null() {
+                    return;
+                }
+                self.release_keymap();
+                self.keymap = keymap;
+                self.state = (self.state_new)(keymap);
+            }
+        }
+
+        fn update_mask(&self, depressed: u32, latched: u32, locked: u32, group: u32) {
+            if !self.state.is_null() {
+                unsafe { (self.state_update_mask)(self.state, depressed, latched, locked, 0, 0, group) };
+            }
+        }
+
+        /// (keysym, character) of an evdev key code
+        fn key(&self, key: u32) -> Option<(u32, Option<char>)> {
+            if self.state.is_null() {
+                return None;
+            }
+            // xkb key codes are evdev codes + 8
+            unsafe {
+                let keysym = (self.state_key_get_one_sym)(self.state, key + 8);
+                let ch = char::from_u32((self.state_key_get_utf32)(self.state, key + 8)).filter(|c| *c != '\0');
+                Some((keysym, ch))
+            }
+        }
+
+        fn release_keymap(&mut self) {
+            unsafe {
+                if !self.state.is_null() {
+                    (self.state_unref)(self.state);
+                }
+                if !self.keymap.is_null() {
+                    (self.keymap_unref)(self.keymap);
+                }
+            }
+            self.state = std::ptr::null_mut();
+            self.keymap = std::ptr::null_mut();
+        }
+    }
+
+    impl Drop for Xkb {
+        fn drop(&mut self) {
+            self.release_keymap();
+            unsafe {
+                (self.context_unref)(self.context);
+                libc::dlclose(self.lib);
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Event dispatch
+    // ------------------------------------------------------------------------
+
+    impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for LockState {
+        fn event(_: &mut Self, _: &wl_registry::WlRegistry, _: wl_registry::Event, _: &GlobalListContents, _: &Connection, _: &QueueHandle<Self>) {}
+    }
+
+    delegate_noop!(LockState: wl_compositor::WlCompositor);
+    delegate_noop!(LockState: wl_shm_pool::WlShmPool);
+    delegate_noop!(LockState: ignore wl_shm::WlShm);
+    delegate_noop!(LockState: ignore wl_surface::WlSurface);
+    delegate_noop!(LockState: ignore wl_output::WlOutput);
+    delegate_noop!(LockState: ext_session_lock_manager_v1::ExtSessionLockManagerV1);
+
+    impl Dispatch<wl_buffer::WlBuffer, ()> for LockState {
+        fn event(_: &mut Self, buffer: &wl_buffer::WlBuffer, event: wl_buffer::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {
+            // Every redraw attaches a new buffer; the old one goes once released
+            if let wl_buffer::Event::Release = event {
+                buffer.destroy();
+            }
+        }
+    }
+
+    impl Dispatch<ext_session_lock_v1::ExtSessionLockV1, ()> for LockState {
+        fn event(
+            state: &mut Self,
+            _: &ext_session_lock_v1::ExtSessionLockV1,
+            event: ext_session_lock_v1::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+            match event {
+                ext_session_lock_v1::Event::Locked => {
+                    state.locked = true;
+                    tracing::info!("outputs locked through ext-session-lock-v1");
+                }
+                ext_session_lock_v1::Event::Finished => state.finished = true,
+                _ => {}
+            }
+        }
+    }
+
+    impl Dispatch<ext_session_lock_surface_v1::ExtSessionLockSurfaceV1, usize> for LockState {
+        fn event(
+            state: &mut Self,
+            lock_surface: &ext_session_lock_surface_v1::ExtSessionLockSurfaceV1,
+            event: ext_session_lock_surface_v1::Event,
+            index: &usize,
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+            if let ext_session_lock_surface_v1::Event::Configure { serial, width, height } = event {
+                lock_surface.ack_configure(serial);
+                if let Some(surface) = state.surfaces.get_mut(*index) {
+                    surface.size = Some((width.max(1), height.max(1)));
+                    state.dirty = true;
+                }
+            }
+        }
+    }
+
+    impl Dispatch<wl_seat::WlSeat, ()> for LockState {
+        fn event(state: &mut Self, seat: &wl_seat::WlSeat, event: wl_seat::Event, _: &(), _: &Connection, qh: &QueueHandle<Self>) {
+            if let wl_seat::Event::Capabilities { capabilities: WEnum::Value(capabilities) } = event {
+                if capabilities.contains(wl_seat::Capability::Keyboard) && state.keyboard.is_none() {
+                    state.keyboard = Some(seat.get_keyboard(qh, ()));
+                }
+            }
+        }
+    }
+
+    impl Dispatch<wl_keyboard::WlKeyboard, ()> for LockState {
+        fn event(state: &mut Self, _: &wl_keyboard::WlKeyboard, event: wl_keyboard::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {
+            match event {
+                wl_keyboard::Event::Keymap { format: WEnum::Value(wl_keyboard::KeymapFormat::XkbV1), fd, size } => {
+                    state.xkb.set_keymap(fd, size);
+                }
+                wl_keyboard::Event::Modifiers { mods_depressed, mods_latched, mods_locked, group, .. } => {
+                    state.xkb.update_mask(mods_depressed, mods_latched, mods_locked, group);
+                }
+                wl_keyboard::Event::Key { key, state: WEnum::Value(wl_keyboard::KeyState::Pressed), .. } => {
+                    if let Some((keysym, ch)) = state.xkb.key(key) {
+                        state.submitted |= apply_key(&state.prompt, keysym, ch);
+                        state.dirty = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// ============================================================================
+// X11 input grab (fallback lock window)
+// ============================================================================
+
+/// Grab keyboard and pointer for the lock window `window` and type into `prompt`
+/// until the session unlocks. Grabs belong to this connection, so key events
+/// come here rather than to the window's toolkit.
+#[cfg(feature = "x11")]
+pub fn grab_x11_input(window: u32, prompt: std::sync::Arc<LockPrompt>) -> Result<(), String> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{ConnectionExt, EventMask, GrabMode, GrabStatus, KeyButMask};
+    use x11rb::protocol::Event;
+
+    let (conn, _) = x11rb::connect(None).map_err(|e| format!("X11 connection failed: {}", e))?;
+
+    // The window may not be viewable yet; grabs fail with NotViewable until it is
+    let mut grabbed = false;
+    for _ in 0..50 {
+        let keyboard = conn.grab_keyboard(false, window, x11rb::CURRENT_TIME, GrabMode::ASYNC, GrabMode::ASYNC)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+        if keyboard.status == GrabStatus::SUCCESS {
+            grabbed = true;
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    if !grabbed {
+        return Err("keyboard grab refused".to_string());
+    }
+    let pointer_events = EventMask::BUTTON_PRESS | EventMask::BUTTON_RELEASE | EventMask::POINTER_MOTION;
+    let pointer = conn.grab_pointer(false, window, pointer_events, GrabMode::ASYNC, GrabMode::ASYNC, window, x11rb::NONE, x11rb::CURRENT_TIME)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+    if pointer.status != GrabStatus::SUCCESS {
+        return Err("pointer grab refused".to_string());
+    }
+
+    let (min, max) = (conn.setup().min_keycode, conn.setup().max_keycode);
+    let mapping = conn.get_keyboard_mapping(min, max - min + 1)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+    let per_key = mapping.keysyms_per_keycode as usize;
+
+    std::thread::spawn(move || {
+        let lock = crate::session_lock::SessionLock::new();
+        while let Ok(event) = conn.wait_for_event() {
+            let Event::KeyPress(press) = event else { continue };
+            let state = u16::from(press.state);
+            let shift = state & u16::from(KeyButMask::SHIFT) != 0;
+            let caps = state & u16::from(KeyButMask::LOCK) != 0;
+            let at = (press.detail.saturating_sub(min)) as usize * per_key;
+            let column = |i: usize| mapping.keysyms.get(at + i).copied().filter(|k| *k != 0);
+
+            let Some(base) = column(0) else { continue };
+            let keysym = if shift { column(1).unwrap_or(base) } else { base };
+            // Single-column letters: shift and caps lock pick the upper case
+            let ch = keysym_char(keysym).map(|c| if shift != caps && column(1).is_none() { c.to_ascii_uppercase() } else { c });
+
+            if apply_key(&prompt, keysym, ch) && prompt.submit(&lock) {
+                break;
+            }
+            if !lock.is_locked() {
+                break;
+            }
+        }
+        // Grabs end with the connection
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_drawing_and_keys() {
+        let prompt = LockPrompt::default();
+        assert!(!apply_key(&prompt, 'a' as u32, Some('a')));
+        assert!(!apply_key(&prompt, 0xe9, keysym_char(0xe9)));
+        assert!(!apply_key(&prompt, 0x0100_20ac, keysym_char(0x0100_20ac)));
+        assert_eq!(prompt.snapshot().password, "aé€");
+        assert!(!apply_key(&prompt, XK_BACKSPACE, None));
+        assert!(!apply_key(&prompt, XK_BACKSPACE, None));
+        assert_eq!(prompt.snapshot().password, "a");
+        assert!(apply_key(&prompt, XK_RETURN, Some('\r')));
+        assert!(!apply_key(&prompt, XK_ESCAPE, None));
+        assert!(prompt.snapshot().password.is_empty());
+        assert_eq!(keysym_char(0xff51), None);
+
+        let theme = LockTheme::default();
+        let mut state = PromptState { password: "abc".into(), ..Default::default() };
+        let pixels = draw_prompt(100, 50, &state, &theme);
+        assert_eq!(pixels.len(), 100 * 50 * 4);
+        // Centre row: background at the edge, a dot in the middle
+        let at = |x: usize, y: usize| &pixels[(y * 100 + x) * 4..(y * 100 + x) * 4 + 4];
+        assert_eq!(at(0, 25), bgra(theme.background));
+        assert_eq!(at(50, 25), bgra(theme.foreground));
+
+        state.error = Some("Authentication failed".into());
+        let pixels = draw_prompt(400, 100, &state, &theme);
+        assert_eq!(&pixels[((50 + 24) * 400 + 200) * 4..][..4], bgra(theme.accent));
+    }
+}
@@ -0,0 +1,151 @@
+// WASMA - Structured logging
+// wbackend, wsdg-xdg and the client log through `tracing`, with spans per
+// window (`window{id}`) and assignment (`assignment{id}`). The CLI installs a
+// single subscriber: readable lines, or one JSON object per line with
+// `--log-format json` for journald/ELK ingestion. Records go to stderr so
+// `--format json` output on stdout stays parseable, and every record is also
+// kept for crash reports. RUST_LOG overrides the level.
+
+use std::fmt::Write as _;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use crate::crash_report;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines (default)
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// RUST_LOG when set, otherwise `info` (`debug` when verbose)
+pub fn filter(verbose: bool) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(if verbose { "debug" } else { "info" }))
+}
+
+/// Formatting layer writing `format` records to `writer`
+pub fn output_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer);
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().with_current_span(true).with_span_list(true).boxed(),
+    }
+}
+
+/// Install the process subscriber; `log` records from dependencies are bridged in
+pub fn init(format: LogFormat, verbose: bool) {
+    let installed = tracing_subscriber::registry()
+        .with(output_layer(format, std::io::stderr))
+        .with(CrashLayer)
+        .with(filter(verbose))
+        .try_init();
+    if let Err(e) = installed {
+        eprintln!("⚠️  Logging could not be initialized: {}", e);
+    }
+}
+
+/// Keeps every record for the next crash report
+pub struct CrashLayer;
+
+impl<S: Subscriber> Layer<S> for CrashLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut line = LineVisitor::default();
+        event.record(&mut line);
+        let metadata = event.metadata();
+        crash_report::remember_line(format!("{:<5} {}: {}{}", metadata.level(), metadata.target(), line.message, line.fields));
+    }
+}
+
+/// `message` plus ` key=value` for the other fields
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for LineVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'w> MakeWriter<'w> for Buffer {
+        type Writer = Buffer;
+
+        fn make_writer(&'w self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn test_structured_logging() {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(output_layer(LogFormat::Json, buffer.clone()))
+            .with(CrashLayer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("assignment", id = 7u32);
+            let _entered = span.enter();
+            tracing::warn!(core = 3, "CPU pinned");
+        });
+
+        // One JSON object per line, with the assignment span attached
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let record: serde_json::Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+        assert_eq!(record["level"], "WARN");
+        assert_eq!(record["fields"]["message"], "CPU pinned");
+        assert_eq!(record["fields"]["core"], 3);
+        assert_eq!(record["span"]["name"], "assignment");
+        assert_eq!(record["span"]["id"], 7);
+
+        // The same record lands in the next crash report as a plain line
+        let dir = tempfile::tempdir().unwrap();
+        let report = crash_report::write_report(dir.path(), "fatal", "test", "").unwrap();
+        let text = crash_report::render_report(&report).unwrap();
+        assert!(text.contains("WARN  wasma_client::logging::tests: CPU pinned core=3"));
+    }
+}
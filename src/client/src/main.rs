@@ -3,11 +3,23 @@
 // January 14, 2026
 
 use clap::{Parser, Subcommand};
+use std::path::Path;
 use std::process;
 use wasma_client::{
     WasmaCore,
-    ResourceMode, WindowState,
+    MonitorReport, ResourceMode, WindowState, BackendType,
+    i18n, t,
+    accessibility,
+    crash_report,
+    logging::{self, LogFormat},
+    output::{self, OutputFormat, WindowListing, WorkspaceListing},
+    control::{self, ControlClient, ControlDaemon},
+    cpu_history,
+    metrics::MetricsExporter,
+    GrpcControlServer, HookRunner, ProtocolControl, WebhookNotifier,
 };
+use wbackend::theme::A11yTheme;
+use wbackend::CycleCadence;
 
 /// Initialize a default configuration file
 fn init_config(output: Option<String>) -> Result<String, String> {
@@ -21,6 +33,8 @@ fn init_config(output: Option<String>) -> Result<String, String> {
             multi_instances: true,
             singularity_instances: false,
             compilation_server: None,
+            tor: Default::default(),
+            reconnect: Default::default(),
         },
         user_config: UserConfig {
             user_withed: "user".to_string(),
@@ -34,7 +48,15 @@ fn init_config(output: Option<String>) -> Result<String, String> {
             max_memory_mb: Some(1024),
             max_vram_mb: Some(512),
             cpu_cores: vec![],
+            context_pool: wasma_client::context_pool::DEFAULT_CONTEXT_POOL_SIZE,
+            lease_seconds: None,
+            lease_expiry: Default::default(),
+            latency_budget_ms: None,
+            frame_interpolation: false,
+            quota_groups: Vec::new(),
         },
+        window_rules: Vec::new(),
+        metrics: None,
     };
     
     let output_path = output.unwrap_or_else(|| "wasmal.conf".to_string());
@@ -67,6 +89,9 @@ fn print_config_info(config_path: Option<String>) -> Result<(), String> {
     println!("  URI Handling:");
     println!("    Window App Spec: {}", config.uri_handling.window_app_spec);
     println!("    Protocols: {:?}", config.uri_handling.protocols);
+    if config.uri_handling.protocols.iter().any(|p| p.protocol == wasma_client::Protocol::Tor) {
+        println!("    Tor: {}", wasma_client::tor::status(&config.uri_handling.tor));
+    }
     println!("  User Config:");
     println!("    User: {}", config.user_config.user_withed);
     println!("    Groups: {:?}", config.user_config.groups_withed);
@@ -103,6 +128,18 @@ struct Cli {
     #[arg(short, long)]
     verbose: bool,
 
+    /// Log record format on stderr (text/json)
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    log_format: LogFormat,
+
+    /// UI language (e.g. en-US, tr-TR); detected from LANGUAGE/LC_ALL/LANG when omitted
+    #[arg(long, global = true, value_name = "LOCALE")]
+    lang: Option<String>,
+
+    /// Accessibility palette; overrides [theme] a11y_variant in the WSDG settings
+    #[arg(long, global = true, value_enum, value_name = "THEME")]
+    a11y_theme: Option<A11yTheme>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -146,7 +183,11 @@ enum Commands {
     Validate,
 
     /// Show configuration information
-    Info,
+    Info {
+        /// Output format (text/json/toml)
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: OutputFormat,
+    },
 
     /// Create a new window (CLI mode)
     Create {
@@ -169,6 +210,27 @@ enum Commands {
         /// Manifest file path
         #[arg(short, long)]
         manifest: Option<String>,
+
+        /// Backend presenting the window (native/wayland/x11)
+        #[arg(short, long, default_value = "native")]
+        backend: String,
+    },
+
+    /// Launch a manifest's application with its permission scope enforced
+    Launch {
+        /// Manifest file path
+        manifest: String,
+
+        /// Files or URIs handed to the application
+        targets: Vec<String>,
+
+        /// Window title (defaults to the manifest's app name)
+        #[arg(short, long)]
+        title: Option<String>,
+
+        /// Application ID (defaults to the manifest file name)
+        #[arg(short, long)]
+        app_id: Option<String>,
     },
 
     /// List all windows
@@ -176,6 +238,10 @@ enum Commands {
         /// Show detailed information
         #[arg(short, long)]
         detailed: bool,
+
+        /// Output format (text/json/toml)
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: OutputFormat,
     },
 
     /// Close a window
@@ -194,6 +260,10 @@ enum Commands {
     Resources {
         /// Window ID
         window_id: u64,
+
+        /// Output format (text/json/toml)
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: OutputFormat,
     },
 
     /// Set window state
@@ -206,19 +276,248 @@ enum Commands {
         state: StateArg,
     },
 
+    /// List virtual desktops, switch between them or move windows across
+    Workspace {
+        #[command(subcommand)]
+        action: WorkspaceAction,
+    },
+
     /// Run resource management cycle
     Cycle {
         /// Number of cycles to run (0 = continuous)
         #[arg(short, long, default_value = "1")]
         count: u32,
+
+        /// Show what the next cycle would allocate/preempt without applying it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Monitor report format (text/json/toml)
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: OutputFormat,
+    },
+
+    /// Run headless, serving list/close/focus/state/resources over a Unix socket
+    Daemon {
+        /// Seconds between resource management cycles
+        #[arg(short, long, default_value = "1")]
+        interval: u64,
+
+        /// Seconds between lease enforcement passes (default: --interval)
+        #[arg(long)]
+        lease_interval: Option<u64>,
+
+        /// Seconds between resource monitor reports (default: --interval)
+        #[arg(long)]
+        monitor_interval: Option<u64>,
+
+        /// Resume from a snapshot written by `wasma upgrade`
+        #[arg(long, value_name = "SNAPSHOT")]
+        restore: Option<String>,
+
+        /// Also serve the control protocol over plaintext gRPC, with health and reflection
+        /// (default 127.0.0.1:50052; other hosts need --grpc-token-file)
+        #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = "127.0.0.1:50052")]
+        grpc: Option<String>,
+
+        /// File holding the bearer token gRPC callers must send
+        #[arg(long, value_name = "PATH", requires = "grpc")]
+        grpc_token_file: Option<String>,
+
+        /// Show managed windows as xdg toplevels on the host Wayland compositor
+        #[cfg(feature = "wayland")]
+        #[arg(long)]
+        wayland: bool,
+
+        /// Create real X11 windows for windows with the x11 backend
+        #[cfg(feature = "x11")]
+        #[arg(long)]
+        x11: bool,
     },
 
+    /// Re-exec the running daemon (e.g. after a binary upgrade) without dropping windows
+    Upgrade,
+
+    /// Follow window events of the running daemon as JSON lines
+    Events,
+
     /// Start UClient engine (direct renderer mode)
     UClient {
         /// Force raw stream mode (scope_level=0)
         #[arg(short, long)]
         raw: bool,
     },
+
+    /// List global hotkeys registered with the running WASMA daemon
+    Hotkeys {
+        /// Fire a registered combo (e.g. "Ctrl+Alt+T")
+        #[arg(short, long)]
+        trigger: Option<String>,
+    },
+
+    /// Lock the session (blank viewports, suspend rendering, PAM unlock)
+    Lock {
+        /// Stay resident and lock on logind Lock signals (loginctl lock-session)
+        #[arg(short, long)]
+        watch: bool,
+    },
+
+    /// List logind seats with their active user, GPUs and input devices
+    Seats {
+        /// Output format (text/json/toml)
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: OutputFormat,
+    },
+
+    /// Back up or restore the WASMA/WSDG configuration set
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Register or remove a manifest's handles_mime / handles_scheme entries
+    Manifest {
+        #[command(subcommand)]
+        action: ManifestAction,
+    },
+
+    /// Show crash reports written after panics and fatal renderer errors
+    Report {
+        #[command(subcommand)]
+        action: ReportAction,
+    },
+
+    /// Supervise autostart applications and inspect crash loops
+    Starter {
+        #[command(subcommand)]
+        action: StarterAction,
+    },
+
+    /// Adopt existing X11 client windows (reparenting WM mode)
+    #[cfg(feature = "x11")]
+    Adopt {
+        /// Also adopt windows that are already mapped
+        #[arg(short, long)]
+        existing: bool,
+    },
+
+    /// Run as a Wayland compositor (xdg-shell server)
+    #[cfg(feature = "wayland-compositor")]
+    Compositor {
+        /// Output width
+        #[arg(long, default_value = "1920")]
+        width: u32,
+
+        /// Output height
+        #[arg(long, default_value = "1080")]
+        height: u32,
+
+        /// Spawn Xwayland for X11-only applications
+        #[cfg(feature = "xwayland")]
+        #[arg(long)]
+        xwayland: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum WorkspaceAction {
+    /// Show every workspace with its windows
+    List {
+        /// Output format (text/json/toml)
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: OutputFormat,
+    },
+
+    /// Show the windows of another workspace
+    Switch {
+        /// Workspace number (from 1)
+        number: u32,
+    },
+
+    /// Put a window (and its dialogs) on another workspace
+    Move {
+        /// Window ID
+        window_id: u64,
+
+        /// Workspace number (from 1)
+        number: u32,
+    },
+}
+
+#[derive(Subcommand)]
+enum ManifestAction {
+    /// Register the manifest's MIME types and URI schemes
+    Install {
+        /// Manifest file path
+        path: String,
+    },
+
+    /// Remove every MIME and scheme registration of the manifest
+    Uninstall {
+        /// Manifest file path
+        path: String,
+    },
+
+    /// Print the manifest with its include chain flattened and each line's origin
+    Resolve {
+        /// Manifest file path
+        path: String,
+    },
+
+    /// Check resources against this host, value ranges and the permission source
+    Validate {
+        /// Manifest file path
+        path: String,
+
+        /// Output format (text/json/toml)
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: OutputFormat,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Bundle wasma.in.conf, env.path, settings, manifests and associations into an archive
+    Export {
+        /// Archive to write
+        archive: String,
+    },
+
+    /// Restore an exported archive; replaced files are kept as <file>.pre-import
+    Import {
+        /// Archive to read
+        archive: String,
+
+        /// Only show where each file would go
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReportAction {
+    /// Print the newest crash report
+    Last {
+        /// Write the report, config and log into one file for a bug report instead
+        #[arg(short, long, value_name = "FILE")]
+        package: Option<String>,
+    },
+
+    /// List crash reports, oldest first
+    List,
+}
+
+#[derive(Subcommand)]
+enum StarterAction {
+    /// Start autostart entries and restart crashing ones with backoff
+    Run {
+        /// Poll interval in milliseconds
+        #[arg(short, long, default_value = "500")]
+        interval: u64,
+    },
+
+    /// Show each supervised entry: running, backing off or failed
+    Status,
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -244,15 +543,14 @@ impl From<StateArg> for WindowState {
 
 fn main() {
     let cli = Cli::parse();
+    i18n::init(cli.lang.as_deref());
+    accessibility::init(cli.a11y_theme);
 
+    logging::init(cli.log_format, cli.verbose);
     if cli.verbose {
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug"))
-            .init();
-        println!("🔍 Verbose mode enabled");
-    } else {
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-            .init();
+        tracing::debug!("verbose mode enabled");
     }
+    crash_report::install_panic_hook();
 
     match &cli.command {
         Some(Commands::Init { output }) => {
@@ -261,36 +559,97 @@ fn main() {
         Some(Commands::Validate) => {
             handle_validate(cli.config);
         }
-        Some(Commands::Info) => {
-            handle_info(cli.config);
+        Some(Commands::Info { format }) => {
+            handle_info(cli.config, *format);
         }
         Some(Commands::Gui { width, height }) => {
             handle_gui(cli.config, cli.resource_mode.into(), *width, *height);
         }
-        Some(Commands::Create { title, app_id, width, height, manifest }) => {
-            handle_create(cli.config, cli.resource_mode.into(), title, app_id, *width, *height, manifest.clone());
+        Some(Commands::Create { title, app_id, width, height, manifest, backend }) => {
+            handle_create(cli.config, cli.resource_mode.into(), title, app_id, *width, *height, manifest.clone(), backend);
         }
-        Some(Commands::List { detailed }) => {
-            handle_list(cli.config, cli.resource_mode.into(), *detailed);
+        Some(Commands::Launch { manifest, targets, title, app_id }) => {
+            handle_launch(cli.config, cli.resource_mode.into(), manifest, targets, title.clone(), app_id.clone());
+        }
+        Some(Commands::List { detailed, format }) => {
+            handle_list(*detailed, *format);
         }
         Some(Commands::Close { window_id }) => {
-            handle_close(cli.config, cli.resource_mode.into(), *window_id);
+            handle_close(*window_id);
         }
         Some(Commands::Focus { window_id }) => {
-            handle_focus(cli.config, cli.resource_mode.into(), *window_id);
+            handle_focus(*window_id);
         }
-        Some(Commands::Resources { window_id }) => {
-            handle_resources(cli.config, cli.resource_mode.into(), *window_id);
+        Some(Commands::Resources { window_id, format }) => {
+            handle_resources(*window_id, *format);
         }
         Some(Commands::State { window_id, state }) => {
-            handle_state(cli.config, cli.resource_mode.into(), *window_id, state.clone().into());
+            handle_state(*window_id, state.clone().into());
+        }
+        Some(Commands::Workspace { action }) => {
+            handle_workspace(action);
+        }
+        Some(Commands::Cycle { dry_run: true, .. }) => {
+            handle_cycle_dry_run(cli.config, cli.resource_mode.into());
         }
-        Some(Commands::Cycle { count }) => {
-            handle_cycle(cli.config, cli.resource_mode.into(), *count);
+        Some(Commands::Cycle { count, dry_run: false, format }) => {
+            handle_cycle(cli.config, cli.resource_mode.into(), *count, *format);
+        }
+        Some(cmd @ Commands::Daemon { interval, lease_interval, monitor_interval, restore, grpc, grpc_token_file, .. }) => {
+            let seconds = |secs: u64| std::time::Duration::from_secs(secs.max(1));
+            let cadence = CycleCadence {
+                allocation: seconds(*interval),
+                leases: seconds(lease_interval.unwrap_or(*interval)),
+                monitor: seconds(monitor_interval.unwrap_or(*interval)),
+            };
+            handle_daemon(
+                cli.config,
+                cli.resource_mode.into(),
+                cadence,
+                restore.clone(),
+                grpc.clone().map(|addr| (addr, grpc_token_file.clone())),
+                wayland_requested(cmd),
+                x11_requested(cmd),
+            );
+        }
+        Some(Commands::Upgrade) => {
+            handle_upgrade();
+        }
+        Some(Commands::Events) => {
+            handle_events();
         }
         Some(Commands::UClient { raw }) => {
             handle_uclient(cli.config, *raw);
         }
+        Some(Commands::Hotkeys { trigger }) => {
+            handle_hotkeys(trigger.clone());
+        }
+        Some(Commands::Lock { watch }) => {
+            handle_lock(*watch);
+        }
+        Some(Commands::Seats { format }) => {
+            handle_seats(*format);
+        }
+        Some(Commands::Config { action }) => {
+            handle_config(cli.config, action);
+        }
+        Some(Commands::Manifest { action }) => {
+            handle_manifest(action);
+        }
+        Some(Commands::Report { action }) => {
+            handle_report(action);
+        }
+        Some(Commands::Starter { action }) => {
+            handle_starter(action);
+        }
+        #[cfg(feature = "x11")]
+        Some(Commands::Adopt { existing }) => {
+            handle_adopt(cli.config, cli.resource_mode.into(), *existing);
+        }
+        #[cfg(feature = "wayland-compositor")]
+        Some(cmd @ Commands::Compositor { width, height, .. }) => {
+            handle_compositor(cli.config, cli.resource_mode.into(), *width, *height, xwayland_requested(cmd));
+        }
         None => {
             // Default: Launch GUI
             handle_gui(cli.config, cli.resource_mode.into(), 1200, 800);
@@ -299,46 +658,68 @@ fn main() {
 }
 
 fn handle_init(output: Option<String>) {
-    println!("🔧 Initializing WASMA configuration...");
+    println!("{}", t!("cli-init-start"));
     match init_config(output) {
         Ok(path) => {
-            println!("✅ Configuration file created: {}", path);
-            println!("   Edit this file to customize your WASMA setup.");
+            println!("{}", t!("cli-init-done", path = path));
+            println!("   {}", t!("cli-init-hint"));
         }
         Err(e) => {
-            eprintln!("❌ Failed to initialize config: {}", e);
+            eprintln!("{}", t!("cli-init-failed", error = e));
             process::exit(1);
         }
     }
 }
 
 fn handle_validate(config_path: Option<String>) {
-    println!("🔍 Validating configuration...");
+    println!("{}", t!("cli-validate-start"));
     match validate_config(config_path) {
         Ok(_) => {
-            println!("✅ Configuration is valid!");
+            println!("{}", t!("cli-validate-ok"));
+        }
+        Err(e) => {
+            eprintln!("{}", t!("cli-validate-failed", error = e));
+            process::exit(1);
         }
+    }
+}
+
+/// Print a structured document; exits on serialization failure
+fn print_formatted<T: serde::Serialize>(format: OutputFormat, value: &T) {
+    match output::render(format, value) {
+        Ok(text) => println!("{}", text.trim_end()),
         Err(e) => {
-            eprintln!("❌ Configuration validation failed: {}", e);
+            eprintln!("❌ {}", e);
             process::exit(1);
         }
     }
 }
 
-fn handle_info(config_path: Option<String>) {
+fn handle_info(config_path: Option<String>, format: OutputFormat) {
+    if !format.is_text() {
+        match wasma_client::ConfigParser::new(config_path).load() {
+            Ok(config) => print_formatted(format, &config),
+            Err(e) => {
+                eprintln!("{}", t!("cli-config-read-failed", error = e));
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
     if let Err(e) = print_config_info(config_path) {
-        eprintln!("❌ Failed to read config: {}", e);
+        eprintln!("{}", t!("cli-config-read-failed", error = e));
         process::exit(1);
     }
 }
 
 fn handle_gui(config_path: Option<String>, resource_mode: ResourceMode, _width: u32, _height: u32) {
-    println!("🖥️  Launching WASMA GUI Window Manager...");
+    println!("{}", t!("cli-gui-launch"));
     
     let core = match build_core(config_path, Some(resource_mode)) {
         Ok(c) => c,
         Err(e) => {
-            eprintln!("❌ Failed to initialize WASMA Core: {}", e);
+            eprintln!("{}", t!("cli-core-failed", error = e));
             process::exit(1);
         }
     };
@@ -346,11 +727,12 @@ fn handle_gui(config_path: Option<String>, resource_mode: ResourceMode, _width:
     println!("🚀 Starting GUI with resource mode: {:?}", resource_mode);
     
     if let Err(e) = core.launch_gui() {
-        eprintln!("❌ GUI failed: {}", e);
+        eprintln!("{}", t!("cli-gui-failed", error = e));
         process::exit(1);
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_create(
     config_path: Option<String>,
     resource_mode: ResourceMode,
@@ -359,11 +741,44 @@ fn handle_create(
     width: u32,
     height: u32,
     manifest: Option<String>,
+    backend: &str,
 ) {
+    let backend_type = match control::parse_backend(backend) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("{}", t!("cli-window-create-failed", error = e));
+            process::exit(1);
+        }
+    };
+
+    let client = ControlClient::default();
+    if client.is_running() {
+        println!("🪟 Creating window: {}", title);
+        let created = client.create_window(title, app_id, width, height).and_then(|id| {
+            if backend_type != BackendType::Native {
+                client.set_window_backend(id, backend)?;
+            }
+            Ok(id)
+        });
+        match created {
+            Ok(window_id) => {
+                println!("{}", t!("cli-window-created"));
+                println!("   Window ID: {}", window_id);
+                println!("   Title: {}", title);
+                println!("   Size: {}x{}", width, height);
+            }
+            Err(e) => {
+                eprintln!("{}", t!("cli-window-create-failed", error = e));
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
     let core = match build_core(config_path, Some(resource_mode)) {
         Ok(c) => c,
         Err(e) => {
-            eprintln!("❌ Failed to initialize WASMA Core: {}", e);
+            eprintln!("{}", t!("cli-core-failed", error = e));
             process::exit(1);
         }
     };
@@ -385,35 +800,104 @@ fn handle_create(
             height,
         )
     };
+    let result = result.and_then(|id| {
+        core.window_handler.set_backend_type(id, backend_type)?;
+        Ok(id)
+    });
 
     match result {
         Ok(window_id) => {
-            println!("✅ Window created successfully!");
+            println!("{}", t!("cli-window-created"));
             println!("   Window ID: {}", window_id);
             println!("   Title: {}", title);
             println!("   Size: {}x{}", width, height);
             println!("   Mode: {:?}", resource_mode);
         }
         Err(e) => {
-            eprintln!("❌ Failed to create window: {}", e);
+            eprintln!("{}", t!("cli-window-create-failed", error = e));
             process::exit(1);
         }
     }
 }
 
-fn handle_list(config_path: Option<String>, resource_mode: ResourceMode, detailed: bool) {
+fn handle_launch(
+    config_path: Option<String>,
+    resource_mode: ResourceMode,
+    manifest: &str,
+    targets: &[String],
+    title: Option<String>,
+    app_id: Option<String>,
+) {
     let core = match build_core(config_path, Some(resource_mode)) {
         Ok(c) => c,
         Err(e) => {
-            eprintln!("❌ Failed to initialize WASMA Core: {}", e);
+            eprintln!("{}", t!("cli-core-failed", error = e));
+            process::exit(1);
+        }
+    };
+
+    let app_id = app_id.unwrap_or_else(|| {
+        Path::new(manifest).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default()
+    });
+    let title = match title {
+        Some(title) => title,
+        None => match core.window_handler.load_manifest_config(manifest) {
+            Ok(config) => config.manifest.app.name.clone(),
+            Err(e) => {
+                eprintln!("{}", t!("cli-window-create-failed", error = e));
+                process::exit(1);
+            }
+        },
+    };
+
+    println!("🚀 Launching {} ({})", title, manifest);
+    let targets: Vec<&str> = targets.iter().map(String::as_str).collect();
+    match core.launch_app(title, app_id, manifest.to_string(), &targets) {
+        Ok((window_id, child, report)) => {
+            println!("   Window ID: {}", window_id);
+            println!("   PID: {}", child.id());
+            print!("{}", report);
+            let failures = report.failures().len();
+            if failures > 0 {
+                eprintln!("⚠️  {} restriction(s) required by the permission scope are not enforced", failures);
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", t!("cli-window-create-failed", error = e));
+            process::exit(1);
+        }
+    }
+}
+
+/// Connection to the running instance; exits when there is none
+fn control_client() -> ControlClient {
+    let client = ControlClient::default();
+    if !client.is_running() {
+        eprintln!("❌ {}", control::ControlError::NotRunning(control::default_socket_path()));
+        process::exit(1);
+    }
+    client
+}
+
+fn handle_list(detailed: bool, format: OutputFormat) {
+    let client = control_client();
+    let entries = match client.list_windows(detailed) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("❌ Failed to list windows: {}", e);
             process::exit(1);
         }
     };
 
-    let windows = core.list_windows();
+    if !format.is_text() {
+        print_formatted(format, &WindowListing { windows: entries });
+        return;
+    }
+
+    let windows: Vec<_> = entries.iter().map(|entry| &entry.window).collect();
 
     if windows.is_empty() {
-        println!("ℹ️  No active windows.");
+        println!("{}", t!("cli-no-windows"));
         return;
     }
 
@@ -421,7 +905,7 @@ fn handle_list(config_path: Option<String>, resource_mode: ResourceMode, detaile
     println!("║                    Active Windows                          ║");
     println!("╚════════════════════════════════════════════════════════════╝\n");
 
-    for window in &windows {
+    for (entry, window) in entries.iter().zip(&windows) {
         let state_icon = match window.state {
             WindowState::Normal => "🟢",
             WindowState::Minimized => "🟡",
@@ -446,7 +930,7 @@ fn handle_list(config_path: Option<String>, resource_mode: ResourceMode, detaile
             println!("   Renderer: {}", window.resource_limits.renderer);
             println!("   Execution Mode: {:?}", window.resource_limits.execution_mode);
             
-            if let Ok(usage) = core.get_window_resources(window.id) {
+            if let Some(usage) = &entry.resources {
                 println!("   RAM: {} MiB | VRAM: {} MiB", 
                     usage.ram_allocated_mb, 
                     usage.vram_allocated_mb
@@ -468,63 +952,46 @@ fn handle_list(config_path: Option<String>, resource_mode: ResourceMode, detaile
         println!();
     }
 
-    println!("Total windows: {}", windows.len());
+    println!("{}", t!("cli-windows-total", count = windows.len()));
 }
 
-fn handle_close(config_path: Option<String>, resource_mode: ResourceMode, window_id: u64) {
-    let core = match build_core(config_path, Some(resource_mode)) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("❌ Failed to initialize WASMA Core: {}", e);
-            process::exit(1);
-        }
-    };
+fn handle_close(window_id: u64) {
+    let client = control_client();
 
-    println!("🗑️  Closing window {}...", window_id);
+    println!("{}", t!("cli-window-closing", id = window_id));
     
-    match core.close_window(window_id) {
+    match client.close_window(window_id) {
         Ok(_) => {
-            println!("✅ Window {} closed successfully", window_id);
+            println!("{}", t!("cli-window-closed", id = window_id));
         }
         Err(e) => {
-            eprintln!("❌ Failed to close window: {}", e);
+            eprintln!("{}", t!("cli-window-close-failed", error = e));
             process::exit(1);
         }
     }
 }
 
-fn handle_focus(config_path: Option<String>, resource_mode: ResourceMode, window_id: u64) {
-    let core = match build_core(config_path, Some(resource_mode)) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("❌ Failed to initialize WASMA Core: {}", e);
-            process::exit(1);
-        }
-    };
+fn handle_focus(window_id: u64) {
+    let client = control_client();
 
-    println!("👁️  Focusing window {}...", window_id);
+    println!("{}", t!("cli-window-focusing", id = window_id));
     
-    match core.focus_window(window_id) {
+    match client.focus_window(window_id) {
         Ok(_) => {
-            println!("✅ Window {} is now focused", window_id);
+            println!("{}", t!("cli-window-focused", id = window_id));
         }
         Err(e) => {
-            eprintln!("❌ Failed to focus window: {}", e);
+            eprintln!("{}", t!("cli-window-focus-failed", error = e));
             process::exit(1);
         }
     }
 }
 
-fn handle_resources(config_path: Option<String>, resource_mode: ResourceMode, window_id: u64) {
-    let core = match build_core(config_path, Some(resource_mode)) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("❌ Failed to initialize WASMA Core: {}", e);
-            process::exit(1);
-        }
-    };
+fn handle_resources(window_id: u64, format: OutputFormat) {
+    let client = control_client();
 
-    match core.get_window_resources(window_id) {
+    match client.window_resources(window_id) {
+        Ok(usage) if !format.is_text() => print_formatted(format, &usage),
         Ok(usage) => {
             println!("╔════════════════════════════════════════════════════════════╗");
             println!("║           Window #{} Resource Usage                       ║", window_id);
@@ -532,7 +999,13 @@ fn handle_resources(config_path: Option<String>, resource_mode: ResourceMode, wi
             
             println!("📊 Assignment ID: {}", usage.assignment_id);
             println!("💾 RAM Allocated: {} MiB", usage.ram_allocated_mb);
+            if let Some(used) = usage.ram_used_mb {
+                println!("📈 RAM Used: {} MiB (cgroup)", used);
+            }
             println!("🎮 VRAM Allocated: {} MiB", usage.vram_allocated_mb);
+            if let Some(used) = usage.vram_used_mb {
+                println!("📈 VRAM Used: {} MiB", used);
+            }
             println!("🔧 CPU Cores: {:?}", usage.cpu_cores);
             
             if let Some(ref gpu) = usage.gpu_device {
@@ -556,23 +1029,12 @@ fn handle_resources(config_path: Option<String>, resource_mode: ResourceMode, wi
     }
 }
 
-fn handle_state(
-    config_path: Option<String>,
-    resource_mode: ResourceMode,
-    window_id: u64,
-    state: WindowState,
-) {
-    let core = match build_core(config_path, Some(resource_mode)) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("❌ Failed to initialize WASMA Core: {}", e);
-            process::exit(1);
-        }
-    };
+fn handle_state(window_id: u64, state: WindowState) {
+    let client = control_client();
 
     println!("🔄 Setting window {} state to {:?}...", window_id, state);
     
-    match core.set_window_state(window_id, state) {
+    match client.set_window_state(window_id, state) {
         Ok(_) => {
             println!("✅ Window state changed successfully");
         }
@@ -583,32 +1045,292 @@ fn handle_state(
     }
 }
 
-fn handle_cycle(config_path: Option<String>, resource_mode: ResourceMode, count: u32) {
+fn handle_workspace(action: &WorkspaceAction) {
+    let client = control_client();
+
+    let result = match action {
+        WorkspaceAction::List { format } => client.list_workspaces().map(|workspaces| {
+            if !format.is_text() {
+                print_formatted(*format, &WorkspaceListing { workspaces });
+                return;
+            }
+            for workspace in workspaces {
+                let marker = if workspace.active { "▶" } else { " " };
+                let windows: Vec<String> = workspace.windows.iter().map(|id| id.to_string()).collect();
+                println!("{} Workspace {}: {} window(s) {}", marker, workspace.number, windows.len(), windows.join(", "));
+            }
+        }),
+        WorkspaceAction::Switch { number } => client.switch_workspace(*number)
+            .map(|_| println!("✅ Switched to workspace {}", number)),
+        WorkspaceAction::Move { window_id, number } => client.move_to_workspace(*window_id, *number)
+            .map(|_| println!("✅ Window {} moved to workspace {}", window_id, number)),
+    };
+    if let Err(e) = result {
+        eprintln!("❌ Workspace command failed: {}", e);
+        process::exit(1);
+    }
+}
+
+/// One backend monitor report, as text or as a structured document
+fn print_report(format: OutputFormat, report: &MonitorReport) {
+    if format.is_text() {
+        println!("{}", report);
+    } else {
+        print_formatted(format, report);
+    }
+}
+
+fn handle_cycle(config_path: Option<String>, resource_mode: ResourceMode, count: u32, format: OutputFormat) {
     let core = match build_core(config_path, Some(resource_mode)) {
         Ok(c) => c,
         Err(e) => {
-            eprintln!("❌ Failed to initialize WASMA Core: {}", e);
+            eprintln!("{}", t!("cli-core-failed", error = e));
             process::exit(1);
         }
     };
 
     if count == 0 {
-        println!("🔄 Running resource management cycle continuously...");
-        println!("   Press Ctrl+C to stop");
+        if format.is_text() {
+            println!("🔄 Running resource management cycle continuously...");
+            println!("   Press Ctrl+C to stop");
+        }
+        core.window_handler.set_resource_cadence(CycleCadence::uniform(std::time::Duration::from_secs(1)));
+        let reports = core.window_handler.subscribe_resource_reports();
+        std::thread::spawn(move || {
+            for report in reports {
+                print_report(format, &report);
+            }
+        });
         loop {
-            core.update();
-            std::thread::sleep(std::time::Duration::from_secs(1));
+            core.window_handler.run_due_resource_phases();
+            core.window_handler.wait_for_resource_work();
         }
     } else {
-        println!("🔄 Running {} resource management cycle(s)...", count);
+        if format.is_text() {
+            println!("🔄 Running {} resource management cycle(s)...", count);
+        }
         for i in 1..=count {
-            println!("   Cycle {}/{}", i, count);
-            core.update();
+            if format.is_text() {
+                println!("   Cycle {}/{}", i, count);
+            }
+            print_report(format, &core.update());
             if i < count {
                 std::thread::sleep(std::time::Duration::from_millis(500));
             }
         }
-        println!("✅ Resource cycles completed");
+        if format.is_text() {
+            println!("✅ Resource cycles completed");
+        }
+    }
+}
+
+fn handle_cycle_dry_run(config_path: Option<String>, resource_mode: ResourceMode) {
+    use wasma_client::window_handling::describe_action;
+
+    // The running instance owns the real assignments; fall back to a local core
+    let client = ControlClient::default();
+    let plan = if client.is_running() {
+        match client.plan_cycle() {
+            Ok(plan) => plan,
+            Err(e) => {
+                eprintln!("❌ Dry run failed: {}", e);
+                process::exit(1);
+            }
+        }
+    } else {
+        match build_core(config_path, Some(resource_mode)) {
+            Ok(core) => core.window_handler.plan_resource_cycle(),
+            Err(e) => {
+                eprintln!("{}", t!("cli-core-failed", error = e));
+                process::exit(1);
+            }
+        }
+    };
+
+    println!("🧪 Dry run: next resource cycle ({:?} mode), nothing is applied", plan.mode);
+    if plan.is_noop() {
+        println!("   Nothing would change");
+    }
+    for assignment in plan.assignments.iter().filter(|a| !a.actions.is_empty()) {
+        let actions: Vec<String> = assignment.actions.iter().map(describe_action).collect();
+        println!("   Assignment {} ({:?}): {}", assignment.id, assignment.execution_mode, actions.join(", "));
+    }
+
+    let preempted = plan.preempted();
+    if !preempted.is_empty() {
+        println!("   Preempted: {:?}", preempted);
+    }
+    match plan.system_ram_mb {
+        Some(total) => println!("   RAM: {} / {} MiB | VRAM: {} MiB", plan.ram_mb, total, plan.vram_mb),
+        None => println!("   RAM: {} MiB | VRAM: {} MiB", plan.ram_mb, plan.vram_mb),
+    }
+    for warning in &plan.warnings {
+        eprintln!("⚠️  {}", warning);
+    }
+}
+
+fn handle_daemon(
+    config_path: Option<String>,
+    resource_mode: ResourceMode,
+    cadence: CycleCadence,
+    restore: Option<String>,
+    // Address and token file
+    grpc: Option<(String, Option<String>)>,
+    wayland: bool,
+    x11: bool,
+) {
+    let core = match build_core(config_path, Some(resource_mode)) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}", t!("cli-core-failed", error = e));
+            process::exit(1);
+        }
+    };
+
+    // Clean up after a crashed previous run before anything is restored
+    match wasma_client::window_handling::default_state_dir()
+        .and_then(|state| core.window_handler.recover_previous_run(&state))
+    {
+        Ok(report) if !report.is_empty() => println!("🧹 Crash recovery: {}", report),
+        Ok(_) => {}
+        Err(e) => eprintln!("⚠️  Crash recovery skipped: {}", e),
+    }
+
+    match (&restore, wasma_client::window_handling::default_session_path()) {
+        (Some(path), _) => match control::restore_snapshot(&core.window_handler, std::path::Path::new(path)) {
+            Ok(count) => println!("♻️  Resumed {} window(s) after upgrade", count),
+            Err(e) => eprintln!("⚠️  Snapshot {} could not be restored: {}", path, e),
+        },
+        // Fresh start: bring back the layout saved before the last shutdown
+        (None, Ok(session)) if session.exists() => {
+            if let Err(e) = core.window_handler.restore_session(&session) {
+                eprintln!("⚠️  Previous session could not be restored: {}", e);
+            }
+        }
+        _ => {}
+    }
+    if let Ok(session) = wasma_client::window_handling::default_session_path() {
+        core.window_handler.autosave_session(session);
+    }
+
+    // Re-exec with the same command line, minus the consumed --restore
+    let mut restart_args = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--restore" {
+            args.next();
+        } else if !arg.starts_with("--restore=") {
+            restart_args.push(arg);
+        }
+    }
+
+    let daemon = ControlDaemon::new(core.window_handler.clone(), resource_mode, control::default_socket_path())
+        .upgradable(restart_args);
+    if let Err(e) = daemon.start() {
+        eprintln!("❌ Control daemon could not be started: {}", e);
+        process::exit(1);
+    }
+    if let Some((addr, grpc_token_file)) = grpc {
+        let mut server = GrpcControlServer::new(core.window_handler.clone(), resource_mode);
+        if let Some(path) = grpc_token_file {
+            match std::fs::read_to_string(&path).map(|t| t.trim().to_string()) {
+                Ok(token) if !token.is_empty() => server = server.with_token(&token),
+                Ok(_) => {
+                    eprintln!("❌ gRPC token file {} is empty", path);
+                    process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("❌ gRPC token file {} could not be read: {}", path, e);
+                    process::exit(1);
+                }
+            }
+        }
+        if let Err(e) = server.start(&addr) {
+            eprintln!("❌ gRPC control service could not be started on {}: {}", addr, e);
+            process::exit(1);
+        }
+    }
+    // org.wasma.WindowManager for desktop tooling; optional, a session bus may not exist
+    #[cfg(feature = "dbus")]
+    if let Err(e) = wasma_client::dbus::DbusService::new(core.window_handler.clone(), resource_mode).start() {
+        eprintln!("⚠️  D-Bus interface unavailable: {}", e);
+    }
+
+    // Optional [webhooks] from WSDG settings
+    if let Some(webhooks) = WebhookNotifier::from_wsdg() {
+        webhooks.watch_windows(core.window_handler.subscribe());
+        webhooks.watch_endpoints(ProtocolControl::global().subscribe());
+        webhooks.watch_reports(core.window_handler.subscribe_resource_reports());
+    }
+    // Prometheus exporter when wasma.in.conf sets metrics_port
+    if let Some(metrics) = core.config.metrics {
+        let exporter = MetricsExporter::new(core.window_handler.clone());
+        exporter.watch_reports(core.window_handler.subscribe_resource_reports());
+        // Per-assignment CPU/RAM/VRAM gauges read the resource sampler
+        core.window_handler.start_cpu_sampler(cpu_history::CPU_SAMPLE_INTERVAL);
+        match exporter.serve(metrics.addr()) {
+            Ok(addr) => println!("📈 Prometheus metrics on http://{}/metrics", addr),
+            Err(e) => eprintln!("⚠️  Metrics endpoint could not be started on {}: {}", metrics.addr(), e),
+        }
+    }
+    // run-parts style scripts in /etc/wasma/hooks/<event>.d
+    let hooks = HookRunner::system();
+    if hooks.dir().is_dir() {
+        hooks.watch_windows(core.window_handler.subscribe());
+        hooks.watch_endpoints(ProtocolControl::global().subscribe());
+    }
+
+    if wayland {
+        #[cfg(feature = "wayland")]
+        if let Err(e) = wasma_client::WaylandBackend::spawn(core.window_handler.clone()) {
+            eprintln!("⚠️  Wayland backend unavailable: {}", e);
+        }
+    }
+    if x11 {
+        #[cfg(feature = "x11")]
+        if let Err(e) = wasma_client::X11Backend::spawn(core.window_handler.clone()) {
+            eprintln!("⚠️  X11 backend unavailable: {}", e);
+        }
+    }
+
+    println!("🛰️  WASMA daemon running ({:?} mode), press Ctrl+C to stop", resource_mode);
+    // Each backend phase runs on its own cadence; new windows and lease events wake the loop early
+    core.window_handler.set_resource_cadence(cadence);
+    loop {
+        core.window_handler.run_due_resource_phases();
+        core.window_handler.wait_for_resource_work();
+    }
+}
+
+fn handle_upgrade() {
+    let client = control_client();
+    match client.upgrade() {
+        Ok(snapshot) => {
+            println!("🔁 Daemon is re-executing, state saved to {}", snapshot.display());
+        }
+        Err(e) => {
+            eprintln!("❌ Upgrade failed: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn handle_events() {
+    let events = match control_client().subscribe() {
+        Ok(events) => events,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            process::exit(1);
+        }
+    };
+    for event in events {
+        match event.and_then(|event| Ok(serde_json::to_string(&event)?)) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("❌ Event stream ended: {}", e);
+                process::exit(1);
+            }
+        }
     }
 }
 
@@ -637,8 +1359,420 @@ fn handle_uclient(config_path: Option<String>, raw: bool) {
     
     if let Err(e) = client.start_engine() {
         eprintln!("❌ UClient engine error: {}", e);
+        crash_report::report_fatal("UClient engine", &e);
+        process::exit(1);
+    }
+}
+
+fn handle_hotkeys(trigger: Option<String>) {
+    use wasma_client::global_hotkeys::{default_socket_path, query_daemon};
+
+    let request = match &trigger {
+        Some(combo) => format!("TRIGGER {}", combo),
+        None => "LIST".to_string(),
+    };
+
+    let replies = match query_daemon(&default_socket_path(), &request) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("❌ Hotkey daemon not reachable: {}", e);
+            process::exit(1);
+        }
+    };
+
+    if trigger.is_some() {
+        for reply in replies {
+            if let Some(err) = reply.strip_prefix("ERR ") {
+                eprintln!("❌ {}", err);
+                process::exit(1);
+            }
+            println!("✅ {}", reply.trim_start_matches("OK "));
+        }
+        return;
+    }
+
+    let bindings: Vec<&String> = replies.iter().filter(|l| l.starts_with("BINDING ")).collect();
+    if bindings.is_empty() {
+        println!("⌨️  No global hotkeys registered");
+        return;
+    }
+
+    println!("⌨️  Registered Global Hotkeys:");
+    for line in bindings {
+        let fields: Vec<&str> = line.split_whitespace().skip(1).collect();
+        if let [id, window_id, app_id, combo, action] = fields.as_slice() {
+            println!("   [{}] {} → {} ({}, window {})", id, combo, action, app_id, window_id);
+        }
+    }
+}
+
+fn handle_seats(format: OutputFormat) {
+    use wasma_client::seats::SeatDiscovery;
+
+    let seats = SeatDiscovery::new().discover();
+    if !format.is_text() {
+        print_formatted(format, &seats);
+        return;
+    }
+    for seat in &seats {
+        print!("{}", seat);
+    }
+}
+
+fn handle_lock(watch: bool) {
+    use wasma_client::session_lock::{launch_lock_screen, watch_loginctl};
+
+    if !watch {
+        if let Err(e) = launch_lock_screen() {
+            eprintln!("❌ Failed to lock session: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    println!("👀 Waiting for logind lock requests...");
+    let (tx, rx) = std::sync::mpsc::channel();
+    if let Err(e) = watch_loginctl(move |locked| {
+        if locked {
+            tx.send(()).ok();
+        }
+    }) {
+        eprintln!("❌ Failed to watch logind: {}", e);
         process::exit(1);
     }
+
+    // The lock surface must run on the main thread
+    while rx.recv().is_ok() {
+        if let Err(e) = launch_lock_screen() {
+            eprintln!("⚠️  Lock request ignored: {}", e);
+        }
+    }
+}
+
+fn handle_config(config_path: Option<String>, action: &ConfigAction) {
+    use wasma_client::config_backup::{self, ConfigLocations};
+    use wsdg_xdg::WsdgEnv;
+
+    let config_path = config_path.unwrap_or_else(wasma_client::parser::default_config_path);
+    let locations = ConfigLocations::detect(&WsdgEnv::new(), Path::new(&config_path));
+
+    let result = match action {
+        ConfigAction::Export { archive } => config_backup::export(&locations, Path::new(archive))
+            .map(|report| (format!("📦 Exported {} files to {}", report.entries.len(), archive), report)),
+        ConfigAction::Import { archive, dry_run } => config_backup::import(Path::new(archive), &locations, *dry_run)
+            .map(|report| {
+                let verb = if *dry_run { "Would restore" } else { "Restored" };
+                (format!("📦 {} {} files from {}", verb, report.entries.len(), archive), report)
+            }),
+    };
+
+    match result {
+        Ok((summary, report)) => {
+            println!("{}", summary);
+            print!("{}", report);
+        }
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn handle_manifest(action: &ManifestAction) {
+    use std::path::Path;
+    use wsdg_xdg::{install_manifest, uninstall_manifest, WsdgEnv};
+
+    let env = WsdgEnv::new();
+    let (verb, result) = match action {
+        ManifestAction::Install { path } => ("Registered", install_manifest(&env, Path::new(path))),
+        ManifestAction::Uninstall { path } => ("Removed", uninstall_manifest(&env, Path::new(path))),
+        ManifestAction::Resolve { path } => {
+            match wsdg_app_manifest::ManifestParser::new(path.clone()).resolve() {
+                Ok(resolved) => print!("{}", resolved.dump()),
+                Err(e) => {
+                    eprintln!("❌ {}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        ManifestAction::Validate { path, format } => {
+            handle_manifest_validate(path, *format);
+            return;
+        }
+    };
+
+    let report = match result {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            process::exit(1);
+        }
+    };
+
+    println!("📦 {}", report.manifest);
+    for warning in &report.warnings {
+        eprintln!("⚠️  {}", warning);
+    }
+    if report.mime_types.is_empty() && report.schemes.is_empty() {
+        println!("   Nothing was registered");
+        return;
+    }
+    for mime in &report.mime_types {
+        println!("   {} MIME type {}", verb, mime);
+    }
+    for scheme in &report.schemes {
+        println!("   {} scheme {}://", verb, scheme);
+    }
+}
+
+/// Exits with status 1 when the manifest has errors, in every format
+fn handle_manifest_validate(path: &str, format: OutputFormat) {
+    let host = wsdg_app_manifest::HostLimits::detect();
+    let report = wsdg_app_manifest::ManifestParser::new(path.to_string()).validate(&host);
+
+    if format.is_text() {
+        for diagnostic in &report.diagnostics {
+            let icon = match diagnostic.severity {
+                wsdg_app_manifest::Severity::Error => "❌",
+                wsdg_app_manifest::Severity::Warning => "⚠️ ",
+            };
+            println!("{} {}", icon, diagnostic);
+        }
+        let (errors, warnings) = (report.errors().count(), report.warnings().count());
+        if errors == 0 {
+            println!("✅ {} is valid ({} warning(s))", report.manifest, warnings);
+        } else {
+            println!("📦 {}: {} error(s), {} warning(s)", report.manifest, errors, warnings);
+        }
+    } else {
+        print_formatted(format, &report);
+    }
+
+    if !report.is_valid() {
+        process::exit(1);
+    }
+}
+
+fn handle_report(action: &ReportAction) {
+    let crashes = crash_report::crash_dir();
+    let package = match action {
+        ReportAction::List => {
+            let reports = crash_report::list_reports(&crashes);
+            if reports.is_empty() {
+                println!("No crash reports in {}", crashes.display());
+            }
+            for report in reports {
+                println!("{}", report.display());
+            }
+            return;
+        }
+        ReportAction::Last { package } => package,
+    };
+
+    let Some(report) = crash_report::latest_report(&crashes) else {
+        println!("No crash reports in {}", crashes.display());
+        return;
+    };
+    let result = match package {
+        Some(output) => crash_report::package_report(&report, std::path::Path::new(output))
+            .map(|()| println!("📦 {} packaged into {}", report.display(), output)),
+        None => crash_report::render_report(&report).map(|text| print!("{}", text)),
+    };
+    if let Err(e) = result {
+        eprintln!("❌ Crash report could not be read: {}", e);
+        process::exit(1);
+    }
+}
+
+fn handle_starter(action: &StarterAction) {
+    use std::time::{Duration, SystemTime};
+    use wbackend::osd::{self, OsdIcon};
+    use wsdg_xdg::{EntryStatus, StarterEvent, WsdgEnv, WsdgStarter};
+
+    let mut starter = WsdgStarter::new(WsdgEnv::new());
+    match action {
+        StarterAction::Status => {
+            let path = starter.status_path();
+            let entries = match WsdgStarter::read_status(&path) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    eprintln!("❌ No starter status at {}: {}", path.display(), e);
+                    process::exit(1);
+                }
+            };
+            if entries.is_empty() {
+                println!("No supervised starter entries");
+            }
+            for entry in entries {
+                let detail = match entry.status {
+                    EntryStatus::Running { pid } => format!("pid {}", pid),
+                    EntryStatus::Backoff { restart_at } => {
+                        let wait = restart_at.duration_since(SystemTime::now()).unwrap_or_default();
+                        format!("restart in {}s", wait.as_secs())
+                    }
+                    EntryStatus::Failed | EntryStatus::Exited => String::new(),
+                };
+                println!(
+                    "{:<24} {:<8} crashes: {:<3} {} {}",
+                    entry.app_name,
+                    entry.status.name(),
+                    entry.crashes,
+                    detail,
+                    entry.last_exit.map(|e| format!("(last: {})", e)).unwrap_or_default(),
+                );
+            }
+        }
+        StarterAction::Run { interval } => {
+            for result in starter.supervise_autostart_apps() {
+                match result {
+                    Ok(pid) => println!("🚀 Started pid {}", pid),
+                    Err(e) => eprintln!("❌ {}", e),
+                }
+            }
+            println!("📄 Status: {}", starter.status_path().display());
+
+            loop {
+                for event in starter.poll() {
+                    match event {
+                        StarterEvent::Crashed { app_name, exit, crashes, restart_in } => {
+                            eprintln!("⚠️  {} crashed ({}), restart #{} in {:?}", app_name, exit, crashes, restart_in);
+                        }
+                        StarterEvent::Restarted { app_name, pid } => {
+                            println!("🔁 {} restarted as pid {}", app_name, pid);
+                        }
+                        StarterEvent::Failed { app_name, exit, crashes } => {
+                            let text = format!("{} keeps crashing ({}), gave up after {} attempts", app_name, exit, crashes);
+                            eprintln!("❌ {}", text);
+                            osd::show(text, OsdIcon::Warning, Duration::from_secs(5));
+                        }
+                    }
+                }
+                std::thread::sleep(Duration::from_millis(*interval));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "x11")]
+fn handle_adopt(config_path: Option<String>, resource_mode: ResourceMode, existing: bool) {
+    use wasma_client::X11Adopter;
+
+    let core = match build_core(config_path, Some(resource_mode)) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}", t!("cli-core-failed", error = e));
+            process::exit(1);
+        }
+    };
+
+    println!("🔌 Connecting to X11 display for window adoption...");
+
+    let mut adopter = match X11Adopter::connect(
+        (*core.config).clone(),
+        core.window_handler.clone(),
+        resource_mode,
+    ) {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("❌ X11 adoption unavailable: {}", e);
+            process::exit(1);
+        }
+    };
+
+    if let Err(e) = adopter.become_manager() {
+        eprintln!("❌ {}", e);
+        process::exit(1);
+    }
+
+    if existing {
+        let adopted = adopter.adopt_existing();
+        println!("✅ Adopted {} existing window(s)", adopted.len());
+    }
+
+    if let Err(e) = adopter.run() {
+        eprintln!("❌ Adoption loop terminated: {}", e);
+        adopter.release_all();
+        process::exit(1);
+    }
+}
+
+#[cfg(feature = "wayland-compositor")]
+fn handle_compositor(
+    config_path: Option<String>,
+    resource_mode: ResourceMode,
+    width: u32,
+    height: u32,
+    xwayland: bool,
+) {
+    use wasma_client::WaylandCompositor;
+
+    let core = match build_core(config_path, Some(resource_mode)) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}", t!("cli-core-failed", error = e));
+            process::exit(1);
+        }
+    };
+
+    let mut compositor = match WaylandCompositor::new(
+        (*core.config).clone(),
+        core.window_handler.clone(),
+        resource_mode,
+        width,
+        height,
+    ) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("❌ Wayland compositor unavailable: {}", e);
+            process::exit(1);
+        }
+    };
+
+    println!("✅ Clients can connect with WAYLAND_DISPLAY={}", compositor.socket_name());
+
+    if xwayland {
+        #[cfg(feature = "xwayland")]
+        match compositor.start_xwayland() {
+            Ok(display) => println!("✅ X11 clients can connect with DISPLAY={}", display),
+            Err(e) => eprintln!("⚠️  Xwayland unavailable: {}", e),
+        }
+    }
+
+    if let Err(e) = compositor.run() {
+        eprintln!("❌ Compositor loop terminated: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(feature = "wayland")]
+fn wayland_requested(cmd: &Commands) -> bool {
+    matches!(cmd, Commands::Daemon { wayland: true, .. })
+}
+
+#[cfg(not(feature = "wayland"))]
+fn wayland_requested(_cmd: &Commands) -> bool {
+    false
+}
+
+#[cfg(feature = "x11")]
+fn x11_requested(cmd: &Commands) -> bool {
+    matches!(cmd, Commands::Daemon { x11: true, .. })
+}
+
+#[cfg(not(feature = "x11"))]
+fn x11_requested(_cmd: &Commands) -> bool {
+    false
+}
+
+#[cfg(feature = "xwayland")]
+fn xwayland_requested(cmd: &Commands) -> bool {
+    matches!(cmd, Commands::Compositor { xwayland: true, .. })
+}
+
+#[cfg(all(feature = "wayland-compositor", not(feature = "xwayland")))]
+fn xwayland_requested(_cmd: &Commands) -> bool {
+    false
 }
 
 fn build_core(
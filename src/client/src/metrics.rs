@@ -0,0 +1,357 @@
+// WASMA - Prometheus metrics
+// With `metrics_port` in wasma.in.conf the daemon serves the Prometheus text
+// format on http://<metrics_bind>:<metrics_port>/metrics. Gauges are read at
+// scrape time: the window list, the last backend MonitorReport and the
+// per-window resource sampler. Counters come from the report subscription
+// (lease expirations), ProtocolControl (stream traffic) and the frame latency
+// histogram the stream clients feed through `record_frame_latency`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use wbackend::MonitorReport;
+
+use crate::protocol_control::ProtocolControl;
+use crate::resource_graphs::ResourceMetric;
+use crate::window_handling::WindowHandler;
+
+/// Port of MetricsConfig::default(), as in the wasma.in.conf example
+pub const DEFAULT_METRICS_PORT: u16 = 9464;
+
+/// Upper bounds (seconds) of the frame latency histogram buckets
+pub const LATENCY_BUCKETS: [f64; 8] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+
+const BYTES_PER_MIB: f64 = (1024 * 1024) as f64;
+
+/// A scraper that connects and goes quiet is dropped after this long
+const SCRAPE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest request line read; Prometheus sends a short GET
+const MAX_REQUEST_LINE: u64 = 8192;
+/// Scrapes served at once; further connections get 503 until one finishes
+const MAX_CONCURRENT_SCRAPES: usize = 8;
+
+/// wasma.in.conf `metrics_port` / `metrics_bind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    pub bind: IpAddr,
+    pub port: u16,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self { bind: IpAddr::V4(Ipv4Addr::LOCALHOST), port: DEFAULT_METRICS_PORT }
+    }
+}
+
+impl MetricsConfig {
+    pub fn addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind, self.port)
+    }
+}
+
+/// Cumulative histogram over LATENCY_BUCKETS
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LatencyHistogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl LatencyHistogram {
+    pub fn observe(&mut self, latency: Duration) {
+        let seconds = latency.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+}
+
+fn frame_latency() -> &'static Mutex<LatencyHistogram> {
+    static HISTOGRAM: OnceLock<Mutex<LatencyHistogram>> = OnceLock::new();
+    HISTOGRAM.get_or_init(Mutex::default)
+}
+
+/// Latency of one received frame, from arrival to display
+pub fn record_frame_latency(latency: Duration) {
+    frame_latency().lock().unwrap().observe(latency);
+}
+
+/// Text exposition builder; one HELP/TYPE header per family
+#[derive(Default)]
+struct Exposition(String);
+
+impl Exposition {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.0, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.0.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, value.replace('\\', "\\\\").replace('"', "\\\"")))
+                .collect();
+            let _ = write!(self.0, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.0, " {}", value);
+    }
+}
+
+/// Collects daemon state into Prometheus metrics
+#[derive(Clone)]
+pub struct MetricsExporter {
+    handler: Arc<WindowHandler>,
+    lease_expirations: Arc<AtomicU64>,
+}
+
+impl MetricsExporter {
+    pub fn new(handler: Arc<WindowHandler>) -> Self {
+        Self { handler, lease_expirations: Arc::new(AtomicU64::new(0)) }
+    }
+
+    /// Count lease expirations from backend monitor reports
+    pub fn watch_reports(&self, reports: Receiver<MonitorReport>) {
+        let expirations = self.lease_expirations.clone();
+        std::thread::spawn(move || {
+            for report in reports {
+                expirations.fetch_add(report.expired.len() as u64, Ordering::Relaxed);
+            }
+        });
+    }
+
+    pub fn lease_expirations(&self) -> u64 {
+        self.lease_expirations.load(Ordering::Relaxed)
+    }
+
+    /// Current metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = Exposition::default();
+        let windows = self.handler.list_windows();
+        out.family("wasma_windows", "gauge", "Open windows");
+        out.sample("wasma_windows", &[], windows.len() as f64);
+
+        // Sampler values per assignment; the report fills in limits and cgroup usage
+        let report = self.handler.last_resource_report();
+        let mut assignments: Vec<(u32, Option<u64>)> = windows.iter().filter_map(|w| Some((w.assignment_id?, Some(w.id)))).collect();
+        for status in report.iter().flat_map(|r| &r.assignments) {
+            if !assignments.iter().any(|(id, _)| *id == status.id) {
+                assignments.push((status.id, None));
+            }
+        }
+        assignments.sort_unstable();
+
+        let latest = |window: Option<u64>, metric: ResourceMetric| {
+            window
+                .and_then(|id| self.handler.get_window_resource_history(id).ok())
+                .and_then(|history| history.latest(metric))
+                .map(f64::from)
+        };
+        let status = |id: u32| report.as_ref().and_then(|r| r.assignment(id));
+
+        out.family("wasma_assignment_cpu_percent", "gauge", "CPU utilization of the assignment's cores");
+        for (id, window) in &assignments {
+            if let Some(cpu) = latest(*window, ResourceMetric::Cpu) {
+                out.sample("wasma_assignment_cpu_percent", &[("assignment", &id.to_string())], cpu);
+            }
+        }
+        out.family("wasma_assignment_ram_bytes", "gauge", "RAM used by the assignment");
+        for (id, window) in &assignments {
+            let used = latest(*window, ResourceMetric::Ram)
+                .map(|mib| mib * BYTES_PER_MIB)
+                .or_else(|| status(*id)?.ram_used_mb.map(|mb| mb as f64 * BYTES_PER_MIB));
+            if let Some(used) = used {
+                out.sample("wasma_assignment_ram_bytes", &[("assignment", &id.to_string())], used);
+            }
+        }
+        out.family("wasma_assignment_ram_limit_bytes", "gauge", "RAM allocated to the assignment");
+        for (id, _) in &assignments {
+            if let Some(status) = status(*id) {
+                out.sample("wasma_assignment_ram_limit_bytes", &[("assignment", &id.to_string())], status.ram_limit_mb as f64 * BYTES_PER_MIB);
+            }
+        }
+        out.family("wasma_assignment_vram_bytes", "gauge", "VRAM used by the assignment");
+        for (id, window) in &assignments {
+            if let Some(vram) = latest(*window, ResourceMetric::Vram) {
+                out.sample("wasma_assignment_vram_bytes", &[("assignment", &id.to_string())], vram * BYTES_PER_MIB);
+            }
+        }
+        out.family("wasma_assignment_lease_remaining_seconds", "gauge", "Time left on the assignment's lease (0 without a lease)");
+        for (id, _) in &assignments {
+            if let Some(status) = status(*id) {
+                out.sample("wasma_assignment_lease_remaining_seconds", &[("assignment", &id.to_string())], status.lease_remaining_secs as f64);
+            }
+        }
+
+        out.family("wasma_lease_expirations_total", "counter", "Assignments removed because their lease expired");
+        out.sample("wasma_lease_expirations_total", &[], self.lease_expirations() as f64);
+
+        // One series per wasma.in.conf protocol_def endpoint
+        let endpoints = ProtocolControl::global().snapshot();
+        out.family("wasma_stream_bytes_total", "counter", "Bytes received from the stream endpoint");
+        for endpoint in &endpoints {
+            out.sample("wasma_stream_bytes_total", &[("endpoint", &endpoint.endpoint)], endpoint.bytes as f64);
+        }
+        out.family("wasma_stream_frames_total", "counter", "Frames received from the stream endpoint");
+        for endpoint in &endpoints {
+            out.sample("wasma_stream_frames_total", &[("endpoint", &endpoint.endpoint)], endpoint.frames as f64);
+        }
+        out.family("wasma_stream_throughput_bytes_per_second", "gauge", "Recent stream throughput");
+        for endpoint in &endpoints {
+            out.sample("wasma_stream_throughput_bytes_per_second", &[("endpoint", &endpoint.endpoint)], endpoint.throughput);
+        }
+
+        let latency = frame_latency().lock().unwrap().clone();
+        out.family("wasma_frame_latency_seconds", "histogram", "Frame latency from arrival to display");
+        for (count, bound) in latency.buckets.iter().zip(LATENCY_BUCKETS) {
+            out.sample("wasma_frame_latency_seconds_bucket", &[("le", &bound.to_string())], *count as f64);
+        }
+        out.sample("wasma_frame_latency_seconds_bucket", &[("le", "+Inf")], latency.count as f64);
+        out.sample("wasma_frame_latency_seconds_sum", &[], latency.sum);
+        out.sample("wasma_frame_latency_seconds_count", &[], latency.count as f64);
+
+        out.0
+    }
+
+    /// Serve GET /metrics on `addr` from a background thread; returns the bound address.
+    /// Each scrape gets its own thread, so a stalled client cannot hold up the others;
+    /// at most MAX_CONCURRENT_SCRAPES run at once
+    pub fn serve(&self, addr: SocketAddr) -> io::Result<SocketAddr> {
+        let listener = TcpListener::bind(addr)?;
+        let bound = listener.local_addr()?;
+        let exporter = self.clone();
+        let active = Arc::new(AtomicUsize::new(0));
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().map_while(Result::ok) {
+                if active.fetch_add(1, Ordering::AcqRel) >= MAX_CONCURRENT_SCRAPES {
+                    active.fetch_sub(1, Ordering::AcqRel);
+                    tracing::debug!("metrics scrape refused, too many in flight");
+                    stream.set_write_timeout(Some(SCRAPE_TIMEOUT)).ok();
+                    stream.write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").ok();
+                    continue;
+                }
+                let exporter = exporter.clone();
+                let active = active.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = exporter.respond(stream) {
+                        tracing::debug!(error = %e, "metrics scrape failed");
+                    }
+                    active.fetch_sub(1, Ordering::AcqRel);
+                });
+            }
+        });
+        Ok(bound)
+    }
+
+    fn respond(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(SCRAPE_TIMEOUT))?;
+        stream.set_write_timeout(Some(SCRAPE_TIMEOUT))?;
+        let mut request = String::new();
+        BufReader::new(&stream).take(MAX_REQUEST_LINE).read_line(&mut request)?;
+        let (status, content_type, body) = match request.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+            ["GET", "/metrics"] => ("200 OK", "text/plain; version=0.0.4", self.render()),
+            ["GET", _] => ("404 Not Found", "text/plain", "metrics are served at /metrics\n".to_string()),
+            _ => ("405 Method Not Allowed", "text/plain", String::new()),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ConfigParser;
+    use crate::window_handling::WindowGeometry;
+    use wbackend::ResourceMode;
+
+    #[test]
+    fn test_metrics_endpoint() {
+        // Exporter is configured from wasma.in.conf; no metrics_port keeps it off
+        let parser = ConfigParser::new(None);
+        let default = parser.generate_default_config();
+        assert_eq!(parser.parse(&default).unwrap().metrics, None);
+        let config = parser.parse(&default.replace("}", "metrics_port : 0\nmetrics_bind : 127.0.0.1\n}")).unwrap();
+        let metrics = config.metrics.unwrap();
+        assert_eq!(metrics.addr(), "127.0.0.1:0".parse().unwrap());
+
+        let handler = Arc::new(WindowHandler::new(ResourceMode::Manual));
+        let geometry = WindowGeometry { x: 0, y: 0, width: 400, height: 300 };
+        let id = handler.create_window("Metrics".into(), "test.metrics".into(), geometry, None, ResourceMode::Manual).unwrap();
+        handler.sample_cpu();
+        handler.run_resource_cycle();
+        record_frame_latency(Duration::from_millis(20));
+
+        let exporter = MetricsExporter::new(handler.clone());
+        let text = exporter.render();
+        assert!(text.contains("# TYPE wasma_windows gauge\nwasma_windows 1\n"));
+        assert!(text.contains(&format!("wasma_assignment_ram_limit_bytes{{assignment=\"{}\"}}", id)));
+        assert!(text.contains("wasma_lease_expirations_total 0\n"));
+        assert!(text.contains("wasma_frame_latency_seconds_bucket{le=\"0.025\"}"));
+        assert!(!text.contains("wasma_frame_latency_seconds_count 0\n"));
+
+        // Scraped over HTTP, even while another client sits idle on its connection
+        let addr = exporter.serve(metrics.addr()).unwrap();
+        let _idle = TcpStream::connect(addr).unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("text/plain; version=0.0.4"));
+        assert!(response.contains("wasma_windows 1"));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn test_scrapes_are_capped() {
+        let exporter = MetricsExporter::new(Arc::new(WindowHandler::new(ResourceMode::Manual)));
+        let addr = exporter.serve("127.0.0.1:0".parse().unwrap()).unwrap();
+        let scrape = || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").ok();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).ok();
+            response
+        };
+
+        let idle: Vec<TcpStream> = (0..MAX_CONCURRENT_SCRAPES).map(|_| TcpStream::connect(addr).unwrap()).collect();
+        assert!(scrape().starts_with("HTTP/1.1 503"));
+
+        // Slots free up as soon as the idle clients go away
+        drop(idle);
+        let served = (0..50).any(|_| {
+            std::thread::sleep(Duration::from_millis(20));
+            scrape().starts_with("HTTP/1.1 200 OK")
+        });
+        assert!(served);
+    }
+}
@@ -0,0 +1,307 @@
+// WASMA - Monitor enumeration
+// The outputs the desktop spans, read from the Wayland compositor (wl_output)
+// or the X server (RandR monitors). WindowMultitary lays viewports out per
+// monitor from a MonitorMap; MonitorWatcher re-enumerates on an interval and
+// reports hotplug (a changed map) so the layout can follow.
+
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+/// How often the watcher re-enumerates outputs
+pub const HOTPLUG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Overrides the detected scale of every output (like GDK_SCALE)
+pub const SCALE_ENV_VAR: &str = "WASMA_SCALE";
+
+/// One output, in desktop coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct Monitor {
+    /// Connector name ("DP-1", "HDMI-A-2"); "default" for the single-screen map
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub primary: bool,
+    /// HiDPI scale factor (2.0 on a "200%" output); geometry stays in physical pixels
+    pub scale: f32,
+}
+
+impl Monitor {
+    pub fn new(name: &str, x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self { name: name.to_string(), x, y, width, height, primary: false, scale: 1.0 }
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Size in logical pixels, as toolkits lay windows out
+    pub fn logical_size(&self) -> (u32, u32) {
+        let scale = self.scale.max(1.0);
+        ((self.width as f32 / scale).round() as u32, (self.height as f32 / scale).round() as u32)
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.x + self.width as i32 && y >= self.y && y < self.y + self.height as i32
+    }
+}
+
+/// Every output of the desktop; never empty
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorMap {
+    monitors: Vec<Monitor>,
+}
+
+impl MonitorMap {
+    /// One screen at the origin (what WASMA assumed before monitors were known)
+    pub fn single(width: u32, height: u32) -> Self {
+        let mut monitor = Monitor::new("default", 0, 0, width, height);
+        monitor.primary = true;
+        Self { monitors: vec![monitor] }
+    }
+
+    /// None for an empty list. Without a primary output the first one becomes
+    /// primary; outputs are ordered left to right, then top to bottom
+    pub fn new(mut monitors: Vec<Monitor>) -> Option<Self> {
+        if monitors.is_empty() {
+            return None;
+        }
+        monitors.sort_by_key(|m| (m.x, m.y));
+        if !monitors.iter().any(|m| m.primary) {
+            monitors[0].primary = true;
+        }
+        Some(Self { monitors })
+    }
+
+    pub fn monitors(&self) -> &[Monitor] {
+        &self.monitors
+    }
+
+    pub fn len(&self) -> usize {
+        self.monitors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.monitors.is_empty()
+    }
+
+    pub fn primary(&self) -> &Monitor {
+        self.monitors.iter().find(|m| m.primary).unwrap_or(&self.monitors[0])
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Monitor> {
+        self.monitors.iter().find(|m| m.name == name)
+    }
+
+    pub fn monitor_at(&self, x: i32, y: i32) -> Option<&Monitor> {
+        self.monitors.iter().find(|m| m.contains(x, y))
+    }
+
+    /// Width and height of the box around all outputs, from the origin
+    pub fn desktop_size(&self) -> (u32, u32) {
+        let right = self.monitors.iter().map(|m| m.x + m.width as i32).max().unwrap_or(0);
+        let bottom = self.monitors.iter().map(|m| m.y + m.height as i32).max().unwrap_or(0);
+        (right.max(0) as u32, bottom.max(0) as u32)
+    }
+
+    /// Resize the primary output (the only one on a single-screen map)
+    pub fn resize_primary(&mut self, width: u32, height: u32) {
+        let index = self.monitors.iter().position(|m| m.primary).unwrap_or(0);
+        self.monitors[index].width = width;
+        self.monitors[index].height = height;
+    }
+
+    /// Outputs of the running session: Wayland first, then X11 RandR.
+    /// WASMA_SCALE replaces the detected scale factors
+    pub fn detect() -> Result<Self, String> {
+        let mut map = Self::enumerate()?;
+        if let Some(scale) = std::env::var(SCALE_ENV_VAR).ok().and_then(|v| v.trim().parse::<f32>().ok()).filter(|s| *s > 0.0) {
+            for monitor in &mut map.monitors {
+                monitor.scale = scale;
+            }
+        }
+        Ok(map)
+    }
+
+    fn enumerate() -> Result<Self, String> {
+        let mut errors = Vec::new();
+        #[cfg(feature = "wayland")]
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            match wayland::enumerate() {
+                Ok(monitors) => return Self::new(monitors).ok_or_else(|| "Wayland compositor has no outputs".to_string()),
+                Err(e) => errors.push(e),
+            }
+        }
+        #[cfg(feature = "x11")]
+        if std::env::var_os("DISPLAY").is_some() {
+            match xrandr::enumerate() {
+                Ok(monitors) => return Self::new(monitors).ok_or_else(|| "X server has no active monitors".to_string()),
+                Err(e) => errors.push(e),
+            }
+        }
+        if errors.is_empty() {
+            errors.push("no display server to ask for outputs".to_string());
+        }
+        Err(errors.join("; "))
+    }
+}
+
+/// Re-enumerates outputs in the background and reports every change
+pub struct MonitorWatcher;
+
+impl MonitorWatcher {
+    /// New maps are sent when outputs appear, vanish or change geometry;
+    /// the thread ends when the receiver is dropped
+    pub fn spawn(initial: MonitorMap, interval: Duration) -> Receiver<MonitorMap> {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut current = initial;
+            loop {
+                std::thread::sleep(interval);
+                let Ok(map) = MonitorMap::detect() else { continue };
+                if map != current {
+                    if tx.send(map.clone()).is_err() {
+                        return;
+                    }
+                    current = map;
+                }
+            }
+        });
+        rx
+    }
+}
+
+#[cfg(feature = "x11")]
+mod xrandr {
+    use super::Monitor;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::randr::ConnectionExt as RandrExt;
+    use x11rb::protocol::xproto::ConnectionExt as XprotoExt;
+
+    pub fn enumerate() -> Result<Vec<Monitor>, String> {
+        let (conn, screen_num) = x11rb::connect(None).map_err(|e| format!("X11 connection failed: {}", e))?;
+        let root = conn.setup().roots[screen_num].root;
+        // RRGetMonitors needs RandR 1.5
+        conn.randr_query_version(1, 5)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| format!("RandR unavailable: {}", e))?;
+        let reply = conn.randr_get_monitors(root, true)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| format!("RandR monitors: {}", e))?;
+
+        reply.monitors.into_iter().map(|info| {
+            let name = conn.get_atom_name(info.name)
+                .map_err(|e| e.to_string())?
+                .reply()
+                .map(|atom| String::from_utf8_lossy(&atom.name).into_owned())
+                .unwrap_or_else(|_| format!("monitor-{}", info.name));
+            let mut monitor = Monitor::new(&name, info.x as i32, info.y as i32, info.width as u32, info.height as u32)
+                .with_scale(scale_for_dpi(info.width, info.width_in_millimeters));
+            monitor.primary = info.primary;
+            Ok(monitor)
+        }).collect()
+    }
+
+    /// X has no per-output scale: whole multiples of 96 DPI, as winit guesses it
+    fn scale_for_dpi(width: u16, width_mm: u32) -> f32 {
+        if width_mm == 0 {
+            return 1.0;
+        }
+        let dpi = width as f32 * 25.4 / width_mm as f32;
+        (dpi / 96.0).round().clamp(1.0, 4.0)
+    }
+}
+
+#[cfg(feature = "wayland")]
+mod wayland {
+    use super::Monitor;
+    use wayland_client::globals::{registry_queue_init, GlobalListContents};
+    use wayland_client::protocol::{wl_output, wl_registry};
+    use wayland_client::{Connection, Dispatch, QueueHandle, WEnum};
+
+    #[derive(Default)]
+    struct Outputs {
+        monitors: Vec<Monitor>,
+    }
+
+    pub fn enumerate() -> Result<Vec<Monitor>, String> {
+        let conn = Connection::connect_to_env().map_err(|e| format!("Wayland connection failed: {}", e))?;
+        let (globals, mut queue) = registry_queue_init::<Outputs>(&conn)
+            .map_err(|e| format!("Wayland registry failed: {}", e))?;
+        let qh = queue.handle();
+
+        let mut state = Outputs::default();
+        let outputs: Vec<(u32, u32)> = globals.contents().with_list(|list| {
+            list.iter().filter(|g| g.interface == "wl_output").map(|g| (g.name, g.version)).collect()
+        });
+        for (index, (name, version)) in outputs.into_iter().enumerate() {
+            // v4 adds the connector name
+            globals.registry().bind::<wl_output::WlOutput, _, _>(name, version.min(4), &qh, index);
+            state.monitors.push(Monitor::new(&format!("output-{}", index), 0, 0, 0, 0));
+        }
+        // Geometry, mode and name arrive with the first roundtrip
+        queue.roundtrip(&mut state).map_err(|e| format!("Wayland roundtrip failed: {}", e))?;
+        Ok(state.monitors)
+    }
+
+    impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for Outputs {
+        fn event(_: &mut Self, _: &wl_registry::WlRegistry, _: wl_registry::Event, _: &GlobalListContents, _: &Connection, _: &QueueHandle<Self>) {}
+    }
+
+    impl Dispatch<wl_output::WlOutput, usize> for Outputs {
+        fn event(
+            state: &mut Self,
+            _: &wl_output::WlOutput,
+            event: wl_output::Event,
+            index: &usize,
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+            let Some(monitor) = state.monitors.get_mut(*index) else { return };
+            match event {
+                wl_output::Event::Geometry { x, y, .. } => {
+                    monitor.x = x;
+                    monitor.y = y;
+                }
+                wl_output::Event::Mode { flags: WEnum::Value(flags), width, height, .. } if flags.contains(wl_output::Mode::Current) => {
+                    monitor.width = width.max(0) as u32;
+                    monitor.height = height.max(0) as u32;
+                }
+                wl_output::Event::Scale { factor } => monitor.scale = factor.max(1) as f32,
+                wl_output::Event::Name { name } => monitor.name = name,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monitor_map() {
+        assert!(MonitorMap::new(Vec::new()).is_none());
+
+        let map = MonitorMap::new(vec![
+            Monitor::new("HDMI-A-1", 1920, 0, 1280, 1024),
+            Monitor::new("DP-1", 0, 0, 1920, 1080),
+        ]).unwrap();
+        assert_eq!(map.monitors()[0].name, "DP-1");
+        assert_eq!(map.primary().name, "DP-1");
+        assert_eq!(map.monitor_at(2000, 1000).map(|m| m.name.as_str()), Some("HDMI-A-1"));
+        assert_eq!(map.monitor_at(100, 1070).map(|m| m.name.as_str()), Some("DP-1"));
+        assert!(map.monitor_at(100, 1080).is_none());
+        assert_eq!(map.desktop_size(), (3200, 1080));
+
+        let retina = Monitor::new("eDP-1", 0, 0, 2880, 1800).with_scale(2.0);
+        assert_eq!(retina.logical_size(), (1440, 900));
+
+        let mut single = MonitorMap::single(800, 600);
+        single.resize_primary(1024, 768);
+        assert_eq!(single.desktop_size(), (1024, 768));
+    }
+}
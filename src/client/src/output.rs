@@ -0,0 +1,101 @@
+// output.rs
+// WASMA - Machine-readable CLI output
+// Shared `--format json|toml|text` handling for `wasma info`, `list` and `resources`
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::window_handling::{ResourceUsage, Window};
+use crate::workspaces::Workspace;
+
+#[derive(Debug, Error)]
+pub enum OutputError {
+    #[error("JSON serialization failed: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("TOML serialization failed: {0}")]
+    Toml(#[from] toml::ser::Error),
+    #[error("Text output is printed by the command itself")]
+    TextFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable (default)
+    #[default]
+    Text,
+    Json,
+    Toml,
+}
+
+impl OutputFormat {
+    pub fn is_text(&self) -> bool {
+        *self == OutputFormat::Text
+    }
+}
+
+/// One `wasma list` row; `resources` only with --detailed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowEntry {
+    #[serde(flatten)]
+    pub window: Window,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourceUsage>,
+}
+
+/// TOML needs a table at the top level, so lists are wrapped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowListing {
+    pub windows: Vec<WindowEntry>,
+}
+
+/// `wasma workspace list`, wrapped for TOML like WindowListing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceListing {
+    pub workspaces: Vec<Workspace>,
+}
+
+/// Serialize `value`; JSON is pretty-printed, TOML requires a struct at the top
+pub fn render<T: Serialize>(format: OutputFormat, value: &T) -> Result<String, OutputError> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(value)?),
+        OutputFormat::Toml => Ok(toml::to_string_pretty(value)?),
+        OutputFormat::Text => Err(OutputError::TextFormat),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window_handling::WindowHandler;
+    use crate::window_handling::WindowGeometry;
+    use wbackend::ResourceMode;
+
+    #[test]
+    fn test_render_listing() {
+        let handler = WindowHandler::new(ResourceMode::Auto);
+        let geometry = WindowGeometry { x: 0, y: 0, width: 320, height: 200 };
+        let id = handler.create_window(
+            "Listed".to_string(), "test.output".to_string(), geometry, None, ResourceMode::Auto,
+        ).unwrap();
+
+        let listing = WindowListing {
+            windows: handler.list_windows().into_iter()
+                .map(|window| WindowEntry {
+                    resources: handler.get_window_resource_usage(window.id).ok(),
+                    window,
+                })
+                .collect(),
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&render(OutputFormat::Json, &listing).unwrap()).unwrap();
+        assert_eq!(json["windows"][0]["id"], id);
+        assert_eq!(json["windows"][0]["app_id"], "test.output");
+        assert_eq!(json["windows"][0]["resources"]["assignment_id"], id);
+
+        let toml_out = render(OutputFormat::Toml, &listing).unwrap();
+        let parsed: toml::Value = toml::from_str(&toml_out).unwrap();
+        assert_eq!(parsed["windows"][0]["title"].as_str(), Some("Listed"));
+
+        assert!(render(OutputFormat::Text, &listing).is_err());
+    }
+}
@@ -7,6 +7,10 @@ use std::net::IpAddr;
 use std::path::Path;
 use thiserror::Error;
 use wbackend::ExecutionMode;
+use crate::metrics::MetricsConfig;
+use crate::tor::TorConfig;
+use crate::uclient::ReconnectPolicy;
+use crate::window_rules::WindowRule;
 
 #[derive(Debug, Error)]
 pub enum ParserError {
@@ -43,6 +47,12 @@ pub struct UriHandlingConfig {
     pub protocols: Vec<ProtocolConfig>,
     pub window_app_spec: String,
     pub compilation_server: Option<CompilationServer>,
+    /// Tor daemon used by tor:// protocols
+    #[serde(default)]
+    pub tor: TorConfig,
+    /// UClient retries after a lost connection
+    #[serde(default)]
+    pub reconnect: ReconnectPolicy,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +67,18 @@ pub struct UserConfig {
     pub groups_withed: Vec<String>,
 }
 
+/// What the window layer does when a window's resource lease runs out
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum LeaseExpiry {
+    /// Minimize the window; focusing it again re-acquires resources
+    #[default]
+    Suspend,
+    /// Close the window
+    Close,
+    /// Renew the lease automatically
+    Renew,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceLimits {
     pub ip_scope: String,
@@ -71,6 +93,78 @@ pub struct ResourceLimits {
     pub max_vram_mb: Option<u64>,
     #[serde(default)]
     pub cpu_cores: Vec<usize>,
+    /// Renderer contexts created ahead of time for new windows
+    #[serde(default = "default_context_pool")]
+    pub context_pool: usize,
+    /// Default assignment lease; None keeps the built-in 30s
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lease_seconds: Option<u64>,
+    #[serde(default)]
+    pub lease_expiry: LeaseExpiry,
+    /// Per-stream presentation buffer delay; None uses DEFAULT_LATENCY_BUDGET
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_budget_ms: Option<u64>,
+    /// Blend held frames towards the next one on jittery streams
+    #[serde(default)]
+    pub frame_interpolation: bool,
+    /// Pooled budgets shared by several windows
+    #[serde(default)]
+    pub quota_groups: Vec<QuotaGroupConfig>,
+}
+
+/// `quota_group : browser ram=4GB vram=1GB cores=4 apps=firefox,chromium evict`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct QuotaGroupConfig {
+    pub name: String,
+    pub ram_mb: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vram_mb: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_cores: Option<usize>,
+    /// App ids that join this group when their window is created
+    #[serde(default)]
+    pub apps: Vec<String>,
+    /// Close the group's least-recently-focused window instead of refusing a new one
+    #[serde(default)]
+    pub evict: bool,
+}
+
+impl QuotaGroupConfig {
+    /// Parse the value part of a `quota_group` line; sizes without a unit are MB
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.split_whitespace();
+        let mut group = QuotaGroupConfig { name: parts.next()?.to_string(), ..Default::default() };
+        for part in parts {
+            match part.split_once('=') {
+                Some(("ram", size)) => group.ram_mb = parse_size_mb(size)?,
+                Some(("vram", size)) => group.vram_mb = Some(parse_size_mb(size)?),
+                Some(("cores", cores)) => group.cpu_cores = Some(cores.parse().ok()?),
+                Some(("apps", apps)) => {
+                    group.apps = apps.split(',').map(str::trim).filter(|a| !a.is_empty()).map(String::from).collect()
+                }
+                None if part.eq_ignore_ascii_case("evict") => group.evict = true,
+                _ => return None,
+            }
+        }
+        (group.ram_mb > 0).then_some(group)
+    }
+
+    pub fn contains_app(&self, app_id: &str) -> bool {
+        self.apps.iter().any(|app| app == app_id)
+    }
+}
+
+/// "4GB" / "512MB" / "512" → MB
+fn parse_size_mb(size: &str) -> Option<u64> {
+    let upper = size.to_ascii_uppercase();
+    match upper.strip_suffix("GB").or_else(|| upper.strip_suffix('G')) {
+        Some(gb) => gb.parse::<u64>().ok().map(|gb| gb * 1024),
+        None => upper.trim_end_matches("MB").trim_end_matches('M').parse().ok(),
+    }
+}
+
+fn default_context_pool() -> usize {
+    crate::context_pool::DEFAULT_CONTEXT_POOL_SIZE
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,6 +172,23 @@ pub struct WasmaConfig {
     pub uri_handling: UriHandlingConfig,
     pub user_config: UserConfig,
     pub resource_limits: ResourceLimits,
+    /// `rule` lines, applied in order to each new window
+    #[serde(default)]
+    pub window_rules: Vec<WindowRule>,
+    /// Prometheus exporter, off unless `metrics_port` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<MetricsConfig>,
+}
+
+/// wasma.in.conf used when no path is given outside kiosk mode
+pub const DEFAULT_CONFIG_PATH: &str = "/etc/wasma/wasma.in.conf";
+
+/// wasma.in.conf in effect: the vendor copy in kiosk mode, else DEFAULT_CONFIG_PATH
+pub fn default_config_path() -> String {
+    match wsdg_xdg::WsdgEnv::new().kiosk() {
+        Some(policy) => policy.wasma_config().display().to_string(),
+        None => DEFAULT_CONFIG_PATH.to_string(),
+    }
 }
 
 /// Config Parser - Sadece dosya okuma ve parsing
@@ -87,7 +198,17 @@ pub struct ConfigParser {
 
 impl ConfigParser {
     pub fn new(config_path: Option<String>) -> Self {
-        let path = config_path.unwrap_or_else(|| "/etc/wasma/wasma.in.conf".to_string());
+        // Kiosk mode: only the read-only vendor config is honoured
+        let vendor = wsdg_xdg::WsdgEnv::new().kiosk().map(|policy| policy.wasma_config().display().to_string());
+        let path = match (config_path, vendor) {
+            (Some(path), Some(vendor)) if path != vendor => {
+                eprintln!("⚠️  Kiosk mode: ignoring {}, using {}", path, vendor);
+                vendor
+            }
+            (_, Some(vendor)) => vendor,
+            (Some(path), None) => path,
+            (None, None) => DEFAULT_CONFIG_PATH.to_string(),
+        };
         Self { config_path: path }
     }
 
@@ -118,7 +239,18 @@ impl ConfigParser {
         let mut execution_mode = None;
         let mut max_memory_mb = None;
         let mut max_vram_mb = None;
+        let mut context_pool = default_context_pool();
+        let mut lease_seconds = None;
+        let mut lease_expiry = LeaseExpiry::default();
+        let mut latency_budget_ms = None;
+        let mut frame_interpolation = false;
+        let mut quota_groups = Vec::new();
+        let mut window_rules = Vec::new();
         let mut cpu_cores = Vec::new();
+        let mut tor = TorConfig::default();
+        let mut reconnect = ReconnectPolicy::default();
+        let mut metrics_port = None;
+        let mut metrics_bind = MetricsConfig::default().bind;
 
         for line in content.lines() {
             let line = line.trim();
@@ -127,6 +259,62 @@ impl ConfigParser {
                 continue;
             }
 
+            if line.starts_with("quota_group") {
+                if let Some(group) = self.extract_value(line) {
+                    let group = QuotaGroupConfig::parse(group)
+                        .ok_or_else(|| ParserError::ParseError(format!("Invalid quota group: {}", group)))?;
+                    quota_groups.push(group);
+                }
+                continue;
+            }
+
+            // `rule app_id=firefox -> workspace=2, maximized`; the ':' is optional
+            if let Some(rule) = line.strip_prefix("rule").filter(|rest| rest.starts_with([' ', ':'])) {
+                let rule = rule.trim_start().trim_start_matches(':').split("*//").next().unwrap_or_default();
+                window_rules.push(WindowRule::parse(rule.trim()).map_err(ParserError::ParseError)?);
+                continue;
+            }
+
+            // tor_control_cookie before tor_control; both before the generic keys below
+            if let Some(key @ ("tor_socks" | "tor_control" | "tor_control_cookie")) = line.split([' ', ':']).next() {
+                if let Some(value) = self.extract_value(line) {
+                    match key {
+                        "tor_control_cookie" => tor.control_cookie = Some(value.into()),
+                        _ => {
+                            let addr = value.parse()
+                                .map_err(|e| ParserError::ParseError(format!("Invalid {} address {}: {}", key, value, e)))?;
+                            if key == "tor_socks" { tor.socks = addr } else { tor.control = Some(addr) }
+                        }
+                    }
+                }
+                continue;
+            }
+
+            if let Some(key @ ("reconnect_max_retries" | "reconnect_backoff_ms" | "reconnect_backoff_max_ms")) = line.split([' ', ':']).next() {
+                if let Some(value) = self.extract_value(line) {
+                    let invalid = |e: std::num::ParseIntError| ParserError::ParseError(format!("Invalid {} {}: {}", key, value, e));
+                    match key {
+                        "reconnect_max_retries" => reconnect.max_retries = value.parse().map_err(invalid)?,
+                        "reconnect_backoff_ms" => reconnect.backoff_ms = value.parse().map_err(invalid)?,
+                        _ => reconnect.max_backoff_ms = value.parse().map_err(invalid)?,
+                    }
+                }
+                continue;
+            }
+
+            if let Some(key @ ("metrics_port" | "metrics_bind")) = line.split([' ', ':']).next() {
+                if let Some(value) = self.extract_value(line) {
+                    if key == "metrics_port" {
+                        metrics_port = Some(value.parse()
+                            .map_err(|e| ParserError::ParseError(format!("Invalid metrics_port {}: {}", value, e)))?);
+                    } else {
+                        metrics_bind = value.parse()
+                            .map_err(|e| ParserError::ParseError(format!("Invalid metrics_bind {}: {}", value, e)))?;
+                    }
+                }
+                continue;
+            }
+
             if line.contains("multi_instances") {
                 multi_instances = line.contains("true");
             }
@@ -255,6 +443,40 @@ impl ConfigParser {
                 }
             }
 
+            if line.contains("context_pool") {
+                if let Some(pool_str) = self.extract_value(line) {
+                    context_pool = pool_str.parse().unwrap_or(context_pool);
+                }
+            }
+
+            if line.contains("lease_seconds") {
+                if let Some(secs_str) = self.extract_value(line) {
+                    lease_seconds = secs_str.parse().ok().filter(|&secs| secs > 0);
+                }
+            }
+
+            if line.contains("lease_expiry") {
+                if let Some(action) = self.extract_value(line) {
+                    lease_expiry = match action.to_lowercase().as_str() {
+                        "close" | "kill" => LeaseExpiry::Close,
+                        "renew" => LeaseExpiry::Renew,
+                        _ => LeaseExpiry::Suspend,
+                    };
+                }
+            }
+
+            if line.contains("latency_budget_ms") {
+                if let Some(ms_str) = self.extract_value(line) {
+                    latency_budget_ms = ms_str.parse().ok().filter(|&ms| ms > 0);
+                }
+            }
+
+            if line.contains("frame_interpolation") {
+                if let Some(flag) = self.extract_value(line) {
+                    frame_interpolation = flag.eq_ignore_ascii_case("true");
+                }
+            }
+
             if line.contains("cpu_cores") {
                 if let Some(cores_str) = self.extract_value(line) {
                     cpu_cores = cores_str
@@ -272,6 +494,8 @@ impl ConfigParser {
                 protocols,
                 window_app_spec,
                 compilation_server,
+                tor,
+                reconnect,
             },
             user_config: UserConfig {
                 user_withed,
@@ -285,7 +509,15 @@ impl ConfigParser {
                 max_memory_mb,
                 max_vram_mb,
                 cpu_cores,
+                context_pool,
+                lease_seconds,
+                lease_expiry,
+                latency_budget_ms,
+                frame_interpolation,
+                quota_groups,
             },
+            window_rules,
+            metrics: metrics_port.map(|port| MetricsConfig { bind: metrics_bind, port }),
         })
     }
 
@@ -295,6 +527,8 @@ impl ConfigParser {
 multi_instances = false;
 singularity_instances = true;
 protocol_def : http://127.0.0.1:8080
+reconnect_max_retries : 5
+reconnect_backoff_ms : 500
 uri_handling_window_appspef : file://server_request/request.manifest
 #*_END_BLOCK_DEFINE
 uO:?? user_withed(*sysuser)
@@ -303,6 +537,14 @@ r0:?? in_limited_scope:ip_base10 in_scoped_bylevel:50 in_request_withed:glx_rend
 execution_mode : gpu_preferred
 max_memory_mb : 512
 max_vram_mb : 256
+context_pool : 2
+lease_seconds : 30
+lease_expiry : suspend
+latency_budget_ms : 50
+frame_interpolation : false
+quota_group : browser ram=4GB apps=firefox,chromium evict
+# rule app_id=firefox -> workspace=2, maximized, gpu_preferred
+# metrics_port : 9464
 }"#.to_string()
     }
 
@@ -332,15 +574,23 @@ max_vram_mb : 256
                     format!("Port must be between 1-65535, got {}", proto.port)
                 ));
             }
+            // Anything but Tor would hand the name to the local resolver
+            let onion = proto.domain.as_deref().is_some_and(|d| d.ends_with(".onion"));
+            if onion && proto.protocol != Protocol::Tor {
+                return Err(ParserError::InvalidConfig(
+                    format!("{} is only reachable over tor://", proto.domain.as_deref().unwrap_or_default())
+                ));
+            }
         }
 
         Ok(())
     }
 
 // Satır 341-345: Some() ile sar
+// Values may contain ':' themselves (tor://127.0.0.1:80), split at the first one only
 fn extract_value<'a>(&self, line: &'a str) -> Option<&'a str> {
-    Some(line.split(':')
-        .nth(1)?
+    Some(line.split_once(':')?
+        .1
         .split("*//")
         .next()?
         .trim())
@@ -389,6 +639,24 @@ mod tests {
         assert!(parser.validate(&config).is_ok());
     }
 
+    #[test]
+    fn test_tor_config_parsing() {
+        let parser = ConfigParser::new(None);
+        let config = parser.parse(
+            "protocol_def : tor://127.0.0.1:80\ndomain_def : example.onion\ntor_socks : 127.0.0.1:9150\ntor_control : 127.0.0.1:9151\n"
+        ).unwrap();
+        let proto = &config.uri_handling.protocols[0];
+        assert_eq!(proto.protocol, Protocol::Tor);
+        assert_eq!(proto.domain.as_deref(), Some("example.onion"));
+        assert_eq!(config.uri_handling.tor.socks, "127.0.0.1:9150".parse().unwrap());
+        assert_eq!(config.uri_handling.tor.control, Some("127.0.0.1:9151".parse().unwrap()));
+        assert!(parser.validate(&config).is_ok());
+
+        // An onion name over plain HTTP would go to the local resolver
+        let config = parser.parse("protocol_def : http://127.0.0.1:80\ndomain_def : example.onion\n").unwrap();
+        assert!(parser.validate(&config).is_err());
+    }
+
     #[test]
     fn test_execution_mode_parsing() {
         let parser = ConfigParser::new(None);
@@ -397,4 +665,23 @@ mod tests {
         
         assert_eq!(config.resource_limits.execution_mode, Some(ExecutionMode::Hybrid));
     }
+
+    #[test]
+    fn test_quota_group_parsing() {
+        let parser = ConfigParser::new(None);
+        let config_content = "quota_group : browser ram=4GB vram=1024 cores=4 apps=firefox,chromium evict\n\
+                              quota_group : tools ram=512MB\n";
+        let groups = parser.parse(config_content).unwrap().resource_limits.quota_groups;
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].name, "browser");
+        assert_eq!(groups[0].ram_mb, 4096);
+        assert_eq!(groups[0].vram_mb, Some(1024));
+        assert_eq!(groups[0].cpu_cores, Some(4));
+        assert!(groups[0].contains_app("chromium"));
+        assert!(groups[0].evict);
+        assert_eq!(groups[1], QuotaGroupConfig { name: "tools".to_string(), ram_mb: 512, ..Default::default() });
+
+        assert!(parser.parse("quota_group : empty\n").is_err());
+    }
 }
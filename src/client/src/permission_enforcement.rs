@@ -0,0 +1,438 @@
+// WASMA - Permission Enforcement
+// A window's PermissionScope used to be stored and never looked at. The
+// enforcer turns it into restrictions on the process launched for the window:
+// a private network namespace when network access is denied, a landlock
+// ruleset when filesystem access is denied, and process hardening by
+// sandbox_level. Every step runs in the child right before exec and reports
+// back over a pipe, so the report says what was actually restricted rather
+// than what was asked for.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use wsdg_xdg::sandbox::{landlock_restrict_self, landlock_ruleset, SYSTEM_READ_PATHS, SYSTEM_WRITE_PATHS};
+
+use crate::window_handling::PermissionScope;
+
+/// sandbox_level from which the process can never gain privileges (setuid, file caps)
+pub const NO_NEW_PRIVS_LEVEL: u8 = 1;
+/// sandbox_level from which core dumps are disabled
+pub const NO_CORE_DUMPS_LEVEL: u8 = 3;
+/// sandbox_level from which the process gets its own session (no controlling terminal)
+pub const NEW_SESSION_LEVEL: u8 = 5;
+
+// Child → parent status record: restriction code, status, errno
+const RECORD_LEN: usize = 6;
+const STATUS_FAILED: u8 = 0;
+const STATUS_APPLIED: u8 = 1;
+const STATUS_APPLIED_USERNS: u8 = 2;
+
+// ============================================================================
+// REPORT
+// ============================================================================
+
+/// One restriction the enforcer can put on a launched process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Restriction {
+    Network,
+    Filesystem,
+    NoNewPrivileges,
+    NoCoreDumps,
+    NewSession,
+}
+
+impl Restriction {
+    pub const ALL: [Restriction; 5] = [
+        Restriction::Network,
+        Restriction::Filesystem,
+        Restriction::NoNewPrivileges,
+        Restriction::NoCoreDumps,
+        Restriction::NewSession,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Restriction::Network => "network",
+            Restriction::Filesystem => "filesystem",
+            Restriction::NoNewPrivileges => "no-new-privileges",
+            Restriction::NoCoreDumps => "no-core-dumps",
+            Restriction::NewSession => "new-session",
+        }
+    }
+
+    fn code(self) -> u8 {
+        Self::ALL.iter().position(|r| *r == self).unwrap() as u8
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
+}
+
+/// What happened to one restriction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    /// In effect on the running process, with the mechanism used
+    Applied(String),
+    /// Required but could not be put in place; the process runs without it
+    Failed(String),
+    /// Not required by the permission scope
+    NotRequired(String),
+}
+
+/// What the enforcer actually did to a launched process
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnforcementReport {
+    pub pid: Option<u32>,
+    pub entries: Vec<(Restriction, Outcome)>,
+}
+
+impl EnforcementReport {
+    pub fn outcome(&self, restriction: Restriction) -> Option<&Outcome> {
+        self.entries.iter().find(|(r, _)| *r == restriction).map(|(_, o)| o)
+    }
+
+    pub fn is_applied(&self, restriction: Restriction) -> bool {
+        matches!(self.outcome(restriction), Some(Outcome::Applied(_)))
+    }
+
+    /// Restrictions the scope asked for that are not in effect
+    pub fn failures(&self) -> Vec<(Restriction, &str)> {
+        self.entries
+            .iter()
+            .filter_map(|(r, o)| match o {
+                Outcome::Failed(reason) => Some((*r, reason.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn set(&mut self, restriction: Restriction, outcome: Outcome) {
+        match self.entries.iter_mut().find(|(r, _)| *r == restriction) {
+            Some(entry) => entry.1 = outcome,
+            None => self.entries.push((restriction, outcome)),
+        }
+    }
+}
+
+impl fmt::Display for EnforcementReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (restriction, outcome) in &self.entries {
+            match outcome {
+                Outcome::Applied(how) => writeln!(f, "✅ {}: {}", restriction.name(), how)?,
+                Outcome::Failed(why) => writeln!(f, "❌ {}: {}", restriction.name(), why)?,
+                Outcome::NotRequired(why) => writeln!(f, "➖ {}: {}", restriction.name(), why)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// ENFORCER
+// ============================================================================
+
+/// Applies a PermissionScope to a `Command` before it is spawned
+#[derive(Debug, Clone)]
+pub struct PermissionEnforcer {
+    scope: PermissionScope,
+    readable: Vec<PathBuf>,
+    writable: Vec<PathBuf>,
+}
+
+impl PermissionEnforcer {
+    /// Enforcer with the system paths every process may read and write
+    pub fn new(scope: &PermissionScope) -> Self {
+        let mut writable: Vec<PathBuf> = SYSTEM_WRITE_PATHS.iter().map(PathBuf::from).collect();
+        if let Ok(runtime) = std::env::var("XDG_RUNTIME_DIR") {
+            writable.push(PathBuf::from(runtime));
+        }
+        Self {
+            scope: scope.clone(),
+            readable: SYSTEM_READ_PATHS.iter().map(PathBuf::from).collect(),
+            writable,
+        }
+    }
+
+    /// Give the app its program directory and its own config/data/cache directories
+    pub fn for_app(self, app_id: &str, program: &Path) -> Self {
+        let mut enforcer = self;
+        if let Some(dir) = program.parent().filter(|d| !d.as_os_str().is_empty()) {
+            enforcer = enforcer.allow_read(dir);
+        }
+        for dir in app_dirs(app_id) {
+            enforcer = enforcer.allow_write(dir);
+        }
+        enforcer
+    }
+
+    pub fn allow_read(mut self, path: impl Into<PathBuf>) -> Self {
+        self.readable.push(path.into());
+        self
+    }
+
+    pub fn allow_write(mut self, path: impl Into<PathBuf>) -> Self {
+        self.writable.push(path.into());
+        self
+    }
+
+    /// Restrictions the scope asks for
+    pub fn required(&self) -> Vec<Restriction> {
+        Restriction::ALL.into_iter().filter(|r| self.not_required_reason(*r).is_none()).collect()
+    }
+
+    fn not_required_reason(&self, restriction: Restriction) -> Option<String> {
+        let level = self.scope.sandbox_level;
+        let below = |min: u8| (level < min).then(|| format!("sandbox_level {} < {}", level, min));
+        match restriction {
+            Restriction::Network => self.scope.can_access_network.then(|| "network access granted".to_string()),
+            Restriction::Filesystem => self.scope.can_access_filesystem.then(|| "filesystem access granted".to_string()),
+            // Landlock needs no_new_privs whatever the level
+            Restriction::NoNewPrivileges if !self.scope.can_access_filesystem => None,
+            Restriction::NoNewPrivileges => below(NO_NEW_PRIVS_LEVEL),
+            Restriction::NoCoreDumps => below(NO_CORE_DUMPS_LEVEL),
+            Restriction::NewSession => below(NEW_SESSION_LEVEL),
+        }
+    }
+
+    /// Install the restrictions as a pre-exec hook on `command`. Call
+    /// `PendingEnforcement::finish` once the command has been spawned.
+    pub fn apply(&self, command: &mut Command) -> PendingEnforcement {
+        let mut report = EnforcementReport::default();
+        let required = self.required();
+        for restriction in Restriction::ALL {
+            let outcome = match self.not_required_reason(restriction) {
+                Some(reason) => Outcome::NotRequired(reason),
+                None => Outcome::Failed("process was not launched".to_string()),
+            };
+            report.set(restriction, outcome);
+        }
+        if required.is_empty() {
+            return PendingEnforcement { report, status: None, status_writer: None, ruleset: None };
+        }
+
+        // Landlock rules are built here; the child only has to restrict itself
+        let ruleset = if required.contains(&Restriction::Filesystem) {
+            match landlock_ruleset(&self.readable, &self.writable) {
+                Ok(fd) => Some(fd),
+                Err(e) => {
+                    report.set(Restriction::Filesystem, Outcome::Failed(format!("landlock unavailable: {}", e)));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let (status, status_writer) = match status_pipe() {
+            Ok(pipe) => pipe,
+            Err(e) => {
+                // No pid yet: the command has not been spawned
+                tracing::warn!(program = ?command.get_program(), error = %e, "permission enforcement status pipe failed");
+                return PendingEnforcement { report, status: None, status_writer: None, ruleset };
+            }
+        };
+
+        // Everything the child touches is prepared up front: no allocation after fork
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let uid_map = format!("{} {} 1\n", uid, uid).into_bytes();
+        let gid_map = format!("{} {} 1\n", gid, gid).into_bytes();
+        let writer = status_writer.as_raw_fd();
+        let ruleset_fd = ruleset.as_ref().map(|fd| fd.as_raw_fd());
+        let child_steps = required.clone();
+
+        unsafe {
+            command.pre_exec(move || {
+                for restriction in &child_steps {
+                    let (status, errno) = match restriction {
+                        Restriction::Network => isolate_network(&uid_map, &gid_map),
+                        Restriction::Filesystem => match ruleset_fd {
+                            Some(fd) => match landlock_restrict_self(fd) {
+                                Ok(()) => (STATUS_APPLIED, 0),
+                                Err(errno) => (STATUS_FAILED, errno),
+                            },
+                            None => continue,
+                        },
+                        Restriction::NoNewPrivileges => {
+                            check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0))
+                        }
+                        Restriction::NoCoreDumps => {
+                            let limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+                            check(libc::setrlimit(libc::RLIMIT_CORE, &limit))
+                        }
+                        Restriction::NewSession => check(libc::setsid()),
+                    };
+                    write_record(writer, restriction.code(), status, errno);
+                }
+                Ok(())
+            });
+        }
+
+        PendingEnforcement { report, status: Some(status), status_writer: Some(status_writer), ruleset }
+    }
+}
+
+/// Restrictions installed on a command that has not reported back yet
+pub struct PendingEnforcement {
+    report: EnforcementReport,
+    status: Option<OwnedFd>,
+    status_writer: Option<OwnedFd>,
+    ruleset: Option<OwnedFd>,
+}
+
+impl PendingEnforcement {
+    /// Collect what the spawned child applied. Only call after a successful spawn.
+    pub fn finish(mut self, pid: u32) -> EnforcementReport {
+        self.report.pid = Some(pid);
+        // Our copy of the write end must go for the read to see EOF at exec
+        self.status_writer.take();
+        self.ruleset.take();
+
+        let Some(status) = self.status.take() else {
+            return self.report;
+        };
+        let mut records = Vec::new();
+        if let Err(e) = File::from(status).read_to_end(&mut records) {
+            tracing::warn!(pid, error = %e, "permission enforcement status unreadable");
+        }
+
+        for record in records.chunks_exact(RECORD_LEN) {
+            let Some(restriction) = Restriction::from_code(record[0]) else { continue };
+            let errno = i32::from_le_bytes([record[2], record[3], record[4], record[5]]);
+            let outcome = match record[1] {
+                STATUS_APPLIED => Outcome::Applied(mechanism(restriction, false).to_string()),
+                STATUS_APPLIED_USERNS => Outcome::Applied(mechanism(restriction, true).to_string()),
+                _ => Outcome::Failed(io::Error::from_raw_os_error(errno).to_string()),
+            };
+            self.report.set(restriction, outcome);
+        }
+        self.report
+    }
+
+    /// Report as it stands before the child ran (planned and pre-spawn failures)
+    pub fn report(&self) -> &EnforcementReport {
+        &self.report
+    }
+}
+
+fn mechanism(restriction: Restriction, via_user_namespace: bool) -> &'static str {
+    match restriction {
+        Restriction::Network if via_user_namespace => "private network namespace (user namespace)",
+        Restriction::Network => "private network namespace",
+        Restriction::Filesystem => "landlock ruleset",
+        Restriction::NoNewPrivileges => "PR_SET_NO_NEW_PRIVS",
+        Restriction::NoCoreDumps => "RLIMIT_CORE = 0",
+        Restriction::NewSession => "setsid",
+    }
+}
+
+/// Per-app directories under $XDG_CONFIG_HOME, $XDG_DATA_HOME and $XDG_CACHE_HOME
+fn app_dirs(app_id: &str) -> Vec<PathBuf> {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    let base = |var: &str, fallback: &str| {
+        std::env::var(var).map(PathBuf::from).unwrap_or_else(|_| Path::new(&home).join(fallback))
+    };
+    vec![
+        base("XDG_CONFIG_HOME", ".config").join(app_id),
+        base("XDG_DATA_HOME", ".local/share").join(app_id),
+        base("XDG_CACHE_HOME", ".cache").join(app_id),
+    ]
+}
+
+// ============================================================================
+// SYSCALLS
+// ============================================================================
+
+fn status_pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0 as RawFd; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    unsafe { Ok((OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1]))) }
+}
+
+// The functions below run in the forked child: raw syscalls only
+
+fn errno() -> i32 {
+    unsafe { *libc::__errno_location() }
+}
+
+fn check(result: libc::c_int) -> (u8, i32) {
+    if result < 0 { (STATUS_FAILED, errno()) } else { (STATUS_APPLIED, 0) }
+}
+
+/// New network namespace; unprivileged callers get one inside a user
+/// namespace that maps their own uid/gid
+fn isolate_network(uid_map: &[u8], gid_map: &[u8]) -> (u8, i32) {
+    unsafe {
+        if libc::unshare(libc::CLONE_NEWNET) == 0 {
+            return (STATUS_APPLIED, 0);
+        }
+        if libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) != 0 {
+            return (STATUS_FAILED, errno());
+        }
+    }
+    // Without the maps the process runs as nobody; isolation holds either way
+    write_file(b"/proc/self/setgroups\0", b"deny");
+    write_file(b"/proc/self/uid_map\0", uid_map);
+    write_file(b"/proc/self/gid_map\0", gid_map);
+    (STATUS_APPLIED_USERNS, 0)
+}
+
+// `path` is NUL-terminated
+fn write_file(path: &[u8], content: &[u8]) {
+    unsafe {
+        let fd = libc::open(path.as_ptr().cast(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd >= 0 {
+            libc::write(fd, content.as_ptr().cast(), content.len());
+            libc::close(fd);
+        }
+    }
+}
+
+fn write_record(fd: RawFd, code: u8, status: u8, errno: i32) {
+    let e = errno.to_le_bytes();
+    let record = [code, status, e[0], e[1], e[2], e[3]];
+    unsafe {
+        libc::write(fd, record.as_ptr().cast(), RECORD_LEN);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enforcement_report() {
+        let scope = PermissionScope {
+            can_access_network: true,
+            can_access_filesystem: true,
+            sandbox_level: 3,
+            ..PermissionScope::default()
+        };
+        let enforcer = PermissionEnforcer::new(&scope);
+        assert_eq!(enforcer.required(), vec![Restriction::NoNewPrivileges, Restriction::NoCoreDumps]);
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "ulimit -c"]).stdout(std::process::Stdio::piped());
+        let pending = enforcer.apply(&mut command);
+        let child = command.spawn().unwrap();
+        let report = pending.finish(child.id());
+        let output = child.wait_with_output().unwrap();
+
+        // Reported from the child, and really in effect
+        assert!(report.is_applied(Restriction::NoCoreDumps));
+        assert!(report.is_applied(Restriction::NoNewPrivileges));
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "0");
+        assert!(matches!(report.outcome(Restriction::Network), Some(Outcome::NotRequired(_))));
+        assert!(matches!(report.outcome(Restriction::NewSession), Some(Outcome::NotRequired(_))));
+        assert!(report.failures().is_empty());
+    }
+}
@@ -0,0 +1,543 @@
+// WASMA - Pixel formats & frame headers
+// Display streams describe their frames instead of being treated as 1280-wide RGBA:
+// the client offers the formats it can convert, the server answers with the one
+// it will send, and every frame then arrives behind a header.
+//   hello   client → server  "WFMT" version(1) count(1) format codes(count)
+//   accept  server → client  "WFMT" version(1) format code(1)      (0: nothing acceptable)
+//   frame   server → client  "WFRM" width(4) height(4) stride(4) format(1) damage(1) reserved(2) length(4)
+//                            damage rects(damage × 16) payload
+// Damage rects (x, y, width, height) are the regions that changed since the
+// previous frame of the stream; none means the whole frame changed.
+// Integers are big-endian. Frames are converted to packed BGRA before a renderer
+// sees them; `encode_frame` output is the message format inside WGClient's mux.
+
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+pub const FORMAT_MAGIC: &[u8; 4] = b"WFMT";
+pub const FRAME_MAGIC: &[u8; 4] = b"WFRM";
+pub const FORMAT_VERSION: u8 = 1;
+/// Largest frame payload accepted (a 4K RGBA frame is ~32 MiB)
+pub const MAX_FRAME_SIZE: usize = 64 * 1024 * 1024;
+/// More damage than this is sent as a whole-frame update
+pub const MAX_DAMAGE_RECTS: usize = 255;
+
+#[derive(Debug, Error)]
+pub enum FormatError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Unexpected record {0:?}")]
+    BadMagic([u8; 4]),
+
+    #[error("Unsupported frame protocol version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Unknown pixel format code {0}")]
+    UnknownFormat(u8),
+
+    #[error("Server accepted none of the offered pixel formats")]
+    NoCommonFormat,
+
+    #[error("Invalid frame layout: {0}")]
+    Layout(String),
+}
+
+// ============================================================================
+// PIXEL FORMAT
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    Rgba8888,
+    Bgra8888,
+    Rgb888,
+    Rgb565,
+    /// Y plane followed by an interleaved half-resolution UV plane (BT.601)
+    Nv12,
+}
+
+impl PixelFormat {
+    /// Everything `to_bgra` converts, in order of preference
+    pub const ALL: [PixelFormat; 5] = [Self::Bgra8888, Self::Rgba8888, Self::Rgb888, Self::Nv12, Self::Rgb565];
+
+    /// Wire code, shared with the gRPC schema (proto/wasma_display.proto)
+    pub fn code(self) -> u8 {
+        match self {
+            Self::Rgba8888 => 1,
+            Self::Bgra8888 => 2,
+            Self::Rgb888 => 3,
+            Self::Rgb565 => 4,
+            Self::Nv12 => 5,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.code() == code)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rgba8888 => "rgba32",
+            Self::Bgra8888 => "bgra32",
+            Self::Rgb888 => "rgb24",
+            Self::Rgb565 => "rgb565",
+            Self::Nv12 => "nv12",
+        }
+    }
+
+    /// Bytes of the shortest valid row (the UV row for NV12)
+    pub fn row_bytes(self, width: u32) -> usize {
+        let width = width as usize;
+        match self {
+            Self::Rgba8888 | Self::Bgra8888 => width * 4,
+            Self::Rgb888 => width * 3,
+            Self::Rgb565 => width * 2,
+            Self::Nv12 => width.div_ceil(2) * 2,
+        }
+    }
+
+    /// Rows of `stride` bytes a frame of `height` occupies
+    fn rows(self, height: u32) -> usize {
+        match self {
+            Self::Nv12 => height as usize + (height as usize).div_ceil(2),
+            _ => height as usize,
+        }
+    }
+}
+
+// ============================================================================
+// FRAME HEADER
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    pub width: u32,
+    pub height: u32,
+    pub stride: u32,
+    pub format: PixelFormat,
+}
+
+impl FrameHeader {
+    pub const LEN: usize = 24;
+
+    /// Header of a packed BGRA frame, what `to_bgra` produces
+    pub fn bgra(width: u32, height: u32) -> Self {
+        Self { width, height, stride: width * 4, format: PixelFormat::Bgra8888 }
+    }
+
+    /// Bytes of pixel data the frame needs (the last row need not be padded)
+    pub fn required_len(&self) -> usize {
+        self.stride as usize * (self.format.rows(self.height) - 1) + self.format.row_bytes(self.width)
+    }
+
+    pub fn validate(&self, payload_len: usize) -> Result<(), FormatError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(FormatError::Layout(format!("empty frame {}x{}", self.width, self.height)));
+        }
+        let row = self.format.row_bytes(self.width);
+        if (self.stride as usize) < row {
+            return Err(FormatError::Layout(format!("stride {} shorter than a {}-byte {} row", self.stride, row, self.format.name())));
+        }
+        if payload_len > MAX_FRAME_SIZE {
+            return Err(FormatError::Layout(format!("{}-byte frame exceeds {} bytes", payload_len, MAX_FRAME_SIZE)));
+        }
+        if payload_len < self.required_len() {
+            return Err(FormatError::Layout(format!("{} bytes of pixel data, {} needed", payload_len, self.required_len())));
+        }
+        Ok(())
+    }
+
+    pub fn encode(&self, payload_len: usize) -> [u8; Self::LEN] {
+        let mut out = [0u8; Self::LEN];
+        out[0..4].copy_from_slice(FRAME_MAGIC);
+        out[4..8].copy_from_slice(&self.width.to_be_bytes());
+        out[8..12].copy_from_slice(&self.height.to_be_bytes());
+        out[12..16].copy_from_slice(&self.stride.to_be_bytes());
+        out[16] = self.format.code();
+        out[20..24].copy_from_slice(&(payload_len as u32).to_be_bytes());
+        out
+    }
+
+    /// Header and announced payload length
+    pub fn decode(buf: &[u8; Self::LEN]) -> Result<(Self, usize), FormatError> {
+        let magic: [u8; 4] = buf[0..4].try_into().unwrap();
+        if &magic != FRAME_MAGIC {
+            return Err(FormatError::BadMagic(magic));
+        }
+        let be = |at: usize| u32::from_be_bytes(buf[at..at + 4].try_into().unwrap());
+        let format = PixelFormat::from_code(buf[16]).ok_or(FormatError::UnknownFormat(buf[16]))?;
+        let header = Self { width: be(4), height: be(8), stride: be(12), format };
+        let len = be(20) as usize;
+        header.validate(len)?;
+        Ok((header, len))
+    }
+
+    /// Number of damage rects between a header and its payload
+    pub fn damage_count(buf: &[u8; Self::LEN]) -> usize {
+        buf[17] as usize
+    }
+
+    /// Split an `encode_frame` message into header and pixels
+    pub fn split(message: &[u8]) -> Result<(Self, &[u8]), FormatError> {
+        Self::split_damaged(message).map(|(header, _, pixels)| (header, pixels))
+    }
+
+    /// Like `split`, keeping the damage rects (empty: the whole frame changed)
+    pub fn split_damaged(message: &[u8]) -> Result<(Self, Vec<DamageRect>, &[u8]), FormatError> {
+        let head: &[u8; Self::LEN] = message.get(..Self::LEN)
+            .and_then(|h| h.try_into().ok())
+            .ok_or_else(|| FormatError::Layout(format!("{}-byte message has no frame header", message.len())))?;
+        let (header, len) = Self::decode(head)?;
+        let rects = Self::damage_count(head) * DamageRect::LEN;
+        let body = &message[Self::LEN..];
+        if body.len() < rects + len {
+            return Err(FormatError::Layout(format!("frame announces {} bytes, {} present", rects + len, body.len())));
+        }
+        Ok((header, DamageRect::decode_all(&body[..rects]), &body[rects..rects + len]))
+    }
+}
+
+// ============================================================================
+// DAMAGE
+// ============================================================================
+
+/// Region of a frame that changed since the previous one, in frame pixels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DamageRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl DamageRect {
+    pub const LEN: usize = 16;
+
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// All of a `width`x`height` frame
+    pub fn full(width: u32, height: u32) -> Self {
+        Self::new(0, 0, width, height)
+    }
+
+    pub fn area(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    /// The part inside a `width`x`height` frame; None when nothing is left
+    pub fn clip(&self, width: u32, height: u32) -> Option<Self> {
+        let right = self.x.saturating_add(self.width).min(width);
+        let bottom = self.y.saturating_add(self.height).min(height);
+        (right > self.x && bottom > self.y).then(|| Self::new(self.x, self.y, right - self.x, bottom - self.y))
+    }
+
+    fn encode(&self) -> [u8; Self::LEN] {
+        let mut out = [0u8; Self::LEN];
+        for (chunk, value) in out.chunks_exact_mut(4).zip([self.x, self.y, self.width, self.height]) {
+            chunk.copy_from_slice(&value.to_be_bytes());
+        }
+        out
+    }
+
+    fn decode_all(buf: &[u8]) -> Vec<Self> {
+        buf.chunks_exact(Self::LEN)
+            .map(|rect| {
+                let be = |at: usize| u32::from_be_bytes(rect[at..at + 4].try_into().unwrap());
+                Self::new(be(0), be(4), be(8), be(12))
+            })
+            .collect()
+    }
+}
+
+/// Header + payload as one message
+pub fn encode_frame(header: &FrameHeader, payload: &[u8]) -> Vec<u8> {
+    encode_frame_damaged(header, &[], payload)
+}
+
+/// Header + damage rects + payload; too many rects are sent as a whole-frame update
+pub fn encode_frame_damaged(header: &FrameHeader, damage: &[DamageRect], payload: &[u8]) -> Vec<u8> {
+    let damage = if damage.len() > MAX_DAMAGE_RECTS { &[] } else { damage };
+    let mut out = Vec::with_capacity(FrameHeader::LEN + damage.len() * DamageRect::LEN + payload.len());
+    out.extend_from_slice(&header.encode(payload.len()));
+    out[17] = damage.len() as u8;
+    for rect in damage {
+        out.extend_from_slice(&rect.encode());
+    }
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Packed BGRA rows of a validated frame
+pub fn to_bgra(header: &FrameHeader, data: &[u8]) -> Vec<u8> {
+    let (width, height, stride) = (header.width as usize, header.height as usize, header.stride as usize);
+    let mut out = Vec::with_capacity(width * height * 4);
+
+    if header.format == PixelFormat::Nv12 {
+        let uv_plane = &data[stride * height..];
+        for y in 0..height {
+            let luma = &data[y * stride..];
+            let chroma = &uv_plane[(y / 2) * stride..];
+            for x in 0..width {
+                let c = luma[x] as i32 - 16;
+                let d = chroma[(x / 2) * 2] as i32 - 128;
+                let e = chroma[(x / 2) * 2 + 1] as i32 - 128;
+                let clamp = |v: i32| ((v + 128) >> 8).clamp(0, 255) as u8;
+                out.extend_from_slice(&[
+                    clamp(298 * c + 516 * d),
+                    clamp(298 * c - 100 * d - 208 * e),
+                    clamp(298 * c + 409 * e),
+                    0xff,
+                ]);
+            }
+        }
+        return out;
+    }
+
+    let bpp = header.format.row_bytes(1);
+    for y in 0..height {
+        let row = &data[y * stride..y * stride + width * bpp];
+        for px in row.chunks_exact(bpp) {
+            let [b, g, r, a] = match header.format {
+                PixelFormat::Rgba8888 => [px[2], px[1], px[0], px[3]],
+                PixelFormat::Bgra8888 => [px[0], px[1], px[2], px[3]],
+                PixelFormat::Rgb888 => [px[2], px[1], px[0], 0xff],
+                PixelFormat::Rgb565 => {
+                    let v = u16::from_le_bytes([px[0], px[1]]);
+                    let expand = |c: u16, bits: u32| ((c as u32 * 255) / ((1 << bits) - 1)) as u8;
+                    [expand(v & 0x1f, 5), expand((v >> 5) & 0x3f, 6), expand(v >> 11, 5), 0xff]
+                }
+                PixelFormat::Nv12 => unreachable!("planar, converted above"),
+            };
+            out.extend_from_slice(&[b, g, r, a]);
+        }
+    }
+    out
+}
+
+// ============================================================================
+// NEGOTIATION & FRAMING
+// ============================================================================
+
+/// Client hello offering `formats`
+pub fn encode_hello(formats: &[PixelFormat]) -> Vec<u8> {
+    let mut out = FORMAT_MAGIC.to_vec();
+    out.push(FORMAT_VERSION);
+    out.push(formats.len() as u8);
+    out.extend(formats.iter().map(|f| f.code()));
+    out
+}
+
+/// Server accept record; None when nothing offered is acceptable
+pub fn encode_accept(format: Option<PixelFormat>) -> [u8; 6] {
+    let [m0, m1, m2, m3] = *FORMAT_MAGIC;
+    [m0, m1, m2, m3, FORMAT_VERSION, format.map(PixelFormat::code).unwrap_or(0)]
+}
+
+fn decode_accept(record: &[u8; 6], offered: &[PixelFormat]) -> Result<PixelFormat, FormatError> {
+    let magic: [u8; 4] = record[0..4].try_into().unwrap();
+    if &magic != FORMAT_MAGIC {
+        return Err(FormatError::BadMagic(magic));
+    }
+    if record[4] != FORMAT_VERSION {
+        return Err(FormatError::UnsupportedVersion(record[4]));
+    }
+    match record[5] {
+        0 => Err(FormatError::NoCommonFormat),
+        code => PixelFormat::from_code(code)
+            .filter(|f| offered.contains(f))
+            .ok_or(FormatError::UnknownFormat(code)),
+    }
+}
+
+/// Offer `formats` and return the one the server will send
+pub fn negotiate<S: Read + Write>(stream: &mut S, formats: &[PixelFormat]) -> Result<PixelFormat, FormatError> {
+    stream.write_all(&encode_hello(formats))?;
+    stream.flush()?;
+    let mut record = [0u8; 6];
+    stream.read_exact(&mut record)?;
+    decode_accept(&record, formats)
+}
+
+/// Read the next frame into `payload`; None on a clean end of stream
+pub fn read_frame<R: Read>(stream: &mut R, payload: &mut Vec<u8>) -> Result<Option<FrameHeader>, FormatError> {
+    read_frame_damaged(stream, payload, &mut Vec::new())
+}
+
+/// Like `read_frame`, also filling `damage` (left empty: the whole frame changed)
+pub fn read_frame_damaged<R: Read>(stream: &mut R, payload: &mut Vec<u8>, damage: &mut Vec<DamageRect>) -> Result<Option<FrameHeader>, FormatError> {
+    let mut head = [0u8; FrameHeader::LEN];
+    match stream.read_exact(&mut head) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let (header, len) = FrameHeader::decode(&head)?;
+    let mut rects = vec![0u8; FrameHeader::damage_count(&head) * DamageRect::LEN];
+    stream.read_exact(&mut rects)?;
+    *damage = DamageRect::decode_all(&rects);
+    payload.resize(len, 0);
+    stream.read_exact(payload)?;
+    Ok(Some(header))
+}
+
+/// Header, damage rects and payload of one frame
+pub type DamagedFrame = (FrameHeader, Vec<DamageRect>, Vec<u8>);
+
+/// Reassembles the accept record and frames from arbitrarily split chunks
+/// (async protocol streams that cannot block in `read_frame`)
+pub struct FrameAssembler {
+    offered: Vec<PixelFormat>,
+    negotiated: Option<PixelFormat>,
+    buf: Vec<u8>,
+}
+
+impl FrameAssembler {
+    pub fn new(offered: &[PixelFormat]) -> Self {
+        Self { offered: offered.to_vec(), negotiated: None, buf: Vec::new() }
+    }
+
+    pub fn negotiated(&self) -> Option<PixelFormat> {
+        self.negotiated
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Next complete frame; the accept record is consumed on the way
+    pub fn next_frame(&mut self) -> Result<Option<(FrameHeader, Vec<u8>)>, FormatError> {
+        Ok(self.next_frame_damaged()?.map(|(header, _, payload)| (header, payload)))
+    }
+
+    /// Like `next_frame`, with the frame's damage rects
+    pub fn next_frame_damaged(&mut self) -> Result<Option<DamagedFrame>, FormatError> {
+        if self.negotiated.is_none() {
+            let Some(record) = self.buf.get(..6) else { return Ok(None) };
+            let format = decode_accept(record.try_into().unwrap(), &self.offered)?;
+            self.negotiated = Some(format);
+            self.buf.drain(..6);
+        }
+
+        let Some(head) = self.buf.get(..FrameHeader::LEN) else { return Ok(None) };
+        let head: &[u8; FrameHeader::LEN] = head.try_into().unwrap();
+        let (header, len) = FrameHeader::decode(head)?;
+        if !self.offered.contains(&header.format) {
+            return Err(FormatError::Layout(format!("{} frame was never offered", header.format.name())));
+        }
+        let rects = FrameHeader::damage_count(head) * DamageRect::LEN;
+        let end = FrameHeader::LEN + rects + len;
+        if self.buf.len() < end {
+            return Ok(None);
+        }
+        let damage = DamageRect::decode_all(&self.buf[FrameHeader::LEN..FrameHeader::LEN + rects]);
+        let payload = self.buf[end - len..end].to_vec();
+        self.buf.drain(..end);
+        Ok(Some((header, damage, payload)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// In-memory peer: reads what the client writes, replays `reply`
+    struct Peer {
+        written: Vec<u8>,
+        reply: Cursor<Vec<u8>>,
+    }
+
+    impl Read for Peer {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reply.read(buf)
+        }
+    }
+
+    impl Write for Peer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_negotiation_and_conversion() {
+        // 2x2 NV12, stride 4: white/black luma, neutral chroma
+        let nv12 = FrameHeader { width: 2, height: 2, stride: 4, format: PixelFormat::Nv12 };
+        let nv12_data = [235, 16, 0, 0, 16, 235, 0, 0, 128, 128];
+        assert_eq!(nv12.required_len(), nv12_data.len());
+        assert_eq!(to_bgra(&nv12, &nv12_data), [
+            255, 255, 255, 255, 0, 0, 0, 255,
+            0, 0, 0, 255, 255, 255, 255, 255,
+        ]);
+        let rgb = FrameHeader { width: 1, height: 2, stride: 4, format: PixelFormat::Rgb888 };
+        assert_eq!(to_bgra(&rgb, &[1, 2, 3, 0, 4, 5, 6]), [3, 2, 1, 255, 6, 5, 4, 255]);
+
+        let mut reply = encode_accept(Some(PixelFormat::Nv12)).to_vec();
+        reply.extend(encode_frame(&nv12, &nv12_data));
+        let mut peer = Peer { written: Vec::new(), reply: Cursor::new(reply.clone()) };
+        assert_eq!(negotiate(&mut peer, &PixelFormat::ALL).unwrap(), PixelFormat::Nv12);
+        assert_eq!(peer.written, [b'W', b'F', b'M', b'T', 1, 5, 2, 1, 3, 5, 4]);
+        let mut payload = Vec::new();
+        assert_eq!(read_frame(&mut peer, &mut payload).unwrap(), Some(nv12));
+        assert_eq!(payload, nv12_data);
+        assert!(read_frame(&mut peer, &mut payload).unwrap().is_none());
+
+        // Chunked delivery through the assembler
+        let mut assembler = FrameAssembler::new(&PixelFormat::ALL);
+        for chunk in reply.chunks(5) {
+            assert!(assembler.next_frame().unwrap().is_none());
+            assembler.push(chunk);
+        }
+        assert_eq!(assembler.next_frame().unwrap(), Some((nv12, nv12_data.to_vec())));
+        assert_eq!(assembler.negotiated(), Some(PixelFormat::Nv12));
+
+        // Refusals: nothing in common, short payload, stride narrower than a row
+        let mut peer = Peer { written: Vec::new(), reply: Cursor::new(encode_accept(None).to_vec()) };
+        assert!(matches!(negotiate(&mut peer, &[PixelFormat::Nv12]), Err(FormatError::NoCommonFormat)));
+        let message = encode_frame(&nv12, &nv12_data[..9]);
+        assert!(matches!(FrameHeader::split(&message), Err(FormatError::Layout(_))));
+        let narrow = FrameHeader { stride: 2, ..rgb };
+        assert!(narrow.validate(16).is_err());
+    }
+
+    #[test]
+    fn test_frame_damage() {
+        let header = FrameHeader::bgra(4, 2);
+        let pixels = [9u8; 32];
+        let damage = [DamageRect::new(1, 0, 2, 1), DamageRect::new(3, 1, 1, 1)];
+        let message = encode_frame_damaged(&header, &damage, &pixels);
+        assert_eq!(message.len(), FrameHeader::LEN + 2 * DamageRect::LEN + pixels.len());
+
+        // Readers that ignore damage still find the pixels
+        assert_eq!(FrameHeader::split(&message).unwrap(), (header, &pixels[..]));
+        assert_eq!(FrameHeader::split_damaged(&message).unwrap(), (header, damage.to_vec(), &pixels[..]));
+        let mut payload = Vec::new();
+        let mut rects = Vec::new();
+        let mut stream = Cursor::new(message.clone());
+        assert_eq!(read_frame_damaged(&mut stream, &mut payload, &mut rects).unwrap(), Some(header));
+        assert_eq!((rects.as_slice(), payload.as_slice()), (&damage[..], &pixels[..]));
+
+        let mut assembler = FrameAssembler::new(&PixelFormat::ALL);
+        assembler.push(&encode_accept(Some(PixelFormat::Bgra8888)));
+        assembler.push(&message[..30]);
+        assert!(assembler.next_frame_damaged().unwrap().is_none());
+        assembler.push(&message[30..]);
+        assembler.push(&encode_frame(&header, &pixels));
+        assert_eq!(assembler.next_frame_damaged().unwrap(), Some((header, damage.to_vec(), pixels.to_vec())));
+        assert_eq!(assembler.next_frame_damaged().unwrap(), Some((header, Vec::new(), pixels.to_vec())));
+
+        // Too many rects collapse into a whole-frame update; clipping stays inside the frame
+        let many = vec![DamageRect::new(0, 0, 1, 1); MAX_DAMAGE_RECTS + 1];
+        assert!(FrameHeader::split_damaged(&encode_frame_damaged(&header, &many, &pixels)).unwrap().1.is_empty());
+        assert_eq!(DamageRect::new(3, 1, 8, 8).clip(4, 2), Some(DamageRect::new(3, 1, 1, 1)));
+        assert_eq!(DamageRect::new(4, 0, 1, 1).clip(4, 2), None);
+    }
+}
@@ -0,0 +1,335 @@
+// placeholder.rs
+// WASMA - Reconnect placeholder
+// While a window's stream is disconnected its viewport shows a themed card
+// (app icon, "Reconnecting…", time since the stream went away) instead of the
+// last frame. Drawn by a small software renderer with a built-in 5x7 font;
+// what is shown comes from WSDG settings, per app via [placeholder.<app_id>].
+
+use std::time::Duration;
+
+use iced::Color;
+use wsdg_xdg::{PlaceholderSettings, WsdgEnv, WsdgSettings, WsdgSettingsManager};
+
+use crate::decorations::DecorationTheme;
+use crate::icon_badges::ComposedIcon;
+
+// 5x7 glyphs, one row per byte (bit 4 = left column); lowercase is drawn as uppercase
+const GLYPH_WIDTH: u32 = 5;
+pub(crate) const GLYPH_HEIGHT: u32 = 7;
+const GLYPHS: &[(char, [u8; 7])] = &[
+    ('A', [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('B', [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110]),
+    ('C', [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110]),
+    ('D', [0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110]),
+    ('E', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111]),
+    ('F', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('G', [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111]),
+    ('H', [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('I', [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100]),
+    ('K', [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001]),
+    ('N', [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('P', [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('Q', [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101]),
+    ('R', [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001]),
+    ('S', [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100]),
+    ('W', [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010]),
+    ('X', [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001]),
+    ('Y', [0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('Z', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111]),
+    ('0', [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('2', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111]),
+    ('3', [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110]),
+    ('4', [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110]),
+    ('6', [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000]),
+    ('8', [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110]),
+    ('9', [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100]),
+    ('.', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100]),
+    (',', [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000]),
+    (':', [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000]),
+    ('-', [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000]),
+    ('!', [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100]),
+    ('?', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100]),
+    ('/', [0b00001, 0b00010, 0b00010, 0b00100, 0b01000, 0b01000, 0b10000]),
+    ('\'', [0b00100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000]),
+];
+
+fn glyph(c: char) -> Option<&'static [u8; 7]> {
+    let c = c.to_ascii_uppercase();
+    GLYPHS.iter().find(|(g, _)| *g == c).map(|(_, rows)| rows)
+}
+
+/// Characters outside the built-in font are folded ("…" → "...", "ğ" → "G")
+fn fold_text(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| {
+            let folded: &str = match c {
+                '…' => "...",
+                'ç' | 'Ç' => "C",
+                'ğ' | 'Ğ' => "G",
+                'ı' | 'İ' => "I",
+                'ö' | 'Ö' => "O",
+                'ş' | 'Ş' => "S",
+                'ü' | 'Ü' => "U",
+                _ => "",
+            };
+            match folded {
+                "" => vec![c],
+                s => s.chars().collect(),
+            }
+        })
+        .collect()
+}
+
+/// "0:42", "12:05", "1:02:03"
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs / 3600 {
+        0 => format!("{}:{:02}", secs / 60, secs % 60),
+        hours => format!("{}:{:02}:{:02}", hours, secs / 60 % 60, secs % 60),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlaceholderMode {
+    /// Icon, message and elapsed time
+    #[default]
+    Card,
+    /// Background color only
+    Blank,
+    /// Keep the last frame (no placeholder)
+    Freeze,
+}
+
+impl PlaceholderMode {
+    pub fn parse(raw: &str) -> Self {
+        match raw.to_ascii_lowercase().as_str() {
+            "blank" => PlaceholderMode::Blank,
+            "freeze" | "none" => PlaceholderMode::Freeze,
+            _ => PlaceholderMode::Card,
+        }
+    }
+}
+
+/// Placeholder of one app, themed like the server-side decorations
+#[derive(Debug, Clone)]
+pub struct Placeholder {
+    pub mode: PlaceholderMode,
+    pub message: String,
+    pub show_elapsed: bool,
+    pub background: Color,
+    pub foreground: Color,
+    pub accent: Color,
+    /// App icon; None draws no icon
+    pub icon: Option<ComposedIcon>,
+    pub show_icon: bool,
+}
+
+impl Default for Placeholder {
+    fn default() -> Self {
+        Self::from_settings(&WsdgSettings::default(), "")
+    }
+}
+
+impl Placeholder {
+    /// `app_id`'s placeholder from `settings`
+    pub fn from_settings(settings: &WsdgSettings, app_id: &str) -> Self {
+        let theme = DecorationTheme::from_settings(settings);
+        let PlaceholderSettings { mode, message, show_icon, show_elapsed } = settings.placeholder_for(app_id);
+        Self {
+            mode: PlaceholderMode::parse(mode),
+            message: message.clone(),
+            show_elapsed: *show_elapsed,
+            background: theme.background,
+            foreground: theme.foreground,
+            accent: theme.accent,
+            icon: None,
+            show_icon: *show_icon,
+        }
+    }
+
+    /// `app_id`'s placeholder from the user's WSDG settings
+    pub fn for_app(env: &WsdgEnv, app_id: &str) -> Self {
+        let mut manager = WsdgSettingsManager::new(env.clone());
+        match manager.load() {
+            Ok(()) => Self::from_settings(manager.settings(), app_id),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Use `icon` unless the app's settings hide it
+    pub fn with_icon(mut self, icon: ComposedIcon) -> Self {
+        if self.show_icon {
+            self.icon = Some(icon);
+        }
+        self
+    }
+
+    /// RGBA pixels of a `width`x`height` placeholder; None in Freeze mode
+    pub fn render(&self, width: u32, height: u32, elapsed: Duration) -> Option<Vec<u8>> {
+        let mut canvas = Canvas::new(width, height, self.background);
+        match self.mode {
+            PlaceholderMode::Freeze => return None,
+            PlaceholderMode::Blank => return Some(canvas.pixels),
+            PlaceholderMode::Card => {}
+        }
+
+        let message = fold_text(&self.message);
+        let elapsed = self.show_elapsed.then(|| format_elapsed(elapsed));
+
+        // Text scale follows the viewport; the icon takes about a quarter of the height
+        let scale = (width.min(height) / 160).max(1);
+        let line = GLYPH_HEIGHT * scale;
+        let icon_size = self.icon.as_ref().map_or(0, |_| (height / 4).min(128));
+        let gap = line;
+        let mut total = line;
+        if icon_size > 0 {
+            total += icon_size + gap;
+        }
+        if elapsed.is_some() {
+            total += gap + line;
+        }
+
+        let mut y = height.saturating_sub(total) / 2;
+        if let Some(icon) = self.icon.as_ref().filter(|_| icon_size > 0) {
+            canvas.draw_icon(icon, width.saturating_sub(icon_size) / 2, y, icon_size);
+            y += icon_size + gap;
+        }
+        canvas.draw_text_centered(&message, y, scale, self.foreground);
+        if let Some(elapsed) = elapsed {
+            canvas.draw_text_centered(&elapsed, y + line + gap, scale, self.accent);
+        }
+        Some(canvas.pixels)
+    }
+}
+
+/// RGBA framebuffer the placeholder (and the raw SSD titlebar) is drawn into
+pub(crate) struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32, background: Color) -> Self {
+        let pixels = background.into_rgba8().repeat((width * height) as usize);
+        Self { width, height, pixels }
+    }
+
+    /// Draw on top of pixels that are already there (width * height * 4 bytes)
+    pub(crate) fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        Self { width, height, pixels }
+    }
+
+    pub(crate) fn into_rgba(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    fn blend(&mut self, x: u32, y: u32, [r, g, b, a]: [u8; 4]) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let i = ((y * self.width + x) * 4) as usize;
+        let alpha = a as u32;
+        for (dst, src) in self.pixels[i..i + 3].iter_mut().zip([r, g, b]) {
+            *dst = ((src as u32 * alpha + *dst as u32 * (255 - alpha)) / 255) as u8;
+        }
+    }
+
+    fn fill(&mut self, x: u32, y: u32, size: u32, color: [u8; 4]) {
+        for dy in 0..size {
+            for dx in 0..size {
+                self.blend(x + dx, y + dy, color);
+            }
+        }
+    }
+
+    fn draw_text_centered(&mut self, text: &str, y: u32, scale: u32, color: Color) {
+        let advance = (GLYPH_WIDTH + 1) * scale;
+        let chars = text.chars().count() as u32;
+        let x0 = self.width.saturating_sub((chars * advance).saturating_sub(scale)) / 2;
+        self.draw_text(text, x0, y, scale, color);
+    }
+
+    /// Left-aligned, folded to the built-in font and cut off at `max_width`
+    pub(crate) fn draw_label(&mut self, text: &str, x: u32, y: u32, max_width: u32, scale: u32, color: Color) {
+        let advance = (GLYPH_WIDTH + 1) * scale;
+        let fits = (max_width + scale) / advance;
+        let label: String = fold_text(text).chars().take(fits as usize).collect();
+        self.draw_text(&label, x, y, scale, color);
+    }
+
+    fn draw_text(&mut self, text: &str, x0: u32, y: u32, scale: u32, color: Color) {
+        let advance = (GLYPH_WIDTH + 1) * scale;
+        let color = color.into_rgba8();
+
+        for (i, c) in text.chars().enumerate() {
+            let Some(rows) = glyph(c) else { continue };
+            let x = x0 + i as u32 * advance;
+            for (row, bits) in rows.iter().enumerate() {
+                for col in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+                        self.fill(x + col * scale, y + row as u32 * scale, scale, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Nearest-neighbour scaled, alpha blended
+    fn draw_icon(&mut self, icon: &ComposedIcon, x: u32, y: u32, size: u32) {
+        let source = icon.rgba();
+        let source_size = icon.size();
+        for dy in 0..size {
+            for dx in 0..size {
+                let sx = dx * source_size / size;
+                let sy = dy * source_size / size;
+                let i = ((sy * source_size + sx) * 4) as usize;
+                if let Some(&[r, g, b, a]) = source.get(i..i + 4).and_then(|p| <&[u8; 4]>::try_from(p).ok()) {
+                    self.blend(x + dx, y + dy, [r, g, b, a]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icon_badges::{BadgeCompositor, IconBadge};
+
+    #[test]
+    fn test_placeholder_rendering() {
+        let mut settings = WsdgSettings::default();
+        settings.theme.background_color = wbackend::Color::rgb(0x10, 0x20, 0x30);
+        let mut muted = settings.placeholder.clone();
+        muted.mode = "freeze".to_string();
+        settings.app_placeholders.insert("org.example.Muted".to_string(), muted);
+
+        let icon = BadgeCompositor::new().compose(None, &[IconBadge::Recording], 32).unwrap();
+        let placeholder = Placeholder::from_settings(&settings, "org.example.Player").with_icon((*icon).clone());
+        assert_eq!(placeholder.mode, PlaceholderMode::Card);
+
+        let frame = placeholder.render(320, 240, Duration::from_secs(75)).unwrap();
+        assert_eq!(frame.len(), 320 * 240 * 4);
+        // Corners keep the theme background; text and icon are drawn in the middle
+        assert_eq!(&frame[..4], &[0x10, 0x20, 0x30, 0xff]);
+        assert!(frame.chunks(4).any(|p| p != [0x10, 0x20, 0x30, 0xff]));
+        // The elapsed time changes the picture
+        assert_ne!(frame, placeholder.render(320, 240, Duration::from_secs(76)).unwrap());
+
+        assert!(Placeholder::from_settings(&settings, "org.example.Muted").render(320, 240, Duration::ZERO).is_none());
+        assert_eq!(format_elapsed(Duration::from_secs(75)), "1:15");
+        assert_eq!(format_elapsed(Duration::from_secs(3723)), "1:02:03");
+        assert_eq!(fold_text("Bağlanıyor…"), "BaGlanIyor...");
+    }
+}
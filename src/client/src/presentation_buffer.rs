@@ -0,0 +1,225 @@
+// WASMA - Presentation Buffer
+// Frames over Tor/HTTP arrive in bursts and gaps. Each stream gets a small
+// jitter buffer: frames are scheduled at the stream's average frame interval,
+// delayed by at most `latency_budget`. When nothing is due the last frame is
+// held; with interpolation on, the gap to the next queued frame is blended.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Latency budget used when a stream has none configured
+pub const DEFAULT_LATENCY_BUDGET: Duration = Duration::from_millis(50);
+/// Weight of the newest inter-arrival sample in the interval estimate
+const INTERVAL_SMOOTHING: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PresentationConfig {
+    /// Longest a frame may wait in the buffer
+    pub latency_budget: Duration,
+    /// Blend held frames towards the next queued frame
+    pub interpolate: bool,
+    /// Queued frames beyond this drop the oldest
+    pub max_frames: usize,
+}
+
+impl Default for PresentationConfig {
+    fn default() -> Self {
+        Self { latency_budget: DEFAULT_LATENCY_BUDGET, interpolate: false, max_frames: 4 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    /// A frame shown for the first time
+    Fresh,
+    /// The previous frame again; nothing new was due
+    Held,
+    /// Blend of the current and next queued frame
+    Interpolated,
+    /// Reconnect placeholder drawn while the stream is disconnected
+    Placeholder,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresentedFrame {
+    pub data: Vec<u8>,
+    pub kind: FrameKind,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PresentationStats {
+    pub presented: u64,
+    pub held: u64,
+    pub interpolated: u64,
+    /// Frames skipped because a newer one was already due, or the queue overflowed
+    pub dropped: u64,
+}
+
+#[derive(Debug)]
+struct QueuedFrame {
+    data: Vec<u8>,
+    due: Instant,
+}
+
+/// Jitter buffer for one stream
+#[derive(Debug)]
+pub struct PresentationBuffer {
+    config: PresentationConfig,
+    queue: VecDeque<QueuedFrame>,
+    current: Option<QueuedFrame>,
+    last_arrival: Option<Instant>,
+    // Smoothed inter-arrival time (seconds)
+    interval: Option<f64>,
+    stats: PresentationStats,
+}
+
+impl Default for PresentationBuffer {
+    fn default() -> Self {
+        Self::new(PresentationConfig::default())
+    }
+}
+
+impl PresentationBuffer {
+    pub fn new(config: PresentationConfig) -> Self {
+        PresentationBuffer {
+            config,
+            queue: VecDeque::new(),
+            current: None,
+            last_arrival: None,
+            interval: None,
+            stats: PresentationStats::default(),
+        }
+    }
+
+    pub fn config(&self) -> PresentationConfig {
+        self.config
+    }
+
+    pub fn set_config(&mut self, config: PresentationConfig) {
+        self.config = config;
+    }
+
+    pub fn stats(&self) -> PresentationStats {
+        self.stats
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Estimated source frame interval
+    pub fn frame_interval(&self) -> Option<Duration> {
+        self.interval.map(Duration::from_secs_f64)
+    }
+
+    pub fn push(&mut self, data: Vec<u8>) {
+        self.push_at(data, Instant::now());
+    }
+
+    pub fn push_at(&mut self, data: Vec<u8>, now: Instant) {
+        if let Some(last) = self.last_arrival {
+            let sample = now.saturating_duration_since(last).as_secs_f64();
+            self.interval = Some(match self.interval {
+                Some(interval) => interval + INTERVAL_SMOOTHING * (sample - interval),
+                None => sample,
+            });
+        }
+        self.last_arrival = Some(now);
+
+        // Evenly spaced after the previous frame, but never earlier than arrival
+        // and never later than the latency budget allows
+        let budget_deadline = now + self.config.latency_budget;
+        let due = match (self.queue.back().or(self.current.as_ref()), self.frame_interval()) {
+            (Some(previous), Some(interval)) => (previous.due + interval).clamp(now, budget_deadline),
+            _ => budget_deadline,
+        };
+
+        self.queue.push_back(QueuedFrame { data, due });
+        while self.queue.len() > self.config.max_frames.max(1) {
+            self.queue.pop_front();
+            self.stats.dropped += 1;
+        }
+    }
+
+    pub fn present(&mut self) -> Option<PresentedFrame> {
+        self.present_at(Instant::now())
+    }
+
+    /// Frame to show at `now`; None until the first frame is due
+    pub fn present_at(&mut self, now: Instant) -> Option<PresentedFrame> {
+        let mut fresh = None;
+        while self.queue.front().is_some_and(|f| f.due <= now) {
+            if fresh.is_some() {
+                self.stats.dropped += 1;
+            }
+            fresh = self.queue.pop_front();
+        }
+        if let Some(frame) = fresh {
+            self.stats.presented += 1;
+            let data = frame.data.clone();
+            self.current = Some(frame);
+            return Some(PresentedFrame { data, kind: FrameKind::Fresh });
+        }
+
+        let current = self.current.as_ref()?;
+        if self.config.interpolate {
+            if let Some(next) = self.queue.front().filter(|next| next.data.len() == current.data.len()) {
+                let span = next.due.saturating_duration_since(current.due).as_secs_f32();
+                let t = now.saturating_duration_since(current.due).as_secs_f32() / span.max(f32::EPSILON);
+                self.stats.interpolated += 1;
+                return Some(PresentedFrame { data: blend(&current.data, &next.data, t), kind: FrameKind::Interpolated });
+            }
+        }
+        self.stats.held += 1;
+        Some(PresentedFrame { data: current.data.clone(), kind: FrameKind::Held })
+    }
+}
+
+/// Per-byte linear blend of two RGBA frames, `t` in [0, 1]
+fn blend(from: &[u8], to: &[u8], t: f32) -> Vec<u8> {
+    let t = t.clamp(0.0, 1.0);
+    from.iter()
+        .zip(to)
+        .map(|(&a, &b)| (a as f32 + (b as f32 - a as f32) * t).round() as u8)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presentation_smooths_bursts_and_holds() {
+        let ms = Duration::from_millis;
+        let start = Instant::now();
+        let mut buffer = PresentationBuffer::new(PresentationConfig { latency_budget: ms(40), interpolate: false, max_frames: 4 });
+
+        // Steady 20ms stream establishes the interval
+        buffer.push_at(vec![0; 4], start);
+        buffer.push_at(vec![10; 4], start + ms(20));
+        assert_eq!(buffer.present_at(start + ms(10)), None);
+        assert_eq!(buffer.present_at(start + ms(40)).unwrap().kind, FrameKind::Fresh);
+        assert_eq!(buffer.present_at(start + ms(60)).unwrap().data, vec![10; 4]);
+
+        // A burst of two frames is spread out instead of shown back to back
+        buffer.push_at(vec![20; 4], start + ms(70));
+        buffer.push_at(vec![30; 4], start + ms(71));
+        assert_eq!(buffer.present_at(start + ms(85)).unwrap().data, vec![20; 4]);
+        assert_eq!(buffer.present_at(start + ms(95)).unwrap().kind, FrameKind::Held);
+        assert_eq!(buffer.present_at(start + ms(105)).unwrap().data, vec![30; 4]);
+
+        // Gap: last frame is held
+        let held = buffer.present_at(start + ms(150)).unwrap();
+        assert_eq!((held.kind, held.data), (FrameKind::Held, vec![30; 4]));
+        assert_eq!(buffer.stats().dropped, 0);
+
+        // Interpolation blends towards the next queued frame
+        buffer.set_config(PresentationConfig { interpolate: true, ..buffer.config() });
+        buffer.push_at(vec![40; 4], start + ms(200));
+        assert_eq!(buffer.present_at(start + ms(200)).unwrap().kind, FrameKind::Fresh);
+        buffer.push_at(vec![140; 4], start + ms(210));
+        let between = buffer.present_at(start + ms(215)).unwrap();
+        assert_eq!(between.kind, FrameKind::Interpolated);
+        assert!(between.data[0] > 40 && between.data[0] < 140);
+    }
+}
@@ -0,0 +1,493 @@
+// WASMA - Native VRAM presenter
+// Streams used to write straight into the scanout buffer, so a frame could be
+// shown half old, half new (tearing). Writers now fill a back buffer in memory
+// and record what they touched; a vsync thread waits for the vertical blank
+// and copies only those damaged ranges to the front (scanout) buffer. Vsync
+// comes from DRM vblank events (/dev/dri/card*), or from a timer at the display
+// rate when no DRM device answers. The front buffer is a DRM dumb buffer on
+// that device, or plain memory for headless sessions.
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// VRAM reserved per stream in a buffer
+pub const STREAM_SLOT_SIZE: usize = 1024 * 1024;
+/// One buffer: a slot for each of the 256 stream ids
+pub const VRAM_BUFFER_SIZE: usize = 256 * STREAM_SLOT_SIZE;
+/// Timer vsync rate without a DRM device
+pub const FALLBACK_REFRESH_HZ: u32 = 60;
+/// Damaged ranges kept apart before they are merged into their bounding range
+const MAX_DAMAGE_RANGES: usize = 64;
+
+// ============================================================================
+// DOUBLE BUFFER
+// ============================================================================
+
+/// Where the front buffer lives
+enum FrontMemory {
+    /// Caller-provided mapping
+    Mapped,
+    Heap { _memory: Box<[u8]> },
+    Dumb { _buffer: DumbBuffer },
+}
+
+/// Back buffer in memory, front buffer for scanout; flips copy the damage
+pub struct DoubleBuffer {
+    front: usize,
+    back: Box<[u8]>,
+    buffer_size: usize,
+    // Byte ranges written since the last flip
+    damage: Vec<(usize, usize)>,
+    flips: u64,
+    presented_bytes: u64,
+    _front_memory: FrontMemory,
+}
+
+impl DoubleBuffer {
+    /// Front buffer mapped at `front`
+    ///
+    /// # Safety
+    /// `front` must point to `buffer_size` writable bytes for the lifetime
+    /// of the DoubleBuffer.
+    pub unsafe fn mapped(front: usize, buffer_size: usize) -> Self {
+        Self::with_front(front, buffer_size, FrontMemory::Mapped)
+    }
+
+    /// Heap-backed front buffer (headless sessions, tests)
+    pub fn in_memory(buffer_size: usize) -> Self {
+        let memory = vec![0u8; buffer_size].into_boxed_slice();
+        let front = memory.as_ptr() as usize;
+        Self::with_front(front, buffer_size, FrontMemory::Heap { _memory: memory })
+    }
+
+    /// Front buffer in a DRM dumb buffer of `card`
+    pub fn dumb(card: &File, buffer_size: usize) -> io::Result<Self> {
+        let dumb = DumbBuffer::create(card, buffer_size)?;
+        Ok(Self::with_front(dumb.addr, buffer_size, FrontMemory::Dumb { _buffer: dumb }))
+    }
+
+    fn with_front(front: usize, buffer_size: usize, memory: FrontMemory) -> Self {
+        Self {
+            front,
+            back: vec![0u8; buffer_size].into_boxed_slice(),
+            buffer_size,
+            damage: Vec::new(),
+            flips: 0,
+            presented_bytes: 0,
+            _front_memory: memory,
+        }
+    }
+
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    /// Address scanout reads from
+    pub fn front_addr(&self) -> usize {
+        self.front
+    }
+
+    pub fn front(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.front as *const u8, self.buffer_size) }
+    }
+
+    fn damage(&mut self, offset: usize, len: usize) {
+        if len == 0 {
+            return;
+        }
+        self.damage.push((offset, offset + len));
+        if self.damage.len() > MAX_DAMAGE_RANGES {
+            let start = self.damage.iter().map(|r| r.0).min().unwrap_or(0);
+            let end = self.damage.iter().map(|r| r.1).max().unwrap_or(0);
+            self.damage = vec![(start, end)];
+        }
+    }
+
+    /// Copy `data` to `offset` of the back buffer, clipped to it; bytes written
+    pub fn write(&mut self, offset: usize, data: &[u8]) -> usize {
+        let Some(target) = self.back.get_mut(offset..) else { return 0 };
+        let len = data.len().min(target.len());
+        target[..len].copy_from_slice(&data[..len]);
+        self.damage(offset, len);
+        len
+    }
+
+    /// Zero `len` bytes of the back buffer from `offset`
+    pub fn clear(&mut self, offset: usize, len: usize) {
+        if let Some(target) = self.back.get_mut(offset..) {
+            let len = len.min(target.len());
+            target[..len].fill(0);
+            self.damage(offset, len);
+        }
+    }
+
+    /// Show what was written since the last flip; false when nothing was.
+    /// Only the damaged ranges reach the front buffer, so partial (damage)
+    /// writes keep building on the frame on screen.
+    pub fn flip(&mut self) -> bool {
+        if self.damage.is_empty() {
+            return false;
+        }
+        let mut damage = std::mem::take(&mut self.damage);
+        damage.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::with_capacity(damage.len());
+        for (start, end) in damage {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        for (start, end) in merged {
+            unsafe {
+                std::ptr::copy_nonoverlapping(self.back[start..end].as_ptr(), (self.front + start) as *mut u8, end - start);
+            }
+            self.presented_bytes += (end - start) as u64;
+        }
+        self.flips += 1;
+        true
+    }
+
+    pub fn flips(&self) -> u64 {
+        self.flips
+    }
+
+    /// Bytes copied to the front buffer so far
+    pub fn presented_bytes(&self) -> u64 {
+        self.presented_bytes
+    }
+}
+
+// drm_mode.h: dumb buffer create / map / destroy
+#[repr(C)]
+struct DrmModeCreateDumb {
+    height: u32,
+    width: u32,
+    bpp: u32,
+    flags: u32,
+    handle: u32,
+    pitch: u32,
+    size: u64,
+}
+
+#[repr(C)]
+struct DrmModeMapDumb {
+    handle: u32,
+    pad: u32,
+    offset: u64,
+}
+
+#[repr(C)]
+struct DrmModeDestroyDumb {
+    handle: u32,
+}
+
+const fn drm_iowr(nr: u64, size: usize) -> u64 {
+    (3 << 30) | ((size as u64) << 16) | ((b'd' as u64) << 8) | nr
+}
+
+const DRM_IOCTL_MODE_CREATE_DUMB: u64 = drm_iowr(0xb2, std::mem::size_of::<DrmModeCreateDumb>());
+const DRM_IOCTL_MODE_MAP_DUMB: u64 = drm_iowr(0xb3, std::mem::size_of::<DrmModeMapDumb>());
+const DRM_IOCTL_MODE_DESTROY_DUMB: u64 = drm_iowr(0xb4, std::mem::size_of::<DrmModeDestroyDumb>());
+
+/// Width of the dumb buffer in 32 bpp pixels; the height follows from the size
+const DUMB_WIDTH: u32 = 4096;
+
+/// Kernel-allocated scanout-capable buffer, mapped into this process
+struct DumbBuffer {
+    card: File,
+    handle: u32,
+    addr: usize,
+    len: usize,
+}
+
+impl DumbBuffer {
+    fn create(card: &File, size: usize) -> io::Result<Self> {
+        let card = card.try_clone()?;
+        let pitch = DUMB_WIDTH as usize * 4;
+        let mut create = DrmModeCreateDumb {
+            height: size.div_ceil(pitch) as u32,
+            width: DUMB_WIDTH,
+            bpp: 32,
+            flags: 0,
+            handle: 0,
+            pitch: 0,
+            size: 0,
+        };
+        if unsafe { libc::ioctl(card.as_raw_fd(), DRM_IOCTL_MODE_CREATE_DUMB as _, &mut create) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // Destroyed on every error path from here on
+        let mut dumb = DumbBuffer { card, handle: create.handle, addr: 0, len: create.size as usize };
+        if dumb.len < size {
+            return Err(io::Error::other(format!("dumb buffer of {} bytes is smaller than {}", dumb.len, size)));
+        }
+
+        let mut map = DrmModeMapDumb { handle: dumb.handle, pad: 0, offset: 0 };
+        if unsafe { libc::ioctl(dumb.card.as_raw_fd(), DRM_IOCTL_MODE_MAP_DUMB as _, &mut map) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let addr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                dumb.len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                dumb.card.as_raw_fd(),
+                map.offset as libc::off_t,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        dumb.addr = addr as usize;
+        Ok(dumb)
+    }
+}
+
+impl Drop for DumbBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            if self.addr != 0 {
+                libc::munmap(self.addr as *mut libc::c_void, self.len);
+            }
+            let mut destroy = DrmModeDestroyDumb { handle: self.handle };
+            libc::ioctl(self.card.as_raw_fd(), DRM_IOCTL_MODE_DESTROY_DUMB as _, &mut destroy);
+        }
+    }
+}
+
+// ============================================================================
+// VSYNC
+// ============================================================================
+
+// drm.h: union drm_wait_vblank, DRM_IOCTL_WAIT_VBLANK = _IOWR('d', 0x3a, ...)
+#[repr(C)]
+struct DrmWaitVblank {
+    kind: u32,
+    sequence: u32,
+    tval_sec: libc::c_long,
+    tval_usec: libc::c_long,
+}
+
+const DRM_VBLANK_RELATIVE: u32 = 0x1;
+const DRM_IOCTL_WAIT_VBLANK: u64 = drm_iowr(0x3a, std::mem::size_of::<DrmWaitVblank>());
+
+pub enum VsyncSource {
+    /// Blocks on the next vertical blank of the DRM device
+    Drm(File),
+    Timer { interval: Duration, next: Instant },
+}
+
+impl VsyncSource {
+    pub fn timer(refresh_hz: u32) -> Self {
+        let interval = Duration::from_secs(1) / refresh_hz.max(1);
+        VsyncSource::Timer { interval, next: Instant::now() + interval }
+    }
+
+    /// First /dev/dri/card* that delivers vblank events, else a 60 Hz timer
+    pub fn detect() -> Self {
+        for index in 0..8 {
+            let Ok(card) = OpenOptions::new().read(true).write(true).open(format!("/dev/dri/card{}", index)) else {
+                continue;
+            };
+            if wait_vblank(&card).is_ok() {
+                return VsyncSource::Drm(card);
+            }
+        }
+        Self::timer(FALLBACK_REFRESH_HZ)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VsyncSource::Drm(_) => "drm-vblank",
+            VsyncSource::Timer { .. } => "timer",
+        }
+    }
+
+    /// Block until the next vertical blank. A DRM device that stops answering
+    /// (VT switch, hot unplug) is replaced by the timer.
+    pub fn wait(&mut self) {
+        if let VsyncSource::Drm(card) = self {
+            match wait_vblank(card) {
+                Ok(()) => return,
+                Err(e) => {
+                    eprintln!("⚠️  DRM vblank unavailable ({}), presenting on a {} Hz timer", e, FALLBACK_REFRESH_HZ);
+                    *self = Self::timer(FALLBACK_REFRESH_HZ);
+                }
+            }
+        }
+        if let VsyncSource::Timer { interval, next } = self {
+            let now = Instant::now();
+            if *next > now {
+                std::thread::sleep(*next - now);
+            }
+            // Missed ticks are skipped, not caught up
+            while *next <= Instant::now() {
+                *next += *interval;
+            }
+        }
+    }
+}
+
+fn wait_vblank(card: &File) -> io::Result<()> {
+    let mut request = DrmWaitVblank { kind: DRM_VBLANK_RELATIVE, sequence: 1, tval_sec: 0, tval_usec: 0 };
+    let result = unsafe { libc::ioctl(card.as_raw_fd(), DRM_IOCTL_WAIT_VBLANK as _, &mut request) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// ============================================================================
+// PRESENTER
+// ============================================================================
+
+/// Double buffer flipped on every vsync by its own thread
+pub struct Presenter {
+    buffers: Arc<Mutex<DoubleBuffer>>,
+    vsync_name: &'static str,
+    running: Arc<AtomicBool>,
+    // Detached session: writes keep collecting damage, nothing reaches scanout
+    paused: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+static GLOBAL: OnceLock<Presenter> = OnceLock::new();
+
+impl Presenter {
+    pub fn new(buffers: DoubleBuffer, mut vsync: VsyncSource) -> Self {
+        let buffers = Arc::new(Mutex::new(buffers));
+        let running = Arc::new(AtomicBool::new(true));
+        let paused = Arc::new(AtomicBool::new(false));
+        let vsync_name = vsync.name();
+        let thread = {
+            let (buffers, running, paused) = (buffers.clone(), running.clone(), paused.clone());
+            std::thread::spawn(move || {
+                while running.load(Ordering::Relaxed) {
+                    // Wait outside the lock: writers keep filling the back buffer meanwhile
+                    vsync.wait();
+                    if !paused.load(Ordering::Relaxed) {
+                        buffers.lock().unwrap().flip();
+                    }
+                }
+            })
+        };
+        Self { buffers, vsync_name, running, paused, thread: Some(thread) }
+    }
+
+    /// The global presenter if something already started it
+    pub fn running() -> Option<&'static Presenter> {
+        GLOBAL.get()
+    }
+
+    /// Stop (or resume) flipping; damage written meanwhile is shown on resume
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Presenter of the native VRAM path, started on first use: a dumb buffer
+    /// on the vblank device, in-memory buffers without one
+    pub fn global() -> &'static Presenter {
+        GLOBAL.get_or_init(|| {
+            let vsync = VsyncSource::detect();
+            let buffers = match &vsync {
+                VsyncSource::Drm(card) => DoubleBuffer::dumb(card, VRAM_BUFFER_SIZE).unwrap_or_else(|e| {
+                    eprintln!("⚠️  DRM dumb buffer unavailable ({}), presenting to memory", e);
+                    DoubleBuffer::in_memory(VRAM_BUFFER_SIZE)
+                }),
+                VsyncSource::Timer { .. } => DoubleBuffer::in_memory(VRAM_BUFFER_SIZE),
+            };
+            Presenter::new(buffers, vsync)
+        })
+    }
+
+    pub fn vsync_source(&self) -> &'static str {
+        self.vsync_name
+    }
+
+    /// Write into the back buffer; shown at the next vsync
+    pub fn write(&self, offset: usize, data: &[u8]) -> usize {
+        self.buffers.lock().unwrap().write(offset, data)
+    }
+
+    pub fn clear(&self, offset: usize, len: usize) {
+        self.buffers.lock().unwrap().clear(offset, len);
+    }
+
+    pub fn flips(&self) -> u64 {
+        self.buffers.lock().unwrap().flips()
+    }
+
+    /// Run `f` on what scanout currently shows
+    pub fn with_front<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(self.buffers.lock().unwrap().front())
+    }
+}
+
+impl Drop for Presenter {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_double_buffered_flips() {
+        let mut buffers = DoubleBuffer::in_memory(16);
+        let front = buffers.front_addr();
+
+        // Writes land in the back buffer; scanout keeps the old frame until the flip
+        assert_eq!(buffers.write(4, &[1, 2, 3]), 3);
+        assert_eq!(buffers.front(), [0; 16]);
+        assert!(buffers.flip());
+        assert_eq!(buffers.front_addr(), front);
+        assert_eq!(&buffers.front()[4..7], [1, 2, 3]);
+        assert_eq!(buffers.presented_bytes(), 3);
+        // Nothing new: no flip. Partial writes build on the shown frame
+        assert!(!buffers.flip());
+        assert_eq!(buffers.write(14, &[9, 9, 9]), 2);
+        assert_eq!(buffers.write(5, &[8]), 1);
+        assert!(buffers.flip());
+        assert_eq!(&buffers.front()[4..7], [1, 8, 3]);
+        assert_eq!(&buffers.front()[14..], [9, 9]);
+        // Only the damage was copied, not the whole buffer
+        assert_eq!(buffers.presented_bytes(), 3 + 2 + 1);
+        assert_eq!(buffers.write(16, &[1]), 0);
+
+        // Overlapping and many small ranges are coalesced
+        for i in 0..MAX_DAMAGE_RANGES + 2 {
+            buffers.write(i % 8, &[i as u8, i as u8]);
+        }
+        assert!(buffers.flip());
+        assert_eq!(buffers.presented_bytes(), 6 + 9);
+
+        // Timer vsync thread
+        let presenter = Presenter::new(DoubleBuffer::in_memory(8), VsyncSource::timer(500));
+        assert_eq!(presenter.vsync_source(), "timer");
+        presenter.write(0, &[7; 8]);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while presenter.flips() == 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        assert_eq!(presenter.flips(), 1);
+        assert!(presenter.with_front(|front| front == [7; 8]));
+
+        // Paused: nothing reaches the front buffer
+        presenter.set_paused(true);
+        presenter.write(0, &[3; 8]);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(presenter.flips(), 1);
+        assert!(presenter.with_front(|front| front == [7; 8]));
+    }
+}
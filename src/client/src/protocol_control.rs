@@ -0,0 +1,336 @@
+// WASMA - Runtime protocol control
+// One entry per wasma.in.conf `protocol_def` endpoint. The streaming engine
+// (WGClient) reports connection state, traffic and errors here; the GUI's
+// protocols panel reads the stats and asks for reconnects or disables
+// endpoints, which the engine picks up through `interrupt`. State changes are
+// also pushed to `subscribe` receivers (webhooks).
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+use crate::parser::{Protocol, ProtocolConfig};
+
+/// Span over which throughput and frame rate are averaged
+pub const RATE_WINDOW: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EndpointState {
+    /// Configured, no stream (never connected, lost, or waiting for a reconnect)
+    Disconnected,
+    Connecting,
+    Connected,
+    Disabled,
+}
+
+impl EndpointState {
+    pub fn name(self) -> &'static str {
+        match self {
+            EndpointState::Disconnected => "disconnected",
+            EndpointState::Connecting => "connecting",
+            EndpointState::Connected => "connected",
+            EndpointState::Disabled => "disabled",
+        }
+    }
+}
+
+/// What the GUI shows for one endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EndpointStats {
+    /// Position in the configured protocol list
+    pub index: usize,
+    pub protocol: Protocol,
+    /// "ip:port" or "domain:port"
+    pub endpoint: String,
+    pub state: EndpointState,
+    pub bytes: u64,
+    pub frames: u64,
+    /// Frames discarded at the display (session locked)
+    pub dropped: u64,
+    /// Bytes per second over RATE_WINDOW
+    pub throughput: f64,
+    pub fps: f64,
+    pub reconnects: u32,
+    pub last_error: Option<String>,
+}
+
+struct Endpoint {
+    stats: EndpointStats,
+    reconnect_requested: bool,
+    connected_before: bool,
+    // (arrival, bytes) of recent frames, for the rates
+    samples: VecDeque<(Instant, usize)>,
+    interrupt: Arc<Notify>,
+}
+
+impl Endpoint {
+    fn new(index: usize, config: &ProtocolConfig) -> Self {
+        Self {
+            stats: EndpointStats {
+                index,
+                protocol: config.protocol.clone(),
+                endpoint: endpoint_name(config),
+                state: EndpointState::Disconnected,
+                bytes: 0,
+                frames: 0,
+                dropped: 0,
+                throughput: 0.0,
+                fps: 0.0,
+                reconnects: 0,
+                last_error: None,
+            },
+            reconnect_requested: false,
+            connected_before: false,
+            samples: VecDeque::new(),
+            interrupt: Arc::new(Notify::new()),
+        }
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self.samples.front().is_some_and(|(at, _)| now.duration_since(*at) > RATE_WINDOW) {
+            self.samples.pop_front();
+        }
+    }
+}
+
+pub fn endpoint_name(config: &ProtocolConfig) -> String {
+    match &config.domain {
+        Some(domain) => format!("{}:{}", domain, config.port),
+        None => format!("{}:{}", config.ip, config.port),
+    }
+}
+
+/// An endpoint's state changed from `previous` to `stats.state`
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndpointTransition {
+    pub previous: EndpointState,
+    pub stats: EndpointStats,
+}
+
+#[derive(Default)]
+pub struct ProtocolControl {
+    endpoints: Mutex<Vec<Endpoint>>,
+    subscribers: Mutex<Vec<Sender<EndpointTransition>>>,
+}
+
+static GLOBAL: OnceLock<Arc<ProtocolControl>> = OnceLock::new();
+
+impl ProtocolControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process-wide instance shared by the engine and the GUI
+    pub fn global() -> Arc<ProtocolControl> {
+        GLOBAL.get_or_init(|| Arc::new(ProtocolControl::new())).clone()
+    }
+
+    /// Follow the configured protocol list; endpoints that are still
+    /// configured keep their counters and state
+    pub fn configure(&self, protocols: &[ProtocolConfig]) {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let mut previous = std::mem::take(&mut *endpoints);
+        for (index, config) in protocols.iter().enumerate() {
+            let name = endpoint_name(config);
+            let endpoint = match previous.iter().position(|e| e.stats.protocol == config.protocol && e.stats.endpoint == name) {
+                Some(pos) => {
+                    let mut endpoint = previous.remove(pos);
+                    endpoint.stats.index = index;
+                    endpoint
+                }
+                None => Endpoint::new(index, config),
+            };
+            endpoints.push(endpoint);
+        }
+        // Streams of dropped endpoints stop at their next check
+        for endpoint in previous {
+            endpoint.interrupt.notify_one();
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<EndpointStats> {
+        let now = Instant::now();
+        let secs = RATE_WINDOW.as_secs_f64();
+        self.endpoints.lock().unwrap().iter_mut().map(|endpoint| {
+            endpoint.prune(now);
+            let mut stats = endpoint.stats.clone();
+            stats.throughput = endpoint.samples.iter().map(|(_, bytes)| *bytes as f64).sum::<f64>() / secs;
+            stats.fps = endpoint.samples.len() as f64 / secs;
+            stats
+        }).collect()
+    }
+
+    fn with_endpoint<R>(&self, index: usize, f: impl FnOnce(&mut Endpoint) -> R) -> Option<R> {
+        self.endpoints.lock().unwrap().get_mut(index).map(f)
+    }
+
+    /// Receives every state change; dropped receivers are pruned on the next one
+    pub fn subscribe(&self) -> Receiver<EndpointTransition> {
+        let (tx, rx) = channel();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// Run `f` on the endpoint and tell subscribers if its state changed
+    fn update(&self, index: usize, f: impl FnOnce(&mut Endpoint)) {
+        let transition = self.with_endpoint(index, |endpoint| {
+            let previous = endpoint.stats.state;
+            f(endpoint);
+            (endpoint.stats.state != previous).then(|| EndpointTransition { previous, stats: endpoint.stats.clone() })
+        }).flatten();
+        if let Some(transition) = transition {
+            self.subscribers.lock().unwrap().retain(|tx| tx.send(transition.clone()).is_ok());
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Requests (GUI side)
+    // ------------------------------------------------------------------------
+
+    /// Drop the current stream of `index` and connect again; false if unknown or disabled
+    pub fn request_reconnect(&self, index: usize) -> bool {
+        self.with_endpoint(index, |endpoint| {
+            if endpoint.stats.state == EndpointState::Disabled {
+                return false;
+            }
+            endpoint.reconnect_requested = true;
+            endpoint.interrupt.notify_one();
+            true
+        }).unwrap_or(false)
+    }
+
+    /// Disabling closes the stream; enabling connects it again
+    pub fn set_enabled(&self, index: usize, enabled: bool) -> bool {
+        let mut known = false;
+        self.update(index, |endpoint| {
+            known = true;
+            let enabled_now = endpoint.stats.state != EndpointState::Disabled;
+            if enabled == enabled_now {
+                return;
+            }
+            endpoint.stats.state = if enabled { EndpointState::Disconnected } else { EndpointState::Disabled };
+            endpoint.reconnect_requested = enabled;
+            endpoint.interrupt.notify_one();
+        });
+        known
+    }
+
+    // ------------------------------------------------------------------------
+    // Reports (engine side)
+    // ------------------------------------------------------------------------
+
+    /// Woken whenever a reconnect or a disable is requested for `index`
+    pub fn interrupt(&self, index: usize) -> Option<Arc<Notify>> {
+        self.with_endpoint(index, |endpoint| endpoint.interrupt.clone())
+    }
+
+    pub fn is_enabled(&self, index: usize) -> bool {
+        self.with_endpoint(index, |endpoint| endpoint.stats.state != EndpointState::Disabled).unwrap_or(false)
+    }
+
+    /// Consume a pending reconnect request
+    pub fn take_reconnect(&self, index: usize) -> bool {
+        self.with_endpoint(index, |endpoint| std::mem::take(&mut endpoint.reconnect_requested)).unwrap_or(false)
+    }
+
+    /// A disabled endpoint stays disabled whatever the engine reports
+    pub fn set_state(&self, index: usize, state: EndpointState) {
+        self.update(index, |endpoint| {
+            if endpoint.stats.state == EndpointState::Disabled {
+                return;
+            }
+            if state == EndpointState::Connected {
+                if endpoint.connected_before {
+                    endpoint.stats.reconnects += 1;
+                }
+                endpoint.connected_before = true;
+            }
+            endpoint.stats.state = state;
+        });
+    }
+
+    /// The stream ended or could not be opened
+    pub fn record_error(&self, index: usize, error: impl Into<String>) {
+        let error = error.into();
+        self.update(index, |endpoint| {
+            endpoint.stats.last_error = Some(error);
+            if endpoint.stats.state != EndpointState::Disabled {
+                endpoint.stats.state = EndpointState::Disconnected;
+            }
+        });
+    }
+
+    pub fn record_frame(&self, index: usize, bytes: usize) {
+        self.with_endpoint(index, |endpoint| {
+            let now = Instant::now();
+            endpoint.stats.frames += 1;
+            endpoint.stats.bytes += bytes as u64;
+            endpoint.samples.push_back((now, bytes));
+            endpoint.prune(now);
+        });
+    }
+
+    pub fn record_drop(&self, index: usize) {
+        self.with_endpoint(index, |endpoint| endpoint.stats.dropped += 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(protocol: Protocol, port: u16) -> ProtocolConfig {
+        ProtocolConfig { protocol, ip: "127.0.0.1".parse().unwrap(), port, domain: None }
+    }
+
+    #[test]
+    fn test_protocol_control() {
+        let control = ProtocolControl::new();
+        control.configure(&[endpoint(Protocol::Http, 8080), endpoint(Protocol::Grpc, 50051)]);
+        let transitions = control.subscribe();
+
+        control.set_state(1, EndpointState::Connected);
+        control.record_frame(1, 1000);
+        control.record_frame(1, 3000);
+        control.record_drop(1);
+        let stats = &control.snapshot()[1];
+        assert_eq!(stats.endpoint, "127.0.0.1:50051");
+        assert_eq!((stats.state, stats.frames, stats.bytes, stats.dropped), (EndpointState::Connected, 2, 4000, 1));
+        assert_eq!(stats.throughput, 4000.0 / RATE_WINDOW.as_secs_f64());
+        assert_eq!(stats.fps, 2.0 / RATE_WINDOW.as_secs_f64());
+
+        // Reconnect: the engine is woken and consumes the request once
+        assert!(control.request_reconnect(1));
+        assert!(control.take_reconnect(1));
+        assert!(!control.take_reconnect(1));
+        control.record_error(1, "connection reset");
+        control.set_state(1, EndpointState::Connected);
+        assert_eq!(control.snapshot()[1].reconnects, 1);
+        assert_eq!(control.snapshot()[1].last_error.as_deref(), Some("connection reset"));
+        let states: Vec<_> = transitions.try_iter().map(|t| (t.previous, t.stats.state)).collect();
+        assert_eq!(states, [
+            (EndpointState::Disconnected, EndpointState::Connected),
+            (EndpointState::Connected, EndpointState::Disconnected),
+            (EndpointState::Disconnected, EndpointState::Connected),
+        ]);
+
+        // Disabled endpoints ignore engine reports and reconnect requests
+        assert!(control.set_enabled(0, false));
+        control.set_state(0, EndpointState::Connected);
+        assert_eq!(control.snapshot()[0].state, EndpointState::Disabled);
+        assert!(!control.request_reconnect(0));
+        assert!(!control.is_enabled(0));
+        assert!(control.set_enabled(0, true));
+        assert!(control.take_reconnect(0));
+
+        // Reloading keeps surviving endpoints and their counters
+        control.configure(&[endpoint(Protocol::Grpc, 50051)]);
+        let stats = control.snapshot();
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].index, stats[0].frames), (0, 2));
+        assert!(!control.request_reconnect(5));
+    }
+}
@@ -1,5 +1,9 @@
 // protocols.rs
 use crate::parser::{ConfigParser, ParserError, Protocol, ProtocolConfig, WasmaConfig};
+use crate::grpc::{FrameRequest, FrameStream, GrpcClient};
+use crate::pixel_format::{self, FrameHeader};
+use crate::protocol_control::{EndpointState, ProtocolControl};
+use crate::tor::{self, SocksTarget, TorError, TorStatus, TOR_CONNECT_TIMEOUT};
 use std::sync::Arc;
 use std::net::TcpStream;
 use std::io::{Read, Write};
@@ -11,6 +15,12 @@ pub trait ProtocolStream: Send {
     async fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize>;
     async fn write(&mut self, buf: &[u8]) -> std::io::Result<usize>;
     async fn flush(&mut self) -> std::io::Result<()>;
+
+    /// Ask the remote for frames of `width`x`height` (adaptive resolution).
+    /// Ok(false): the remote cannot resize, frames are downscaled locally instead.
+    async fn request_resize(&mut self, _width: u32, _height: u32) -> std::io::Result<bool> {
+        Ok(false)
+    }
     
     async fn next_message(&mut self) -> std::io::Result<Option<Vec<u8>>> {
         let mut buf = vec![0u8; 65536];
@@ -33,6 +43,9 @@ pub trait ProtocolStream: Send {
 pub struct ProtocolManager {
     config: Arc<WasmaConfig>,
     pub active_streams: Vec<Box<dyn ProtocolStream>>,
+    // Configured protocol index of each active stream
+    stream_endpoints: Vec<usize>,
+    control: Arc<ProtocolControl>,
 }
 
 impl ProtocolManager {
@@ -40,19 +53,28 @@ impl ProtocolManager {
         let parser = ConfigParser::new(config_path);
         let config = parser.load()?;
         
-        Ok(Self {
-            config: Arc::new(config),
-            active_streams: Vec::new(),
-        })
+        Ok(Self::from_config(Arc::new(config)))
     }
 
     pub fn from_config(config: Arc<WasmaConfig>) -> Self {
+        let control = ProtocolControl::global();
+        control.configure(&config.uri_handling.protocols);
         Self {
             config,
             active_streams: Vec::new(),
+            stream_endpoints: Vec::new(),
+            control,
         }
     }
 
+    pub fn shared_config(&self) -> Arc<WasmaConfig> {
+        self.config.clone()
+    }
+
+    pub fn control(&self) -> Arc<ProtocolControl> {
+        self.control.clone()
+    }
+
     pub fn load_config(&mut self) -> Result<(), ParserError> {
         // Config zaten new()'de yüklendi, sadece validate et
         self.validate()
@@ -67,15 +89,28 @@ impl ProtocolManager {
         Some(&self.config)
     }
 
+    /// Bootstrap state of the Tor daemon, when a tor:// protocol is configured
+    pub fn tor_status(&self) -> Option<TorStatus> {
+        self.config.uri_handling.protocols.iter()
+            .any(|p| p.protocol == Protocol::Tor)
+            .then(|| tor::status(&self.config.uri_handling.tor))
+    }
+
     /// Tüm protokollere bağlan
     pub fn connect_all(&mut self) -> Result<(), String> {
-        for proto_config in &self.config.uri_handling.protocols {
+        for (index, proto_config) in self.config.uri_handling.protocols.iter().enumerate() {
+            if !self.control.is_enabled(index) {
+                continue;
+            }
+            self.control.set_state(index, EndpointState::Connecting);
             match self.connect_protocol(proto_config) {
                 Ok(stream) => {
+                    self.control.set_state(index, EndpointState::Connected);
                     self.active_streams.push(stream);
+                    self.stream_endpoints.push(index);
                     println!("✅ Connected to {:?} at {}:{}", 
                         proto_config.protocol,
-                        proto_config.ip,
+                        proto_config.domain.clone().unwrap_or_else(|| proto_config.ip.to_string()),
                         proto_config.port
                     );
                 }
@@ -83,13 +118,14 @@ impl ProtocolManager {
                     eprintln!("❌ Failed to connect to {:?}: {}", 
                         proto_config.protocol, e
                     );
+                    self.control.record_error(index, e);
                 }
             }
         }
         Ok(())
     }
 
-    fn connect_protocol(&self, config: &ProtocolConfig) -> Result<Box<dyn ProtocolStream>, String> {
+    pub fn connect_protocol(&self, config: &ProtocolConfig) -> Result<Box<dyn ProtocolStream>, String> {
         let addr = format!("{}:{}", config.ip, config.port);
         
         match config.protocol {
@@ -101,10 +137,22 @@ impl ProtocolManager {
             Protocol::Grpc => {
                 let stream = TcpStream::connect(&addr)
                     .map_err(|e| format!("gRPC connection failed: {}", e))?;
-                Ok(Box::new(GrpcStream::new(stream)))
+                let authority = match &config.domain {
+                    Some(domain) => format!("{}:{}", domain, config.port),
+                    None => addr,
+                };
+                Ok(Box::new(GrpcStream::new(stream, authority)))
             }
             Protocol::Tor => {
-                let stream = TcpStream::connect(&addr)
+                let tor_config = &self.config.uri_handling.tor;
+                match tor::status(tor_config) {
+                    TorStatus::Bootstrapping(phase) => return Err(TorError::Bootstrapping(phase).to_string()),
+                    TorStatus::Unreachable(reason) => return Err(format!("Tor daemon at {}: {}", tor_config.socks, reason)),
+                    status => println!("🧅 Tor {}", status),
+                }
+
+                let target = SocksTarget::for_endpoint(config.ip, config.port, config.domain.as_deref());
+                let stream = tor::connect(tor_config, &target, TOR_CONNECT_TIMEOUT)
                     .map_err(|e| format!("Tor connection failed: {}", e))?;
                 Ok(Box::new(TorStream::new(stream)))
             }
@@ -126,6 +174,16 @@ impl ProtocolManager {
     pub fn get_streams_mut(&mut self) -> &mut Vec<Box<dyn ProtocolStream>> {
         &mut self.active_streams
     }
+
+    /// Connected streams with their configured protocol index
+    pub fn take_streams(&mut self) -> Vec<(usize, Box<dyn ProtocolStream>)> {
+        let endpoints = std::mem::take(&mut self.stream_endpoints);
+        std::mem::take(&mut self.active_streams)
+            .into_iter()
+            .enumerate()
+            .map(|(i, stream)| (endpoints.get(i).copied().unwrap_or(i), stream))
+            .collect()
+    }
 }
 
 // HTTP/HTTPS Stream
@@ -160,15 +218,39 @@ impl ProtocolStream for HttpStream {
     }
 }
 
-// gRPC Stream
+// gRPC Stream - message based: frames come from a StreamFrames call, not raw reads
 struct GrpcStream {
-    stream: TcpStream,
+    // Until the HTTP/2 handshake, which needs the async runtime
+    stream: Option<TcpStream>,
+    authority: String,
+    client: Option<GrpcClient>,
+    frames: Option<FrameStream>,
+    request: FrameRequest,
 }
 
 impl GrpcStream {
-    fn new(stream: TcpStream) -> Self {
+    fn new(stream: TcpStream, authority: String) -> Self {
         stream.set_nonblocking(true).ok();
-        Self { stream }
+        Self { stream: Some(stream), authority, client: None, frames: None, request: FrameRequest::default() }
+    }
+
+    async fn client(&mut self) -> std::io::Result<GrpcClient> {
+        if let Some(client) = &self.client {
+            return Ok(client.clone());
+        }
+        let stream = self.stream.take()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "gRPC handshake failed earlier"))?;
+        let client = GrpcClient::handshake(tokio::net::TcpStream::from_std(stream)?, self.authority.clone())
+            .await
+            .map_err(std::io::Error::other)?;
+        self.client = Some(client.clone());
+        Ok(client)
+    }
+
+    async fn restart_call(&mut self) -> std::io::Result<()> {
+        let client = self.client().await?;
+        self.frames = Some(client.stream_frames(&self.request).await.map_err(std::io::Error::other)?);
+        Ok(())
     }
 }
 
@@ -178,16 +260,38 @@ impl ProtocolStream for GrpcStream {
         Protocol::Grpc
     }
 
-    async fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        self.stream.read(buf)
+    async fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "gRPC streams are read with next_message"))
     }
 
-    async fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.stream.write(buf)
+    async fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "gRPC streams do not accept raw writes"))
     }
 
     async fn flush(&mut self) -> std::io::Result<()> {
-        self.stream.flush()
+        Ok(())
+    }
+
+    /// A new StreamFrames call asking for the new size; the old call is dropped
+    async fn request_resize(&mut self, width: u32, height: u32) -> std::io::Result<bool> {
+        self.request.width = width;
+        self.request.height = height;
+        if self.frames.is_some() {
+            self.restart_call().await?;
+        }
+        Ok(true)
+    }
+
+    /// Next frame as a BGRA `pixel_format::encode_frame` message; None when the server ends the call
+    async fn next_message(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        if self.frames.is_none() {
+            self.restart_call().await?;
+        }
+        let frames = self.frames.as_mut().expect("call started above");
+        match frames.next_frame().await {
+            Ok(frame) => Ok(frame.map(|f| pixel_format::encode_frame(&FrameHeader::bgra(f.width, f.height), &f.to_bgra()))),
+            Err(e) => Err(std::io::Error::other(e)),
+        }
     }
 }
 
@@ -0,0 +1,480 @@
+// WASMA - Software renderer
+// `in_request_withed:renderer_soft` in wasma.in.conf. Frames are composited
+// on the CPU into a memory framebuffer (scaled to the window) and presented
+// through the same fallbacks the window client uses: X11 PutImage or a
+// Wayland shm buffer. Without a display the framebuffer is kept in memory
+// only, so headless CI and machines without GL/OpenCL drivers still run.
+
+use crate::pixel_format::FrameHeader;
+
+#[cfg(feature = "x11")]
+use x11rb::connection::{Connection as _, RequestConnection as _};
+#[cfg(feature = "x11")]
+use x11rb::protocol::xproto::{self, ConnectionExt as _};
+#[cfg(feature = "x11")]
+use x11rb::protocol::Event as X11Event;
+#[cfg(feature = "x11")]
+use x11rb::rust_connection::RustConnection;
+
+#[cfg(feature = "wayland")]
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+#[cfg(feature = "wayland")]
+use wayland_client::protocol::{wl_buffer, wl_compositor, wl_registry, wl_shm, wl_shm_pool, wl_surface};
+#[cfg(feature = "wayland")]
+use wayland_client::{delegate_noop, Connection, Dispatch, EventQueue, QueueHandle};
+#[cfg(feature = "wayland")]
+use wayland_protocols::xdg::shell::client::{xdg_surface, xdg_toplevel, xdg_wm_base};
+
+/// Packed BGRA pixels, what every presenter takes as is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoftFramebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl SoftFramebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height, pixels: vec![0; width as usize * height as usize * 4] }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Resizing clears the framebuffer
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) != (self.width, self.height) {
+            *self = Self::new(width, height);
+        }
+    }
+
+    pub fn clear(&mut self, bgra: [u8; 4]) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&bgra);
+        }
+    }
+
+    /// Draw a BGRA frame scaled (nearest neighbour) into `dst`, clipped to the
+    /// framebuffer; false if the frame is shorter than its header says
+    pub fn composite(&mut self, frame: &FrameHeader, data: &[u8], dst: (i32, i32, u32, u32)) -> bool {
+        let (dx, dy, dw, dh) = dst;
+        if data.len() < frame.required_len() {
+            return false;
+        }
+        if frame.width == 0 || frame.height == 0 || dw == 0 || dh == 0 {
+            return true;
+        }
+
+        let fb_width = self.width as i64;
+        let x0 = (dx as i64).clamp(0, fb_width);
+        let x1 = (dx as i64 + dw as i64).clamp(0, fb_width);
+        let y0 = (dy as i64).clamp(0, self.height as i64);
+        let y1 = (dy as i64 + dh as i64).clamp(0, self.height as i64);
+
+        for y in y0..y1 {
+            let sy = ((y - dy as i64) * frame.height as i64 / dh as i64) as usize;
+            let src_row = &data[sy * frame.stride as usize..];
+            let dst_row = &mut self.pixels[(y * fb_width) as usize * 4..][..self.width as usize * 4];
+            for x in x0..x1 {
+                let sx = ((x - dx as i64) * frame.width as i64 / dw as i64) as usize;
+                dst_row[x as usize * 4..][..4].copy_from_slice(&src_row[sx * 4..][..4]);
+            }
+        }
+        true
+    }
+}
+
+/// Where the framebuffer goes
+pub enum SoftPresenter {
+    #[cfg(feature = "x11")]
+    X11(Box<X11Presenter>),
+    #[cfg(feature = "wayland")]
+    Wayland(Box<WaylandPresenter>),
+    /// No display: the framebuffer is only kept in memory
+    Headless,
+}
+
+impl SoftPresenter {
+    /// Wayland when $WAYLAND_DISPLAY is set, X11 when $DISPLAY is, else headless
+    pub fn detect(width: u32, height: u32) -> Self {
+        #[cfg(feature = "wayland")]
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            match WaylandPresenter::open(width, height) {
+                Ok(presenter) => return SoftPresenter::Wayland(Box::new(presenter)),
+                Err(e) => eprintln!("⚠️  Soft renderer: {}", e),
+            }
+        }
+        #[cfg(feature = "x11")]
+        if std::env::var_os("DISPLAY").is_some() {
+            match X11Presenter::open(width, height) {
+                Ok(presenter) => return SoftPresenter::X11(Box::new(presenter)),
+                Err(e) => eprintln!("⚠️  Soft renderer: {}", e),
+            }
+        }
+        let _ = (width, height);
+        SoftPresenter::Headless
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "x11")]
+            SoftPresenter::X11(_) => "x11",
+            #[cfg(feature = "wayland")]
+            SoftPresenter::Wayland(_) => "wayland",
+            SoftPresenter::Headless => "headless",
+        }
+    }
+
+    /// New window size after the user or compositor resized it
+    fn poll_resize(&mut self) -> Option<(u32, u32)> {
+        match self {
+            #[cfg(feature = "x11")]
+            SoftPresenter::X11(presenter) => presenter.poll_resize(),
+            #[cfg(feature = "wayland")]
+            SoftPresenter::Wayland(presenter) => presenter.poll_resize(),
+            SoftPresenter::Headless => None,
+        }
+    }
+
+    fn present(&mut self, framebuffer: &SoftFramebuffer) -> Result<(), String> {
+        match self {
+            #[cfg(feature = "x11")]
+            SoftPresenter::X11(presenter) => presenter.present(framebuffer),
+            #[cfg(feature = "wayland")]
+            SoftPresenter::Wayland(presenter) => presenter.present(framebuffer),
+            SoftPresenter::Headless => {
+                let _ = framebuffer;
+                Ok(())
+            }
+        }
+    }
+}
+
+pub struct SoftRenderer {
+    framebuffer: SoftFramebuffer,
+    presenter: SoftPresenter,
+}
+
+impl SoftRenderer {
+    /// Framebuffer of `width`x`height` shown on the detected display
+    pub fn new(width: u32, height: u32) -> Self {
+        let presenter = SoftPresenter::detect(width, height);
+        println!("🖌️  Soft renderer: {}x{} via {}", width, height, presenter.name());
+        Self { framebuffer: SoftFramebuffer::new(width, height), presenter }
+    }
+
+    pub fn headless(width: u32, height: u32) -> Self {
+        Self { framebuffer: SoftFramebuffer::new(width, height), presenter: SoftPresenter::Headless }
+    }
+
+    pub fn framebuffer(&self) -> &SoftFramebuffer {
+        &self.framebuffer
+    }
+
+    pub fn presenter(&self) -> &SoftPresenter {
+        &self.presenter
+    }
+
+    /// Composite a BGRA frame over the whole framebuffer and present it
+    pub fn render(&mut self, frame: &FrameHeader, data: &[u8]) -> Result<(), String> {
+        if let Some((width, height)) = self.presenter.poll_resize() {
+            self.framebuffer.resize(width, height);
+        }
+        let (width, height) = self.framebuffer.size();
+        if !self.framebuffer.composite(frame, data, (0, 0, width, height)) {
+            return Err(format!("{}x{} frame is truncated ({} bytes)", frame.width, frame.height, data.len()));
+        }
+        self.presenter.present(&self.framebuffer)
+    }
+}
+
+// ============================================================================
+// X11: PutImage into our own window
+// ============================================================================
+
+#[cfg(feature = "x11")]
+pub struct X11Presenter {
+    conn: RustConnection,
+    window: xproto::Window,
+    gc: xproto::Gcontext,
+    depth: u8,
+}
+
+#[cfg(feature = "x11")]
+impl X11Presenter {
+    pub fn open(width: u32, height: u32) -> Result<Self, String> {
+        let (conn, screen_num) = x11rb::connect(None).map_err(|e| format!("X11 connection failed: {}", e))?;
+        let screen = &conn.setup().roots[screen_num];
+        let (root, depth, black) = (screen.root, screen.root_depth, screen.black_pixel);
+
+        let window = conn.generate_id().map_err(|e| e.to_string())?;
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT, window, root,
+            0, 0, width.max(1) as u16, height.max(1) as u16, 0,
+            xproto::WindowClass::INPUT_OUTPUT, 0,
+            &xproto::CreateWindowAux::new()
+                .background_pixel(black)
+                .event_mask(xproto::EventMask::STRUCTURE_NOTIFY),
+        ).map_err(|e| e.to_string())?;
+        let gc = conn.generate_id().map_err(|e| e.to_string())?;
+        conn.create_gc(gc, window, &xproto::CreateGCAux::new()).map_err(|e| e.to_string())?;
+        conn.map_window(window).map_err(|e| e.to_string())?;
+        conn.flush().map_err(|e| e.to_string())?;
+        Ok(Self { conn, window, gc, depth })
+    }
+
+    fn poll_resize(&mut self) -> Option<(u32, u32)> {
+        let mut size = None;
+        while let Ok(Some(event)) = self.conn.poll_for_event() {
+            if let X11Event::ConfigureNotify(e) = event {
+                size = Some((e.width as u32, e.height as u32));
+            }
+        }
+        size
+    }
+
+    fn present(&mut self, framebuffer: &SoftFramebuffer) -> Result<(), String> {
+        let (width, height) = framebuffer.size();
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        // Z_PIXMAP depth 24 takes packed 32-bit BGRX rows; split into
+        // bands that fit the server's maximum request size
+        let stride = width as usize * 4;
+        let max_rows = ((self.conn.maximum_request_bytes().saturating_sub(64)) / stride).max(1);
+        for (band, rows) in framebuffer.pixels().chunks(stride * max_rows).enumerate() {
+            let y = (band * max_rows) as i16;
+            self.conn.put_image(
+                xproto::ImageFormat::Z_PIXMAP, self.window, self.gc,
+                width as u16, (rows.len() / stride) as u16,
+                0, y, 0, self.depth, rows,
+            ).map_err(|e| e.to_string())?;
+        }
+        self.conn.flush().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "x11")]
+impl Drop for X11Presenter {
+    fn drop(&mut self) {
+        self.conn.free_gc(self.gc).ok();
+        self.conn.destroy_window(self.window).ok();
+        self.conn.flush().ok();
+    }
+}
+
+// ============================================================================
+// Wayland: xdg_toplevel with one shm buffer per present
+// ============================================================================
+
+#[cfg(feature = "wayland")]
+pub struct WaylandPresenter {
+    queue: EventQueue<SoftWaylandState>,
+    state: SoftWaylandState,
+    surface: wl_surface::WlSurface,
+    xdg_surface: xdg_surface::XdgSurface,
+    toplevel: xdg_toplevel::XdgToplevel,
+    shm: wl_shm::WlShm,
+}
+
+#[cfg(feature = "wayland")]
+#[derive(Default)]
+struct SoftWaylandState {
+    configured: bool,
+    closed: bool,
+    /// Size from the last toplevel configure, not yet applied
+    pending_size: Option<(u32, u32)>,
+}
+
+#[cfg(feature = "wayland")]
+impl WaylandPresenter {
+    pub fn open(width: u32, height: u32) -> Result<Self, String> {
+        let conn = Connection::connect_to_env().map_err(|e| format!("Wayland connection failed: {}", e))?;
+        let (globals, mut queue) = registry_queue_init::<SoftWaylandState>(&conn)
+            .map_err(|e| format!("Wayland registry failed: {}", e))?;
+        let qh = queue.handle();
+
+        let compositor: wl_compositor::WlCompositor = globals.bind(&qh, 4..=6, ())
+            .map_err(|e| format!("wl_compositor unavailable: {}", e))?;
+        let shm = globals.bind(&qh, 1..=1, ())
+            .map_err(|e| format!("wl_shm unavailable: {}", e))?;
+        let wm_base: xdg_wm_base::XdgWmBase = globals.bind(&qh, 1..=5, ())
+            .map_err(|e| format!("xdg_wm_base unavailable: {}", e))?;
+
+        let surface = compositor.create_surface(&qh, ());
+        let xdg_surface = wm_base.get_xdg_surface(&surface, &qh, ());
+        let toplevel = xdg_surface.get_toplevel(&qh, ());
+        toplevel.set_title("WASMA".to_string());
+        surface.commit();
+
+        // Nothing may be attached before the first configure
+        let mut state = SoftWaylandState { pending_size: Some((width, height)), ..Default::default() };
+        while !state.configured {
+            queue.blocking_dispatch(&mut state).map_err(|e| format!("Wayland dispatch failed: {}", e))?;
+        }
+        Ok(Self { queue, state, surface, xdg_surface, toplevel, shm })
+    }
+
+    fn poll_resize(&mut self) -> Option<(u32, u32)> {
+        self.queue.dispatch_pending(&mut self.state).ok();
+        self.state.pending_size.take()
+    }
+
+    fn present(&mut self, framebuffer: &SoftFramebuffer) -> Result<(), String> {
+        if self.state.closed {
+            return Err("window closed by the compositor".to_string());
+        }
+        let (width, height) = framebuffer.size();
+        let qh = self.queue.handle();
+        // Frames carry no meaningful alpha
+        let buffer = crate::wayland_backend::shm_buffer(
+            &self.shm, &qh, width.max(1), height.max(1), framebuffer.pixels(), wl_shm::Format::Xrgb8888,
+        )?;
+        self.xdg_surface.set_window_geometry(0, 0, width as i32, height as i32);
+        self.surface.attach(Some(&buffer), 0, 0);
+        self.surface.damage_buffer(0, 0, width as i32, height as i32);
+        self.surface.commit();
+        self.queue.flush().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "wayland")]
+impl Drop for WaylandPresenter {
+    fn drop(&mut self) {
+        self.toplevel.destroy();
+        self.xdg_surface.destroy();
+        self.surface.destroy();
+        self.queue.flush().ok();
+    }
+}
+
+#[cfg(feature = "wayland")]
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for SoftWaylandState {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+#[cfg(feature = "wayland")]
+delegate_noop!(SoftWaylandState: wl_compositor::WlCompositor);
+#[cfg(feature = "wayland")]
+delegate_noop!(SoftWaylandState: wl_shm_pool::WlShmPool);
+#[cfg(feature = "wayland")]
+delegate_noop!(SoftWaylandState: ignore wl_shm::WlShm);
+#[cfg(feature = "wayland")]
+delegate_noop!(SoftWaylandState: ignore wl_surface::WlSurface);
+
+#[cfg(feature = "wayland")]
+impl Dispatch<wl_buffer::WlBuffer, ()> for SoftWaylandState {
+    fn event(
+        _: &mut Self,
+        buffer: &wl_buffer::WlBuffer,
+        event: wl_buffer::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_buffer::Event::Release = event {
+            buffer.destroy();
+        }
+    }
+}
+
+#[cfg(feature = "wayland")]
+impl Dispatch<xdg_wm_base::XdgWmBase, ()> for SoftWaylandState {
+    fn event(
+        _: &mut Self,
+        wm_base: &xdg_wm_base::XdgWmBase,
+        event: xdg_wm_base::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_wm_base::Event::Ping { serial } = event {
+            wm_base.pong(serial);
+        }
+    }
+}
+
+#[cfg(feature = "wayland")]
+impl Dispatch<xdg_surface::XdgSurface, ()> for SoftWaylandState {
+    fn event(
+        state: &mut Self,
+        xdg_surface: &xdg_surface::XdgSurface,
+        event: xdg_surface::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_surface::Event::Configure { serial } = event {
+            xdg_surface.ack_configure(serial);
+            state.configured = true;
+        }
+    }
+}
+
+#[cfg(feature = "wayland")]
+impl Dispatch<xdg_toplevel::XdgToplevel, ()> for SoftWaylandState {
+    fn event(
+        state: &mut Self,
+        _: &xdg_toplevel::XdgToplevel,
+        event: xdg_toplevel::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            // 0x0: the client picks, keep the current size
+            xdg_toplevel::Event::Configure { width, height, .. } if width > 0 && height > 0 => {
+                state.pending_size = Some((width as u32, height as u32));
+            }
+            xdg_toplevel::Event::Close => state.closed = true,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_soft_compositing() {
+        // 2x2 frame: blue, green / red, white
+        let frame = FrameHeader::bgra(2, 2);
+        let data = [255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255];
+
+        let mut renderer = SoftRenderer::headless(4, 4);
+        renderer.render(&frame, &data).unwrap();
+        let pixel = |fb: &SoftFramebuffer, x: usize, y: usize| fb.pixels()[(y * 4 + x) * 4..][..4].to_vec();
+        let fb = renderer.framebuffer();
+        // Scaled 2x: each source pixel covers a 2x2 block
+        assert_eq!(pixel(fb, 1, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(fb, 2, 0), [0, 255, 0, 255]);
+        assert_eq!(pixel(fb, 0, 3), [0, 0, 255, 255]);
+        assert_eq!(pixel(fb, 3, 3), [255, 255, 255, 255]);
+
+        // Clipped at the edges, untouched outside the destination
+        let mut fb = SoftFramebuffer::new(4, 4);
+        fb.clear([9, 9, 9, 255]);
+        assert!(fb.composite(&frame, &data, (-1, 3, 2, 2)));
+        assert_eq!(pixel(&fb, 0, 3), [0, 255, 0, 255]);
+        assert_eq!(pixel(&fb, 1, 3), [9, 9, 9, 255]);
+        assert_eq!(pixel(&fb, 0, 2), [9, 9, 9, 255]);
+
+        assert!(!fb.composite(&frame, &data[..8], (0, 0, 4, 4)));
+        assert!(renderer.render(&frame, &data[..8]).is_err());
+        assert_eq!(renderer.presenter().name(), "headless");
+    }
+}
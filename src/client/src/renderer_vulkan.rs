@@ -0,0 +1,563 @@
+// WASMA - Vulkan renderer
+// `in_request_withed:vulkan` in wasma.in.conf. Frames (BGRA, see pixel_format)
+// are copied cell by cell into a persistently mapped staging buffer, uploaded
+// to a frame-sized image and blitted (scaled) onto the next swapchain image.
+// The window comes from the windowing backend as a SurfaceTarget; one frame
+// is in flight at a time.
+
+use ash::extensions::khr;
+use ash::vk;
+use ash::{Device, Entry, Instance};
+use std::ffi::{c_void, CStr};
+use thiserror::Error;
+
+use crate::pixel_format::FrameHeader;
+
+#[derive(Error, Debug)]
+pub enum VulkanError {
+    #[error("Vulkan loader not available: {0}")]
+    Loader(String),
+
+    #[error("No Vulkan device can present to this surface")]
+    NoDevice,
+
+    #[error("Surface offers no usable format")]
+    NoSurfaceFormat,
+
+    #[error("No memory type for {0}")]
+    NoMemoryType(&'static str),
+
+    #[error("Vulkan call failed: {0}")]
+    Vk(#[from] vk::Result),
+}
+
+/// Native window the swapchain presents to; the backend that created it keeps it alive
+#[derive(Debug, Clone, Copy)]
+pub enum SurfaceTarget {
+    Xlib { display: *mut c_void, window: u64 },
+    Xcb { connection: *mut c_void, window: u32 },
+    Wayland { display: *mut c_void, surface: *mut c_void },
+}
+
+// Plain handles: only dereferenced by the Vulkan driver
+unsafe impl Send for SurfaceTarget {}
+
+impl SurfaceTarget {
+    fn extension(&self) -> &'static CStr {
+        match self {
+            SurfaceTarget::Xlib { .. } => khr::XlibSurface::name(),
+            SurfaceTarget::Xcb { .. } => khr::XcbSurface::name(),
+            SurfaceTarget::Wayland { .. } => khr::WaylandSurface::name(),
+        }
+    }
+
+    unsafe fn create_surface(&self, entry: &Entry, instance: &Instance) -> Result<vk::SurfaceKHR, vk::Result> {
+        match *self {
+            SurfaceTarget::Xlib { display, window } => {
+                let info = vk::XlibSurfaceCreateInfoKHR::builder()
+                    .dpy(display as *mut vk::Display)
+                    .window(window as vk::Window);
+                khr::XlibSurface::new(entry, instance).create_xlib_surface(&info, None)
+            }
+            SurfaceTarget::Xcb { connection, window } => {
+                let info = vk::XcbSurfaceCreateInfoKHR::builder().connection(connection).window(window);
+                khr::XcbSurface::new(entry, instance).create_xcb_surface(&info, None)
+            }
+            SurfaceTarget::Wayland { display, surface } => {
+                let info = vk::WaylandSurfaceCreateInfoKHR::builder().display(display).surface(surface);
+                khr::WaylandSurface::new(entry, instance).create_wayland_surface(&info, None)
+            }
+        }
+    }
+}
+
+/// Swapchain size: what the surface dictates, else the frame size within its limits
+pub fn swapchain_extent(caps: &vk::SurfaceCapabilitiesKHR, width: u32, height: u32) -> vk::Extent2D {
+    if caps.current_extent.width != u32::MAX {
+        return caps.current_extent;
+    }
+    vk::Extent2D {
+        width: width.clamp(caps.min_image_extent.width, caps.max_image_extent.width.max(caps.min_image_extent.width)),
+        height: height.clamp(caps.min_image_extent.height, caps.max_image_extent.height.max(caps.min_image_extent.height)),
+    }
+}
+
+/// BGRA matches the frames and needs no swizzle; otherwise the first format the surface offers
+pub fn pick_surface_format(formats: &[vk::SurfaceFormatKHR]) -> Option<vk::SurfaceFormatKHR> {
+    formats
+        .iter()
+        .find(|f| f.format == vk::Format::B8G8R8A8_UNORM)
+        .or_else(|| formats.first())
+        .map(|f| match f.format {
+            // Any format will do
+            vk::Format::UNDEFINED => vk::SurfaceFormatKHR { format: vk::Format::B8G8R8A8_UNORM, ..*f },
+            _ => *f,
+        })
+}
+
+struct Swapchain {
+    handle: vk::SwapchainKHR,
+    images: Vec<vk::Image>,
+    extent: vk::Extent2D,
+}
+
+/// Host-visible staging buffer, mapped for its whole life
+struct Staging {
+    buffer: vk::Buffer,
+    memory: vk::DeviceMemory,
+    mapped: *mut u8,
+    size: usize,
+}
+
+/// Device-local image the staging buffer is copied into, sized like the frames
+struct Upload {
+    image: vk::Image,
+    memory: vk::DeviceMemory,
+    width: u32,
+    height: u32,
+}
+
+pub struct VulkanRenderer {
+    _entry: Entry,
+    instance: Instance,
+    surface_fn: khr::Surface,
+    surface: vk::SurfaceKHR,
+    physical: vk::PhysicalDevice,
+    device: Device,
+    queue: vk::Queue,
+    swapchain_fn: khr::Swapchain,
+    swapchain: Option<Swapchain>,
+    surface_format: vk::SurfaceFormatKHR,
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    command_pool: vk::CommandPool,
+    commands: vk::CommandBuffer,
+    image_available: vk::Semaphore,
+    blit_done: vk::Semaphore,
+    in_flight: vk::Fence,
+    staging: Option<Staging>,
+    upload: Option<Upload>,
+}
+
+// The mapped staging pointer is owned memory, used only behind &mut self
+unsafe impl Send for VulkanRenderer {}
+
+impl VulkanRenderer {
+    pub fn new(target: SurfaceTarget) -> Result<Self, VulkanError> {
+        unsafe {
+            let entry = Entry::load().map_err(|e| VulkanError::Loader(e.to_string()))?;
+
+            let app_name = CStr::from_bytes_with_nul(b"WASMA\0").unwrap();
+            let app_info = vk::ApplicationInfo::builder()
+                .application_name(app_name)
+                .engine_name(app_name)
+                .api_version(vk::API_VERSION_1_1);
+            let extensions = [khr::Surface::name().as_ptr(), target.extension().as_ptr()];
+            let instance_info = vk::InstanceCreateInfo::builder()
+                .application_info(&app_info)
+                .enabled_extension_names(&extensions);
+            let instance = entry.create_instance(&instance_info, None)?;
+
+            let surface_fn = khr::Surface::new(&entry, &instance);
+            let surface = match target.create_surface(&entry, &instance) {
+                Ok(surface) => surface,
+                Err(e) => {
+                    instance.destroy_instance(None);
+                    return Err(e.into());
+                }
+            };
+
+            let Some((physical, family)) = Self::pick_device(&instance, &surface_fn, surface) else {
+                surface_fn.destroy_surface(surface, None);
+                instance.destroy_instance(None);
+                return Err(VulkanError::NoDevice);
+            };
+
+            let priorities = [1.0];
+            let queue_info = [vk::DeviceQueueCreateInfo::builder()
+                .queue_family_index(family)
+                .queue_priorities(&priorities)
+                .build()];
+            let device_extensions = [khr::Swapchain::name().as_ptr()];
+            let device_info = vk::DeviceCreateInfo::builder()
+                .queue_create_infos(&queue_info)
+                .enabled_extension_names(&device_extensions);
+            let device = instance.create_device(physical, &device_info, None)?;
+            let queue = device.get_device_queue(family, 0);
+
+            let command_pool = device.create_command_pool(
+                &vk::CommandPoolCreateInfo::builder()
+                    .queue_family_index(family)
+                    .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER),
+                None,
+            )?;
+            let commands = device.allocate_command_buffers(
+                &vk::CommandBufferAllocateInfo::builder()
+                    .command_pool(command_pool)
+                    .level(vk::CommandBufferLevel::PRIMARY)
+                    .command_buffer_count(1),
+            )?[0];
+
+            let surface_format = pick_surface_format(&surface_fn.get_physical_device_surface_formats(physical, surface)?)
+                .ok_or(VulkanError::NoSurfaceFormat)?;
+
+            let renderer = Self {
+                swapchain_fn: khr::Swapchain::new(&instance, &device),
+                memory_properties: instance.get_physical_device_memory_properties(physical),
+                image_available: device.create_semaphore(&vk::SemaphoreCreateInfo::default(), None)?,
+                blit_done: device.create_semaphore(&vk::SemaphoreCreateInfo::default(), None)?,
+                in_flight: device.create_fence(&vk::FenceCreateInfo::builder().flags(vk::FenceCreateFlags::SIGNALED), None)?,
+                _entry: entry,
+                instance,
+                surface_fn,
+                surface,
+                physical,
+                device,
+                queue,
+                swapchain: None,
+                surface_format,
+                command_pool,
+                commands,
+                staging: None,
+                upload: None,
+            };
+            println!("🌋 Vulkan renderer ready ({:?})", renderer.surface_format.format);
+            Ok(renderer)
+        }
+    }
+
+    /// First device with a queue that can both copy and present to `surface`
+    unsafe fn pick_device(
+        instance: &Instance,
+        surface_fn: &khr::Surface,
+        surface: vk::SurfaceKHR,
+    ) -> Option<(vk::PhysicalDevice, u32)> {
+        instance.enumerate_physical_devices().ok()?.into_iter().find_map(|physical| {
+            instance
+                .get_physical_device_queue_family_properties(physical)
+                .iter()
+                .enumerate()
+                .find(|(index, family)| {
+                    family.queue_flags.contains(vk::QueueFlags::GRAPHICS)
+                        && surface_fn
+                            .get_physical_device_surface_support(physical, *index as u32, surface)
+                            .unwrap_or(false)
+                })
+                .map(|(index, _)| (physical, index as u32))
+        })
+    }
+
+    fn memory_type(&self, bits: u32, flags: vk::MemoryPropertyFlags, what: &'static str) -> Result<u32, VulkanError> {
+        let props = &self.memory_properties;
+        (0..props.memory_type_count)
+            .find(|&i| bits & (1 << i) != 0 && props.memory_types[i as usize].property_flags.contains(flags))
+            .ok_or(VulkanError::NoMemoryType(what))
+    }
+
+    /// Upload one BGRA frame and present it. `cell_size` is the section
+    /// memory cell size the frame is copied in (0: one copy)
+    pub fn present(&mut self, frame: &FrameHeader, data: &[u8], cell_size: usize) -> Result<(), VulkanError> {
+        unsafe {
+            self.device.wait_for_fences(&[self.in_flight], true, u64::MAX)?;
+
+            if self.swapchain.is_none() {
+                self.create_swapchain(frame.width, frame.height)?;
+            }
+            self.ensure_staging(data.len())?;
+            self.ensure_upload(frame.width, frame.height)?;
+
+            let staging = self.staging.as_ref().unwrap();
+            let chunk = if cell_size == 0 { data.len().max(1) } else { cell_size };
+            for (i, cell) in data.chunks(chunk).enumerate() {
+                std::ptr::copy_nonoverlapping(cell.as_ptr(), staging.mapped.add(i * chunk), cell.len());
+            }
+
+            let (handle, extent) = self.swapchain.as_ref().map(|s| (s.handle, s.extent)).unwrap();
+            let index = match self.swapchain_fn.acquire_next_image(handle, u64::MAX, self.image_available, vk::Fence::null()) {
+                Ok((index, _)) => index,
+                Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                    // Window resized: present into a new swapchain from the next frame on
+                    self.destroy_swapchain();
+                    return Ok(());
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            self.device.reset_fences(&[self.in_flight])?;
+            let target = self.swapchain.as_ref().unwrap().images[index as usize];
+            self.record(target, extent)?;
+
+            let wait = [self.image_available];
+            let stages = [vk::PipelineStageFlags::TRANSFER];
+            let signal = [self.blit_done];
+            let commands = [self.commands];
+            let submit = vk::SubmitInfo::builder()
+                .wait_semaphores(&wait)
+                .wait_dst_stage_mask(&stages)
+                .command_buffers(&commands)
+                .signal_semaphores(&signal)
+                .build();
+            self.device.queue_submit(self.queue, &[submit], self.in_flight)?;
+
+            let swapchains = [handle];
+            let indices = [index];
+            let present = vk::PresentInfoKHR::builder()
+                .wait_semaphores(&signal)
+                .swapchains(&swapchains)
+                .image_indices(&indices);
+            match self.swapchain_fn.queue_present(self.queue, &present) {
+                Ok(false) => Ok(()),
+                Ok(true) | Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                    self.device.wait_for_fences(&[self.in_flight], true, u64::MAX)?;
+                    self.destroy_swapchain();
+                    Ok(())
+                }
+                Err(e) => Err(e.into()),
+            }
+        }
+    }
+
+    /// staging → upload image → blit onto the swapchain image
+    unsafe fn record(&self, target: vk::Image, extent: vk::Extent2D) -> Result<(), VulkanError> {
+        let staging = self.staging.as_ref().unwrap();
+        let upload = self.upload.as_ref().unwrap();
+        let cb = self.commands;
+        let device = &self.device;
+
+        device.reset_command_buffer(cb, vk::CommandBufferResetFlags::empty())?;
+        device.begin_command_buffer(
+            cb,
+            &vk::CommandBufferBeginInfo::builder().flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+        )?;
+
+        let to_dst = [
+            barrier(upload.image, vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::AccessFlags::empty(), vk::AccessFlags::TRANSFER_WRITE),
+            barrier(target, vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::AccessFlags::empty(), vk::AccessFlags::TRANSFER_WRITE),
+        ];
+        device.cmd_pipeline_barrier(cb, vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(), &[], &[], &to_dst);
+
+        let copy = vk::BufferImageCopy::builder()
+            .image_subresource(color_layers())
+            .image_extent(vk::Extent3D { width: upload.width, height: upload.height, depth: 1 })
+            .build();
+        device.cmd_copy_buffer_to_image(cb, staging.buffer, upload.image, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &[copy]);
+
+        let to_src = [barrier(upload.image, vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::TRANSFER_READ)];
+        device.cmd_pipeline_barrier(cb, vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(), &[], &[], &to_src);
+
+        let blit = vk::ImageBlit::builder()
+            .src_subresource(color_layers())
+            .src_offsets([vk::Offset3D::default(), corner(upload.width, upload.height)])
+            .dst_subresource(color_layers())
+            .dst_offsets([vk::Offset3D::default(), corner(extent.width, extent.height)])
+            .build();
+        device.cmd_blit_image(cb, upload.image, vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            target, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &[blit], vk::Filter::LINEAR);
+
+        let to_present = [barrier(target, vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::PRESENT_SRC_KHR,
+            vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::empty())];
+        device.cmd_pipeline_barrier(cb, vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            vk::DependencyFlags::empty(), &[], &[], &to_present);
+
+        device.end_command_buffer(cb)?;
+        Ok(())
+    }
+
+    unsafe fn create_swapchain(&mut self, width: u32, height: u32) -> Result<(), VulkanError> {
+        let caps = self.surface_fn.get_physical_device_surface_capabilities(self.physical, self.surface)?;
+        let extent = swapchain_extent(&caps, width, height);
+        let mut image_count = caps.min_image_count + 1;
+        if caps.max_image_count > 0 {
+            image_count = image_count.min(caps.max_image_count);
+        }
+
+        let info = vk::SwapchainCreateInfoKHR::builder()
+            .surface(self.surface)
+            .min_image_count(image_count)
+            .image_format(self.surface_format.format)
+            .image_color_space(self.surface_format.color_space)
+            .image_extent(extent)
+            .image_array_layers(1)
+            .image_usage(vk::ImageUsageFlags::TRANSFER_DST)
+            .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .pre_transform(caps.current_transform)
+            .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+            // FIFO is the one mode every driver has
+            .present_mode(vk::PresentModeKHR::FIFO)
+            .clipped(true);
+        let handle = self.swapchain_fn.create_swapchain(&info, None)?;
+        let images = self.swapchain_fn.get_swapchain_images(handle)?;
+        self.swapchain = Some(Swapchain { handle, images, extent });
+        Ok(())
+    }
+
+    unsafe fn destroy_swapchain(&mut self) {
+        if let Some(swapchain) = self.swapchain.take() {
+            self.device.device_wait_idle().ok();
+            self.swapchain_fn.destroy_swapchain(swapchain.handle, None);
+        }
+    }
+
+    /// Grow the staging buffer to at least `size` bytes
+    unsafe fn ensure_staging(&mut self, size: usize) -> Result<(), VulkanError> {
+        if self.staging.as_ref().is_some_and(|s| s.size >= size) {
+            return Ok(());
+        }
+        self.destroy_staging();
+
+        let buffer = self.device.create_buffer(
+            &vk::BufferCreateInfo::builder()
+                .size(size.max(1) as u64)
+                .usage(vk::BufferUsageFlags::TRANSFER_SRC)
+                .sharing_mode(vk::SharingMode::EXCLUSIVE),
+            None,
+        )?;
+        let requirements = self.device.get_buffer_memory_requirements(buffer);
+        let memory_type = self.memory_type(
+            requirements.memory_type_bits,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+            "the staging buffer",
+        )?;
+        let memory = self.device.allocate_memory(
+            &vk::MemoryAllocateInfo::builder().allocation_size(requirements.size).memory_type_index(memory_type),
+            None,
+        )?;
+        self.device.bind_buffer_memory(buffer, memory, 0)?;
+        let mapped = self.device.map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())? as *mut u8;
+        self.staging = Some(Staging { buffer, memory, mapped, size });
+        Ok(())
+    }
+
+    unsafe fn destroy_staging(&mut self) {
+        if let Some(staging) = self.staging.take() {
+            self.device.unmap_memory(staging.memory);
+            self.device.destroy_buffer(staging.buffer, None);
+            self.device.free_memory(staging.memory, None);
+        }
+    }
+
+    /// (Re)create the upload image when the frame size changes
+    unsafe fn ensure_upload(&mut self, width: u32, height: u32) -> Result<(), VulkanError> {
+        if self.upload.as_ref().is_some_and(|u| (u.width, u.height) == (width, height)) {
+            return Ok(());
+        }
+        self.destroy_upload();
+
+        let image = self.device.create_image(
+            &vk::ImageCreateInfo::builder()
+                .image_type(vk::ImageType::TYPE_2D)
+                .format(vk::Format::B8G8R8A8_UNORM)
+                .extent(vk::Extent3D { width: width.max(1), height: height.max(1), depth: 1 })
+                .mip_levels(1)
+                .array_layers(1)
+                .samples(vk::SampleCountFlags::TYPE_1)
+                .tiling(vk::ImageTiling::OPTIMAL)
+                .usage(vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::TRANSFER_SRC)
+                .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                .initial_layout(vk::ImageLayout::UNDEFINED),
+            None,
+        )?;
+        let requirements = self.device.get_image_memory_requirements(image);
+        let memory_type = self.memory_type(
+            requirements.memory_type_bits,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            "the upload image",
+        )?;
+        let memory = self.device.allocate_memory(
+            &vk::MemoryAllocateInfo::builder().allocation_size(requirements.size).memory_type_index(memory_type),
+            None,
+        )?;
+        self.device.bind_image_memory(image, memory, 0)?;
+        self.upload = Some(Upload { image, memory, width, height });
+        Ok(())
+    }
+
+    unsafe fn destroy_upload(&mut self) {
+        if let Some(upload) = self.upload.take() {
+            self.device.destroy_image(upload.image, None);
+            self.device.free_memory(upload.memory, None);
+        }
+    }
+}
+
+impl Drop for VulkanRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.device_wait_idle().ok();
+            self.destroy_swapchain();
+            self.destroy_staging();
+            self.destroy_upload();
+            self.device.destroy_fence(self.in_flight, None);
+            self.device.destroy_semaphore(self.blit_done, None);
+            self.device.destroy_semaphore(self.image_available, None);
+            self.device.destroy_command_pool(self.command_pool, None);
+            self.device.destroy_device(None);
+            self.surface_fn.destroy_surface(self.surface, None);
+            self.instance.destroy_instance(None);
+        }
+    }
+}
+
+fn color_layers() -> vk::ImageSubresourceLayers {
+    vk::ImageSubresourceLayers { aspect_mask: vk::ImageAspectFlags::COLOR, mip_level: 0, base_array_layer: 0, layer_count: 1 }
+}
+
+fn corner(width: u32, height: u32) -> vk::Offset3D {
+    vk::Offset3D { x: width as i32, y: height as i32, z: 1 }
+}
+
+fn barrier(
+    image: vk::Image,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout,
+    src_access_mask: vk::AccessFlags,
+    dst_access_mask: vk::AccessFlags,
+) -> vk::ImageMemoryBarrier {
+    vk::ImageMemoryBarrier::builder()
+        .image(image)
+        .old_layout(old_layout)
+        .new_layout(new_layout)
+        .src_access_mask(src_access_mask)
+        .dst_access_mask(dst_access_mask)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .subresource_range(vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        })
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swapchain_setup() {
+        let mut caps = vk::SurfaceCapabilitiesKHR {
+            min_image_extent: vk::Extent2D { width: 1, height: 1 },
+            max_image_extent: vk::Extent2D { width: 1920, height: 1080 },
+            current_extent: vk::Extent2D { width: u32::MAX, height: u32::MAX },
+            ..Default::default()
+        };
+        // No fixed extent: the frame size, clamped
+        assert_eq!(swapchain_extent(&caps, 2560, 720), vk::Extent2D { width: 1920, height: 720 });
+        caps.current_extent = vk::Extent2D { width: 800, height: 600 };
+        assert_eq!(swapchain_extent(&caps, 2560, 720), vk::Extent2D { width: 800, height: 600 });
+
+        let srgb = vk::SurfaceFormatKHR { format: vk::Format::R8G8B8A8_SRGB, color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR };
+        let bgra = vk::SurfaceFormatKHR { format: vk::Format::B8G8R8A8_UNORM, color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR };
+        assert_eq!(pick_surface_format(&[srgb, bgra]).unwrap().format, vk::Format::B8G8R8A8_UNORM);
+        assert_eq!(pick_surface_format(&[srgb]).unwrap().format, vk::Format::R8G8B8A8_SRGB);
+        let any = vk::SurfaceFormatKHR { format: vk::Format::UNDEFINED, ..srgb };
+        assert_eq!(pick_surface_format(&[any]).unwrap().format, vk::Format::B8G8R8A8_UNORM);
+        assert!(pick_surface_format(&[]).is_none());
+    }
+}
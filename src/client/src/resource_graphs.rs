@@ -0,0 +1,294 @@
+// WASMA - Live resource graphs
+// Every window card charts CPU %, RAM MiB and VRAM MiB over the last minute.
+// The CPU sampler thread records one ResourceSample per window each
+// CPU_SAMPLE_INTERVAL; the GUI redraws the charts on a refresh subscription
+// whose period is [window] graph_refresh_ms in settings.conf (0 turns live
+// refresh off, leaving the "Update Resources" button). The resource cycle that
+// feeds them runs the backend phases as they fall due, on the cadence set by
+// [window] resource_cycle_ms; both pause while the manager window is minimized.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::renderer;
+use iced::advanced::widget::{Tree, Widget};
+use iced::{mouse, Border, Color, Element, Length, Rectangle, Size};
+use wsdg_xdg::{WsdgEnv, WsdgSettings, WsdgSettingsManager};
+
+use crate::cpu_history::CPU_SAMPLE_INTERVAL;
+
+/// Time span shown by each graph
+pub const GRAPH_SPAN: Duration = Duration::from_secs(60);
+pub const GRAPH_WIDTH: f32 = 180.0;
+pub const GRAPH_HEIGHT: f32 = 40.0;
+
+const BYTES_PER_MIB: f32 = (1024 * 1024) as f32;
+
+/// One sampler tick of a window; None where the value could not be measured
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceSample {
+    pub cpu_percent: Option<f32>,
+    pub ram_mib: Option<f32>,
+    pub vram_mib: Option<f32>,
+}
+
+impl ResourceSample {
+    /// From CPU utilization (0.0..=1.0) and byte counts
+    pub fn measured(cpu: Option<f32>, ram_bytes: Option<u64>, vram_bytes: Option<u64>) -> Self {
+        ResourceSample {
+            cpu_percent: cpu.map(|c| c * 100.0),
+            ram_mib: ram_bytes.map(|b| b as f32 / BYTES_PER_MIB),
+            vram_mib: vram_bytes.map(|b| b as f32 / BYTES_PER_MIB),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceMetric {
+    Cpu,
+    Ram,
+    Vram,
+}
+
+impl ResourceMetric {
+    pub const ALL: [ResourceMetric; 3] = [ResourceMetric::Cpu, ResourceMetric::Ram, ResourceMetric::Vram];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ResourceMetric::Cpu => "CPU",
+            ResourceMetric::Ram => "RAM",
+            ResourceMetric::Vram => "VRAM",
+        }
+    }
+
+    pub fn value(&self, sample: &ResourceSample) -> Option<f32> {
+        match self {
+            ResourceMetric::Cpu => sample.cpu_percent,
+            ResourceMetric::Ram => sample.ram_mib,
+            ResourceMetric::Vram => sample.vram_mib,
+        }
+    }
+
+    /// "42%" / "512 MiB"; "–" when unmeasured
+    pub fn format(&self, value: Option<f32>) -> String {
+        match (self, value) {
+            (_, None) => "–".to_string(),
+            (ResourceMetric::Cpu, Some(v)) => format!("{:.0}%", v),
+            (_, Some(v)) => format!("{:.0} MiB", v),
+        }
+    }
+}
+
+/// Samples of the last GRAPH_SPAN, oldest first
+#[derive(Debug, Clone)]
+pub struct ResourceHistory {
+    samples: VecDeque<ResourceSample>,
+    capacity: usize,
+}
+
+impl Default for ResourceHistory {
+    fn default() -> Self {
+        Self::new((GRAPH_SPAN.as_millis() / CPU_SAMPLE_INTERVAL.as_millis()) as usize)
+    }
+}
+
+impl ResourceHistory {
+    pub fn new(capacity: usize) -> Self {
+        ResourceHistory { samples: VecDeque::with_capacity(capacity), capacity: capacity.max(1) }
+    }
+
+    pub fn push(&mut self, sample: ResourceSample) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn series(&self, metric: ResourceMetric) -> Vec<Option<f32>> {
+        self.samples.iter().map(|s| metric.value(s)).collect()
+    }
+
+    pub fn latest(&self, metric: ResourceMetric) -> Option<f32> {
+        self.samples.back().and_then(|s| metric.value(s))
+    }
+}
+
+/// Graph refresh period from [window] graph_refresh_ms; None when turned off
+pub fn refresh_interval(settings: &WsdgSettings) -> Option<Duration> {
+    match settings.window.graph_refresh_ms {
+        0 => None,
+        ms => Some(Duration::from_millis(ms as u64)),
+    }
+}
+
+/// Background resource cycle period from [window] resource_cycle_ms; None when turned off
+pub fn cycle_interval(settings: &WsdgSettings) -> Option<Duration> {
+    match settings.window.resource_cycle_ms {
+        0 => None,
+        ms => Some(Duration::from_millis(ms as u64)),
+    }
+}
+
+/// (graph refresh, resource cycle) periods from the user's settings.conf
+pub fn intervals_from_wsdg() -> (Option<Duration>, Option<Duration>) {
+    let mut manager = WsdgSettingsManager::new(WsdgEnv::new());
+    let settings = match manager.load() {
+        Ok(()) => manager.settings().clone(),
+        Err(_) => WsdgSettings::default(),
+    };
+    (refresh_interval(&settings), cycle_interval(&settings))
+}
+
+/// Bar chart of one metric; the newest sample is at the right edge
+#[derive(Debug, Clone)]
+pub struct ResourceGraph {
+    series: Vec<Option<f32>>,
+    capacity: usize,
+    max: f32,
+    color: Color,
+    background: Color,
+}
+
+impl ResourceGraph {
+    /// `limit` scales RAM/VRAM to the assignment's allocation; CPU is always 0–100%
+    pub fn new(history: &ResourceHistory, metric: ResourceMetric, limit: Option<f32>, color: Color, background: Color) -> Self {
+        let series = history.series(metric);
+        let max = match metric {
+            ResourceMetric::Cpu => 100.0,
+            _ => limit
+                .filter(|l| *l > 0.0)
+                .unwrap_or_else(|| series.iter().flatten().copied().fold(0.0, f32::max)),
+        };
+        ResourceGraph { series, capacity: history.capacity(), max: max.max(1.0), color, background }
+    }
+
+    /// Bars of the samples inside `bounds`; unmeasured samples leave a gap
+    pub fn bars(&self, bounds: Rectangle) -> Vec<Rectangle> {
+        let slot = bounds.width / self.capacity as f32;
+        let offset = self.capacity.saturating_sub(self.series.len());
+        self.series
+            .iter()
+            .enumerate()
+            .filter_map(|(i, value)| {
+                let height = ((*value)? / self.max).clamp(0.0, 1.0) * bounds.height;
+                Some(Rectangle {
+                    x: bounds.x + (offset + i) as f32 * slot,
+                    y: bounds.y + bounds.height - height,
+                    width: (slot - 1.0).max(1.0),
+                    height,
+                })
+            })
+            .collect()
+    }
+}
+
+impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer> for ResourceGraph
+where
+    Renderer: renderer::Renderer,
+{
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Fixed(GRAPH_WIDTH), Length::Fixed(GRAPH_HEIGHT))
+    }
+
+    fn layout(&self, _tree: &mut Tree, _renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        layout::atomic(limits, GRAPH_WIDTH, GRAPH_HEIGHT)
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        renderer.fill_quad(
+            renderer::Quad { bounds, border: Border::with_radius(3.0), ..renderer::Quad::default() },
+            self.background,
+        );
+        for bar in self.bars(bounds) {
+            renderer.fill_quad(renderer::Quad { bounds: bar, ..renderer::Quad::default() }, self.color);
+        }
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<ResourceGraph> for Element<'a, Message, Theme, Renderer>
+where
+    Renderer: renderer::Renderer,
+{
+    fn from(graph: ResourceGraph) -> Self {
+        Element::new(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::window_handling::{WindowGeometry, WindowHandler};
+    use wbackend::ResourceMode;
+
+    #[test]
+    fn test_resource_graphs() {
+        let mut history = ResourceHistory::new(4);
+        for (cpu, ram) in [(10.0, 100), (20.0, 200), (50.0, 300), (100.0, 400), (25.0, 512)] {
+            history.push(ResourceSample { cpu_percent: Some(cpu), ram_mib: Some(ram as f32), vram_mib: None });
+        }
+        assert_eq!(history.len(), 4);
+        assert_eq!(history.series(ResourceMetric::Cpu), vec![Some(20.0), Some(50.0), Some(100.0), Some(25.0)]);
+        assert_eq!(history.latest(ResourceMetric::Ram), Some(512.0));
+        assert_eq!(ResourceMetric::Vram.format(history.latest(ResourceMetric::Vram)), "–");
+        assert_eq!(ResourceMetric::Cpu.format(Some(42.4)), "42%");
+        assert_eq!(ResourceSample::measured(Some(0.5), Some(64 << 20), None).ram_mib, Some(64.0));
+
+        // CPU bars scale to 100%, newest at the right edge
+        let bounds = Rectangle { x: 0.0, y: 0.0, width: 40.0, height: 20.0 };
+        let cpu = ResourceGraph::new(&history, ResourceMetric::Cpu, None, Color::WHITE, Color::BLACK);
+        let bars = cpu.bars(bounds);
+        assert_eq!(bars.len(), 4);
+        assert_eq!((bars[2].x, bars[2].y, bars[2].height), (20.0, 0.0, 20.0));
+        assert_eq!((bars[3].x, bars[3].height), (30.0, 5.0));
+
+        // RAM scales to the allocation; a short history starts mid-graph
+        let mut short = ResourceHistory::new(4);
+        short.push(ResourceSample { ram_mib: Some(256.0), ..ResourceSample::default() });
+        short.push(ResourceSample::default());
+        let ram = ResourceGraph::new(&short, ResourceMetric::Ram, Some(1024.0), Color::WHITE, Color::BLACK).bars(bounds);
+        assert_eq!(ram, vec![Rectangle { x: 20.0, y: 15.0, width: 9.0, height: 5.0 }]);
+
+        // Refresh period comes from settings.conf; 0 turns it off
+        let mut settings = WsdgSettings::default();
+        assert_eq!(refresh_interval(&settings), Some(Duration::from_secs(1)));
+        settings.window.graph_refresh_ms = 0;
+        assert_eq!(refresh_interval(&settings), None);
+        assert_eq!(cycle_interval(&settings), Some(Duration::from_secs(2)));
+        settings.window.resource_cycle_ms = 0;
+        assert_eq!(cycle_interval(&settings), None);
+
+        // The sampler records one sample per window with an assignment
+        let handler = Arc::new(WindowHandler::new(ResourceMode::Manual));
+        let geometry = WindowGeometry { x: 0, y: 0, width: 400, height: 300 };
+        let id = handler.create_window("Graphs".into(), "test.graphs".into(), geometry, None, ResourceMode::Manual).unwrap();
+        handler.sample_cpu();
+        handler.sample_cpu();
+        let recorded = handler.get_window_resource_history(id).unwrap();
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(recorded.capacity(), 60);
+        assert!(handler.get_window_resource_history(id + 1).is_err());
+    }
+}
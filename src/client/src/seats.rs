@@ -0,0 +1,554 @@
+// WASMA - Multi-seat support
+// One WASMA host can drive several logind seats. Seats, their active user and
+// their devices come from logind/udev runtime state:
+//   /run/systemd/seats/<seat>      ACTIVE=<session>, ACTIVE_UID=<uid>
+//   /run/systemd/sessions/<id>     USER=<name> (fallback when passwd has no entry)
+//   /sys/class/drm/card*, /sys/class/input/event*  (+ /run/udev/data/c<maj>:<min>, E:ID_SEAT=)
+// Devices without ID_SEAT belong to seat0. Every seat gets its own WindowHandler,
+// built from the active user's WSDG environment (HOME, settings.conf, env.path),
+// with GPU work pinned to the seat's first DRM card. When logind switches a seat
+// to another user, the previous user's session is detached (and locked) instead of
+// dropped, and re-attached when they come back and have unlocked it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use wbackend::ResourceMode;
+use wsdg_xdg::{EnvPathParser, WsdgEnv, WsdgEnvBuilder, WsdgSettingsManager};
+
+use crate::window_handling::{DetachMode, WindowHandler};
+
+/// The seat every device without an ID_SEAT tag belongs to
+pub const DEFAULT_SEAT: &str = "seat0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceKind {
+    Gpu,
+    Input,
+}
+
+/// A DRM card or evdev node assigned to a seat
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeatDevice {
+    pub kind: DeviceKind,
+    /// sysfs name (`card1`, `event4`)
+    pub name: String,
+    /// Device node (`/dev/dri/card1`, `/dev/input/event4`)
+    pub node: PathBuf,
+}
+
+/// The user whose session is active on a seat
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeatUser {
+    pub uid: u32,
+    pub name: String,
+    pub home: PathBuf,
+    /// XDG_RUNTIME_DIR (`/run/user/<uid>`)
+    pub runtime_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Seat {
+    pub id: String,
+    pub user: Option<SeatUser>,
+    pub devices: Vec<SeatDevice>,
+}
+
+impl Seat {
+    pub fn gpus(&self) -> impl Iterator<Item = &SeatDevice> {
+        self.devices.iter().filter(|d| d.kind == DeviceKind::Gpu)
+    }
+
+    pub fn inputs(&self) -> impl Iterator<Item = &SeatDevice> {
+        self.devices.iter().filter(|d| d.kind == DeviceKind::Input)
+    }
+
+    /// DRM card assignments of this seat are pinned to
+    pub fn primary_gpu(&self) -> Option<&SeatDevice> {
+        self.gpus().next()
+    }
+
+    /// WSDG environment of the seat's active user: their home-based XDG
+    /// directories, then the variables their env.path defines
+    pub fn env(&self) -> WsdgEnv {
+        let mut builder = WsdgEnvBuilder::new().var("XDG_SEAT", self.id.as_str());
+        if let Some(user) = &self.user {
+            let home = |sub: &str| user.home.join(sub).to_string_lossy().into_owned();
+            builder = builder
+                .home(user.home.to_string_lossy())
+                .user(user.name.as_str())
+                .var("LOGNAME", user.name.as_str())
+                .var("UID", user.uid.to_string())
+                .config(home(".config"))
+                .local(home(".local"))
+                .share(home(".local/share"))
+                .cache(home(".cache"))
+                .var("STATE", home(".local/state"))
+                .var("XDG_CONFIG_HOME", home(".config"))
+                .var("XDG_DATA_HOME", home(".local/share"))
+                .var("XDG_CACHE_HOME", home(".cache"))
+                .var("XDG_STATE_HOME", home(".local/state"))
+                .var("XDG_RUNTIME_DIR", user.runtime_dir.to_string_lossy());
+        }
+        let mut env = builder.build();
+
+        let env_path = match env.kiosk() {
+            Some(policy) => Some(policy.env_path()),
+            None => env.config_dir().ok().map(|dir| dir.join("wsdg/env.path")),
+        };
+        if let Some(config) = env_path.and_then(|path| EnvPathParser::new(path).load().ok()) {
+            env.set_from_config(&config.defined_vars());
+        }
+        env
+    }
+}
+
+impl fmt::Display for Seat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.user {
+            Some(user) => writeln!(f, "{}: {} (uid {}, {})", self.id, user.name, user.uid, user.home.display())?,
+            None => writeln!(f, "{}: no active session", self.id)?,
+        }
+        for device in &self.devices {
+            let kind = match device.kind {
+                DeviceKind::Gpu => "gpu",
+                DeviceKind::Input => "input",
+            };
+            writeln!(f, "  {:<6} {}", kind, device.node.display())?;
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// DISCOVERY
+// ============================================================================
+
+/// Reads seats from logind/udev state; roots are configurable for tests
+#[derive(Debug, Clone)]
+pub struct SeatDiscovery {
+    run_dir: PathBuf,
+    sys_dir: PathBuf,
+    passwd: PathBuf,
+}
+
+impl Default for SeatDiscovery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SeatDiscovery {
+    pub fn new() -> Self {
+        Self::with_roots("/run", "/sys", "/etc/passwd")
+    }
+
+    pub fn with_roots(run_dir: impl Into<PathBuf>, sys_dir: impl Into<PathBuf>, passwd: impl Into<PathBuf>) -> Self {
+        Self { run_dir: run_dir.into(), sys_dir: sys_dir.into(), passwd: passwd.into() }
+    }
+
+    /// Seats logind knows about (seat0 always), sorted by id
+    pub fn discover(&self) -> Vec<Seat> {
+        let mut seats: BTreeMap<String, Seat> = BTreeMap::new();
+        seats.insert(DEFAULT_SEAT.to_string(), Seat { id: DEFAULT_SEAT.to_string(), user: None, devices: Vec::new() });
+
+        if let Ok(entries) = fs::read_dir(self.run_dir.join("systemd/seats")) {
+            for entry in entries.flatten() {
+                let id = entry.file_name().to_string_lossy().into_owned();
+                let state = read_keyed(&entry.path());
+                let user = self.active_user(&state);
+                seats.insert(id.clone(), Seat { id, user, devices: Vec::new() });
+            }
+        }
+
+        for device in self.devices("drm", DeviceKind::Gpu, "card", "/dev/dri")
+            .into_iter()
+            .chain(self.devices("input", DeviceKind::Input, "event", "/dev/input"))
+        {
+            // Devices tagged for a seat logind does not (yet) know are left unassigned
+            if let Some(seat) = seats.get_mut(&device.0) {
+                seat.devices.push(device.1);
+            }
+        }
+
+        seats.into_values().collect()
+    }
+
+    fn active_user(&self, state: &HashMap<String, String>) -> Option<SeatUser> {
+        let uid: u32 = state.get("ACTIVE_UID")?.parse().ok()?;
+        let runtime_dir = self.run_dir.join("user").join(uid.to_string());
+        if let Some((name, home)) = self.passwd_entry(uid) {
+            return Some(SeatUser { uid, name, home, runtime_dir });
+        }
+
+        // No passwd entry (e.g. a directory-service user): the session still names them
+        let session = read_keyed(&self.run_dir.join("systemd/sessions").join(state.get("ACTIVE")?));
+        let name = session.get("USER")?.clone();
+        let home = PathBuf::from("/home").join(&name);
+        Some(SeatUser { uid, name, home, runtime_dir })
+    }
+
+    fn passwd_entry(&self, uid: u32) -> Option<(String, PathBuf)> {
+        let content = fs::read_to_string(&self.passwd).ok()?;
+        content.lines().find_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            (fields.len() >= 6 && fields[2].parse() == Ok(uid))
+                .then(|| (fields[0].to_string(), PathBuf::from(fields[5])))
+        })
+    }
+
+    /// (seat, device) for every `<prefix>N` entry of /sys/class/<class>
+    fn devices(&self, class: &str, kind: DeviceKind, prefix: &str, dev_dir: &str) -> Vec<(String, SeatDevice)> {
+        let Ok(entries) = fs::read_dir(self.sys_dir.join("class").join(class)) else {
+            return Vec::new();
+        };
+
+        let mut devices: Vec<(String, SeatDevice)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                // card0-DP-1 and friends are connectors, not devices
+                let index = name.strip_prefix(prefix)?;
+                if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                let seat = self.device_seat(&entry.path());
+                let node = Path::new(dev_dir).join(&name);
+                Some((seat, SeatDevice { kind, name, node }))
+            })
+            .collect();
+        devices.sort_by_key(|(_, device)| {
+            let index: u32 = device.name.trim_start_matches(prefix).parse().unwrap_or(u32::MAX);
+            index
+        });
+        devices
+    }
+
+    /// ID_SEAT from the device's udev database entry, seat0 when untagged
+    fn device_seat(&self, sys_path: &Path) -> String {
+        fs::read_to_string(sys_path.join("dev"))
+            .ok()
+            .and_then(|dev| fs::read_to_string(self.run_dir.join("udev/data").join(format!("c{}", dev.trim()))).ok())
+            .and_then(|db| {
+                db.lines()
+                    .find_map(|line| line.strip_prefix("E:ID_SEAT="))
+                    .map(|seat| seat.trim().to_string())
+            })
+            .filter(|seat| !seat.is_empty())
+            .unwrap_or_else(|| DEFAULT_SEAT.to_string())
+    }
+}
+
+/// `KEY=value` lines of a logind state file
+fn read_keyed(path: &Path) -> HashMap<String, String> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
+// ============================================================================
+// SEAT MANAGER
+// ============================================================================
+
+/// A seat and the WindowHandler driving it
+pub struct SeatInstance {
+    pub seat: Seat,
+    pub env: WsdgEnv,
+    pub handler: Arc<WindowHandler>,
+}
+
+impl SeatInstance {
+    pub fn new(seat: Seat, resource_mode: ResourceMode) -> Self {
+        let env = seat.env();
+        let handler = Arc::new(WindowHandler::with_env(resource_mode, &env));
+        let instance = Self { seat, env, handler };
+        instance.pin_devices();
+        instance
+    }
+
+    fn pin_devices(&self) {
+        self.handler.set_seat_gpu(self.seat.primary_gpu().map(|gpu| gpu.node.to_string_lossy().into_owned()));
+    }
+
+    fn user_name(&self) -> String {
+        self.seat.user.as_ref().map(|u| u.name.clone()).unwrap_or_default()
+    }
+
+    /// The seat user's WSDG settings (defaults when they have none)
+    pub fn settings(&self) -> WsdgSettingsManager {
+        let mut manager = WsdgSettingsManager::new(self.env.clone());
+        if let Err(e) = manager.load() {
+            eprintln!("⚠️  {}: settings could not be loaded: {}", self.seat.id, e);
+        }
+        manager
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeatChange {
+    Added(String),
+    Removed(String),
+    /// A different user became active: their detached session, or a fresh
+    /// WindowHandler, now has the seat
+    UserChanged { seat: String, user: Option<String> },
+    /// The previous user's session was taken off the seat and locked
+    Detached { seat: String, user: String },
+    /// A returning user's session is back on the display
+    Attached { seat: String, user: String },
+    /// A returning user's session is still locked; attach() once they unlock
+    AttachPending { seat: String, user: String },
+    /// GPUs or input devices were plugged or unplugged
+    DevicesChanged(String),
+}
+
+/// One WindowHandler per seat, kept in step with logind
+pub struct SeatManager {
+    resource_mode: ResourceMode,
+    detach_mode: DetachMode,
+    instances: BTreeMap<String, SeatInstance>,
+    // Sessions of users switched away from, by (seat, uid)
+    detached: BTreeMap<(String, u32), SeatInstance>,
+}
+
+impl SeatManager {
+    pub fn new(resource_mode: ResourceMode) -> Self {
+        Self { resource_mode, detach_mode: DetachMode::default(), instances: BTreeMap::new(), detached: BTreeMap::new() }
+    }
+
+    /// How sessions of users switched away from keep running
+    pub fn with_detach_mode(mut self, mode: DetachMode) -> Self {
+        self.detach_mode = mode;
+        self
+    }
+
+    /// Bring instances in line with `seats`: start new seats, drop vanished ones and
+    /// switch a seat's session when its active user changes
+    pub fn sync(&mut self, seats: Vec<Seat>) -> Vec<SeatChange> {
+        let mut changes = Vec::new();
+
+        let current: Vec<String> = seats.iter().map(|s| s.id.clone()).collect();
+        self.instances.retain(|id, _| {
+            let keep = current.contains(id);
+            if !keep {
+                changes.push(SeatChange::Removed(id.clone()));
+            }
+            keep
+        });
+        self.detached.retain(|(seat, _), _| current.contains(seat));
+
+        for seat in seats {
+            let id = seat.id.clone();
+            match self.instances.get_mut(&id) {
+                None => {
+                    self.instances.insert(id.clone(), SeatInstance::new(seat, self.resource_mode));
+                    changes.push(SeatChange::Added(id));
+                }
+                Some(instance) if instance.seat.user != seat.user => {
+                    let user = seat.user.as_ref().map(|u| u.name.clone());
+                    let returning = seat.user.as_ref().and_then(|u| self.detached.remove(&(id.clone(), u.uid)));
+                    let next = match returning {
+                        Some(mut returning) => {
+                            returning.seat = seat;
+                            returning.pin_devices();
+                            returning
+                        }
+                        None => SeatInstance::new(seat, self.resource_mode),
+                    };
+                    let previous = std::mem::replace(instance, next);
+
+                    if let Some(uid) = previous.seat.user.as_ref().map(|u| u.uid) {
+                        match previous.handler.detach_session(self.detach_mode) {
+                            Ok(()) => {
+                                changes.push(SeatChange::Detached { seat: id.clone(), user: previous.user_name() });
+                                self.detached.insert((id.clone(), uid), previous);
+                            }
+                            Err(e) => eprintln!("⚠️  {}: session of {} dropped: {}", id, previous.user_name(), e),
+                        }
+                    }
+                    changes.push(SeatChange::UserChanged { seat: id.clone(), user });
+
+                    if instance.handler.detached().is_some() {
+                        let user = instance.user_name();
+                        changes.push(match instance.handler.attach_session() {
+                            Ok(()) => SeatChange::Attached { seat: id, user },
+                            Err(_) => SeatChange::AttachPending { seat: id, user },
+                        });
+                    }
+                }
+                Some(instance) if instance.seat.devices != seat.devices => {
+                    instance.seat = seat;
+                    instance.pin_devices();
+                    changes.push(SeatChange::DevicesChanged(id));
+                }
+                Some(_) => {}
+            }
+        }
+
+        changes
+    }
+
+    pub fn get(&self, seat: &str) -> Option<&SeatInstance> {
+        self.instances.get(seat)
+    }
+
+    /// Attach the seat's session after its user unlocked it (see AttachPending)
+    pub fn attach(&self, seat: &str) -> Result<(), String> {
+        self.instances
+            .get(seat)
+            .ok_or_else(|| format!("No such seat: {}", seat))?
+            .handler
+            .attach_session()
+    }
+
+    /// Sessions waiting for their users to come back
+    pub fn detached_sessions(&self) -> impl Iterator<Item = &SeatInstance> {
+        self.detached.values()
+    }
+
+    pub fn instances(&self) -> impl Iterator<Item = &SeatInstance> {
+        self.instances.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window_handling::{WindowEvent, WindowGeometry};
+
+    fn fake_host(root: &Path) -> SeatDiscovery {
+        let run = root.join("run");
+        let sys = root.join("sys");
+        fs::create_dir_all(run.join("systemd/seats")).unwrap();
+        fs::create_dir_all(run.join("systemd/sessions")).unwrap();
+        fs::create_dir_all(run.join("udev/data")).unwrap();
+        fs::write(run.join("systemd/seats/seat0"), "IS_SEAT0=1\nACTIVE=c1\nACTIVE_UID=1000\n").unwrap();
+        fs::write(run.join("systemd/seats/seat1"), "IS_SEAT0=0\nACTIVE=c2\nACTIVE_UID=1001\n").unwrap();
+        fs::write(run.join("systemd/sessions/c2"), "UID=1001\nUSER=bob\nSEAT=seat1\n").unwrap();
+
+        for (class, name, dev, seat) in [
+            ("drm", "card0", "226:0", None),
+            ("drm", "card0-HDMI-A-1", "", None),
+            ("drm", "card1", "226:1", Some("seat1")),
+            ("input", "event2", "13:66", None),
+            ("input", "event10", "13:74", Some("seat1")),
+            ("input", "event11", "13:75", Some("seat9")),
+        ] {
+            let path = sys.join("class").join(class).join(name);
+            fs::create_dir_all(&path).unwrap();
+            if !dev.is_empty() {
+                fs::write(path.join("dev"), format!("{}\n", dev)).unwrap();
+                let tag = seat.map(|s| format!("E:ID_SEAT={}\n", s)).unwrap_or_default();
+                fs::write(run.join("udev/data").join(format!("c{}", dev)), format!("E:ID_FOR_SEAT=x\n{}", tag)).unwrap();
+            }
+        }
+
+        let passwd = root.join("passwd");
+        fs::write(&passwd, format!("alice:x:1000:1000:Alice:{}:/bin/bash\n", root.join("alice").display())).unwrap();
+        SeatDiscovery::with_roots(run, sys, passwd)
+    }
+
+    #[test]
+    fn test_seat_discovery_and_manager() {
+        let dir = tempfile::tempdir().unwrap();
+        let discovery = fake_host(dir.path());
+        let seats = discovery.discover();
+
+        assert_eq!(seats.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), ["seat0", "seat1"]);
+        let (seat0, seat1) = (&seats[0], &seats[1]);
+        assert_eq!(seat0.user.as_ref().unwrap().name, "alice");
+        let bob = SeatUser {
+            uid: 1001,
+            name: "bob".into(),
+            home: PathBuf::from("/home/bob"),
+            runtime_dir: dir.path().join("run/user/1001"),
+        };
+        assert_eq!(seat1.user.as_ref(), Some(&bob));
+        assert_eq!(seat0.primary_gpu().unwrap().node, Path::new("/dev/dri/card0"));
+        assert_eq!(seat1.primary_gpu().unwrap().node, Path::new("/dev/dri/card1"));
+        assert_eq!(seat0.gpus().count(), 1);
+        assert_eq!(seat0.inputs().map(|d| d.name.as_str()).collect::<Vec<_>>(), ["event2"]);
+        assert_eq!(seat1.inputs().map(|d| d.name.as_str()).collect::<Vec<_>>(), ["event10"]);
+
+        // Per-user settings and env.path translation
+        let alice = dir.path().join("alice");
+        fs::create_dir_all(alice.join(".config/wsdg")).unwrap();
+        fs::write(alice.join(".config/wsdg/env.path"), "use_std export:$EDITOR = vim\n").unwrap();
+        let env = seat0.env();
+        assert_eq!(env.get("EDITOR").map(String::as_str), Some("vim"));
+        assert_eq!(env.home_dir().unwrap(), alice);
+        assert_eq!(env.config_dir().unwrap(), alice.join(".config"));
+        assert_eq!(env.get("XDG_SEAT").map(String::as_str), Some("seat0"));
+        assert_eq!(env.uid().unwrap(), 1000);
+        assert_eq!(seat1.env().user().unwrap(), "bob");
+
+        let mut manager = SeatManager::new(ResourceMode::Auto);
+        let changes = manager.sync(seats.clone());
+        assert_eq!(changes, [SeatChange::Added("seat0".into()), SeatChange::Added("seat1".into())]);
+        let first = manager.get("seat0").unwrap();
+        assert_eq!(first.settings().settings_path(), alice.join(".config/wsdg/settings.conf"));
+        assert_eq!(first.handler.seat_gpu().as_deref(), Some("/dev/dri/card0"));
+
+        // Seats are independent: a window on seat1 does not show up on seat0
+        let seat1_handler = manager.get("seat1").unwrap().handler.clone();
+        let geometry = WindowGeometry { x: 0, y: 0, width: 640, height: 480 };
+        seat1_handler.create_window("Term".into(), "test.seats".into(), geometry, None, ResourceMode::Auto).unwrap();
+        assert_eq!(seat1_handler.list_windows().len(), 1);
+        assert!(first.handler.list_windows().is_empty());
+        let backend = seat1_handler.snapshot().backend;
+        assert_eq!(backend.assignments[0].gpu_device.as_deref(), Some("/dev/dri/card1"));
+        assert_eq!(backend.assignments[0].gpu_id.as_deref(), Some("drm:card1"));
+
+        // seat0 unplugs its keyboard; seat1 switches to carol, bob's session is detached and locked
+        let events = seat1_handler.subscribe();
+        let carol = SeatUser { uid: 1002, name: "carol".into(), home: dir.path().join("carol"), runtime_dir: dir.path().join("run/user/1002") };
+        let mut next = seats.clone();
+        next[1].user = Some(carol);
+        next[0].devices.retain(|d| d.kind == DeviceKind::Gpu);
+        assert_eq!(manager.sync(next.clone()), [
+            SeatChange::DevicesChanged("seat0".into()),
+            SeatChange::Detached { seat: "seat1".into(), user: "bob".into() },
+            SeatChange::UserChanged { seat: "seat1".into(), user: Some("carol".into()) },
+        ]);
+        assert!(manager.get("seat1").unwrap().handler.list_windows().is_empty());
+        assert_eq!(events.try_recv().unwrap(), WindowEvent::SessionDetached { mode: DetachMode::Headless });
+        assert_eq!(seat1_handler.detached(), Some(DetachMode::Headless));
+        assert_eq!(seat1_handler.list_windows().len(), 1);
+        let bob_lock = seat1_handler.session_lock().marker_path().to_path_buf();
+        assert_eq!(bob_lock, bob.runtime_dir.join("wasma/session.lock"));
+        assert!(bob_lock.exists());
+
+        // Bob comes back: his windows return, but stay off the display until he unlocks
+        assert_eq!(manager.sync(seats.clone()), [
+            SeatChange::DevicesChanged("seat0".into()),
+            SeatChange::Detached { seat: "seat1".into(), user: "carol".into() },
+            SeatChange::UserChanged { seat: "seat1".into(), user: Some("bob".into()) },
+            SeatChange::AttachPending { seat: "seat1".into(), user: "bob".into() },
+        ]);
+        assert!(Arc::ptr_eq(&manager.get("seat1").unwrap().handler, &seat1_handler));
+        assert!(manager.attach("seat1").is_err());
+        fs::remove_file(&bob_lock).unwrap();
+        manager.attach("seat1").unwrap();
+        assert_eq!(seat1_handler.detached(), None);
+        assert_eq!(events.try_recv().unwrap(), WindowEvent::SessionAttached);
+        assert_eq!(manager.detached_sessions().map(|s| s.user_name()).collect::<Vec<_>>(), ["carol"]);
+
+        assert_eq!(manager.sync(seats[..1].to_vec()), [SeatChange::Removed("seat1".into())]);
+        assert_eq!(manager.len(), 1);
+        assert_eq!(manager.detached_sessions().count(), 0);
+    }
+}
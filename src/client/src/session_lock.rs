@@ -0,0 +1,773 @@
+// WASMA - Session Lock & Screensaver
+// `wasma lock`: blanks every viewport, suspends stream rendering while the
+// assignments keep their leases, shows a themed lock surface and resumes on
+// PAM-authenticated unlock. logind Lock/Unlock signals of our own session are
+// honored too. The surface is an ext-session-lock-v1 lock where the compositor
+// offers it, otherwise (X11) a fullscreen window holding the keyboard/pointer
+// grab. With neither the lock fails closed: the marker stays and rendering
+// stays suspended, but no window pretends to be a lock.
+
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command as ProcessCommand, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+use iced::{
+    Application, Command, Element, Settings, Subscription, Theme,
+    widget::{column, container, text, text_input},
+    executor, window, Alignment, Background, Color, Length,
+};
+use thiserror::Error;
+use wsdg_xdg::{WsdgEnv, WsdgSettings, WsdgSettingsManager};
+
+use crate::accessibility;
+
+/// Set while the session is locked – render paths drop frames, streams keep reading
+pub static SESSION_LOCKED: AtomicBool = AtomicBool::new(false);
+
+static MARKER_WATCH: Once = Once::new();
+
+#[derive(Error, Debug)]
+pub enum LockError {
+    #[error("Session is already locked")]
+    AlreadyLocked,
+
+    #[error("Session is not locked")]
+    NotLocked,
+
+    #[error("Authentication failed: {0}")]
+    AuthFailed(String),
+
+    #[error("PAM unavailable: {0}")]
+    PamUnavailable(String),
+
+    #[error("No secure lock surface: {0}")]
+    NoLockSurface(String),
+
+    #[error("User of uid {0} is not in the password database")]
+    UnknownUser(u32),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+// ============================================================================
+// SESSION LOCK
+// ============================================================================
+
+/// Session lock state shared by `wasma lock` and the render engines
+pub struct SessionLock {
+    // None when the uid has no passwd entry: such a session is never locked
+    user: Option<String>,
+    pam_service: String,
+    marker_path: PathBuf,
+}
+
+impl SessionLock {
+    pub fn new() -> Self {
+        // Who has to authenticate comes from the password database, not $USER
+        let user = passwd_name(unsafe { libc::geteuid() });
+
+        // Distribution-provided /etc/pam.d/wasma wins, "login" otherwise
+        let pam_service = if PathBuf::from("/etc/pam.d/wasma").exists() {
+            "wasma".to_string()
+        } else {
+            "login".to_string()
+        };
+
+        Self {
+            user,
+            pam_service,
+            marker_path: lock_marker_path(),
+        }
+    }
+
+    /// Lock state of another user's session (e.g. a seat's user): their name,
+    /// and the marker in their runtime directory
+    pub fn for_env(env: &WsdgEnv) -> Self {
+        let mut lock = Self::new();
+        if let Ok(user) = env.user() {
+            lock.user = Some(user);
+        }
+        if let Some(runtime) = env.get("RUNTIME").or_else(|| env.get("XDG_RUNTIME_DIR")) {
+            lock.marker_path = PathBuf::from(runtime).join("wasma").join("session.lock");
+        }
+        lock
+    }
+
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    /// The user PAM authenticates; locking without one would be unlockable by nobody
+    fn require_user(&self) -> Result<&str, LockError> {
+        self.user.as_deref().ok_or_else(|| LockError::UnknownUser(unsafe { libc::geteuid() }))
+    }
+
+    pub fn marker_path(&self) -> &Path {
+        &self.marker_path
+    }
+
+    pub fn is_locked(&self) -> bool {
+        SESSION_LOCKED.load(Ordering::SeqCst) || self.marker_path.exists()
+    }
+
+    /// Engage the lock: blank viewports, suspend rendering, tell logind
+    pub fn lock(&self) -> Result<(), LockError> {
+        if self.is_locked() {
+            return Err(LockError::AlreadyLocked);
+        }
+        self.require_user()?;
+
+        if let Some(parent) = self.marker_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.marker_path, std::process::id().to_string())?;
+        SESSION_LOCKED.store(true, Ordering::SeqCst);
+        set_locked_hint(true);
+
+        tracing::info!("session locked, rendering suspended, leases kept");
+        Ok(())
+    }
+
+    /// Lock a session that is being detached (user switch): marker only, the
+    /// seat keeps rendering for whoever is attached next. Already locked is fine.
+    pub fn lock_detached(&self) -> Result<(), LockError> {
+        if self.marker_path.exists() {
+            return Ok(());
+        }
+        self.require_user()?;
+        if let Some(parent) = self.marker_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.marker_path, std::process::id().to_string())?;
+        tracing::info!(user = %self.require_user()?, "session locked while detached");
+        Ok(())
+    }
+
+    /// Authenticate through PAM and release the lock
+    pub fn unlock(&self, password: &str) -> Result<(), LockError> {
+        if !self.is_locked() {
+            return Err(LockError::NotLocked);
+        }
+
+        pam_authenticate(&self.pam_service, self.require_user()?, password)?;
+        self.release();
+        Ok(())
+    }
+
+    /// Release without authentication (logind Unlock signal)
+    fn release(&self) {
+        fs::remove_file(&self.marker_path).ok();
+        SESSION_LOCKED.store(false, Ordering::SeqCst);
+        set_locked_hint(false);
+        tracing::info!("session unlocked, rendering resumed");
+    }
+}
+
+impl Default for SessionLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Login name of `uid` from the password database
+fn passwd_name(uid: libc::uid_t) -> Option<String> {
+    let mut buf = vec![0 as c_char; 4096];
+    let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    let status = unsafe { libc::getpwuid_r(uid, &mut entry, buf.as_mut_ptr(), buf.len(), &mut result) };
+    if status != 0 || result.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(entry.pw_name) }.to_str().ok().map(str::to_string)
+}
+
+/// Runtime marker shared between the lock process and render engines
+pub fn lock_marker_path() -> PathBuf {
+    wbackend::rundir::runtime_dir().join("session.lock")
+}
+
+/// Mirror the runtime marker into SESSION_LOCKED (once per process)
+pub fn watch_lock_marker() {
+    MARKER_WATCH.call_once(|| {
+        let path = lock_marker_path();
+        std::thread::spawn(move || loop {
+            SESSION_LOCKED.store(path.exists(), Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(250));
+        });
+    });
+}
+
+/// Follow logind Lock/Unlock signals (`loginctl lock-session` / `unlock-session`)
+/// of the session this process runs in; other sessions' signals are ignored
+pub fn watch_loginctl<F>(on_signal: F) -> Result<(), LockError>
+where
+    F: Fn(bool) + Send + 'static,
+{
+    let session = own_session_path()
+        .ok_or_else(|| LockError::IoError(std::io::Error::other("logind session of this process is unknown")))?;
+
+    // Matching on the well-known sender lets the bus drop signals forged by other peers
+    let rule = format!(
+        "type='signal',sender='org.freedesktop.login1',interface='org.freedesktop.login1.Session',path='{}'",
+        session
+    );
+    let mut child = ProcessCommand::new("busctl")
+        .args(["--system", "monitor", "--match", &rule])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let stdout = child.stdout.take()
+        .ok_or_else(|| LockError::IoError(std::io::Error::other("busctl produced no stdout")))?;
+
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            match parse_logind_member(&line, &session) {
+                Some(locked) => on_signal(locked),
+                None => continue,
+            }
+        }
+        child.wait().ok();
+    });
+
+    Ok(())
+}
+
+/// busctl monitor line of `session` → Some(true) for Lock, Some(false) for Unlock
+pub fn parse_logind_member(line: &str, session: &str) -> Option<bool> {
+    let mut tokens = line.split_whitespace();
+    if !tokens.clone().any(|token| token.strip_prefix("Path=") == Some(session)) {
+        return None;
+    }
+    let member = tokens.find_map(|token| token.strip_prefix("Member="))?;
+
+    match member {
+        "Lock" => Some(true),
+        "Unlock" => Some(false),
+        _ => None,
+    }
+}
+
+/// logind object path of the session this process belongs to:
+/// $XDG_SESSION_ID, else Manager.GetSessionByPID
+pub fn own_session_path() -> Option<String> {
+    if let Some(id) = std::env::var("XDG_SESSION_ID").ok().filter(|id| !id.is_empty()) {
+        return Some(format!("/org/freedesktop/login1/session/{}", bus_path_label(&id)));
+    }
+
+    let output = ProcessCommand::new("busctl")
+        .args([
+            "call",
+            "--system",
+            "org.freedesktop.login1",
+            "/org/freedesktop/login1",
+            "org.freedesktop.login1.Manager",
+            "GetSessionByPID",
+            "u",
+            &std::process::id().to_string(),
+        ])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    // Reply: o "/org/freedesktop/login1/session/_32"
+    let reply = String::from_utf8_lossy(&output.stdout);
+    let path = reply.trim().strip_prefix("o ")?.trim_matches('"');
+    path.starts_with("/org/freedesktop/login1/session/").then(|| path.to_string())
+}
+
+/// sd-bus object path label: alphanumerics kept (not a leading digit), anything else _xx
+pub fn bus_path_label(id: &str) -> String {
+    if id.is_empty() {
+        return "_".to_string();
+    }
+    id.bytes()
+        .enumerate()
+        .map(|(i, b)| {
+            if b.is_ascii_alphabetic() || (b.is_ascii_digit() && i > 0) {
+                (b as char).to_string()
+            } else {
+                format!("_{:02x}", b)
+            }
+        })
+        .collect()
+}
+
+/// LockedHint on the current logind session (best effort)
+fn set_locked_hint(locked: bool) {
+    ProcessCommand::new("busctl")
+        .args([
+            "call",
+            "--system",
+            "org.freedesktop.login1",
+            "/org/freedesktop/login1/session/auto",
+            "org.freedesktop.login1.Session",
+            "SetLockedHint",
+            "b",
+            if locked { "true" } else { "false" },
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .ok();
+}
+
+// ============================================================================
+// PAM (loaded at runtime, no link-time dependency on libpam)
+// ============================================================================
+
+const PAM_SUCCESS: c_int = 0;
+const PAM_BUF_ERR: c_int = 5;
+const PAM_PROMPT_ECHO_OFF: c_int = 1;
+const PAM_PROMPT_ECHO_ON: c_int = 2;
+
+#[repr(C)]
+#[allow(dead_code)]
+struct PamMessage {
+    msg_style: c_int,
+    msg: *const c_char,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct PamResponse {
+    resp: *mut c_char,
+    resp_retcode: c_int,
+}
+
+#[repr(C)]
+struct PamConv {
+    conv: extern "C" fn(c_int, *mut *const PamMessage, *mut *mut PamResponse, *mut c_void) -> c_int,
+    appdata_ptr: *mut c_void,
+}
+
+type PamStartFn = unsafe extern "C" fn(*const c_char, *const c_char, *const PamConv, *mut *mut c_void) -> c_int;
+type PamHandleFn = unsafe extern "C" fn(*mut c_void, c_int) -> c_int;
+
+/// Answers every prompt with the password passed in appdata_ptr
+extern "C" fn pam_conversation(
+    num_msg: c_int,
+    msg: *mut *const PamMessage,
+    resp: *mut *mut PamResponse,
+    appdata_ptr: *mut c_void,
+) -> c_int {
+    unsafe {
+        let replies = libc::calloc(num_msg as usize, std::mem::size_of::<PamResponse>()) as *mut PamResponse;
+        if replies.is_null() {
+            return PAM_BUF_ERR;
+        }
+
+        let password = appdata_ptr as *const c_char;
+        for i in 0..num_msg as usize {
+            let message = &**msg.add(i);
+            if message.msg_style == PAM_PROMPT_ECHO_OFF || message.msg_style == PAM_PROMPT_ECHO_ON {
+                (*replies.add(i)).resp = libc::strdup(password);
+            }
+        }
+
+        *resp = replies;
+    }
+    PAM_SUCCESS
+}
+
+fn pam_authenticate(service: &str, user: &str, password: &str) -> Result<(), LockError> {
+    let service_c = CString::new(service).map_err(|e| LockError::AuthFailed(e.to_string()))?;
+    let user_c = CString::new(user).map_err(|e| LockError::AuthFailed(e.to_string()))?;
+    let password_c = CString::new(password).map_err(|e| LockError::AuthFailed(e.to_string()))?;
+
+    unsafe {
+        let lib = libc::dlopen(c"libpam.so.0".as_ptr(), libc::RTLD_NOW);
+        if lib.is_null() {
+            return Err(LockError::PamUnavailable("libpam.so.0 not found".to_string()));
+        }
+
+        let start = libc::dlsym(lib, c"pam_start".as_ptr());
+        let authenticate = libc::dlsym(lib, c"pam_authenticate".as_ptr());
+        let acct_mgmt = libc::dlsym(lib, c"pam_acct_mgmt".as_ptr());
+        let end = libc::dlsym(lib, c"pam_end".as_ptr());
+
+        if start.is_null() || authenticate.is_null() || acct_mgmt.is_null() || end.is_null() {
+            libc::dlclose(lib);
+            return Err(LockError::PamUnavailable("missing PAM symbols".to_string()));
+        }
+
+        let pam_start: PamStartFn = std::mem::transmute(start);
+        let pam_auth: PamHandleFn = std::mem::transmute(authenticate);
+        let pam_acct: PamHandleFn = std::mem::transmute(acct_mgmt);
+        let pam_end: PamHandleFn = std::mem::transmute(end);
+
+        let conv = PamConv {
+            conv: pam_conversation,
+            appdata_ptr: password_c.as_ptr() as *mut c_void,
+        };
+
+        let mut handle: *mut c_void = std::ptr::null_mut();
+        let mut status = pam_start(service_c.as_ptr(), user_c.as_ptr(), &conv, &mut handle);
+        if status == PAM_SUCCESS {
+            status = pam_auth(handle, 0);
+        }
+        if status == PAM_SUCCESS {
+            status = pam_acct(handle, 0);
+        }
+        if !handle.is_null() {
+            pam_end(handle, status);
+        }
+        libc::dlclose(lib);
+
+        if status == PAM_SUCCESS {
+            Ok(())
+        } else {
+            Err(LockError::AuthFailed(format!("PAM status {}", status)))
+        }
+    }
+}
+
+// ============================================================================
+// LOCK SURFACE (ICED)
+// ============================================================================
+
+/// Lock surface colors taken from WsdgSettings
+#[derive(Debug, Clone, Copy)]
+pub struct LockTheme {
+    pub background: Color,
+    pub foreground: Color,
+    pub accent: Color,
+}
+
+impl Default for LockTheme {
+    fn default() -> Self {
+        Self {
+            background: Color::from_rgb(0.08, 0.08, 0.1),
+            foreground: Color::from_rgb(0.9, 0.9, 0.9),
+            accent: Color::from_rgb(0.2, 0.6, 1.0),
+        }
+    }
+}
+
+impl LockTheme {
+    /// Load the user's WSDG theme, falling back to the dark default
+    pub fn from_wsdg() -> Self {
+        let mut manager = WsdgSettingsManager::new(WsdgEnv::new());
+        if manager.load().is_err() {
+            return Self::default();
+        }
+        Self::from_settings(manager.settings())
+    }
+
+    pub fn from_settings(settings: &WsdgSettings) -> Self {
+        let theme = &settings.theme;
+        Self {
+            background: accessibility::color(theme.background_color),
+            foreground: accessibility::color(theme.foreground_color),
+            accent: accessibility::color(theme.accent_color),
+        }
+    }
+}
+
+// ============================================================================
+// PROMPT
+// ============================================================================
+
+/// What a lock surface shows
+#[derive(Debug, Clone, Default)]
+pub struct PromptState {
+    pub password: String,
+    pub error: Option<String>,
+    pub unlocked: bool,
+}
+
+/// Password typed on a lock surface, shared between the surface and an input grab
+#[derive(Debug, Default)]
+pub struct LockPrompt {
+    state: Mutex<PromptState>,
+}
+
+impl LockPrompt {
+    pub fn push(&self, c: char) {
+        self.state.lock().unwrap().password.push(c);
+    }
+
+    pub fn backspace(&self) {
+        self.state.lock().unwrap().password.pop();
+    }
+
+    pub fn clear(&self) {
+        self.state.lock().unwrap().password.clear();
+    }
+
+    pub fn set_password(&self, password: String) {
+        self.state.lock().unwrap().password = password;
+    }
+
+    pub fn snapshot(&self) -> PromptState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// PAM-check the typed password; true once the session is unlocked
+    pub fn submit(&self, lock: &SessionLock) -> bool {
+        let password = std::mem::take(&mut self.state.lock().unwrap().password);
+        let result = lock.unlock(&password);
+        let mut state = self.state.lock().unwrap();
+        match result {
+            Ok(_) | Err(LockError::NotLocked) => {
+                state.unlocked = true;
+                state.error = None;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "unlock attempt failed");
+                state.error = Some(e.to_string());
+            }
+        }
+        state.unlocked
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum LockMessage {
+    PasswordChanged(String),
+    Submit,
+    /// Native id of the lock window, for the input grab
+    NativeId(u64),
+    Tick,
+}
+
+/// Shared with the X11 lock window: typed password, and why the grab failed
+#[derive(Debug, Default, Clone)]
+pub struct LockWindowFlags {
+    pub prompt: Arc<LockPrompt>,
+    pub grab_error: Arc<Mutex<Option<String>>>,
+}
+
+/// Fullscreen X11 lock window, used when there is no ext-session-lock-v1.
+/// It closes itself at once when the input grab fails
+pub struct LockScreen {
+    lock: SessionLock,
+    theme: LockTheme,
+    prompt: Arc<LockPrompt>,
+    grab_error: Arc<Mutex<Option<String>>>,
+}
+
+impl Application for LockScreen {
+    type Executor = executor::Default;
+    type Message = LockMessage;
+    type Theme = Theme;
+    type Flags = LockWindowFlags;
+
+    fn new(flags: Self::Flags) -> (Self, Command<LockMessage>) {
+        (
+            LockScreen {
+                lock: SessionLock::new(),
+                theme: LockTheme::from_wsdg(),
+                prompt: flags.prompt,
+                grab_error: flags.grab_error,
+            },
+            Command::batch([
+                window::change_mode(window::Id::MAIN, window::Mode::Fullscreen),
+                window::gain_focus(window::Id::MAIN),
+                window::fetch_id(window::Id::MAIN, LockMessage::NativeId),
+            ]),
+        )
+    }
+
+    fn title(&self) -> String {
+        String::from("WASMA - Session Locked")
+    }
+
+    fn update(&mut self, message: LockMessage) -> Command<LockMessage> {
+        match message {
+            LockMessage::PasswordChanged(password) => {
+                self.prompt.set_password(password);
+                Command::none()
+            }
+            LockMessage::Submit => {
+                if self.prompt.submit(&self.lock) {
+                    window::close(window::Id::MAIN)
+                } else {
+                    Command::none()
+                }
+            }
+            LockMessage::NativeId(id) => {
+                // Without the grab other clients still get input: no lock at all
+                #[cfg(feature = "x11")]
+                let grabbed = crate::lock_surface::grab_x11_input(id as u32, self.prompt.clone());
+                #[cfg(not(feature = "x11"))]
+                let grabbed: Result<(), String> = Err(format!("window {} cannot be grabbed without X11 support", id));
+
+                match grabbed {
+                    Ok(()) => Command::none(),
+                    Err(e) => {
+                        *self.grab_error.lock().unwrap() = Some(e);
+                        window::close(window::Id::MAIN)
+                    }
+                }
+            }
+            LockMessage::Tick => {
+                // Unlocked through the grab or by logind
+                if self.prompt.snapshot().unlocked || !self.lock.is_locked() {
+                    window::close(window::Id::MAIN)
+                } else {
+                    Command::none()
+                }
+            }
+        }
+    }
+
+    fn subscription(&self) -> Subscription<LockMessage> {
+        iced::time::every(Duration::from_millis(200)).map(|_| LockMessage::Tick)
+    }
+
+    fn view(&self) -> Element<'_, LockMessage> {
+        let prompt = self.prompt.snapshot();
+        let mut content = column![
+            text("🔒").size(64),
+            text(format!("Locked – {}", self.lock.user().unwrap_or_default()))
+                .size(24)
+                .style(self.theme.foreground),
+            text_input("Password", &prompt.password)
+                .secure(true)
+                .on_input(LockMessage::PasswordChanged)
+                .on_submit(LockMessage::Submit)
+                .width(320),
+        ]
+        .spacing(16)
+        .align_items(Alignment::Center);
+
+        if let Some(error) = &prompt.error {
+            content = content.push(text(error).size(14).style(self.theme.accent));
+        }
+
+        let background = self.theme.background;
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .center_y()
+            .style(move |_theme: &Theme| container::Appearance {
+                background: Some(Background::Color(background)),
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn theme(&self) -> Theme {
+        Theme::Dark
+    }
+}
+
+/// Lock the session and block on the lock surface until unlocked. Without a
+/// surface that really holds the input (ext-session-lock-v1, or an X11 grab)
+/// this fails with NoLockSurface and the session stays locked, suspended
+pub fn launch_lock_screen() -> Result<(), LockError> {
+    let lock = SessionLock::new();
+    lock.lock()?;
+
+    // logind unlock-session of our own session releases the lock; the surface notices
+    if let Err(e) = watch_loginctl(|locked| {
+        if !locked {
+            SessionLock::new().release();
+        }
+    }) {
+        tracing::warn!(error = %e, "logind unlock requests not followed");
+    }
+
+    let prompt = Arc::new(LockPrompt::default());
+
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        // A Wayland compositor gives other clients no grab to take: ext-session-lock or nothing
+        #[cfg(feature = "wayland")]
+        let locked = crate::lock_surface::run_session_lock(&lock, LockTheme::from_wsdg(), prompt);
+        #[cfg(not(feature = "wayland"))]
+        let locked: Result<(), String> = Err("built without Wayland support".to_string());
+        return locked.map_err(|e| LockError::NoLockSurface(format!("ext-session-lock unavailable: {}", e)));
+    }
+    if std::env::var_os("DISPLAY").is_none() {
+        return Err(LockError::NoLockSurface("no Wayland or X11 display".to_string()));
+    }
+
+    let flags = LockWindowFlags { prompt, grab_error: Arc::new(Mutex::new(None)) };
+    let grab_error = flags.grab_error.clone();
+    let result = LockScreen::run(Settings {
+        window: window::Settings {
+            decorations: false,
+            resizable: false,
+            level: window::Level::AlwaysOnTop,
+            ..Default::default()
+        },
+        flags,
+        fonts: vec![],
+        antialiasing: true,
+        default_font: Default::default(),
+        default_text_size: iced::Pixels(16.0),
+        id: None,
+    });
+
+    if let Some(e) = grab_error.lock().unwrap().take() {
+        return Err(LockError::NoLockSurface(format!("lock window could not grab input: {}", e)));
+    }
+    if let Err(e) = result {
+        return Err(LockError::NoLockSurface(format!("lock window failed: {}", e)));
+    }
+
+    // Closing the surface without authenticating keeps the marker in place
+    if lock.is_locked() {
+        tracing::warn!("lock surface closed while locked, session stays locked");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_logind_member() {
+        let ours = "/org/freedesktop/login1/session/_32";
+        let lock = "‣ Type=signal  Endian=l  Flags=1  Version=1 Cookie=42  Sender=:1.3  Path=/org/freedesktop/login1/session/_32  Interface=org.freedesktop.login1.Session  Member=Lock";
+        assert_eq!(parse_logind_member(lock, ours), Some(true));
+        let unlock = "Path=/org/freedesktop/login1/session/_32  Interface=org.freedesktop.login1.Session  Member=Unlock";
+        assert_eq!(parse_logind_member(unlock, ours), Some(false));
+        // Another session's Unlock must not release ours
+        let other = "Path=/org/freedesktop/login1/session/_37  Interface=org.freedesktop.login1.Session  Member=Unlock";
+        assert_eq!(parse_logind_member(other, ours), None);
+        assert_eq!(parse_logind_member("Path=/org/freedesktop/login1/session/_32  Member=PropertiesChanged", ours), None);
+        assert_eq!(parse_logind_member("unrelated line", ours), None);
+
+        assert_eq!(bus_path_label("2"), "_32");
+        assert_eq!(bus_path_label("c12"), "c12");
+        assert_eq!(bus_path_label("a-b"), "a_2db");
+    }
+
+    #[test]
+    fn test_lock_needs_passwd_user() {
+        assert_eq!(passwd_name(0).as_deref(), Some("root"));
+        assert_eq!(passwd_name(u32::MAX - 7), None);
+
+        // A uid without a passwd entry is refused instead of authenticating someone else
+        let dir = tempfile::tempdir().unwrap();
+        let lock = SessionLock {
+            user: None,
+            pam_service: "login".to_string(),
+            marker_path: dir.path().join("session.lock"),
+        };
+        assert!(matches!(lock.lock(), Err(LockError::UnknownUser(_))));
+        assert!(matches!(lock.lock_detached(), Err(LockError::UnknownUser(_))));
+        assert!(!lock.marker_path().exists());
+    }
+
+    #[test]
+    fn test_lock_theme_from_settings() {
+        let mut settings = WsdgSettings::default();
+        settings.theme.accent_color = "rgb(53, 132, 228)".parse().unwrap();
+        settings.theme.background_color = "#fff".parse().unwrap();
+
+        let theme = LockTheme::from_settings(&settings);
+        assert!((theme.accent.r - 0x35 as f32 / 255.0).abs() < 1e-6);
+        assert!((theme.accent.b - 0xe4 as f32 / 255.0).abs() < 1e-6);
+        assert_eq!(theme.background, Color::WHITE);
+        assert_eq!(theme.foreground, Color::BLACK);
+    }
+}
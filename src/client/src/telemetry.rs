@@ -0,0 +1,277 @@
+// WASMA - Opt-in usage statistics
+// Nothing is recorded unless WSDG settings have `[telemetry] enabled = true`.
+// Only counts are kept: windows created, renderer choices and error categories.
+// Titles, app ids, paths and addresses never enter a report. Reports are
+// appended to $STATE/wasma/telemetry.jsonl, or POSTed to `[telemetry] endpoint`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use wsdg_xdg::{TelemetrySettings, WsdgEnv, WsdgSettingsManager};
+
+pub const TELEMETRY_FLUSH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Renderer names reported as-is; anything else is counted as "other"
+const KNOWN_RENDERERS: [&str; 2] = ["cpu_renderer", "glx_renderer"];
+
+/// Coarse failure kinds; error messages themselves are never reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    Manifest,
+    Config,
+    Quota,
+    Session,
+}
+
+/// Where flushed reports go
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TelemetrySink {
+    /// One JSON report per line
+    File(PathBuf),
+    /// http:// URL receiving each report as a JSON POST body
+    Endpoint(String),
+}
+
+impl TelemetrySink {
+    /// `endpoint` when set, otherwise telemetry.jsonl in the WSDG state dir
+    pub fn from_settings(settings: &TelemetrySettings, env: &WsdgEnv) -> Self {
+        if !settings.endpoint.is_empty() {
+            return TelemetrySink::Endpoint(settings.endpoint.clone());
+        }
+        let state = env.state_dir().unwrap_or_else(|_| std::env::temp_dir());
+        TelemetrySink::File(state.join("wasma").join("telemetry.jsonl"))
+    }
+}
+
+/// Counts collected since the previous flush
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryReport {
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    pub period_secs: u64,
+    pub windows_created: u64,
+    pub renderers: BTreeMap<String, u64>,
+    pub errors: BTreeMap<ErrorCategory, u64>,
+}
+
+impl TelemetryReport {
+    fn is_empty(&self) -> bool {
+        self.windows_created == 0 && self.errors.is_empty()
+    }
+}
+
+struct Counts {
+    since: Instant,
+    windows_created: u64,
+    renderers: BTreeMap<String, u64>,
+    errors: BTreeMap<ErrorCategory, u64>,
+}
+
+impl Counts {
+    fn new() -> Self {
+        Counts { since: Instant::now(), windows_created: 0, renderers: BTreeMap::new(), errors: BTreeMap::new() }
+    }
+}
+
+pub struct Telemetry {
+    enabled: AtomicBool,
+    sink: Mutex<TelemetrySink>,
+    counts: Mutex<Counts>,
+}
+
+impl Telemetry {
+    pub fn new(enabled: bool, sink: TelemetrySink) -> Self {
+        Telemetry { enabled: AtomicBool::new(enabled), sink: Mutex::new(sink), counts: Mutex::new(Counts::new()) }
+    }
+
+    /// Opt-in state and sink from the user's WSDG settings; disabled if they cannot be read
+    pub fn from_wsdg() -> Self {
+        Self::from_env(&WsdgEnv::new())
+    }
+
+    /// Same as from_wsdg, for the settings of another WSDG environment
+    pub fn from_env(env: &WsdgEnv) -> Self {
+        let mut manager = WsdgSettingsManager::new(env.clone());
+        let settings = match manager.load() {
+            Ok(()) => manager.settings().telemetry.clone(),
+            Err(_) => TelemetrySettings::default(),
+        };
+        Self::new(settings.enabled, TelemetrySink::from_settings(&settings, env))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Turning telemetry off discards everything collected so far
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            *self.counts.lock().unwrap() = Counts::new();
+        }
+    }
+
+    pub fn sink(&self) -> TelemetrySink {
+        self.sink.lock().unwrap().clone()
+    }
+
+    pub fn set_sink(&self, sink: TelemetrySink) {
+        *self.sink.lock().unwrap() = sink;
+    }
+
+    pub fn record_window(&self, renderer: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+        let renderer = if KNOWN_RENDERERS.contains(&renderer) { renderer } else { "other" };
+        let mut counts = self.counts.lock().unwrap();
+        counts.windows_created += 1;
+        *counts.renderers.entry(renderer.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn record_error(&self, category: ErrorCategory) {
+        if !self.is_enabled() {
+            return;
+        }
+        *self.counts.lock().unwrap().errors.entry(category).or_insert(0) += 1;
+    }
+
+    /// What the next flush would send
+    pub fn report(&self) -> TelemetryReport {
+        let counts = self.counts.lock().unwrap();
+        TelemetryReport {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            period_secs: counts.since.elapsed().as_secs(),
+            windows_created: counts.windows_created,
+            renderers: counts.renderers.clone(),
+            errors: counts.errors.clone(),
+        }
+    }
+
+    /// Send the collected counts to the sink and start a new period.
+    /// Returns the report sent; None when disabled or nothing happened.
+    /// On failure the counts are kept for the next attempt.
+    pub fn flush(&self) -> io::Result<Option<TelemetryReport>> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+        let report = self.report();
+        if report.is_empty() {
+            return Ok(None);
+        }
+
+        let body = serde_json::to_string(&report).map_err(io::Error::other)?;
+        match self.sink() {
+            TelemetrySink::File(path) => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+                writeln!(file, "{}", body)?;
+            }
+            TelemetrySink::Endpoint(url) => post_json(&url, &body)?,
+        }
+
+        *self.counts.lock().unwrap() = Counts::new();
+        Ok(Some(report))
+    }
+}
+
+/// Minimal HTTP/1.1 POST; only a 2xx status counts as delivered
+pub(crate) fn post_json(url: &str, body: &str) -> io::Result<()> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| {
+        io::Error::new(io::ErrorKind::Unsupported, format!("endpoint must be http://: {}", url))
+    })?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, "/"),
+    };
+    let address = if authority.contains(':') { authority.to_string() } else { format!("{}:80", authority) };
+
+    let mut stream = TcpStream::connect(&address)?;
+    stream.set_read_timeout(Some(ENDPOINT_TIMEOUT))?;
+    stream.set_write_timeout(Some(ENDPOINT_TIMEOUT))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        authority,
+        body.len(),
+        body
+    )?;
+
+    let mut status = [0u8; 12];
+    stream.read_exact(&mut status)?;
+    // "HTTP/1.1 204"
+    match std::str::from_utf8(&status[9..12]).ok().and_then(|code| code.parse::<u16>().ok()) {
+        Some(code) if (200..300).contains(&code) => Ok(()),
+        code => Err(io::Error::other(format!("endpoint answered {:?}", code))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_telemetry_opt_in_and_sinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("telemetry.jsonl");
+        let telemetry = Telemetry::new(false, TelemetrySink::File(path.clone()));
+
+        // Disabled: nothing is counted or written
+        telemetry.record_window("cpu_renderer");
+        assert_eq!(telemetry.flush().unwrap(), None);
+        assert!(!path.exists());
+
+        telemetry.set_enabled(true);
+        telemetry.record_window("cpu_renderer");
+        telemetry.record_window("/home/user/custom_renderer");
+        telemetry.record_error(ErrorCategory::Quota);
+        let report = telemetry.flush().unwrap().unwrap();
+        assert_eq!(report.windows_created, 2);
+        assert_eq!(report.renderers["cpu_renderer"], 1);
+        assert_eq!(report.renderers["other"], 1);
+        assert_eq!(report.errors[&ErrorCategory::Quota], 1);
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(!written.contains("/home/user"));
+        let parsed: TelemetryReport = serde_json::from_str(written.trim()).unwrap();
+        assert_eq!(parsed, report);
+        // Counts restart after a flush
+        assert_eq!(telemetry.flush().unwrap(), None);
+
+        // Endpoint sink
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/wasma", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !String::from_utf8_lossy(&request).contains("\"errors\"") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        telemetry.set_sink(TelemetrySink::Endpoint(url));
+        telemetry.record_error(ErrorCategory::Config);
+        assert!(telemetry.flush().unwrap().is_some());
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /wasma HTTP/1.1"));
+        assert!(request.contains("\"config\":1"));
+    }
+}
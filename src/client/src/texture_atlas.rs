@@ -0,0 +1,358 @@
+// WASMA - Texture Atlas
+// With dozens of small tiled streams, one texture per viewport means one bind
+// per viewport per frame and a fragmented VRAM heap. Small viewports are packed
+// into shared atlas pages (shelf packing) and composited by UV rectangle;
+// viewports too large for a page keep a dedicated texture.
+
+use std::collections::HashMap;
+use crate::window_multitary::Viewport;
+
+/// Edge length of one atlas page (texels)
+pub const ATLAS_PAGE_SIZE: u32 = 2048;
+/// Viewports larger than this on either axis get a dedicated texture
+pub const ATLAS_MAX_SLOT: u32 = 512;
+/// Empty texels around every slot so linear filtering does not bleed
+const SLOT_GUTTER: u32 = 1;
+/// A shelf is reused for slots at least this fraction of its height
+const SHELF_FIT: f32 = 0.7;
+
+/// Normalized texture coordinates of a slot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UvRect {
+    pub u0: f32,
+    pub v0: f32,
+    pub u1: f32,
+    pub v1: f32,
+}
+
+/// Texel rectangle of a viewport inside an atlas page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasSlot {
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl AtlasSlot {
+    pub fn uv(&self, page_size: u32) -> UvRect {
+        let size = page_size as f32;
+        UvRect {
+            u0: self.x as f32 / size,
+            v0: self.y as f32 / size,
+            u1: (self.x + self.width) as f32 / size,
+            v1: (self.y + self.height) as f32 / size,
+        }
+    }
+}
+
+/// Texture a draw samples from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TextureRef {
+    Atlas(usize),
+    Dedicated(u8),
+}
+
+/// One viewport of the frame: sample `uv` of `texture` into `dest`
+#[derive(Debug, Clone)]
+pub struct DrawCommand {
+    pub stream_id: u8,
+    pub texture: TextureRef,
+    pub uv: UvRect,
+    pub dest: Viewport,
+}
+
+/// Draws of one frame, ordered by z_index then texture so binds are grouped
+#[derive(Debug, Clone, Default)]
+pub struct CompositePlan {
+    pub draws: Vec<DrawCommand>,
+}
+
+impl CompositePlan {
+    /// Texture binds needed to draw the plan in order
+    pub fn bind_count(&self) -> usize {
+        let mut binds = 0;
+        let mut bound = None;
+        for draw in &self.draws {
+            if bound != Some(draw.texture) {
+                bound = Some(draw.texture);
+                binds += 1;
+            }
+        }
+        binds
+    }
+}
+
+// ============================================================================
+// SHELF PACKING
+// ============================================================================
+
+#[derive(Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    // Next unused x at the end of the shelf
+    cursor: u32,
+    // Freed [x, x + width) spans, kept sorted and merged
+    free: Vec<(u32, u32)>,
+}
+
+impl Shelf {
+    fn take(&mut self, width: u32, page_size: u32) -> Option<u32> {
+        if let Some(i) = self.free.iter().position(|&(_, w)| w >= width) {
+            let (x, w) = self.free[i];
+            if w == width {
+                self.free.remove(i);
+            } else {
+                self.free[i] = (x + width, w - width);
+            }
+            return Some(x);
+        }
+        if self.cursor + width <= page_size {
+            let x = self.cursor;
+            self.cursor += width;
+            return Some(x);
+        }
+        None
+    }
+
+    fn give_back(&mut self, x: u32, width: u32) {
+        let at = self.free.partition_point(|&(fx, _)| fx < x);
+        self.free.insert(at, (x, width));
+
+        let mut merged: Vec<(u32, u32)> = Vec::with_capacity(self.free.len());
+        for (fx, fw) in self.free.drain(..) {
+            match merged.last_mut() {
+                Some((mx, mw)) if *mx + *mw == fx => *mw += fw,
+                _ => merged.push((fx, fw)),
+            }
+        }
+        // A free span touching the cursor just moves the cursor back
+        if let Some(&(lx, lw)) = merged.last() {
+            if lx + lw == self.cursor {
+                self.cursor = lx;
+                merged.pop();
+            }
+        }
+        self.free = merged;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.cursor == 0
+    }
+}
+
+#[derive(Debug, Default)]
+struct AtlasPage {
+    shelves: Vec<Shelf>,
+}
+
+impl AtlasPage {
+    fn allocate(&mut self, width: u32, height: u32, page_size: u32) -> Option<(u32, u32)> {
+        // Best-fitting existing shelf first
+        let mut candidates: Vec<usize> = (0..self.shelves.len())
+            .filter(|&i| {
+                let shelf = &self.shelves[i];
+                shelf.height >= height && height as f32 >= shelf.height as f32 * SHELF_FIT
+            })
+            .collect();
+        candidates.sort_by_key(|&i| self.shelves[i].height);
+        for i in candidates {
+            if let Some(x) = self.shelves[i].take(width, page_size) {
+                return Some((x, self.shelves[i].y));
+            }
+        }
+
+        let top = self.shelves.last().map_or(0, |s| s.y + s.height);
+        if top + height > page_size {
+            return None;
+        }
+        let mut shelf = Shelf { y: top, height, cursor: 0, free: Vec::new() };
+        let x = shelf.take(width, page_size)?;
+        self.shelves.push(shelf);
+        Some((x, top))
+    }
+
+    fn free(&mut self, x: u32, y: u32, width: u32) {
+        if let Some(shelf) = self.shelves.iter_mut().find(|s| s.y == y) {
+            shelf.give_back(x, width);
+        }
+        // Empty shelves at the top of the page give their rows back
+        while self.shelves.last().is_some_and(Shelf::is_empty) {
+            self.shelves.pop();
+        }
+    }
+}
+
+// ============================================================================
+// ATLAS
+// ============================================================================
+
+/// Atlas allocator keyed by stream_id
+#[derive(Debug)]
+pub struct TextureAtlas {
+    page_size: u32,
+    max_slot: u32,
+    pages: Vec<AtlasPage>,
+    slots: HashMap<u8, AtlasSlot>,
+}
+
+impl Default for TextureAtlas {
+    fn default() -> Self {
+        Self::new(ATLAS_PAGE_SIZE, ATLAS_MAX_SLOT)
+    }
+}
+
+impl TextureAtlas {
+    pub fn new(page_size: u32, max_slot: u32) -> Self {
+        TextureAtlas {
+            page_size,
+            max_slot: max_slot.min(page_size - 2 * SLOT_GUTTER),
+            pages: Vec::new(),
+            slots: HashMap::new(),
+        }
+    }
+
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn slot(&self, stream_id: u8) -> Option<AtlasSlot> {
+        self.slots.get(&stream_id).copied()
+    }
+
+    /// Whether a viewport of this size is packed rather than given its own texture
+    pub fn fits(&self, width: u32, height: u32) -> bool {
+        width > 0 && height > 0 && width <= self.max_slot && height <= self.max_slot
+    }
+
+    /// Slot for `stream_id`; an existing slot of the same size is kept.
+    /// None when the viewport is too large for the atlas.
+    pub fn allocate(&mut self, stream_id: u8, width: u32, height: u32) -> Option<AtlasSlot> {
+        if let Some(slot) = self.slot(stream_id) {
+            if slot.width == width && slot.height == height {
+                return Some(slot);
+            }
+            self.release(stream_id);
+        }
+        if !self.fits(width, height) {
+            return None;
+        }
+
+        let (padded_w, padded_h) = (width + 2 * SLOT_GUTTER, height + 2 * SLOT_GUTTER);
+        let page_size = self.page_size;
+        let placed = self
+            .pages
+            .iter_mut()
+            .enumerate()
+            .find_map(|(i, page)| page.allocate(padded_w, padded_h, page_size).map(|pos| (i, pos)));
+        let (page, (x, y)) = match placed {
+            Some(placed) => placed,
+            None => {
+                let mut page = AtlasPage::default();
+                let pos = page.allocate(padded_w, padded_h, page_size)?;
+                self.pages.push(page);
+                (self.pages.len() - 1, pos)
+            }
+        };
+
+        let slot = AtlasSlot { page, x: x + SLOT_GUTTER, y: y + SLOT_GUTTER, width, height };
+        self.slots.insert(stream_id, slot);
+        Some(slot)
+    }
+
+    pub fn release(&mut self, stream_id: u8) {
+        if let Some(slot) = self.slots.remove(&stream_id) {
+            self.pages[slot.page].free(
+                slot.x - SLOT_GUTTER,
+                slot.y - SLOT_GUTTER,
+                slot.width + 2 * SLOT_GUTTER,
+            );
+        }
+    }
+
+    /// Bring slots in line with `viewports` and build the frame's draw list
+    pub fn plan(&mut self, viewports: &HashMap<u8, Viewport>) -> CompositePlan {
+        let gone: Vec<u8> = self.slots.keys().filter(|id| !viewports.contains_key(id)).copied().collect();
+        for id in gone {
+            self.release(id);
+        }
+
+        let mut ids: Vec<u8> = viewports.keys().copied().filter(|id| viewports[id].active).collect();
+        // Big viewports first: packs tighter and keeps slots stable across frames
+        ids.sort_by_key(|id| (std::cmp::Reverse(viewports[id].height), *id));
+
+        let mut draws: Vec<DrawCommand> = ids
+            .into_iter()
+            .map(|stream_id| {
+                let vp = &viewports[&stream_id];
+                match self.allocate(stream_id, vp.width, vp.height) {
+                    Some(slot) => DrawCommand {
+                        stream_id,
+                        texture: TextureRef::Atlas(slot.page),
+                        uv: slot.uv(self.page_size),
+                        dest: vp.clone(),
+                    },
+                    None => DrawCommand {
+                        stream_id,
+                        texture: TextureRef::Dedicated(stream_id),
+                        uv: UvRect { u0: 0.0, v0: 0.0, u1: 1.0, v1: 1.0 },
+                        dest: vp.clone(),
+                    },
+                }
+            })
+            .collect();
+
+        draws.sort_by_key(|d| (d.dest.z_index, d.texture, d.stream_id));
+        CompositePlan { draws }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport(width: u32, height: u32) -> Viewport {
+        Viewport { x: 0, y: 0, width, height, z_index: 1, active: true, scale: 1.0 }
+    }
+
+    #[test]
+    fn test_atlas_packs_small_viewports() {
+        let mut atlas = TextureAtlas::new(1024, 256);
+
+        // 24 thumbnails share one page, one large stream keeps its own texture
+        let mut viewports: HashMap<u8, Viewport> = (0..24).map(|id| (id, viewport(160, 120))).collect();
+        viewports.insert(100, viewport(1280, 720));
+        let plan = atlas.plan(&viewports);
+        assert_eq!(atlas.page_count(), 1);
+        assert_eq!(plan.draws.len(), 25);
+        assert_eq!(plan.bind_count(), 2);
+
+        let slot = atlas.slot(0).unwrap();
+        let uv = slot.uv(1024);
+        assert!(uv.u0 > 0.0 && uv.u1 < 1.0);
+        assert!(atlas.slot(100).is_none());
+
+        // Slots never overlap
+        let slots: Vec<AtlasSlot> = (0..24).map(|id| atlas.slot(id).unwrap()).collect();
+        for (i, a) in slots.iter().enumerate() {
+            for b in &slots[i + 1..] {
+                let apart = a.x + a.width <= b.x || b.x + b.width <= a.x || a.y + a.height <= b.y || b.y + b.height <= a.y;
+                assert!(apart, "{:?} overlaps {:?}", a, b);
+            }
+        }
+
+        // Freed slots are reused instead of growing the page
+        viewports.remove(&5);
+        atlas.plan(&viewports);
+        viewports.insert(50, viewport(160, 120));
+        atlas.plan(&viewports);
+        assert_eq!(atlas.slot(50), Some(slots[5]));
+        assert_eq!(atlas.page_count(), 1);
+    }
+}
@@ -0,0 +1,183 @@
+// WASMA - Window thumbnails
+// Live previews for the window cards of the GUI manager. Each window has a
+// FrameSource telling where its pixels can be read back from: its slot in the
+// native VRAM presenter, its X11 top-level (GetImage), or the last frame handed
+// to WASMA for it (Wayland and remote windows, whose surfaces other clients
+// cannot read). Captures are converted to RGBA and box-filtered down to the
+// requested size.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::pixel_format::{self, FrameHeader};
+use crate::presenter::{Presenter, STREAM_SLOT_SIZE};
+
+/// Longest edge of the previews in window cards
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 240;
+/// How often the GUI re-captures visible previews
+pub const THUMBNAIL_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Packed, non-premultiplied RGBA pixels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// None when `data` is not `width * height` pixels
+    pub fn new(width: u32, height: u32, data: Vec<u8>) -> Option<Self> {
+        (data.len() == width as usize * height as usize * 4).then_some(Self { width, height, data })
+    }
+
+    /// From packed BGRA rows (what `pixel_format::to_bgra` produces)
+    pub fn from_bgra(width: u32, height: u32, mut data: Vec<u8>) -> Option<Self> {
+        for px in data.chunks_exact_mut(4) {
+            px.swap(0, 2);
+        }
+        Self::new(width, height, data)
+    }
+
+    /// Any validated stream frame
+    pub fn from_frame(header: &FrameHeader, data: &[u8]) -> Result<Self, String> {
+        header.validate(data.len()).map_err(|e| e.to_string())?;
+        Self::from_bgra(header.width, header.height, pixel_format::to_bgra(header, data))
+            .ok_or_else(|| "frame size mismatch".to_string())
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        [self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]]
+    }
+
+    /// Scaled down (never up) so the longer edge is at most `max_size`,
+    /// keeping the aspect ratio; every output pixel averages its source box
+    pub fn fit(&self, max_size: u32) -> RgbaImage {
+        let longest = self.width.max(self.height);
+        if longest <= max_size || max_size == 0 {
+            return self.clone();
+        }
+        let scale = max_size as f64 / longest as f64;
+        let width = ((self.width as f64 * scale).round() as u32).max(1);
+        let height = ((self.height as f64 * scale).round() as u32).max(1);
+
+        let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height {
+            let (y0, y1) = box_range(y, height, self.height);
+            for x in 0..width {
+                let (x0, x1) = box_range(x, width, self.width);
+                let mut sum = [0u64; 4];
+                for sy in y0..y1 {
+                    for sx in x0..x1 {
+                        for (total, c) in sum.iter_mut().zip(self.pixel(sx, sy)) {
+                            *total += c as u64;
+                        }
+                    }
+                }
+                let count = ((x1 - x0) * (y1 - y0)) as u64;
+                data.extend(sum.map(|total| ((total + count / 2) / count) as u8));
+            }
+        }
+        RgbaImage { width, height, data }
+    }
+}
+
+/// Source pixels [start, end) covered by output pixel `i` of `out` pixels
+fn box_range(i: u32, out: u32, src: u32) -> (u32, u32) {
+    let start = (i as u64 * src as u64 / out as u64) as u32;
+    let end = (((i as u64 + 1) * src as u64).div_ceil(out as u64) as u32).clamp(start + 1, src);
+    (start, end)
+}
+
+/// Where a window's current pixels can be read back from
+#[derive(Debug, Clone)]
+pub enum FrameSource {
+    /// The stream's slot in the native VRAM presenter, in the stream's format
+    Vram { stream_id: u8, header: FrameHeader },
+    /// X11 top-level, read with GetImage
+    X11(u32),
+    /// Last frame submitted for the window
+    Frame { header: FrameHeader, data: Arc<Vec<u8>> },
+}
+
+impl FrameSource {
+    /// Full-size capture of what the window shows now
+    pub fn capture(&self) -> Result<RgbaImage, String> {
+        match self {
+            FrameSource::Vram { stream_id, header } => {
+                let len = header.required_len();
+                if len > STREAM_SLOT_SIZE {
+                    return Err(format!("{}x{} frame does not fit a VRAM slot", header.width, header.height));
+                }
+                let offset = *stream_id as usize * STREAM_SLOT_SIZE;
+                // What scanout shows, not a half-written back buffer
+                let data = Presenter::global().with_front(|front| front.get(offset..offset + len).map(<[u8]>::to_vec));
+                RgbaImage::from_frame(header, &data.ok_or("stream slot outside the VRAM buffer")?)
+            }
+            FrameSource::X11(xid) => capture_x11(*xid),
+            FrameSource::Frame { header, data } => RgbaImage::from_frame(header, data),
+        }
+    }
+}
+
+#[cfg(feature = "x11")]
+fn capture_x11(xid: u32) -> Result<RgbaImage, String> {
+    use x11rb::protocol::xproto::{ConnectionExt, ImageFormat};
+
+    let (conn, _) = x11rb::connect(None).map_err(|e| format!("X11 connection failed: {}", e))?;
+    let geometry = conn.get_geometry(xid).map_err(|e| e.to_string())?.reply().map_err(|e| e.to_string())?;
+    let image = conn.get_image(ImageFormat::Z_PIXMAP, xid, 0, 0, geometry.width, geometry.height, !0)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| format!("GetImage on 0x{:x}: {}", xid, e))?;
+    if image.depth < 24 {
+        return Err(format!("unsupported X11 depth {}", image.depth));
+    }
+    // Depth 24/32 Z_PIXMAP: packed BGRX rows; X has no alpha for top-levels
+    let mut data = image.data;
+    for px in data.chunks_exact_mut(4) {
+        px[3] = 0xff;
+    }
+    RgbaImage::from_bgra(geometry.width as u32, geometry.height as u32, data)
+        .ok_or_else(|| "unexpected X11 image size".to_string())
+}
+
+#[cfg(not(feature = "x11"))]
+fn capture_x11(_xid: u32) -> Result<RgbaImage, String> {
+    Err("built without X11 support".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixel_format::PixelFormat;
+
+    #[test]
+    fn test_thumbnail_capture() {
+        // 4x2 RGB888: left half red, right half blue
+        let mut data = Vec::new();
+        for _ in 0..2 {
+            data.extend([255, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 255]);
+        }
+        let header = FrameHeader { width: 4, height: 2, stride: 12, format: PixelFormat::Rgb888 };
+        let source = FrameSource::Frame { header, data: Arc::new(data) };
+
+        let image = source.capture().unwrap();
+        assert_eq!((image.width, image.height), (4, 2));
+        assert_eq!(image.pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(3, 1), [0, 0, 255, 255]);
+
+        let thumb = image.fit(2);
+        assert_eq!((thumb.width, thumb.height), (2, 1));
+        assert_eq!(thumb.pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(thumb.pixel(1, 0), [0, 0, 255, 255]);
+        // Boxes straddling both halves average them
+        assert_eq!(image.fit(3).pixel(1, 0), [128, 0, 128, 255]);
+        // Never upscaled
+        assert_eq!(image.fit(100), image);
+
+        let truncated = FrameSource::Frame { header, data: Arc::new(vec![0; 10]) };
+        assert!(truncated.capture().is_err());
+    }
+}
@@ -0,0 +1,405 @@
+// WASMA - Tor transport
+// tor:// endpoints are reached through the Tor daemon's SOCKS5 port
+// (wasma.in.conf `tor_socks`, default 127.0.0.1:9050). With a `domain_def`
+// the name (usually .onion) is handed to Tor unresolved, so it never leaks to
+// local DNS; without one the protocol_def address is used as the destination.
+// Bootstrap progress is read from the control port (`tor_control`, optional
+// `tor_control_cookie`) with GETINFO status/bootstrap-phase.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Tor's default SOCKS port
+pub const DEFAULT_TOR_SOCKS: &str = "127.0.0.1:9050";
+
+/// Onion circuits take a while to build; exit connections are faster
+pub const TOR_CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Error, Debug)]
+pub enum TorError {
+    #[error("Tor daemon not reachable at {addr}: {source}")]
+    DaemonUnreachable { addr: SocketAddr, source: io::Error },
+
+    #[error("SOCKS5 handshake failed: {0}")]
+    Handshake(String),
+
+    #[error("Tor could not reach {target}: {reason}")]
+    Refused { target: String, reason: &'static str },
+
+    #[error("Tor is still bootstrapping ({0})")]
+    Bootstrapping(Bootstrap),
+
+    #[error("Tor control port: {0}")]
+    Control(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// wasma.in.conf `tor_socks` / `tor_control` / `tor_control_cookie`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TorConfig {
+    pub socks: SocketAddr,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control: Option<SocketAddr>,
+    /// CookieAuthentication file; null authentication when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control_cookie: Option<PathBuf>,
+}
+
+impl Default for TorConfig {
+    fn default() -> Self {
+        Self {
+            socks: DEFAULT_TOR_SOCKS.parse().unwrap(),
+            control: None,
+            control_cookie: None,
+        }
+    }
+}
+
+/// Where Tor should connect to
+#[derive(Debug, Clone, PartialEq)]
+pub enum SocksTarget {
+    /// Resolved by Tor (required for .onion)
+    Domain(String, u16),
+    Addr(SocketAddr),
+}
+
+impl SocksTarget {
+    /// `domain_def` wins over the protocol_def address
+    pub fn for_endpoint(ip: IpAddr, port: u16, domain: Option<&str>) -> Self {
+        match domain {
+            Some(domain) => Self::Domain(domain.to_string(), port),
+            None => Self::Addr(SocketAddr::new(ip, port)),
+        }
+    }
+}
+
+impl fmt::Display for SocksTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Domain(domain, port) => write!(f, "{}:{}", domain, port),
+            Self::Addr(addr) => write!(f, "{}", addr),
+        }
+    }
+}
+
+/// `status/bootstrap-phase` of the Tor daemon
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bootstrap {
+    pub progress: u8,
+    pub tag: String,
+    pub summary: String,
+}
+
+impl Bootstrap {
+    pub fn is_done(&self) -> bool {
+        self.progress >= 100
+    }
+
+    /// `NOTICE BOOTSTRAP PROGRESS=45 TAG=loading_descriptors SUMMARY="Loading relay descriptors"`
+    pub fn parse(phase: &str) -> Option<Self> {
+        let field = |name: &str| {
+            let start = phase.find(&format!("{}=", name))? + name.len() + 1;
+            let rest = &phase[start..];
+            Some(match rest.strip_prefix('"') {
+                Some(quoted) => quoted.split('"').next().unwrap_or_default().to_string(),
+                None => rest.split_whitespace().next().unwrap_or_default().to_string(),
+            })
+        };
+        Some(Self {
+            progress: field("PROGRESS")?.parse().ok()?,
+            tag: field("TAG").unwrap_or_default(),
+            summary: field("SUMMARY").unwrap_or_default(),
+        })
+    }
+}
+
+impl fmt::Display for Bootstrap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%: {}", self.progress, self.summary)
+    }
+}
+
+/// What a tor:// connection would run into right now
+#[derive(Debug, Clone, PartialEq)]
+pub enum TorStatus {
+    /// Nothing answers SOCKS5 on the configured port
+    Unreachable(String),
+    /// SOCKS port answers; no control port to ask about bootstrap progress
+    SocksReady,
+    Bootstrapping(Bootstrap),
+    Ready(Bootstrap),
+}
+
+impl TorStatus {
+    pub fn is_usable(&self) -> bool {
+        matches!(self, Self::SocksReady | Self::Ready(_))
+    }
+}
+
+impl fmt::Display for TorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreachable(reason) => write!(f, "unreachable ({})", reason),
+            Self::SocksReady => write!(f, "SOCKS port ready (bootstrap unknown, no tor_control)"),
+            Self::Bootstrapping(phase) => write!(f, "bootstrapping {}", phase),
+            Self::Ready(phase) => write!(f, "ready {}", phase),
+        }
+    }
+}
+
+/// Bootstrap progress when a control port is configured, else a SOCKS5 probe
+pub fn status(config: &TorConfig) -> TorStatus {
+    if config.control.is_some() {
+        match bootstrap_phase(config) {
+            Ok(phase) if phase.is_done() => return TorStatus::Ready(phase),
+            Ok(phase) => return TorStatus::Bootstrapping(phase),
+            Err(e) => eprintln!("⚠️  Tor control port unavailable, probing SOCKS instead: {}", e),
+        }
+    }
+
+    match open_socks(config, PROBE_TIMEOUT) {
+        Ok(_) => TorStatus::SocksReady,
+        Err(e) => TorStatus::Unreachable(e.to_string()),
+    }
+}
+
+/// GETINFO status/bootstrap-phase over the control port
+pub fn bootstrap_phase(config: &TorConfig) -> Result<Bootstrap, TorError> {
+    let addr = config.control.ok_or_else(|| TorError::Control("tor_control is not configured".to_string()))?;
+    let stream = TcpStream::connect_timeout(&addr, PROBE_TIMEOUT)
+        .map_err(|source| TorError::DaemonUnreachable { addr, source })?;
+    stream.set_read_timeout(Some(PROBE_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+
+    let auth = match &config.control_cookie {
+        Some(path) => std::fs::read(path)?.iter().map(|b| format!("{:02x}", b)).collect(),
+        None => "\"\"".to_string(),
+    };
+    writer.write_all(format!("AUTHENTICATE {}\r\n", auth).as_bytes())?;
+    let reply = control_reply(&mut reader)?;
+    if !reply.last().is_some_and(|line| line.starts_with("250")) {
+        return Err(TorError::Control(reply.join(" ")));
+    }
+
+    writer.write_all(b"GETINFO status/bootstrap-phase\r\nQUIT\r\n")?;
+    let reply = control_reply(&mut reader)?;
+    reply.iter()
+        .find_map(|line| line.strip_prefix("250-status/bootstrap-phase="))
+        .and_then(Bootstrap::parse)
+        .ok_or_else(|| TorError::Control(reply.join(" ")))
+}
+
+/// Lines of one control port reply, up to the final "NNN " line
+fn control_reply(reader: &mut impl BufRead) -> Result<Vec<String>, TorError> {
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(TorError::Control("connection closed".to_string()));
+        }
+        let line = line.trim_end().to_string();
+        let last = line.as_bytes().get(3) == Some(&b' ');
+        lines.push(line);
+        if last {
+            return Ok(lines);
+        }
+    }
+}
+
+/// Connected SOCKS5 session past method negotiation (no authentication)
+fn open_socks(config: &TorConfig, timeout: Duration) -> Result<TcpStream, TorError> {
+    let addr = config.socks;
+    let mut stream = TcpStream::connect_timeout(&addr, PROBE_TIMEOUT.min(timeout))
+        .map_err(|source| TorError::DaemonUnreachable { addr, source })?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    stream.write_all(&[0x05, 0x01, 0x00])?;
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice)?;
+    if choice != [0x05, 0x00] {
+        return Err(TorError::Handshake(format!("{} does not offer unauthenticated SOCKS5", addr)));
+    }
+    Ok(stream)
+}
+
+/// Open a stream to `target` through Tor
+pub fn connect(config: &TorConfig, target: &SocksTarget, timeout: Duration) -> Result<TcpStream, TorError> {
+    let mut stream = open_socks(config, timeout)?;
+
+    let mut request = vec![0x05, 0x01, 0x00];
+    let port = match target {
+        SocksTarget::Domain(domain, port) => {
+            let name = domain.as_bytes();
+            if name.is_empty() || name.len() > 255 {
+                return Err(TorError::Handshake(format!("invalid domain {:?}", domain)));
+            }
+            request.push(0x03);
+            request.push(name.len() as u8);
+            request.extend_from_slice(name);
+            *port
+        }
+        SocksTarget::Addr(SocketAddr::V4(addr)) => {
+            request.push(0x01);
+            request.extend_from_slice(&addr.ip().octets());
+            addr.port()
+        }
+        SocksTarget::Addr(SocketAddr::V6(addr)) => {
+            request.push(0x04);
+            request.extend_from_slice(&addr.ip().octets());
+            addr.port()
+        }
+    };
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request)?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply)?;
+    if reply[0] != 0x05 {
+        return Err(TorError::Handshake(format!("unexpected SOCKS version {}", reply[0])));
+    }
+    if reply[1] != 0x00 {
+        return Err(TorError::Refused { target: target.to_string(), reason: reply_reason(reply[1]) });
+    }
+
+    // Bound address, unused
+    let bound = match reply[3] {
+        0x01 => 4 + 2,
+        0x04 => 16 + 2,
+        0x03 => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len)?;
+            len[0] as usize + 2
+        }
+        other => return Err(TorError::Handshake(format!("unknown address type {}", other))),
+    };
+    stream.read_exact(&mut vec![0u8; bound])?;
+
+    stream.set_read_timeout(None)?;
+    stream.set_write_timeout(None)?;
+    Ok(stream)
+}
+
+/// SOCKS5 reply codes, including Tor's onion service extensions (prop304)
+fn reply_reason(code: u8) -> &'static str {
+    match code {
+        0x01 => "general SOCKS server failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        0xF0 => "onion service descriptor not found",
+        0xF1 => "onion service descriptor is invalid",
+        0xF2 => "onion service introduction failed",
+        0xF3 => "onion service rendezvous failed",
+        0xF4 => "onion service requires client authorization",
+        0xF5 => "onion service client authorization is wrong",
+        0xF6 => "invalid onion address",
+        0xF7 => "onion service introduction timed out",
+        _ => "unknown SOCKS error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// SOCKS5 server answering each connection with the next reply code
+    fn fake_socks(replies: Vec<u8>) -> (SocketAddr, std::thread::JoinHandle<Vec<Vec<u8>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for code in replies {
+                let (mut client, _) = listener.accept().unwrap();
+                let mut greeting = [0u8; 3];
+                client.read_exact(&mut greeting).unwrap();
+                client.write_all(&[0x05, 0x00]).unwrap();
+
+                let mut head = [0u8; 5];
+                client.read_exact(&mut head).unwrap();
+                let mut rest = vec![0u8; head[4] as usize + 2];
+                client.read_exact(&mut rest).unwrap();
+                requests.push([&head[..], &rest[..]].concat());
+
+                client.write_all(&[0x05, code, 0x00, 0x01, 0, 0, 0, 0, 0, 0]).unwrap();
+                if code == 0 {
+                    client.write_all(b"frame").unwrap();
+                }
+            }
+            requests
+        });
+        (addr, server)
+    }
+
+    #[test]
+    fn test_socks5_onion_connect() {
+        let (socks, server) = fake_socks(vec![0x00, 0xF0]);
+        let config = TorConfig { socks, ..TorConfig::default() };
+        let onion = SocksTarget::for_endpoint("127.0.0.1".parse().unwrap(), 80, Some("example.onion"));
+
+        let mut stream = connect(&config, &onion, TOR_CONNECT_TIMEOUT).unwrap();
+        let mut frame = [0u8; 5];
+        stream.read_exact(&mut frame).unwrap();
+        assert_eq!(&frame, b"frame");
+
+        match connect(&config, &onion, TOR_CONNECT_TIMEOUT) {
+            Err(TorError::Refused { target, reason }) => {
+                assert_eq!(target, "example.onion:80");
+                assert_eq!(reason, "onion service descriptor not found");
+            }
+            other => panic!("expected refusal, got {:?}", other.map(|_| ())),
+        }
+
+        // The name goes to Tor unresolved (ATYP 3)
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0], [&[0x05, 0x01, 0x00, 0x03, 13][..], b"example.onion", &[0, 80]].concat());
+    }
+
+    #[test]
+    fn test_bootstrap_status() {
+        let phase = "NOTICE BOOTSTRAP PROGRESS=45 TAG=loading_descriptors SUMMARY=\"Loading relay descriptors\"";
+        let parsed = Bootstrap::parse(phase).unwrap();
+        assert_eq!(parsed.progress, 45);
+        assert_eq!(parsed.tag, "loading_descriptors");
+        assert_eq!(parsed.to_string(), "45%: Loading relay descriptors");
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let control = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (client, _) = listener.accept().unwrap();
+            let mut writer = client.try_clone().unwrap();
+            let mut reader = BufReader::new(client);
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!(line, "AUTHENTICATE \"\"\r\n");
+            writer.write_all(b"250 OK\r\n").unwrap();
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            writer.write_all(format!("250-status/bootstrap-phase={}\r\n250 OK\r\n", phase).as_bytes()).unwrap();
+        });
+
+        let unused = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let config = TorConfig { socks: unused, control: Some(control), control_cookie: None };
+        assert_eq!(status(&config), TorStatus::Bootstrapping(parsed));
+        server.join().unwrap();
+
+        // No daemon at all
+        let config = TorConfig { socks: unused, ..TorConfig::default() };
+        assert!(matches!(status(&config), TorStatus::Unreachable(_)));
+    }
+}
@@ -1,7 +1,15 @@
 use std::net::TcpStream;
-use std::io::{Read, ErrorKind};
 use crate::parser::WasmaConfig;
-use std::sync::Arc;
+use crate::context_pool::RendererContext;
+use crate::metrics;
+use crate::pixel_format::{self, FormatError, FrameHeader, PixelFormat};
+use crate::renderer_soft::SoftRenderer;
+use crate::session_lock::{self, SESSION_LOCKED};
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
 
 #[cfg(feature = "glx")]
 use gl;
@@ -18,6 +26,47 @@ use opencl3::memory::{Buffer, CL_MEM_READ_ONLY, CL_MEM_COPY_HOST_PTR};
 #[cfg(feature = "intel-uhd")]
 use rayon::prelude::*;
 
+#[cfg(feature = "vulkan")]
+use crate::renderer_vulkan::{SurfaceTarget, VulkanRenderer};
+#[cfg(feature = "vulkan")]
+use std::sync::OnceLock;
+
+/// wasma.in.conf `reconnect_max_retries` / `reconnect_backoff_ms` / `reconnect_backoff_max_ms`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReconnectPolicy {
+    /// Consecutive failed attempts retried before giving up; 0 disables reconnection
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every further one
+    pub backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self { max_retries: 5, backoff_ms: 500, max_backoff_ms: 30_000 }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before retry number `attempt` (1-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u64.checked_shl(attempt.saturating_sub(1)).unwrap_or(u64::MAX);
+        Duration::from_millis(self.backoff_ms.saturating_mul(factor).min(self.max_backoff_ms))
+    }
+}
+
+/// Connection state changes, for GUIs showing whether the stream is live
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionEvent {
+    Connected { addr: String, format: PixelFormat },
+    /// A connected session ended; `reason` is the error or "stream closed"
+    Disconnected { addr: String, reason: String },
+    /// Waiting `delay` before retry number `attempt`
+    Reconnecting { attempt: u32, delay: Duration },
+    /// max_retries exhausted; start_engine returns
+    GaveUp { attempts: u32, reason: String },
+}
+
 /// WASMA Section Memory: Memory divided into mathematical sections
 pub struct SectionMemory {
     pub raw_storage: Vec<u8>,
@@ -51,11 +100,29 @@ impl SectionMemory {
         let end = (start + self.cell_size).min(self.raw_storage.len());
         &self.raw_storage[start..end]
     }
+
+    /// Copy a frame into the cells from the first one on; None if it does not fit
+    pub fn stage(&mut self, data: &[u8]) -> Option<usize> {
+        let target = self.raw_storage.get_mut(..data.len())?;
+        target.copy_from_slice(data);
+        Some(data.len())
+    }
 }
 
 pub struct UClient {
     config: Arc<WasmaConfig>,
     memory: SectionMemory,
+    subscribers: Vec<Sender<ConnectionEvent>>,
+    // renderer_soft framebuffer and window, opened on the first frame
+    soft: Mutex<Option<SoftRenderer>>,
+    // Pre-opened by the context pool; otherwise opened per dispatch
+    #[cfg(feature = "opencl-gpu")]
+    cl_context: Option<Context>,
+    // Window to present to, and the renderer opened on the first frame (None: unavailable)
+    #[cfg(feature = "vulkan")]
+    vulkan_target: Option<SurfaceTarget>,
+    #[cfg(feature = "vulkan")]
+    vulkan: OnceLock<Option<Mutex<VulkanRenderer>>>,
 }
 
 impl UClient {
@@ -64,6 +131,14 @@ impl UClient {
         Self {
             config: Arc::new(config),
             memory: SectionMemory::new(level),
+            subscribers: Vec::new(),
+            soft: Mutex::new(None),
+            #[cfg(feature = "opencl-gpu")]
+            cl_context: None,
+            #[cfg(feature = "vulkan")]
+            vulkan_target: None,
+            #[cfg(feature = "vulkan")]
+            vulkan: OnceLock::new(),
         }
     }
 
@@ -72,9 +147,52 @@ impl UClient {
         Self {
             config,
             memory: SectionMemory::new(level),
+            subscribers: Vec::new(),
+            soft: Mutex::new(None),
+            #[cfg(feature = "opencl-gpu")]
+            cl_context: None,
+            #[cfg(feature = "vulkan")]
+            vulkan_target: None,
+            #[cfg(feature = "vulkan")]
+            vulkan: OnceLock::new(),
+        }
+    }
+
+    /// Start from a warm context (see ContextPool) instead of allocating on the first frame
+    pub fn from_context(config: Arc<WasmaConfig>, context: RendererContext) -> Self {
+        Self {
+            config,
+            memory: context.memory,
+            subscribers: Vec::new(),
+            soft: Mutex::new(None),
+            #[cfg(feature = "opencl-gpu")]
+            cl_context: context.cl_context,
+            #[cfg(feature = "vulkan")]
+            vulkan_target: None,
+            #[cfg(feature = "vulkan")]
+            vulkan: OnceLock::new(),
         }
     }
 
+    /// Window the Vulkan renderer presents to; set before the engine starts
+    #[cfg(feature = "vulkan")]
+    pub fn set_vulkan_target(&mut self, target: SurfaceTarget) {
+        self.vulkan_target = Some(target);
+    }
+
+    /// Receive connection events; dropped receivers are pruned on the next event
+    pub fn subscribe(&mut self) -> Receiver<ConnectionEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.push(tx);
+        rx
+    }
+
+    fn emit(&mut self, event: ConnectionEvent) {
+        self.subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+    }
+
+    /// Stream until the server goes away for good: lost connections are retried
+    /// with exponential backoff per wasma.in.conf `reconnect_*`
     pub fn start_engine(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         if self.config.uri_handling.protocols.is_empty() {
             return Err("No protocols configured".into());
@@ -83,69 +201,146 @@ impl UClient {
         let proto = &self.config.uri_handling.protocols[0];
         let addr = format!("{}:{}", proto.ip, proto.port);
         
-        println!("🔌 Connecting to {}...", addr);
-        let mut stream = TcpStream::connect(&addr)?;
-        
         let level = self.config.resource_limits.scope_level;
+        session_lock::watch_lock_marker();
+
+        let mode = if level == 0 { "NULL_EXCEPTION (Bypass/Raw)" } else { "Partitioned" };
+        tracing::info!(mode, renderer = %self.config.resource_limits.renderer, "UClient engine started");
 
-        println!("🟢 WASMA UClient: Engine Started");
-        println!("📡 Mode: {}", if level == 0 { "NULL_EXCEPTION (Bypass/Raw)" } else { "Partitioned" });
-        println!("🎨 Renderer: {}", self.config.resource_limits.renderer);
+        let policy = self.config.uri_handling.reconnect.clone();
+        let mut failures = 0;
+        loop {
+            let mut frames = 0;
+            let result = self.run_session(&addr, &mut frames);
+            let reason = match result {
+                Ok(()) => None,
+                // Retrying will not change what either side can speak
+                Err(e @ (FormatError::NoCommonFormat | FormatError::UnsupportedVersion(_))) => return Err(e.into()),
+                Err(e) => Some(e.to_string()),
+            };
+
+            // A session that delivered frames starts the backoff over
+            if frames > 0 {
+                failures = 0;
+            }
+            failures += 1;
 
+            if failures > policy.max_retries {
+                let gave_up = reason.clone().unwrap_or_else(|| "stream closed".to_string());
+                self.emit(ConnectionEvent::GaveUp { attempts: failures, reason: gave_up });
+                return match reason {
+                    // Clean end of stream without reconnection: finished, not failed
+                    None => Ok(()),
+                    Some(reason) => Err(format!("{} unreachable after {} attempt(s): {}", addr, failures, reason).into()),
+                };
+            }
+
+            let delay = policy.backoff(failures);
+            tracing::warn!(%addr, ?delay, attempt = failures, max = policy.max_retries, "reconnecting");
+            self.emit(ConnectionEvent::Reconnecting { attempt: failures, delay });
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// One connection: negotiate, then render frames until the stream ends
+    fn run_session(&mut self, addr: &str, frames: &mut u64) -> Result<(), FormatError> {
+        tracing::info!(%addr, "connecting");
+        let mut stream = TcpStream::connect(addr)?;
+
+        // Agree on a pixel format; every frame then carries width/height/stride/format
+        let format = pixel_format::negotiate(&mut stream, &PixelFormat::ALL)?;
+        tracing::info!(format = format.name(), "pixel format negotiated");
+        self.emit(ConnectionEvent::Connected { addr: addr.to_string(), format });
+
+        let result = self.render_frames(&mut stream, frames);
+        let reason = match &result {
+            Ok(()) => "stream closed".to_string(),
+            Err(e) => e.to_string(),
+        };
+        tracing::warn!(%addr, %reason, "disconnected");
+        self.emit(ConnectionEvent::Disconnected { addr: addr.to_string(), reason });
+        result
+    }
+
+    fn render_frames(&mut self, stream: &mut TcpStream, frames: &mut u64) -> Result<(), FormatError> {
+        let level = self.config.resource_limits.scope_level;
+        let mut frame = Vec::new();
         if level == 0 {
             // NULL_EXCEPTION: Raw stream mode - no memory partitioning
-            // Data is passed directly to renderer in 4KB windows
-            let mut raw_buffer = [0u8; 4096]; 
-            loop {
-                match stream.read(&mut raw_buffer) {
-                    Ok(0) => break,
-                    Ok(n) => self.execute_raw_stream(&raw_buffer[..n]),
-                    Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
-                    Err(e) => return Err(Box::new(e)),
-                }
+            // Each frame is passed directly to the renderer
+            while let Some(header) = pixel_format::read_frame(stream, &mut frame)? {
+                *frames += 1;
+                let arrived = Instant::now();
+                self.execute_raw_stream(&header, &frame);
+                metrics::record_frame_latency(arrived.elapsed());
             }
         } else {
             // Mathematical partitioning mode
-            // Data fills cells and is processed synchronously
-            loop {
-                for i in 0..self.memory.cell_count {
-                    let cell = self.memory.get_cell_mut(i);
-                    match stream.read_exact(cell) {
-                        Ok(_) => self.execute_renderer(i),
-                        Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
-                        Err(e) => return Err(Box::new(e)),
+            // Frames are staged in the section cells and processed synchronously
+            while let Some(header) = pixel_format::read_frame(stream, &mut frame)? {
+                *frames += 1;
+                let arrived = Instant::now();
+                match self.memory.stage(&frame) {
+                    Some(len) => {
+                        self.execute_renderer(&header, len);
+                        metrics::record_frame_latency(arrived.elapsed());
                     }
+                    None => tracing::warn!(
+                        width = header.width,
+                        height = header.height,
+                        bytes = frame.len(),
+                        "frame exceeds section memory, dropped"
+                    ),
                 }
             }
         }
         Ok(())
     }
 
-    fn execute_renderer(&self, cell_index: usize) {
-        let cell_data = self.memory.get_cell(cell_index);
-        self.dispatch_to_hardware(cell_data);
+    fn execute_renderer(&self, header: &FrameHeader, staged_len: usize) {
+        let staged = &self.memory.raw_storage[..staged_len];
+        self.dispatch_to_hardware(header, staged);
     }
 
-    fn execute_raw_stream(&self, raw_data: &[u8]) {
-        self.dispatch_to_hardware(raw_data);
+    fn execute_raw_stream(&self, header: &FrameHeader, raw_data: &[u8]) {
+        self.dispatch_to_hardware(header, raw_data);
     }
 
-    fn dispatch_to_hardware(&self, data: &[u8]) {
+    fn dispatch_to_hardware(&self, header: &FrameHeader, data: &[u8]) {
+        // Renderers take packed BGRA; anything else is converted first.
+        // Locked session: the renderer presents black instead of the stream
+        let frame = FrameHeader::bgra(header.width, header.height);
+        let converted;
+        let data = if SESSION_LOCKED.load(Ordering::SeqCst) {
+            converted = vec![0u8; frame.width as usize * frame.height as usize * 4];
+            &converted
+        } else if *header == frame {
+            data
+        } else {
+            converted = pixel_format::to_bgra(header, data);
+            &converted
+        };
+
         match self.config.resource_limits.renderer.as_str() {
             #[cfg(feature = "glx")]
-            "glx_renderer" => self.run_glx(data),
+            "glx_renderer" => self.run_glx(&frame, data),
             
             #[cfg(feature = "intel-uhd")]
             "renderer_iuhd" | "intel_uhd" => self.run_iuhd(data),
             
             #[cfg(feature = "opencl-gpu")]
             "renderer_opencl" | "opencl" => self.run_opencl(data),
+
+            #[cfg(feature = "vulkan")]
+            "renderer_vulkan" | "vulkan" => self.run_vulkan(&frame, data),
             
+            "renderer_soft" | "soft" => self.run_soft(&frame, data),
+
             "cpu_renderer" | "cpu" => self.run_cpu(data),
             
             _ => {
                 #[cfg(feature = "glx")]
-                self.run_glx(data);
+                self.run_glx(&frame, data);
                 
                 #[cfg(not(feature = "glx"))]
                 self.run_cpu(data);
@@ -156,13 +351,13 @@ impl UClient {
     // Renderer implementations
     
     #[cfg(feature = "glx")]
-    fn run_glx(&self, data: &[u8]) {
+    fn run_glx(&self, frame: &FrameHeader, data: &[u8]) {
         unsafe {
             // Direct VRAM texture update bypassing X11/Wayland
             gl::TexSubImage2D(
                 gl::TEXTURE_2D, 0, 0, 0,
-                1024, 1, // Width based on raw data
-                gl::RGBA, gl::UNSIGNED_BYTE,
+                frame.width as i32, frame.height as i32,
+                gl::BGRA, gl::UNSIGNED_BYTE,
                 data.as_ptr() as *const _
             );
         }
@@ -170,8 +365,8 @@ impl UClient {
 
     #[cfg(not(feature = "glx"))]
     #[allow(dead_code)]
-    fn run_glx(&self, _data: &[u8]) {
-        eprintln!("⚠️  GLX renderer not available - build with 'glx' feature");
+    fn run_glx(&self, _frame: &FrameHeader, _data: &[u8]) {
+        tracing::warn!("GLX renderer not available - build with 'glx' feature");
     }
 
     #[cfg(feature = "intel-uhd")]
@@ -187,25 +382,33 @@ impl UClient {
     #[cfg(not(feature = "intel-uhd"))]
     #[allow(dead_code)]
     fn run_iuhd(&self, _data: &[u8]) {
-        eprintln!("⚠️  Intel UHD renderer not available - build with 'intel-uhd' feature");
+        tracing::warn!("Intel UHD renderer not available - build with 'intel-uhd' feature");
     }
 
     #[cfg(feature = "opencl-gpu")]
     #[allow(dead_code)]
     fn run_opencl(&self, data: &[u8]) {
         // GPGPU: Zero-copy host pointer mapping
+        let upload = |context: &Context| {
+            let _buffer = unsafe {
+                Buffer::<u8>::create(
+                    context,
+                    CL_MEM_READ_ONLY | CL_MEM_COPY_HOST_PTR,
+                    data.len(),
+                    data.as_ptr() as *mut _,
+                ).ok()
+            };
+        };
+
+        if let Some(context) = &self.cl_context {
+            upload(context);
+            return;
+        }
         if let Ok(devices) = get_all_devices(CL_DEVICE_TYPE_GPU) {
             if let Some(device_id) = devices.first() {
                 let device = Device::new(*device_id);
                 if let Ok(context) = Context::from_device(&device) {
-                    let _buffer = unsafe {
-                        Buffer::<u8>::create(
-                            &context,
-                            CL_MEM_READ_ONLY | CL_MEM_COPY_HOST_PTR,
-                            data.len(),
-                            data.as_ptr() as *mut _,
-                        ).ok()
-                    };
+                    upload(&context);
                 }
             }
         }
@@ -214,7 +417,41 @@ impl UClient {
     #[cfg(not(feature = "opencl-gpu"))]
     #[allow(dead_code)]
     fn run_opencl(&self, _data: &[u8]) {
-        eprintln!("⚠️  OpenCL renderer not available - build with 'opencl-gpu' feature");
+        tracing::warn!("OpenCL renderer not available - build with 'opencl-gpu' feature");
+    }
+
+    #[cfg(feature = "vulkan")]
+    fn run_vulkan(&self, frame: &FrameHeader, data: &[u8]) {
+        let renderer = self.vulkan.get_or_init(|| {
+            let Some(target) = self.vulkan_target else {
+                tracing::warn!("Vulkan renderer has no window to present to, using CPU");
+                return None;
+            };
+            match VulkanRenderer::new(target) {
+                Ok(renderer) => Some(Mutex::new(renderer)),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Vulkan renderer unavailable, using CPU");
+                    None
+                }
+            }
+        });
+
+        match renderer {
+            Some(renderer) => {
+                if let Err(e) = renderer.lock().unwrap().present(frame, data, self.memory.cell_size) {
+                    tracing::warn!(error = %e, "Vulkan present failed");
+                }
+            }
+            None => self.run_cpu(data),
+        }
+    }
+
+    fn run_soft(&self, frame: &FrameHeader, data: &[u8]) {
+        let mut soft = self.soft.lock().unwrap();
+        let renderer = soft.get_or_insert_with(|| SoftRenderer::new(frame.width, frame.height));
+        if let Err(e) = renderer.render(frame, data) {
+            tracing::warn!(error = %e, "soft renderer failed");
+        }
     }
 
     fn run_cpu(&self, data: &[u8]) {
@@ -264,4 +501,52 @@ mod tests {
         assert!(cells > 0);
         assert_eq!(cell_size, 1024 * 1024);
     }
+
+    #[test]
+    fn test_reconnect_backoff() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            // 1st connection: one frame, then a clean close
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut hello = [0u8; 6 + PixelFormat::ALL.len()];
+            conn.read_exact(&mut hello).unwrap();
+            conn.write_all(&pixel_format::encode_accept(Some(PixelFormat::Rgb888))).unwrap();
+            let header = FrameHeader { width: 2, height: 2, stride: 6, format: PixelFormat::Rgb888 };
+            conn.write_all(&pixel_format::encode_frame(&header, &[0x7f; 12])).unwrap();
+            drop(conn);
+            // 2nd connection: dropped before negotiating; then nothing listens any more
+            drop(listener.accept().unwrap());
+        });
+
+        let parser = ConfigParser::new(None);
+        let mut config = parser.parse(&format!(
+            "protocol_def : http://127.0.0.1:{}\nreconnect_max_retries : 2\nreconnect_backoff_ms : 1\n", port
+        )).unwrap();
+        config.resource_limits.renderer = "cpu".to_string();
+        assert_eq!(config.uri_handling.reconnect.max_retries, 2);
+
+        let mut client = UClient::new(config);
+        let events = client.subscribe();
+        let started = std::thread::spawn(move || client.start_engine().map_err(|e| e.to_string()));
+        server.join().unwrap();
+        assert!(started.join().unwrap().is_err());
+
+        let events: Vec<_> = events.try_iter().collect();
+        assert!(matches!(&events[0], ConnectionEvent::Connected { format: PixelFormat::Rgb888, .. }));
+        assert!(matches!(&events[1], ConnectionEvent::Disconnected { reason, .. } if reason == "stream closed"));
+        // The first session delivered a frame, so the 2nd connection is retry 1 again
+        assert_eq!(events[2], ConnectionEvent::Reconnecting { attempt: 1, delay: Duration::from_millis(1) });
+        assert_eq!(events[3], ConnectionEvent::Reconnecting { attempt: 2, delay: Duration::from_millis(2) });
+        assert!(matches!(&events[4], ConnectionEvent::GaveUp { attempts: 3, .. }));
+        assert_eq!(events.len(), 5);
+
+        let policy = ReconnectPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(3), Duration::from_millis(2000));
+        assert_eq!(policy.backoff(64), Duration::from_millis(30_000));
+    }
 }
@@ -0,0 +1,525 @@
+// WASMA - Wayland xdg-shell backend
+// Presents WASMA-managed windows on a host Wayland compositor: every native
+// window gets a wl_surface + xdg_toplevel, kept in sync with the WindowHandler
+// (creation, title/app_id, geometry, state) and reporting compositor-side
+// resizes, maximize/fullscreen toggles, activation and close requests back.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::os::fd::{AsFd, AsRawFd, FromRawFd};
+use std::sync::Arc;
+
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_buffer, wl_compositor, wl_registry, wl_shm, wl_shm_pool, wl_surface};
+use wayland_client::{delegate_noop, Connection, Dispatch, EventQueue, QueueHandle};
+use wayland_protocols::xdg::shell::client::{xdg_surface, xdg_toplevel, xdg_wm_base};
+
+use crate::accessibility;
+use crate::window_handling::{BackendType, Window, WindowGeometry, WindowHandler, WindowState};
+
+/// How long one loop iteration waits for compositor events before re-syncing
+const POLL_TIMEOUT_MS: i32 = 50;
+
+/// Compositor-side view of a toplevel, decoded from xdg_toplevel.configure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ToplevelConfigure {
+    /// 0 = client decides
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    pub activated: bool,
+}
+
+impl ToplevelConfigure {
+    /// Decode the packed u32 state array of xdg_toplevel.configure
+    pub fn decode(width: i32, height: i32, states: &[u8]) -> Self {
+        let mut configure = ToplevelConfigure {
+            width: width.max(0) as u32,
+            height: height.max(0) as u32,
+            ..Default::default()
+        };
+        for raw in states.chunks_exact(4) {
+            let value = u32::from_ne_bytes([raw[0], raw[1], raw[2], raw[3]]);
+            match xdg_toplevel::State::try_from(value) {
+                Ok(xdg_toplevel::State::Maximized) => configure.maximized = true,
+                Ok(xdg_toplevel::State::Fullscreen) => configure.fullscreen = true,
+                Ok(xdg_toplevel::State::Activated) => configure.activated = true,
+                _ => {}
+            }
+        }
+        configure
+    }
+
+    /// WASMA state implied by the compositor; None keeps the current one
+    /// (xdg-shell never reports minimization back)
+    pub fn window_state(&self, current: &WindowState) -> Option<WindowState> {
+        let state = if self.fullscreen {
+            WindowState::Fullscreen
+        } else if self.maximized {
+            WindowState::Maximized
+        } else if matches!(current, WindowState::Maximized | WindowState::Fullscreen) {
+            WindowState::Normal
+        } else {
+            return None;
+        };
+        (state != *current).then_some(state)
+    }
+}
+
+/// Windows that get a toplevel: WASMA's own windows, not ones that already
+/// belong to a Wayland/X11 client, and only while they are shown
+pub fn is_presentable(window: &Window) -> bool {
+    window.backend_type == BackendType::Native
+        && window.visible
+        && window.state != WindowState::Hidden
+}
+
+/// Surface objects and last applied values of one mirrored window
+struct ManagedSurface {
+    surface: wl_surface::WlSurface,
+    xdg_surface: xdg_surface::XdgSurface,
+    toplevel: xdg_toplevel::XdgToplevel,
+    title: String,
+    app_id: String,
+    state: WindowState,
+    geometry: WindowGeometry,
+    /// Buffer size currently attached
+    buffer_size: Option<(u32, u32)>,
+    /// Latest xdg_toplevel.configure, applied on xdg_surface.configure
+    pending: Option<ToplevelConfigure>,
+    configured: bool,
+}
+
+impl ManagedSurface {
+    fn destroy(self) {
+        self.toplevel.destroy();
+        self.xdg_surface.destroy();
+        self.surface.destroy();
+    }
+}
+
+/// Dispatch state shared by all wayland-client callbacks
+struct BackendState {
+    handler: Arc<WindowHandler>,
+    compositor: wl_compositor::WlCompositor,
+    shm: wl_shm::WlShm,
+    wm_base: xdg_wm_base::XdgWmBase,
+    surfaces: HashMap<u64, ManagedSurface>,
+    /// Window closed by the compositor; skipped until the handler drops it
+    closed: Vec<u64>,
+}
+
+/// Wayland client backend
+pub struct WaylandBackend {
+    queue: EventQueue<BackendState>,
+    state: BackendState,
+}
+
+impl WaylandBackend {
+    /// Connect to $WAYLAND_DISPLAY and bind wl_compositor, wl_shm and xdg_wm_base
+    pub fn connect(handler: Arc<WindowHandler>) -> Result<Self, String> {
+        let conn = Connection::connect_to_env()
+            .map_err(|e| format!("Wayland connection failed: {}", e))?;
+        let (globals, queue) = registry_queue_init::<BackendState>(&conn)
+            .map_err(|e| format!("Wayland registry failed: {}", e))?;
+        let qh = queue.handle();
+
+        let compositor = globals.bind(&qh, 4..=6, ())
+            .map_err(|e| format!("wl_compositor unavailable: {}", e))?;
+        let shm = globals.bind(&qh, 1..=1, ())
+            .map_err(|e| format!("wl_shm unavailable: {}", e))?;
+        let wm_base = globals.bind(&qh, 1..=5, ())
+            .map_err(|e| format!("xdg_wm_base unavailable: {}", e))?;
+
+        Ok(Self {
+            queue,
+            state: BackendState {
+                handler,
+                compositor,
+                shm,
+                wm_base,
+                surfaces: HashMap::new(),
+                closed: Vec::new(),
+            },
+        })
+    }
+
+    /// Connect and run the backend on its own thread
+    pub fn spawn(handler: Arc<WindowHandler>) -> Result<std::thread::JoinHandle<()>, String> {
+        let mut backend = Self::connect(handler)?;
+        Ok(std::thread::spawn(move || {
+            if let Err(e) = backend.run() {
+                eprintln!("❌ Wayland backend stopped: {}", e);
+            }
+        }))
+    }
+
+    /// Number of windows currently shown as toplevels
+    pub fn surface_count(&self) -> usize {
+        self.state.surfaces.len()
+    }
+
+    /// Mirror handler changes and dispatch compositor events until the connection drops
+    pub fn run(&mut self) -> Result<(), String> {
+        println!("🪟 Wayland backend presenting managed windows");
+        loop {
+            self.sync()?;
+            self.dispatch(POLL_TIMEOUT_MS)?;
+        }
+    }
+
+    /// Create, update and destroy toplevels so they match the handler
+    pub fn sync(&mut self) -> Result<(), String> {
+        let qh = self.queue.handle();
+        let state = &mut self.state;
+        let windows: HashMap<u64, Window> = state.handler.list_windows()
+            .into_iter()
+            .filter(is_presentable)
+            .map(|w| (w.id, w))
+            .collect();
+
+        state.closed.retain(|id| windows.contains_key(id));
+
+        let gone: Vec<u64> = state.surfaces.keys()
+            .filter(|id| !windows.contains_key(id))
+            .copied()
+            .collect();
+        for id in gone {
+            if let Some(surface) = state.surfaces.remove(&id) {
+                surface.destroy();
+            }
+        }
+
+        for window in windows.values() {
+            if state.closed.contains(&window.id) {
+                continue;
+            }
+            if state.surfaces.contains_key(&window.id) {
+                state.update_surface(window, &qh)?;
+            } else {
+                state.create_surface(window, &qh);
+            }
+        }
+
+        self.queue.flush().map_err(|e| format!("Wayland flush failed: {}", e))
+    }
+
+    /// Wait up to `timeout_ms` for events and dispatch them
+    pub fn dispatch(&mut self, timeout_ms: i32) -> Result<usize, String> {
+        self.queue.flush().map_err(|e| format!("Wayland flush failed: {}", e))?;
+
+        if let Some(guard) = self.queue.prepare_read() {
+            let mut fds = [libc::pollfd {
+                fd: guard.connection_fd().as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            }];
+            let ready = unsafe { libc::poll(fds.as_mut_ptr(), 1, timeout_ms) };
+            if ready > 0 {
+                match guard.read() {
+                    Ok(_) => {}
+                    Err(wayland_client::backend::WaylandError::Io(e))
+                        if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                    Err(e) => return Err(format!("Wayland read failed: {}", e)),
+                }
+            }
+        }
+
+        self.queue.dispatch_pending(&mut self.state)
+            .map_err(|e| format!("Wayland dispatch failed: {}", e))
+    }
+}
+
+impl Drop for WaylandBackend {
+    fn drop(&mut self) {
+        for (_, surface) in self.state.surfaces.drain() {
+            surface.destroy();
+        }
+        let _ = self.queue.flush();
+    }
+}
+
+impl BackendState {
+    fn create_surface(&mut self, window: &Window, qh: &QueueHandle<Self>) {
+        let surface = self.compositor.create_surface(qh, ());
+        let xdg_surface = self.wm_base.get_xdg_surface(&surface, qh, window.id);
+        let toplevel = xdg_surface.get_toplevel(qh, window.id);
+
+        toplevel.set_title(window.title.clone());
+        toplevel.set_app_id(window.app_id.clone());
+        apply_state(&toplevel, &window.state);
+
+        // Initial commit without a buffer; the first configure maps the surface
+        surface.commit();
+
+        self.surfaces.insert(window.id, ManagedSurface {
+            surface,
+            xdg_surface,
+            toplevel,
+            title: window.title.clone(),
+            app_id: window.app_id.clone(),
+            state: window.state.clone(),
+            geometry: window.geometry,
+            buffer_size: None,
+            pending: None,
+            configured: false,
+        });
+        println!("🪟 Wayland toplevel created for window {}", window.id);
+    }
+
+    fn update_surface(&mut self, window: &Window, qh: &QueueHandle<Self>) -> Result<(), String> {
+        let Some(managed) = self.surfaces.get_mut(&window.id) else { return Ok(()) };
+
+        if managed.title != window.title {
+            managed.toplevel.set_title(window.title.clone());
+            managed.title = window.title.clone();
+        }
+        if managed.app_id != window.app_id {
+            managed.toplevel.set_app_id(window.app_id.clone());
+            managed.app_id = window.app_id.clone();
+        }
+        if managed.state != window.state {
+            apply_state(&managed.toplevel, &window.state);
+            managed.state = window.state.clone();
+        }
+        if managed.geometry != window.geometry {
+            managed.geometry = window.geometry;
+        }
+
+        let size = (window.geometry.width.max(1), window.geometry.height.max(1));
+        if managed.configured && managed.buffer_size != Some(size) {
+            self.present(window.id, qh)?;
+        }
+        Ok(())
+    }
+
+    /// Attach a freshly filled buffer at the window's current size and commit
+    fn present(&mut self, id: u64, qh: &QueueHandle<Self>) -> Result<(), String> {
+        let Some(managed) = self.surfaces.get(&id) else { return Ok(()) };
+        let (width, height) = (managed.geometry.width.max(1), managed.geometry.height.max(1));
+
+        let buffer = create_buffer(&self.shm, qh, width, height)?;
+        let managed = self.surfaces.get_mut(&id).unwrap();
+        managed.xdg_surface.set_window_geometry(0, 0, width as i32, height as i32);
+        managed.surface.attach(Some(&buffer), 0, 0);
+        managed.surface.damage_buffer(0, 0, width as i32, height as i32);
+        managed.surface.commit();
+        managed.buffer_size = Some((width, height));
+        Ok(())
+    }
+
+    /// Apply the last toplevel configure to the handler, then draw
+    fn configure(&mut self, id: u64, qh: &QueueHandle<Self>) {
+        let Some(managed) = self.surfaces.get_mut(&id) else { return };
+        managed.configured = true;
+
+        if let Some(configure) = managed.pending.take() {
+            if configure.width > 0 && configure.height > 0 {
+                managed.geometry.width = configure.width;
+                managed.geometry.height = configure.height;
+                let _ = self.handler.set_geometry(id, managed.geometry);
+            }
+            if let Some(state) = configure.window_state(&managed.state) {
+                managed.state = state.clone();
+                let _ = self.handler.set_window_state(id, state);
+            }
+            if configure.activated && self.handler.get_focused_window() != Some(id) {
+                let _ = self.handler.focus_window(id);
+            }
+        }
+
+        if let Err(e) = self.present(id, qh) {
+            eprintln!("⚠️  Window {} could not be drawn: {}", id, e);
+        }
+    }
+}
+
+fn apply_state(toplevel: &xdg_toplevel::XdgToplevel, state: &WindowState) {
+    match state {
+        WindowState::Normal => {
+            toplevel.unset_fullscreen();
+            toplevel.unset_maximized();
+        }
+        WindowState::Maximized => {
+            toplevel.unset_fullscreen();
+            toplevel.set_maximized();
+        }
+        WindowState::Fullscreen => toplevel.set_fullscreen(None),
+        WindowState::Minimized => toplevel.set_minimized(),
+        // Hidden windows have no toplevel at all
+        WindowState::Hidden => {}
+    }
+}
+
+/// ARGB8888 shm buffer filled with the theme's surface colour
+fn create_buffer(
+    shm: &wl_shm::WlShm,
+    qh: &QueueHandle<BackendState>,
+    width: u32,
+    height: u32,
+) -> Result<wl_buffer::WlBuffer, String> {
+    let surface = accessibility::tokens().surface;
+    let pixels: Vec<u8> = [surface.b, surface.g, surface.r, 0xff].repeat(width as usize * height as usize);
+    shm_buffer(shm, qh, width, height, &pixels, wl_shm::Format::Argb8888)
+}
+
+/// shm buffer holding `pixels` (packed rows, 4 bytes per pixel, little-endian `format`)
+pub(crate) fn shm_buffer<D>(
+    shm: &wl_shm::WlShm,
+    qh: &QueueHandle<D>,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+    format: wl_shm::Format,
+) -> Result<wl_buffer::WlBuffer, String>
+where
+    D: Dispatch<wl_shm_pool::WlShmPool, ()> + Dispatch<wl_buffer::WlBuffer, ()> + 'static,
+{
+    let stride = width as usize * 4;
+    let size = stride * height as usize;
+
+    let name = c"wasma-surface";
+    let fd = unsafe { libc::memfd_create(name.as_ptr(), libc::MFD_CLOEXEC) };
+    if fd < 0 {
+        return Err(format!("memfd_create failed: {}", std::io::Error::last_os_error()));
+    }
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    file.write_all(&pixels[..size])
+        .map_err(|e| format!("Buffer write failed: {}", e))?;
+
+    let pool = shm.create_pool(file.as_fd(), size as i32, qh, ());
+    let buffer = pool.create_buffer(
+        0,
+        width as i32,
+        height as i32,
+        stride as i32,
+        format,
+        qh,
+        (),
+    );
+    pool.destroy();
+    Ok(buffer)
+}
+
+// ============================================================================
+// Event dispatch
+// ============================================================================
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for BackendState {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_noop!(BackendState: wl_compositor::WlCompositor);
+delegate_noop!(BackendState: wl_shm_pool::WlShmPool);
+delegate_noop!(BackendState: ignore wl_shm::WlShm);
+delegate_noop!(BackendState: ignore wl_surface::WlSurface);
+
+impl Dispatch<wl_buffer::WlBuffer, ()> for BackendState {
+    fn event(
+        _: &mut Self,
+        buffer: &wl_buffer::WlBuffer,
+        event: wl_buffer::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Every present attaches a new buffer; the old one goes once released
+        if let wl_buffer::Event::Release = event {
+            buffer.destroy();
+        }
+    }
+}
+
+impl Dispatch<xdg_wm_base::XdgWmBase, ()> for BackendState {
+    fn event(
+        _: &mut Self,
+        wm_base: &xdg_wm_base::XdgWmBase,
+        event: xdg_wm_base::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_wm_base::Event::Ping { serial } = event {
+            wm_base.pong(serial);
+        }
+    }
+}
+
+impl Dispatch<xdg_surface::XdgSurface, u64> for BackendState {
+    fn event(
+        state: &mut Self,
+        xdg_surface: &xdg_surface::XdgSurface,
+        event: xdg_surface::Event,
+        window_id: &u64,
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_surface::Event::Configure { serial } = event {
+            xdg_surface.ack_configure(serial);
+            state.configure(*window_id, qh);
+        }
+    }
+}
+
+impl Dispatch<xdg_toplevel::XdgToplevel, u64> for BackendState {
+    fn event(
+        state: &mut Self,
+        _: &xdg_toplevel::XdgToplevel,
+        event: xdg_toplevel::Event,
+        window_id: &u64,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            xdg_toplevel::Event::Configure { width, height, states } => {
+                if let Some(managed) = state.surfaces.get_mut(window_id) {
+                    managed.pending = Some(ToplevelConfigure::decode(width, height, &states));
+                }
+            }
+            xdg_toplevel::Event::Close => {
+                println!("🪟 Compositor closed window {}", window_id);
+                if let Some(surface) = state.surfaces.remove(window_id) {
+                    surface.destroy();
+                }
+                state.closed.push(*window_id);
+                if let Err(e) = state.handler.close_window(*window_id) {
+                    eprintln!("⚠️  Window {} could not be closed: {}", window_id, e);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toplevel_configure_decode() {
+        let states: Vec<u8> = [xdg_toplevel::State::Maximized, xdg_toplevel::State::Activated]
+            .iter()
+            .flat_map(|s| (*s as u32).to_ne_bytes())
+            .collect();
+        let configure = ToplevelConfigure::decode(800, 600, &states);
+        assert_eq!((configure.width, configure.height), (800, 600));
+        assert!(configure.maximized && configure.activated && !configure.fullscreen);
+        assert_eq!(configure.window_state(&WindowState::Normal), Some(WindowState::Maximized));
+        assert_eq!(configure.window_state(&WindowState::Maximized), None);
+
+        // Compositor dropped maximize; minimized windows are left alone
+        let floating = ToplevelConfigure::decode(0, 0, &[]);
+        assert_eq!(floating.window_state(&WindowState::Maximized), Some(WindowState::Normal));
+        assert_eq!(floating.window_state(&WindowState::Minimized), None);
+    }
+}
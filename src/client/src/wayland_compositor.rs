@@ -0,0 +1,817 @@
+// WASMA - Wayland Compositor Front-End
+// xdg-shell server mode: native Wayland clients connect directly to WASMA,
+// every xdg_toplevel becomes a WASMA Window with its own assignment and
+// buffer commits are checked against the assignment's RAM limit.
+// Built on wayland-server directly rather than smithay: every smithay release
+// on the wayland-server 0.31 stack (shared with wayland-client/-protocols and
+// xwayland.rs) needs rustc 1.80, above this crate's rust-version, and the
+// front-end only needs wl_compositor, wl_shm and xdg-shell
+
+use std::collections::HashMap;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use wayland_protocols::xdg::shell::server::{
+    xdg_popup, xdg_positioner, xdg_surface, xdg_toplevel, xdg_wm_base,
+};
+use wayland_server::backend::{ClientId, ObjectId};
+use wayland_server::protocol::{
+    wl_buffer, wl_callback, wl_compositor, wl_region, wl_shm, wl_shm_pool, wl_surface,
+};
+use wayland_server::{
+    Client, DataInit, Dispatch, Display, DisplayHandle, GlobalDispatch, ListeningSocket, New,
+    Resource, WEnum,
+};
+use wbackend::ResourceMode;
+
+use crate::parser::WasmaConfig;
+use crate::pixel_format::{FrameHeader, PixelFormat};
+use crate::thumbnails;
+use crate::window_handling::{BackendType, WindowGeometry, WindowHandler};
+use crate::window_multitary::{Viewport, WindowMultitary};
+#[cfg(feature = "xwayland")]
+use crate::xwayland::XWayland;
+
+/// Wayland toplevels get stream ids in WAYLAND_STREAM_BASE..WAYLAND_STREAM_END,
+/// between protocol streams and the X11 adoption range
+pub const WAYLAND_STREAM_BASE: u8 = 64;
+pub const WAYLAND_STREAM_END: u8 = 128;
+
+/// Resource cycle cadence of the compositor loop
+const CYCLE_INTERVAL: Duration = Duration::from_secs(1);
+
+// ============================================================================
+// SHM BUFFERS
+// ============================================================================
+
+/// Client-side shared memory pool (fd kept for the renderer side)
+#[derive(Debug)]
+pub struct ShmPool {
+    pub fd: Arc<OwnedFd>,
+    pub size: std::sync::Mutex<i32>,
+}
+
+/// Metadata of a wl_buffer created from a wl_shm_pool
+#[derive(Debug, Clone, Copy)]
+pub struct ShmBuffer {
+    pub offset: i32,
+    pub width: i32,
+    pub height: i32,
+    pub stride: i32,
+    pub format: u32,
+}
+
+impl ShmBuffer {
+    /// Bytes occupied by the buffer inside its pool
+    pub fn byte_size(&self) -> u64 {
+        (self.stride.max(0) as u64) * (self.height.max(0) as u64)
+    }
+}
+
+// ============================================================================
+// COMPOSITOR STATE
+// ============================================================================
+
+/// A Wayland xdg_toplevel mapped to a WASMA Window
+#[derive(Debug, Clone)]
+pub struct WaylandToplevel {
+    pub window_id: u64,
+    pub stream_id: u8,
+    pub surface: wl_surface::WlSurface,
+    pub xdg_surface: xdg_surface::XdgSurface,
+    pub toplevel: xdg_toplevel::XdgToplevel,
+    pub title: String,
+    pub app_id: String,
+    pub configured: bool,
+}
+
+/// Per-surface pending/committed state
+#[derive(Debug, Default)]
+struct SurfaceState {
+    surface: Option<wl_surface::WlSurface>,
+    pending_buffer: Option<wl_buffer::WlBuffer>,
+    pending_callbacks: Vec<wl_callback::WlCallback>,
+    toplevel: Option<ObjectId>,
+}
+
+/// Shared state passed to every dispatch handler
+pub struct CompositorState {
+    handler: Arc<WindowHandler>,
+    multitary: WindowMultitary,
+    resource_mode: ResourceMode,
+    surfaces: HashMap<ObjectId, SurfaceState>,
+    toplevels: HashMap<ObjectId, WaylandToplevel>,
+    // Pool fd of every live wl_buffer, to copy thumbnail frames out of
+    buffer_pools: HashMap<ObjectId, Arc<OwnedFd>>,
+    // Last thumbnail copy per window
+    thumbnail_copies: HashMap<u64, Instant>,
+    next_serial: u32,
+    started: Instant,
+}
+
+impl CompositorState {
+    fn new(config: WasmaConfig, handler: Arc<WindowHandler>, resource_mode: ResourceMode, width: u32, height: u32) -> Self {
+        Self {
+            handler,
+            multitary: WindowMultitary::new(config, width, height),
+            resource_mode,
+            surfaces: HashMap::new(),
+            toplevels: HashMap::new(),
+            buffer_pools: HashMap::new(),
+            thumbnail_copies: HashMap::new(),
+            next_serial: 1,
+            started: Instant::now(),
+        }
+    }
+
+    fn serial(&mut self) -> u32 {
+        let serial = self.next_serial;
+        self.next_serial = self.next_serial.wrapping_add(1).max(1);
+        serial
+    }
+
+    fn allocate_stream_id(&self) -> Option<u8> {
+        let used: Vec<u8> = self.toplevels.values().map(|t| t.stream_id).collect();
+        (WAYLAND_STREAM_BASE..WAYLAND_STREAM_END).find(|id| !used.contains(id))
+    }
+
+    /// Register a new toplevel: WASMA Window + assignment + viewport
+    fn map_toplevel(
+        &mut self,
+        surface: wl_surface::WlSurface,
+        xdg_surface: xdg_surface::XdgSurface,
+        toplevel: xdg_toplevel::XdgToplevel,
+    ) -> Result<(), String> {
+        let stream_id = self.allocate_stream_id()
+            .ok_or_else(|| "No free stream slots for Wayland clients".to_string())?;
+
+        let window_id = self.handler.create_window(
+            "Wayland Client".to_string(),
+            "wayland.client".to_string(),
+            WindowGeometry { x: 0, y: 0, width: 800, height: 600 },
+            None,
+            self.resource_mode,
+        )?;
+        self.handler.set_backend_type(window_id, BackendType::Wayland)?;
+        // xdg-decoration is not advertised, so xdg-shell clients draw their own CSD
+        self.handler.set_server_decorations(window_id, false)?;
+
+        if let Some(state) = self.surfaces.get_mut(&surface.id()) {
+            state.toplevel = Some(toplevel.id());
+        }
+
+        self.toplevels.insert(toplevel.id(), WaylandToplevel {
+            window_id,
+            stream_id,
+            surface,
+            xdg_surface,
+            toplevel,
+            title: String::new(),
+            app_id: String::new(),
+            configured: false,
+        });
+        self.multitary.attach_stream(stream_id);
+        self.configure_all();
+
+        println!("🪟 Wayland toplevel mapped → Window {} | Stream {}", window_id, stream_id);
+        Ok(())
+    }
+
+    /// Drop a toplevel and close its WASMA Window
+    fn unmap_toplevel(&mut self, toplevel_id: &ObjectId) {
+        if let Some(top) = self.toplevels.remove(toplevel_id) {
+            if let Some(state) = self.surfaces.get_mut(&top.surface.id()) {
+                state.toplevel = None;
+            }
+            self.multitary.detach_stream(top.stream_id);
+            self.thumbnail_copies.remove(&top.window_id);
+            self.handler.close_window(top.window_id).ok();
+            self.configure_all();
+            println!("🗑️  Wayland toplevel unmapped (Window {})", top.window_id);
+        }
+    }
+
+    /// Send xdg configure with the multitary viewport size to every toplevel
+    fn configure_all(&mut self) {
+        let ids: Vec<ObjectId> = self.toplevels.keys().cloned().collect();
+        for id in ids {
+            let serial = self.serial();
+            let top = match self.toplevels.get_mut(&id) {
+                Some(t) => t,
+                None => continue,
+            };
+
+            let vp = match self.multitary.get_viewport_for_stream(top.stream_id) {
+                Some(vp) => vp.clone(),
+                None => continue,
+            };
+
+            top.toplevel.configure(vp.width as i32, vp.height as i32, Vec::new());
+            top.xdg_surface.configure(serial);
+
+            self.handler.set_geometry(top.window_id, WindowGeometry {
+                x: vp.x,
+                y: vp.y,
+                width: vp.width,
+                height: vp.height,
+            }).ok();
+        }
+    }
+
+    /// Buffer commit: enforce the assignment RAM limit, then fire frame callbacks
+    fn commit_surface(&mut self, surface: &wl_surface::WlSurface) {
+        let (buffer, callbacks, toplevel) = match self.surfaces.get_mut(&surface.id()) {
+            Some(s) => (s.pending_buffer.take(), std::mem::take(&mut s.pending_callbacks), s.toplevel.clone()),
+            None => return,
+        };
+
+        if let (Some(buffer), Some(top_id)) = (buffer.as_ref(), toplevel.as_ref()) {
+            let window_id = match self.toplevels.get(top_id) {
+                Some(top) => top.window_id,
+                None => return,
+            };
+
+            let bytes = buffer.data::<ShmBuffer>().map(|b| b.byte_size()).unwrap_or(0);
+
+            match self.handler.get_window_resource_usage(window_id) {
+                Ok(usage) if bytes > usage.ram_allocated_mb * 1024 * 1024 => {
+                    eprintln!(
+                        "⚠️  Window {} buffer ({} bytes) exceeds assignment RAM ({} MB) – closing",
+                        window_id, bytes, usage.ram_allocated_mb
+                    );
+                    self.request_close(top_id);
+                    return;
+                }
+                Ok(_) => self.copy_thumbnail_frame(window_id, buffer),
+                Err(e) => {
+                    eprintln!("⚠️  {} – closing Wayland client", e);
+                    self.request_close(top_id);
+                    return;
+                }
+            }
+        }
+
+        if let Some(buffer) = buffer {
+            buffer.release();
+        }
+
+        let time = self.started.elapsed().as_millis() as u32;
+        for callback in callbacks {
+            callback.done(time);
+        }
+    }
+
+    /// Hand the committed buffer to the window's thumbnail. The pixels are the
+    /// client's again once the buffer is released, so they are copied, at most
+    /// once per thumbnail refresh interval
+    fn copy_thumbnail_frame(&mut self, window_id: u64, buffer: &wl_buffer::WlBuffer) {
+        let now = Instant::now();
+        if self.thumbnail_copies.get(&window_id).is_some_and(|last| now - *last < thumbnails::THUMBNAIL_REFRESH_INTERVAL) {
+            return;
+        }
+        let (Some(shm), Some(fd)) = (buffer.data::<ShmBuffer>(), self.buffer_pools.get(&buffer.id())) else { return };
+        // Argb8888 (0) and Xrgb8888 (1): little-endian BGRA / BGRX
+        if shm.format > 1 || shm.width <= 0 || shm.height <= 0 || shm.stride < shm.width * 4 {
+            return;
+        }
+        let mut data = vec![0u8; shm.byte_size() as usize];
+        let read = unsafe { libc::pread(fd.as_raw_fd(), data.as_mut_ptr().cast(), data.len(), shm.offset as libc::off_t) };
+        if read != data.len() as isize {
+            return;
+        }
+        if shm.format == 1 {
+            for px in data.chunks_exact_mut(4) {
+                px[3] = 0xff;
+            }
+        }
+        let header = FrameHeader { width: shm.width as u32, height: shm.height as u32, stride: shm.stride as u32, format: PixelFormat::Bgra8888 };
+        self.handler.submit_frame(window_id, header, &data);
+        self.thumbnail_copies.insert(window_id, now);
+    }
+
+    fn request_close(&self, toplevel_id: &ObjectId) {
+        if let Some(top) = self.toplevels.get(toplevel_id) {
+            top.toplevel.close();
+        }
+    }
+
+    /// Run a resource cycle and close toplevels whose assignment lease expired
+    fn enforce_resources(&mut self) {
+        self.handler.run_resource_cycle();
+
+        let expired: Vec<ObjectId> = self.toplevels.iter()
+            .filter(|(_, top)| self.handler.get_window_resource_usage(top.window_id).is_err())
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in expired {
+            println!("⏱️  Assignment lease expired – closing Wayland toplevel");
+            self.request_close(&id);
+        }
+    }
+
+    /// Currently mapped Wayland toplevels
+    pub fn toplevels(&self) -> Vec<WaylandToplevel> {
+        self.toplevels.values().cloned().collect()
+    }
+
+    pub(crate) fn handler(&self) -> &Arc<WindowHandler> {
+        &self.handler
+    }
+
+    pub(crate) fn resource_mode(&self) -> ResourceMode {
+        self.resource_mode
+    }
+
+    /// Tile a non-xdg stream (e.g. an XWayland window) next to the toplevels
+    pub(crate) fn attach_stream(&mut self, stream_id: u8) {
+        self.multitary.attach_stream(stream_id);
+        self.configure_all();
+    }
+
+    pub(crate) fn detach_stream(&mut self, stream_id: u8) {
+        self.multitary.detach_stream(stream_id);
+        self.configure_all();
+    }
+
+    pub(crate) fn viewport_for_stream(&self, stream_id: u8) -> Option<Viewport> {
+        self.multitary.get_viewport_for_stream(stream_id).cloned()
+    }
+
+    /// Look up a surface by its protocol id within one client (WL_SURFACE_ID)
+    pub(crate) fn find_surface(&self, client: &ClientId, protocol_id: u32) -> Option<wl_surface::WlSurface> {
+        self.surfaces.values()
+            .filter_map(|s| s.surface.as_ref())
+            .find(|surface| {
+                surface.id().protocol_id() == protocol_id
+                    && surface.client().map(|c| &c.id() == client).unwrap_or(false)
+            })
+            .cloned()
+    }
+}
+
+// ============================================================================
+// COMPOSITOR
+// ============================================================================
+
+/// Wayland compositor front-end (xdg-shell server)
+pub struct WaylandCompositor {
+    display: Display<CompositorState>,
+    socket: ListeningSocket,
+    state: CompositorState,
+    #[cfg(feature = "xwayland")]
+    xwayland: Option<XWayland>,
+}
+
+impl WaylandCompositor {
+    /// Create the display, advertise globals and bind a wayland-N socket
+    pub fn new(
+        config: WasmaConfig,
+        handler: Arc<WindowHandler>,
+        resource_mode: ResourceMode,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, String> {
+        let display: Display<CompositorState> = Display::new()
+            .map_err(|e| format!("Wayland display init failed: {}", e))?;
+
+        let dh = display.handle();
+        dh.create_global::<CompositorState, wl_compositor::WlCompositor, _>(5, ());
+        dh.create_global::<CompositorState, wl_shm::WlShm, _>(1, ());
+        dh.create_global::<CompositorState, xdg_wm_base::XdgWmBase, _>(3, ());
+
+        let socket = ListeningSocket::bind_auto("wayland", 1..33)
+            .map_err(|e| format!("Wayland socket bind failed: {}", e))?;
+
+        Ok(Self {
+            display,
+            socket,
+            state: CompositorState::new(config, handler, resource_mode, width, height),
+            #[cfg(feature = "xwayland")]
+            xwayland: None,
+        })
+    }
+
+    /// Spawn Xwayland so X11-only applications get WASMA windows too;
+    /// returns the DISPLAY value for X11 clients
+    #[cfg(feature = "xwayland")]
+    pub fn start_xwayland(&mut self) -> Result<String, String> {
+        if let Some(xwayland) = &self.xwayland {
+            return Ok(xwayland.display_name());
+        }
+
+        let xwayland = XWayland::spawn(&mut self.display)?;
+        let display_name = xwayland.display_name();
+        self.xwayland = Some(xwayland);
+        Ok(display_name)
+    }
+
+    /// Socket name clients should use as WAYLAND_DISPLAY
+    pub fn socket_name(&self) -> String {
+        self.socket.socket_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Compositor state (mapped toplevels etc.)
+    pub fn state(&self) -> &CompositorState {
+        &self.state
+    }
+
+    /// Accept clients and dispatch requests once
+    pub fn dispatch(&mut self) -> Result<(), String> {
+        while let Some(stream) = self.socket.accept().map_err(|e| e.to_string())? {
+            self.display.handle()
+                .insert_client(stream, Arc::new(()))
+                .map_err(|e| format!("Client insert failed: {}", e))?;
+        }
+
+        self.display.dispatch_clients(&mut self.state).map_err(|e| e.to_string())?;
+
+        #[cfg(feature = "xwayland")]
+        if let Some(xwayland) = self.xwayland.as_mut() {
+            xwayland.dispatch(&mut self.state)?;
+        }
+
+        self.display.flush_clients().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Compositor main loop
+    pub fn run(&mut self) -> Result<(), String> {
+        println!("🌊 Wayland compositor listening on {}", self.socket_name());
+
+        let mut last_cycle = Instant::now();
+        loop {
+            self.dispatch()?;
+
+            if last_cycle.elapsed() >= CYCLE_INTERVAL {
+                self.state.enforce_resources();
+
+                #[cfg(feature = "xwayland")]
+                if let Some(xwayland) = self.xwayland.as_mut() {
+                    xwayland.enforce_resources(&self.state);
+                }
+
+                self.display.flush_clients().map_err(|e| e.to_string())?;
+                last_cycle = Instant::now();
+            }
+
+            std::thread::sleep(Duration::from_millis(4));
+        }
+    }
+}
+
+// ============================================================================
+// wl_compositor / wl_surface / wl_region / wl_callback
+// ============================================================================
+
+impl GlobalDispatch<wl_compositor::WlCompositor, ()> for CompositorState {
+    fn bind(
+        _state: &mut Self,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<wl_compositor::WlCompositor>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl Dispatch<wl_compositor::WlCompositor, ()> for CompositorState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _resource: &wl_compositor::WlCompositor,
+        request: wl_compositor::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            wl_compositor::Request::CreateSurface { id } => {
+                let surface = data_init.init(id, ());
+                state.surfaces.insert(surface.id(), SurfaceState {
+                    surface: Some(surface.clone()),
+                    ..Default::default()
+                });
+            }
+            wl_compositor::Request::CreateRegion { id } => {
+                data_init.init(id, ());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_surface::WlSurface, ()> for CompositorState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &wl_surface::WlSurface,
+        request: wl_surface::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            wl_surface::Request::Attach { buffer, .. } => {
+                if let Some(s) = state.surfaces.get_mut(&resource.id()) {
+                    s.pending_buffer = buffer;
+                }
+            }
+            wl_surface::Request::Frame { callback } => {
+                let callback = data_init.init(callback, ());
+                if let Some(s) = state.surfaces.get_mut(&resource.id()) {
+                    s.pending_callbacks.push(callback);
+                }
+            }
+            wl_surface::Request::Commit => {
+                state.commit_surface(resource);
+            }
+            _ => {}
+        }
+    }
+
+    fn destroyed(state: &mut Self, _client: ClientId, resource: &wl_surface::WlSurface, _data: &()) {
+        if let Some(s) = state.surfaces.remove(&resource.id()) {
+            if let Some(top_id) = s.toplevel {
+                state.unmap_toplevel(&top_id);
+            }
+        }
+    }
+}
+
+impl Dispatch<wl_region::WlRegion, ()> for CompositorState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &wl_region::WlRegion,
+        _request: wl_region::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_callback::WlCallback, ()> for CompositorState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &wl_callback::WlCallback,
+        _request: wl_callback::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+// ============================================================================
+// wl_shm / wl_shm_pool / wl_buffer
+// ============================================================================
+
+impl GlobalDispatch<wl_shm::WlShm, ()> for CompositorState {
+    fn bind(
+        _state: &mut Self,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<wl_shm::WlShm>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let shm = data_init.init(resource, ());
+        shm.format(wl_shm::Format::Argb8888);
+        shm.format(wl_shm::Format::Xrgb8888);
+    }
+}
+
+impl Dispatch<wl_shm::WlShm, ()> for CompositorState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &wl_shm::WlShm,
+        request: wl_shm::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let wl_shm::Request::CreatePool { id, fd, size } = request {
+            data_init.init(id, ShmPool { fd: Arc::new(fd), size: std::sync::Mutex::new(size) });
+        }
+    }
+}
+
+impl Dispatch<wl_shm_pool::WlShmPool, ShmPool> for CompositorState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &wl_shm_pool::WlShmPool,
+        request: wl_shm_pool::Request,
+        data: &ShmPool,
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            wl_shm_pool::Request::CreateBuffer { id, offset, width, height, stride, format } => {
+                let pool_size = *data.size.lock().unwrap() as i64;
+                if offset < 0 || (offset as i64) + (stride as i64) * (height as i64) > pool_size {
+                    resource.post_error(
+                        wl_shm::Error::InvalidStride,
+                        "Buffer exceeds pool size".to_string(),
+                    );
+                    return;
+                }
+
+                let format = match format {
+                    WEnum::Value(f) => f as u32,
+                    WEnum::Unknown(f) => f,
+                };
+                let buffer = data_init.init(id, ShmBuffer { offset, width, height, stride, format });
+                state.buffer_pools.insert(buffer.id(), data.fd.clone());
+            }
+            wl_shm_pool::Request::Resize { size } => {
+                *data.size.lock().unwrap() = size;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_buffer::WlBuffer, ShmBuffer> for CompositorState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &wl_buffer::WlBuffer,
+        _request: wl_buffer::Request,
+        _data: &ShmBuffer,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+
+    fn destroyed(state: &mut Self, _client: ClientId, resource: &wl_buffer::WlBuffer, _data: &ShmBuffer) {
+        state.buffer_pools.remove(&resource.id());
+    }
+}
+
+// ============================================================================
+// xdg_wm_base / xdg_surface / xdg_toplevel
+// ============================================================================
+
+impl GlobalDispatch<xdg_wm_base::XdgWmBase, ()> for CompositorState {
+    fn bind(
+        _state: &mut Self,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<xdg_wm_base::XdgWmBase>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl Dispatch<xdg_wm_base::XdgWmBase, ()> for CompositorState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &xdg_wm_base::XdgWmBase,
+        request: xdg_wm_base::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            xdg_wm_base::Request::GetXdgSurface { id, surface } => {
+                data_init.init(id, surface);
+            }
+            xdg_wm_base::Request::CreatePositioner { id } => {
+                data_init.init(id, ());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<xdg_positioner::XdgPositioner, ()> for CompositorState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &xdg_positioner::XdgPositioner,
+        _request: xdg_positioner::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl Dispatch<xdg_surface::XdgSurface, wl_surface::WlSurface> for CompositorState {
+    fn request(
+        state: &mut Self,
+        client: &Client,
+        resource: &xdg_surface::XdgSurface,
+        request: xdg_surface::Request,
+        surface: &wl_surface::WlSurface,
+        dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            xdg_surface::Request::GetToplevel { id } => {
+                let toplevel = data_init.init(id, ());
+                if let Err(e) = state.map_toplevel(surface.clone(), resource.clone(), toplevel.clone()) {
+                    eprintln!("❌ Wayland toplevel rejected: {}", e);
+                    toplevel.close();
+                } else if let (Ok(credentials), Some(top)) = (client.get_credentials(dhandle), state.toplevels.get(&toplevel.id())) {
+                    // SO_PEERCRED of the Wayland socket: the window's real owner
+                    state.handler.set_window_pid(top.window_id, credentials.pid as u32);
+                }
+            }
+            xdg_surface::Request::GetPopup { id, .. } => {
+                // Popups are not managed as WASMA Windows yet
+                let popup = data_init.init(id, ());
+                popup.popup_done();
+            }
+            xdg_surface::Request::AckConfigure { .. } => {
+                if let Some(top) = state.toplevels.values_mut().find(|t| &t.xdg_surface == resource) {
+                    top.configured = true;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<xdg_popup::XdgPopup, ()> for CompositorState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &xdg_popup::XdgPopup,
+        _request: xdg_popup::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl Dispatch<xdg_toplevel::XdgToplevel, ()> for CompositorState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &xdg_toplevel::XdgToplevel,
+        request: xdg_toplevel::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        // Transient toplevels (dialogs) name their parent; xdg-shell has no
+        // modality, so the child stays non-modal unless typed as a dialog
+        if let xdg_toplevel::Request::SetParent { parent: Some(parent) } = &request {
+            let child = state.toplevels.get(&resource.id()).map(|t| t.window_id);
+            let parent = state.toplevels.get(&parent.id()).map(|t| t.window_id);
+            if let (Some(child), Some(parent)) = (child, parent) {
+                state.handler.set_parent(child, parent).ok();
+            }
+            return;
+        }
+
+        let top = match state.toplevels.get_mut(&resource.id()) {
+            Some(t) => t,
+            None => return,
+        };
+
+        match request {
+            xdg_toplevel::Request::SetTitle { title } => {
+                top.title = title.clone();
+                state.handler.set_title(top.window_id, title).ok();
+            }
+            xdg_toplevel::Request::SetAppId { app_id } => {
+                top.app_id = app_id.clone();
+                state.handler.set_app_id(top.window_id, app_id).ok();
+            }
+            _ => {}
+        }
+    }
+
+    fn destroyed(state: &mut Self, _client: ClientId, resource: &xdg_toplevel::XdgToplevel, _data: &()) {
+        state.unmap_toplevel(&resource.id());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shm_buffer_size() {
+        let buffer = ShmBuffer { offset: 0, width: 100, height: 50, stride: 400, format: 0 };
+        assert_eq!(buffer.byte_size(), 20_000);
+
+        let broken = ShmBuffer { offset: 0, width: 10, height: -1, stride: 40, format: 0 };
+        assert_eq!(broken.byte_size(), 0);
+    }
+}
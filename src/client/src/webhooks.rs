@@ -0,0 +1,369 @@
+// WASMA - Event webhooks
+// Window lifecycle, lease expiry and endpoint outages are POSTed as JSON to the
+// URLs of WSDG `[webhooks]`, for chat-ops bots and alerting. Every URL has its
+// own delivery thread: a slow or dead receiver delays nobody else. Failed
+// deliveries are retried with a doubling delay; each URL gets at most
+// `max_per_minute` events, the rest are dropped and reported as `suppressed`
+// with the next delivery. Backend monitor reports are only sent when
+// `resource_report` is listed in `events` explicitly, as they come every cycle.
+
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use wsdg_xdg::{WebhookSettings, WsdgEnv, WsdgSettingsManager};
+
+use crate::parser::Protocol;
+use crate::protocol_control::{EndpointState, EndpointTransition};
+use crate::telemetry::post_json;
+use crate::window_handling::WindowEvent;
+use crate::MonitorReport;
+
+/// Delay before the first retry; doubled for each further attempt
+pub const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    WindowCreated { window_id: u64, title: String, app_id: String },
+    WindowClosed { window_id: u64 },
+    /// The resource cycle preempted the window's assignment
+    LeaseExpired { window_id: u64, assignment_id: u32 },
+    /// A connected protocol endpoint lost its stream
+    EndpointDown { endpoint: String, protocol: Protocol, error: Option<String> },
+    /// One backend monitor phase; opt-in
+    ResourceReport { report: MonitorReport },
+}
+
+impl WebhookEvent {
+    /// Name used in `[webhooks] events`
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::WindowCreated { .. } => "window_created",
+            WebhookEvent::WindowClosed { .. } => "window_closed",
+            WebhookEvent::LeaseExpired { .. } => "lease_expired",
+            WebhookEvent::EndpointDown { .. } => "endpoint_down",
+            WebhookEvent::ResourceReport { .. } => "resource_report",
+        }
+    }
+
+    /// One line for humans; chat services show it as the message
+    pub fn summary(&self) -> String {
+        match self {
+            WebhookEvent::WindowCreated { window_id, title, app_id } => {
+                format!("WASMA: window {} created ({} - {})", window_id, app_id, title)
+            }
+            WebhookEvent::WindowClosed { window_id } => format!("WASMA: window {} closed", window_id),
+            WebhookEvent::LeaseExpired { window_id, assignment_id } => {
+                format!("WASMA: lease of window {} expired (assignment {})", window_id, assignment_id)
+            }
+            WebhookEvent::EndpointDown { endpoint, protocol, error } => match error {
+                Some(error) => format!("WASMA: {:?} endpoint {} is down: {}", protocol, endpoint, error),
+                None => format!("WASMA: {:?} endpoint {} is down", protocol, endpoint),
+            },
+            WebhookEvent::ResourceReport { report } => format!(
+                "WASMA: {} assignment(s), {} running, {} lease(s) expired",
+                report.assignments.len(),
+                report.running(),
+                report.expired.len()
+            ),
+        }
+    }
+
+    /// Sent only when `[webhooks] events` names it
+    pub fn is_opt_in(&self) -> bool {
+        matches!(self, WebhookEvent::ResourceReport { .. })
+    }
+
+    /// The webhook event of a window event, if it has one
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::WindowCreated { window_id, title, app_id } => Some(WebhookEvent::WindowCreated {
+                window_id: *window_id,
+                title: title.clone(),
+                app_id: app_id.clone(),
+            }),
+            WindowEvent::WindowClosed { window_id } => Some(WebhookEvent::WindowClosed { window_id: *window_id }),
+            WindowEvent::ResourceLeaseExpired { window_id, assignment_id } => Some(WebhookEvent::LeaseExpired {
+                window_id: *window_id,
+                assignment_id: *assignment_id,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Only a connected endpoint going away counts as down
+    pub fn from_transition(transition: &EndpointTransition) -> Option<Self> {
+        let stats = &transition.stats;
+        (transition.previous == EndpointState::Connected && stats.state == EndpointState::Disconnected).then(|| {
+            WebhookEvent::EndpointDown {
+                endpoint: stats.endpoint.clone(),
+                protocol: stats.protocol.clone(),
+                error: stats.last_error.clone(),
+            }
+        })
+    }
+}
+
+/// JSON body of one delivery
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    #[serde(flatten)]
+    pub event: WebhookEvent,
+    /// Unix seconds
+    pub timestamp: u64,
+    pub text: String,
+    /// Events dropped by the rate limit since the previous delivery to this URL
+    pub suppressed: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WebhookStats {
+    pub delivered: u64,
+    /// Given up after the last retry
+    pub failed: u64,
+    pub rate_limited: u64,
+}
+
+/// Token bucket: `per_minute` deliveries, refilled continuously
+struct RateLimit {
+    per_minute: u32,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimit {
+    fn new(per_minute: u32) -> Self {
+        Self { per_minute, tokens: per_minute as f64, refilled: Instant::now() }
+    }
+
+    fn try_take(&mut self) -> bool {
+        if self.per_minute == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        let refill = now.duration_since(self.refilled).as_secs_f64() * self.per_minute as f64 / 60.0;
+        self.tokens = (self.tokens + refill).min(self.per_minute as f64);
+        self.refilled = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+#[derive(Clone)]
+pub struct WebhookNotifier {
+    events: Vec<String>,
+    queues: Vec<Sender<WebhookEvent>>,
+    stats: Arc<Mutex<WebhookStats>>,
+}
+
+impl WebhookNotifier {
+    pub fn new(settings: &WebhookSettings) -> Self {
+        Self::with_retry_delay(settings, RETRY_DELAY)
+    }
+
+    pub fn with_retry_delay(settings: &WebhookSettings, retry_delay: Duration) -> Self {
+        let stats = Arc::new(Mutex::new(WebhookStats::default()));
+        let queues = settings.urls.iter().map(|url| {
+            let (tx, rx) = channel();
+            let (url, stats) = (url.clone(), stats.clone());
+            let (per_minute, retries) = (settings.max_per_minute, settings.retries);
+            std::thread::spawn(move || deliver_loop(url, rx, RateLimit::new(per_minute), retries, retry_delay, stats));
+            tx
+        }).collect();
+        Self { events: settings.events.clone(), queues, stats }
+    }
+
+    /// Notifier for the user's WSDG settings; None when no URL is configured
+    pub fn from_wsdg() -> Option<Self> {
+        let mut manager = WsdgSettingsManager::new(WsdgEnv::new());
+        manager.load().ok()?;
+        let settings = &manager.settings().webhooks;
+        (!settings.urls.is_empty()).then(|| Self::new(settings))
+    }
+
+    pub fn stats(&self) -> WebhookStats {
+        *self.stats.lock().unwrap()
+    }
+
+    /// Queue `event` for every URL, unless `[webhooks] events` leaves it out
+    pub fn notify(&self, event: WebhookEvent) {
+        let listed = self.events.iter().any(|name| name == event.name());
+        if !listed && (!self.events.is_empty() || event.is_opt_in()) {
+            return;
+        }
+        for queue in &self.queues {
+            queue.send(event.clone()).ok();
+        }
+    }
+
+    /// Forward window events until the handler drops `events`
+    pub fn watch_windows(&self, events: Receiver<WindowEvent>) {
+        let notifier = self.clone();
+        std::thread::spawn(move || {
+            for event in events {
+                if let Some(event) = WebhookEvent::from_window_event(&event) {
+                    notifier.notify(event);
+                }
+            }
+        });
+    }
+
+    /// Forward backend monitor reports (see WindowHandler::subscribe_resource_reports)
+    pub fn watch_reports(&self, reports: Receiver<MonitorReport>) {
+        let notifier = self.clone();
+        std::thread::spawn(move || {
+            for report in reports {
+                notifier.notify(WebhookEvent::ResourceReport { report });
+            }
+        });
+    }
+
+    /// Forward endpoint outages from a ProtocolControl subscription
+    pub fn watch_endpoints(&self, transitions: Receiver<EndpointTransition>) {
+        let notifier = self.clone();
+        std::thread::spawn(move || {
+            for transition in transitions {
+                if let Some(event) = WebhookEvent::from_transition(&transition) {
+                    notifier.notify(event);
+                }
+            }
+        });
+    }
+}
+
+fn deliver_loop(
+    url: String,
+    queue: Receiver<WebhookEvent>,
+    mut limit: RateLimit,
+    retries: u32,
+    retry_delay: Duration,
+    stats: Arc<Mutex<WebhookStats>>,
+) {
+    let mut suppressed = 0;
+    for event in queue {
+        if !limit.try_take() {
+            suppressed += 1;
+            stats.lock().unwrap().rate_limited += 1;
+            continue;
+        }
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let payload = WebhookPayload { text: event.summary(), event, timestamp, suppressed };
+        let Ok(body) = serde_json::to_string(&payload) else { continue };
+
+        let mut delay = retry_delay;
+        let mut attempt = 0;
+        let delivered = loop {
+            match post_json(&url, &body) {
+                Ok(()) => break true,
+                Err(_) if attempt < retries => {
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    eprintln!("⚠️  Webhook {} failed after {} attempt(s): {}", url, attempt + 1, e);
+                    break false;
+                }
+            }
+        };
+
+        let mut stats = stats.lock().unwrap();
+        if delivered {
+            stats.delivered += 1;
+            suppressed = 0;
+        } else {
+            stats.failed += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Body of one HTTP request, answered with `status`
+    fn serve_one(listener: &TcpListener, status: &str) -> String {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 1024];
+        loop {
+            let n = stream.read(&mut buf).unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length: usize = head.lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .and_then(|len| len.parse().ok())
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    stream.write_all(format!("HTTP/1.1 {}\r\n\r\n", status).as_bytes()).unwrap();
+                    return body.to_string();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_webhook_delivery() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let settings = WebhookSettings {
+            urls: vec![format!("http://{}/hooks/wasma", listener.local_addr().unwrap())],
+            events: vec!["window_created".to_string(), "endpoint_down".to_string()],
+            max_per_minute: 2,
+            retries: 1,
+        };
+        let notifier = WebhookNotifier::with_retry_delay(&settings, Duration::from_millis(10));
+
+        // Filtered out, delivered after a retry, delivered, rate limited
+        notifier.notify(WebhookEvent::WindowClosed { window_id: 1 });
+        notifier.notify(WebhookEvent::WindowCreated { window_id: 2, title: "Editor".to_string(), app_id: "org.example.Editor".to_string() });
+        let transition = EndpointTransition {
+            previous: EndpointState::Connected,
+            stats: crate::protocol_control::EndpointStats {
+                index: 0,
+                protocol: Protocol::Tor,
+                endpoint: "example.onion:9050".to_string(),
+                state: EndpointState::Disconnected,
+                bytes: 0,
+                frames: 0,
+                dropped: 0,
+                throughput: 0.0,
+                fps: 0.0,
+                reconnects: 0,
+                last_error: Some("circuit closed".to_string()),
+            },
+        };
+        let down = WebhookEvent::from_transition(&transition).unwrap();
+        notifier.notify(down.clone());
+        notifier.notify(down.clone());
+
+        let created = serve_one(&listener, "500 Internal Server Error");
+        assert_eq!(created, serve_one(&listener, "204 No Content"));
+        assert!(created.contains("\"event\":\"window_created\""));
+        let payload: WebhookPayload = serde_json::from_str(&serve_one(&listener, "200 OK")).unwrap();
+        assert_eq!(payload.event, down);
+        assert_eq!(payload.text, "WASMA: Tor endpoint example.onion:9050 is down: circuit closed");
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while notifier.stats().rate_limited == 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(notifier.stats(), WebhookStats { delivered: 2, failed: 0, rate_limited: 1 });
+
+        // Only events that mean something outside WASMA become webhooks
+        assert!(WebhookEvent::from_window_event(&WindowEvent::FocusChanged { previous: None, current: Some(2) }).is_none());
+        assert!(WebhookEvent::from_transition(&EndpointTransition { previous: EndpointState::Connecting, ..transition }).is_none());
+
+        // Monitor reports only go to URLs that list them
+        let report = WebhookEvent::ResourceReport { report: wbackend::WBackend::new(wbackend::ResourceMode::Manual).run_cycle() };
+        assert!(report.is_opt_in() && !down.is_opt_in());
+        assert_eq!(report.summary(), "WASMA: 0 assignment(s), 0 running, 0 lease(s) expired");
+    }
+}
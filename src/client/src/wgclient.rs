@@ -1,18 +1,185 @@
 // WASMA - WGClient (Wayland/X11 Graphics Client)
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use crate::parser::{WasmaConfig, Protocol}; // Protocol import düzeltildi
-use crate::protocols::ProtocolManager;
+use crate::protocols::{ProtocolManager, ProtocolStream};
+use crate::protocol_control::{EndpointState, ProtocolControl};
+use crate::pixel_format::{self, FormatError, FrameAssembler, FrameHeader, PixelFormat};
+use crate::presenter::{Presenter, STREAM_SLOT_SIZE};
+use crate::session_lock::{self, SESSION_LOCKED};
+use crate::metrics;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, error::TryRecvError, error::TrySendError};
+use tokio::sync::Notify;
 use x11rb::connection::Connection as XConnection;
 use x11rb::protocol::xproto::{self, ConnectionExt};
 
-// Global VRAM adresleri tanımlandı
+// Global VRAM adresleri tanımlandı (çift tampon, bkz. presenter.rs)
 pub static WASMA_VRAM_ADDR: usize = 0xB0000000; // Örnek base adres
 pub static mut WASMA_CORE_ACTIVE: bool = true;
 
+/// Frames buffered per stream; a full queue stops that stream's reader
+/// (TCP backpressure) without holding up the other streams
+pub const STREAM_QUEUE_CAPACITY: usize = 32;
+
+/// Flow counters of one protocol stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamStats {
+    pub stream_id: u8,
+    pub protocol: Protocol,
+    /// Frames waiting for the dispatcher right now
+    pub queued: usize,
+    pub peak_queued: usize,
+    /// Frames handed to the display
+    pub delivered: u64,
+    /// Frames discarded at the display (session locked)
+    pub dropped: u64,
+    /// Times the reader waited on a full queue
+    pub stalls: u64,
+    pub closed: bool,
+}
+
+type StatsTable = Arc<Mutex<Vec<StreamStats>>>;
+
+/// A frame with its arrival time, for the frame latency metric
+type QueuedFrame = (Instant, Vec<u8>);
+
+fn with_stats(stats: &StatsTable, stream_id: u8, update: impl FnOnce(&mut StreamStats)) {
+    if let Some(entry) = stats.lock().unwrap().iter_mut().find(|s| s.stream_id == stream_id) {
+        update(entry);
+    }
+}
+
+/// Reader side of one stream's bounded queue
+pub struct StreamSender {
+    stream_id: u8,
+    tx: mpsc::Sender<QueuedFrame>,
+    notify: Arc<Notify>,
+    stats: StatsTable,
+}
+
+impl StreamSender {
+    /// Queue a frame, waiting while the queue is full.
+    /// false: the dispatcher is gone and the reader should stop
+    pub async fn send(&self, frame: Vec<u8>) -> bool {
+        let arrived = Instant::now();
+        let permit = match self.tx.try_reserve() {
+            Ok(permit) => permit,
+            Err(TrySendError::Full(())) => {
+                with_stats(&self.stats, self.stream_id, |s| s.stalls += 1);
+                match self.tx.reserve().await {
+                    Ok(permit) => permit,
+                    Err(_) => return false,
+                }
+            }
+            Err(TrySendError::Closed(())) => return false,
+        };
+
+        // Counted before the frame becomes visible to the dispatcher
+        with_stats(&self.stats, self.stream_id, |s| {
+            s.queued += 1;
+            s.peak_queued = s.peak_queued.max(s.queued);
+        });
+        permit.send((arrived, frame));
+        self.notify.notify_one();
+        true
+    }
+
+    /// Queue every complete frame of `assembler`, converted to BGRA with its damage kept.
+    /// Ok(false): the dispatcher is gone and the reader should stop
+    pub async fn send_frames(&self, assembler: &mut FrameAssembler) -> Result<bool, FormatError> {
+        while let Some((header, damage, pixels)) = assembler.next_frame_damaged()? {
+            let bgra = FrameHeader::bgra(header.width, header.height);
+            let frame = pixel_format::encode_frame_damaged(&bgra, &damage, &pixel_format::to_bgra(&header, &pixels));
+            if !self.send(frame).await {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// The dispatcher has stopped
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
+impl Drop for StreamSender {
+    fn drop(&mut self) {
+        // Wake the dispatcher so it notices the closed stream
+        self.notify.notify_one();
+    }
+}
+
+/// Fair multiplexer: one frame per stream per round, so a busy stream
+/// cannot starve the others
+pub struct StreamMux {
+    receivers: Vec<(u8, mpsc::Receiver<QueuedFrame>)>,
+    notify: Arc<Notify>,
+    stats: StatsTable,
+    capacity: usize,
+}
+
+impl StreamMux {
+    fn new(stats: StatsTable, capacity: usize) -> Self {
+        Self { receivers: Vec::new(), notify: Arc::new(Notify::new()), stats, capacity }
+    }
+
+    pub fn add_stream(&mut self, stream_id: u8, protocol: Protocol) -> StreamSender {
+        let (tx, rx) = mpsc::channel(self.capacity);
+        self.receivers.push((stream_id, rx));
+        self.stats.lock().unwrap().push(StreamStats {
+            stream_id,
+            protocol,
+            queued: 0,
+            peak_queued: 0,
+            delivered: 0,
+            dropped: 0,
+            stalls: 0,
+            closed: false,
+        });
+        StreamSender { stream_id, tx, notify: self.notify.clone(), stats: self.stats.clone() }
+    }
+
+    /// Round-robin until every stream has closed. `sink` returns false for a dropped frame
+    pub async fn run(mut self, mut sink: impl FnMut(&[u8], u8) -> bool) {
+        while !self.receivers.is_empty() {
+            let mut progressed = false;
+
+            let stats = &self.stats;
+            self.receivers.retain_mut(|(stream_id, rx)| match rx.try_recv() {
+                Ok((arrived, frame)) => {
+                    progressed = true;
+                    with_stats(stats, *stream_id, |s| s.queued = s.queued.saturating_sub(1));
+                    let delivered = sink(&frame, *stream_id);
+                    if delivered {
+                        metrics::record_frame_latency(arrived.elapsed());
+                    }
+                    with_stats(stats, *stream_id, |s| {
+                        if delivered { s.delivered += 1 } else { s.dropped += 1 }
+                    });
+                    true
+                }
+                Err(TryRecvError::Empty) => true,
+                Err(TryRecvError::Disconnected) => {
+                    progressed = true;
+                    with_stats(stats, *stream_id, |s| s.closed = true);
+                    false
+                }
+            });
+
+            if !progressed {
+                self.notify.notified().await;
+            }
+        }
+    }
+}
+
 pub struct WGClient {
     config: Arc<WasmaConfig>,
     x11_ctx: Option<(Arc<x11rb::rust_connection::RustConnection>, xproto::Window)>,
+    stream_stats: StatsTable,
 }
 
 impl WGClient {
@@ -39,89 +206,192 @@ impl WGClient {
         Self {
             config: Arc::new(config),
             x11_ctx,
+            stream_stats: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
+    /// Queue depth, deliveries, drops and stalls of every stream of the running engine
+    pub fn stream_stats(&self) -> Vec<StreamStats> {
+        self.stream_stats.lock().unwrap().clone()
+    }
+
     pub async fn run_engine(&self, mut manager: ProtocolManager) {
         let is_multi = self.config.uri_handling.multi_instances;
         let is_singularity = self.config.uri_handling.singularity_instances;
         let mut stream_count = 0;
 
-        // active_streams artık public, direkt erişilebilir
-        let streams = std::mem::take(&mut manager.active_streams);
+        session_lock::watch_lock_marker();
+
+        // Her stream, wasma.in.conf'taki protocol_def sırasıyla eşleşir
+        let streams = manager.take_streams();
+        let control = manager.control();
+        let config = manager.shared_config();
+        self.stream_stats.lock().unwrap().clear();
+        let mut mux = StreamMux::new(self.stream_stats.clone(), STREAM_QUEUE_CAPACITY);
+        let mut endpoints = Vec::new();
         
-        for mut stream in streams {
+        for (index, stream) in streams {
             if is_singularity && stream_count >= 1 { 
                 break; 
             }
             
-            let proto_type = stream.get_type();
             let stream_id = stream_count;
-            
-            tokio::spawn(async move {
-                match proto_type {
-                    Protocol::Tor => {
-                        let mut buf = [0u8; 65536];
-                        while let Ok(n) = stream.read(&mut buf).await {
-                            if n == 0 { break; }
-                            Self::route_to_display(&buf[..n], stream_id);
-                        }
-                    },
-                    Protocol::Grpc => {
-                        while let Ok(Some(frame)) = stream.next_message().await {
-                            Self::route_to_display(&frame, stream_id);
-                        }
-                    },
-                    Protocol::Https | Protocol::Http => {
-                        while let Ok(chunk) = stream.next_chunk().await {
-                            if chunk.is_empty() { break; }
-                            Self::route_to_display(&chunk, stream_id);
-                        }
-                    }
-                }
-            });
+            let sender = mux.add_stream(stream_id, stream.get_type());
+            endpoints.push((stream_id, index));
+            tokio::spawn(Self::drive_endpoint(stream, index, sender, control.clone(), config.clone()));
             
             stream_count += 1;
             if !is_multi { break; }
         }
+
+        tokio::spawn(mux.run(move |frame, stream_id| {
+            let delivered = Self::route_to_display(frame, stream_id);
+            if let Some(&(_, index)) = endpoints.iter().find(|(id, _)| *id == stream_id) {
+                control.record_frame(index, frame.len());
+                if !delivered {
+                    control.record_drop(index);
+                }
+            }
+            delivered
+        }));
+    }
+
+    /// Feed one endpoint's stream into the mux. A reconnect or disable request
+    /// (ProtocolControl) drops the stream; a lost or disabled endpoint waits
+    /// for the next reconnect request before connecting again
+    async fn drive_endpoint(
+        stream: Box<dyn ProtocolStream>,
+        index: usize,
+        sender: StreamSender,
+        control: Arc<ProtocolControl>,
+        config: Arc<WasmaConfig>,
+    ) {
+        let Some(interrupt) = control.interrupt(index) else { return };
+        let mut stream = Some(stream);
+        loop {
+            if let Some(mut current) = stream.take() {
+                control.set_state(index, EndpointState::Connected);
+                tokio::select! {
+                    result = Self::pump(current.as_mut(), &sender) => {
+                        let reason = result.err().unwrap_or_else(|| "stream closed".to_string());
+                        eprintln!("⚠️  Stream {}: {}", index, reason);
+                        control.record_error(index, reason);
+                    }
+                    _ = interrupt.notified() => {}
+                }
+            }
+            if sender.is_closed() {
+                return;
+            }
+
+            while !(control.is_enabled(index) && control.take_reconnect(index)) {
+                interrupt.notified().await;
+            }
+            control.set_state(index, EndpointState::Connecting);
+            let Some(proto) = config.uri_handling.protocols.get(index).cloned() else { return };
+            let manager = ProtocolManager::from_config(config.clone());
+            match tokio::task::spawn_blocking(move || manager.connect_protocol(&proto)).await {
+                Ok(Ok(reconnected)) => stream = Some(reconnected),
+                Ok(Err(e)) => {
+                    eprintln!("❌ Stream {}: {}", index, e);
+                    control.record_error(index, e);
+                }
+                Err(_) => return,
+            }
+        }
     }
 
-    fn route_to_display(data: &[u8], stream_id: u8) {
+    /// Read frames until the stream ends (Ok) or fails; Ok also when the dispatcher is gone
+    async fn pump(stream: &mut dyn ProtocolStream, sender: &StreamSender) -> Result<(), String> {
+        let mut buf = vec![0u8; 65536];
+        match stream.get_type() {
+            Protocol::Grpc => {
+                // StreamFrames messages, decoded and normalized to BGRA
+                while let Some(frame) = stream.next_message().await.map_err(|e| e.to_string())? {
+                    if !sender.send(frame).await { break; }
+                }
+            }
+            Protocol::Tor | Protocol::Https | Protocol::Http => {
+                let mut frames = FrameAssembler::new(&PixelFormat::ALL);
+                stream.write(&pixel_format::encode_hello(&PixelFormat::ALL)).await.map_err(|e| e.to_string())?;
+                loop {
+                    let n = match stream.read(&mut buf).await {
+                        Ok(0) => break,
+                        Ok(n) => n,
+                        // HTTP sockets are non-blocking
+                        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            tokio::time::sleep(Duration::from_millis(5)).await;
+                            continue;
+                        }
+                        Err(e) => return Err(e.to_string()),
+                    };
+                    frames.push(&buf[..n]);
+                    if !sender.send_frames(&mut frames).await.map_err(|e| e.to_string())? { break; }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// false when the frame was dropped
+    fn route_to_display(data: &[u8], stream_id: u8) -> bool {
+        // Header'sız ya da bozuk frame VRAM'e yazılmaz
+        let Ok((_, pixels)) = FrameHeader::split(data) else {
+            return false;
+        };
+        // Kilitli oturum: stream okunmaya devam eder, slot siyahla temizlenir
+        let blank;
+        let pixels = if SESSION_LOCKED.load(Ordering::SeqCst) {
+            blank = vec![0u8; pixels.len()];
+            &blank[..]
+        } else {
+            pixels
+        };
+
         unsafe {
             if WASMA_CORE_ACTIVE {
-                Self::write_raw_vram(data, stream_id);
+                Self::write_raw_vram(pixels, stream_id);
             } else {
                 // Fallback rendering (X11/Wayland)
                 // Bu durumda instance'a ihtiyaç var, static olduğu için şimdilik skip
             }
         }
+        true
     }
 
     fn write_raw_vram(data: &[u8], stream_id: u8) {
-        unsafe {
-            let offset = stream_id as usize * (1024 * 1024);
-            let target_ptr = (WASMA_VRAM_ADDR + offset) as *mut u8;
-            std::ptr::copy_nonoverlapping(
-                data.as_ptr(),
-                target_ptr,
-                data.len()
-            );
-        }
+        // Back buffer of the stream's slot; scanout sees it after the next vsync flip
+        Presenter::global().write(stream_id as usize * STREAM_SLOT_SIZE, data);
     }
 
     pub fn write_x11_frame(&self, data: &[u8], stream_id: u8) {
+        let Ok((header, pixels)) = FrameHeader::split(data) else {
+            return;
+        };
+        // Z_PIXMAP depth 24 expects packed 32-bit BGRX rows; a locked session puts black
+        let converted;
+        let pixels = if SESSION_LOCKED.load(Ordering::SeqCst) {
+            converted = vec![0u8; header.width as usize * header.height as usize * 4];
+            &converted
+        } else if header == FrameHeader::bgra(header.width, header.height) {
+            pixels
+        } else {
+            converted = pixel_format::to_bgra(&header, pixels);
+            &converted
+        };
+
         if let Some((conn, win)) = &self.x11_ctx {
             let gc = conn.generate_id().unwrap();
             conn.create_gc(gc, *win, &xproto::CreateGCAux::new()).ok();
             
-            let y_pos = (stream_id as i16) * 200;
+            let y_pos = (stream_id as i16).saturating_mul(header.height as i16);
             
             conn.put_image(
                 xproto::ImageFormat::Z_PIXMAP,
                 *win,
                 gc,
-                1280, 200,
-                0, y_pos, 0, 24, data
+                header.width as u16, header.height as u16,
+                0, y_pos, 0, 24, pixels
             ).ok();
             
             conn.free_gc(gc).ok();
@@ -144,4 +414,46 @@ mod tests {
         let _client = WGClient::new(config);
         assert!(unsafe { WASMA_CORE_ACTIVE });
     }
+
+    #[test]
+    fn test_stream_mux_fairness() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let stats: StatsTable = Arc::new(Mutex::new(Vec::new()));
+        let mut mux = StreamMux::new(stats.clone(), 4);
+        let grpc = mux.add_stream(0, Protocol::Grpc);
+        let tor = mux.add_stream(1, Protocol::Tor);
+
+        let order = runtime.block_on(async move {
+            // gRPC fills its queue before Tor gets a single frame in
+            let fast = tokio::spawn(async move {
+                for n in 0..12u8 {
+                    grpc.send(vec![n]).await;
+                }
+            });
+            tokio::task::yield_now().await;
+            tor.send(vec![100]).await;
+            tor.send(vec![101]).await;
+            drop(tor);
+
+            let mut order = Vec::new();
+            mux.run(|frame, stream_id| {
+                order.push(stream_id);
+                frame[0] != 5
+            }).await;
+            fast.await.unwrap();
+            order
+        });
+
+        // Tor frames go out within the first rounds instead of after gRPC's backlog
+        let first_tor = order.iter().position(|&id| id == 1).unwrap();
+        assert!(first_tor <= 1, "{:?}", order);
+        assert_eq!(order.iter().filter(|&&id| id == 1).count(), 2);
+
+        let stats = stats.lock().unwrap().clone();
+        assert_eq!(stats[0].delivered + stats[0].dropped, 12);
+        assert_eq!(stats[0].dropped, 1);
+        assert!(stats[0].stalls > 0);
+        assert_eq!(stats[0].peak_queued, 4);
+        assert!(stats.iter().all(|s| s.closed && s.queued == 0));
+    }
 }
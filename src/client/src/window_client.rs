@@ -1,7 +1,25 @@
-use std::sync::Arc;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::Receiver;
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::time::{Duration, Instant};
+use crate::adaptive_resolution::{self, ResolutionScaler, ScaleChange, ScaleMethod};
+use crate::decorations::Decorations;
+use crate::presentation_buffer::{self, FrameKind, PresentationBuffer, PresentationConfig, PresentationStats};
 use crate::parser::WasmaConfig;
-use crate::window_multitary::WindowMultitary;
+use crate::pixel_format::DamageRect;
+use crate::placeholder::{Placeholder, PlaceholderMode};
+use crate::focus_policy::{FocusEngine, PointerEvent};
+use crate::global_hotkeys::{KeyCombo, Modifiers};
+use crate::hotkeys::{WmAction, WmHotkeys};
+use crate::monitors::MonitorMap;
+use crate::window_multitary::{InputAction, LayoutPolicy, StreamFocusEvent, WindowMultitary, BUTTON_LEFT, BUTTON_RIGHT};
+use crate::window_snapping::WindowSnapper;
+use crate::window_handling::WindowHandler;
 use crate::window_singularity::{WindowSingularity, SINGULARITY_LOCK};
+use crate::session_lock::SESSION_LOCKED;
 use std::sync::atomic::Ordering;
 
 pub struct WindowClient {
@@ -10,6 +28,64 @@ pub struct WindowClient {
     singularity: WindowSingularity,
     width: u32,
     height: u32,
+    decorations: Option<Decorations>,
+    decorated_streams: HashSet<u8>,
+    // Titlebar text per decorated stream, and the last painted titlebar
+    titles: HashMap<u8, String>,
+    titlebars: Mutex<HashMap<u8, Titlebar>>,
+    scalers: HashMap<u8, ResolutionScaler>,
+    presentation: HashMap<u8, PresentationBuffer>,
+    // Per-stream reconnect placeholder (default theme when unset) and disconnect time
+    placeholders: HashMap<u8, Placeholder>,
+    disconnected: HashMap<u8, Instant>,
+    // Last composited frame per stream: identical frames skip upload + composite
+    frame_signatures: Mutex<HashMap<u8, FrameSignature>>,
+    skipped_frames: AtomicU64,
+    uploaded_bytes: AtomicU64,
+    hotkeys: WmHotkeys,
+    // The handler's detached flag (follow_session): nothing is blitted while set
+    off_display: Arc<AtomicBool>,
+    // Super+drag of streams bound to handler windows (attach_snapper)
+    snapper: Option<WindowSnapper>,
+    // Stream under the snapper's grab
+    snap_stream: Option<u8>,
+}
+
+/// Identity of a frame: sender sequence number when known, else content hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum FrameTag {
+    Sequence(u64),
+    Content(u64),
+    /// Cleared while the session is locked
+    Blank,
+}
+
+/// How much of a frame has to reach the hardware
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlitScope {
+    /// Already on screen
+    Skip,
+    /// Only the damage rects; the rest still shows the previous frame
+    Damage,
+    Full,
+}
+
+/// Frame identity plus everything that changes how it is composited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FrameSignature {
+    tag: FrameTag,
+    bounds: (i32, i32, u32, u32),
+    scale_bits: u32,
+}
+
+/// Painted SSD titlebar; repainted only when frame, focus or title change
+struct Titlebar {
+    frame: (i32, i32, u32, u32),
+    focused: bool,
+    title: String,
+    pixels: Vec<u8>,
+    /// Still on screen (cleared by invalidation and blanking)
+    shown: bool,
 }
 
 impl WindowClient {
@@ -21,6 +97,21 @@ impl WindowClient {
             config: config_arc,
             width,
             height,
+            decorations: None,
+            decorated_streams: HashSet::new(),
+            titles: HashMap::new(),
+            titlebars: Mutex::new(HashMap::new()),
+            scalers: HashMap::new(),
+            presentation: HashMap::new(),
+            placeholders: HashMap::new(),
+            disconnected: HashMap::new(),
+            frame_signatures: Mutex::new(HashMap::new()),
+            skipped_frames: AtomicU64::new(0),
+            uploaded_bytes: AtomicU64::new(0),
+            hotkeys: WmHotkeys::default(),
+            off_display: Arc::new(AtomicBool::new(false)),
+            snapper: None,
+            snap_stream: None,
         }
     }
 
@@ -31,70 +122,424 @@ impl WindowClient {
             config,
             width,
             height,
+            decorations: None,
+            decorated_streams: HashSet::new(),
+            titles: HashMap::new(),
+            titlebars: Mutex::new(HashMap::new()),
+            scalers: HashMap::new(),
+            presentation: HashMap::new(),
+            placeholders: HashMap::new(),
+            disconnected: HashMap::new(),
+            frame_signatures: Mutex::new(HashMap::new()),
+            skipped_frames: AtomicU64::new(0),
+            uploaded_bytes: AtomicU64::new(0),
+            hotkeys: WmHotkeys::default(),
+            off_display: Arc::new(AtomicBool::new(false)),
+            snapper: None,
+            snap_stream: None,
+        }
+    }
+
+    /// Stop presenting while `handler`'s session is detached (fast user switching)
+    pub fn follow_session(&mut self, handler: &WindowHandler) {
+        self.off_display = handler.off_display();
+    }
+
+    /// Move, resize and snap bound windows with Super+drag; the snapper
+    /// works on this client's screen and drives the stream viewports
+    pub fn attach_snapper(&mut self, mut snapper: WindowSnapper) {
+        snapper.set_screen(self.width, self.height);
+        snapper.sync_viewports(&mut self.multitary);
+        self.snapper = Some(snapper);
+        self.invalidate_all();
+    }
+
+    /// The stream shows this handler window; its geometry becomes the viewport
+    pub fn bind_window(&mut self, stream_id: u8, window_id: u64) {
+        if let Some(snapper) = self.snapper.as_mut() {
+            snapper.bind_stream(window_id, stream_id);
+            snapper.sync_viewports(&mut self.multitary);
+            self.invalidate_all();
+        }
+    }
+
+    /// Advance snap animations; returns true while any is still running
+    pub fn tick_snapper(&mut self, now: Instant) -> bool {
+        let Some(snapper) = self.snapper.as_mut() else {
+            return false;
+        };
+        if !snapper.is_animating() {
+            return false;
         }
+        let running = snapper.tick(now);
+        snapper.sync_viewports(&mut self.multitary);
+        self.invalidate_all();
+        running
     }
 
     pub fn render_frame(&self, stream_id: u8, data: &[u8]) {
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        self.render_tagged(stream_id, data, FrameTag::Content(hasher.finish()), &[]);
+    }
+
+    /// Like render_frame, but a repeated sequence number is treated as a
+    /// repeated frame without hashing its pixels
+    pub fn render_frame_seq(&self, stream_id: u8, sequence: u64, data: &[u8]) {
+        self.render_tagged(stream_id, data, FrameTag::Sequence(sequence), &[]);
+    }
+
+    /// Like render_frame, but only the `damage` rects (frame pixels, as sent in
+    /// the WFRM header) are uploaded when the previous frame is still on screen
+    /// at the same bounds; empty damage means the whole frame changed
+    pub fn render_frame_damaged(&self, stream_id: u8, data: &[u8], damage: &[DamageRect]) {
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        self.render_tagged(stream_id, data, FrameTag::Content(hasher.finish()), damage);
+    }
+
+    /// Frames skipped because they matched the last composited frame
+    pub fn skipped_frames(&self) -> u64 {
+        self.skipped_frames.load(Ordering::Relaxed)
+    }
+
+    /// Pixel bytes handed to the blits so far
+    pub fn uploaded_bytes(&self) -> u64 {
+        self.uploaded_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Escape hatch for damage the sender did not report (screen corruption,
+    /// a lost frame): every stream's next frame is uploaded whole
+    pub fn full_redraw(&self) {
+        self.invalidate_all();
+    }
+
+    /// Force the stream's next frame through the composite path
+    pub fn invalidate_stream(&self, stream_id: u8) {
+        self.frame_signatures.lock().unwrap().remove(&stream_id);
+        if let Some(titlebar) = self.titlebars.lock().unwrap().get_mut(&stream_id) {
+            titlebar.shown = false;
+        }
+    }
+
+    fn invalidate_all(&self) {
+        self.frame_signatures.lock().unwrap().clear();
+        self.titlebars.lock().unwrap().values_mut().for_each(|titlebar| titlebar.shown = false);
+    }
+
+    /// Skip (and count) a frame the stream already shows at these bounds; damage
+    /// only applies on top of the previous frame at the same bounds and scale
+    fn blit_scope(&self, stream_id: u8, tag: FrameTag, bounds: (i32, i32, u32, u32), damaged: bool) -> BlitScope {
+        let signature = FrameSignature { tag, bounds, scale_bits: self.stream_scale(stream_id).to_bits() };
+        let previous = self.frame_signatures.lock().unwrap().insert(stream_id, signature);
+        match previous {
+            Some(previous) if previous == signature => {
+                self.skipped_frames.fetch_add(1, Ordering::Relaxed);
+                BlitScope::Skip
+            }
+            Some(previous) if damaged && previous.bounds == bounds && previous.scale_bits == signature.scale_bits => BlitScope::Damage,
+            _ => BlitScope::Full,
+        }
+    }
+
+    fn render_tagged(&self, stream_id: u8, data: &[u8], tag: FrameTag, damage: &[DamageRect]) {
+        // Detached session: another user has the display, not even a blank frame
+        // goes out. Everything is repainted once the session is attached again
+        if self.off_display.load(Ordering::SeqCst) {
+            self.invalidate_all();
+            return;
+        }
+
+        // Locked session: the stream keeps its lease, its viewport is cleared once
+        // (the Blank signature makes the next unlocked frame a full repaint)
+        if SESSION_LOCKED.load(Ordering::SeqCst) {
+            self.blank_stream(stream_id);
+            return;
+        }
+
         let is_singularity = SINGULARITY_LOCK.load(Ordering::SeqCst);
         
         if is_singularity {
             let bounds = self.singularity.get_exclusive_bounds();
-            self.dispatch_to_hardware(data, bounds, stream_id);
+            let scale = self.multitary.monitors().primary().scale;
+            let upscaled = fit_hidpi(data, bounds, scale);
+            match self.blit_scope(stream_id, tag, bounds, !damage.is_empty() && upscaled.is_none()) {
+                BlitScope::Skip => {}
+                BlitScope::Damage => self.dispatch_to_hardware(data, bounds, damage, stream_id),
+                BlitScope::Full => self.dispatch_to_hardware(upscaled.as_deref().unwrap_or(data), bounds, &[DamageRect::full(bounds.2, bounds.3)], stream_id),
+            }
         } else {
             if let Some(viewport) = self.multitary.get_viewport_for_stream(stream_id) {
                 if viewport.active {
-                    let bounds = (viewport.x, viewport.y, viewport.width, viewport.height);
-                    self.dispatch_to_hardware(data, bounds, stream_id);
+                    let mut bounds = (viewport.x, viewport.y, viewport.width, viewport.height);
+                    let content = match self.decorations.as_ref().filter(|_| self.decorated_streams.contains(&stream_id)) {
+                        Some(deco) => deco.content_bounds(bounds),
+                        None => bounds,
+                    };
+                    // A logical-size frame on a HiDPI output is stretched to the physical
+                    // viewport (always whole: its damage rects are in logical pixels)
+                    let upscaled = fit_hidpi(data, content, viewport.scale);
+                    let data = upscaled.as_deref().unwrap_or(data);
+                    let damage = if upscaled.is_some() { &[][..] } else { damage };
+
+                    // Server-side decorations: titlebar first, stream pixels inside the frame.
+                    // It is checked even for a static window, focus may have moved
+                    if let Some(deco) = self.decorations.as_ref().filter(|_| self.decorated_streams.contains(&stream_id)) {
+                        self.present_titlebar(deco, stream_id, bounds);
+                    }
+
+                    // Static window: nothing to upload or recomposite
+                    let scope = self.blit_scope(stream_id, tag, bounds, !damage.is_empty());
+                    if scope == BlitScope::Skip {
+                        return;
+                    }
+                    bounds = content;
+                    let whole = [DamageRect::full(bounds.2, bounds.3)];
+                    let regions = if scope == BlitScope::Damage { damage } else { &whole[..] };
+
+                    // Overloaded stream without remote resize: upload fewer pixels, the blit stretches them
+                    // (always whole: damage rects are in full-size frame pixels)
+                    match self.scalers.get(&stream_id) {
+                        Some(scaler) if scaler.scale() < 1.0 && scaler.method() == ScaleMethod::Downscale => {
+                            let (_, _, w, h) = bounds;
+                            let (small, _, _) = adaptive_resolution::downscale_rgba(data, w, h, scaler.scale());
+                            self.dispatch_to_hardware(&small, bounds, &whole, stream_id);
+                        }
+                        _ => self.dispatch_to_hardware(data, bounds, regions, stream_id),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Blit the stream's titlebar unless the one on screen is still current
+    fn present_titlebar(&self, deco: &Decorations, stream_id: u8, frame: (i32, i32, u32, u32)) {
+        let focused = self.multitary.focused_stream() == Some(stream_id);
+        let title = self.titles.get(&stream_id).map_or("", String::as_str);
+        let bounds = deco.titlebar_bounds(frame);
+
+        let mut titlebars = self.titlebars.lock().unwrap();
+        let titlebar = match titlebars.get_mut(&stream_id) {
+            Some(titlebar) if titlebar.frame == frame && titlebar.focused == focused && titlebar.title == title => {
+                if titlebar.shown {
+                    return;
                 }
+                titlebar
+            }
+            _ => {
+                let pixels = deco.paint_titlebar(frame, focused, title);
+                let titlebar = Titlebar { frame, focused, title: title.to_string(), pixels, shown: false };
+                titlebars.insert(stream_id, titlebar);
+                titlebars.get_mut(&stream_id).unwrap()
+            }
+        };
+        self.dispatch_to_hardware(&titlebar.pixels, bounds, &[DamageRect::full(bounds.2, bounds.3)], stream_id);
+        titlebar.shown = true;
+    }
+
+    /// Clear everything the stream has on screen, titlebar included
+    fn blank_stream(&self, stream_id: u8) {
+        let bounds = if SINGULARITY_LOCK.load(Ordering::SeqCst) {
+            self.singularity.get_exclusive_bounds()
+        } else {
+            match self.multitary.get_viewport_for_stream(stream_id) {
+                Some(viewport) if viewport.active => (viewport.x, viewport.y, viewport.width, viewport.height),
+                _ => return,
+            }
+        };
+        if self.blit_scope(stream_id, FrameTag::Blank, bounds, false) == BlitScope::Skip {
+            return;
+        }
+        let blank = vec![0u8; bounds.2 as usize * bounds.3 as usize * 4];
+        self.dispatch_to_hardware(&blank, bounds, &[DamageRect::full(bounds.2, bounds.3)], stream_id);
+        if let Some(titlebar) = self.titlebars.lock().unwrap().get_mut(&stream_id) {
+            titlebar.shown = false;
+        }
+    }
+
+    /// Placeholder shown while the stream is disconnected (usually Placeholder::for_app)
+    pub fn set_placeholder(&mut self, stream_id: u8, placeholder: Placeholder) {
+        self.placeholders.insert(stream_id, placeholder);
+    }
+
+    /// The stream lost its connection: present_stream() shows the placeholder from now on
+    pub fn stream_disconnected(&mut self, stream_id: u8) {
+        self.disconnected.entry(stream_id).or_insert_with(Instant::now);
+    }
+
+    /// Back to the stream's frames; also implied by queue_frame()
+    pub fn stream_reconnected(&mut self, stream_id: u8) {
+        if self.disconnected.remove(&stream_id).is_some() {
+            self.invalidate_stream(stream_id);
+        }
+    }
+
+    /// Time since the stream disconnected; None while connected
+    pub fn disconnected_for(&self, stream_id: u8) -> Option<Duration> {
+        self.disconnected.get(&stream_id).map(Instant::elapsed)
+    }
+
+    /// Buffer a received frame; present_stream() shows it on schedule
+    pub fn queue_frame(&mut self, stream_id: u8, data: Vec<u8>) {
+        self.stream_reconnected(stream_id);
+        let config = self.presentation_config();
+        self.presentation
+            .entry(stream_id)
+            .or_insert_with(|| PresentationBuffer::new(config))
+            .push(data);
+    }
+
+    /// Render the stream's due (or held / interpolated) frame, or its placeholder
+    /// while disconnected; None before the first frame
+    pub fn present_stream(&mut self, stream_id: u8) -> Option<FrameKind> {
+        if let Some(elapsed) = self.disconnected_for(stream_id) {
+            if self.render_placeholder(stream_id, elapsed) {
+                return Some(FrameKind::Placeholder);
             }
         }
+        let frame = self.presentation.get_mut(&stream_id)?.present()?;
+        self.render_frame(stream_id, &frame.data);
+        Some(frame.kind)
     }
 
-    fn dispatch_to_hardware(&self, data: &[u8], bounds: (i32, i32, u32, u32), stream_id: u8) {
+    /// false when the stream has no viewport or its placeholder freezes the last frame
+    fn render_placeholder(&self, stream_id: u8, elapsed: Duration) -> bool {
+        let default = Placeholder::default();
+        let placeholder = self.placeholders.get(&stream_id).unwrap_or(&default);
+        if placeholder.mode == PlaceholderMode::Freeze {
+            return false;
+        }
+        let Some((_, _, width, height)) = self.content_bounds(stream_id) else {
+            return false;
+        };
+        match placeholder.render(width, height, elapsed) {
+            Some(frame) => {
+                // Content-hashed: redrawn only when the elapsed time ticks
+                self.render_frame(stream_id, &frame);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Where the stream's pixels go, inside the decorations
+    fn content_bounds(&self, stream_id: u8) -> Option<(i32, i32, u32, u32)> {
+        if SINGULARITY_LOCK.load(Ordering::SeqCst) {
+            return Some(self.singularity.get_exclusive_bounds());
+        }
+        let viewport = self.multitary.get_viewport_for_stream(stream_id).filter(|v| v.active)?;
+        let bounds = (viewport.x, viewport.y, viewport.width, viewport.height);
+        match self.decorations.as_ref().filter(|_| self.decorated_streams.contains(&stream_id)) {
+            Some(deco) => Some(deco.content_bounds(bounds)),
+            None => Some(bounds),
+        }
+    }
+
+    /// Per-stream override of the wasma.in.conf latency budget
+    pub fn set_latency_budget(&mut self, stream_id: u8, budget: Duration) {
+        let config = self.presentation_config();
+        let buffer = self.presentation.entry(stream_id).or_insert_with(|| PresentationBuffer::new(config));
+        buffer.set_config(PresentationConfig { latency_budget: budget, ..buffer.config() });
+    }
+
+    pub fn presentation_stats(&self, stream_id: u8) -> Option<PresentationStats> {
+        self.presentation.get(&stream_id).map(PresentationBuffer::stats)
+    }
+
+    fn presentation_config(&self) -> PresentationConfig {
+        let limits = &self.config.resource_limits;
+        PresentationConfig {
+            latency_budget: limits.latency_budget_ms
+                .map_or(presentation_buffer::DEFAULT_LATENCY_BUDGET, Duration::from_millis),
+            interpolate: limits.frame_interpolation,
+            ..PresentationConfig::default()
+        }
+    }
+
+    /// Feed a frame's end-to-end latency; on a level change the caller sends
+    /// frame_request_size() to the remote via ProtocolStream::request_resize
+    pub fn report_latency(&mut self, stream_id: u8, latency: Duration) -> Option<ScaleChange> {
+        let change = self.scalers.entry(stream_id).or_default().record(latency);
+        if let Some(change) = change {
+            println!("📉 Stream {} resolution {:.0}% → {:.0}%", stream_id, change.from * 100.0, change.to * 100.0);
+        }
+        change
+    }
+
+    /// Result of the last request_resize: remote resizing or local downscale
+    pub fn set_remote_resize(&mut self, stream_id: u8, supported: bool) {
+        let method = if supported { ScaleMethod::RemoteResize } else { ScaleMethod::Downscale };
+        self.scalers.entry(stream_id).or_default().set_method(method);
+    }
+
+    pub fn stream_scale(&self, stream_id: u8) -> f32 {
+        self.scalers.get(&stream_id).map_or(1.0, ResolutionScaler::scale)
+    }
+
+    /// Frame size to ask the remote for, given the stream's viewport and scale
+    pub fn frame_request_size(&self, stream_id: u8) -> Option<(u32, u32)> {
+        let viewport = self.multitary.get_viewport_for_stream(stream_id)?;
+        Some(adaptive_resolution::scaled_size(viewport.width, viewport.height, self.stream_scale(stream_id)))
+    }
+
+    /// `regions` are in frame pixels, relative to `bounds`
+    fn dispatch_to_hardware(&self, data: &[u8], bounds: (i32, i32, u32, u32), regions: &[DamageRect], stream_id: u8) {
+        if self.off_display.load(Ordering::SeqCst) {
+            return;
+        }
         if self.config.resource_limits.scope_level > 0 {
-            self.blit_native_vram(data, bounds, stream_id);
+            self.blit_native_vram(data, bounds, regions, stream_id);
         } else {
-            self.blit_os_fallback(data, bounds, stream_id);
+            self.blit_os_fallback(data, bounds, regions, stream_id);
         }
     }
 
-    fn blit_native_vram(&self, data: &[u8], bounds: (i32, i32, u32, u32), _stream_id: u8) {
+    fn blit_native_vram(&self, data: &[u8], bounds: (i32, i32, u32, u32), regions: &[DamageRect], _stream_id: u8) {
         let (x, y, w, h) = bounds;
-        
-        // Calculate offset in framebuffer
-        let offset = (y as usize * self.width as usize + x as usize) * 4; // 4 bytes per pixel (RGBA)
-        let max_size = (w * h * 4) as usize;
-        let copy_size = data.len().min(max_size);
-        
-        // Simulate direct VRAM write
-        // In production, this would write to actual VRAM through DRM/KMS or similar
-        let _vram_operation = WasmaVramWrite {
-            offset,
-            size: copy_size,
-            bounds: (x, y, w, h),
-        };
-        
-        // For now, just acknowledge the operation
-        if copy_size > 0 {
-            // VRAM write would happen here
+
+        for region in regions.iter().filter_map(|r| r.clip(w, h)) {
+            // One write per row: the frame is w pixels wide, the framebuffer self.width
+            for (row, pixels) in region_rows(data, w, region) {
+                // Calculate offset in framebuffer
+                let offset = ((y as usize + row as usize) * self.width as usize + x as usize + region.x as usize) * 4; // 4 bytes per pixel (RGBA)
+
+                // Simulate direct VRAM write
+                // In production, this would write to actual VRAM through DRM/KMS or similar
+                let _vram_operation = WasmaVramWrite {
+                    offset,
+                    size: pixels.len(),
+                    bounds: (x + region.x as i32, y + row as i32, region.width, 1),
+                };
+                self.uploaded_bytes.fetch_add(pixels.len() as u64, Ordering::Relaxed);
+            }
         }
     }
 
-    fn blit_os_fallback(&self, data: &[u8], bounds: (i32, i32, u32, u32), _stream_id: u8) {
+    fn blit_os_fallback(&self, data: &[u8], bounds: (i32, i32, u32, u32), regions: &[DamageRect], _stream_id: u8) {
         let (x, y, w, h) = bounds;
-        
+
         // Fallback: Use OS-specific rendering (X11 PutImage, Wayland subsurface, etc.)
-        // This would integrate with the WGClient for actual rendering
-        let _fallback_operation = WasmaFallbackRender {
-            data_size: data.len(),
-            bounds: (x, y, w, h),
-        };
+        // This would integrate with the WGClient for actual rendering; one
+        // sub-image per damaged region
+        for region in regions.iter().filter_map(|r| r.clip(w, h)) {
+            let data_size: usize = region_rows(data, w, region).map(|(_, pixels)| pixels.len()).sum();
+            let _fallback_operation = WasmaFallbackRender {
+                data_size,
+                bounds: (x + region.x as i32, y + region.y as i32, region.width, region.height),
+            };
+            self.uploaded_bytes.fetch_add(data_size as u64, Ordering::Relaxed);
+        }
     }
 
     pub fn resize(&mut self, new_width: u32, new_height: u32) {
         self.width = new_width;
         self.height = new_height;
         self.multitary.update_resolution(new_width, new_height);
+        if let Some(snapper) = self.snapper.as_mut() {
+            snapper.set_screen(new_width, new_height);
+            snapper.sync_viewports(&mut self.multitary);
+        }
+        self.invalidate_all();
     }
 
     pub fn get_dimensions(&self) -> (u32, u32) {
@@ -105,12 +550,215 @@ impl WindowClient {
         &self.config
     }
 
+    pub fn set_decorations(&mut self, decorations: Option<Decorations>) {
+        self.decorations = decorations;
+        self.titlebars.lock().unwrap().clear();
+        self.invalidate_all();
+    }
+
+    /// Text on the stream's SSD titlebar (usually the window title)
+    pub fn set_stream_title(&mut self, stream_id: u8, title: impl Into<String>) {
+        self.titles.insert(stream_id, title.into());
+    }
+
+    /// Streams that cannot draw their own CSD get a WASMA titlebar
+    pub fn set_stream_decorated(&mut self, stream_id: u8, decorated: bool) {
+        if decorated {
+            self.decorated_streams.insert(stream_id);
+        } else {
+            self.decorated_streams.remove(&stream_id);
+        }
+        self.invalidate_stream(stream_id);
+    }
+
+    /// Re-tile the viewports with another layout
+    pub fn set_layout_policy(&mut self, policy: LayoutPolicy) {
+        self.multitary.set_layout_policy(policy);
+        self.invalidate_all();
+    }
+
+    pub fn layout_policy(&self) -> LayoutPolicy {
+        self.multitary.layout_policy()
+    }
+
+    /// Lay the viewports out over these outputs (startup, hotplug)
+    pub fn set_monitors(&mut self, monitors: MonitorMap) {
+        let primary = monitors.primary();
+        let (width, height) = (primary.width, primary.height);
+        self.multitary.set_monitors(monitors);
+        if let Some(snapper) = self.snapper.as_mut() {
+            snapper.set_screen(width, height);
+            snapper.sync_viewports(&mut self.multitary);
+        }
+        self.invalidate_all();
+    }
+
+    /// Keep a stream on one output; None returns it to the primary
+    pub fn pin_stream(&mut self, stream_id: u8, output: Option<&str>) {
+        match output {
+            Some(output) => self.multitary.pin_stream(stream_id, output),
+            None => self.multitary.unpin_stream(stream_id),
+        }
+        self.invalidate_all();
+    }
+
     pub fn enter_singularity(&mut self, stream_id: u8) {
         self.singularity.enter_singularity_mode(stream_id);
+        self.multitary.set_singularity_stream(Some(stream_id));
+        self.invalidate_all();
     }
 
     pub fn exit_singularity(&mut self) {
         self.singularity.exit_singularity_mode();
+        self.multitary.set_singularity_stream(None);
+        self.invalidate_all();
+    }
+
+    /// Route pointer input through the viewports; moved/resized streams are
+    /// repainted. The Pointer actions are left for the caller to deliver
+    pub fn route_pointer(&mut self, event: PointerEvent, modifiers: Modifiers) -> Vec<InputAction> {
+        let mut actions = match self.snap_pointer(event, modifiers) {
+            // The focus policy sees grabbed input too
+            Some(grabbed) => self.multitary.feed_focus_engine(event).into_iter().chain(grabbed).collect(),
+            None => self.multitary.route_pointer(event, modifiers),
+        };
+        self.focus_bound_streams(&mut actions);
+        self.apply_input_actions(&actions);
+        actions
+    }
+
+    /// Window focus by policy (click, follows mouse, sloppy) for the handler
+    /// windows behind the streams; see bind_window
+    pub fn attach_focus_engine(&mut self, engine: FocusEngine) {
+        self.multitary.attach_focus_engine(engine);
+    }
+
+    /// Apply a hover focus whose delay has passed
+    pub fn tick_focus(&mut self, now: Instant) -> Vec<InputAction> {
+        let mut actions: Vec<InputAction> = self.multitary.tick_focus(now).into_iter().collect();
+        self.focus_bound_streams(&mut actions);
+        actions
+    }
+
+    /// Input focus follows window focus onto the window's stream
+    fn focus_bound_streams(&mut self, actions: &mut Vec<InputAction>) {
+        let focused: Vec<u8> = actions.iter()
+            .filter_map(|action| match action {
+                InputAction::WindowFocused(window_id) => self.snapper.as_ref()?.stream_for_window(*window_id),
+                _ => None,
+            })
+            .collect();
+        for stream_id in focused {
+            actions.extend(self.multitary.set_focus(Some(stream_id)));
+        }
+    }
+
+    /// Super+drag on a window-bound stream and the rest of that grab go to
+    /// the snapper (edge resistance, drop-to-snap); None leaves the event to
+    /// the multitary
+    fn snap_pointer(&mut self, event: PointerEvent, modifiers: Modifiers) -> Option<Vec<InputAction>> {
+        if self.multitary.singularity_stream().is_some() {
+            return None;
+        }
+        let snapper = self.snapper.as_mut()?;
+
+        let Some(stream_id) = self.snap_stream else {
+            let PointerEvent::ButtonPress { x, y, button } = event else {
+                return None;
+            };
+            if !modifiers.super_key {
+                return None;
+            }
+            let stream_id = self.multitary.handle_input_focus(x, y)?;
+            let window_id = snapper.window_for_stream(stream_id)?;
+            let began = match button {
+                BUTTON_LEFT => snapper.begin_move(window_id, x, y),
+                BUTTON_RIGHT => snapper.begin_resize_at(window_id, x, y),
+                _ => return None,
+            };
+            if let Err(e) = began {
+                eprintln!("⚠️  Grab of window {} refused: {}", window_id, e);
+                return None;
+            }
+            self.snap_stream = Some(stream_id);
+            return Some(self.multitary.set_focus(Some(stream_id)).into_iter().collect());
+        };
+
+        let resizing = snapper.grabbed().is_some_and(|(_, resize)| resize);
+        let result = match event {
+            PointerEvent::Motion { x, y } => snapper.motion(x, y),
+            PointerEvent::ButtonRelease { x, y, .. } => {
+                self.snap_stream = None;
+                snapper.end_grab(x, y)
+            }
+            // Other buttons are swallowed until the grab ends
+            PointerEvent::ButtonPress { .. } => return Some(Vec::new()),
+            // The pointer left the output mid-drag: the window stays where it is
+            PointerEvent::Leave => {
+                self.snap_stream = None;
+                snapper.cancel_grab();
+                return None;
+            }
+        };
+        if let Err(e) = result {
+            eprintln!("⚠️  Grab of stream {} ended: {}", stream_id, e);
+            self.snap_stream = None;
+            return Some(Vec::new());
+        }
+
+        snapper.sync_viewports(&mut self.multitary);
+        let viewport = self.multitary.get_viewport_for_stream(stream_id)?.clone();
+        Some(vec![match resizing {
+            true => InputAction::Resized { stream_id, viewport },
+            false => InputAction::Moved { stream_id, viewport },
+        }])
+    }
+
+    /// Window manager bindings for route_key (settings.conf [hotkeys])
+    pub fn set_hotkeys(&mut self, hotkeys: WmHotkeys) {
+        match hotkeys.combo(WmAction::ToggleSingularity) {
+            Some(combo) => self.multitary.set_singularity_shortcut(combo.clone()),
+            None => self.multitary.disable_singularity_shortcut(),
+        }
+        self.hotkeys = hotkeys;
+    }
+
+    /// Route a key press; window manager hotkeys act here (the singularity
+    /// shortcut switches modes), everything else goes to the focused stream
+    pub fn route_key(&mut self, combo: &KeyCombo) -> Vec<InputAction> {
+        let actions = match self.hotkeys.action(combo) {
+            Some(WmAction::CycleFocus) => self.multitary.cycle_focus(false).into_iter().collect(),
+            Some(WmAction::CycleFocusBack) => self.multitary.cycle_focus(true).into_iter().collect(),
+            Some(WmAction::CloseWindow) => self.multitary.focused_stream()
+                .map(|stream_id| InputAction::CloseRequested { stream_id })
+                .into_iter()
+                .collect(),
+            Some(WmAction::NextLayout) => {
+                let next = self.layout_policy().next();
+                self.set_layout_policy(next);
+                vec![InputAction::LayoutChanged(next)]
+            }
+            Some(WmAction::ToggleSingularity) | None => self.multitary.route_key(combo),
+        };
+        self.apply_input_actions(&actions);
+        actions
+    }
+
+    fn apply_input_actions(&mut self, actions: &[InputAction]) {
+        for action in actions {
+            match action {
+                InputAction::EnterSingularity(stream_id) => self.enter_singularity(*stream_id),
+                InputAction::ExitSingularity => self.exit_singularity(),
+                // Uncovered areas of other streams need repainting too
+                InputAction::Moved { .. } | InputAction::Resized { .. } => self.invalidate_all(),
+                _ => {}
+            }
+        }
+    }
+
+    /// Focus-in/focus-out events for the protocol streams
+    pub fn subscribe_focus(&mut self) -> Receiver<StreamFocusEvent> {
+        self.multitary.subscribe_focus()
     }
 
     pub fn is_singularity_active(&self) -> bool {
@@ -118,6 +766,27 @@ impl WindowClient {
     }
 }
 
+/// `data` stretched to the physical `bounds` when it is the logical-size
+/// frame of a `scale`d output; None when it already fits
+fn fit_hidpi(data: &[u8], bounds: (i32, i32, u32, u32), scale: f32) -> Option<Vec<u8>> {
+    let (_, _, width, height) = bounds;
+    if scale <= 1.0 {
+        return None;
+    }
+    let (logical_w, logical_h) = ((width as f32 / scale).round() as u32, (height as f32 / scale).round() as u32);
+    (data.len() == logical_w as usize * logical_h as usize * 4)
+        .then(|| adaptive_resolution::upscale_rgba(data, logical_w, logical_h, width, height))
+}
+
+/// Rows of `region` in an RGBA frame `frame_width` pixels wide, stopping where a short frame ends
+fn region_rows(data: &[u8], frame_width: u32, region: DamageRect) -> impl Iterator<Item = (u32, &[u8])> {
+    (region.y..region.y + region.height).map_while(move |row| {
+        let start = (row as usize * frame_width as usize + region.x as usize) * 4;
+        let rest = data.get(start..).filter(|rest| !rest.is_empty())?;
+        Some((row, &rest[..rest.len().min(region.width as usize * 4)]))
+    })
+}
+
 // Helper structures for VRAM operations
 #[allow(dead_code)]
 struct WasmaVramWrite {
@@ -136,6 +805,7 @@ struct WasmaFallbackRender {
 mod tests {
     use super::*;
     use crate::parser::ConfigParser;
+    use crate::monitors::Monitor;
 
     #[test]
     fn test_window_client_creation() {
@@ -163,4 +833,244 @@ mod tests {
         client.exit_singularity();
         assert!(!client.is_singularity_active());
     }
+
+    #[test]
+    fn test_identical_frames_skip_composite() {
+        let parser = ConfigParser::new(None);
+        let config = parser.parse(&parser.generate_default_config()).unwrap();
+        let mut client = WindowClient::new(config, 640, 480);
+
+        let frame = vec![7u8; 64];
+        client.render_frame(0, &frame);
+        client.render_frame(0, &frame);
+        assert_eq!(client.skipped_frames(), 1);
+
+        client.render_frame(0, &[8u8; 64]);
+        assert_eq!(client.skipped_frames(), 1);
+
+        // Sequence numbers skip without hashing
+        client.render_frame_seq(0, 41, &frame);
+        client.render_frame_seq(0, 41, &frame);
+        assert_eq!(client.skipped_frames(), 2);
+
+        // A resize recomposites even an unchanged frame
+        client.resize(800, 600);
+        client.render_frame_seq(0, 41, &frame);
+        assert_eq!(client.skipped_frames(), 2);
+    }
+
+    #[test]
+    fn test_damaged_frames_upload_changed_regions() {
+        let parser = ConfigParser::new(None);
+        let config = parser.parse(&parser.generate_default_config()).unwrap();
+        let client = WindowClient::new(config, 640, 480);
+        let (w, h) = client.frame_request_size(0).unwrap();
+        let whole = (w * h * 4) as u64;
+
+        let mut frame = vec![1u8; whole as usize];
+        client.render_frame(0, &frame);
+        assert_eq!(client.uploaded_bytes(), whole);
+
+        // Only the 16x8 block the sender reported is uploaded
+        frame[0] = 2;
+        client.render_frame_damaged(0, &frame, &[DamageRect::new(0, 0, 16, 8)]);
+        assert_eq!(client.uploaded_bytes(), whole + 16 * 8 * 4);
+
+        // Damage reaching past the frame is clipped to it
+        frame[0] = 3;
+        client.render_frame_damaged(0, &frame, &[DamageRect::new(w - 4, h - 1, 16, 16)]);
+        assert_eq!(client.uploaded_bytes(), whole + 16 * 8 * 4 + 4 * 4);
+
+        // full_redraw: the next frame goes up whole whatever its damage
+        client.full_redraw();
+        frame[0] = 4;
+        client.render_frame_damaged(0, &frame, &[DamageRect::new(0, 0, 1, 1)]);
+        assert_eq!(client.uploaded_bytes(), 2 * whole + 16 * 8 * 4 + 4 * 4);
+    }
+
+    #[test]
+    fn test_detached_session_blits_nothing() {
+        let runtime = std::env::temp_dir().join(format!("wasma-detach-blit-{}", std::process::id()));
+        let mut env = wsdg_xdg::WsdgEnv::new();
+        env.set("XDG_RUNTIME_DIR", runtime.to_string_lossy());
+        let handler = WindowHandler::with_env(wbackend::ResourceMode::Auto, &env);
+
+        let parser = ConfigParser::new(None);
+        let config = parser.parse(&parser.generate_default_config()).unwrap();
+        let mut client = WindowClient::new(config, 640, 480);
+        client.follow_session(&handler);
+        let (w, h) = client.frame_request_size(0).unwrap();
+        let whole = (w * h * 4) as u64;
+
+        client.render_frame(0, &vec![1u8; whole as usize]);
+        assert_eq!(client.uploaded_bytes(), whole);
+
+        handler.detach_session(crate::window_handling::DetachMode::Headless).unwrap();
+        client.render_frame(0, &vec![2u8; whole as usize]);
+        client.full_redraw();
+        client.render_frame(0, &vec![3u8; whole as usize]);
+        assert_eq!(client.uploaded_bytes(), whole);
+
+        // Back on the display after unlocking: the next frame goes up whole
+        std::fs::remove_file(handler.session_lock().marker_path()).unwrap();
+        handler.attach_session().unwrap();
+        client.render_frame(0, &vec![3u8; whole as usize]);
+        assert_eq!(client.uploaded_bytes(), 2 * whole);
+
+        let _ = std::fs::remove_dir_all(&runtime);
+    }
+
+    #[test]
+    fn test_hidpi_frames_fill_viewport() {
+        let parser = ConfigParser::new(None);
+        let config = parser.parse(&parser.generate_default_config()).unwrap();
+        let mut client = WindowClient::new(config, 640, 480);
+        client.set_monitors(MonitorMap::new(vec![Monitor::new("eDP-1", 0, 0, 640, 480).with_scale(2.0)]).unwrap());
+        let (w, h) = client.frame_request_size(0).unwrap();
+        let physical = (w * h * 4) as u64;
+
+        // A frame rendered at the logical size is stretched over the whole viewport
+        client.render_frame(0, &vec![1u8; (w / 2 * h / 2 * 4) as usize]);
+        assert_eq!(client.uploaded_bytes(), physical);
+        // A HiDPI-aware stream already sends physical pixels
+        client.render_frame(0, &vec![2u8; physical as usize]);
+        assert_eq!(client.uploaded_bytes(), 2 * physical);
+
+        let stretched = adaptive_resolution::upscale_rgba(&[1, 1, 1, 1, 2, 2, 2, 2], 2, 1, 4, 2);
+        assert_eq!(stretched.chunks(4).map(|px| px[0]).collect::<Vec<_>>(), [1, 1, 2, 2, 1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn test_titlebar_repaints_on_focus_and_title() {
+        let parser = ConfigParser::new(None);
+        let config = parser.parse(&parser.generate_default_config()).unwrap();
+        let mut client = WindowClient::new(config, 640, 480);
+        client.set_decorations(Some(Decorations::default()));
+        client.set_stream_decorated(0, true);
+
+        let viewport = client.multitary.get_viewport_for_stream(0).unwrap();
+        let frame = (viewport.x, viewport.y, viewport.width, viewport.height);
+        let deco = Decorations::default();
+        let (_, _, bar_w, bar_h) = deco.titlebar_bounds(frame);
+        let (_, _, w, h) = deco.content_bounds(frame);
+        let (bar, whole) = ((bar_w * bar_h * 4) as u64, (w * h * 4) as u64);
+
+        client.render_frame(0, &vec![1u8; whole as usize]);
+        assert_eq!(client.uploaded_bytes(), bar + whole);
+
+        // New content under an unchanged titlebar: only the content goes up
+        client.render_frame(0, &vec![2u8; whole as usize]);
+        assert_eq!(client.uploaded_bytes(), bar + 2 * whole);
+
+        // Focus and title changes repaint the titlebar of a static window
+        client.multitary.set_focus(Some(0));
+        client.render_frame(0, &vec![2u8; whole as usize]);
+        assert_eq!(client.uploaded_bytes(), 2 * bar + 2 * whole);
+        client.set_stream_title(0, "Files");
+        client.render_frame(0, &vec![2u8; whole as usize]);
+        client.render_frame(0, &vec![2u8; whole as usize]);
+        assert_eq!(client.uploaded_bytes(), 3 * bar + 2 * whole);
+    }
+
+    #[test]
+    fn test_super_drag_snaps_bound_window() {
+        use crate::window_handling::WindowGeometry;
+        use wbackend::ResourceMode;
+
+        let parser = ConfigParser::new(None);
+        let config = parser.parse(&parser.generate_default_config()).unwrap();
+        let mut client = WindowClient::new(config, 1280, 720);
+        let handler = Arc::new(WindowHandler::new(ResourceMode::Manual));
+        let id = handler.create_window(
+            "Drag".to_string(),
+            "test.drag".to_string(),
+            WindowGeometry { x: 200, y: 150, width: 400, height: 300 },
+            None,
+            ResourceMode::Manual,
+        ).unwrap();
+
+        // The snapper takes the client's screen, not the size it was built with
+        let mut snapper = WindowSnapper::new(handler.clone(), 1920, 1080);
+        snapper.set_animation_duration(Duration::ZERO);
+        client.attach_snapper(snapper);
+        client.bind_window(0, id);
+        let vp = client.multitary.get_viewport_for_stream(0).unwrap();
+        assert_eq!((vp.x, vp.y, vp.width, vp.height), (200, 150, 400, 300));
+
+        let none = Modifiers::default();
+        let super_key = Modifiers { super_key: true, ..none };
+        client.route_pointer(PointerEvent::ButtonPress { x: 300, y: 200, button: BUTTON_LEFT }, super_key);
+        let actions = client.route_pointer(PointerEvent::Motion { x: 350, y: 260 }, none);
+        assert!(matches!(&actions[..], [InputAction::Moved { stream_id: 0, viewport }] if (viewport.x, viewport.y) == (250, 210)));
+        let g = handler.get_window(id).unwrap().geometry;
+        assert_eq!((g.x, g.y), (250, 210));
+
+        // Dropped on the left edge: left half of the 1280x720 screen
+        client.route_pointer(PointerEvent::Motion { x: 0, y: 360 }, none);
+        client.route_pointer(PointerEvent::ButtonRelease { x: 0, y: 360, button: BUTTON_LEFT }, none);
+        let vp = client.multitary.get_viewport_for_stream(0).unwrap();
+        assert_eq!((vp.x, vp.y, vp.width, vp.height), (0, 0, 640, 720));
+
+        // A plain click is the stream's again
+        let actions = client.route_pointer(PointerEvent::ButtonPress { x: 100, y: 100, button: BUTTON_LEFT }, none);
+        assert!(actions.iter().any(|a| matches!(a, InputAction::Pointer { stream_id: 0, .. })));
+
+        // Output resize re-applies the zone
+        client.resize(1000, 600);
+        let g = handler.get_window(id).unwrap().geometry;
+        assert_eq!((g.width, g.height), (500, 600));
+    }
+
+    #[test]
+    fn test_focus_policy_follows_pointer_onto_streams() {
+        use crate::focus_policy::{FocusConfig, FocusPolicy};
+        use crate::parser::{Protocol, ProtocolConfig};
+        use crate::window_handling::WindowGeometry;
+        use wbackend::ResourceMode;
+
+        let parser = ConfigParser::new(None);
+        let mut config = parser.parse(&parser.generate_default_config()).unwrap();
+        config.uri_handling.multi_instances = true;
+        config.uri_handling.singularity_instances = false;
+        config.uri_handling.protocols = (0..2)
+            .map(|i| ProtocolConfig { protocol: Protocol::Http, ip: "127.0.0.1".parse().unwrap(), port: 8080 + i, domain: None })
+            .collect();
+        let mut client = WindowClient::new(config, 1200, 900);
+
+        let handler = Arc::new(WindowHandler::new(ResourceMode::Manual));
+        let window = |y: i32| handler.create_window(
+            format!("W{}", y),
+            "test.focus".to_string(),
+            WindowGeometry { x: 0, y, width: 1200, height: 450 },
+            None,
+            ResourceMode::Manual,
+        ).unwrap();
+        let (top, bottom) = (window(0), window(450));
+
+        client.attach_snapper(WindowSnapper::new(handler.clone(), 1200, 900));
+        client.bind_window(0, top);
+        client.bind_window(1, bottom);
+        client.attach_focus_engine(FocusEngine::new(handler.clone(), FocusConfig {
+            policy: FocusPolicy::FocusFollowsMouse,
+            delay: Duration::ZERO,
+            prevent_stealing: true,
+        }));
+
+        // Hovering is enough: the window and its stream take focus
+        let none = Modifiers::default();
+        let actions = client.route_pointer(PointerEvent::Motion { x: 100, y: 100 }, none);
+        assert!(actions.contains(&InputAction::WindowFocused(top)));
+        assert!(actions.contains(&InputAction::FocusChanged { previous: None, current: Some(0) }));
+        assert_eq!(handler.get_focused_window(), Some(top));
+
+        client.route_pointer(PointerEvent::Motion { x: 100, y: 600 }, none);
+        assert_eq!(handler.get_focused_window(), Some(bottom));
+        assert_eq!(client.multitary.focused_stream(), Some(1));
+
+        // A Super+drag taken by the snapper still reaches the focus engine
+        let super_key = Modifiers { super_key: true, ..none };
+        let actions = client.route_pointer(PointerEvent::ButtonPress { x: 100, y: 100, button: BUTTON_LEFT }, super_key);
+        assert!(actions.contains(&InputAction::WindowFocused(top)));
+        assert_eq!(client.multitary.focused_stream(), Some(0));
+    }
 }
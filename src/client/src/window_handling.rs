@@ -3,26 +3,64 @@
 // Window creation with Iced GUI
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
-use wbackend::{Assignment, ExecutionMode, ResourceMode, WBackend};
+use serde::{Deserialize, Serialize};
+use wbackend::{
+    Assignment, BackendSnapshot, CoreClass, CorePlacement, CyclePlan, ExecutionMode, LeaseDecision, PlannedAction,
+    CycleCadence, MonitorReport, QuotaError, QuotaGroup, QuotaOverflow, RecoveryReport, ResourceMode, WBackend,
+};
+use wbackend::osd::{self, OsdIcon};
 use iced::{
-    Application, Command, Element, Settings, Theme,
-    widget::{button, column, container, row, text, scrollable, Space},
+    Application, Command, Element, Settings, Subscription, Theme,
+    widget::{button, column, container, image, row, text, text_input, scrollable, Space},
     executor, window, Length, Color, Background,
 };
 use iced::window::{Id as WindowId, Position};
+use iced::keyboard::{self, key::Named};
 
 // Imports from other modules (within same crate)
-use crate::parser::{ConfigParser, WasmaConfig, Protocol};
-use wsdg_app_manifest::manifest_parser::{ManifestParser, CpuCoreServe};
+use crate::parser::{self, ConfigParser, LeaseExpiry, QuotaGroupConfig, WasmaConfig, Protocol};
+use crate::context_pool::{ContextPool, RendererContext};
+use crate::cpu_history::{self, CpuHistory, CpuSampler, CpuSource};
+use crate::resource_graphs::{self, ResourceGraph, ResourceHistory, ResourceMetric, ResourceSample};
+use crate::telemetry::{self, ErrorCategory, Telemetry};
+use crate::crash_report;
+use crate::session_lock::SessionLock;
+use crate::global_hotkeys::{self, HotkeyDaemon, HotkeyRegistry};
+use crate::hotkeys::{self, WmHotkeys};
+use crate::window_multitary::LayoutPolicy;
+use crate::control::{self, ControlDaemon};
+use crate::decorations::{self, Decorations, DecorationTheme};
+use crate::icon_badges::{BadgeCompositor, ComposedIcon, IconBadge};
+use crate::permission_enforcement::EnforcementReport;
+use crate::window_snapping::{SnapDirection, WindowSnapper};
+use crate::focus_policy::{FocusConfig, FocusEngine, FocusPolicy};
+use crate::command_palette::{self, CommandRegistry, PaletteCommand};
+use crate::protocol_control::{EndpointState, EndpointStats, ProtocolControl};
+use crate::t;
+use crate::accessibility;
+use crate::monitors::{MonitorMap, MonitorWatcher, HOTPLUG_POLL_INTERVAL};
+use crate::pixel_format::FrameHeader;
+use crate::presenter::Presenter;
+use crate::thumbnails::{self, FrameSource, RgbaImage};
+use crate::window_placement::{self, Placement, TypeBehavior};
+use crate::workspaces::{self, Workspace};
+use crate::window_rules::{self, RuleActions};
+use wsdg_xdg::{IcoFormat, IconInfo, IconSize, KioskPolicy, WsdgEnv, WsdgIcoCtl, WsdgSettingsManager};
+use wsdg_app_manifest::manifest_parser::{
+    ManifestParser, CpuCoreServe, WasmaManifest, WindowConfig, ManifestWindowType, InitialWindowState,
+};
 use wsdg_app_manifest::source_parser::{SourceParser, PermissionSource, FileException};
 
 // ============================================================================
 // WINDOW STRUCTURES
 // ============================================================================
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum WindowState {
     Normal,
     Minimized,
@@ -31,7 +69,7 @@ pub enum WindowState {
     Hidden,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum WindowType {
     Normal,
     Dialog,
@@ -44,7 +82,7 @@ pub enum WindowType {
     Notification,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct WindowGeometry {
     pub x: i32,
     pub y: i32,
@@ -52,7 +90,7 @@ pub struct WindowGeometry {
     pub height: u32,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum BackendType {
     Native,
     Wayland,
@@ -61,7 +99,7 @@ pub enum BackendType {
 }
 
 /// Resource limits - WBackend + manifest + wasma.in.conf
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceLimits {
     // wbackend-compatible fields (required)
     pub max_memory_mb: u64,
@@ -69,9 +107,18 @@ pub struct ResourceLimits {
     pub cpu_cores: Vec<usize>,
     pub lease_duration: Duration,
     pub execution_mode: Option<ExecutionMode>,
+    /// Auto-bind core preference (manifest `cpu_placement`)
+    #[serde(default)]
+    pub core_placement: CorePlacement,
+    /// P-core / E-core request on hybrid CPUs (manifest `core_class`)
+    #[serde(default)]
+    pub core_class: Option<CoreClass>,
     // WASMA-specific fields (optional)
     pub renderer: String,
     pub pixel_load_limit: u32,
+    /// wasma.in.conf quota group sharing its budget with other windows
+    #[serde(default)]
+    pub quota_group: Option<String>,
 }
 
 impl Default for ResourceLimits {
@@ -81,22 +128,30 @@ impl Default for ResourceLimits {
             max_gpu_memory_mb: 256,
             cpu_cores: Vec::new(),
             execution_mode: Some(ExecutionMode::GpuPreferred),
+            core_placement: CorePlacement::default(),
+            core_class: None,
             lease_duration: Duration::from_secs(30),
             renderer: "cpu_renderer".to_string(),
             pixel_load_limit: 50,
+            quota_group: None,
         }
     }
 }
 
 /// Permissions - from source
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PermissionScope {
     pub can_access_network: bool,
     pub can_access_filesystem: bool,
     pub can_spawn_children: bool,
     pub can_use_gpu: bool,
     pub allowed_protocols: Vec<String>,
+    /// Entries of `allowed_protocols` granted by wasma.in.conf, not the manifest;
+    /// only these are revoked when the config drops them
+    #[serde(default)]
+    pub config_protocols: Vec<String>,
     pub sandbox_level: u8,
+    pub can_register_hotkeys: bool,
 }
 
 impl Default for PermissionScope {
@@ -107,15 +162,19 @@ impl Default for PermissionScope {
             can_spawn_children: false,
             can_use_gpu: true,
             allowed_protocols: vec!["http".to_string(), "https".to_string()],
+            config_protocols: Vec::new(),
             sandbox_level: 5,
+            can_register_hotkeys: false,
         }
     }
 }
 
 /// Main window structure
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Window {
     pub id: u64,
+    // iced handle, only meaningful inside the GUI process
+    #[serde(skip)]
     pub iced_window_id: Option<WindowId>,
     pub title: String,
     pub app_id: String,
@@ -134,9 +193,65 @@ pub struct Window {
     pub backend_type: BackendType,
     pub assignment_id: Option<u32>,
     pub resource_mode: ResourceMode,
+    /// Titlebar drawn by WASMA (stream has no CSD of its own)
+    pub server_decorations: bool,
+    /// Theme icon, or one extracted from the app's executable
+    #[serde(default)]
+    pub icon: Option<PathBuf>,
+    /// Fixed-size windows keep their width/height on geometry changes
+    #[serde(default = "default_resizable")]
+    pub resizable: bool,
+    /// Stacked above windows without the flag
+    #[serde(default)]
+    pub always_on_top: bool,
+    /// Status drawn over the icon (notifications, recording, paused)
+    #[serde(default)]
+    pub badges: Vec<IconBadge>,
+    /// What was restricted on the process launched for this window
+    #[serde(default)]
+    pub enforcement: Option<EnforcementReport>,
+    /// Left out of taskbars and window lists (splash screens, menus, tooltips)
+    #[serde(default)]
+    pub skip_taskbar: bool,
+    /// Virtual desktop the window lives on (see workspaces)
+    #[serde(default = "workspaces::default_workspace")]
+    pub workspace: u32,
+    /// While visible, takes the parent's input and focus; closes with it
+    #[serde(default)]
+    pub modal: bool,
 }
 
-#[derive(Debug, Clone)]
+fn default_resizable() -> bool {
+    true
+}
+
+/// Change notifications delivered to WindowHandler::subscribe receivers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WindowEvent {
+    WindowCreated { window_id: u64, title: String, app_id: String },
+    WindowClosed { window_id: u64 },
+    /// `current` is None when focus went to the desktop
+    FocusChanged { previous: Option<u64>, current: Option<u64> },
+    StateChanged { window_id: u64, from: WindowState, to: WindowState },
+    /// The resource cycle preempted the window's assignment
+    ResourceLeaseExpired { window_id: u64, assignment_id: u32 },
+    /// The user's session was taken off the display (fast user switching)
+    SessionDetached { mode: DetachMode },
+    SessionAttached,
+    WorkspaceChanged { previous: u32, current: u32 },
+}
+
+/// What a detached user session does while another user has the display
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DetachMode {
+    /// Windows keep their streams and leases, nothing is presented
+    #[default]
+    Headless,
+    /// Window processes are frozen (cgroup.freeze) and leases do not run out
+    Suspend,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceUsage {
     pub window_id: u64,
     pub assignment_id: u32,
@@ -148,12 +263,205 @@ pub struct ResourceUsage {
     pub gpu_active: bool,
     pub remaining_lease_secs: u64,
     pub execution_mode: ExecutionMode,
+    /// memory.current of the assignment's cgroup; None without cgroups v2
+    #[serde(default)]
+    pub ram_used_mb: Option<u64>,
+    #[serde(default)]
+    pub cpu_usage_usec: Option<u64>,
+    /// VRAM in use by the assignment's processes (NVML / DRM fdinfo)
+    #[serde(default)]
+    pub vram_used_mb: Option<u64>,
+}
+
+/// "used/allocated" when usage was measured, otherwise just the allocation
+fn used_of(used: Option<u64>, allocated: u64) -> String {
+    match used {
+        Some(used) => format!("{}/{}", used, allocated),
+        None => allocated.to_string(),
+    }
+}
+
+/// Theme icon named after the app id, else the icon embedded in the manifest's `exec` binary
+/// Who gets input when `id` is asked for: its newest visible modal child,
+/// down to the innermost one
+fn modal_target(windows: &HashMap<u64, Window>, mut id: u64) -> u64 {
+    // Bounded, in case parent links ever form a cycle
+    for _ in 0..windows.len() {
+        let modal = windows.get(&id).and_then(|window| {
+            window.children_ids.iter().rev().copied().find(|child| {
+                windows.get(child).is_some_and(|c| c.visible && c.modal)
+            })
+        });
+        match modal {
+            Some(modal) => id = modal,
+            None => break,
+        }
+    }
+    id
+}
+
+/// Children of `id`, their children and so on (not `id` itself)
+fn descendants(windows: &HashMap<u64, Window>, id: u64) -> Vec<u64> {
+    let mut found = Vec::new();
+    let mut pending: Vec<u64> = windows.get(&id).map(|w| w.children_ids.clone()).unwrap_or_default();
+    while let Some(child) = pending.pop() {
+        // Parent links forming a cycle would revisit windows
+        if child == id || found.contains(&child) {
+            continue;
+        }
+        if let Some(window) = windows.get(&child) {
+            pending.extend(window.children_ids.iter().copied());
+            found.push(child);
+        }
+    }
+    found
+}
+
+/// PPid of a process from /proc/<pid>/stat (the comm field may contain spaces)
+fn parent_pid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let after_comm = &stat[stat.rfind(')')? + 1..];
+    after_comm.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn resolve_window_icon(app_id: &str, program: Option<String>) -> Option<PathBuf> {
+    let mut icons = WsdgIcoCtl::new();
+    if let Some(icon) = icons.find_app_icon(app_id, None) {
+        return Some(icon.path);
+    }
+
+    let program = program?;
+    let exe = if program.contains('/') {
+        PathBuf::from(program)
+    } else {
+        std::env::split_paths(&std::env::var_os("PATH")?)
+            .map(|dir| dir.join(&program))
+            .find(|candidate| candidate.is_file())?
+    };
+    icons.icon_for_executable(&exe, None).map(|i| i.path)
+}
+
+/// Backend assignment carrying a window's resource limits
+fn assignment_for(assignment_id: u32, resource_limits: &ResourceLimits) -> Assignment {
+    let mut assignment = Assignment::new(assignment_id);
+    assignment.execution_mode = resource_limits.execution_mode.unwrap_or(ExecutionMode::GpuPreferred);
+    assignment.ram_limit = (resource_limits.max_memory_mb * 1024 * 1024) as usize;
+    assignment.vram_limit = (resource_limits.max_gpu_memory_mb * 1024 * 1024) as usize;
+    assignment.core_placement = resource_limits.core_placement;
+    assignment.core_class = resource_limits.core_class;
+    if !resource_limits.cpu_cores.is_empty() {
+        assignment.cpu_cores = resource_limits.cpu_cores.clone();
+    }
+    assignment.quota_group = resource_limits.quota_group.clone();
+
+    assignment.start_lease(resource_limits.lease_duration);
+    assignment
+}
+
+/// Session file format version; newer files are refused
+pub const SESSION_VERSION: u32 = 1;
+
+/// Windows persisted across restarts by save_session / restore_session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowSession {
+    pub version: u32,
+    pub windows: Vec<Window>,
+    #[serde(default = "workspaces::default_workspace")]
+    pub active_workspace: u32,
+}
+
+/// `$STATE/wasma/session.json` for the current WSDG environment
+pub fn default_session_path() -> Result<std::path::PathBuf, String> {
+    default_state_dir().map(|state| state.join("session.json"))
+}
+
+/// `$STATE/wasma`, where the backend records assignments for crash recovery
+pub fn default_state_dir() -> Result<std::path::PathBuf, String> {
+    wsdg_xdg::WsdgEnv::new()
+        .state_dir()
+        .map(|state| state.join("wasma"))
+        .map_err(|e| e.to_string())
+}
+
+/// Live WindowHandler state carried across a daemon re-exec
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandlerSnapshot {
+    pub windows: Vec<Window>,
+    pub next_id: u64,
+    pub focused_window: Option<u64>,
+    pub assignment_to_window: Vec<(u32, u64)>,
+    pub backend: BackendSnapshot,
+    /// Session lock state at snapshot time; a locked session stays locked
+    pub session_locked: bool,
+    #[serde(default = "workspaces::default_workspace")]
+    pub active_workspace: u32,
 }
 
 // ============================================================================
 // WINDOW HANDLER - Manifest, Source, Protocol Integration
 // ============================================================================
 
+/// How often watch_wasma_config checks the file's modification time
+const CONFIG_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often autosave_session checks the window layout for changes
+const SESSION_AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+fn protocol_name(protocol: &Protocol) -> &'static str {
+    match protocol {
+        Protocol::Http => "http",
+        Protocol::Https => "https",
+        Protocol::Grpc => "grpc",
+        Protocol::Tor => "tor",
+    }
+}
+
+/// Backend quota group for a wasma.in.conf `quota_group` line
+fn quota_group_for(config: &QuotaGroupConfig) -> QuotaGroup {
+    let mut group = QuotaGroup::new(config.name.clone(), (config.ram_mb * 1024 * 1024) as usize);
+    group.vram_limit = config.vram_mb.map(|mb| (mb * 1024 * 1024) as usize);
+    group.cpu_cores = config.cpu_cores;
+    if config.evict {
+        group.on_full = QuotaOverflow::EvictLeastRecentlyFocused;
+    }
+    group
+}
+
+/// Renderer, scope_level and protocol permissions from wasma.in.conf.
+/// Protocols an earlier config granted and this one drops are revoked;
+/// those the manifest granted stay.
+fn apply_config_to(
+    config: &WasmaConfig,
+    limits: &mut ResourceLimits,
+    permissions: &mut PermissionScope,
+) {
+    limits.renderer = config.resource_limits.renderer.clone();
+    limits.pixel_load_limit = config.resource_limits.scope_level;
+    if let Some(secs) = config.resource_limits.lease_seconds {
+        limits.lease_duration = Duration::from_secs(secs);
+    }
+
+    let granted: Vec<&str> = config.uri_handling.protocols.iter()
+        .map(|p| protocol_name(&p.protocol))
+        .collect();
+
+    let PermissionScope { allowed_protocols, config_protocols, .. } = permissions;
+    config_protocols.retain(|proto| {
+        let kept = granted.contains(&proto.as_str());
+        if !kept {
+            allowed_protocols.retain(|p| p != proto);
+        }
+        kept
+    });
+
+    for proto_str in granted {
+        if !allowed_protocols.iter().any(|p| p == proto_str) {
+            allowed_protocols.push(proto_str.to_string());
+            config_protocols.push(proto_str.to_string());
+        }
+    }
+}
+
 pub struct WindowHandler {
     windows: Arc<Mutex<HashMap<u64, Window>>>,
     next_id: Arc<Mutex<u64>>,
@@ -163,36 +471,404 @@ pub struct WindowHandler {
     
     // WASMA config
     wasma_config: Arc<Mutex<Option<WasmaConfig>>>,
+
+    // Server-side decorations, themed from WsdgSettings
+    decorations: Arc<Decorations>,
+
+    // subscribe() receivers; dropped ones are pruned on the next event
+    subscribers: Arc<Mutex<Vec<Sender<WindowEvent>>>>,
+
+    // Warm renderer contexts for new windows, sized by wasma.in.conf `context_pool`
+    context_pool: Arc<Mutex<Option<Arc<ContextPool>>>>,
+    renderer_contexts: Arc<Mutex<HashMap<u64, RendererContext>>>,
+
+    // wasma.in.conf `lease_expiry`, read by the backend's lease hook
+    lease_expiry: Arc<Mutex<LeaseExpiry>>,
+
+    // Per-window CPU utilization, filled by start_cpu_sampler()
+    cpu_history: Arc<Mutex<HashMap<u64, CpuHistory>>>,
+    cpu_sampler: Arc<Mutex<CpuSampler>>,
+    // CPU / RAM / VRAM of the last minute per window, for the card graphs
+    resource_history: Arc<Mutex<HashMap<u64, ResourceHistory>>>,
+
+    // Last focus time per window, for quota group eviction
+    focus_history: Arc<Mutex<HashMap<u64, SystemTime>>>,
+
+    // Opt-in usage counts, off unless WSDG settings enable them
+    telemetry: Arc<Telemetry>,
+
+    // Window icons with their badges, shared by the GUI and taskbar exporters
+    badge_compositor: Arc<BadgeCompositor>,
+
+    // Parsed manifests by path, reused while file mtimes are unchanged
+    manifest_cache: Arc<Mutex<HashMap<String, CachedManifest>>>,
+
+    // Kiosk mode: vendor manifests only, resources within manifest bounds
+    kiosk: Arc<Mutex<Option<KioskPolicy>>>,
+
+    // DRM device of the seat this handler drives; assignments are pinned to it
+    seat_gpu: Arc<Mutex<Option<String>>>,
+
+    // Fast user switching: Some while the session is off the display
+    detached: Arc<Mutex<Option<DetachMode>>>,
+    // Read by WindowClients on every frame: set while detached, nothing is presented
+    off_display: Arc<AtomicBool>,
+    session_lock: Arc<SessionLock>,
+
+    // Where each window's pixels are read back from for thumbnails
+    frame_sources: Arc<Mutex<HashMap<u64, FrameSource>>>,
+
+    // Outputs dialogs, splash screens and notifications are placed on
+    monitors: Arc<Mutex<MonitorMap>>,
+    // When tooltips and splash screens are closed, by start_auto_close()
+    auto_close: Arc<Mutex<HashMap<u64, SystemTime>>>,
+
+    // Workspace whose windows are visible; new windows open on it
+    active_workspace: Arc<Mutex<u32>>,
+
+    // Stacking order: windows are raised when created and when a parent
+    // focus brings their modal up (higher is closer to the top)
+    raised: Arc<Mutex<HashMap<u64, u64>>>,
+
+    // Process behind each window, only from kernel-verified sources (cgroup
+    // attach, Wayland client credentials); never from client-set properties
+    window_pids: Arc<Mutex<HashMap<u64, u32>>>,
+}
+
+/// A manifest with the limits and permissions window creation derives from it
+#[derive(Debug, Clone)]
+pub struct ManifestConfig {
+    pub manifest: WasmaManifest,
+    pub resource_limits: ResourceLimits,
+    pub permissions: PermissionScope,
+}
+
+struct CachedManifest {
+    manifest_modified: Option<SystemTime>,
+    source_file: Option<PathBuf>,
+    source_modified: Option<SystemTime>,
+    config: Arc<ManifestConfig>,
 }
 
 impl WindowHandler {
     pub fn new(resource_mode: ResourceMode) -> Self {
+        Self::with_env(resource_mode, &WsdgEnv::new())
+    }
+
+    /// Handler whose decorations, telemetry and kiosk policy follow `env`
+    /// (e.g. the active user of a seat) instead of the process environment
+    pub fn with_env(resource_mode: ResourceMode, env: &WsdgEnv) -> Self {
+        let wbackend = Arc::new(WBackend::new(resource_mode));
+        let lease_expiry = Arc::new(Mutex::new(LeaseExpiry::default()));
+        let detached = Arc::new(Mutex::new(None));
+        let policy = lease_expiry.clone();
+        let suspended = detached.clone();
+        wbackend.resource_manager.on_lease_expired(move |_| {
+            // Frozen processes cannot use their lease; releasing it would also thaw them
+            if *suspended.lock().unwrap() == Some(DetachMode::Suspend) {
+                return LeaseDecision::Renew;
+            }
+            match *policy.lock().unwrap() {
+                LeaseExpiry::Renew => LeaseDecision::Renew,
+                LeaseExpiry::Suspend | LeaseExpiry::Close => LeaseDecision::Release,
+            }
+        });
+
         Self {
             windows: Arc::new(Mutex::new(HashMap::new())),
             next_id: Arc::new(Mutex::new(1)),
             focused_window: Arc::new(Mutex::new(None)),
-            wbackend: Arc::new(WBackend::new(resource_mode)),
+            wbackend,
             assignment_to_window: Arc::new(Mutex::new(HashMap::new())),
             wasma_config: Arc::new(Mutex::new(None)),
+            decorations: Arc::new(Decorations::new(DecorationTheme::from_env(env))),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            context_pool: Arc::new(Mutex::new(None)),
+            renderer_contexts: Arc::new(Mutex::new(HashMap::new())),
+            lease_expiry,
+            cpu_history: Arc::new(Mutex::new(HashMap::new())),
+            cpu_sampler: Arc::new(Mutex::new(CpuSampler::new())),
+            resource_history: Arc::new(Mutex::new(HashMap::new())),
+            focus_history: Arc::new(Mutex::new(HashMap::new())),
+            telemetry: Arc::new(Telemetry::from_env(env)),
+            badge_compositor: Arc::new(BadgeCompositor::new()),
+            manifest_cache: Arc::new(Mutex::new(HashMap::new())),
+            kiosk: Arc::new(Mutex::new(env.kiosk())),
+            seat_gpu: Arc::new(Mutex::new(None)),
+            detached,
+            off_display: Arc::new(AtomicBool::new(false)),
+            session_lock: Arc::new(SessionLock::for_env(env)),
+            frame_sources: Arc::new(Mutex::new(HashMap::new())),
+            monitors: Arc::new(Mutex::new(MonitorMap::single(crate::DEFAULT_SCREEN_SIZE.0, crate::DEFAULT_SCREEN_SIZE.1))),
+            auto_close: Arc::new(Mutex::new(HashMap::new())),
+            active_workspace: Arc::new(Mutex::new(workspaces::DEFAULT_WORKSPACE)),
+            raised: Arc::new(Mutex::new(HashMap::new())),
+            window_pids: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// Receive every window event from now on, instead of polling list_windows()
+    pub fn subscribe(&self) -> Receiver<WindowEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    fn emit(&self, event: WindowEvent) {
+        self.subscribers.lock().unwrap().retain(|tx| tx.send(event.clone()).is_ok());
+    }
+
+    pub fn decorations(&self) -> Arc<Decorations> {
+        self.decorations.clone()
+    }
+
+    pub fn telemetry(&self) -> Arc<Telemetry> {
+        self.telemetry.clone()
+    }
+
+    /// Count `error` under `category` (when telemetry is on) and pass it through
+    fn report_error(&self, category: ErrorCategory, error: String) -> String {
+        self.telemetry.record_error(category);
+        error
+    }
+
+    /// Flush usage counts every `interval` until the handler is dropped; a no-op while telemetry is off
+    pub fn start_telemetry(self: &Arc<Self>, interval: Duration) {
+        let handler = Arc::downgrade(self);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            let Some(handler) = handler.upgrade() else { break };
+            if let Err(e) = handler.telemetry.flush() {
+                tracing::warn!(error = %e, "telemetry report could not be sent");
+            }
+        });
+    }
+
     /// Load wasma.in.conf
     pub fn load_wasma_config(&self, config_path: &str) -> Result<(), String> {
         let parser = ConfigParser::new(Some(config_path.to_string()));
         let config = parser.load()
-            .map_err(|e| format!("Config could not be loaded: {:?}", e))?;
+            .map_err(|e| self.report_error(ErrorCategory::Config, format!("Config could not be loaded: {:?}", e)))?;
         
         parser.validate(&config)
-            .map_err(|e| format!("Config is invalid: {:?}", e))?;
+            .map_err(|e| self.report_error(ErrorCategory::Config, format!("Config is invalid: {:?}", e)))?;
 
+        self.apply_backend_config(&config);
         let mut wasma_cfg = self.wasma_config.lock().unwrap();
         *wasma_cfg = Some(config);
 
-        println!("✅ WASMA Config loaded: {}", config_path);
+        tracing::info!(path = %config_path, "WASMA config loaded");
         Ok(())
     }
 
+    /// Re-read wasma.in.conf and push the new values into live windows.
+    /// An invalid file keeps the previous config in place.
+    pub fn reload_wasma_config(&self, config_path: &str) -> Result<usize, String> {
+        let parser = ConfigParser::new(Some(config_path.to_string()));
+        let config = parser.load()
+            .map_err(|e| self.report_error(ErrorCategory::Config, format!("Config could not be loaded: {:?}", e)))?;
+
+        parser.validate(&config)
+            .map_err(|e| self.report_error(ErrorCategory::Config, format!("Config is invalid: {:?}", e)))?;
+
+        let updated = self.apply_wasma_config(config);
+        tracing::info!(path = %config_path, updated, "WASMA config reloaded");
+        Ok(updated)
+    }
+
+    /// Swap in `config` and re-apply renderer, scope_level and protocol
+    /// permissions to every live window; returns the number of windows touched
+    pub fn apply_wasma_config(&self, config: WasmaConfig) -> usize {
+        self.apply_backend_config(&config);
+        *self.wasma_config.lock().unwrap() = Some(config.clone());
+
+        let mut windows = self.windows.lock().unwrap();
+        for window in windows.values_mut() {
+            apply_config_to(&config, &mut window.resource_limits, &mut window.permissions);
+        }
+        windows.len()
+    }
+
+    /// Settings that live outside individual windows: lease defaults and the context pool
+    fn apply_backend_config(&self, config: &WasmaConfig) {
+        crash_report::set_config_snapshot(config);
+        ProtocolControl::global().configure(&config.uri_handling.protocols);
+        let limits = &config.resource_limits;
+        *self.lease_expiry.lock().unwrap() = limits.lease_expiry;
+        if let Some(secs) = limits.lease_seconds {
+            self.wbackend.resource_manager.set_default_lease(Duration::from_secs(secs));
+        }
+
+        // Groups dropped from the file stop constraining new windows
+        for group in self.wbackend.quota_groups() {
+            if !limits.quota_groups.iter().any(|g| g.name == group.name) {
+                self.wbackend.remove_quota_group(&group.name);
+            }
+        }
+        for group in &limits.quota_groups {
+            self.wbackend.set_quota_group(quota_group_for(group));
+        }
+        self.rebuild_context_pool(config);
+    }
+
+    /// Replace the context pool when renderer, scope_level or pool size changed,
+    /// and warm the new one up off the calling thread
+    fn rebuild_context_pool(&self, config: &WasmaConfig) {
+        let limits = &config.resource_limits;
+        let mut current = self.context_pool.lock().unwrap();
+        if let Some(pool) = current.as_ref() {
+            if pool.renderer() == limits.renderer && pool.scope_level() == limits.scope_level && pool.size() == limits.context_pool {
+                return;
+            }
+        }
+
+        if limits.context_pool == 0 {
+            *current = None;
+            return;
+        }
+        let pool = Arc::new(ContextPool::new(&limits.renderer, limits.scope_level, limits.context_pool));
+        pool.warm_up_in_background();
+        *current = Some(pool);
+    }
+
+    pub fn context_pool(&self) -> Option<Arc<ContextPool>> {
+        self.context_pool.lock().unwrap().clone()
+    }
+
+    /// Fill the context pool now instead of in the background; returns contexts created
+    pub fn warm_up_contexts(&self) -> usize {
+        self.context_pool().map_or(0, |pool| pool.warm_up())
+    }
+
+    /// Hand the window's pre-created renderer context to whoever draws it
+    pub fn take_renderer_context(&self, id: u64) -> Option<RendererContext> {
+        self.renderer_contexts.lock().unwrap().remove(&id)
+    }
+
+    /// Record every window's CPU utilization each `interval` until the handler is dropped
+    pub fn start_cpu_sampler(self: &Arc<Self>, interval: Duration) {
+        let handler = Arc::downgrade(self);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            let Some(handler) = handler.upgrade() else { break };
+            handler.sample_cpu();
+        });
+    }
+
+    /// Take one CPU / RAM / VRAM sample for every window with an assignment
+    pub fn sample_cpu(&self) {
+        let windows: Vec<(u64, u32)> = self.windows.lock().unwrap()
+            .values()
+            .filter_map(|w| w.assignment_id.map(|aid| (w.id, aid)))
+            .collect();
+        let mut memory = HashMap::new();
+        let sources: Vec<CpuSource> = windows
+            .iter()
+            .filter_map(|&(_, aid)| {
+                let assignment = self.wbackend.get_assignment(aid)?;
+                let usage = self.wbackend.resource_usage(aid);
+                let vram = self.wbackend.gpu_usage(aid).map(|u| u.vram_bytes);
+                memory.insert(aid, (usage.map(|u| u.memory_current), vram));
+                Some(CpuSource {
+                    assignment_id: aid,
+                    cpu_cores: assignment.cpu_cores.clone(),
+                    cgroup_usage_usec: usage.map(|u| u.cpu_usage_usec),
+                })
+            })
+            .collect();
+        let utilization = self.cpu_sampler.lock().unwrap().sample(&sources);
+
+        let mut history = self.cpu_history.lock().unwrap();
+        history.retain(|id, _| windows.iter().any(|(wid, _)| wid == id));
+        let mut resources = self.resource_history.lock().unwrap();
+        resources.retain(|id, _| windows.iter().any(|(wid, _)| wid == id));
+        for (window_id, aid) in windows {
+            if let Some(&value) = utilization.get(&aid) {
+                history.entry(window_id).or_default().push(value);
+            }
+            if let Some(&(ram, vram)) = memory.get(&aid) {
+                let sample = ResourceSample::measured(utilization.get(&aid).copied(), ram, vram);
+                resources.entry(window_id).or_default().push(sample);
+            }
+        }
+    }
+
+    /// CPU %, RAM MiB and VRAM MiB of the last minute, oldest first
+    pub fn get_window_resource_history(&self, window_id: u64) -> Result<ResourceHistory, String> {
+        if !self.windows.lock().unwrap().contains_key(&window_id) {
+            return Err(t!("error-window-not-found", id = window_id));
+        }
+        Ok(self.resource_history.lock().unwrap().get(&window_id).cloned().unwrap_or_default())
+    }
+
+    /// Newest `samples` utilization values (0.0..=1.0), oldest first
+    pub fn get_window_cpu_history(&self, window_id: u64, samples: usize) -> Result<Vec<f32>, String> {
+        if !self.windows.lock().unwrap().contains_key(&window_id) {
+            return Err(t!("error-window-not-found", id = window_id));
+        }
+        Ok(self.cpu_history.lock().unwrap()
+            .get(&window_id)
+            .map(|h| h.recent(samples))
+            .unwrap_or_default())
+    }
+
+    /// Where `capture_thumbnail` reads the window's pixels; backends and
+    /// stream owners register it once the window has a surface
+    pub fn set_frame_source(&self, window_id: u64, source: FrameSource) {
+        self.frame_sources.lock().unwrap().insert(window_id, source);
+    }
+
+    /// Keep the newest frame of a window whose surface cannot be read back
+    /// (Wayland, remote streams)
+    pub fn submit_frame(&self, window_id: u64, header: FrameHeader, data: &[u8]) {
+        self.set_frame_source(window_id, FrameSource::Frame { header, data: Arc::new(data.to_vec()) });
+    }
+
+    /// Current contents of the window, scaled to fit `max_size` pixels
+    pub fn capture_thumbnail(&self, window_id: u64, max_size: u32) -> Result<RgbaImage, String> {
+        if !self.windows.lock().unwrap().contains_key(&window_id) {
+            return Err(t!("error-window-not-found", id = window_id));
+        }
+        let source = self.frame_sources.lock().unwrap().get(&window_id).cloned()
+            .ok_or_else(|| format!("Window {} has no frames yet", window_id))?;
+        Ok(source.capture()?.fit(max_size))
+    }
+
+    /// Poll `config_path` for edits and hot-reload it into live windows.
+    /// The watcher stops once the handler is dropped.
+    pub fn watch_wasma_config(self: &Arc<Self>, config_path: &str) {
+        let handler = Arc::downgrade(self);
+        let path = config_path.to_string();
+
+        std::thread::spawn(move || {
+            let modified = |path: &str| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+            let mut last = modified(&path);
+
+            loop {
+                std::thread::sleep(CONFIG_WATCH_INTERVAL);
+                let Some(handler) = handler.upgrade() else { break };
+
+                let current = modified(&path);
+                if current == last {
+                    continue;
+                }
+                last = current;
+
+                // Deleted file: keep running with the last good config
+                if current.is_none() {
+                    continue;
+                }
+
+                match handler.reload_wasma_config(&path) {
+                    Ok(_) => {
+                        osd::show("wasma.in.conf reloaded", OsdIcon::Info, Duration::from_millis(1500));
+                    }
+                    Err(e) => tracing::warn!(error = %e, "WASMA config reload skipped"),
+                }
+            }
+        });
+    }
+
     /// Create window - manifest + source + wasma.in.conf combination
     pub fn create_window(
         &self,
@@ -202,61 +878,91 @@ impl WindowHandler {
         manifest_path: Option<String>,
         resource_mode: ResourceMode,
     ) -> Result<u64, String> {
-        let mut next_id = self.next_id.lock().unwrap();
-        let window_id = *next_id;
-        *next_id += 1;
-
         // 1. Load manifest if available
         let (mut resource_limits, mut permissions) = if let Some(ref path) = manifest_path {
-            self.load_manifest_and_source(path)?
+            let config = self.load_manifest_config(path)
+                .map_err(|e| self.report_error(ErrorCategory::Manifest, e))?;
+            (config.resource_limits.clone(), config.permissions.clone())
         } else {
             (ResourceLimits::default(), PermissionScope::default())
         };
 
         // 2. Get renderer and scope_level from wasma.in.conf
+        let mut rules = RuleActions::default();
         if let Some(ref wasma_cfg) = *self.wasma_config.lock().unwrap() {
-            resource_limits.renderer = wasma_cfg.resource_limits.renderer.clone();
-            resource_limits.pixel_load_limit = wasma_cfg.resource_limits.scope_level;            
-            // Add protocol permissions
-            for proto_cfg in &wasma_cfg.uri_handling.protocols {
-                let proto_str = match proto_cfg.protocol {
-                    Protocol::Http => "http",
-                    Protocol::Https => "https",
-                    Protocol::Grpc => "grpc",
-                    Protocol::Tor => "tor",
-                };
-                if !permissions.allowed_protocols.contains(&proto_str.to_string()) {
-                    permissions.allowed_protocols.push(proto_str.to_string());
-                }
+            apply_config_to(wasma_cfg, &mut resource_limits, &mut permissions);
+            if let Some(group) = wasma_cfg.resource_limits.quota_groups.iter().find(|g| g.contains_app(&app_id)) {
+                resource_limits.quota_group = Some(group.name.clone());
+            }
+            rules = window_rules::evaluate(&wasma_cfg.window_rules, &app_id, &title);
+        }
+        // Rules override the manifest's execution mode, before the assignment is built
+        if let Some(mode) = rules.execution_mode {
+            resource_limits.execution_mode = Some(mode);
+        }
+
+        let id = self.insert_window(title, app_id, geometry, manifest_path, resource_mode, resource_limits, permissions)?;
+        self.apply_rule_actions(id, &rules)?;
+        Ok(id)
+    }
+
+    /// Placement and state from matching wasma.in.conf rules
+    fn apply_rule_actions(&self, id: u64, rules: &RuleActions) -> Result<(), String> {
+        if let Some(on_top) = rules.always_on_top {
+            if let Some(window) = self.windows.lock().unwrap().get_mut(&id) {
+                window.always_on_top = on_top;
             }
         }
+        if let Some(number) = rules.workspace {
+            self.move_to_workspace(id, number)?;
+        }
+        if let Some(state) = rules.state.clone() {
+            self.set_window_state(id, state)?;
+        }
+        Ok(())
+    }
+
+    /// Register a window with a fresh assignment built from `resource_limits`
+    #[allow(clippy::too_many_arguments)]
+    fn insert_window(
+        &self,
+        title: String,
+        app_id: String,
+        geometry: WindowGeometry,
+        manifest_path: Option<String>,
+        resource_mode: ResourceMode,
+        mut resource_limits: ResourceLimits,
+        permissions: PermissionScope,
+    ) -> Result<u64, String> {
+        let mut next_id = self.next_id.lock().unwrap();
+        let window_id = *next_id;
+        *next_id += 1;
+        drop(next_id);
+        let _span = tracing::info_span!("window", id = window_id).entered();
 
-        // 3. Create WBackend Assignment
+        // 3. Create WBackend Assignment; a full quota group refuses it or makes room
         let assignment_id = window_id as u32;
-        let mut assignment = Assignment::new(assignment_id);
-        // Define this variable before use:
-        let new_limits = ResourceLimits {
-            max_memory_mb: 512,
-            max_gpu_memory_mb: 256,
-            cpu_cores: Vec::new(),
-            lease_duration: Duration::from_secs(30),
-            execution_mode: Some(ExecutionMode::GpuPreferred),
-            renderer: "cpu_renderer".to_string(),
-            pixel_load_limit: 50,
-        };
-        assignment.execution_mode = resource_limits.execution_mode.unwrap_or(ExecutionMode::GpuPreferred);
-        assignment.ram_limit = (new_limits.max_memory_mb * 1024 * 1024) as usize;
-        assignment.vram_limit = (new_limits.max_gpu_memory_mb * 1024 * 1024) as usize;        
-        if !resource_limits.cpu_cores.is_empty() {
-            assignment.cpu_cores = resource_limits.cpu_cores.clone();
+        let mut assignment = assignment_for(assignment_id, &resource_limits);
+        self.pin_seat_gpu(&mut assignment);
+        resource_limits.quota_group = self.admit_assignment(assignment)
+            .map_err(|e| self.report_error(ErrorCategory::Quota, e))?;
+        self.telemetry.record_window(&resource_limits.renderer);
+
+        // Warm renderer context, so the first frame does not stall on setup
+        if let Some(pool) = self.context_pool() {
+            if pool.renderer() == resource_limits.renderer && pool.scope_level() == resource_limits.pixel_load_limit {
+                self.renderer_contexts.lock().unwrap().insert(window_id, pool.acquire());
+                pool.warm_up_in_background();
+            }
         }
-        
-        assignment.start_lease(resource_limits.lease_duration);
 
-        self.wbackend.add_assignment(assignment);
+        let exec = manifest_path.as_deref()
+            .and_then(|path| self.load_manifest_config(path).ok())
+            .and_then(|config| config.manifest.app.launch_command(&[]))
+            .map(|command| command.program);
+        let icon = resolve_window_icon(&app_id, exec);
 
-        let mut mapping = self.assignment_to_window.lock().unwrap();
-        mapping.insert(assignment_id, window_id);
+        self.assignment_to_window.lock().unwrap().insert(assignment_id, window_id);
 
         // 4. Create Window structure
         let window = Window {
@@ -279,25 +985,106 @@ impl WindowHandler {
             backend_type: BackendType::Native,
             assignment_id: Some(assignment_id),
             resource_mode,
+            server_decorations: self.decorations.theme().enabled,
+            icon,
+            resizable: true,
+            always_on_top: false,
+            badges: Vec::new(),
+            enforcement: None,
+            skip_taskbar: false,
+            workspace: self.active_workspace(),
+            modal: false,
         };
 
+        let created = WindowEvent::WindowCreated {
+            window_id,
+            title: window.title.clone(),
+            app_id: window.app_id.clone(),
+        };
         let mut windows = self.windows.lock().unwrap();
+        // The app's next window replaces its splash screen
+        let splashes: Vec<u64> = windows.values()
+            .filter(|w| w.window_type == WindowType::Splash && w.app_id == window.app_id)
+            .map(|w| w.id)
+            .collect();
         windows.insert(window_id, window);
+        drop(windows);
+        // New windows open on top
+        self.raise_window(window_id);
 
-        println!(
-            "🪟 Window {} created | Assignment {} | Mode: {:?}",
-            window_id, assignment_id, resource_mode
-        );
+        tracing::info!(assignment = assignment_id, mode = ?resource_mode, "window created");
 
+        self.emit(created);
+        for splash in splashes {
+            self.close_window(splash).ok();
+        }
         Ok(window_id)
     }
 
+    /// Add `assignment` to the backend; returns the quota group it joined.
+    /// A full group either refuses the window or, when configured to evict,
+    /// closes its least-recently-focused windows until the new one fits.
+    fn admit_assignment(&self, mut assignment: Assignment) -> Result<Option<String>, String> {
+        loop {
+            let error = match self.wbackend.try_add_assignment(assignment.clone()) {
+                Ok(()) => return Ok(assignment.quota_group),
+                Err(error) => error,
+            };
+            let group = match &error {
+                QuotaError::UnknownGroup(name) => {
+                    tracing::warn!(group = %name, "quota group is not configured, window joins no group");
+                    assignment.quota_group = None;
+                    continue;
+                }
+                QuotaError::Exceeded { group, .. } => group,
+            };
+
+            let evicts = self.wbackend.quota_group(group)
+                .is_some_and(|g| g.on_full == QuotaOverflow::EvictLeastRecentlyFocused);
+            let Some(victim) = evicts.then(|| self.least_recently_focused(group)).flatten() else {
+                return Err(error.to_string());
+            };
+            tracing::info!(%group, window = victim, "quota group is full, evicting window");
+            osd::show(format!("Closed window {} for quota group {}", victim, group), OsdIcon::Info, Duration::from_millis(1500));
+            self.close_window(victim)?;
+        }
+    }
+
+    /// Member of `group` focused longest ago; never-focused windows count from creation
+    fn least_recently_focused(&self, group: &str) -> Option<u64> {
+        let focus_history = self.focus_history.lock().unwrap();
+        let windows = self.windows.lock().unwrap();
+        windows.values()
+            .filter(|w| w.resource_limits.quota_group.as_deref() == Some(group))
+            .min_by_key(|w| (focus_history.get(&w.id).copied().unwrap_or(w.created_at), w.id))
+            .map(|w| w.id)
+    }
+
     /// Load Manifest and Source
-    fn load_manifest_and_source(&self, manifest_path: &str) -> Result<(ResourceLimits, PermissionScope), String> {
+    /// Manifest + permission source of `manifest_path`, parsed once and reused
+    /// until the manifest or its permission source file changes on disk
+    pub fn load_manifest_config(&self, manifest_path: &str) -> Result<Arc<ManifestConfig>, String> {
+        if let Some(policy) = self.kiosk_policy() {
+            policy.check_manifest(Path::new(manifest_path)).map_err(|e| e.to_string())?;
+        }
+
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let manifest_modified = modified(Path::new(manifest_path));
+
+        if let Some(cached) = self.manifest_cache.lock().unwrap().get(manifest_path) {
+            let source_modified = cached.source_file.as_deref().and_then(modified);
+            if cached.manifest_modified == manifest_modified && cached.source_modified == source_modified {
+                return Ok(cached.config.clone());
+            }
+        }
+
         // 1. Parse manifest
         let parser = ManifestParser::new(manifest_path.to_string());
         let manifest = parser.load()
-            .map_err(|e| format!("Manifest could not be loaded: {:?}", e))?;
+            .map_err(|e| format!("Manifest could not be loaded: {}", e))?;
+        for warning in &manifest.warnings {
+            tracing::warn!(manifest = %manifest_path, "{}", warning);
+        }
 
         // 2. Create resource limits
         let mut limits = ResourceLimits::default();
@@ -308,6 +1095,8 @@ impl WindowHandler {
             CpuCoreServe::Dynamic => Vec::new(),
             CpuCoreServe::AffinityDefault => Vec::new(),
         };
+        limits.core_placement = manifest.resources.cpu_placement;
+        limits.core_class = manifest.resources.core_class;
         
         // RAM
         limits.max_memory_mb = manifest.resources.ram_using.size;
@@ -315,22 +1104,43 @@ impl WindowHandler {
         // GPU
         limits.max_gpu_memory_mb = manifest.resources.gpu_using.size;
         
-        // Execution mode (not in manifest, use default)
-        limits.execution_mode = Some(ExecutionMode::GpuPreferred);
+        // Execution mode
+        limits.execution_mode = Some(manifest.resources.execution_mode);
 
-        // 3. Load permission source
+        // 3. Permission source: embedded [source], else the referenced file
         let source_parser = SourceParser::new(None);
-        let perms = if let Ok(source) = source_parser.load_embedded(&std::fs::read_to_string(manifest_path).unwrap_or_default()) {
-            if let Some(src) = source {
-                self.parse_permissions(src)
-            } else {
-                PermissionScope::default()
-            }
+        let embedded = if parser.is_structured() {
+            None
         } else {
-            PermissionScope::default()
+            let content = std::fs::read_to_string(manifest_path).unwrap_or_default();
+            source_parser.load_embedded(&content)
+                .map_err(|e| format!("Embedded [source] of {}: {}", manifest_path, e))?
+        };
+        let (perms, source_file) = match embedded {
+            Some(source) => (self.parse_permissions(source), None),
+            None => {
+                let file = manifest.permissions.source_file();
+                if file.exists() {
+                    let source = source_parser.load(&file.display().to_string())
+                        .map_err(|e| format!("Permission source {}: {}", file.display(), e))?;
+                    (self.parse_permissions(source), Some(file))
+                } else if manifest.permissions.source_path.is_some() {
+                    return Err(format!("Permission source {} does not exist", file.display()));
+                } else {
+                    // Default location not installed: default scope, picked up once it appears
+                    (PermissionScope::default(), Some(file))
+                }
+            }
         };
 
-        Ok((limits, perms))
+        let config = Arc::new(ManifestConfig { manifest, resource_limits: limits, permissions: perms });
+        self.manifest_cache.lock().unwrap().insert(manifest_path.to_string(), CachedManifest {
+            manifest_modified,
+            source_modified: source_file.as_deref().and_then(modified),
+            source_file,
+            config: config.clone(),
+        });
+        Ok(config)
     }
 
     /// Parse permissions from Source
@@ -348,49 +1158,329 @@ impl WindowHandler {
         
         // Protocols
         perms.allowed_protocols = vec!["http".to_string(), "https".to_string()];
+
+        // Global hotkeys
+        perms.can_register_hotkeys = source.system.global_hotkeys;
+
+        // Isolation profile
+        if let Some(level) = source.system.sandbox_level {
+            perms.sandbox_level = level;
+        }
         
         perms
     }
 
-    // Existing functions remain the same
-    pub fn run_resource_cycle(&self) {
-        self.wbackend.run_cycle();
+    /// Run every backend phase; the report lists the assignments whose lease expired
+    pub fn run_resource_cycle(&self) -> MonitorReport {
+        let report = self.wbackend.run_cycle();
+        self.handle_expired_leases(report.expired.clone());
+        report
     }
 
-    pub fn adjust_window_resources(&self, window_id: u64, new_limits: ResourceLimits) -> Result<(), String> {
-        let mut windows = self.windows.lock().unwrap();
-        let window = windows.get_mut(&window_id)
-            .ok_or_else(|| format!("Window {} not found", window_id))?;
+    /// Only the backend phases whose cadence is due (see WBackend::set_cadence)
+    pub fn run_due_resource_phases(&self) {
+        let expired = self.wbackend.run_due();
+        self.handle_expired_leases(expired);
+    }
 
-        if window.resource_mode != ResourceMode::Manual {
-            return Err(format!("Window {} is in Auto mode, manual adjustment not allowed", window_id));
-        }
+    /// Block until the backend has work: a phase falls due, a lease nears expiry,
+    /// or an assignment is added or removed
+    pub fn wait_for_resource_work(&self) {
+        self.wbackend.wait_for_work();
+    }
 
-        window.resource_limits = new_limits.clone();
+    pub fn set_resource_cadence(&self, cadence: CycleCadence) {
+        self.wbackend.set_cadence(cadence);
+    }
 
-        if let Some(assignment_id) = window.assignment_id {
-            if let Some(mut assignment) = self.wbackend.get_assignment(assignment_id) {
-                assignment.execution_mode = new_limits.execution_mode.unwrap_or(ExecutionMode::GpuPreferred);
-                assignment.ram_limit = (new_limits.max_memory_mb * 1024 * 1024) as usize;
-                assignment.vram_limit = (new_limits.max_gpu_memory_mb * 1024 * 1024) as usize;                
-                if !new_limits.cpu_cores.is_empty() {
-                    assignment.cpu_cores = new_limits.cpu_cores.clone();
-                    assignment.bind_cpu();
-                }
+    pub fn resource_cadence(&self) -> CycleCadence {
+        self.wbackend.cadence()
+    }
 
-                if assignment.should_bind_gpu() {
-                    assignment.bind_gpu();
-                }
-            }
+    /// Receive the report of every backend monitor phase from now on
+    pub fn subscribe_resource_reports(&self) -> Receiver<MonitorReport> {
+        self.wbackend.subscribe_reports()
+    }
+
+    /// Report of the latest monitor phase; None before the first one
+    pub fn last_resource_report(&self) -> Option<MonitorReport> {
+        self.wbackend.last_report()
+    }
+
+    fn handle_expired_leases(&self, expired: Vec<u32>) {
+        if expired.is_empty() {
+            return;
+        }
+
+        let mapping = self.assignment_to_window.lock().unwrap().clone();
+        let policy = *self.lease_expiry.lock().unwrap();
+        for assignment_id in expired {
+            let Some(&window_id) = mapping.get(&assignment_id) else { continue };
+            self.emit(WindowEvent::ResourceLeaseExpired { window_id, assignment_id });
+
+            let handled = match policy {
+                LeaseExpiry::Suspend => self.set_window_state(window_id, WindowState::Minimized),
+                LeaseExpiry::Close => self.close_window(window_id),
+                LeaseExpiry::Renew => Ok(()),
+            };
+            if let Err(e) = handled {
+                tracing::warn!(window = window_id, error = %e, "lease expiry could not be handled");
+            }
+        }
+    }
+
+    /// Restart the window's lease; a window suspended by lease expiry gets a
+    /// fresh assignment
+    pub fn renew_window_lease(&self, id: u64) -> Result<(), String> {
+        let window = self.get_window(id).ok_or_else(|| t!("error-window-not-found", id = id))?;
+        let Some(assignment_id) = window.assignment_id else {
+            return Ok(());
+        };
+
+        if !self.wbackend.renew_lease(assignment_id) {
+            let mut assignment = assignment_for(assignment_id, &window.resource_limits);
+            self.pin_seat_gpu(&mut assignment);
+            self.wbackend.add_assignment(assignment);
+            tracing::info!(window = id, "window resumed with a new lease");
+        }
+        Ok(())
+    }
+
+    /// What the next resource cycle would do, without applying it
+    pub fn plan_resource_cycle(&self) -> CyclePlan {
+        self.wbackend.dry_run_cycle()
+    }
+
+    /// Next resource cycle if a window with `limits` were created now
+    pub fn preview_window(&self, limits: &ResourceLimits) -> CyclePlan {
+        let assignment_id = *self.next_id.lock().unwrap() as u32;
+        self.wbackend.preview_with(&[assignment_for(assignment_id, limits)])
+    }
+
+    /// Kiosk mode in effect (detected from the WSDG environment at startup)
+    pub fn kiosk_policy(&self) -> Option<KioskPolicy> {
+        self.kiosk.lock().unwrap().clone()
+    }
+
+    pub fn set_kiosk_policy(&self, policy: Option<KioskPolicy>) {
+        *self.kiosk.lock().unwrap() = policy;
+        self.manifest_cache.lock().unwrap().clear();
+    }
+
+    pub fn seat_gpu(&self) -> Option<String> {
+        self.seat_gpu.lock().unwrap().clone()
+    }
+
+    /// Pin GPU work of windows created from now on to `device` (a seat's DRM card)
+    pub fn set_seat_gpu(&self, device: Option<String>) {
+        *self.seat_gpu.lock().unwrap() = device;
+    }
+
+    /// Bind `assignment` to the seat's GPU; false when no seat GPU is set
+    fn pin_seat_gpu(&self, assignment: &mut Assignment) -> bool {
+        match self.seat_gpu.lock().unwrap().as_deref() {
+            Some(device) => {
+                assignment.bind_gpu_device(device);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// None while the session is attached to the display
+    pub fn detached(&self) -> Option<DetachMode> {
+        *self.detached.lock().unwrap()
+    }
+
+    pub fn session_lock(&self) -> &SessionLock {
+        &self.session_lock
+    }
+
+    /// True while the session is detached; WindowClient::follow_session gates on it
+    pub fn off_display(&self) -> Arc<AtomicBool> {
+        self.off_display.clone()
+    }
+
+    fn set_off_display(&self, off: bool) {
+        self.off_display.store(off, Ordering::SeqCst);
+        if let Some(presenter) = Presenter::running() {
+            presenter.set_paused(off);
+        }
+    }
+
+    /// Fast user switching: take this user's session off the display. The session
+    /// is locked until its user unlocks it again.
+    pub fn detach_session(&self, mode: DetachMode) -> Result<(), String> {
+        let mut detached = self.detached.lock().unwrap();
+        if detached.is_some() {
+            return Err("Session is already detached".to_string());
+        }
+        self.session_lock.lock_detached().map_err(|e| e.to_string())?;
+        if mode == DetachMode::Suspend {
+            self.freeze_assignments(true);
+        }
+        *detached = Some(mode);
+        drop(detached);
+        // Another user has the display now: not even a blank frame goes out
+        self.set_off_display(true);
+
+        tracing::info!(user = ?self.session_lock.user(), ?mode, "session detached");
+        self.emit(WindowEvent::SessionDetached { mode });
+        Ok(())
+    }
+
+    /// Put a detached session back on the display; refused while it is locked
+    pub fn attach_session(&self) -> Result<(), String> {
+        let mut detached = self.detached.lock().unwrap();
+        let Some(mode) = *detached else {
+            return Err("Session is not detached".to_string());
+        };
+        if self.session_lock.is_locked() {
+            return Err(format!("Session of {} is locked – unlock it before attaching", self.session_lock.user().unwrap_or("this user")));
+        }
+        if mode == DetachMode::Suspend {
+            self.freeze_assignments(false);
+        }
+        *detached = None;
+        drop(detached);
+        self.set_off_display(false);
+
+        // Leases that ran out while away start over instead of closing windows
+        for window in self.list_windows() {
+            if let Err(e) = self.renew_window_lease(window.id) {
+                tracing::warn!(window = window.id, error = %e, "lease could not be renewed after attach");
+            }
+        }
+        tracing::info!(user = ?self.session_lock.user(), "session attached");
+        self.emit(WindowEvent::SessionAttached);
+        Ok(())
+    }
+
+    fn freeze_assignments(&self, frozen: bool) {
+        let assignments: Vec<u32> = self.assignment_to_window.lock().unwrap().keys().copied().collect();
+        for id in assignments {
+            match self.wbackend.set_frozen(id, frozen) {
+                Ok(()) => {}
+                // No cgroup (cgroups v2 unavailable): nothing to freeze
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => tracing::warn!(assignment = id, frozen, error = %e, "cgroup freeze state could not be changed"),
+            }
+        }
+    }
+
+    /// Kiosk mode: resources may only move within what the window's manifest grants
+    fn check_manifest_bounds(&self, policy: &KioskPolicy, window_id: u64, limits: &ResourceLimits) -> Result<(), String> {
+        let manifest_path = self.windows.lock().unwrap()
+            .get(&window_id)
+            .ok_or_else(|| t!("error-window-not-found", id = window_id))?
+            .manifest_path
+            .clone()
+            .ok_or_else(|| policy.deny(format!("window {} has no manifest bounding its resources", window_id)).to_string())?;
+        let bounds = &self.load_manifest_config(&manifest_path)?.resource_limits;
+
+        let exceeded = if limits.max_memory_mb > bounds.max_memory_mb {
+            Some(format!("{} MB RAM (manifest allows {} MB)", limits.max_memory_mb, bounds.max_memory_mb))
+        } else if limits.max_gpu_memory_mb > bounds.max_gpu_memory_mb {
+            Some(format!("{} MB VRAM (manifest allows {} MB)", limits.max_gpu_memory_mb, bounds.max_gpu_memory_mb))
+        } else if !bounds.cpu_cores.is_empty()
+            && (limits.cpu_cores.is_empty() || limits.cpu_cores.len() > bounds.cpu_cores.len())
+        {
+            Some(format!("CPU cores {:?} (manifest allows {})", limits.cpu_cores, bounds.cpu_cores.len()))
+        } else {
+            None
+        };
+
+        match exceeded {
+            Some(what) => Err(policy.deny(format!("window {}: {}", window_id, what)).to_string()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn adjust_window_resources(&self, window_id: u64, new_limits: ResourceLimits) -> Result<(), String> {
+        if let Some(policy) = self.kiosk_policy() {
+            self.check_manifest_bounds(&policy, window_id, &new_limits)?;
+        }
+
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.get_mut(&window_id)
+            .ok_or_else(|| t!("error-window-not-found", id = window_id))?;
+
+        if window.resource_mode != ResourceMode::Manual {
+            return Err(format!("Window {} is in Auto mode, manual adjustment not allowed", window_id));
+        }
+
+        // Quota membership is decided at creation; limits without a group keep it
+        let quota_group = window.resource_limits.quota_group.take();
+        window.resource_limits = new_limits.clone();
+        if window.resource_limits.quota_group.is_none() {
+            window.resource_limits.quota_group = quota_group;
+        }
+
+        if let Some(assignment_id) = window.assignment_id {
+            if let Some(mut assignment) = self.wbackend.get_assignment(assignment_id) {
+                assignment.execution_mode = new_limits.execution_mode.unwrap_or(ExecutionMode::GpuPreferred);
+                assignment.ram_limit = (new_limits.max_memory_mb * 1024 * 1024) as usize;
+                assignment.vram_limit = (new_limits.max_gpu_memory_mb * 1024 * 1024) as usize;
+                assignment.core_placement = new_limits.core_placement;
+                assignment.core_class = new_limits.core_class;
+                if !new_limits.cpu_cores.is_empty() {
+                    assignment.cpu_cores = new_limits.cpu_cores.clone();
+                    assignment.bind_cpu();
+                }
+
+                if assignment.should_bind_gpu() && !self.pin_seat_gpu(&mut assignment) {
+                    assignment.bind_gpu();
+                }
+                // New memory.max / cpu.max take effect immediately
+                self.wbackend.resource_manager.sync_cgroup(&mut assignment);
+            }
         }
 
         Ok(())
     }
 
+    /// Move a process into the window's assignment cgroup so its limits apply
+    pub fn attach_window_process(&self, window_id: u64, pid: u32) -> Result<(), String> {
+        let assignment_id = self.windows.lock().unwrap()
+            .get(&window_id)
+            .ok_or_else(|| t!("error-window-not-found", id = window_id))?
+            .assignment_id
+            .ok_or_else(|| format!("Assignment not found: window {}", window_id))?;
+        self.wbackend.attach_process(assignment_id, pid).map_err(|e| e.to_string())?;
+        self.set_window_pid(window_id, pid);
+        Ok(())
+    }
+
+    /// Record the process that owns a window; the pid must come from the kernel
+    pub fn set_window_pid(&self, window_id: u64, pid: u32) {
+        self.window_pids.lock().unwrap().insert(window_id, pid);
+    }
+
+    /// Windows owned by `pid` or the nearest ancestor that owns any (helper
+    /// processes act for the application that spawned them)
+    pub fn windows_for_pid(&self, pid: u32) -> Vec<u64> {
+        let window_pids = self.window_pids.lock().unwrap().clone();
+        let mut current = Some(pid);
+        // A bounded walk: a reparented chain ends at init
+        for _ in 0..32 {
+            let Some(pid) = current.filter(|&p| p > 1) else { break };
+            let mut owned: Vec<u64> = window_pids.iter()
+                .filter(|(_, &owner)| owner == pid)
+                .map(|(&id, _)| id)
+                .collect();
+            if !owned.is_empty() {
+                owned.sort_unstable();
+                return owned;
+            }
+            current = parent_pid(pid);
+        }
+        Vec::new()
+    }
+
     pub fn get_window_resource_usage(&self, window_id: u64) -> Result<ResourceUsage, String> {
         let windows = self.windows.lock().unwrap();
         let window = windows.get(&window_id)
-            .ok_or_else(|| format!("Window {} not found", window_id))?;
+            .ok_or_else(|| t!("error-window-not-found", id = window_id))?;
 
         if let Some(assignment_id) = window.assignment_id {
             if let Some(assignment) = self.wbackend.get_assignment(assignment_id) {
@@ -403,6 +1493,7 @@ impl WindowHandler {
                         d.as_secs().saturating_sub(s.elapsed().as_secs())
                     }))
                     .unwrap_or(0);
+                let measured = self.wbackend.resource_usage(assignment_id);
 
                 return Ok(ResourceUsage {
                     window_id,
@@ -415,6 +1506,9 @@ impl WindowHandler {
                     gpu_active,
                     remaining_lease_secs: remaining_lease,
                     execution_mode: assignment.execution_mode,
+                    ram_used_mb: measured.map(|u| u.memory_current >> 20),
+                    cpu_usage_usec: measured.map(|u| u.cpu_usage_usec),
+                    vram_used_mb: self.wbackend.gpu_usage(assignment_id).map(|u| u.vram_bytes >> 20),
                 });
             }
         }
@@ -425,27 +1519,354 @@ impl WindowHandler {
     pub fn set_window_state(&self, id: u64, state: WindowState) -> Result<(), String> {
         let mut windows = self.windows.lock().unwrap();
         if let Some(window) = windows.get_mut(&id) {
-            window.state = state;
+            let from = std::mem::replace(&mut window.state, state.clone());
             window.last_activity = SystemTime::now();
+            drop(windows);
+            if from != state {
+                tracing::debug!(window = id, ?from, to = ?state, "window state changed");
+                self.emit(WindowEvent::StateChanged { window_id: id, from, to: state });
+            }
             Ok(())
         } else {
-            Err(format!("Window {} not found", id))
+            Err(t!("error-window-not-found", id = id))
         }
     }
 
     pub fn set_geometry(&self, id: u64, geometry: WindowGeometry) -> Result<(), String> {
         let mut windows = self.windows.lock().unwrap();
         if let Some(window) = windows.get_mut(&id) {
-            window.geometry = geometry;
+            window.geometry = if window.resizable {
+                geometry
+            } else {
+                WindowGeometry { width: window.geometry.width, height: window.geometry.height, ..geometry }
+            };
+            window.last_activity = SystemTime::now();
+            Ok(())
+        } else {
+            Err(t!("error-window-not-found", id = id))
+        }
+    }
+
+    pub fn set_title(&self, id: u64, title: String) -> Result<(), String> {
+        let mut windows = self.windows.lock().unwrap();
+        if let Some(window) = windows.get_mut(&id) {
+            window.title = title;
+            window.last_activity = SystemTime::now();
+            Ok(())
+        } else {
+            Err(t!("error-window-not-found", id = id))
+        }
+    }
+
+    pub fn set_app_id(&self, id: u64, app_id: String) -> Result<(), String> {
+        let mut windows = self.windows.lock().unwrap();
+        if let Some(window) = windows.get_mut(&id) {
+            window.app_id = app_id;
+            window.last_activity = SystemTime::now();
+            Ok(())
+        } else {
+            Err(t!("error-window-not-found", id = id))
+        }
+    }
+
+    /// Set the type and apply its placement and behavior (see window_placement)
+    pub fn set_window_type(&self, id: u64, window_type: WindowType) -> Result<(), String> {
+        let mut windows = self.windows.lock().unwrap();
+        if let Some(window) = windows.get_mut(&id) {
+            // Dialogs are modal unless set_modal() says otherwise
+            window.modal = TypeBehavior::of(&window_type).modal;
+            window.window_type = window_type;
+            window.last_activity = SystemTime::now();
+            drop(windows);
+            self.apply_type_behavior(id);
+            Ok(())
+        } else {
+            Err(t!("error-window-not-found", id = id))
+        }
+    }
+
+    /// Place the window for its type, set its taskbar/stacking flags and
+    /// schedule its auto-close; runs again when it gets a parent
+    fn apply_type_behavior(&self, id: u64) {
+        let mut windows = self.windows.lock().unwrap();
+        let Some(window) = windows.get(&id) else { return };
+        let behavior = TypeBehavior::of(&window.window_type);
+        let parent = window.parent_id.and_then(|pid| windows.get(&pid)).map(|p| p.geometry);
+        let monitors = self.monitors.lock().unwrap().clone();
+        let output = monitors.primary();
+
+        let geometry = match behavior.placement {
+            Placement::AsRequested | Placement::CornerStack => window.geometry,
+            Placement::CenterOnParent => {
+                let area = parent.unwrap_or_else(|| window_placement::output_area(output));
+                // On the parent's output, or the primary one
+                let output = parent.and_then(|p| monitors.monitor_at(p.x, p.y)).unwrap_or(output);
+                window_placement::center_on(window.geometry, area, output)
+            }
+            Placement::Centered => window_placement::center_on(window.geometry, window_placement::output_area(output), output),
+        };
+        let window = windows.get_mut(&id).unwrap();
+        window.geometry = geometry;
+        window.skip_taskbar = behavior.skip_taskbar;
+        window.always_on_top |= behavior.above;
+        drop(windows);
+
+        let mut auto_close = self.auto_close.lock().unwrap();
+        match behavior.auto_close {
+            Some(timeout) => { auto_close.entry(id).or_insert_with(|| SystemTime::now() + timeout); }
+            None => { auto_close.remove(&id); }
+        }
+        drop(auto_close);
+        self.restack_notifications();
+    }
+
+    /// Move notifications back into one stack in the top-right corner, oldest on top
+    fn restack_notifications(&self) {
+        let output = self.monitors.lock().unwrap().primary().clone();
+        let mut windows = self.windows.lock().unwrap();
+        let mut stack: Vec<(u64, WindowGeometry)> = windows.values()
+            .filter(|w| w.window_type == WindowType::Notification && w.visible)
+            .map(|w| (w.id, w.geometry))
+            .collect();
+        stack.sort_by_key(|(id, _)| *id);
+        for (id, geometry) in window_placement::stack_notifications(&output, &stack) {
+            if let Some(window) = windows.get_mut(&id) {
+                window.geometry = geometry;
+            }
+        }
+    }
+
+    /// Tile the normal, managed top-level windows of the active workspace
+    /// over the primary output; returns how many were placed
+    pub fn arrange_windows(&self, policy: LayoutPolicy) -> usize {
+        let output = self.monitors.lock().unwrap().primary().clone();
+        let mut windows = self.windows.lock().unwrap();
+        let mut ids: Vec<u64> = windows.values()
+            .filter(|w| w.visible && w.state == WindowState::Normal && w.parent_id.is_none())
+            .filter(|w| TypeBehavior::of(&w.window_type).managed)
+            .map(|w| w.id)
+            .collect();
+        ids.sort_unstable();
+        let tiles = policy.arrange(ids.len(), output.width, output.height);
+        for (id, (x, y, width, height)) in ids.iter().zip(tiles) {
+            if let Some(window) = windows.get_mut(id) {
+                window.geometry = WindowGeometry { x: output.x + x, y: output.y + y, width, height };
+            }
+        }
+        ids.len()
+    }
+
+    /// Outputs to place dialogs, splash screens and notifications on
+    pub fn set_monitors(&self, monitors: MonitorMap) {
+        *self.monitors.lock().unwrap() = monitors;
+        self.restack_notifications();
+    }
+
+    /// Close tooltips and splash screens whose time is up; returns their ids
+    pub fn close_expired_windows(&self) -> Vec<u64> {
+        let now = SystemTime::now();
+        let expired: Vec<u64> = self.auto_close.lock().unwrap()
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &expired {
+            self.auto_close.lock().unwrap().remove(id);
+            self.close_window(*id).ok();
+        }
+        expired
+    }
+
+    /// Run close_expired_windows() every `interval` until the handler is dropped
+    pub fn start_auto_close(self: &Arc<Self>, interval: Duration) {
+        let handler = Arc::downgrade(self);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            let Some(handler) = handler.upgrade() else { break };
+            handler.close_expired_windows();
+        });
+    }
+
+    pub fn set_resizable(&self, id: u64, resizable: bool) -> Result<(), String> {
+        let mut windows = self.windows.lock().unwrap();
+        if let Some(window) = windows.get_mut(&id) {
+            window.resizable = resizable;
+            Ok(())
+        } else {
+            Err(t!("error-window-not-found", id = id))
+        }
+    }
+
+    pub fn set_always_on_top(&self, id: u64, always_on_top: bool) -> Result<(), String> {
+        let mut windows = self.windows.lock().unwrap();
+        if let Some(window) = windows.get_mut(&id) {
+            window.always_on_top = always_on_top;
+            window.last_activity = SystemTime::now();
+            Ok(())
+        } else {
+            Err(t!("error-window-not-found", id = id))
+        }
+    }
+
+    /// Apply a manifest `[window]` section to a freshly created window
+    pub fn apply_manifest_window(&self, id: u64, config: &WindowConfig) -> Result<(), String> {
+        let window_type = match config.window_type {
+            ManifestWindowType::Normal => WindowType::Normal,
+            ManifestWindowType::Dialog => WindowType::Dialog,
+            ManifestWindowType::Utility => WindowType::Utility,
+            ManifestWindowType::Splash => WindowType::Splash,
+            ManifestWindowType::Notification => WindowType::Notification,
+        };
+        let above = TypeBehavior::of(&window_type).above;
+        self.set_window_type(id, window_type)?;
+        if let Some(enabled) = config.decorations {
+            self.set_server_decorations(id, enabled)?;
+        }
+        self.set_always_on_top(id, config.always_on_top || above)?;
+        // Sized by the manifest first, so the lock does not undo it
+        self.set_resizable(id, config.resizable)?;
+
+        let state = match config.initial_state {
+            InitialWindowState::Normal => WindowState::Normal,
+            InitialWindowState::Maximized => WindowState::Maximized,
+            InitialWindowState::Minimized => WindowState::Minimized,
+            InitialWindowState::Fullscreen => WindowState::Fullscreen,
+        };
+        self.set_window_state(id, state)
+    }
+
+    /// Toggle WASMA-drawn decorations, e.g. when a client negotiates its own CSD
+    pub fn set_server_decorations(&self, id: u64, enabled: bool) -> Result<(), String> {
+        let mut windows = self.windows.lock().unwrap();
+        if let Some(window) = windows.get_mut(&id) {
+            window.server_decorations = enabled && self.decorations.theme().enabled;
+            Ok(())
+        } else {
+            Err(t!("error-window-not-found", id = id))
+        }
+    }
+
+    /// Replace the badges drawn over a window's icon
+    pub fn set_window_badges(&self, id: u64, badges: Vec<IconBadge>) -> Result<(), String> {
+        let mut windows = self.windows.lock().unwrap();
+        if let Some(window) = windows.get_mut(&id) {
+            window.badges = badges;
+            Ok(())
+        } else {
+            Err(t!("error-window-not-found", id = id))
+        }
+    }
+
+    /// Record the enforcement report of the process launched for a window
+    pub fn set_window_enforcement(&self, id: u64, report: EnforcementReport) -> Result<(), String> {
+        let mut windows = self.windows.lock().unwrap();
+        if let Some(window) = windows.get_mut(&id) {
+            window.enforcement = Some(report);
+            Ok(())
+        } else {
+            Err(t!("error-window-not-found", id = id))
+        }
+    }
+
+    /// Window icon at `size` px with its badges, for taskbars and previews
+    pub fn composed_icon(&self, id: u64, size: u32) -> Result<Arc<ComposedIcon>, String> {
+        let (icon, badges) = {
+            let windows = self.windows.lock().unwrap();
+            let window = windows.get(&id).ok_or_else(|| t!("error-window-not-found", id = id))?;
+            (window.icon.clone(), window.badges.clone())
+        };
+        let info = icon.map(|path| IconInfo {
+            name: path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+            format: path.extension()
+                .and_then(|e| e.to_str())
+                .and_then(IcoFormat::from_extension)
+                .unwrap_or(IcoFormat::Png),
+            size: IconSize::from_u32(size).unwrap_or(IconSize::Size48),
+            path,
+        });
+        self.badge_compositor.compose(info.as_ref(), &badges, size)
+    }
+
+    /// Give an icon-less window the icon embedded in the binary it runs (ELF, PE, AppImage)
+    pub fn set_window_icon_from_executable(&self, id: u64, exe: &Path) -> Result<Option<PathBuf>, String> {
+        if let Some(icon) = self.windows.lock().unwrap()
+            .get(&id)
+            .ok_or_else(|| t!("error-window-not-found", id = id))?
+            .icon
+            .clone()
+        {
+            return Ok(Some(icon));
+        }
+
+        // Extraction reads the binary; keep the windows lock released meanwhile
+        let icon = WsdgIcoCtl::new().icon_for_executable(exe, None).map(|i| i.path);
+        if let Some(window) = self.windows.lock().unwrap().get_mut(&id) {
+            window.icon = icon.clone();
+        }
+        Ok(icon)
+    }
+
+    /// Icon of the process that owns the window (`_NET_WM_PID` of X11 clients).
+    /// The pid is client-set, so the binary is only read, on a worker thread off the map path.
+    pub fn set_window_icon_from_pid(self: &Arc<Self>, id: u64, pid: u32) -> Result<(), String> {
+        let exe = std::fs::read_link(format!("/proc/{}/exe", pid))
+            .map_err(|e| format!("Executable of pid {} unknown: {}", pid, e))?;
+        let handler = Arc::downgrade(self);
+        std::thread::spawn(move || {
+            if let Some(handler) = handler.upgrade() {
+                handler.set_window_icon_from_executable(id, &exe).ok();
+            }
+        });
+        Ok(())
+    }
+
+    pub fn set_permissions(&self, id: u64, permissions: PermissionScope) -> Result<(), String> {
+        let mut windows = self.windows.lock().unwrap();
+        if let Some(window) = windows.get_mut(&id) {
+            window.permissions = permissions;
+            window.last_activity = SystemTime::now();
+            Ok(())
+        } else {
+            Err(t!("error-window-not-found", id = id))
+        }
+    }
+
+    pub fn set_backend_type(&self, id: u64, backend_type: BackendType) -> Result<(), String> {
+        let mut windows = self.windows.lock().unwrap();
+        if let Some(window) = windows.get_mut(&id) {
+            window.backend_type = backend_type;
             window.last_activity = SystemTime::now();
             Ok(())
         } else {
-            Err(format!("Window {} not found", id))
+            Err(t!("error-window-not-found", id = id))
         }
     }
 
+    /// Focus `id`; unmanaged windows (tooltips, menus, notifications) never
+    /// take focus, and a window with a visible dialog passes it to the dialog
     pub fn focus_window(&self, id: u64) -> Result<(), String> {
+        // Activating a window on another workspace switches to it
+        let workspace = self.windows.lock().unwrap()
+            .get(&id)
+            .filter(|w| !workspaces::visible_on(w, self.active_workspace()))
+            .map(|w| w.workspace);
+        if let Some(workspace) = workspace {
+            self.switch_workspace(workspace)?;
+        }
+
         let mut windows = self.windows.lock().unwrap();
+        let Some(window) = windows.get(&id) else {
+            return Err(t!("error-window-not-found", id = id));
+        };
+        if !TypeBehavior::of(&window.window_type).managed {
+            return Ok(());
+        }
+        let requested = id;
+        let id = modal_target(&windows, id);
+        if id != requested {
+            // Focusing the parent brings its modal to the front
+            self.raise_window(id);
+        }
         
         for window in windows.values_mut() {
             window.focused = false;
@@ -454,33 +1875,78 @@ impl WindowHandler {
         if let Some(window) = windows.get_mut(&id) {
             window.focused = true;
             window.last_activity = SystemTime::now();
+            let title = window.title.clone();
             drop(windows);
             
             let mut focused = self.focused_window.lock().unwrap();
-            *focused = Some(id);
+            let previous = focused.replace(id);
+            drop(focused);
+            self.focus_history.lock().unwrap().insert(id, SystemTime::now());
+            // Active windows keep their resources
+            self.renew_window_lease(id)?;
+            if previous != Some(id) {
+                osd::show(title, OsdIcon::Focus, Duration::from_millis(1200));
+                self.emit(WindowEvent::FocusChanged { previous, current: Some(id) });
+            }
             Ok(())
         } else {
-            Err(format!("Window {} not found", id))
+            Err(t!("error-window-not-found", id = id))
+        }
+    }
+
+    /// Focus the next (or previous) window of the active workspace in id
+    /// order; minimized windows and parents blocked by a modal are skipped
+    pub fn cycle_focus(&self, reverse: bool) -> Option<u64> {
+        let mut ids: Vec<u64> = self.windows.lock().unwrap().values()
+            .filter(|w| w.visible && w.state != WindowState::Minimized && w.state != WindowState::Hidden)
+            .filter(|w| TypeBehavior::of(&w.window_type).managed)
+            .map(|w| w.id)
+            .collect();
+        ids.retain(|id| self.modal_blocker(*id).is_none());
+        ids.sort_unstable();
+        let position = self.get_focused_window().and_then(|focused| ids.iter().position(|id| *id == focused));
+        let next = match (position, reverse) {
+            (None, false) => *ids.first()?,
+            (None, true) => *ids.last()?,
+            (Some(i), false) => ids[(i + 1) % ids.len()],
+            (Some(i), true) => ids[(i + ids.len() - 1) % ids.len()],
+        };
+        self.focus_window(next).ok()?;
+        Some(next)
+    }
+
+    /// Drop keyboard focus (e.g. focus-follows-mouse over the desktop)
+    pub fn clear_focus(&self) {
+        let mut windows = self.windows.lock().unwrap();
+        for window in windows.values_mut() {
+            window.focused = false;
+        }
+        drop(windows);
+        let previous = self.focused_window.lock().unwrap().take();
+        if previous.is_some() {
+            self.emit(WindowEvent::FocusChanged { previous, current: None });
         }
     }
 
     pub fn close_window(&self, id: u64) -> Result<(), String> {
+        let _span = tracing::info_span!("window", id).entered();
         let mut windows = self.windows.lock().unwrap();
         
         if let Some(window) = windows.get(&id).cloned() {
             let assignment_id = window.assignment_id;
-            let children = window.children_ids.clone();
             
-            // Close child windows
-            for child_id in children {
+            // Close child windows; modals and dialogs of children go too
+            let mut closed = Vec::new();
+            for child_id in descendants(&windows, id) {
                 if let Some(child) = windows.get(&child_id) {
                     if let Some(child_assignment_id) = child.assignment_id {
-                        if let Some(mut assignment) = self.wbackend.get_assignment(child_assignment_id) {
-                            assignment.stop_task();
-                        }
+                        self.wbackend.remove_assignment(child_assignment_id);
+                        self.assignment_to_window.lock().unwrap().remove(&child_assignment_id);
                     }
                 }
-                windows.remove(&child_id);
+                if windows.remove(&child_id).is_some() {
+                    closed.push(child_id);
+                }
             }
             
             // Remove child from parent
@@ -490,21 +1956,54 @@ impl WindowHandler {
                 }
             }
             
-            // Stop assignment and remove from mapping
+            // Remove the assignment (stops its task, frees its quota share) and the mapping
             if let Some(aid) = assignment_id {
-                if let Some(mut assignment) = self.wbackend.get_assignment(aid) {
-                    assignment.stop_task();
-                }
+                self.wbackend.remove_assignment(aid);
                 
                 let mut mapping = self.assignment_to_window.lock().unwrap();
                 mapping.remove(&aid);
             }
             
             windows.remove(&id);
-            println!("🗑️  Window {} closed", id);
+            drop(windows);
+            closed.push(id);
+            self.auto_close.lock().unwrap().retain(|window_id, _| !closed.contains(window_id));
+            if window.window_type == WindowType::Notification {
+                self.restack_notifications();
+            }
+
+            let mut focus_history = self.focus_history.lock().unwrap();
+            let mut frame_sources = self.frame_sources.lock().unwrap();
+            let mut raised = self.raised.lock().unwrap();
+            let mut window_pids = self.window_pids.lock().unwrap();
+            for window_id in &closed {
+                focus_history.remove(window_id);
+                frame_sources.remove(window_id);
+                raised.remove(window_id);
+                window_pids.remove(window_id);
+            }
+            drop(window_pids);
+            drop(raised);
+            drop(focus_history);
+            drop(frame_sources);
+
+            // Unused contexts go back to the pool for the next window
+            let pool = self.context_pool();
+            let mut contexts = self.renderer_contexts.lock().unwrap();
+            for window_id in &closed {
+                if let (Some(context), Some(pool)) = (contexts.remove(window_id), pool.as_ref()) {
+                    pool.release(context);
+                }
+            }
+            drop(contexts);
+
+            for window_id in closed {
+                self.emit(WindowEvent::WindowClosed { window_id });
+            }
+            tracing::info!("window closed");
             Ok(())
         } else {
-            Err(format!("Window {} not found", id))
+            Err(t!("error-window-not-found", id = id))
         }
     }
 
@@ -526,7 +2025,10 @@ impl WindowHandler {
                 parent.children_ids.push(child_id);
             }
         }
-        
+        drop(windows);
+
+        // Dialogs typed before they were parented move onto the parent now
+        self.apply_type_behavior(child_id);
         Ok(())
     }
 
@@ -535,15 +2037,302 @@ impl WindowHandler {
         windows.get(&id).cloned()
     }
 
+    /// Windows in stacking order: creation order with raised modals moved up,
+    /// always-on-top windows last
     pub fn list_windows(&self) -> Vec<Window> {
         let windows = self.windows.lock().unwrap();
-        windows.values().cloned().collect()
+        let mut list: Vec<Window> = windows.values().cloned().collect();
+        let raised = self.raised.lock().unwrap();
+        list.sort_by_key(|w| (w.always_on_top, raised.get(&w.id).copied().unwrap_or(0), w.id));
+        list
+    }
+
+    /// Stack a window above every window raised before it (always-on-top
+    /// windows stay above)
+    pub fn raise_window(&self, id: u64) {
+        let mut raised = self.raised.lock().unwrap();
+        let top = raised.values().max().copied().unwrap_or(0);
+        if raised.get(&id) != Some(&top) {
+            raised.insert(id, top + 1);
+        }
+    }
+
+    /// Mark a child window modal (or not): while visible it takes its
+    /// parent's input and focus, and it closes with the parent
+    pub fn set_modal(&self, id: u64, modal: bool) -> Result<(), String> {
+        let mut windows = self.windows.lock().unwrap();
+        let Some(window) = windows.get_mut(&id) else {
+            return Err(t!("error-window-not-found", id = id));
+        };
+        window.modal = modal;
+        drop(windows);
+
+        // A new modal takes over from the parent that has focus now
+        let blocked = self.get_focused_window().filter(|focused| modal && self.modal_blocker(*focused) == Some(id));
+        if let Some(parent) = blocked {
+            self.focus_window(parent)?;
+        }
+        Ok(())
+    }
+
+    /// The modal window that takes `id`'s input, if any
+    pub fn modal_blocker(&self, id: u64) -> Option<u64> {
+        let windows = self.windows.lock().unwrap();
+        let target = modal_target(&windows, id);
+        (target != id).then_some(target)
+    }
+
+    /// Whether input routed to `id` should reach it; false while a modal child is up
+    pub fn accepts_input(&self, id: u64) -> bool {
+        self.modal_blocker(id).is_none()
+    }
+
+    pub fn active_workspace(&self) -> u32 {
+        *self.active_workspace.lock().unwrap()
+    }
+
+    pub fn list_workspaces(&self) -> Vec<Workspace> {
+        workspaces::list(&self.list_windows(), self.active_workspace())
+    }
+
+    /// Show the windows of workspace `number` and hide the rest; focus goes
+    /// to the window there that had it last
+    pub fn switch_workspace(&self, number: u32) -> Result<(), String> {
+        let number = workspaces::validate(number)?;
+        let previous = std::mem::replace(&mut *self.active_workspace.lock().unwrap(), number);
+        self.apply_workspace_visibility();
+
+        let focused_visible = self.get_focused_window()
+            .and_then(|id| self.get_window(id))
+            .is_some_and(|w| w.visible);
+        if !focused_visible {
+            let history = self.focus_history.lock().unwrap().clone();
+            let last = self.list_windows().into_iter()
+                .filter(|w| w.visible && TypeBehavior::of(&w.window_type).managed)
+                .filter_map(|w| history.get(&w.id).map(|at| (*at, w.id)))
+                .max()
+                .map(|(_, id)| id);
+            match last {
+                Some(id) => self.focus_window(id)?,
+                None => self.clear_focus(),
+            }
+        }
+
+        if previous != number {
+            self.emit(WindowEvent::WorkspaceChanged { previous, current: number });
+        }
+        Ok(())
+    }
+
+    /// Put a window, with its dialogs and other children, on workspace `number`
+    pub fn move_to_workspace(&self, id: u64, number: u32) -> Result<(), String> {
+        let number = workspaces::validate(number)?;
+        let mut windows = self.windows.lock().unwrap();
+        if !windows.contains_key(&id) {
+            return Err(t!("error-window-not-found", id = id));
+        }
+        for window_id in std::iter::once(id).chain(descendants(&windows, id)) {
+            if let Some(window) = windows.get_mut(&window_id) {
+                window.workspace = number;
+            }
+        }
+        drop(windows);
+        self.apply_workspace_visibility();
+
+        if self.get_focused_window().and_then(|f| self.get_window(f)).is_some_and(|w| !w.visible) {
+            self.clear_focus();
+        }
+        Ok(())
+    }
+
+    fn apply_workspace_visibility(&self) {
+        let active = self.active_workspace();
+        for window in self.windows.lock().unwrap().values_mut() {
+            window.visible = workspaces::visible_on(window, active);
+        }
     }
 
     pub fn get_focused_window(&self) -> Option<u64> {
         let focused = self.focused_window.lock().unwrap();
         *focused
     }
+
+    /// Write every window (geometry, state, manifest path, limits) to `path`
+    pub fn save_session(&self, path: impl AsRef<std::path::Path>) -> Result<usize, String> {
+        let path = path.as_ref();
+        let mut windows = self.list_windows();
+        windows.sort_by_key(|w| w.id);
+        let count = windows.len();
+
+        let session = WindowSession { version: SESSION_VERSION, windows, active_workspace: self.active_workspace() };
+        let json = serde_json::to_string_pretty(&session)
+            .map_err(|e| format!("Session could not be serialized: {}", e))?;
+
+        // Temp file + fsync + rename: a crash never leaves half a session.
+        // The previous session stays as <name>.bak
+        wsdg_xdg::write_atomic(path, json)
+            .map_err(|e| format!("Session could not be written to {}: {}", path.display(), e))?;
+
+        Ok(count)
+    }
+
+    /// Release what a crashed previous run left behind (assignments, cgroups)
+    /// and start recording this run's assignments under `state_dir`.
+    /// Call before restoring a session so restored windows are not swept up.
+    pub fn recover_previous_run(&self, state_dir: &std::path::Path) -> Result<RecoveryReport, String> {
+        self.wbackend.recover_previous_run(state_dir).map_err(|e| e.to_string())
+    }
+
+    /// Re-create the windows of a saved session with fresh assignments.
+    /// Returns the new window ids in saved order; parent/child links are remapped.
+    pub fn restore_session(&self, path: impl AsRef<std::path::Path>) -> Result<Vec<u64>, String> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Session could not be read from {}: {}", path.display(), e))?;
+        let session: WindowSession = serde_json::from_str(&content)
+            .map_err(|e| self.report_error(ErrorCategory::Session, format!("Session file is invalid: {}", e)))?;
+        if session.version > SESSION_VERSION {
+            return Err(format!(
+                "Session version {} is newer than supported version {}",
+                session.version, SESSION_VERSION
+            ));
+        }
+
+        let mut id_map = HashMap::new();
+        let mut restored = Vec::new();
+        let mut focused = None;
+
+        for saved in &session.windows {
+            // Saved limits win over the manifest: it may have moved or changed since
+            let id = self.insert_window(
+                saved.title.clone(),
+                saved.app_id.clone(),
+                saved.geometry,
+                saved.manifest_path.clone(),
+                saved.resource_mode,
+                saved.resource_limits.clone(),
+                saved.permissions.clone(),
+            )?;
+            id_map.insert(saved.id, id);
+            restored.push(id);
+            if saved.focused {
+                focused = Some(id);
+            }
+        }
+
+        {
+            let mut windows = self.windows.lock().unwrap();
+            for saved in &session.windows {
+                let Some(window) = id_map.get(&saved.id).and_then(|id| windows.get_mut(id)) else {
+                    continue;
+                };
+                window.state = saved.state.clone();
+                window.window_type = saved.window_type.clone();
+                window.visible = saved.visible;
+                window.backend_type = saved.backend_type.clone();
+                window.server_decorations = saved.server_decorations;
+                window.resizable = saved.resizable;
+                window.always_on_top = saved.always_on_top;
+                window.skip_taskbar = saved.skip_taskbar;
+                window.workspace = saved.workspace;
+                window.modal = saved.modal;
+                window.badges = saved.badges.clone();
+                window.icon = window.icon.take().or_else(|| saved.icon.clone());
+                window.parent_id = saved.parent_id.and_then(|p| id_map.get(&p).copied());
+                window.children_ids = saved.children_ids.iter()
+                    .filter_map(|c| id_map.get(c).copied())
+                    .collect();
+            }
+        }
+
+        self.switch_workspace(session.active_workspace).ok();
+        if let Some(id) = focused {
+            self.focus_window(id).ok();
+        }
+
+        tracing::info!(count = restored.len(), path = %path.display(), "windows restored from session");
+        Ok(restored)
+    }
+
+    /// Periodically save the session while the handler is alive; only writes on change
+    pub fn autosave_session(self: &Arc<Self>, path: std::path::PathBuf) {
+        let handler = Arc::downgrade(self);
+
+        std::thread::spawn(move || {
+            let mut last = String::new();
+            loop {
+                std::thread::sleep(SESSION_AUTOSAVE_INTERVAL);
+                let Some(handler) = handler.upgrade() else { break };
+
+                let mut windows = handler.list_windows();
+                windows.sort_by_key(|w| w.id);
+                // last_activity changes on every touch; compare the layout only
+                let layout: Vec<_> = windows.iter()
+                    .map(|w| (w.id, &w.title, &w.state, w.geometry.x, w.geometry.y, w.geometry.width, w.geometry.height, w.visible, w.workspace))
+                    .collect();
+                let current = format!("{:?}", layout);
+                if current == last {
+                    continue;
+                }
+
+                match handler.save_session(&path) {
+                    Ok(_) => last = current,
+                    Err(e) => tracing::warn!(error = %e, "session autosave failed"),
+                }
+            }
+        });
+    }
+
+    /// Freeze windows, id counters and assignments for a live upgrade
+    pub fn snapshot(&self) -> HandlerSnapshot {
+        let mut windows = self.list_windows();
+        windows.sort_by_key(|w| w.id);
+
+        let mut assignment_to_window: Vec<(u32, u64)> = self.assignment_to_window.lock().unwrap()
+            .iter()
+            .map(|(a, w)| (*a, *w))
+            .collect();
+        assignment_to_window.sort();
+
+        HandlerSnapshot {
+            windows,
+            next_id: *self.next_id.lock().unwrap(),
+            focused_window: self.get_focused_window(),
+            assignment_to_window,
+            backend: self.wbackend.snapshot(),
+            session_locked: crate::session_lock::SESSION_LOCKED.load(std::sync::atomic::Ordering::SeqCst),
+            active_workspace: self.active_workspace(),
+        }
+    }
+
+    /// Adopt a snapshot taken by the previous process; window ids are kept
+    pub fn restore_snapshot(&self, snapshot: HandlerSnapshot) -> Result<usize, String> {
+        self.wbackend.restore(&snapshot.backend).map_err(|e| e.to_string())?;
+
+        let restored = snapshot.windows.len();
+        {
+            let mut windows = self.windows.lock().unwrap();
+            for window in snapshot.windows {
+                self.raise_window(window.id);
+                windows.insert(window.id, window);
+            }
+            let mut next_id = self.next_id.lock().unwrap();
+            let highest = windows.keys().max().copied().unwrap_or(0);
+            *next_id = (*next_id).max(snapshot.next_id).max(highest + 1);
+        }
+
+        self.assignment_to_window.lock().unwrap().extend(snapshot.assignment_to_window);
+        *self.active_workspace.lock().unwrap() = snapshot.active_workspace;
+        if snapshot.focused_window.is_some() {
+            *self.focused_window.lock().unwrap() = snapshot.focused_window;
+        }
+        if snapshot.session_locked {
+            crate::session_lock::SESSION_LOCKED.store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        tracing::info!(count = restored, "windows restored from snapshot");
+        Ok(restored)
+    }
 }
 
 // ============================================================================
@@ -562,11 +2351,77 @@ pub enum Message {
     UpdateResourceCycle,
     AdjustResources(u64),
     ChangeExecutionMode(u64, ExecutionMode),
+    ToggleHotkeys,
+    TogglePreview,
+    OsdTick,
+    DismissOsd(u64),
+    SnapWindow(u64, SnapDirection),
+    SnapFocused(SnapDirection),
+    AnimationTick,
+    FocusTick,
+    SetFocusPolicy(FocusPolicy),
+    TogglePalette,
+    ClosePalette,
+    PaletteQueryChanged(String),
+    PaletteMove(i32),
+    PaletteSubmit,
+    PaletteRun(usize),
+    /// Timer: run the backend phases that are due, redraw the resource graphs
+    ResourceTick,
+    /// Focus or size of the manager window changed; ask whether it is minimized
+    ManagerWindowChanged,
+    /// Minimized state of the manager window; None where the platform cannot tell
+    ManagerMinimized(Option<bool>),
+    /// Reload settings.conf if it changed, restyling the manager; pick up
+    /// output hotplug
+    SettingsTick,
+    ThumbnailTick,
+    ToggleProtocols,
+    ProtocolTick,
+    ReconnectProtocol(usize),
+    SetProtocolEnabled(usize, bool),
+    SwitchWorkspace(u32),
+    MoveToWorkspace(u64, u32),
+    /// Focus the next window, or the previous one when true
+    CycleFocus(bool),
+    CloseFocused,
+    ToggleSingularity,
+    NextLayout,
 }
 
 pub struct WasmaWindowManager {
     handler: Arc<WindowHandler>,
     selected_window: Option<u64>,
+    hotkeys: Arc<HotkeyRegistry>,
+    show_hotkeys: bool,
+    // Dry-run of the next cycle with a heavy window added
+    preview: Option<CyclePlan>,
+    snapper: WindowSnapper,
+    // Output changes: tiling zones follow the primary output
+    monitor_updates: Receiver<MonitorMap>,
+    focus: FocusEngine,
+    commands: Arc<CommandRegistry>,
+    // Open palette: (query, selected result)
+    palette: Option<(String, usize)>,
+    protocols: Arc<ProtocolControl>,
+    show_protocols: bool,
+    // Live previews for the window cards, refreshed every THUMBNAIL_REFRESH_INTERVAL
+    thumbnails: HashMap<u64, image::Handle>,
+    // Last layout NextLayout tiled the windows with
+    layout: LayoutPolicy,
+    // [window] graph_refresh_ms; None leaves refreshing to "Update Resources"
+    graph_refresh: Option<Duration>,
+    // [window] resource_cycle_ms; None leaves the cycle to "Update Resources"
+    resource_cycle: Option<Duration>,
+    // Timers pause while nobody can see the manager
+    minimized: bool,
+    // settings.conf; its WASMA sync callback sets the GUI appearance
+    settings: WsdgSettingsManager,
+    settings_modified: Option<SystemTime>,
+}
+
+fn palette_input_id() -> text_input::Id {
+    text_input::Id::new("wasma-command-palette")
 }
 
 impl Application for WasmaWindowManager {
@@ -579,14 +2434,121 @@ impl Application for WasmaWindowManager {
         let handler = Arc::new(WindowHandler::new(flags));
         
         // Load wasma.in.conf (optional)
-        if let Err(e) = handler.load_wasma_config("/etc/wasma/wasma.in.conf") {
-            eprintln!("⚠️  WASMA config could not be loaded: {}", e);
+        let config_path = parser::default_config_path();
+        if let Err(e) = handler.load_wasma_config(&config_path) {
+            tracing::warn!(error = %e, "WASMA config could not be loaded");
+        }
+        handler.watch_wasma_config(&config_path);
+        handler.start_cpu_sampler(cpu_history::CPU_SAMPLE_INTERVAL);
+        handler.start_auto_close(window_placement::AUTO_CLOSE_INTERVAL);
+        handler.start_telemetry(telemetry::TELEMETRY_FLUSH_INTERVAL);
+
+        // Clean up after a crashed previous run before anything is restored
+        match default_state_dir().and_then(|state| handler.recover_previous_run(&state)) {
+            Ok(report) if !report.is_empty() => tracing::info!(%report, "crash recovery"),
+            Ok(_) => {}
+            Err(e) => tracing::warn!(error = %e, "crash recovery skipped"),
+        }
+
+        // Bring back the previous layout, then keep it on disk
+        match default_session_path() {
+            Ok(session) => {
+                if session.exists() {
+                    if let Err(e) = handler.restore_session(&session) {
+                        tracing::warn!(error = %e, "previous session could not be restored");
+                    }
+                }
+                handler.autosave_session(session);
+            }
+            Err(e) => tracing::warn!(error = %e, "session persistence disabled"),
+        }
+
+        let hotkeys = Arc::new(HotkeyRegistry::new(handler.clone()));
+        let daemon = HotkeyDaemon::new(hotkeys.clone(), global_hotkeys::default_socket_path());
+        if let Err(e) = daemon.start() {
+            tracing::warn!(error = %e, "hotkey daemon could not be started");
+        }
+
+        // `wasma close`, `wasma list`, ... act on this instance
+        let control = ControlDaemon::new(handler.clone(), flags, control::default_socket_path());
+        if let Err(e) = control.start() {
+            tracing::warn!(error = %e, "control daemon could not be started");
+        }
+        #[cfg(feature = "dbus")]
+        if let Err(e) = crate::dbus::DbusService::new(handler.clone(), flags).start() {
+            tracing::warn!(error = %e, "D-Bus interface could not be started");
+        }
+
+        // Tile on the real primary output, and keep following hotplug
+        let monitors = MonitorMap::detect().unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "outputs could not be detected, assuming one default screen");
+            MonitorMap::single(crate::DEFAULT_SCREEN_SIZE.0, crate::DEFAULT_SCREEN_SIZE.1)
+        });
+        let primary = monitors.primary();
+        let snapper = WindowSnapper::new(handler.clone(), primary.width, primary.height);
+        let monitor_updates = MonitorWatcher::spawn(monitors.clone(), HOTPLUG_POLL_INTERVAL);
+        handler.set_monitors(monitors);
+        let focus = FocusEngine::new(handler.clone(), FocusConfig::from_wsdg());
+
+        let commands = CommandRegistry::global();
+        command_palette::register_builtin(&commands, handler.clone());
+
+        // Super+Arrow tiling belongs to the window manager
+        for (combo, name) in [
+            ("Super+Left", "Tile left"),
+            ("Super+Right", "Tile right"),
+            ("Super+Up", "Maximize / tile top"),
+            ("Super+Down", "Restore / tile bottom"),
+        ] {
+            if let Ok(combo) = global_hotkeys::KeyCombo::parse(combo) {
+                hotkeys.reserve(combo, name);
+            }
+        }
+        // Focus cycling, close, singularity and layout bindings from settings.conf
+        let wm_hotkeys = WmHotkeys::from_wsdg();
+        wm_hotkeys.reserve_in(&hotkeys);
+        WmHotkeys::set_global(wm_hotkeys);
+
+        // This process draws the OSD; other processes forward to us
+        if let Err(e) = osd::serve(osd::default_socket_path()) {
+            tracing::warn!(error = %e, "OSD server could not be started");
         }
         
+        let (graph_refresh, resource_cycle) = resource_graphs::intervals_from_wsdg();
+        if let Some(every) = resource_cycle {
+            handler.set_resource_cadence(CycleCadence::uniform(every));
+        }
+
+        // Palette and text size follow [theme] and [font] in settings.conf, live
+        let mut settings = WsdgSettingsManager::new(WsdgEnv::new());
+        settings.enable_wasma_sync(|settings| accessibility::set_appearance(accessibility::Appearance::from_settings(settings)));
+        let settings_modified = modified_time(settings.settings_path());
+        if let Err(e) = settings.load_and_sync() {
+            tracing::warn!(error = %e, "settings.conf could not be loaded");
+            settings.trigger_wasma_sync();
+        }
+
         (
             WasmaWindowManager {
                 handler,
                 selected_window: None,
+                snapper,
+                monitor_updates,
+                focus,
+                commands,
+                palette: None,
+                hotkeys,
+                show_hotkeys: false,
+                preview: None,
+                protocols: ProtocolControl::global(),
+                show_protocols: false,
+                thumbnails: HashMap::new(),
+                layout: LayoutPolicy::Floating,
+                graph_refresh,
+                resource_cycle,
+                minimized: false,
+                settings,
+                settings_modified,
             },
             Command::none(),
         )
@@ -596,9 +2558,14 @@ impl Application for WasmaWindowManager {
         String::from("WASMA - Window Assignment System Monitoring Architecture")
     }
 
+    fn theme(&self) -> Theme {
+        accessibility::iced_theme()
+    }
+
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::CreateWindow => {
+                self.focus.note_user_input();
                 let geometry = WindowGeometry {
                     x: 100,
                     y: 100,
@@ -614,11 +2581,13 @@ impl Application for WasmaWindowManager {
                     ResourceMode::Auto,
                 ) {
                     Ok(id) => {
-                        println!("✅ Window {} created", id);
+                        if let Err(e) = self.focus.request_focus(id) {
+                            tracing::error!(window = id, error = %e, "could not focus");
+                        }
                         Command::none()
                     }
                     Err(e) => {
-                        eprintln!("❌ Window could not be created: {}", e);
+                        tracing::error!(error = %e, "window could not be created");
                         Command::none()
                     }
                 }
@@ -626,17 +2595,20 @@ impl Application for WasmaWindowManager {
             
             Message::CloseWindow(id) => {
                 if let Err(e) = self.handler.close_window(id) {
-                    eprintln!("❌ Window could not be closed {}: {}", id, e);
+                    tracing::error!(window = id, error = %e, "window could not be closed");
                 }
                 if self.selected_window == Some(id) {
                     self.selected_window = None;
                 }
+                self.snapper.unbind_window(id);
+                self.focus.forget_window(id);
                 Command::none()
             }
             
             Message::FocusWindow(id) => {
+                self.focus.note_user_input();
                 if let Err(e) = self.handler.focus_window(id) {
-                    eprintln!("❌ Could not focus {}: {}", id, e);
+                    tracing::error!(window = id, error = %e, "could not focus");
                 }
                 self.selected_window = Some(id);
                 Command::none()
@@ -644,14 +2616,14 @@ impl Application for WasmaWindowManager {
             
             Message::MinimizeWindow(id) => {
                 if let Err(e) = self.handler.set_window_state(id, WindowState::Minimized) {
-                    eprintln!("❌ Could not minimize {}: {}", id, e);
+                    tracing::error!(window = id, error = %e, "could not minimize");
                 }
                 Command::none()
             }
             
             Message::MaximizeWindow(id) => {
                 if let Err(e) = self.handler.set_window_state(id, WindowState::Maximized) {
-                    eprintln!("❌ Could not maximize {}: {}", id, e);
+                    tracing::error!(window = id, error = %e, "could not maximize");
                 }
                 Command::none()
             }
@@ -664,64 +2636,342 @@ impl Application for WasmaWindowManager {
                         WindowState::Fullscreen
                     };
                     if let Err(e) = self.handler.set_window_state(id, new_state) {
-                        eprintln!("❌ Could not toggle fullscreen {}: {}", id, e);
+                        tracing::error!(window = id, error = %e, "could not toggle fullscreen");
+                    }
+                }
+                Command::none()
+            }
+            
+            Message::HideWindow(id) => {
+                if let Err(e) = self.handler.set_window_state(id, WindowState::Hidden) {
+                    tracing::error!(window = id, error = %e, "could not hide");
+                }
+                Command::none()
+            }
+            
+            Message::UpdateResourceCycle => {
+                self.handler.run_resource_cycle();
+                Command::none()
+            }
+            
+            Message::AdjustResources(id) => {
+                tracing::debug!(window = id, "resource adjustment requested");
+                Command::none()
+            }
+            
+            Message::ChangeExecutionMode(id, mode) => {
+                if let Some(window) = self.handler.get_window(id) {
+                    let mut new_limits = window.resource_limits.clone();
+                    new_limits.execution_mode = Some(mode);
+                    if let Err(e) = self.handler.adjust_window_resources(id, new_limits) {
+                        tracing::error!(error = %e, "execution mode could not be changed");
+                    }
+                }
+                Command::none()
+            }
+
+            Message::ToggleHotkeys => {
+                self.show_hotkeys = !self.show_hotkeys;
+                Command::none()
+            }
+
+            Message::TogglePreview => {
+                self.preview = match self.preview {
+                    Some(_) => None,
+                    None => Some(self.handler.preview_window(&heavy_window_limits())),
+                };
+                Command::none()
+            }
+
+            Message::ToggleProtocols => {
+                self.show_protocols = !self.show_protocols;
+                Command::none()
+            }
+
+            Message::ReconnectProtocol(index) => {
+                self.protocols.request_reconnect(index);
+                Command::none()
+            }
+
+            Message::SetProtocolEnabled(index, enabled) => {
+                self.protocols.set_enabled(index, enabled);
+                Command::none()
+            }
+
+            Message::SwitchWorkspace(number) => {
+                if let Err(e) = self.handler.switch_workspace(number) {
+                    tracing::error!(error = %e, "could not switch workspace");
+                }
+                Command::none()
+            }
+
+            Message::MoveToWorkspace(id, number) => {
+                if let Err(e) = self.handler.move_to_workspace(id, number) {
+                    tracing::error!(window = id, workspace = number, error = %e, "could not move to workspace");
+                }
+                Command::none()
+            }
+
+            Message::CycleFocus(reverse) => {
+                if let Some(id) = self.handler.cycle_focus(reverse) {
+                    self.selected_window = Some(id);
+                }
+                Command::none()
+            }
+
+            Message::CloseFocused => match self.handler.get_focused_window() {
+                Some(id) => self.update(Message::CloseWindow(id)),
+                None => Command::none(),
+            },
+
+            // The manager's singularity: the focused window alone, fullscreen
+            Message::ToggleSingularity => match self.handler.get_focused_window().or(self.selected_window) {
+                Some(id) => self.update(Message::ToggleFullscreen(id)),
+                None => Command::none(),
+            },
+
+            Message::NextLayout => {
+                self.layout = self.layout.next();
+                let tiled = self.handler.arrange_windows(self.layout);
+                osd::show(format!("Layout: {} ({} windows)", self.layout.name(), tiled), OsdIcon::Info, Duration::from_millis(1200));
+                Command::none()
+            }
+
+            Message::ManagerWindowChanged => window::fetch_minimized(window::Id::MAIN, Message::ManagerMinimized),
+
+            Message::ManagerMinimized(minimized) => {
+                if let Some(minimized) = minimized {
+                    self.minimized = minimized;
+                }
+                Command::none()
+            }
+
+            Message::ResourceTick => {
+                self.handler.run_due_resource_phases();
+                Command::none()
+            }
+
+            Message::SettingsTick => {
+                let modified = modified_time(self.settings.settings_path());
+                // Deleted file: keep the last appearance
+                if modified != self.settings_modified && modified.is_some() {
+                    if let Err(e) = self.settings.load_and_sync() {
+                        tracing::warn!(error = %e, "settings.conf reload skipped");
                     }
                 }
+                self.settings_modified = modified;
+
+                if let Some(monitors) = self.monitor_updates.try_iter().last() {
+                    let primary = monitors.primary();
+                    self.snapper.set_screen(primary.width, primary.height);
+                    self.handler.set_monitors(monitors);
+                }
+                Command::none()
+            }
+
+            // Only forces a redraw; expired toasts are pruned in view()
+            Message::OsdTick | Message::ProtocolTick => Command::none(),
+
+            Message::ThumbnailTick => {
+                // Windows without readable frames keep the text-only card
+                self.thumbnails = self.handler.list_windows().iter()
+                    .filter_map(|w| {
+                        let thumb = self.handler.capture_thumbnail(w.id, thumbnails::DEFAULT_THUMBNAIL_SIZE).ok()?;
+                        Some((w.id, image::Handle::from_pixels(thumb.width, thumb.height, thumb.data)))
+                    })
+                    .collect();
+                Command::none()
+            }
+
+            Message::DismissOsd(id) => {
+                osd::dismiss(id);
+                Command::none()
+            }
+
+            Message::SnapWindow(id, direction) => {
+                if let Err(e) = self.snapper.tile(id, direction) {
+                    tracing::error!(window = id, error = %e, "could not tile");
+                }
                 Command::none()
             }
-            
-            Message::HideWindow(id) => {
-                if let Err(e) = self.handler.set_window_state(id, WindowState::Hidden) {
-                    eprintln!("❌ Could not hide {}: {}", id, e);
+
+            Message::SnapFocused(direction) => {
+                if let Some(id) = self.handler.get_focused_window().or(self.selected_window) {
+                    return self.update(Message::SnapWindow(id, direction));
                 }
                 Command::none()
             }
-            
-            Message::UpdateResourceCycle => {
-                self.handler.run_resource_cycle();
+
+            Message::AnimationTick => {
+                self.snapper.tick(std::time::Instant::now());
                 Command::none()
             }
-            
-            Message::AdjustResources(id) => {
-                println!("🔧 Resource adjustment: window {}", id);
+
+            Message::FocusTick => {
+                self.focus.tick(std::time::Instant::now());
                 Command::none()
             }
-            
-            Message::ChangeExecutionMode(id, mode) => {
-                if let Some(window) = self.handler.get_window(id) {
-                    let mut new_limits = window.resource_limits.clone();
-                    new_limits.execution_mode = Some(mode);
-                    if let Err(e) = self.handler.adjust_window_resources(id, new_limits) {
-                        eprintln!("❌ Execution mode could not be changed: {}", e);
+
+            Message::SetFocusPolicy(policy) => {
+                let mut config = self.focus.config().clone();
+                config.policy = policy;
+                self.focus.set_config(config);
+                osd::show(format!("Focus policy: {:?}", policy), OsdIcon::Info, Duration::from_millis(1500));
+                Command::none()
+            }
+
+            Message::TogglePalette => {
+                if self.palette.take().is_some() {
+                    return Command::none();
+                }
+                self.palette = Some((String::new(), 0));
+                text_input::focus(palette_input_id())
+            }
+
+            Message::ClosePalette => {
+                self.palette = None;
+                Command::none()
+            }
+
+            Message::PaletteQueryChanged(query) => {
+                if let Some(palette) = self.palette.as_mut() {
+                    *palette = (query, 0);
+                }
+                Command::none()
+            }
+
+            Message::PaletteMove(delta) => {
+                // Only the first MAX_RESULTS are listed
+                let count = self.palette_results().len().min(command_palette::MAX_RESULTS);
+                if let Some((_, selected)) = self.palette.as_mut() {
+                    if count > 0 {
+                        *selected = (*selected as i32 + delta).rem_euclid(count as i32) as usize;
                     }
                 }
                 Command::none()
             }
+
+            Message::PaletteSubmit => {
+                let selected = self.palette.as_ref().map(|(_, s)| *s).unwrap_or(0);
+                self.update(Message::PaletteRun(selected))
+            }
+
+            Message::PaletteRun(index) => {
+                let Some(command) = self.palette_results().into_iter().nth(index) else {
+                    return Command::none();
+                };
+                self.palette = None;
+                self.update(command.action)
+            }
+        }
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = vec![
+            keyboard::on_key_press(snap_shortcut),
+            keyboard::on_key_press(hotkeys::wm_shortcut),
+            iced::event::listen_with(palette_shortcut),
+            iced::event::listen_with(manager_window_changed),
+            iced::time::every(CONFIG_WATCH_INTERVAL).map(|_| Message::SettingsTick),
+        ];
+
+        if !osd::active().is_empty() {
+            subscriptions.push(iced::time::every(Duration::from_millis(100)).map(|_| Message::OsdTick));
+        }
+        if self.focus.is_pending() {
+            subscriptions.push(iced::time::every(Duration::from_millis(50)).map(|_| Message::FocusTick));
+        }
+        // Background resource cycle, so leases and allocations move without the button
+        let cadence = self.resource_cycle.map(|_| self.handler.resource_cadence());
+        if let Some(every) = resource_tick_period(cadence, self.minimized) {
+            subscriptions.push(iced::time::every(every).map(|_| Message::ResourceTick));
+        }
+        // Redraw the resource graphs as the sampler fills them
+        if !self.minimized && !self.handler.list_windows().is_empty() {
+            if let Some(refresh) = self.graph_refresh {
+                subscriptions.push(iced::time::every(refresh).map(|_| Message::ResourceTick));
+            }
+            subscriptions.push(iced::time::every(thumbnails::THUMBNAIL_REFRESH_INTERVAL).map(|_| Message::ThumbnailTick));
+        }
+        if self.snapper.is_animating() {
+            subscriptions.push(iced::time::every(Duration::from_millis(16)).map(|_| Message::AnimationTick));
         }
+        if self.show_protocols {
+            subscriptions.push(iced::time::every(Duration::from_secs(1)).map(|_| Message::ProtocolTick));
+        }
+
+        Subscription::batch(subscriptions)
     }
 
     fn view(&self) -> Element<'_, Message> {
-        let windows = self.handler.list_windows();
+        let tokens = accessibility::tokens();
+        let active_workspace = self.handler.active_workspace();
+        let windows: Vec<Window> = self.handler.list_windows().into_iter()
+            .filter(|w| workspaces::visible_on(w, active_workspace))
+            .collect();
         
+        // Latest backend monitor report; empty until the first resource cycle
+        let monitor_summary = self.handler.last_resource_report()
+            .map(|report| t!(
+                "gui-monitor-summary",
+                running = report.running(),
+                total = report.assignments.len(),
+                expired = report.expired.len()
+            ))
+            .unwrap_or_default();
+
         let header = row![
-            text("WASMA Window Manager")
-                .size(24)
-                .style(Color::from_rgb(0.2, 0.6, 1.0)),
+            text(t!("gui-title"))
+                .size(accessibility::text_size(24))
+                .style(accessibility::color(tokens.accent)),
+            Space::with_width(15),
+            text(monitor_summary)
+                .size(accessibility::text_size(14))
+                .style(accessibility::color(tokens.muted)),
             Space::with_width(Length::Fill),
-            button("+ New Window").on_press(Message::CreateWindow),
+            button(text(t!("gui-new-window"))).on_press(Message::CreateWindow),
+            Space::with_width(10),
+            button(text(t!("gui-update-resources"))).on_press(Message::UpdateResourceCycle),
+            Space::with_width(10),
+            button(text(t!("gui-hotkeys"))).on_press(Message::ToggleHotkeys),
+            Space::with_width(10),
+            button(text(t!("gui-preview-heavy"))).on_press(Message::TogglePreview),
             Space::with_width(10),
-            button("⟳ Update Resources").on_press(Message::UpdateResourceCycle),
+            button(text(t!("gui-protocols"))).on_press(Message::ToggleProtocols),
         ]
         .padding(20)
         .spacing(10);
 
+        let mut tabs = row![].spacing(5).padding([0, 20]);
+        for workspace in self.handler.list_workspaces() {
+            let label = t!("gui-workspace", number = workspace.number, count = workspace.windows.len());
+            let tab = if workspace.active {
+                button(text(format!("▶ {}", label)))
+            } else {
+                button(text(label)).on_press(Message::SwitchWorkspace(workspace.number))
+            };
+            tabs = tabs.push(tab);
+        }
+
         let mut window_list = column![].spacing(10).padding(20);
 
+        if self.show_hotkeys {
+            window_list = window_list.push(self.create_hotkey_panel());
+        }
+
+        if let Some(plan) = &self.preview {
+            window_list = window_list.push(self.create_preview_panel(plan));
+        }
+
+        if self.show_protocols {
+            window_list = window_list.push(self.create_protocol_panel());
+        }
+
         if windows.is_empty() {
             window_list = window_list.push(
-                text("No active windows. Create one with 'New Window'.")
-                    .size(16)
-                    .style(Color::from_rgb(0.5, 0.5, 0.5))
+                text(t!("gui-no-windows"))
+                    .size(accessibility::text_size(16))
+                    .style(accessibility::color(tokens.muted))
             );
         } else {
             for window in windows {
@@ -733,6 +2983,9 @@ impl Application for WasmaWindowManager {
 
         let content = column![
             header,
+            tabs,
+            self.create_palette(),
+            self.create_osd_layer(),
             scrollable(window_list)
         ];
 
@@ -743,11 +2996,127 @@ impl Application for WasmaWindowManager {
     }
 }
 
+/// Limits used by the GUI's "preview heavy window" dry run
+fn heavy_window_limits() -> ResourceLimits {
+    ResourceLimits {
+        max_memory_mb: 4096,
+        max_gpu_memory_mb: 2048,
+        execution_mode: Some(ExecutionMode::GpuOnly),
+        ..ResourceLimits::default()
+    }
+}
+
+/// One-line description of a planned cycle action
+pub fn describe_action(action: &PlannedAction) -> String {
+    match action {
+        PlannedAction::BindCpu { core } => format!("pin to core {}", core),
+        PlannedAction::BindGpu { device } => format!("bind {}", device),
+        PlannedAction::GpuUnavailable => "no GPU, stays on CPU".to_string(),
+        PlannedAction::StartTask => "start task".to_string(),
+        PlannedAction::StartLease { secs } => format!("start {}s lease", secs),
+        PlannedAction::LeaseWarning { remaining_secs } => format!("lease warning ({}s left)", remaining_secs),
+        PlannedAction::Preempt => "preempt (lease expired)".to_string(),
+    }
+}
+
+/// One-line summary of a protocol endpoint for the protocols panel
+pub fn describe_endpoint(endpoint: &EndpointStats) -> String {
+    let icon = match endpoint.state {
+        EndpointState::Connected => "🟢",
+        EndpointState::Connecting => "🟡",
+        EndpointState::Disconnected => "🔴",
+        EndpointState::Disabled => "⚫",
+    };
+    format!(
+        "{} {:?} {} – {} | {:.1} KiB/s | {:.1} fps | {} frames, {} dropped | {} reconnects",
+        icon,
+        endpoint.protocol,
+        endpoint.endpoint,
+        endpoint.state.name(),
+        endpoint.throughput / 1024.0,
+        endpoint.fps,
+        endpoint.frames,
+        endpoint.dropped,
+        endpoint.reconnects,
+    )
+}
+
+/// Ctrl+K toggles the command palette; Escape/arrows drive it while open.
+/// Listens to captured events too, since the palette's text input holds focus.
+fn palette_shortcut(event: iced::Event, _status: iced::event::Status) -> Option<Message> {
+    let iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) = event else {
+        return None;
+    };
+
+    match key {
+        keyboard::Key::Character(c) if modifiers.command() && c.as_str().eq_ignore_ascii_case("k") => {
+            Some(Message::TogglePalette)
+        }
+        keyboard::Key::Named(Named::Escape) => Some(Message::ClosePalette),
+        keyboard::Key::Named(Named::ArrowDown) if modifiers.is_empty() => Some(Message::PaletteMove(1)),
+        keyboard::Key::Named(Named::ArrowUp) if modifiers.is_empty() => Some(Message::PaletteMove(-1)),
+        _ => None,
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// How often the manager runs due backend phases: the shortest phase of the
+/// cadence; None while the cycle is off or nobody can see the manager
+fn resource_tick_period(cadence: Option<CycleCadence>, minimized: bool) -> Option<Duration> {
+    let cadence = cadence.filter(|_| !minimized)?;
+    Some(cadence.allocation.min(cadence.leases).min(cadence.monitor))
+}
+
+/// Minimizing and restoring move focus or resize; the state itself is fetched
+fn manager_window_changed(event: iced::Event, _status: iced::event::Status) -> Option<Message> {
+    match event {
+        iced::Event::Window(_, window::Event::Focused | window::Event::Unfocused | window::Event::Resized { .. }) => {
+            Some(Message::ManagerWindowChanged)
+        }
+        _ => None,
+    }
+}
+
+/// Super+Arrow → keyboard tiling of the focused window
+fn snap_shortcut(key: keyboard::Key, modifiers: keyboard::Modifiers) -> Option<Message> {
+    if !modifiers.logo() {
+        return None;
+    }
+
+    let direction = match key {
+        keyboard::Key::Named(Named::ArrowLeft) => SnapDirection::Left,
+        keyboard::Key::Named(Named::ArrowRight) => SnapDirection::Right,
+        keyboard::Key::Named(Named::ArrowUp) => SnapDirection::Up,
+        keyboard::Key::Named(Named::ArrowDown) => SnapDirection::Down,
+        _ => return None,
+    };
+    Some(Message::SnapFocused(direction))
+}
+
+/// Manager window size in logical pixels: 1200x800, shrunk to fit the
+/// primary output. iced sizes windows logically and the windowing system
+/// applies the output scale, so a 2x output gets a 2400x1600 window
+fn manager_window_size() -> iced::Size {
+    let (width, height) = match MonitorMap::detect() {
+        Ok(monitors) => monitors.primary().logical_size(),
+        Err(_) => return iced::Size::new(1200.0, 800.0),
+    };
+    iced::Size::new(1200f32.min(width as f32 * 0.9), 800f32.min(height as f32 * 0.9))
+}
+
 /// Launch WASMA Window Manager
 pub fn launch_window_manager(resource_mode: ResourceMode) -> iced::Result {
+    // Text without an explicit size keeps the [font] size it started with
+    let mut settings = WsdgSettingsManager::new(WsdgEnv::new());
+    settings.load().ok();
+    let text_size = accessibility::Appearance::from_settings(settings.settings()).text_size(16);
+
     WasmaWindowManager::run(Settings {
         window: window::Settings {
-            size: [1200.0, 800.0].into(),
+            size: manager_window_size(),
             position: Position::Centered,
             ..Default::default()
         },
@@ -755,7 +3124,7 @@ pub fn launch_window_manager(resource_mode: ResourceMode) -> iced::Result {
         fonts: vec![],
         antialiasing: true,
         default_font: Default::default(),
-        default_text_size: iced::Pixels(16.0),
+        default_text_size: iced::Pixels(text_size as f32),
         id: None,
     })
 }
@@ -899,6 +3268,92 @@ mod tests {
         println!("✅ Test: Parent-child relationship established");
     }
 
+    #[test]
+    fn test_modal_windows() {
+        let handler = WindowHandler::new(ResourceMode::Manual);
+        let geometry = WindowGeometry { x: 0, y: 0, width: 400, height: 300 };
+        let create = |title: &str| handler.create_window(title.into(), "test.modal".into(), geometry, None, ResourceMode::Manual).unwrap();
+
+        let editor = create("Editor");
+        let save = create("Save As");
+        let other = create("Other");
+        handler.set_parent(save, editor).unwrap();
+        // Not modal yet: the parent keeps its input
+        assert!(handler.accepts_input(editor));
+        handler.focus_window(editor).unwrap();
+
+        // Becoming modal takes focus from the parent right away
+        handler.set_modal(save, true).unwrap();
+        assert_eq!(handler.get_focused_window(), Some(save));
+        assert!(!handler.accepts_input(editor));
+        assert_eq!(handler.modal_blocker(editor), Some(save));
+        assert!(handler.accepts_input(save));
+
+        // Focusing the parent brings the modal in front of later windows
+        handler.raise_window(other);
+        assert_eq!(handler.list_windows().last().unwrap().id, other);
+        handler.focus_window(editor).unwrap();
+        assert_eq!(handler.get_focused_window(), Some(save));
+        assert_eq!(handler.list_windows().last().unwrap().id, save);
+
+        // Nested modals close with the top-level parent
+        let confirm = create("Overwrite?");
+        handler.set_parent(confirm, save).unwrap();
+        handler.set_modal(confirm, true).unwrap();
+        assert_eq!(handler.modal_blocker(editor), Some(confirm));
+        let events = handler.subscribe();
+        handler.close_window(editor).unwrap();
+        assert!(handler.get_window(save).is_none() && handler.get_window(confirm).is_none());
+        assert_eq!(events.try_iter().filter(|e| matches!(e, WindowEvent::WindowClosed { .. })).count(), 3);
+        assert_eq!(handler.list_windows().len(), 1);
+    }
+
+    #[test]
+    fn test_window_type_placement() {
+        let handler = WindowHandler::new(ResourceMode::Manual);
+        let create = |app_id: &str, width, height| handler.create_window(
+            app_id.to_string(), app_id.to_string(), WindowGeometry { x: 0, y: 0, width, height }, None, ResourceMode::Manual,
+        ).unwrap();
+
+        // Splash screen: centered, off the taskbar, closed when the app maps its window
+        let splash = create("test.editor", 400, 200);
+        handler.set_window_type(splash, WindowType::Splash).unwrap();
+        let window = handler.get_window(splash).unwrap();
+        assert!(window.skip_taskbar && window.always_on_top);
+        assert_eq!((window.geometry.x, window.geometry.y), (760, 440));
+        let editor = create("test.editor", 800, 600);
+        assert!(handler.get_window(splash).is_none());
+
+        // Dialog: centered on its parent, which passes focus on to it
+        handler.set_geometry(editor, WindowGeometry { x: 100, y: 100, width: 800, height: 600 }).unwrap();
+        let dialog = create("test.editor", 400, 200);
+        handler.set_window_type(dialog, WindowType::Dialog).unwrap();
+        handler.set_parent(dialog, editor).unwrap();
+        assert_eq!(handler.get_window(dialog).unwrap().geometry, WindowGeometry { x: 300, y: 300, width: 400, height: 200 });
+        handler.focus_window(editor).unwrap();
+        assert_eq!(handler.get_focused_window(), Some(dialog));
+
+        // Tooltip: never focused, closed once it expires
+        let tooltip = create("test.editor", 120, 30);
+        handler.set_window_type(tooltip, WindowType::Tooltip).unwrap();
+        handler.focus_window(tooltip).unwrap();
+        assert_eq!(handler.get_focused_window(), Some(dialog));
+        assert!(handler.close_expired_windows().is_empty());
+        handler.auto_close.lock().unwrap().insert(tooltip, SystemTime::now());
+        assert_eq!(handler.close_expired_windows(), vec![tooltip]);
+        assert!(handler.get_window(tooltip).is_none());
+
+        // Notifications: stacked in the top-right corner, closing one moves the rest up
+        let first = create("test.notify", 300, 80);
+        let second = create("test.notify", 300, 80);
+        handler.set_window_type(first, WindowType::Notification).unwrap();
+        handler.set_window_type(second, WindowType::Notification).unwrap();
+        assert_eq!(handler.get_window(first).unwrap().geometry, WindowGeometry { x: 1608, y: 12, width: 300, height: 80 });
+        assert_eq!(handler.get_window(second).unwrap().geometry.y, 104);
+        handler.close_window(first).unwrap();
+        assert_eq!(handler.get_window(second).unwrap().geometry.y, 12);
+    }
+
     #[test]
     fn test_wasma_config_loading() {
         let handler = WindowHandler::new(ResourceMode::Auto);
@@ -922,10 +3377,537 @@ mod tests {
             println!("⚠️  WASMA config file not found, test skipped");
         }
     }
+
+    #[test]
+    fn test_wasma_config_hot_apply() {
+        let handler = WindowHandler::new(ResourceMode::Auto);
+        let parser = ConfigParser::new(None);
+        let geometry = WindowGeometry { x: 0, y: 0, width: 640, height: 480 };
+
+        let mut config = parser.parse("r0:?? in_scoped_bylevel:40 in_request_withed:cpu_renderer\n").unwrap();
+        config.uri_handling.protocols.push(crate::parser::ProtocolConfig {
+            protocol: Protocol::Grpc,
+            ip: "127.0.0.1".parse().unwrap(),
+            port: 9000,
+            domain: None,
+        });
+        // Already granted by the manifest defaults
+        config.uri_handling.protocols.push(crate::parser::ProtocolConfig {
+            protocol: Protocol::Https,
+            ip: "127.0.0.1".parse().unwrap(),
+            port: 443,
+            domain: None,
+        });
+        handler.apply_wasma_config(config);
+
+        let id = handler.create_window(
+            "Hot".to_string(), "test.hot".to_string(), geometry, None, ResourceMode::Auto,
+        ).unwrap();
+        let window = handler.get_window(id).unwrap();
+        assert_eq!(window.resource_limits.renderer, "cpu_renderer");
+        assert!(window.permissions.allowed_protocols.contains(&"grpc".to_string()));
+
+        // Edited config: new renderer/scope, grpc dropped in favour of tor
+        let mut edited = parser.parse("r0:?? in_scoped_bylevel:80 in_request_withed:glx_renderer\n").unwrap();
+        edited.uri_handling.protocols.push(crate::parser::ProtocolConfig {
+            protocol: Protocol::Tor,
+            ip: "127.0.0.1".parse().unwrap(),
+            port: 9050,
+            domain: None,
+        });
+        assert_eq!(handler.apply_wasma_config(edited), 1);
+
+        let window = handler.get_window(id).unwrap();
+        assert_eq!(window.resource_limits.renderer, "glx_renderer");
+        assert_eq!(window.resource_limits.pixel_load_limit, 80);
+        assert!(window.permissions.allowed_protocols.contains(&"tor".to_string()));
+        assert!(!window.permissions.allowed_protocols.contains(&"grpc".to_string()));
+        // Dropped from the config, but the manifest granted it
+        assert!(window.permissions.allowed_protocols.contains(&"https".to_string()));
+        assert_eq!(window.permissions.config_protocols, vec!["tor".to_string()]);
+    }
+
+    #[test]
+    fn test_window_gets_warm_context() {
+        let handler = WindowHandler::new(ResourceMode::Manual);
+        let parser = ConfigParser::new(None);
+        let geometry = WindowGeometry { x: 0, y: 0, width: 640, height: 480 };
+
+        let config = parser
+            .parse("r0:?? in_scoped_bylevel:1 in_request_withed:cpu_renderer\ncontext_pool : 1\n")
+            .unwrap();
+        assert_eq!(config.resource_limits.context_pool, 1);
+        handler.apply_wasma_config(config);
+        handler.warm_up_contexts();
+        let pool = handler.context_pool().unwrap();
+
+        let id = handler.create_window(
+            "Warm".to_string(), "test.warm".to_string(), geometry, None, ResourceMode::Manual,
+        ).unwrap();
+        let context = handler.take_renderer_context(id).unwrap();
+        assert_eq!(context.renderer, "cpu_renderer");
+        assert_eq!(context.scope_level, 1);
+        assert_eq!(pool.misses(), 0);
+    }
+
+    #[test]
+    fn test_session_save_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+
+        let handler = WindowHandler::new(ResourceMode::Manual);
+        let left = WindowGeometry { x: 0, y: 0, width: 960, height: 1080 };
+        let right = WindowGeometry { x: 960, y: 0, width: 960, height: 1080 };
+        let editor = handler.create_window("Editor".into(), "test.editor".into(), left, None, ResourceMode::Manual).unwrap();
+        let dialog = handler.create_window("Find".into(), "test.editor".into(), right, None, ResourceMode::Manual).unwrap();
+        handler.set_parent(dialog, editor).unwrap();
+        handler.set_window_state(editor, WindowState::Maximized).unwrap();
+        assert_eq!(handler.save_session(&path).unwrap(), 2);
+
+        // Next start: ids and assignments are fresh, layout is the same
+        let next = WindowHandler::new(ResourceMode::Manual);
+        next.create_window("Other".into(), "test.other".into(), left, None, ResourceMode::Manual).unwrap();
+        let restored = next.restore_session(&path).unwrap();
+        assert_eq!(restored.len(), 2);
+
+        let editor = next.get_window(restored[0]).unwrap();
+        let dialog = next.get_window(restored[1]).unwrap();
+        assert_eq!(editor.geometry.width, 960);
+        assert_eq!(dialog.geometry.x, 960);
+        assert_eq!(editor.state, WindowState::Maximized);
+        assert_eq!(dialog.parent_id, Some(editor.id));
+        assert_eq!(editor.children_ids, vec![dialog.id]);
+        assert_eq!(editor.assignment_id, Some(editor.id as u32));
+        assert!(next.get_window_resource_usage(dialog.id).is_ok());
+    }
+
+    #[test]
+    fn test_window_events() {
+        let handler = WindowHandler::new(ResourceMode::Manual);
+        let events = handler.subscribe();
+        let geometry = WindowGeometry { x: 0, y: 0, width: 800, height: 600 };
+
+        let id = handler.create_window("Editor".into(), "test.events".into(), geometry, None, ResourceMode::Manual).unwrap();
+        handler.focus_window(id).unwrap();
+        handler.focus_window(id).unwrap();
+        handler.set_window_state(id, WindowState::Maximized).unwrap();
+
+        // Expire the lease so the next cycle preempts the assignment
+        let mut assignment = handler.wbackend.get_assignment(id as u32).unwrap();
+        assignment.start_lease(Duration::ZERO);
+        handler.wbackend.add_assignment(assignment);
+        handler.run_resource_cycle();
+        handler.close_window(id).unwrap();
+
+        let received: Vec<WindowEvent> = events.try_iter().collect();
+        assert_eq!(received, vec![
+            WindowEvent::WindowCreated { window_id: id, title: "Editor".into(), app_id: "test.events".into() },
+            WindowEvent::FocusChanged { previous: None, current: Some(id) },
+            WindowEvent::StateChanged { window_id: id, from: WindowState::Normal, to: WindowState::Maximized },
+            WindowEvent::ResourceLeaseExpired { window_id: id, assignment_id: id as u32 },
+            // Default lease_expiry = suspend
+            WindowEvent::StateChanged { window_id: id, from: WindowState::Maximized, to: WindowState::Minimized },
+            WindowEvent::WindowClosed { window_id: id },
+        ]);
+
+        // Dropped receivers are pruned
+        drop(events);
+        handler.create_window("Other".into(), "test.events".into(), geometry, None, ResourceMode::Manual).unwrap();
+        assert!(handler.subscribers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lease_expiry_policy() {
+        let handler = WindowHandler::new(ResourceMode::Manual);
+        let parser = ConfigParser::new(None);
+        let geometry = WindowGeometry { x: 0, y: 0, width: 800, height: 600 };
+        let expire = |handler: &WindowHandler, id: u64| {
+            let mut assignment = handler.wbackend.get_assignment(id as u32).unwrap();
+            assignment.start_lease(Duration::ZERO);
+            handler.wbackend.add_assignment(assignment);
+            handler.run_resource_cycle();
+        };
+
+        let config = parser.parse("lease_seconds : 90\nlease_expiry : suspend\ncontext_pool : 0\n").unwrap();
+        handler.apply_wasma_config(config);
+        let id = handler.create_window("Idle".into(), "test.lease".into(), geometry, None, ResourceMode::Manual).unwrap();
+        assert_eq!(handler.get_window(id).unwrap().resource_limits.lease_duration, Duration::from_secs(90));
+
+        // Suspended on expiry, resumed with a fresh assignment on focus
+        expire(&handler, id);
+        assert_eq!(handler.get_window(id).unwrap().state, WindowState::Minimized);
+        assert!(handler.wbackend.get_assignment(id as u32).is_none());
+        handler.focus_window(id).unwrap();
+        assert!(handler.wbackend.get_assignment(id as u32).is_some());
+
+        let config = parser.parse("lease_expiry : renew\ncontext_pool : 0\n").unwrap();
+        handler.apply_wasma_config(config);
+        expire(&handler, id);
+        assert!(handler.wbackend.get_assignment(id as u32).is_some());
+
+        let config = parser.parse("lease_expiry : close\ncontext_pool : 0\n").unwrap();
+        handler.apply_wasma_config(config);
+        expire(&handler, id);
+        assert!(handler.get_window(id).is_none());
+    }
+
+    #[test]
+    fn test_quota_group_evicts_least_recently_focused() {
+        let handler = WindowHandler::new(ResourceMode::Manual);
+        let parser = ConfigParser::new(None);
+        let geometry = WindowGeometry { x: 0, y: 0, width: 800, height: 600 };
+        let config = parser
+            .parse("context_pool : 0\n\
+                    quota_group : browser ram=1GB apps=test.browser evict\n\
+                    quota_group : tools ram=512 apps=test.tool\n")
+            .unwrap();
+        handler.apply_wasma_config(config);
+        let open = |app: &str| handler.create_window(app.into(), app.into(), geometry, None, ResourceMode::Manual);
+
+        // Two 512MB windows fill the browser group
+        let first = open("test.browser").unwrap();
+        let second = open("test.browser").unwrap();
+        assert_eq!(handler.get_window(first).unwrap().resource_limits.quota_group.as_deref(), Some("browser"));
+        handler.focus_window(first).unwrap();
+
+        // `second` was never focused, so it makes room for the third
+        let third = open("test.browser").unwrap();
+        assert!(handler.get_window(second).is_none());
+        assert!(handler.get_window(first).is_some());
+        assert_eq!(handler.wbackend.quota_members("browser"), vec![first as u32, third as u32]);
+
+        // Groups without eviction refuse the window
+        open("test.tool").unwrap();
+        assert!(open("test.tool").unwrap_err().contains("tools"));
+        // Apps outside any group are unaffected
+        assert!(open("test.other").is_ok());
+    }
+
+    #[test]
+    fn test_manifest_config_cache() {
+        let handler = WindowHandler::new(ResourceMode::Manual);
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        std::fs::write(&source, "USE_WIFI_CONNECTION = 1\n").unwrap();
+        let manifest = dir.path().join("app.toml");
+        std::fs::write(&manifest, format!(
+            "[app]\nname = \"Player\"\n\n[resources]\nexecution_mode = \"cpu_only\"\n\n[permissions]\nsource = \"{}\"\n",
+            source.display()
+        )).unwrap();
+        let path = manifest.display().to_string();
+
+        let first = handler.load_manifest_config(&path).unwrap();
+        assert_eq!(first.resource_limits.execution_mode, Some(ExecutionMode::CpuOnly));
+        assert!(first.permissions.can_access_network);
+        assert!(Arc::ptr_eq(&first, &handler.load_manifest_config(&path).unwrap()));
+
+        // Editing the permission source invalidates the entry
+        std::fs::write(&source, "USE_WIFI_CONNECTION = 0\n").unwrap();
+        std::fs::File::options().write(true).open(&source).unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+        let second = handler.load_manifest_config(&path).unwrap();
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert!(!second.permissions.can_access_network);
+
+        // A missing explicit source is an error rather than a silent default
+        std::fs::remove_file(&source).unwrap();
+        assert!(handler.load_manifest_config(&path).is_err());
+    }
+
+    #[test]
+    fn test_kiosk_manifest_bounds() {
+        let handler = WindowHandler::new(ResourceMode::Manual);
+        let vendor = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(vendor.path().join("manifests")).unwrap();
+        let manifest = vendor.path().join("manifests/kiosk.manifest");
+        std::fs::write(&manifest, "name = Kiosk\nram_using = \"DDR5\" \"1024MB\"\ngpu_using = \"GDDR6\" \"512MB\"\n").unwrap();
+        let outside = vendor.path().join("user.manifest");
+        std::fs::copy(&manifest, &outside).unwrap();
+        handler.set_kiosk_policy(Some(KioskPolicy::new(vendor.path())));
+
+        let geometry = WindowGeometry { x: 0, y: 0, width: 800, height: 600 };
+        let id = handler.create_window(
+            "Kiosk".to_string(),
+            "kiosk.app".to_string(),
+            geometry,
+            Some(manifest.display().to_string()),
+            ResourceMode::Manual,
+        ).unwrap();
+        assert!(handler.create_window(
+            "User".to_string(),
+            "user.app".to_string(),
+            geometry,
+            Some(outside.display().to_string()),
+            ResourceMode::Manual,
+        ).unwrap_err().contains("kiosk policy"));
+
+        let mut limits = handler.get_window(id).unwrap().resource_limits;
+        limits.max_memory_mb = 512;
+        handler.adjust_window_resources(id, limits.clone()).unwrap();
+        limits.max_memory_mb = 2048;
+        assert!(handler.adjust_window_resources(id, limits).unwrap_err().contains("kiosk policy"));
+        assert_eq!(handler.get_window(id).unwrap().resource_limits.max_memory_mb, 512);
+    }
+
+    #[test]
+    fn test_resource_tick_follows_cadence_and_pauses() {
+        let handler = WindowHandler::new(ResourceMode::Manual);
+        handler.set_resource_cadence(CycleCadence {
+            allocation: Duration::from_secs(4),
+            leases: Duration::from_millis(500),
+            monitor: Duration::from_secs(2),
+        });
+
+        // The shortest phase sets the pace; run_due skips the phases not yet due
+        let cadence = Some(handler.resource_cadence());
+        assert_eq!(resource_tick_period(cadence, false), Some(Duration::from_millis(500)));
+        assert_eq!(resource_tick_period(None, false), None);
+
+        // Minimized: no background cycle at all
+        assert_eq!(resource_tick_period(cadence, true), None);
+    }
 } 
 
 impl WasmaWindowManager {
+    fn palette_results(&self) -> Vec<PaletteCommand> {
+        match &self.palette {
+            Some((query, _)) => self.commands.search(query),
+            None => Vec::new(),
+        }
+    }
+
+    fn create_palette(&self) -> Element<'_, Message> {
+        let tokens = accessibility::tokens();
+        let Some((query, selected)) = &self.palette else {
+            return column![].into();
+        };
+
+        let mut list = column![
+            text_input(&t!("palette-placeholder"), query)
+                .id(palette_input_id())
+                .on_input(Message::PaletteQueryChanged)
+                .on_submit(Message::PaletteSubmit)
+                .padding(10)
+                .size(accessibility::text_size(18))
+        ]
+        .spacing(4);
+
+        let results = self.palette_results();
+        if results.is_empty() {
+            list = list.push(text(t!("palette-no-match")).size(accessibility::text_size(14)).style(accessibility::color(tokens.muted)));
+        }
+
+        for (i, command) in results.into_iter().take(command_palette::MAX_RESULTS).enumerate() {
+            let label = row![
+                text(command.title).size(accessibility::text_size(15)),
+                Space::with_width(Length::Fill),
+                text(command.category).size(accessibility::text_size(13)).style(accessibility::color(tokens.muted)),
+            ];
+            let style = if i == *selected {
+                iced::theme::Button::Primary
+            } else {
+                iced::theme::Button::Text
+            };
+            list = list.push(
+                button(label)
+                    .width(Length::Fill)
+                    .style(style)
+                    .on_press(Message::PaletteRun(i))
+            );
+        }
+
+        container(container(list).width(600).padding(10).style(move |_theme: &Theme| container::Appearance {
+            background: Some(Background::Color(accessibility::color(tokens.surface))),
+            ..Default::default()
+        }))
+        .width(Length::Fill)
+        .center_x()
+        .into()
+    }
+
+    /// OSD toasts, drawn above every viewport card
+    fn create_osd_layer(&self) -> Element<'_, Message> {
+        let surface = accessibility::tokens().surface;
+        let mut layer = column![].spacing(6).padding([0, 20]).width(Length::Fill);
+
+        for toast in osd::active() {
+            // Fade out during the last third of the toast's lifetime (no fade with reduce motion)
+            let alpha = if accessibility::reduce_motion() {
+                1.0
+            } else {
+                (toast.remaining_fraction() * 3.0).min(1.0)
+            };
+            let bubble = container(
+                button(
+                    text(format!("{}  {}", toast.icon.glyph(), toast.text))
+                        .size(accessibility::text_size(16))
+                        .style(Color { a: alpha, ..Color::WHITE })
+                )
+                .on_press(Message::DismissOsd(toast.id))
+                .style(iced::theme::Button::Text)
+            )
+            .padding(8)
+            .style(move |_theme: &Theme| container::Appearance {
+                background: Some(Background::Color(accessibility::color(surface.with_alpha(0.85 * alpha)))),
+                ..Default::default()
+            });
+
+            layer = layer.push(container(bubble).width(Length::Fill).center_x());
+        }
+
+        layer.into()
+    }
+
+    fn create_preview_panel(&self, plan: &CyclePlan) -> Element<'_, Message> {
+        let tokens = accessibility::tokens();
+        let mut panel = column![
+            text(t!("preview-title")).size(accessibility::text_size(18)),
+            text(t!("preview-totals", ram = plan.ram_mb, vram = plan.vram_mb)).size(accessibility::text_size(14)),
+        ]
+        .spacing(5)
+        .padding(15);
+
+        if plan.is_noop() {
+            panel = panel.push(text(t!("preview-no-changes")).size(accessibility::text_size(14)).style(accessibility::color(tokens.muted)));
+        }
+
+        for assignment in plan.assignments.iter().filter(|a| !a.actions.is_empty()) {
+            let actions: Vec<String> = assignment.actions.iter().map(describe_action).collect();
+            let label = if assignment.is_new { "new window" } else { "window" };
+            panel = panel.push(
+                text(format!("{} {} ({:?}): {}", label, assignment.id, assignment.execution_mode, actions.join(", ")))
+                    .size(accessibility::text_size(14))
+            );
+        }
+
+        for warning in &plan.warnings {
+            panel = panel.push(text(format!("⚠️  {}", warning)).size(accessibility::text_size(14)).style(accessibility::color(tokens.accent)));
+        }
+
+        container(panel)
+            .width(Length::Fill)
+            .style(move |_theme: &Theme| container::Appearance {
+                background: Some(Background::Color(accessibility::color(tokens.surface))),
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn create_protocol_panel(&self) -> Element<'_, Message> {
+        let tokens = accessibility::tokens();
+        let endpoints = self.protocols.snapshot();
+        let mut panel = column![text(t!("protocols-title")).size(accessibility::text_size(18))].spacing(5).padding(15);
+
+        if endpoints.is_empty() {
+            panel = panel.push(
+                text(t!("protocols-empty"))
+                    .size(accessibility::text_size(14))
+                    .style(accessibility::color(tokens.muted))
+            );
+        }
+
+        for endpoint in endpoints {
+            let toggle = match endpoint.state {
+                EndpointState::Disabled => button(text(t!("protocols-enable")))
+                    .on_press(Message::SetProtocolEnabled(endpoint.index, true)),
+                _ => button(text(t!("protocols-disable")))
+                    .on_press(Message::SetProtocolEnabled(endpoint.index, false)),
+            };
+            let mut reconnect = button(text(t!("protocols-reconnect")));
+            if endpoint.state != EndpointState::Disabled {
+                reconnect = reconnect.on_press(Message::ReconnectProtocol(endpoint.index));
+            }
+
+            panel = panel.push(
+                row![
+                    text(describe_endpoint(&endpoint)).size(accessibility::text_size(14)).width(Length::Fill),
+                    reconnect,
+                    toggle,
+                ]
+                .spacing(10)
+            );
+            if let Some(error) = &endpoint.last_error {
+                panel = panel.push(
+                    text(format!("   ⚠️  {}", error))
+                        .size(accessibility::text_size(13))
+                        .style(accessibility::color(tokens.muted))
+                );
+            }
+        }
+
+        container(panel)
+            .width(Length::Fill)
+            .style(move |_theme: &Theme| container::Appearance {
+                background: Some(Background::Color(accessibility::color(tokens.surface))),
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn create_hotkey_panel(&self) -> Element<'_, Message> {
+        let tokens = accessibility::tokens();
+        let bindings = self.hotkeys.list();
+        let mut panel = column![text(t!("hotkeys-title")).size(accessibility::text_size(18))].spacing(5).padding(15);
+
+        if bindings.is_empty() {
+            panel = panel.push(
+                text(t!("hotkeys-empty"))
+                    .size(accessibility::text_size(14))
+                    .style(accessibility::color(tokens.muted))
+            );
+        }
+
+        for binding in bindings {
+            panel = panel.push(
+                text(format!(
+                    "{} → {} | {} (window {}){}",
+                    binding.combo,
+                    binding.action,
+                    binding.app_id,
+                    binding.window_id,
+                    if binding.description.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" – {}", binding.description)
+                    }
+                ))
+                .size(accessibility::text_size(14))
+            );
+        }
+
+        for (combo, name) in self.hotkeys.reserved() {
+            panel = panel.push(
+                text(format!("{} → {} (reserved by WASMA)", combo, name))
+                    .size(accessibility::text_size(14))
+                    .style(accessibility::color(tokens.muted))
+            );
+        }
+
+        container(panel)
+            .width(Length::Fill)
+            .style(move |_theme: &Theme| container::Appearance {
+                background: Some(Background::Color(accessibility::color(tokens.surface))),
+                ..Default::default()
+            })
+            .into()
+    }
+
+    /// "Modal dialog of …" / "Input blocked by …" under a card's title
+    fn relation_line(&self, window: &Window) -> Option<String> {
+        let describe = |id: u64| self.handler.get_window(id).map(|w| (w.title, id));
+        let mut parts = Vec::new();
+        if let Some((title, id)) = window.parent_id.and_then(describe) {
+            let key = if window.modal { "gui-window-modal-for" } else { "gui-window-child-of" };
+            parts.push(t!(key, title = title, id = id));
+        }
+        if let Some((title, id)) = self.handler.modal_blocker(window.id).and_then(describe) {
+            parts.push(t!("gui-window-blocked-by", title = title, id = id));
+        }
+        (!parts.is_empty()).then(|| parts.join(" | "))
+    }
+
     fn create_window_card(&self, window: &Window, is_selected: bool) -> Element<'_, Message> {
+        let tokens = accessibility::tokens();
         let state_icon = match window.state {
             WindowState::Normal => "🟢",
             WindowState::Minimized => "🟡",
@@ -935,26 +3917,44 @@ impl WasmaWindowManager {
         };
 
         let focus_indicator = if window.focused { "👁️ " } else { "" };
+        let badges: String = window.badges.iter().map(|b| format!(" {}", b.label())).collect();
 
         let title_row = row![
-            text(format!("{}{} {}", focus_indicator, state_icon, window.title))
-                .size(18),
+            text(format!("{}{} {}{}", focus_indicator, state_icon, window.title, badges))
+                .size(accessibility::text_size(18)),
             Space::with_width(Length::Fill),
-            button("Focus").on_press(Message::FocusWindow(window.id)),
+            button(text(t!("gui-window-focus"))).on_press(Message::FocusWindow(window.id)),
+            Space::with_width(5),
+            button(text(t!("gui-window-minimize"))).on_press(Message::MinimizeWindow(window.id)),
             Space::with_width(5),
-            button("Min").on_press(Message::MinimizeWindow(window.id)),
+            button(text(t!("gui-window-maximize"))).on_press(Message::MaximizeWindow(window.id)),
             Space::with_width(5),
-            button("Max").on_press(Message::MaximizeWindow(window.id)),
+            button(text(t!("gui-window-fullscreen"))).on_press(Message::ToggleFullscreen(window.id)),
             Space::with_width(5),
-            button("FS").on_press(Message::ToggleFullscreen(window.id)),
+            button(text(t!("gui-window-hide"))).on_press(Message::HideWindow(window.id)),
             Space::with_width(5),
-            button("Hide").on_press(Message::HideWindow(window.id)),
+            button(text(t!("gui-window-next-workspace")))
+                .on_press(Message::MoveToWorkspace(window.id, window.workspace % workspaces::WORKSPACE_COUNT + 1)),
             Space::with_width(5),
             button("✕").on_press(Message::CloseWindow(window.id)),
         ]
         .spacing(5);
 
         let info = if let Ok(usage) = self.handler.get_window_resource_usage(window.id) {
+            let history = self.handler.get_window_resource_history(window.id).unwrap_or_default();
+            let mut graphs = row![].spacing(10);
+            for metric in ResourceMetric::ALL {
+                let limit = match metric {
+                    ResourceMetric::Cpu => None,
+                    ResourceMetric::Ram => Some(usage.ram_allocated_mb as f32),
+                    ResourceMetric::Vram => Some(usage.vram_allocated_mb as f32),
+                };
+                let graph = ResourceGraph::new(&history, metric, limit, accessibility::color(tokens.accent), accessibility::color(tokens.surface));
+                graphs = graphs.push(column![
+                    text(format!("{} {}", metric.label(), metric.format(history.latest(metric)))).size(accessibility::text_size(12)),
+                    graph,
+                ].spacing(2));
+            }
             let mode_str = match usage.execution_mode {
                 ExecutionMode::CpuOnly => "🔵 CPU-Only",
                 ExecutionMode::GpuPreferred => "🟢 GPU Preferred",
@@ -964,42 +3964,57 @@ impl WasmaWindowManager {
 
             column![
                 text(format!("ID: {} | Assignment: {}", window.id, usage.assignment_id))
-                    .size(14),
+                    .size(accessibility::text_size(14)),
                 text(format!(
                     "{} | Status: {}",
                     mode_str,
                     if usage.task_active { "RUNNING" } else { "STOPPED" }
                 ))
-                .size(14),
+                .size(accessibility::text_size(14)),
                 text(format!(
                     "RAM: {} MiB | VRAM: {} MiB | Core: {:?}",
-                    usage.ram_allocated_mb, usage.vram_allocated_mb, usage.cpu_cores
+                    used_of(usage.ram_used_mb, usage.ram_allocated_mb),
+                    used_of(usage.vram_used_mb, usage.vram_allocated_mb),
+                    usage.cpu_cores
                 ))
-                .size(14),
+                .size(accessibility::text_size(14)),
+                graphs,
                 text(format!(
                     "GPU: {} | Remaining time: {}s",
                     usage.gpu_device.unwrap_or_else(|| "None".to_string()),
                     usage.remaining_lease_secs
                 ))
-                .size(14),
+                .size(accessibility::text_size(14)),
                 text(format!("Renderer: {} | {}x{}", 
                     window.resource_limits.renderer,
                     window.geometry.width,
                     window.geometry.height
                 ))
-                .size(14),
+                .size(accessibility::text_size(14)),
             ]
             .spacing(5)
         } else {
-            column![text("No resource information").size(14)]
+            column![text(t!("gui-no-resources")).size(accessibility::text_size(14))]
         };
 
-        let card_content = column![title_row, info].spacing(10).padding(15);
+        let body: Element<'_, Message> = match self.thumbnails.get(&window.id) {
+            Some(thumbnail) => row![image(thumbnail.clone()).width(thumbnails::DEFAULT_THUMBNAIL_SIZE as f32), info].spacing(15).into(),
+            None => info.into(),
+        };
+        let mut card_content = column![title_row].spacing(10).padding(15);
+        if let Some(relation) = self.relation_line(window) {
+            card_content = card_content.push(text(relation).size(accessibility::text_size(14)).style(accessibility::color(tokens.muted)));
+        }
+        card_content = card_content.push(body);
+
+        if window.server_decorations && decorations::wants_decorations(&window.window_type) {
+            card_content = column![self.handler.decorations().titlebar(window), card_content];
+        }
 
         let card_background = if is_selected {
-            Background::Color(Color::from_rgb(0.2, 0.3, 0.4))
+            Background::Color(accessibility::blend(tokens.background, tokens.accent, 0.35))
         } else {
-            Background::Color(Color::from_rgb(0.15, 0.15, 0.15))
+            Background::Color(accessibility::color(tokens.background))
         };
 
         container(card_content)
@@ -1009,11 +4024,11 @@ impl WasmaWindowManager {
                     background: Some(card_background),
                     border: iced::Border {
                         color: if is_selected {
-                            Color::from_rgb(0.3, 0.6, 1.0)
+                            accessibility::color(tokens.accent)
                         } else {
-                            Color::from_rgb(0.3, 0.3, 0.3)
+                            accessibility::color(tokens.muted)
                         },
-                        width: 2.0,
+                        width: (tokens.border_width + 1) as f32,
                         radius: 8.0.into(),
                     },
                     ..Default::default()
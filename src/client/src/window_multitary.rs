@@ -1,8 +1,14 @@
 use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Instant;
+use crate::focus_policy::{FocusEngine, PointerEvent};
+use crate::global_hotkeys::{KeyCombo, Modifiers};
+use crate::monitors::{Monitor, MonitorMap};
 use crate::parser::WasmaConfig;
+use crate::texture_atlas::{CompositePlan, TextureAtlas};
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Viewport {
     pub x: i32,
     pub y: i32,
@@ -10,13 +16,213 @@ pub struct Viewport {
     pub height: u32,
     pub z_index: u8,
     pub active: bool,
+    /// HiDPI scale of the output the viewport is on; x/y/width/height are
+    /// physical pixels, so a logical-size frame covers width/scale pixels
+    pub scale: f32,
+}
+
+impl Viewport {
+    /// Size in logical pixels (what a non-HiDPI-aware stream renders at)
+    pub fn logical_size(&self) -> (u32, u32) {
+        let scale = self.scale.max(1.0);
+        (((self.width as f32 / scale).round() as u32).max(1), ((self.height as f32 / scale).round() as u32).max(1))
+    }
+}
+
+/// How the tiled streams share the screen; floating overrides apply on top of any of them
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LayoutPolicy {
+    /// Equal-height rows, one per stream
+    #[default]
+    Vertical,
+    /// Equal-width columns
+    Horizontal,
+    /// Near-square grid, filled row by row
+    Grid,
+    /// The first stream takes `ratio` of the width, the others stack on the right
+    MasterStack { ratio: f32 },
+    /// Cascaded, two-thirds of the screen each; meant to be arranged by hand
+    Floating,
+}
+
+impl LayoutPolicy {
+    pub const DEFAULT_MASTER_RATIO: f32 = 0.6;
+
+    /// "vertical", "horizontal", "grid", "master-stack" (optionally ":<ratio>"), "floating"
+    pub fn parse(raw: &str) -> Option<Self> {
+        let (name, ratio) = match raw.trim().split_once(':') {
+            Some((name, ratio)) => (name, Some(ratio.trim().parse::<f32>().ok()?)),
+            None => (raw.trim(), None),
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "vertical" | "rows" => Some(LayoutPolicy::Vertical),
+            "horizontal" | "columns" => Some(LayoutPolicy::Horizontal),
+            "grid" => Some(LayoutPolicy::Grid),
+            "master-stack" | "master_stack" | "master" => Some(LayoutPolicy::MasterStack {
+                ratio: ratio.unwrap_or(Self::DEFAULT_MASTER_RATIO),
+            }),
+            "floating" | "free" => Some(LayoutPolicy::Floating),
+            _ => None,
+        }
+    }
+
+    /// The layout after this one when cycling through them
+    pub fn next(&self) -> Self {
+        match self {
+            LayoutPolicy::Vertical => LayoutPolicy::Horizontal,
+            LayoutPolicy::Horizontal => LayoutPolicy::Grid,
+            LayoutPolicy::Grid => LayoutPolicy::MasterStack { ratio: Self::DEFAULT_MASTER_RATIO },
+            LayoutPolicy::MasterStack { .. } => LayoutPolicy::Floating,
+            LayoutPolicy::Floating => LayoutPolicy::Vertical,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LayoutPolicy::Vertical => "vertical",
+            LayoutPolicy::Horizontal => "horizontal",
+            LayoutPolicy::Grid => "grid",
+            LayoutPolicy::MasterStack { .. } => "master-stack",
+            LayoutPolicy::Floating => "floating",
+        }
+    }
+
+    /// Geometry of `count` tiled streams on a `width`x`height` screen, in stream order
+    pub fn arrange(&self, count: usize, width: u32, height: u32) -> Vec<(i32, i32, u32, u32)> {
+        if count == 0 {
+            return Vec::new();
+        }
+        let n = count as u32;
+        match *self {
+            LayoutPolicy::Vertical => (0..n).map(|i| (0, (i * (height / n)) as i32, width, height / n)).collect(),
+            LayoutPolicy::Horizontal => (0..n).map(|i| ((i * (width / n)) as i32, 0, width / n, height)).collect(),
+            LayoutPolicy::Grid => {
+                let cols = (n as f64).sqrt().ceil() as u32;
+                let rows = n.div_ceil(cols);
+                let (cell_w, cell_h) = (width / cols, height / rows);
+                (0..n).map(|i| (((i % cols) * cell_w) as i32, ((i / cols) * cell_h) as i32, cell_w, cell_h)).collect()
+            }
+            LayoutPolicy::MasterStack { .. } if n == 1 => vec![(0, 0, width, height)],
+            LayoutPolicy::MasterStack { ratio } => {
+                let master_w = (width as f32 * ratio.clamp(0.1, 0.9)) as u32;
+                let stack_h = height / (n - 1);
+                std::iter::once((0, 0, master_w, height))
+                    .chain((0..n - 1).map(|i| (master_w as i32, (i * stack_h) as i32, width - master_w, stack_h)))
+                    .collect()
+            }
+            LayoutPolicy::Floating => {
+                let (w, h) = (width * 2 / 3, height * 2 / 3);
+                // Cascade steps wrap before a window would leave the screen
+                let steps = ((width - w).min(height - h) / CASCADE_STEP).max(1);
+                (0..n).map(|i| {
+                    let offset = ((i % steps) * CASCADE_STEP) as i32;
+                    (offset, offset, w, h)
+                }).collect()
+            }
+        }
+    }
+}
+
+const CASCADE_STEP: u32 = 32;
+
+// ============================================================================
+// INPUT ROUTING
+// ============================================================================
+
+/// Pixels along the right and bottom edge of a viewport that start a resize
+pub const RESIZE_EDGE: i32 = 8;
+/// Smallest width/height a viewport can be resized to
+pub const MIN_VIEWPORT_SIZE: u32 = 64;
+/// Promotes the focused stream to singularity mode, and back
+pub const DEFAULT_SINGULARITY_SHORTCUT: &str = "Super+F";
+
+/// Pointer button numbers as the display servers report them
+pub const BUTTON_LEFT: u32 = 1;
+pub const BUTTON_RIGHT: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragMode {
+    /// Super + left button
+    Move,
+    /// Super + right button, or left button on the right/bottom edge
+    Resize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    stream_id: u8,
+    mode: DragMode,
+    origin: (i32, i32),
+    start: (i32, i32, u32, u32),
+}
+
+/// Sent to focus subscribers: the stream gained or lost input focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamFocusEvent {
+    pub stream_id: u8,
+    pub focused: bool,
+}
+
+/// What a routed input event did
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputAction {
+    FocusChanged { previous: Option<u8>, current: Option<u8> },
+    /// The stream is floating at its new geometry
+    Moved { stream_id: u8, viewport: Viewport },
+    Resized { stream_id: u8, viewport: Viewport },
+    EnterSingularity(u8),
+    ExitSingularity,
+    /// The focus engine focused this handler window
+    WindowFocused(u64),
+    /// Not taken by WASMA: deliver to the stream, coordinates relative to its viewport
+    Pointer { stream_id: u8, event: PointerEvent },
+    Key { stream_id: u8, combo: KeyCombo },
+    /// The close hotkey was pressed on the stream; the caller ends it
+    CloseRequested { stream_id: u8 },
+    LayoutChanged(LayoutPolicy),
+}
+
+/// A hand-placed stream and the screen size it was placed on
+#[derive(Debug, Clone)]
+struct GeometryOverride {
+    viewport: Viewport,
+    screen: (u32, u32),
+}
+
+impl GeometryOverride {
+    /// The geometry scaled from its own screen to `width`x`height`
+    fn on_screen(&self, width: u32, height: u32) -> Viewport {
+        let (from_w, from_h) = (self.screen.0.max(1) as i64, self.screen.1.max(1) as i64);
+        let scale_x = |v: i64| (v * width as i64 / from_w) as i32;
+        let scale_y = |v: i64| (v * height as i64 / from_h) as i32;
+        Viewport {
+            x: scale_x(self.viewport.x as i64),
+            y: scale_y(self.viewport.y as i64),
+            width: scale_x(self.viewport.width as i64) as u32,
+            height: scale_y(self.viewport.height as i64) as u32,
+            ..self.viewport.clone()
+        }
+    }
 }
 
 pub struct WindowMultitary {
     config: WasmaConfig,
     pub viewports: HashMap<u8, Viewport>, // stream_id -> Viewport
+    attached: Vec<u8>, // harici stream'ler (X11 adoption vb.)
+    floating: HashMap<u8, GeometryOverride>, // taşınan/snap edilen stream'ler, tiling'i ezer
+    policy: LayoutPolicy,
+    monitors: MonitorMap,
+    pins: HashMap<u8, String>, // stream_id -> çıkış adı; pinsizler birincil monitörde
+    // Tüm monitörleri kapsayan masaüstü boyutu
     screen_width: u32,
     screen_height: u32,
+    atlas: TextureAtlas, // küçük viewport'lar ortak texture sayfalarında
+    focused: Option<u8>,
+    singularity: Option<u8>, // tam ekrana alınmış stream
+    drag: Option<Drag>,
+    singularity_shortcut: Option<KeyCombo>,
+    focus_subscribers: Vec<Sender<StreamFocusEvent>>,
+    focus_engine: Option<FocusEngine>, // pencere odak politikası (tıkla / fareyi izle)
 }
 
 impl WindowMultitary {
@@ -24,61 +230,586 @@ impl WindowMultitary {
         let mut multitary = Self {
             config,
             viewports: HashMap::new(),
+            attached: Vec::new(),
+            floating: HashMap::new(),
+            policy: LayoutPolicy::default(),
+            monitors: MonitorMap::single(screen_width, screen_height),
+            pins: HashMap::new(),
             screen_width,
             screen_height,
+            atlas: TextureAtlas::default(),
+            focused: None,
+            singularity: None,
+            drag: None,
+            singularity_shortcut: Some(KeyCombo::parse(DEFAULT_SINGULARITY_SHORTCUT).expect("valid default shortcut")),
+            focus_subscribers: Vec::new(),
+            focus_engine: None,
         };
         multitary.calculate_layouts();
         multitary
     }
 
     pub fn calculate_layouts(&mut self) {
+        if !self.attached.is_empty() {
+            self.retile();
+            self.apply_floating();
+            return;
+        }
+
         let proto_count = self.config.uri_handling.protocols.len();
         let is_singularity = self.config.uri_handling.singularity_instances;
         let is_multi = self.config.uri_handling.multi_instances;
 
         if is_singularity && !is_multi {
-            // Singularity: Tek pencere, tam ekran
+            // Singularity: Tek pencere, birincil monitörde tam ekran
+            let primary = self.monitors.primary();
             self.viewports.insert(0, Viewport {
-                x: 0, y: 0,
-                width: self.screen_width,
-                height: self.screen_height,
+                x: primary.x, y: primary.y,
+                width: primary.width,
+                height: primary.height,
                 z_index: 1,
                 active: true,
+                scale: primary.scale,
             });
-        } else if is_multi {
-            // Multi-Instance: Ekranı protokol sayısına göre dikey böl (Tiling)
-            let section_height = self.screen_height / (proto_count as u32);
-            for i in 0..proto_count {
-                self.viewports.insert(i as u8, Viewport {
-                    x: 0,
-                    y: (i as i32) * (section_height as i32),
-                    width: self.screen_width,
-                    height: section_height,
-                    z_index: 1,
-                    active: true,
-                });
+        } else if is_multi && proto_count > 0 {
+            // Multi-Instance: Ekranı protokol sayısına göre layout policy ile böl (Tiling)
+            self.tile((0..proto_count as u8).collect());
+        }
+
+        self.apply_floating();
+    }
+
+    pub fn layout_policy(&self) -> LayoutPolicy {
+        self.policy
+    }
+
+    /// Switch layouts at runtime; floating streams keep their geometry
+    pub fn set_layout_policy(&mut self, policy: LayoutPolicy) {
+        self.policy = policy;
+        self.viewports.clear();
+        self.calculate_layouts();
+    }
+
+    /// Pin a stream to explicit geometry (interactive move/resize, snapping).
+    /// The override survives layout switches and scales with the resolution.
+    pub fn set_floating(&mut self, stream_id: u8, x: i32, y: i32, width: u32, height: u32) {
+        self.floating.insert(stream_id, GeometryOverride {
+            viewport: Viewport { x, y, width, height, z_index: 2, active: true, scale: self.scale_at(x, y) },
+            screen: (self.screen_width, self.screen_height),
+        });
+        self.apply_floating();
+    }
+
+    /// Manual geometry of a stream at the current resolution
+    pub fn floating_geometry(&self, stream_id: u8) -> Option<Viewport> {
+        self.floating.get(&stream_id).map(|o| {
+            let mut viewport = o.on_screen(self.screen_width, self.screen_height);
+            viewport.scale = self.scale_at(viewport.x, viewport.y);
+            viewport
+        })
+    }
+
+    /// Return a stream to the tiling layout
+    pub fn clear_floating(&mut self, stream_id: u8) {
+        if self.floating.remove(&stream_id).is_some() {
+            self.viewports.clear();
+            self.calculate_layouts();
+        }
+    }
+
+    fn apply_floating(&mut self) {
+        let ids: Vec<u8> = self.floating.keys().copied().collect();
+        for id in ids {
+            if let Some(viewport) = self.floating_geometry(id) {
+                self.viewports.insert(id, viewport);
             }
         }
     }
 
+    /// Scale of the output under (x, y), the primary's off-screen
+    fn scale_at(&self, x: i32, y: i32) -> f32 {
+        self.monitors.monitor_at(x, y).unwrap_or_else(|| self.monitors.primary()).scale
+    }
+
+    /// Attach an external stream (e.g. an adopted X11 client) to the tiling layout
+    pub fn attach_stream(&mut self, stream_id: u8) {
+        if !self.attached.contains(&stream_id) {
+            self.attached.push(stream_id);
+        }
+        self.calculate_layouts();
+    }
+
+    /// Detach an external stream and re-tile the remaining ones
+    pub fn detach_stream(&mut self, stream_id: u8) {
+        self.attached.retain(|id| *id != stream_id);
+        self.floating.remove(&stream_id);
+        self.pins.remove(&stream_id);
+        if self.drag.is_some_and(|drag| drag.stream_id == stream_id) {
+            self.drag = None;
+        }
+        if self.singularity == Some(stream_id) {
+            self.singularity = None;
+        }
+        if self.focused == Some(stream_id) {
+            self.set_focus(None);
+        }
+        self.viewports.clear();
+        self.calculate_layouts();
+    }
+
+    /// Protokol stream'leri + harici stream'ler: hepsini layout policy ile böl
+    fn retile(&mut self) {
+        let mut ids: Vec<u8> = if self.config.uri_handling.multi_instances {
+            (0..self.config.uri_handling.protocols.len() as u8).collect()
+        } else {
+            Vec::new()
+        };
+        ids.extend(self.attached.iter().copied());
+
+        self.viewports.clear();
+        self.tile(ids);
+    }
+
+    /// Every monitor tiles its own streams with the layout policy
+    fn tile(&mut self, ids: Vec<u8>) {
+        for monitor in self.monitors.monitors().to_vec() {
+            let group: Vec<u8> = ids.iter().copied().filter(|id| self.monitor_for_stream(*id).name == monitor.name).collect();
+            let rects = self.policy.arrange(group.len(), monitor.width, monitor.height);
+            for (id, (x, y, width, height)) in group.into_iter().zip(rects) {
+                self.viewports.insert(id, Viewport { x: monitor.x + x, y: monitor.y + y, width, height, z_index: 1, active: true, scale: monitor.scale });
+            }
+        }
+    }
+
+    pub fn monitors(&self) -> &MonitorMap {
+        &self.monitors
+    }
+
+    /// New output configuration (startup enumeration, hotplug): viewports are
+    /// laid out again; streams pinned to a vanished output fall back to the
+    /// primary one until it returns
+    pub fn set_monitors(&mut self, monitors: MonitorMap) {
+        (self.screen_width, self.screen_height) = monitors.desktop_size();
+        self.monitors = monitors;
+        self.viewports.clear();
+        self.calculate_layouts();
+    }
+
+    /// Keep a stream on the output called `output` (connector name)
+    pub fn pin_stream(&mut self, stream_id: u8, output: &str) {
+        self.pins.insert(stream_id, output.to_string());
+        self.viewports.clear();
+        self.calculate_layouts();
+    }
+
+    pub fn unpin_stream(&mut self, stream_id: u8) {
+        if self.pins.remove(&stream_id).is_some() {
+            self.viewports.clear();
+            self.calculate_layouts();
+        }
+    }
+
+    /// Output the stream is laid out on: its pin when connected, else the primary
+    pub fn monitor_for_stream(&self, stream_id: u8) -> &Monitor {
+        self.pins.get(&stream_id)
+            .and_then(|name| self.monitors.get(name))
+            .unwrap_or_else(|| self.monitors.primary())
+    }
+
+    /// Frame'in çizim listesi: küçük viewport'lar atlas UV'si ile, büyükler kendi texture'ı ile
+    pub fn composite_plan(&mut self) -> CompositePlan {
+        self.atlas.plan(&self.viewports)
+    }
+
+    pub fn atlas(&self) -> &TextureAtlas {
+        &self.atlas
+    }
+
     pub fn get_viewport_for_stream(&self, stream_id: u8) -> Option<&Viewport> {
         self.viewports.get(&stream_id)
     }
 
+    /// Resize the primary output (the whole screen without monitor enumeration)
     pub fn update_resolution(&mut self, width: u32, height: u32) {
-        self.screen_width = width;
-        self.screen_height = height;
+        self.monitors.resize_primary(width, height);
+        (self.screen_width, self.screen_height) = self.monitors.desktop_size();
         self.calculate_layouts();
     }
 
     pub fn handle_input_focus(&self, x: i32, y: i32) -> Option<u8> {
-        // Fare koordinatına göre hangi protokolün (stream_id) aktif olduğunu bulur
-        for (id, vp) in &self.viewports {
-            if x >= vp.x && x <= (vp.x + vp.width as i32) &&
-               y >= vp.y && y <= (vp.y + vp.height as i32) {
-                return Some(*id);
+        // Fare koordinatına göre hangi protokolün (stream_id) aktif olduğunu bulur.
+        // Üst üste binen viewport'larda en üstteki (z_index) kazanır
+        self.viewports.iter()
+            .filter(|(_, vp)| vp.active && x >= vp.x && x < vp.x + vp.width as i32 && y >= vp.y && y < vp.y + vp.height as i32)
+            .max_by_key(|(id, vp)| (vp.z_index, std::cmp::Reverse(**id)))
+            .map(|(id, _)| *id)
+    }
+
+    pub fn focused_stream(&self) -> Option<u8> {
+        self.focused
+    }
+
+    /// Focus-in/focus-out events for the protocol streams
+    pub fn subscribe_focus(&mut self) -> Receiver<StreamFocusEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.focus_subscribers.push(tx);
+        rx
+    }
+
+    /// Move input focus; the streams losing and gaining it are notified
+    pub fn set_focus(&mut self, stream_id: Option<u8>) -> Option<InputAction> {
+        if self.focused == stream_id {
+            return None;
+        }
+        let previous = std::mem::replace(&mut self.focused, stream_id);
+        let events: Vec<StreamFocusEvent> = previous.map(|id| StreamFocusEvent { stream_id: id, focused: false }).into_iter()
+            .chain(stream_id.map(|id| StreamFocusEvent { stream_id: id, focused: true }))
+            .collect();
+        self.focus_subscribers.retain(|tx| events.iter().all(|event| tx.send(*event).is_ok()));
+        Some(InputAction::FocusChanged { previous, current: stream_id })
+    }
+
+    /// Let the focus engine see pointer input; its policy (click, follows
+    /// mouse, sloppy) decides which handler window gets focus
+    pub fn attach_focus_engine(&mut self, engine: FocusEngine) {
+        self.focus_engine = Some(engine);
+    }
+
+    /// Hand a pointer event (screen coordinates) to the focus engine
+    pub fn feed_focus_engine(&mut self, event: PointerEvent) -> Option<InputAction> {
+        let engine = self.focus_engine.as_mut()?;
+        engine.handle_pointer(event, Instant::now()).map(InputAction::WindowFocused)
+    }
+
+    /// Focus the hovered window once the policy's delay has passed
+    pub fn tick_focus(&mut self, now: Instant) -> Option<InputAction> {
+        self.focus_engine.as_mut()?.tick(now).map(InputAction::WindowFocused)
+    }
+
+    /// Stream shown in singularity mode, if any
+    pub fn singularity_stream(&self) -> Option<u8> {
+        self.singularity
+    }
+
+    /// Keep the router in step when singularity mode is switched elsewhere
+    pub fn set_singularity_stream(&mut self, stream_id: Option<u8>) {
+        self.singularity = stream_id;
+        self.drag = None;
+    }
+
+    pub fn set_singularity_shortcut(&mut self, combo: KeyCombo) {
+        self.singularity_shortcut = Some(combo);
+    }
+
+    /// Deliver the singularity shortcut to the stream like any other key
+    pub fn disable_singularity_shortcut(&mut self) {
+        self.singularity_shortcut = None;
+    }
+
+    /// Focus the next (or previous) stream in id order; not in singularity mode
+    pub fn cycle_focus(&mut self, reverse: bool) -> Option<InputAction> {
+        if self.singularity.is_some() {
+            return None;
+        }
+        let mut ids: Vec<u8> = self.viewports.keys().copied().collect();
+        ids.sort_unstable();
+        let position = self.focused.and_then(|focused| ids.iter().position(|id| *id == focused));
+        let next = match (position, reverse) {
+            (None, false) => *ids.first()?,
+            (None, true) => *ids.last()?,
+            (Some(i), false) => ids[(i + 1) % ids.len()],
+            (Some(i), true) => ids[(i + ids.len() - 1) % ids.len()],
+        };
+        self.set_focus(Some(next))
+    }
+
+    /// Route pointer input: click-to-focus, drag to move/resize, everything
+    /// else to the stream under the pointer (all of it in singularity mode)
+    pub fn route_pointer(&mut self, event: PointerEvent, modifiers: Modifiers) -> Vec<InputAction> {
+        if let Some(stream_id) = self.singularity {
+            return vec![InputAction::Pointer { stream_id, event }];
+        }
+
+        let mut actions: Vec<InputAction> = self.feed_focus_engine(event).into_iter().collect();
+        match event {
+            PointerEvent::Motion { x, y } => match self.drag {
+                Some(drag) => actions.push(self.drag_to(drag, x, y)),
+                None => actions.extend(self.forward_pointer(event, x, y)),
+            },
+            PointerEvent::ButtonPress { x, y, button } => {
+                let hit = self.handle_input_focus(x, y);
+                actions.extend(self.set_focus(hit));
+                let Some((stream_id, vp)) = hit.and_then(|id| Some((id, self.viewports.get(&id)?.clone()))) else {
+                    return actions;
+                };
+                let on_edge = x >= vp.x + vp.width as i32 - RESIZE_EDGE || y >= vp.y + vp.height as i32 - RESIZE_EDGE;
+                let mode = match button {
+                    BUTTON_LEFT if modifiers.super_key => Some(DragMode::Move),
+                    BUTTON_RIGHT if modifiers.super_key => Some(DragMode::Resize),
+                    BUTTON_LEFT if on_edge => Some(DragMode::Resize),
+                    _ => None,
+                };
+                match mode {
+                    Some(mode) => {
+                        self.drag = Some(Drag { stream_id, mode, origin: (x, y), start: (vp.x, vp.y, vp.width, vp.height) });
+                    }
+                    None => actions.extend(self.forward_pointer(event, x, y)),
+                }
+            }
+            PointerEvent::ButtonRelease { x, y, .. } => {
+                if self.drag.take().is_none() {
+                    actions.extend(self.forward_pointer(event, x, y));
+                }
+            }
+            PointerEvent::Leave => {
+                self.drag = None;
+                if let Some(stream_id) = self.focused {
+                    actions.push(InputAction::Pointer { stream_id, event });
+                }
             }
         }
-        None
+        actions
+    }
+
+    /// Route a key press: the singularity shortcut is taken, everything else
+    /// goes to the focused stream
+    pub fn route_key(&mut self, combo: &KeyCombo) -> Vec<InputAction> {
+        if self.singularity_shortcut.as_ref() == Some(combo) {
+            return match (self.singularity, self.focused) {
+                (Some(_), _) => {
+                    self.set_singularity_stream(None);
+                    vec![InputAction::ExitSingularity]
+                }
+                (None, Some(stream_id)) => {
+                    self.set_singularity_stream(Some(stream_id));
+                    vec![InputAction::EnterSingularity(stream_id)]
+                }
+                (None, None) => Vec::new(),
+            };
+        }
+        self.singularity.or(self.focused)
+            .map(|stream_id| vec![InputAction::Key { stream_id, combo: combo.clone() }])
+            .unwrap_or_default()
+    }
+
+    fn drag_to(&mut self, drag: Drag, x: i32, y: i32) -> InputAction {
+        let (dx, dy) = (x - drag.origin.0, y - drag.origin.1);
+        let (start_x, start_y, start_w, start_h) = drag.start;
+        let (screen_w, screen_h) = (self.screen_width as i32, self.screen_height as i32);
+        let (x, y, width, height) = match drag.mode {
+            // Top-left corner stays on screen
+            DragMode::Move => (
+                (start_x + dx).clamp(0, (screen_w - start_w as i32).max(0)),
+                (start_y + dy).clamp(0, (screen_h - start_h as i32).max(0)),
+                start_w,
+                start_h,
+            ),
+            DragMode::Resize => (
+                start_x,
+                start_y,
+                (start_w as i32 + dx).clamp(MIN_VIEWPORT_SIZE as i32, (screen_w - start_x).max(MIN_VIEWPORT_SIZE as i32)) as u32,
+                (start_h as i32 + dy).clamp(MIN_VIEWPORT_SIZE as i32, (screen_h - start_y).max(MIN_VIEWPORT_SIZE as i32)) as u32,
+            ),
+        };
+        self.set_floating(drag.stream_id, x, y, width, height);
+        let viewport = Viewport { x, y, width, height, z_index: 2, active: true, scale: self.scale_at(x, y) };
+        match drag.mode {
+            DragMode::Move => InputAction::Moved { stream_id: drag.stream_id, viewport },
+            DragMode::Resize => InputAction::Resized { stream_id: drag.stream_id, viewport },
+        }
+    }
+
+    /// The event in the coordinates of the viewport under (x, y)
+    fn forward_pointer(&self, event: PointerEvent, x: i32, y: i32) -> Option<InputAction> {
+        let stream_id = self.handle_input_focus(x, y)?;
+        let vp = self.viewports.get(&stream_id)?;
+        let (x, y) = (x - vp.x, y - vp.y);
+        let event = match event {
+            PointerEvent::Motion { .. } => PointerEvent::Motion { x, y },
+            PointerEvent::ButtonPress { button, .. } => PointerEvent::ButtonPress { x, y, button },
+            PointerEvent::ButtonRelease { button, .. } => PointerEvent::ButtonRelease { x, y, button },
+            PointerEvent::Leave => PointerEvent::Leave,
+        };
+        Some(InputAction::Pointer { stream_id, event })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitors::Monitor;
+    use crate::parser::{ConfigParser, Protocol, ProtocolConfig};
+
+    #[test]
+    fn test_layout_policies() {
+        let parser = ConfigParser::new(None);
+        let mut config = parser.parse(&parser.generate_default_config()).unwrap();
+        config.uri_handling.multi_instances = true;
+        config.uri_handling.singularity_instances = false;
+        config.uri_handling.protocols = (0..3)
+            .map(|i| ProtocolConfig { protocol: Protocol::Http, ip: "127.0.0.1".parse().unwrap(), port: 8080 + i, domain: None })
+            .collect();
+        let mut multitary = WindowMultitary::new(config, 1200, 900);
+        let geometry = |m: &WindowMultitary, id: u8| {
+            let vp = m.get_viewport_for_stream(id).unwrap();
+            (vp.x, vp.y, vp.width, vp.height)
+        };
+        assert_eq!(geometry(&multitary, 2), (0, 600, 1200, 300));
+
+        multitary.set_layout_policy(LayoutPolicy::Horizontal);
+        assert_eq!(geometry(&multitary, 2), (800, 0, 400, 900));
+        multitary.set_layout_policy(LayoutPolicy::Grid);
+        assert_eq!(geometry(&multitary, 2), (0, 450, 600, 450));
+        multitary.set_layout_policy(LayoutPolicy::parse("master-stack:0.5").unwrap());
+        assert_eq!(geometry(&multitary, 0), (0, 0, 600, 900));
+        assert_eq!(geometry(&multitary, 2), (600, 450, 600, 450));
+        multitary.set_layout_policy(LayoutPolicy::Floating);
+        assert_eq!(geometry(&multitary, 1), (32, 32, 800, 600));
+
+        // A hand-placed stream keeps its place across layouts and scales with the screen
+        multitary.set_floating(1, 100, 100, 400, 300);
+        multitary.set_layout_policy(LayoutPolicy::Vertical);
+        assert_eq!(geometry(&multitary, 1), (100, 100, 400, 300));
+        multitary.update_resolution(2400, 1800);
+        assert_eq!(geometry(&multitary, 1), (200, 200, 800, 600));
+        assert_eq!(geometry(&multitary, 0), (0, 0, 2400, 600));
+        multitary.clear_floating(1);
+        assert_eq!(geometry(&multitary, 1), (0, 600, 2400, 600));
+
+        assert_eq!(LayoutPolicy::parse("master"), Some(LayoutPolicy::MasterStack { ratio: LayoutPolicy::DEFAULT_MASTER_RATIO }));
+        assert_eq!(LayoutPolicy::parse("spiral"), None);
+    }
+
+    #[test]
+    fn test_multi_monitor_layout() {
+        let parser = ConfigParser::new(None);
+        let mut config = parser.parse(&parser.generate_default_config()).unwrap();
+        config.uri_handling.multi_instances = true;
+        config.uri_handling.singularity_instances = false;
+        config.uri_handling.protocols = (0..3)
+            .map(|i| ProtocolConfig { protocol: Protocol::Http, ip: "127.0.0.1".parse().unwrap(), port: 8080 + i, domain: None })
+            .collect();
+        let mut multitary = WindowMultitary::new(config, 1920, 1080);
+        let geometry = |m: &WindowMultitary, id: u8| {
+            let vp = m.get_viewport_for_stream(id).unwrap();
+            (vp.x, vp.y, vp.width, vp.height)
+        };
+
+        let monitors = |outputs: &[(&str, i32)]| {
+            MonitorMap::new(outputs.iter().map(|(name, x)| Monitor::new(name, *x, 0, 1920, 1080)).collect()).unwrap()
+        };
+        multitary.set_monitors(monitors(&[("DP-1", 0), ("HDMI-A-1", 1920)]));
+        multitary.pin_stream(2, "HDMI-A-1");
+        // Streams 0 and 1 share the primary output, stream 2 has the second one to itself
+        assert_eq!(geometry(&multitary, 0), (0, 0, 1920, 540));
+        assert_eq!(geometry(&multitary, 1), (0, 540, 1920, 540));
+        assert_eq!(geometry(&multitary, 2), (1920, 0, 1920, 1080));
+
+        // Unplugged: back on the primary output; replugged: back on its own
+        multitary.set_monitors(monitors(&[("DP-1", 0)]));
+        assert_eq!(geometry(&multitary, 2), (0, 720, 1920, 360));
+        multitary.set_monitors(monitors(&[("DP-1", 0), ("HDMI-A-1", 1920)]));
+        assert_eq!(geometry(&multitary, 2), (1920, 0, 1920, 1080));
+        assert_eq!(multitary.monitor_for_stream(2).name, "HDMI-A-1");
+
+        multitary.unpin_stream(2);
+        assert_eq!(geometry(&multitary, 2), (0, 720, 1920, 360));
+        assert_eq!(multitary.handle_input_focus(2000, 10), None);
+    }
+
+    #[test]
+    fn test_hidpi_viewports() {
+        let parser = ConfigParser::new(None);
+        let mut config = parser.parse(&parser.generate_default_config()).unwrap();
+        config.uri_handling.multi_instances = true;
+        config.uri_handling.singularity_instances = false;
+        config.uri_handling.protocols = (0..2)
+            .map(|i| ProtocolConfig { protocol: Protocol::Http, ip: "127.0.0.1".parse().unwrap(), port: 8080 + i, domain: None })
+            .collect();
+        let mut multitary = WindowMultitary::new(config, 1920, 1080);
+        multitary.set_monitors(MonitorMap::new(vec![
+            Monitor::new("DP-1", 0, 0, 1920, 1080),
+            Monitor::new("eDP-1", 1920, 0, 2880, 1800).with_scale(2.0),
+        ]).unwrap());
+        multitary.pin_stream(1, "eDP-1");
+
+        // Geometry stays physical; the scale tells how big a logical frame is
+        let hidpi = multitary.get_viewport_for_stream(1).unwrap().clone();
+        assert_eq!((hidpi.x, hidpi.width, hidpi.height, hidpi.scale), (1920, 2880, 1800, 2.0));
+        assert_eq!(hidpi.logical_size(), (1440, 900));
+        assert_eq!(multitary.get_viewport_for_stream(0).unwrap().scale, 1.0);
+
+        // A stream dragged onto the HiDPI output takes its scale
+        multitary.set_floating(0, 2000, 100, 800, 600);
+        assert_eq!(multitary.get_viewport_for_stream(0).unwrap().scale, 2.0);
+    }
+
+    #[test]
+    fn test_input_routing() {
+        let parser = ConfigParser::new(None);
+        let mut config = parser.parse(&parser.generate_default_config()).unwrap();
+        config.uri_handling.multi_instances = true;
+        config.uri_handling.singularity_instances = false;
+        config.uri_handling.protocols = (0..2)
+            .map(|i| ProtocolConfig { protocol: Protocol::Http, ip: "127.0.0.1".parse().unwrap(), port: 8080 + i, domain: None })
+            .collect();
+        // Vertical: stream 0 at (0, 0, 1200, 450), stream 1 at (0, 450, 1200, 450)
+        let mut multitary = WindowMultitary::new(config, 1200, 900);
+        let focus = multitary.subscribe_focus();
+        let none = Modifiers::default();
+        let super_key = Modifiers { super_key: true, ..Modifiers::default() };
+
+        // Click-to-focus; the click itself goes to the stream in its own coordinates
+        let actions = multitary.route_pointer(PointerEvent::ButtonPress { x: 100, y: 500, button: 1 }, none);
+        assert_eq!(actions, [
+            InputAction::FocusChanged { previous: None, current: Some(1) },
+            InputAction::Pointer { stream_id: 1, event: PointerEvent::ButtonPress { x: 100, y: 50, button: 1 } },
+        ]);
+        multitary.route_pointer(PointerEvent::ButtonRelease { x: 100, y: 500, button: 1 }, none);
+        multitary.route_pointer(PointerEvent::ButtonPress { x: 100, y: 100, button: 1 }, none);
+        assert_eq!(focus.try_iter().collect::<Vec<_>>(), [
+            StreamFocusEvent { stream_id: 1, focused: true },
+            StreamFocusEvent { stream_id: 1, focused: false },
+            StreamFocusEvent { stream_id: 0, focused: true },
+        ]);
+        multitary.route_pointer(PointerEvent::ButtonRelease { x: 100, y: 100, button: 1 }, none);
+
+        // Super + drag moves the viewport; it floats above the tiling from then on.
+        // Clamped: the full-width viewport cannot move right
+        assert!(multitary.route_pointer(PointerEvent::ButtonPress { x: 100, y: 100, button: 1 }, super_key).is_empty());
+        let actions = multitary.route_pointer(PointerEvent::Motion { x: 150, y: 300 }, super_key);
+        let moved = Viewport { x: 0, y: 200, width: 1200, height: 450, z_index: 2, active: true, scale: 1.0 };
+        assert_eq!(actions, [InputAction::Moved { stream_id: 0, viewport: moved.clone() }]);
+        assert!(multitary.route_pointer(PointerEvent::ButtonRelease { x: 150, y: 300, button: 1 }, none).is_empty());
+        assert_eq!(multitary.floating_geometry(0), Some(moved));
+
+        // Dragging the bottom edge resizes, down to MIN_VIEWPORT_SIZE
+        multitary.route_pointer(PointerEvent::ButtonPress { x: 600, y: 648, button: 1 }, none);
+        multitary.route_pointer(PointerEvent::Motion { x: 600, y: 100 }, none);
+        multitary.route_pointer(PointerEvent::ButtonRelease { x: 600, y: 100, button: 1 }, none);
+        let vp = multitary.get_viewport_for_stream(0).unwrap();
+        assert_eq!((vp.x, vp.y, vp.width, vp.height), (0, 200, 1200, MIN_VIEWPORT_SIZE));
+        // The floating viewport is on top of stream 1 where they overlap
+        assert_eq!(multitary.handle_input_focus(10, 210), Some(0));
+        assert_eq!(multitary.handle_input_focus(10, 500), Some(1));
+
+        // Keys go to the focused stream; the shortcut toggles singularity mode
+        let key = KeyCombo::parse("a").unwrap();
+        assert_eq!(multitary.route_key(&key), [InputAction::Key { stream_id: 0, combo: key.clone() }]);
+        let shortcut = KeyCombo::parse(DEFAULT_SINGULARITY_SHORTCUT).unwrap();
+        assert_eq!(multitary.route_key(&shortcut), [InputAction::EnterSingularity(0)]);
+        // In singularity mode the whole screen belongs to the stream
+        let press = PointerEvent::ButtonPress { x: 10, y: 800, button: 1 };
+        assert_eq!(multitary.route_pointer(press, super_key), [InputAction::Pointer { stream_id: 0, event: press }]);
+        assert_eq!(multitary.route_key(&shortcut), [InputAction::ExitSingularity]);
+        assert_eq!(multitary.singularity_stream(), None);
+
+        // Detaching the focused stream drops focus
+        multitary.attach_stream(9);
+        multitary.route_pointer(PointerEvent::ButtonPress { x: 1199, y: 899, button: 3 }, none);
+        assert_eq!(multitary.focused_stream(), Some(9));
+        multitary.detach_stream(9);
+        assert_eq!(multitary.focused_stream(), None);
+        assert!(multitary.route_key(&key).is_empty());
     }
 }
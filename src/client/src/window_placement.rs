@@ -0,0 +1,157 @@
+// WASMA - Window-type placement
+// What the window manager does with each WindowType: where the window goes
+// when it is typed or parented, whether it takes focus and shows in the
+// taskbar, and when WASMA closes it on its own. Dialogs are centered on their
+// parent and keep focus from it, tooltips stay unmanaged and disappear after a
+// few seconds, notifications stack in the top-right corner of the primary
+// output, and splash screens skip the taskbar and close once their app maps a
+// normal window (or after a timeout).
+
+use std::time::Duration;
+
+use crate::monitors::Monitor;
+use crate::window_handling::{WindowGeometry, WindowType};
+
+/// How long a tooltip stays up
+pub const TOOLTIP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest a splash screen stays up when its app never maps a window
+pub const SPLASH_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often expired tooltips and splash screens are closed
+pub const AUTO_CLOSE_INTERVAL: Duration = Duration::from_millis(500);
+/// Gap between stacked notifications and between the stack and the output edge
+pub const NOTIFICATION_SPACING: u32 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// Where the client put it
+    AsRequested,
+    /// Centered on the parent, or on the primary output without one
+    CenterOnParent,
+    /// Centered on the primary output
+    Centered,
+    /// Top-right corner of the primary output, below older notifications
+    CornerStack,
+}
+
+/// Window-manager behavior of one WindowType
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeBehavior {
+    pub placement: Placement,
+    /// Takes focus; unmanaged windows are only drawn
+    pub managed: bool,
+    pub skip_taskbar: bool,
+    /// A visible one keeps focus away from its parent
+    pub modal: bool,
+    /// Stacked above normal windows
+    pub above: bool,
+    /// Closed by WASMA after this long
+    pub auto_close: Option<Duration>,
+}
+
+impl TypeBehavior {
+    pub fn of(window_type: &WindowType) -> Self {
+        let normal = TypeBehavior {
+            placement: Placement::AsRequested,
+            managed: true,
+            skip_taskbar: false,
+            modal: false,
+            above: false,
+            auto_close: None,
+        };
+        // Menus and tooltips are placed by their client, next to what opened them
+        let transient = TypeBehavior { managed: false, skip_taskbar: true, above: true, ..normal };
+        match window_type {
+            WindowType::Normal => normal,
+            WindowType::Utility => TypeBehavior { skip_taskbar: true, ..normal },
+            WindowType::Dialog => TypeBehavior { placement: Placement::CenterOnParent, modal: true, ..normal },
+            WindowType::Splash => TypeBehavior {
+                placement: Placement::Centered,
+                auto_close: Some(SPLASH_TIMEOUT),
+                ..transient
+            },
+            WindowType::Menu | WindowType::Dropdown | WindowType::Popup => transient,
+            WindowType::Tooltip => TypeBehavior { auto_close: Some(TOOLTIP_TIMEOUT), ..transient },
+            WindowType::Notification => TypeBehavior { placement: Placement::CornerStack, ..transient },
+        }
+    }
+}
+
+/// `window` centered on `area`, kept inside `output` where it fits
+pub fn center_on(window: WindowGeometry, area: WindowGeometry, output: &Monitor) -> WindowGeometry {
+    let x = area.x + (area.width as i32 - window.width as i32) / 2;
+    let y = area.y + (area.height as i32 - window.height as i32) / 2;
+    let clamp = |pos: i32, size: u32, start: i32, extent: u32| {
+        pos.min(start + extent as i32 - size as i32).max(start)
+    };
+    WindowGeometry {
+        x: clamp(x, window.width, output.x, output.width),
+        y: clamp(y, window.height, output.y, output.height),
+        ..window
+    }
+}
+
+/// The output as a geometry, for centering on it
+pub fn output_area(output: &Monitor) -> WindowGeometry {
+    WindowGeometry { x: output.x, y: output.y, width: output.width, height: output.height }
+}
+
+/// Positions for `stack` (oldest first) from the top-right corner of `output`
+/// downward; notifications that no longer fit start a new column to the left
+pub fn stack_notifications(output: &Monitor, stack: &[(u64, WindowGeometry)]) -> Vec<(u64, WindowGeometry)> {
+    let spacing = NOTIFICATION_SPACING as i32;
+    let right = output.x + output.width as i32 - spacing;
+    let bottom = output.y + output.height as i32;
+    let (mut column_right, mut column_width) = (right, 0);
+    let mut y = output.y + spacing;
+
+    stack.iter().map(|&(id, geometry)| {
+        if y > output.y + spacing && y + geometry.height as i32 > bottom {
+            column_right -= column_width + spacing;
+            column_width = 0;
+            y = output.y + spacing;
+        }
+        let placed = WindowGeometry { x: column_right - geometry.width as i32, y, ..geometry };
+        y += geometry.height as i32 + spacing;
+        column_width = column_width.max(geometry.width as i32);
+        (id, placed)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geometry(x: i32, y: i32, width: u32, height: u32) -> WindowGeometry {
+        WindowGeometry { x, y, width, height }
+    }
+
+    #[test]
+    fn test_type_placement() {
+        let output = Monitor::new("DP-1", 0, 0, 1920, 1080);
+
+        let dialog = TypeBehavior::of(&WindowType::Dialog);
+        assert_eq!(dialog.placement, Placement::CenterOnParent);
+        assert!(dialog.modal && dialog.managed && !dialog.skip_taskbar);
+        let tooltip = TypeBehavior::of(&WindowType::Tooltip);
+        assert!(!tooltip.managed && tooltip.skip_taskbar);
+        assert_eq!(tooltip.auto_close, Some(TOOLTIP_TIMEOUT));
+        let splash = TypeBehavior::of(&WindowType::Splash);
+        assert!(splash.skip_taskbar && splash.auto_close.is_some());
+        assert_eq!(TypeBehavior::of(&WindowType::Normal).placement, Placement::AsRequested);
+
+        // Centered on the parent, pushed back on screen near the edge
+        let parent = geometry(100, 100, 800, 600);
+        assert_eq!(center_on(geometry(0, 0, 400, 200), parent, &output), geometry(300, 300, 400, 200));
+        let edge = geometry(1700, 900, 200, 100);
+        assert_eq!(center_on(geometry(0, 0, 400, 300), edge, &output), geometry(1520, 780, 400, 300));
+        assert_eq!(center_on(geometry(5, 5, 200, 100), output_area(&output), &output), geometry(860, 490, 200, 100));
+
+        // Stacked downward from the top-right corner, wrapping into a second column
+        let stack: Vec<_> = (1..=4).map(|id| (id, geometry(0, 0, 300, 400))).collect();
+        let placed = stack_notifications(&output, &stack);
+        assert_eq!(placed[0], (1, geometry(1608, 12, 300, 400)));
+        assert_eq!(placed[1], (2, geometry(1608, 424, 300, 400)));
+        assert_eq!(placed[2], (3, geometry(1296, 12, 300, 400)));
+        assert_eq!(placed[3].1.y, 424);
+    }
+}
@@ -0,0 +1,160 @@
+// WASMA - Window rules
+// Declarative placement from wasma.in.conf, applied when a window is created:
+//
+//   rule app_id=firefox -> workspace=2, maximized, gpu_preferred
+//   rule title=Picture-in-Picture -> above
+//
+// `app_id=` matches the app id exactly, `title=` matches a substring of the
+// title (case-insensitive, no spaces); a rule with both needs both. Every matching rule
+// applies in file order, so later rules override earlier ones.
+
+use serde::{Deserialize, Serialize};
+use wbackend::ExecutionMode;
+
+use crate::window_handling::WindowState;
+use crate::workspaces;
+
+/// One `rule` line of wasma.in.conf
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WindowRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub actions: RuleActions,
+}
+
+/// What a rule changes on the windows it matches; None leaves it alone
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleActions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<WindowState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_mode: Option<ExecutionMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub always_on_top: Option<bool>,
+}
+
+impl RuleActions {
+    /// `other` on top of `self`
+    fn merge(&mut self, other: &RuleActions) {
+        self.workspace = other.workspace.or(self.workspace);
+        self.state = other.state.clone().or(self.state.take());
+        self.execution_mode = other.execution_mode.or(self.execution_mode);
+        self.always_on_top = other.always_on_top.or(self.always_on_top);
+    }
+}
+
+impl WindowRule {
+    /// Parse the value part of a `rule` line: `<matchers> -> <actions>`
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (matchers, actions) = value.split_once("->")
+            .ok_or_else(|| format!("Rule needs '->' between matchers and actions: {}", value))?;
+
+        let mut rule = WindowRule::default();
+        for matcher in matchers.split_whitespace() {
+            match matcher.split_once('=') {
+                Some(("app_id", app_id)) if !app_id.is_empty() => rule.app_id = Some(app_id.to_string()),
+                Some(("title", title)) if !title.is_empty() => rule.title = Some(title.to_lowercase()),
+                _ => return Err(format!("Unknown rule matcher: {}", matcher)),
+            }
+        }
+        if rule.app_id.is_none() && rule.title.is_none() {
+            return Err(format!("Rule matches no window: {}", value));
+        }
+
+        for action in actions.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+            let actions = &mut rule.actions;
+            match action.split_once('=') {
+                Some(("workspace", number)) => {
+                    let number = number.parse().map_err(|_| format!("Invalid rule workspace: {}", number))?;
+                    actions.workspace = Some(workspaces::validate(number)?);
+                }
+                Some(_) => return Err(format!("Unknown rule action: {}", action)),
+                None => match action.to_lowercase().as_str() {
+                    "normal" => actions.state = Some(WindowState::Normal),
+                    "maximized" => actions.state = Some(WindowState::Maximized),
+                    "minimized" => actions.state = Some(WindowState::Minimized),
+                    "fullscreen" => actions.state = Some(WindowState::Fullscreen),
+                    "cpu_only" => actions.execution_mode = Some(ExecutionMode::CpuOnly),
+                    "gpu_only" => actions.execution_mode = Some(ExecutionMode::GpuOnly),
+                    "gpu_preferred" => actions.execution_mode = Some(ExecutionMode::GpuPreferred),
+                    "hybrid" => actions.execution_mode = Some(ExecutionMode::Hybrid),
+                    "above" | "always_on_top" => actions.always_on_top = Some(true),
+                    _ => return Err(format!("Unknown rule action: {}", action)),
+                },
+            }
+        }
+        Ok(rule)
+    }
+
+    pub fn matches(&self, app_id: &str, title: &str) -> bool {
+        self.app_id.as_deref().map_or(true, |id| id == app_id)
+            && self.title.as_deref().map_or(true, |part| title.to_lowercase().contains(part))
+    }
+}
+
+/// Combined actions of every rule in `rules` matching the window
+pub fn evaluate(rules: &[WindowRule], app_id: &str, title: &str) -> RuleActions {
+    let mut actions = RuleActions::default();
+    for rule in rules.iter().filter(|r| r.matches(app_id, title)) {
+        actions.merge(&rule.actions);
+    }
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ConfigParser;
+    use crate::window_handling::{WindowGeometry, WindowHandler};
+    use wbackend::ResourceMode;
+
+    #[test]
+    fn test_window_rules() {
+        let parser = ConfigParser::new(None);
+        let mut config = parser.parse(&parser.generate_default_config()).unwrap();
+        let rules = parser.parse(
+            "rule app_id=firefox -> workspace=2, maximized, gpu_preferred\n\
+             rule app_id=firefox title=private -> cpu_only, above *// private windows stay off the GPU\n\
+             rule : title=Picture-in-Picture -> above\n"
+        ).unwrap().window_rules;
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].app_id.as_deref(), Some("firefox"));
+        assert_eq!(rules[0].actions.workspace, Some(2));
+        assert_eq!(rules[2].app_id, None);
+
+        // Later rules override earlier ones; unmatched fields stay as they were
+        let private = evaluate(&rules, "firefox", "Mozilla Firefox Private Browsing");
+        assert_eq!(private.execution_mode, Some(ExecutionMode::CpuOnly));
+        assert_eq!(private.state, Some(WindowState::Maximized));
+        assert_eq!(private.always_on_top, Some(true));
+        assert_eq!(evaluate(&rules, "org.gnome.Terminal", "Terminal"), RuleActions::default());
+
+        assert!(WindowRule::parse("app_id=firefox workspace=2").is_err());
+        assert!(WindowRule::parse("-> maximized").is_err());
+        assert!(WindowRule::parse("app_id=firefox -> workspace=9").is_err());
+        assert!(WindowRule::parse("app_id=firefox -> sideways").is_err());
+        assert!(parser.parse("rule class=firefox -> maximized\n").is_err());
+
+        // Applied when the window is created
+        config.window_rules = rules;
+        let handler = WindowHandler::new(ResourceMode::Manual);
+        handler.apply_wasma_config(config);
+        let geometry = WindowGeometry { x: 0, y: 0, width: 640, height: 480 };
+        let id = handler.create_window("Mozilla Firefox".into(), "firefox".into(), geometry, None, ResourceMode::Manual).unwrap();
+        let window = handler.get_window(id).unwrap();
+        assert_eq!(window.workspace, 2);
+        assert!(!window.visible);
+        assert_eq!(window.state, WindowState::Maximized);
+        assert_eq!(window.resource_limits.execution_mode, Some(ExecutionMode::GpuPreferred));
+        assert!(!window.always_on_top);
+
+        let other = handler.create_window("Notes".into(), "notes".into(), geometry, None, ResourceMode::Manual).unwrap();
+        let window = handler.get_window(other).unwrap();
+        assert_eq!((window.workspace, window.state), (1, WindowState::Normal));
+    }
+}
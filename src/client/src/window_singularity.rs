@@ -28,10 +28,8 @@ impl WindowSingularity {
 
     fn enforce_exclusive_resource(&self) {
         if self.config.resource_limits.scope_level > 0 {
-            unsafe {
-                let vram_ptr = crate::wgclient::WASMA_VRAM_ADDR as *mut u8;
-                std::ptr::write_bytes(vram_ptr, 0, (self.screen_width * self.screen_height * 4) as usize);
-            }
+            // Cleared in the back buffer, shown blank from the next vsync
+            crate::presenter::Presenter::global().clear(0, (self.screen_width * self.screen_height * 4) as usize);
         }
     }
 
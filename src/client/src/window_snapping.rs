@@ -0,0 +1,630 @@
+// window_snapping.rs
+// WASMA - Interactive move/resize, edge snapping and keyboard tiling
+// Super+Arrow: half/quarter tiling, drag to screen edge: snap,
+// geometry changes are animated and mirrored into multitary viewports
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::window_handling::{WindowGeometry, WindowHandler, WindowState};
+use crate::window_multitary::WindowMultitary;
+
+/// Default distance (px) at which edges attract each other
+pub const DEFAULT_RESISTANCE: i32 = 16;
+/// Default duration of geometry animations
+pub const DEFAULT_ANIMATION: Duration = Duration::from_millis(180);
+/// Smallest size an interactive resize may produce
+const MIN_SIZE: u32 = 64;
+
+// ============================================================================
+// SNAP ZONES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SnapZone {
+    LeftHalf,
+    RightHalf,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Maximized,
+}
+
+impl SnapZone {
+    /// Target geometry on a screen of the given size
+    pub fn geometry(&self, screen_width: u32, screen_height: u32) -> WindowGeometry {
+        let half_w = screen_width / 2;
+        let half_h = screen_height / 2;
+        let (x, y, width, height) = match self {
+            SnapZone::LeftHalf => (0, 0, half_w, screen_height),
+            SnapZone::RightHalf => (half_w, 0, screen_width - half_w, screen_height),
+            SnapZone::TopLeft => (0, 0, half_w, half_h),
+            SnapZone::TopRight => (half_w, 0, screen_width - half_w, half_h),
+            SnapZone::BottomLeft => (0, half_h, half_w, screen_height - half_h),
+            SnapZone::BottomRight => (half_w, half_h, screen_width - half_w, screen_height - half_h),
+            SnapZone::Maximized => (0, 0, screen_width, screen_height),
+        };
+        WindowGeometry { x: x as i32, y: y as i32, width, height }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Result of a Super+Arrow press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapAction {
+    Snap(SnapZone),
+    Restore,
+    Minimize,
+}
+
+/// Keyboard tiling state machine:
+/// halves split into quarters with Up/Down, quarters merge back into halves
+pub fn next_action(current: Option<SnapZone>, direction: SnapDirection) -> SnapAction {
+    use SnapDirection::*;
+    use SnapZone::*;
+
+    match (current, direction) {
+        (None, Left) => SnapAction::Snap(LeftHalf),
+        (None, Right) => SnapAction::Snap(RightHalf),
+        (None, Up) => SnapAction::Snap(Maximized),
+        (None, Down) => SnapAction::Minimize,
+
+        (Some(LeftHalf), Left) | (Some(RightHalf), Right) => SnapAction::Restore,
+        (Some(LeftHalf), Right) | (Some(RightHalf), Left) => SnapAction::Restore,
+        (Some(LeftHalf), Up) => SnapAction::Snap(TopLeft),
+        (Some(LeftHalf), Down) => SnapAction::Snap(BottomLeft),
+        (Some(RightHalf), Up) => SnapAction::Snap(TopRight),
+        (Some(RightHalf), Down) => SnapAction::Snap(BottomRight),
+
+        (Some(TopLeft), Down) | (Some(BottomLeft), Up) => SnapAction::Snap(LeftHalf),
+        (Some(TopRight), Down) | (Some(BottomRight), Up) => SnapAction::Snap(RightHalf),
+        (Some(TopLeft), Right) => SnapAction::Snap(TopRight),
+        (Some(TopRight), Left) => SnapAction::Snap(TopLeft),
+        (Some(BottomLeft), Right) => SnapAction::Snap(BottomRight),
+        (Some(BottomRight), Left) => SnapAction::Snap(BottomLeft),
+        (Some(TopLeft | TopRight), Up) => SnapAction::Snap(Maximized),
+        (Some(BottomLeft | BottomRight), Down) => SnapAction::Minimize,
+        (Some(TopLeft | BottomLeft), Left) | (Some(TopRight | BottomRight), Right) => SnapAction::Restore,
+
+        (Some(Maximized), Down) => SnapAction::Restore,
+        (Some(Maximized), Left) => SnapAction::Snap(LeftHalf),
+        (Some(Maximized), Right) => SnapAction::Snap(RightHalf),
+        (Some(Maximized), Up) => SnapAction::Snap(Maximized),
+    }
+}
+
+/// Zone under the pointer while dragging (corners win over edges)
+pub fn zone_at_pointer(px: i32, py: i32, screen_width: u32, screen_height: u32, margin: i32) -> Option<SnapZone> {
+    let left = px <= margin;
+    let right = px >= screen_width as i32 - margin;
+    let top = py <= margin;
+    let bottom = py >= screen_height as i32 - margin;
+
+    match (left, right, top, bottom) {
+        (true, _, true, _) => Some(SnapZone::TopLeft),
+        (_, true, true, _) => Some(SnapZone::TopRight),
+        (true, _, _, true) => Some(SnapZone::BottomLeft),
+        (_, true, _, true) => Some(SnapZone::BottomRight),
+        (true, _, _, _) => Some(SnapZone::LeftHalf),
+        (_, true, _, _) => Some(SnapZone::RightHalf),
+        (_, _, true, _) => Some(SnapZone::Maximized),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// EDGE RESISTANCE
+// ============================================================================
+
+/// Pull `value` onto the closest candidate edge within `threshold`
+fn attract(value: i32, candidates: &[i32], threshold: i32) -> Option<i32> {
+    candidates
+        .iter()
+        .map(|c| (c - value, *c))
+        .filter(|(d, _)| d.abs() <= threshold)
+        .min_by_key(|(d, _)| d.abs())
+        .map(|(_, c)| c)
+}
+
+/// Snap a moved geometry to screen edges and neighbouring window edges
+pub fn apply_resistance(
+    geometry: WindowGeometry,
+    screen_width: u32,
+    screen_height: u32,
+    others: &[WindowGeometry],
+    threshold: i32,
+) -> WindowGeometry {
+    let mut xs = vec![0, screen_width as i32];
+    let mut ys = vec![0, screen_height as i32];
+    for o in others {
+        xs.extend([o.x, o.x + o.width as i32]);
+        ys.extend([o.y, o.y + o.height as i32]);
+    }
+
+    let mut out = geometry;
+    let (w, h) = (geometry.width as i32, geometry.height as i32);
+
+    if let Some(x) = attract(geometry.x, &xs, threshold) {
+        out.x = x;
+    } else if let Some(right) = attract(geometry.x + w, &xs, threshold) {
+        out.x = right - w;
+    }
+
+    if let Some(y) = attract(geometry.y, &ys, threshold) {
+        out.y = y;
+    } else if let Some(bottom) = attract(geometry.y + h, &ys, threshold) {
+        out.y = bottom - h;
+    }
+
+    out
+}
+
+// ============================================================================
+// INTERACTIVE GRABS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeEdge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Edge a Super+right-drag resizes: the part of the window the pointer is
+/// in (outer thirds), bottom-right from the middle
+pub fn resize_edge_at(geometry: WindowGeometry, px: i32, py: i32) -> ResizeEdge {
+    let third_w = geometry.width as i32 / 3;
+    let third_h = geometry.height as i32 / 3;
+    let left = px < geometry.x + third_w;
+    let right = px >= geometry.x + geometry.width as i32 - third_w;
+    let top = py < geometry.y + third_h;
+    let bottom = py >= geometry.y + geometry.height as i32 - third_h;
+
+    match (left, right, top, bottom) {
+        (true, _, true, _) => ResizeEdge::TopLeft,
+        (true, _, _, true) => ResizeEdge::BottomLeft,
+        (_, true, true, _) => ResizeEdge::TopRight,
+        (true, _, _, _) => ResizeEdge::Left,
+        (_, true, false, false) => ResizeEdge::Right,
+        (_, _, true, _) => ResizeEdge::Top,
+        (false, false, _, true) => ResizeEdge::Bottom,
+        _ => ResizeEdge::BottomRight,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum GrabKind {
+    Move,
+    Resize(ResizeEdge),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Grab {
+    window_id: u64,
+    kind: GrabKind,
+    pointer: (i32, i32),
+    start: WindowGeometry,
+}
+
+fn resize_geometry(start: WindowGeometry, edge: ResizeEdge, dx: i32, dy: i32) -> WindowGeometry {
+    let (mut x, mut y) = (start.x, start.y);
+    let (mut w, mut h) = (start.width as i32, start.height as i32);
+    let min = MIN_SIZE as i32;
+
+    let (left, right, top, bottom) = match edge {
+        ResizeEdge::Left => (true, false, false, false),
+        ResizeEdge::Right => (false, true, false, false),
+        ResizeEdge::Top => (false, false, true, false),
+        ResizeEdge::Bottom => (false, false, false, true),
+        ResizeEdge::TopLeft => (true, false, true, false),
+        ResizeEdge::TopRight => (false, true, true, false),
+        ResizeEdge::BottomLeft => (true, false, false, true),
+        ResizeEdge::BottomRight => (false, true, false, true),
+    };
+
+    if left {
+        let dx = dx.min(w - min);
+        x += dx;
+        w -= dx;
+    }
+    if right {
+        w = (w + dx).max(min);
+    }
+    if top {
+        let dy = dy.min(h - min);
+        y += dy;
+        h -= dy;
+    }
+    if bottom {
+        h = (h + dy).max(min);
+    }
+
+    WindowGeometry { x, y, width: w as u32, height: h as u32 }
+}
+
+// ============================================================================
+// ANIMATION
+// ============================================================================
+
+#[derive(Debug, Clone, Copy)]
+pub struct GeometryAnimation {
+    pub from: WindowGeometry,
+    pub to: WindowGeometry,
+    pub started: Instant,
+    pub duration: Duration,
+}
+
+impl GeometryAnimation {
+    /// Interpolated geometry (ease-out cubic) and whether the animation finished
+    pub fn sample(&self, now: Instant) -> (WindowGeometry, bool) {
+        if self.duration.is_zero() {
+            return (self.to, true);
+        }
+
+        let t = (now.saturating_duration_since(self.started).as_secs_f32() / self.duration.as_secs_f32()).min(1.0);
+        let eased = 1.0 - (1.0 - t).powi(3);
+        let lerp = |a: i64, b: i64| a + ((b - a) as f32 * eased).round() as i64;
+
+        let geometry = WindowGeometry {
+            x: lerp(self.from.x as i64, self.to.x as i64) as i32,
+            y: lerp(self.from.y as i64, self.to.y as i64) as i32,
+            width: lerp(self.from.width as i64, self.to.width as i64) as u32,
+            height: lerp(self.from.height as i64, self.to.height as i64) as u32,
+        };
+        (geometry, t >= 1.0)
+    }
+}
+
+// ============================================================================
+// SNAPPER
+// ============================================================================
+
+pub struct WindowSnapper {
+    handler: Arc<WindowHandler>,
+    screen_width: u32,
+    screen_height: u32,
+    resistance: i32,
+    animation_duration: Duration,
+    // window_id -> (zone, geometry to restore)
+    snapped: HashMap<u64, (SnapZone, WindowGeometry)>,
+    animations: HashMap<u64, GeometryAnimation>,
+    grab: Option<Grab>,
+    // window_id -> multitary stream
+    streams: HashMap<u64, u8>,
+}
+
+impl WindowSnapper {
+    pub fn new(handler: Arc<WindowHandler>, screen_width: u32, screen_height: u32) -> Self {
+        Self {
+            handler,
+            screen_width,
+            screen_height,
+            resistance: DEFAULT_RESISTANCE,
+            animation_duration: wbackend::theme::animation_duration(DEFAULT_ANIMATION),
+            snapped: HashMap::new(),
+            animations: HashMap::new(),
+            grab: None,
+            streams: HashMap::new(),
+        }
+    }
+
+    pub fn set_screen(&mut self, width: u32, height: u32) {
+        self.screen_width = width;
+        self.screen_height = height;
+
+        // Re-apply zones on the new screen
+        let zones: Vec<(u64, SnapZone)> = self.snapped.iter().map(|(id, (z, _))| (*id, *z)).collect();
+        for (id, zone) in zones {
+            self.handler.set_geometry(id, zone.geometry(width, height)).ok();
+        }
+    }
+
+    pub fn set_resistance(&mut self, pixels: i32) {
+        self.resistance = pixels.max(0);
+    }
+
+    /// Zero disables animations (geometry jumps to the target)
+    pub fn set_animation_duration(&mut self, duration: Duration) {
+        self.animation_duration = duration;
+    }
+
+    /// Mirror this window's geometry into a multitary viewport
+    pub fn bind_stream(&mut self, window_id: u64, stream_id: u8) {
+        self.streams.insert(window_id, stream_id);
+    }
+
+    pub fn unbind_window(&mut self, window_id: u64) {
+        self.streams.remove(&window_id);
+        self.snapped.remove(&window_id);
+        self.animations.remove(&window_id);
+        if self.grab.is_some_and(|g| g.window_id == window_id) {
+            self.grab = None;
+        }
+    }
+
+    /// Window whose geometry drives this multitary stream
+    pub fn window_for_stream(&self, stream_id: u8) -> Option<u64> {
+        self.streams.iter().find(|(_, s)| **s == stream_id).map(|(id, _)| *id)
+    }
+
+    pub fn stream_for_window(&self, window_id: u64) -> Option<u8> {
+        self.streams.get(&window_id).copied()
+    }
+
+    /// Window being moved or resized by the pointer, and whether it is a resize
+    pub fn grabbed(&self) -> Option<(u64, bool)> {
+        self.grab.map(|g| (g.window_id, matches!(g.kind, GrabKind::Resize(_))))
+    }
+
+    pub fn zone_of(&self, window_id: u64) -> Option<SnapZone> {
+        self.snapped.get(&window_id).map(|(z, _)| *z)
+    }
+
+    /// Super+Arrow
+    pub fn tile(&mut self, window_id: u64, direction: SnapDirection) -> Result<(), String> {
+        let window = self.handler.get_window(window_id)
+            .ok_or_else(|| crate::t!("error-window-not-found", id = window_id))?;
+
+        match next_action(self.zone_of(window_id), direction) {
+            SnapAction::Snap(zone) => self.snap(window_id, zone),
+            SnapAction::Restore => self.restore(window_id),
+            SnapAction::Minimize => {
+                self.handler.set_window_state(window.id, WindowState::Minimized)
+            }
+        }
+    }
+
+    pub fn snap(&mut self, window_id: u64, zone: SnapZone) -> Result<(), String> {
+        let window = self.handler.get_window(window_id)
+            .ok_or_else(|| crate::t!("error-window-not-found", id = window_id))?;
+
+        // Keep the original floating geometry across zone changes
+        let restore = self.snapped.get(&window_id).map(|(_, g)| *g).unwrap_or(window.geometry);
+        self.snapped.insert(window_id, (zone, restore));
+
+        let state = if zone == SnapZone::Maximized { WindowState::Maximized } else { WindowState::Normal };
+        self.handler.set_window_state(window_id, state)?;
+        self.animate_to(window_id, zone.geometry(self.screen_width, self.screen_height))
+    }
+
+    pub fn restore(&mut self, window_id: u64) -> Result<(), String> {
+        let Some((_, geometry)) = self.snapped.remove(&window_id) else {
+            return Ok(());
+        };
+        self.handler.set_window_state(window_id, WindowState::Normal)?;
+        self.animate_to(window_id, geometry)
+    }
+
+    pub fn animate_to(&mut self, window_id: u64, target: WindowGeometry) -> Result<(), String> {
+        let current = self.animations.get(&window_id)
+            .map(|a| a.sample(Instant::now()).0)
+            .or_else(|| self.handler.get_window(window_id).map(|w| w.geometry))
+            .ok_or_else(|| crate::t!("error-window-not-found", id = window_id))?;
+
+        if self.animation_duration.is_zero() {
+            self.animations.remove(&window_id);
+            return self.handler.set_geometry(window_id, target);
+        }
+
+        self.animations.insert(window_id, GeometryAnimation {
+            from: current,
+            to: target,
+            started: Instant::now(),
+            duration: self.animation_duration,
+        });
+        Ok(())
+    }
+
+    pub fn is_animating(&self) -> bool {
+        !self.animations.is_empty()
+    }
+
+    /// Advance animations; returns true while any is still running
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let mut finished = Vec::new();
+        for (id, anim) in &self.animations {
+            let (geometry, done) = anim.sample(now);
+            if self.handler.set_geometry(*id, geometry).is_err() || done {
+                finished.push(*id);
+            }
+        }
+        for id in finished {
+            self.animations.remove(&id);
+        }
+        self.is_animating()
+    }
+
+    pub fn begin_move(&mut self, window_id: u64, px: i32, py: i32) -> Result<(), String> {
+        self.begin(window_id, GrabKind::Move, px, py)
+    }
+
+    pub fn begin_resize(&mut self, window_id: u64, edge: ResizeEdge, px: i32, py: i32) -> Result<(), String> {
+        self.begin(window_id, GrabKind::Resize(edge), px, py)
+    }
+
+    /// Resize from the edge or corner nearest to the pointer
+    pub fn begin_resize_at(&mut self, window_id: u64, px: i32, py: i32) -> Result<(), String> {
+        let window = self.handler.get_window(window_id)
+            .ok_or_else(|| crate::t!("error-window-not-found", id = window_id))?;
+        self.begin_resize(window_id, resize_edge_at(window.geometry, px, py), px, py)
+    }
+
+    fn begin(&mut self, window_id: u64, kind: GrabKind, px: i32, py: i32) -> Result<(), String> {
+        let window = self.handler.get_window(window_id)
+            .ok_or_else(|| crate::t!("error-window-not-found", id = window_id))?;
+        self.animations.remove(&window_id);
+
+        // Dragging a snapped window pulls it back to its floating size under the pointer
+        let mut start = window.geometry;
+        if matches!(kind, GrabKind::Move) {
+            if let Some((_, restore)) = self.snapped.remove(&window_id) {
+                let rel = (px - start.x) as f32 / start.width.max(1) as f32;
+                start = WindowGeometry {
+                    x: px - (rel * restore.width as f32) as i32,
+                    y: start.y,
+                    width: restore.width,
+                    height: restore.height,
+                };
+                self.handler.set_window_state(window_id, WindowState::Normal)?;
+                self.handler.set_geometry(window_id, start)?;
+            }
+        }
+
+        self.grab = Some(Grab { window_id, kind, pointer: (px, py), start });
+        Ok(())
+    }
+
+    /// Pointer motion during a grab
+    pub fn motion(&mut self, px: i32, py: i32) -> Result<(), String> {
+        let Some(grab) = self.grab else {
+            return Ok(());
+        };
+        let (dx, dy) = (px - grab.pointer.0, py - grab.pointer.1);
+
+        let geometry = match grab.kind {
+            GrabKind::Move => {
+                let moved = WindowGeometry { x: grab.start.x + dx, y: grab.start.y + dy, ..grab.start };
+                let others: Vec<WindowGeometry> = self.handler.list_windows()
+                    .into_iter()
+                    .filter(|w| w.id != grab.window_id && w.visible && w.state != WindowState::Minimized)
+                    .map(|w| w.geometry)
+                    .collect();
+                apply_resistance(moved, self.screen_width, self.screen_height, &others, self.resistance)
+            }
+            GrabKind::Resize(edge) => resize_geometry(grab.start, edge, dx, dy),
+        };
+
+        self.handler.set_geometry(grab.window_id, geometry)
+    }
+
+    /// Release: dropping a moved window on a screen edge snaps it
+    pub fn end_grab(&mut self, px: i32, py: i32) -> Result<(), String> {
+        let Some(grab) = self.grab.take() else {
+            return Ok(());
+        };
+
+        if matches!(grab.kind, GrabKind::Move) {
+            let margin = self.resistance.max(1);
+            if let Some(zone) = zone_at_pointer(px, py, self.screen_width, self.screen_height, margin) {
+                return self.snap(grab.window_id, zone);
+            }
+        } else {
+            // A manual resize leaves the zone
+            self.snapped.remove(&grab.window_id);
+        }
+        Ok(())
+    }
+
+    /// Drop the grab where the window is, without snapping
+    pub fn cancel_grab(&mut self) {
+        self.grab = None;
+    }
+
+    /// Copy window geometry of bound streams into the multitary viewports
+    pub fn sync_viewports(&self, multitary: &mut WindowMultitary) {
+        for (window_id, stream_id) in &self.streams {
+            if let Some(window) = self.handler.get_window(*window_id) {
+                let g = window.geometry;
+                multitary.set_floating(*stream_id, g.x, g.y, g.width, g.height);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wbackend::ResourceMode;
+
+    #[test]
+    fn test_keyboard_tiling_sequence() {
+        assert_eq!(next_action(None, SnapDirection::Left), SnapAction::Snap(SnapZone::LeftHalf));
+        assert_eq!(next_action(Some(SnapZone::LeftHalf), SnapDirection::Up), SnapAction::Snap(SnapZone::TopLeft));
+        assert_eq!(next_action(Some(SnapZone::TopLeft), SnapDirection::Right), SnapAction::Snap(SnapZone::TopRight));
+        assert_eq!(next_action(Some(SnapZone::TopRight), SnapDirection::Down), SnapAction::Snap(SnapZone::RightHalf));
+        assert_eq!(next_action(Some(SnapZone::Maximized), SnapDirection::Down), SnapAction::Restore);
+
+        let g = SnapZone::BottomRight.geometry(1921, 1080);
+        assert_eq!((g.x, g.y, g.width, g.height), (960, 540, 961, 540));
+    }
+
+    #[test]
+    fn test_edge_resistance() {
+        let moved = WindowGeometry { x: 10, y: 300, width: 400, height: 300 };
+        let other = WindowGeometry { x: 900, y: 0, width: 400, height: 400 };
+
+        let out = apply_resistance(moved, 1920, 1080, &[other], DEFAULT_RESISTANCE);
+        assert_eq!(out.x, 0);
+        assert_eq!(out.y, 300);
+
+        // Right edge attracted to the neighbour's left edge
+        let near = WindowGeometry { x: 490, y: 500, width: 400, height: 300 };
+        assert_eq!(apply_resistance(near, 1920, 1080, &[other], DEFAULT_RESISTANCE).x, 500);
+
+        assert_eq!(zone_at_pointer(0, 0, 1920, 1080, 16), Some(SnapZone::TopLeft));
+        assert_eq!(zone_at_pointer(1919, 500, 1920, 1080, 16), Some(SnapZone::RightHalf));
+        assert_eq!(zone_at_pointer(900, 500, 1920, 1080, 16), None);
+
+        let g = WindowGeometry { x: 100, y: 100, width: 300, height: 300 };
+        assert_eq!(resize_edge_at(g, 110, 110), ResizeEdge::TopLeft);
+        assert_eq!(resize_edge_at(g, 390, 250), ResizeEdge::Right);
+        assert_eq!(resize_edge_at(g, 250, 390), ResizeEdge::Bottom);
+        assert_eq!(resize_edge_at(g, 250, 250), ResizeEdge::BottomRight);
+    }
+
+    #[test]
+    fn test_animation_interpolates() {
+        let anim = GeometryAnimation {
+            from: WindowGeometry { x: 0, y: 0, width: 100, height: 100 },
+            to: WindowGeometry { x: 100, y: 0, width: 200, height: 100 },
+            started: Instant::now(),
+            duration: Duration::from_millis(100),
+        };
+        let (mid, done) = anim.sample(anim.started + Duration::from_millis(50));
+        assert!(!done);
+        assert!(mid.x > 50 && mid.x < 100);
+        let (end, done) = anim.sample(anim.started + Duration::from_millis(200));
+        assert!(done);
+        assert_eq!((end.x, end.width), (100, 200));
+    }
+
+    #[test]
+    fn test_snap_and_restore_updates_geometry() {
+        let handler = Arc::new(WindowHandler::new(ResourceMode::Manual));
+        let id = handler.create_window(
+            "Snap".to_string(),
+            "test.snap".to_string(),
+            WindowGeometry { x: 100, y: 100, width: 640, height: 480 },
+            None,
+            ResourceMode::Manual,
+        ).unwrap();
+
+        let mut snapper = WindowSnapper::new(handler.clone(), 1920, 1080);
+        snapper.set_animation_duration(Duration::ZERO);
+
+        snapper.tile(id, SnapDirection::Left).unwrap();
+        let g = handler.get_window(id).unwrap().geometry;
+        assert_eq!((g.x, g.y, g.width, g.height), (0, 0, 960, 1080));
+
+        snapper.tile(id, SnapDirection::Up).unwrap();
+        assert_eq!(snapper.zone_of(id), Some(SnapZone::TopLeft));
+
+        snapper.restore(id).unwrap();
+        let g = handler.get_window(id).unwrap().geometry;
+        assert_eq!((g.x, g.y, g.width, g.height), (100, 100, 640, 480));
+    }
+}
@@ -0,0 +1,116 @@
+// WASMA - Workspaces
+// Virtual desktops: every window belongs to one numbered workspace and only
+// the windows of the active workspace are visible. Unmanaged windows
+// (tooltips, menus, notifications, splash screens) are sticky and stay
+// visible on every workspace. Assignments are saved with the session.
+
+use serde::{Deserialize, Serialize};
+
+use crate::window_handling::Window;
+use crate::window_placement::TypeBehavior;
+
+/// Workspaces the handler offers, numbered from 1
+pub const WORKSPACE_COUNT: u32 = 4;
+/// Where new windows and sessions saved before workspaces existed start
+pub const DEFAULT_WORKSPACE: u32 = 1;
+
+pub fn default_workspace() -> u32 {
+    DEFAULT_WORKSPACE
+}
+
+/// One virtual desktop and the windows on it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub number: u32,
+    pub active: bool,
+    pub windows: Vec<u64>,
+}
+
+/// `number` when it names an existing workspace
+pub fn validate(number: u32) -> Result<u32, String> {
+    if (1..=WORKSPACE_COUNT).contains(&number) {
+        Ok(number)
+    } else {
+        Err(format!("Workspace {} does not exist (1-{})", number, WORKSPACE_COUNT))
+    }
+}
+
+/// Shown on every workspace
+pub fn is_sticky(window: &Window) -> bool {
+    !TypeBehavior::of(&window.window_type).managed
+}
+
+/// Whether `window` is visible while `active` is the current workspace
+pub fn visible_on(window: &Window, active: u32) -> bool {
+    window.workspace == active || is_sticky(window)
+}
+
+/// Every workspace with its windows (ids ascending); sticky windows are
+/// listed on the workspace they were opened on
+pub fn list(windows: &[Window], active: u32) -> Vec<Workspace> {
+    (1..=WORKSPACE_COUNT)
+        .map(|number| {
+            let mut ids: Vec<u64> = windows.iter().filter(|w| w.workspace == number).map(|w| w.id).collect();
+            ids.sort_unstable();
+            Workspace { number, active: number == active, windows: ids }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window_handling::{WindowEvent, WindowGeometry, WindowHandler, WindowType};
+    use wbackend::ResourceMode;
+
+    #[test]
+    fn test_switch_and_move() {
+        let handler = WindowHandler::new(ResourceMode::Manual);
+        let geometry = WindowGeometry { x: 0, y: 0, width: 640, height: 480 };
+        let create = |title: &str| handler.create_window(title.into(), "test.workspace".into(), geometry, None, ResourceMode::Manual).unwrap();
+
+        let editor = create("Editor");
+        let dialog = create("Find");
+        handler.set_parent(dialog, editor).unwrap();
+        let terminal = create("Terminal");
+        let tooltip = create("Tip");
+        handler.set_window_type(tooltip, WindowType::Tooltip).unwrap();
+        handler.focus_window(editor).unwrap();
+        handler.focus_window(terminal).unwrap();
+        let events = handler.subscribe();
+
+        // Children follow their parent; sticky windows are shown everywhere
+        handler.move_to_workspace(editor, 2).unwrap();
+        assert_eq!(handler.get_window(dialog).unwrap().workspace, 2);
+        assert!(!handler.get_window(editor).unwrap().visible);
+        handler.switch_workspace(2).unwrap();
+        assert!(handler.get_window(editor).unwrap().visible);
+        assert!(!handler.get_window(terminal).unwrap().visible);
+        assert!(handler.get_window(tooltip).unwrap().visible);
+        assert_eq!(handler.get_focused_window(), Some(editor));
+        assert_eq!(events.try_iter().last(), Some(WindowEvent::WorkspaceChanged { previous: 1, current: 2 }));
+
+        let listed = handler.list_workspaces();
+        assert_eq!(listed.len(), WORKSPACE_COUNT as usize);
+        assert_eq!(listed[1], Workspace { number: 2, active: true, windows: vec![editor, dialog] });
+        assert!(handler.switch_workspace(0).is_err());
+        assert!(handler.move_to_workspace(editor, WORKSPACE_COUNT + 1).is_err());
+
+        // Focusing a window elsewhere brings its workspace up; new windows open there
+        handler.focus_window(terminal).unwrap();
+        assert_eq!(handler.active_workspace(), 1);
+        assert_eq!(handler.get_window(create("Notes")).unwrap().workspace, 1);
+
+        // Assignments and the active workspace survive a session round trip
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        handler.switch_workspace(2).unwrap();
+        handler.save_session(&path).unwrap();
+        let next = WindowHandler::new(ResourceMode::Manual);
+        let restored = next.restore_session(&path).unwrap();
+        assert_eq!(next.active_workspace(), 2);
+        let windows: Vec<_> = restored.iter().map(|id| next.get_window(*id).unwrap()).collect();
+        assert_eq!(windows.iter().filter(|w| w.workspace == 2).count(), 2);
+        assert!(windows.iter().all(|w| w.visible == visible_on(w, 2)));
+    }
+}
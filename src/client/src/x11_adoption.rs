@@ -0,0 +1,589 @@
+// WASMA - X11 Window Adoption
+// Captures existing X11 client windows (XComposite redirection + reparenting),
+// wraps each one in a WASMA Window with its own assignment and tiles it
+// through the WindowMultitary layout – drop-in resource-aware WM for legacy apps.
+// Top-levels are redirected manually: WASMA composites their contents
+// (XDamage + XRender) onto the composite overlay window itself
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use wbackend::ResourceMode;
+use x11rb::connection::Connection;
+use x11rb::protocol::composite::{ConnectionExt as CompositeExt, Redirect};
+use x11rb::protocol::damage::{self, ConnectionExt as DamageExt};
+use x11rb::protocol::render::{self, ConnectionExt as RenderExt};
+use x11rb::protocol::shape;
+use x11rb::protocol::xfixes::ConnectionExt as XfixesExt;
+use x11rb::protocol::xproto::{self, ConnectionExt as XprotoExt};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+
+use crate::parser::WasmaConfig;
+use crate::window_handling::{BackendType, WindowGeometry, WindowHandler};
+use crate::window_multitary::WindowMultitary;
+
+/// Adopted clients get stream ids from this base upwards so they never
+/// collide with protocol streams (0..protocol_count)
+pub const ADOPTED_STREAM_BASE: u8 = 128;
+
+x11rb::atom_manager! {
+    pub AdoptionAtoms: AdoptionAtomsCookie {
+        _NET_WM_NAME,
+        _NET_WM_PID,
+        UTF8_STRING,
+    }
+}
+
+/// An X11 client window captured by WASMA
+#[derive(Debug, Clone)]
+pub struct AdoptedClient {
+    pub xid: xproto::Window,
+    pub frame: xproto::Window,
+    pub window_id: u64,
+    pub stream_id: u8,
+    pub title: String,
+    pub wm_class: String,
+}
+
+/// Where composited frames are painted: a back buffer copied onto the
+/// composite overlay window in one go
+struct Scene {
+    overlay: xproto::Window,
+    target: render::Picture,
+    back: xproto::Pixmap,
+    back_picture: render::Picture,
+}
+
+/// X11 Adopter - reparenting, compositing window manager mode
+pub struct X11Adopter {
+    conn: RustConnection,
+    root: xproto::Window,
+    root_depth: u8,
+    root_visual: xproto::Visualid,
+    screen_size: (u16, u16),
+    atoms: AdoptionAtoms,
+    handler: Arc<WindowHandler>,
+    multitary: WindowMultitary,
+    resource_mode: ResourceMode,
+    adopted: HashMap<xproto::Window, AdoptedClient>,
+    /// XRender picture format of every visual on the display
+    formats: HashMap<xproto::Visualid, render::Pictformat>,
+    /// Damage object of every top-level being composited
+    damage: HashMap<xproto::Window, damage::Damage>,
+    scene: Option<Scene>,
+    dirty: bool,
+}
+
+impl X11Adopter {
+    /// Connect to the X server and prepare XComposite redirection
+    pub fn connect(
+        config: WasmaConfig,
+        handler: Arc<WindowHandler>,
+        resource_mode: ResourceMode,
+    ) -> Result<Self, String> {
+        let (conn, screen_num) = x11rb::connect(None)
+            .map_err(|e| format!("X11 connection failed: {}", e))?;
+
+        let screen = &conn.setup().roots[screen_num];
+        let (root, root_depth, root_visual) = (screen.root, screen.root_depth, screen.root_visual);
+        let screen_size = (screen.width_in_pixels, screen.height_in_pixels);
+
+        conn.composite_query_version(0, 4)
+            .map_err(|e| format!("XComposite unavailable: {}", e))?
+            .reply()
+            .map_err(|e| format!("XComposite unavailable: {}", e))?;
+        // XFixes has to be negotiated before XDamage, which reports through its regions
+        conn.xfixes_query_version(5, 0)
+            .map_err(|e| format!("XFixes unavailable: {}", e))?
+            .reply()
+            .map_err(|e| format!("XFixes unavailable: {}", e))?;
+        conn.damage_query_version(1, 1)
+            .map_err(|e| format!("XDamage unavailable: {}", e))?
+            .reply()
+            .map_err(|e| format!("XDamage unavailable: {}", e))?;
+        let pict_formats = conn.render_query_pict_formats()
+            .map_err(|e| format!("XRender unavailable: {}", e))?
+            .reply()
+            .map_err(|e| format!("XRender unavailable: {}", e))?;
+        let formats = pict_formats.screens.iter()
+            .flat_map(|screen| &screen.depths)
+            .flat_map(|depth| &depth.visuals)
+            .map(|visual| (visual.visual, visual.format))
+            .collect();
+
+        let atoms = AdoptionAtoms::new(&conn)
+            .map_err(|e| format!("Atom interning failed: {}", e))?
+            .reply()
+            .map_err(|e| format!("Atom interning failed: {}", e))?;
+
+        Ok(Self {
+            conn,
+            root,
+            root_depth,
+            root_visual,
+            screen_size,
+            atoms,
+            handler,
+            multitary: WindowMultitary::new(config, screen_size.0 as u32, screen_size.1 as u32),
+            resource_mode,
+            adopted: HashMap::new(),
+            formats,
+            damage: HashMap::new(),
+            scene: None,
+            dirty: false,
+        })
+    }
+
+    /// Take over window management and compositing on the root window
+    pub fn become_manager(&mut self) -> Result<(), String> {
+        let aux = xproto::ChangeWindowAttributesAux::new().event_mask(
+            xproto::EventMask::SUBSTRUCTURE_REDIRECT | xproto::EventMask::SUBSTRUCTURE_NOTIFY,
+        );
+
+        self.conn
+            .change_window_attributes(self.root, &aux)
+            .map_err(|e| e.to_string())?
+            .check()
+            .map_err(|_| "Another window manager is already running".to_string())?;
+
+        // Offscreen storage for every top-level; only WASMA paints them to the screen
+        self.conn
+            .composite_redirect_subwindows(self.root, Redirect::MANUAL)
+            .map_err(|e| e.to_string())?
+            .check()
+            .map_err(|_| "XComposite redirection failed, another compositor is running".to_string())?;
+
+        self.scene = Some(self.create_scene().map_err(|e| format!("Compositing setup failed: {}", e))?);
+        let children = self.conn.query_tree(self.root).ok().and_then(|c| c.reply().ok())
+            .map(|tree| tree.children)
+            .unwrap_or_default();
+        for window in children {
+            self.track(window);
+        }
+        self.dirty = true;
+
+        println!("🪟 X11 adoption mode active on root 0x{:x}", self.root);
+        Ok(())
+    }
+
+    /// Adopt every mapped, managed top-level window that already exists
+    pub fn adopt_existing(&mut self) -> Vec<u64> {
+        let children = match self.conn.query_tree(self.root).ok().and_then(|c| c.reply().ok()) {
+            Some(tree) => tree.children,
+            None => return Vec::new(),
+        };
+
+        let mut adopted = Vec::new();
+        for xid in children {
+            let attrs = match self.conn.get_window_attributes(xid).ok().and_then(|c| c.reply().ok()) {
+                Some(a) => a,
+                None => continue,
+            };
+
+            if attrs.override_redirect || attrs.map_state != xproto::MapState::VIEWABLE {
+                continue;
+            }
+
+            match self.adopt(xid) {
+                Ok(window_id) => adopted.push(window_id),
+                Err(e) => eprintln!("⚠️  Could not adopt 0x{:x}: {}", xid, e),
+            }
+        }
+
+        adopted
+    }
+
+    /// Adopt a single X11 client: WASMA Window + assignment + frame + viewport
+    pub fn adopt(&mut self, xid: xproto::Window) -> Result<u64, String> {
+        if self.adopted.contains_key(&xid) {
+            return Err(format!("Window 0x{:x} already adopted", xid));
+        }
+
+        let stream_id = allocate_stream_id(self.adopted.values().map(|c| c.stream_id))
+            .ok_or_else(|| "No free stream slots for adoption".to_string())?;
+
+        let geo = self.conn.get_geometry(xid)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+
+        let title = self.read_title(xid);
+        let wm_class = self.read_wm_class(xid);
+
+        let geometry = WindowGeometry {
+            x: geo.x as i32,
+            y: geo.y as i32,
+            width: geo.width as u32,
+            height: geo.height as u32,
+        };
+
+        let window_id = self.handler.create_window(
+            title.clone(),
+            wm_class.clone(),
+            geometry,
+            None,
+            self.resource_mode,
+        )?;
+        self.handler.set_backend_type(window_id, BackendType::X11)?;
+        if let Some(pid) = self.read_pid(xid) {
+            self.handler.set_window_icon_from_pid(window_id, pid).ok();
+        }
+
+        let frame = self.create_frame(xid, geometry)?;
+        // The frame is the composited top-level from now on
+        if let Some(damage) = self.damage.remove(&xid) {
+            self.conn.damage_destroy(damage).ok();
+        }
+
+        self.adopted.insert(xid, AdoptedClient {
+            xid,
+            frame,
+            window_id,
+            stream_id,
+            title,
+            wm_class,
+        });
+        self.multitary.attach_stream(stream_id);
+        self.apply_layout();
+
+        println!("📥 Adopted X11 window 0x{:x} → Window {} | Stream {}", xid, window_id, stream_id);
+        Ok(window_id)
+    }
+
+    /// Hand a client back to the root window and close its WASMA Window
+    pub fn release(&mut self, xid: xproto::Window, destroyed: bool) -> Result<(), String> {
+        let client = self.adopted.remove(&xid)
+            .ok_or_else(|| format!("Window 0x{:x} is not adopted", xid))?;
+
+        if !destroyed {
+            self.conn.reparent_window(xid, self.root, 0, 0).ok();
+            self.conn.change_save_set(xproto::SetMode::DELETE, xid).ok();
+        }
+        self.conn.destroy_window(client.frame).ok();
+        self.conn.flush().ok();
+
+        self.multitary.detach_stream(client.stream_id);
+        self.handler.close_window(client.window_id)?;
+        self.apply_layout();
+
+        println!("📤 Released X11 window 0x{:x} (Window {})", xid, client.window_id);
+        Ok(())
+    }
+
+    /// Release every adopted client and hand the screen back (shutdown path)
+    pub fn release_all(&mut self) {
+        let xids: Vec<xproto::Window> = self.adopted.keys().cloned().collect();
+        for xid in xids {
+            self.release(xid, false).ok();
+        }
+        if let Some(scene) = self.scene.take() {
+            self.conn.render_free_picture(scene.back_picture).ok();
+            self.conn.render_free_picture(scene.target).ok();
+            self.conn.free_pixmap(scene.back).ok();
+            self.conn.composite_release_overlay_window(self.root).ok();
+            self.conn.composite_unredirect_subwindows(self.root, Redirect::MANUAL).ok();
+            self.conn.flush().ok();
+        }
+    }
+
+    /// Name the offscreen pixmap holding a redirected top-level's current
+    /// contents; the caller frees it
+    pub fn capture_pixmap(&self, xid: xproto::Window) -> Result<xproto::Pixmap, String> {
+        let pixmap = self.conn.generate_id().map_err(|e| e.to_string())?;
+        self.conn.composite_name_window_pixmap(xid, pixmap)
+            .map_err(|e| e.to_string())?
+            .check()
+            .map_err(|e| e.to_string())?;
+        Ok(pixmap)
+    }
+
+    /// Push multitary viewport geometry to frames, clients and WASMA Windows
+    pub fn apply_layout(&self) {
+        for client in self.adopted.values() {
+            let vp = match self.multitary.get_viewport_for_stream(client.stream_id) {
+                Some(vp) => vp.clone(),
+                None => continue,
+            };
+
+            let frame_aux = xproto::ConfigureWindowAux::new()
+                .x(vp.x)
+                .y(vp.y)
+                .width(vp.width)
+                .height(vp.height);
+            let client_aux = xproto::ConfigureWindowAux::new()
+                .x(0)
+                .y(0)
+                .width(vp.width)
+                .height(vp.height);
+
+            self.conn.configure_window(client.frame, &frame_aux).ok();
+            self.conn.configure_window(client.xid, &client_aux).ok();
+
+            self.handler.set_geometry(client.window_id, WindowGeometry {
+                x: vp.x,
+                y: vp.y,
+                width: vp.width,
+                height: vp.height,
+            }).ok();
+        }
+        self.conn.flush().ok();
+    }
+
+    /// Adopted clients keyed by X11 window id
+    pub fn adopted_clients(&self) -> Vec<AdoptedClient> {
+        self.adopted.values().cloned().collect()
+    }
+
+    /// Window manager event loop – adopts on MapRequest, releases on destroy/unmap
+    /// and repaints the scene once per burst of damage
+    pub fn run(&mut self) -> Result<(), String> {
+        loop {
+            if self.dirty {
+                self.paint();
+            }
+            let event = self.conn.wait_for_event().map_err(|e| e.to_string())?;
+            self.handle_event(event);
+            while let Some(event) = self.conn.poll_for_event().map_err(|e| e.to_string())? {
+                self.handle_event(event);
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        if matches!(
+            event,
+            Event::MapNotify(_) | Event::UnmapNotify(_) | Event::ConfigureNotify(_)
+                | Event::DestroyNotify(_) | Event::ReparentNotify(_)
+        ) {
+            self.dirty = true;
+        }
+
+        match event {
+            Event::MapRequest(ev) => {
+                if let Err(e) = self.adopt(ev.window) {
+                    eprintln!("⚠️  Adoption failed for 0x{:x}: {}", ev.window, e);
+                    self.conn.map_window(ev.window).ok();
+                    self.conn.flush().ok();
+                }
+            }
+            Event::DestroyNotify(ev) if self.adopted.contains_key(&ev.window) => {
+                self.release(ev.window, true).ok();
+            }
+            Event::UnmapNotify(ev) if self.adopted.contains_key(&ev.window) && ev.event != self.root => {
+                self.release(ev.window, false).ok();
+            }
+            Event::ConfigureRequest(ev) => {
+                if self.adopted.contains_key(&ev.window) {
+                    // Geometry of adopted clients is owned by the multitary layout
+                    self.apply_layout();
+                } else {
+                    let aux = xproto::ConfigureWindowAux::from_configure_request(&ev);
+                    self.conn.configure_window(ev.window, &aux).ok();
+                    self.conn.flush().ok();
+                }
+            }
+            Event::DamageNotify(ev) => {
+                self.conn.damage_subtract(ev.damage, x11rb::NONE, x11rb::NONE).ok();
+                self.dirty = true;
+            }
+            Event::MapNotify(ev) if ev.event == self.root => self.track(ev.window),
+            // Released clients are top-levels again
+            Event::ReparentNotify(ev) if ev.event == self.root && ev.parent == self.root => self.track(ev.window),
+            // The server frees a damage object along with its window
+            Event::DestroyNotify(ev) if ev.event == self.root => {
+                self.damage.remove(&ev.window);
+            }
+            _ => {}
+        }
+    }
+
+    /// Overlay window (input passes through it) plus a screen-sized back buffer
+    fn create_scene(&self) -> Result<Scene, Box<dyn std::error::Error>> {
+        let overlay = self.conn.composite_get_overlay_window(self.root)?.reply()?.overlay_win;
+        let region = self.conn.generate_id()?;
+        self.conn.xfixes_create_region(region, &[])?;
+        self.conn.xfixes_set_window_shape_region(overlay, shape::SK::INPUT, 0, 0, region)?;
+        self.conn.xfixes_destroy_region(region)?;
+
+        let format = *self.formats.get(&self.root_visual).ok_or("no picture format for the root visual")?;
+        let (width, height) = self.screen_size;
+        let target = self.conn.generate_id()?;
+        self.conn.render_create_picture(target, overlay, format, &render::CreatePictureAux::new())?;
+        let back = self.conn.generate_id()?;
+        self.conn.create_pixmap(self.root_depth, back, self.root, width, height)?;
+        let back_picture = self.conn.generate_id()?;
+        self.conn.render_create_picture(back_picture, back, format, &render::CreatePictureAux::new())?.check()?;
+
+        Ok(Scene { overlay, target, back, back_picture })
+    }
+
+    /// Start reporting damage on a redirected top-level
+    fn track(&mut self, window: xproto::Window) {
+        if self.scene.as_ref().map_or(true, |scene| scene.overlay == window) || self.damage.contains_key(&window) {
+            return;
+        }
+        let Ok(damage) = self.conn.generate_id() else {
+            return;
+        };
+        if self.conn.damage_create(damage, window, damage::ReportLevel::NON_EMPTY).is_ok() {
+            self.damage.insert(window, damage);
+        }
+    }
+
+    /// Composite every viewable top-level in stacking order; adopted clients
+    /// sit in frames the multitary layout has already placed
+    fn paint(&mut self) {
+        self.dirty = false;
+        let Some(scene) = &self.scene else {
+            return;
+        };
+        let (width, height) = self.screen_size;
+        let screen = xproto::Rectangle { x: 0, y: 0, width, height };
+        let black = render::Color { red: 0, green: 0, blue: 0, alpha: 0xFFFF };
+        self.conn.render_fill_rectangles(render::PictOp::SRC, scene.back_picture, black, &[screen]).ok();
+
+        let children = self.conn.query_tree(self.root).ok().and_then(|c| c.reply().ok())
+            .map(|tree| tree.children)
+            .unwrap_or_default();
+        for window in children {
+            if window == scene.overlay {
+                continue;
+            }
+            let attrs = match self.conn.get_window_attributes(window).ok().and_then(|c| c.reply().ok()) {
+                Some(attrs) => attrs,
+                None => continue,
+            };
+            if attrs.map_state != xproto::MapState::VIEWABLE || attrs.class == xproto::WindowClass::INPUT_ONLY {
+                continue;
+            }
+            let (Some(&format), Some(geo)) = (
+                self.formats.get(&attrs.visual),
+                self.conn.get_geometry(window).ok().and_then(|c| c.reply().ok()),
+            ) else {
+                continue;
+            };
+            let Ok(pixmap) = self.capture_pixmap(window) else {
+                continue;
+            };
+            if let Ok(picture) = self.conn.generate_id() {
+                let aux = render::CreatePictureAux::new().subwindowmode(xproto::SubwindowMode::INCLUDE_INFERIORS);
+                self.conn.render_create_picture(picture, pixmap, format, &aux).ok();
+                let border = geo.border_width * 2;
+                self.conn.render_composite(
+                    render::PictOp::OVER, picture, x11rb::NONE, scene.back_picture,
+                    0, 0, 0, 0, geo.x, geo.y, geo.width + border, geo.height + border,
+                ).ok();
+                self.conn.render_free_picture(picture).ok();
+            }
+            self.conn.free_pixmap(pixmap).ok();
+        }
+
+        self.conn.render_composite(
+            render::PictOp::SRC, scene.back_picture, x11rb::NONE, scene.target,
+            0, 0, 0, 0, 0, 0, width, height,
+        ).ok();
+        self.conn.flush().ok();
+    }
+
+    fn create_frame(&self, xid: xproto::Window, geometry: WindowGeometry) -> Result<xproto::Window, String> {
+        let frame = self.conn.generate_id().map_err(|e| e.to_string())?;
+        let aux = xproto::CreateWindowAux::new()
+            .event_mask(xproto::EventMask::SUBSTRUCTURE_NOTIFY | xproto::EventMask::SUBSTRUCTURE_REDIRECT);
+
+        self.conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            frame,
+            self.root,
+            geometry.x as i16,
+            geometry.y as i16,
+            geometry.width.max(1) as u16,
+            geometry.height.max(1) as u16,
+            0,
+            xproto::WindowClass::INPUT_OUTPUT,
+            0,
+            &aux,
+        ).map_err(|e| e.to_string())?;
+
+        self.conn.change_save_set(xproto::SetMode::INSERT, xid).map_err(|e| e.to_string())?;
+        self.conn.reparent_window(xid, frame, 0, 0).map_err(|e| e.to_string())?;
+        self.conn.map_window(frame).map_err(|e| e.to_string())?;
+        self.conn.map_window(xid).map_err(|e| e.to_string())?;
+        self.conn.flush().map_err(|e| e.to_string())?;
+
+        Ok(frame)
+    }
+
+    fn read_title(&self, xid: xproto::Window) -> String {
+        let net_name = self.conn
+            .get_property(false, xid, self.atoms._NET_WM_NAME, self.atoms.UTF8_STRING, 0, 1024)
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .map(|r| String::from_utf8_lossy(&r.value).to_string())
+            .filter(|s| !s.is_empty());
+
+        net_name
+            .or_else(|| {
+                self.conn
+                    .get_property(false, xid, xproto::AtomEnum::WM_NAME, xproto::AtomEnum::STRING, 0, 1024)
+                    .ok()
+                    .and_then(|c| c.reply().ok())
+                    .map(|r| String::from_utf8_lossy(&r.value).to_string())
+                    .filter(|s| !s.is_empty())
+            })
+            .unwrap_or_else(|| format!("X11 0x{:x}", xid))
+    }
+
+    fn read_pid(&self, xid: xproto::Window) -> Option<u32> {
+        self.conn
+            .get_property(false, xid, self.atoms._NET_WM_PID, xproto::AtomEnum::CARDINAL, 0, 1)
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .and_then(|r| r.value32().and_then(|mut v| v.next()))
+    }
+
+    fn read_wm_class(&self, xid: xproto::Window) -> String {
+        self.conn
+            .get_property(false, xid, xproto::AtomEnum::WM_CLASS, xproto::AtomEnum::STRING, 0, 256)
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .map(|r| parse_wm_class(&r.value))
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "x11.adopted".to_string())
+    }
+}
+
+/// WM_CLASS is "instance\0class\0" – the class part is the stable app id
+pub fn parse_wm_class(raw: &[u8]) -> String {
+    let parts: Vec<&[u8]> = raw.split(|&b| b == 0).filter(|p| !p.is_empty()).collect();
+    parts.get(1)
+        .or_else(|| parts.first())
+        .map(|p| String::from_utf8_lossy(p).to_string())
+        .unwrap_or_default()
+}
+
+/// First free stream id at or above ADOPTED_STREAM_BASE
+pub fn allocate_stream_id(used: impl Iterator<Item = u8>) -> Option<u8> {
+    let used: Vec<u8> = used.collect();
+    (ADOPTED_STREAM_BASE..=u8::MAX).find(|id| !used.contains(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wm_class() {
+        assert_eq!(parse_wm_class(b"xterm\0XTerm\0"), "XTerm");
+        assert_eq!(parse_wm_class(b"single\0"), "single");
+        assert_eq!(parse_wm_class(b""), "");
+    }
+
+    #[test]
+    fn test_allocate_stream_id() {
+        assert_eq!(allocate_stream_id(std::iter::empty()), Some(ADOPTED_STREAM_BASE));
+        assert_eq!(
+            allocate_stream_id(vec![ADOPTED_STREAM_BASE, ADOPTED_STREAM_BASE + 1].into_iter()),
+            Some(ADOPTED_STREAM_BASE + 2)
+        );
+    }
+}
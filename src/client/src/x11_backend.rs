@@ -0,0 +1,547 @@
+// WASMA - X11 Backend
+// Creates a real X11 top-level for every WASMA Window with BackendType::X11
+// and keeps it in sync both ways: title/app_id, badged icon (_NET_WM_ICON), geometry, state (EWMH
+// _NET_WM_STATE + ICCCM WM_CHANGE_STATE) and focus (_NET_ACTIVE_WINDOW) go to
+// the X server; WM-driven moves, resizes, state changes, focus and
+// WM_DELETE_WINDOW come back to the WindowHandler.
+
+use std::collections::HashMap;
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
+use std::sync::Arc;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{self, ConnectionExt as XprotoExt};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as WrapperExt;
+
+use crate::accessibility;
+use crate::icon_badges::IconBadge;
+use crate::thumbnails::FrameSource;
+use crate::window_handling::{BackendType, Window, WindowGeometry, WindowHandler, WindowState};
+
+/// How long one loop iteration waits for X events before re-syncing
+const POLL_TIMEOUT_MS: i32 = 50;
+
+// _NET_WM_STATE client message actions
+const NET_WM_STATE_REMOVE: u32 = 0;
+const NET_WM_STATE_ADD: u32 = 1;
+// ICCCM WM_STATE value for minimized windows
+const ICONIC_STATE: u32 = 3;
+// Edge of the icon exported to taskbars
+const NET_WM_ICON_SIZE: u32 = 48;
+
+x11rb::atom_manager! {
+    pub X11BackendAtoms: X11BackendAtomsCookie {
+        WM_PROTOCOLS,
+        WM_DELETE_WINDOW,
+        WM_CHANGE_STATE,
+        _NET_WM_NAME,
+        _NET_WM_STATE,
+        _NET_WM_STATE_MAXIMIZED_VERT,
+        _NET_WM_STATE_MAXIMIZED_HORZ,
+        _NET_WM_STATE_FULLSCREEN,
+        _NET_WM_STATE_HIDDEN,
+        _NET_WM_STATE_SKIP_TASKBAR,
+        _NET_WM_STATE_ABOVE,
+        _NET_ACTIVE_WINDOW,
+        _NET_WM_ICON,
+        UTF8_STRING,
+    }
+}
+
+/// EWMH _NET_WM_STATE entries WASMA maps onto WindowState
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetWmState {
+    MaximizedVert,
+    MaximizedHorz,
+    Fullscreen,
+    Hidden,
+}
+
+/// _NET_WM_STATE entries a window in `state` should carry
+pub fn net_wm_states(state: &WindowState) -> Vec<NetWmState> {
+    match state {
+        WindowState::Maximized => vec![NetWmState::MaximizedVert, NetWmState::MaximizedHorz],
+        WindowState::Fullscreen => vec![NetWmState::Fullscreen],
+        WindowState::Minimized | WindowState::Hidden => vec![NetWmState::Hidden],
+        WindowState::Normal => Vec::new(),
+    }
+}
+
+/// WindowState from the window manager's _NET_WM_STATE; maximized only
+/// counts when both axes are set
+pub fn window_state_from_net(states: &[NetWmState]) -> WindowState {
+    if states.contains(&NetWmState::Hidden) {
+        WindowState::Minimized
+    } else if states.contains(&NetWmState::Fullscreen) {
+        WindowState::Fullscreen
+    } else if states.contains(&NetWmState::MaximizedVert) && states.contains(&NetWmState::MaximizedHorz) {
+        WindowState::Maximized
+    } else {
+        WindowState::Normal
+    }
+}
+
+/// A WASMA Window currently backed by an X11 top-level
+#[derive(Debug, Clone)]
+struct PresentedWindow {
+    xid: xproto::Window,
+    title: String,
+    app_id: String,
+    state: WindowState,
+    geometry: WindowGeometry,
+    mapped: bool,
+    focused: bool,
+    // Icon inputs last exported as _NET_WM_ICON
+    icon: (Option<PathBuf>, Vec<IconBadge>),
+}
+
+/// X11 Backend - one X11 top-level per BackendType::X11 window
+pub struct X11Backend {
+    conn: RustConnection,
+    root: xproto::Window,
+    root_visual: xproto::Visualid,
+    atoms: X11BackendAtoms,
+    handler: Arc<WindowHandler>,
+    windows: HashMap<u64, PresentedWindow>,
+    by_xid: HashMap<xproto::Window, u64>,
+}
+
+impl X11Backend {
+    /// Connect to $DISPLAY and intern the ICCCM/EWMH atoms
+    pub fn connect(handler: Arc<WindowHandler>) -> Result<Self, String> {
+        let (conn, screen_num) = x11rb::connect(None)
+            .map_err(|e| format!("X11 connection failed: {}", e))?;
+
+        let screen = &conn.setup().roots[screen_num];
+        let (root, root_visual) = (screen.root, screen.root_visual);
+
+        let atoms = X11BackendAtoms::new(&conn)
+            .map_err(|e| format!("Atom interning failed: {}", e))?
+            .reply()
+            .map_err(|e| format!("Atom interning failed: {}", e))?;
+
+        Ok(Self {
+            conn,
+            root,
+            root_visual,
+            atoms,
+            handler,
+            windows: HashMap::new(),
+            by_xid: HashMap::new(),
+        })
+    }
+
+    /// Connect and run the backend on its own thread
+    pub fn spawn(handler: Arc<WindowHandler>) -> Result<std::thread::JoinHandle<()>, String> {
+        let mut backend = Self::connect(handler)?;
+        Ok(std::thread::spawn(move || {
+            if let Err(e) = backend.run() {
+                eprintln!("❌ X11 backend stopped: {}", e);
+            }
+        }))
+    }
+
+    /// X11 window id backing a WASMA Window
+    pub fn xid_for(&self, window_id: u64) -> Option<xproto::Window> {
+        self.windows.get(&window_id).map(|w| w.xid)
+    }
+
+    /// Mirror handler changes and process X events until the connection drops
+    pub fn run(&mut self) -> Result<(), String> {
+        println!("🪟 X11 backend presenting managed windows");
+        loop {
+            self.sync()?;
+            self.dispatch(POLL_TIMEOUT_MS)?;
+        }
+    }
+
+    /// Create, update and destroy X11 windows so they match the handler
+    pub fn sync(&mut self) -> Result<(), String> {
+        let windows: HashMap<u64, Window> = self.handler.list_windows()
+            .into_iter()
+            .filter(|w| w.backend_type == BackendType::X11)
+            .map(|w| (w.id, w))
+            .collect();
+
+        let gone: Vec<u64> = self.windows.keys()
+            .filter(|id| !windows.contains_key(id))
+            .copied()
+            .collect();
+        for id in gone {
+            self.destroy(id);
+        }
+
+        for window in windows.values() {
+            if self.windows.contains_key(&window.id) {
+                self.update(window)?;
+            } else {
+                self.create(window)?;
+            }
+        }
+
+        self.conn.flush().map_err(|e| e.to_string())
+    }
+
+    /// Wait up to `timeout_ms` for X events and handle them
+    pub fn dispatch(&mut self, timeout_ms: i32) -> Result<usize, String> {
+        let mut handled = 0;
+        let mut waited = false;
+        loop {
+            match self.conn.poll_for_event().map_err(|e| e.to_string())? {
+                Some(event) => {
+                    self.handle_event(event);
+                    handled += 1;
+                }
+                None if waited || handled > 0 => break,
+                None => {
+                    let mut fds = [libc::pollfd {
+                        fd: self.conn.stream().as_raw_fd(),
+                        events: libc::POLLIN,
+                        revents: 0,
+                    }];
+                    unsafe { libc::poll(fds.as_mut_ptr(), 1, timeout_ms) };
+                    waited = true;
+                }
+            }
+        }
+        self.conn.flush().map_err(|e| e.to_string())?;
+        Ok(handled)
+    }
+
+    fn create(&mut self, window: &Window) -> Result<(), String> {
+        let xid = self.conn.generate_id().map_err(|e| e.to_string())?;
+        let surface = accessibility::tokens().surface;
+        let aux = xproto::CreateWindowAux::new()
+            .background_pixel(u32::from_be_bytes([0, surface.r, surface.g, surface.b]))
+            .event_mask(
+                xproto::EventMask::STRUCTURE_NOTIFY
+                    | xproto::EventMask::PROPERTY_CHANGE
+                    | xproto::EventMask::FOCUS_CHANGE,
+            );
+
+        self.conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            xid,
+            self.root,
+            window.geometry.x as i16,
+            window.geometry.y as i16,
+            window.geometry.width.max(1) as u16,
+            window.geometry.height.max(1) as u16,
+            0,
+            xproto::WindowClass::INPUT_OUTPUT,
+            self.root_visual,
+            &aux,
+        ).map_err(|e| e.to_string())?;
+
+        self.set_title(xid, &window.title)?;
+        self.set_app_id(xid, &window.app_id)?;
+        self.set_icon(xid, window.id)?;
+        self.conn.change_property32(
+            xproto::PropMode::REPLACE,
+            xid,
+            self.atoms.WM_PROTOCOLS,
+            xproto::AtomEnum::ATOM,
+            &[self.atoms.WM_DELETE_WINDOW],
+        ).map_err(|e| e.to_string())?;
+
+        // Before mapping, EWMH lets the client set _NET_WM_STATE directly
+        let mut initial: Vec<u32> = net_wm_states(&window.state)
+            .into_iter()
+            .filter(|s| *s != NetWmState::Hidden)
+            .map(|s| self.atom(s))
+            .collect();
+        // Placement flags of the window's type (splash screens, notifications)
+        if window.skip_taskbar {
+            initial.push(self.atoms._NET_WM_STATE_SKIP_TASKBAR);
+        }
+        if window.always_on_top {
+            initial.push(self.atoms._NET_WM_STATE_ABOVE);
+        }
+        self.conn.change_property32(
+            xproto::PropMode::REPLACE,
+            xid,
+            self.atoms._NET_WM_STATE,
+            xproto::AtomEnum::ATOM,
+            &initial,
+        ).map_err(|e| e.to_string())?;
+
+        let mapped = window.visible && window.state != WindowState::Hidden;
+        if mapped {
+            self.conn.map_window(xid).map_err(|e| e.to_string())?;
+            if window.state == WindowState::Minimized {
+                self.iconify(xid)?;
+            }
+        }
+
+        self.by_xid.insert(xid, window.id);
+        self.handler.set_frame_source(window.id, FrameSource::X11(xid));
+        self.windows.insert(window.id, PresentedWindow {
+            xid,
+            title: window.title.clone(),
+            app_id: window.app_id.clone(),
+            state: window.state.clone(),
+            geometry: window.geometry,
+            mapped,
+            focused: false,
+            icon: (window.icon.clone(), window.badges.clone()),
+        });
+
+        println!("🪟 X11 window 0x{:x} created for window {}", xid, window.id);
+        Ok(())
+    }
+
+    fn update(&mut self, window: &Window) -> Result<(), String> {
+        let Some(presented) = self.windows.get(&window.id).cloned() else { return Ok(()) };
+        let xid = presented.xid;
+
+        if presented.title != window.title {
+            self.set_title(xid, &window.title)?;
+        }
+        if presented.app_id != window.app_id {
+            self.set_app_id(xid, &window.app_id)?;
+        }
+        if presented.icon.0 != window.icon || presented.icon.1 != window.badges {
+            self.set_icon(xid, window.id)?;
+        }
+        if presented.geometry != window.geometry {
+            let aux = xproto::ConfigureWindowAux::new()
+                .x(window.geometry.x)
+                .y(window.geometry.y)
+                .width(window.geometry.width.max(1))
+                .height(window.geometry.height.max(1));
+            self.conn.configure_window(xid, &aux).map_err(|e| e.to_string())?;
+        }
+
+        let visible = window.visible && window.state != WindowState::Hidden;
+        if visible != presented.mapped {
+            if visible {
+                self.conn.map_window(xid).map_err(|e| e.to_string())?;
+            } else {
+                self.conn.unmap_window(xid).map_err(|e| e.to_string())?;
+            }
+        }
+        if presented.state != window.state && visible {
+            self.apply_state(xid, &presented.state, &window.state)?;
+        }
+
+        if window.focused && !presented.focused && visible {
+            self.activate(xid)?;
+        }
+
+        if let Some(entry) = self.windows.get_mut(&window.id) {
+            entry.title = window.title.clone();
+            entry.app_id = window.app_id.clone();
+            entry.geometry = window.geometry;
+            entry.state = window.state.clone();
+            entry.mapped = visible;
+            entry.focused = window.focused;
+            entry.icon = (window.icon.clone(), window.badges.clone());
+        }
+        Ok(())
+    }
+
+    fn destroy(&mut self, window_id: u64) {
+        if let Some(presented) = self.windows.remove(&window_id) {
+            self.by_xid.remove(&presented.xid);
+            self.conn.destroy_window(presented.xid).ok();
+            println!("🗑️ X11 window 0x{:x} destroyed (window {})", presented.xid, window_id);
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::ConfigureNotify(ev) => {
+                let Some(&id) = self.by_xid.get(&ev.window) else { return };
+                let Some(presented) = self.windows.get_mut(&id) else { return };
+                let mut geometry = presented.geometry;
+                geometry.width = ev.width as u32;
+                geometry.height = ev.height as u32;
+                // Only synthetic notifies carry root coordinates once reparented
+                if ev.response_type & 0x80 != 0 {
+                    geometry.x = ev.x as i32;
+                    geometry.y = ev.y as i32;
+                }
+                if geometry != presented.geometry {
+                    presented.geometry = geometry;
+                    self.handler.set_geometry(id, geometry).ok();
+                }
+            }
+            Event::PropertyNotify(ev) if ev.atom == self.atoms._NET_WM_STATE => {
+                let Some(&id) = self.by_xid.get(&ev.window) else { return };
+                let state = window_state_from_net(&self.read_net_wm_state(ev.window));
+                if let Some(presented) = self.windows.get_mut(&id) {
+                    if presented.mapped && presented.state != state {
+                        presented.state = state.clone();
+                        self.handler.set_window_state(id, state).ok();
+                    }
+                }
+            }
+            Event::FocusIn(ev) => {
+                let Some(&id) = self.by_xid.get(&ev.event) else { return };
+                if let Some(presented) = self.windows.get_mut(&id) {
+                    presented.focused = true;
+                }
+                if self.handler.get_focused_window() != Some(id) {
+                    self.handler.focus_window(id).ok();
+                }
+            }
+            Event::ClientMessage(ev) if ev.type_ == self.atoms.WM_PROTOCOLS => {
+                let Some(&id) = self.by_xid.get(&ev.window) else { return };
+                if ev.data.as_data32()[0] == self.atoms.WM_DELETE_WINDOW {
+                    println!("🪟 X11 window manager closed window {}", id);
+                    self.destroy(id);
+                    if let Err(e) = self.handler.close_window(id) {
+                        eprintln!("⚠️  Window {} could not be closed: {}", id, e);
+                    }
+                }
+            }
+            Event::DestroyNotify(ev) => {
+                // Destroyed behind our back (e.g. X server reset or xkill)
+                if let Some(id) = self.by_xid.remove(&ev.window) {
+                    self.windows.remove(&id);
+                    self.handler.close_window(id).ok();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_state(&self, xid: xproto::Window, from: &WindowState, to: &WindowState) -> Result<(), String> {
+        if *to == WindowState::Minimized {
+            return self.iconify(xid);
+        }
+        if *from == WindowState::Minimized {
+            // ICCCM: mapping an iconic window restores it
+            self.conn.map_window(xid).map_err(|e| e.to_string())?;
+        }
+
+        let wanted = net_wm_states(to);
+        let pairs = [
+            (NetWmState::MaximizedVert, NetWmState::MaximizedHorz),
+            (NetWmState::Fullscreen, NetWmState::Fullscreen),
+        ];
+        for (first, second) in pairs {
+            let action = if wanted.contains(&first) { NET_WM_STATE_ADD } else { NET_WM_STATE_REMOVE };
+            let second = if second == first { 0 } else { self.atom(second) };
+            self.send_root_message(xid, self.atoms._NET_WM_STATE, [action, self.atom(first), second, 1, 0])?;
+        }
+        Ok(())
+    }
+
+    fn iconify(&self, xid: xproto::Window) -> Result<(), String> {
+        self.send_root_message(xid, self.atoms.WM_CHANGE_STATE, [ICONIC_STATE, 0, 0, 0, 0])
+    }
+
+    fn activate(&self, xid: xproto::Window) -> Result<(), String> {
+        // Source indication 1 = normal application
+        self.send_root_message(xid, self.atoms._NET_ACTIVE_WINDOW, [1, x11rb::CURRENT_TIME, 0, 0, 0])
+    }
+
+    fn send_root_message(&self, xid: xproto::Window, message_type: xproto::Atom, data: [u32; 5]) -> Result<(), String> {
+        let event = xproto::ClientMessageEvent::new(32, xid, message_type, data);
+        self.conn.send_event(
+            false,
+            self.root,
+            xproto::EventMask::SUBSTRUCTURE_REDIRECT | xproto::EventMask::SUBSTRUCTURE_NOTIFY,
+            event,
+        ).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn set_title(&self, xid: xproto::Window, title: &str) -> Result<(), String> {
+        self.conn.change_property8(
+            xproto::PropMode::REPLACE,
+            xid,
+            xproto::AtomEnum::WM_NAME,
+            xproto::AtomEnum::STRING,
+            title.as_bytes(),
+        ).map_err(|e| e.to_string())?;
+        self.conn.change_property8(
+            xproto::PropMode::REPLACE,
+            xid,
+            self.atoms._NET_WM_NAME,
+            self.atoms.UTF8_STRING,
+            title.as_bytes(),
+        ).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// _NET_WM_ICON with the window's badges, read by taskbars and pagers
+    fn set_icon(&self, xid: xproto::Window, window_id: u64) -> Result<(), String> {
+        let icon = self.handler.composed_icon(window_id, NET_WM_ICON_SIZE)?;
+        self.conn.change_property32(
+            xproto::PropMode::REPLACE,
+            xid,
+            self.atoms._NET_WM_ICON,
+            xproto::AtomEnum::CARDINAL,
+            &icon.to_net_wm_icon(),
+        ).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// WM_CLASS = "instance\0class\0", both set to the app id
+    fn set_app_id(&self, xid: xproto::Window, app_id: &str) -> Result<(), String> {
+        let class = format!("{0}\0{0}\0", app_id);
+        self.conn.change_property8(
+            xproto::PropMode::REPLACE,
+            xid,
+            xproto::AtomEnum::WM_CLASS,
+            xproto::AtomEnum::STRING,
+            class.as_bytes(),
+        ).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn read_net_wm_state(&self, xid: xproto::Window) -> Vec<NetWmState> {
+        self.conn
+            .get_property(false, xid, self.atoms._NET_WM_STATE, xproto::AtomEnum::ATOM, 0, 32)
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .and_then(|r| r.value32().map(|atoms| atoms.filter_map(|a| self.net_state(a)).collect()))
+            .unwrap_or_default()
+    }
+
+    fn atom(&self, state: NetWmState) -> xproto::Atom {
+        match state {
+            NetWmState::MaximizedVert => self.atoms._NET_WM_STATE_MAXIMIZED_VERT,
+            NetWmState::MaximizedHorz => self.atoms._NET_WM_STATE_MAXIMIZED_HORZ,
+            NetWmState::Fullscreen => self.atoms._NET_WM_STATE_FULLSCREEN,
+            NetWmState::Hidden => self.atoms._NET_WM_STATE_HIDDEN,
+        }
+    }
+
+    fn net_state(&self, atom: xproto::Atom) -> Option<NetWmState> {
+        [NetWmState::MaximizedVert, NetWmState::MaximizedHorz, NetWmState::Fullscreen, NetWmState::Hidden]
+            .into_iter()
+            .find(|s| self.atom(*s) == atom)
+    }
+}
+
+impl Drop for X11Backend {
+    fn drop(&mut self) {
+        for presented in self.windows.values() {
+            self.conn.destroy_window(presented.xid).ok();
+        }
+        self.conn.flush().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_net_wm_state_mapping() {
+        for state in [WindowState::Normal, WindowState::Maximized, WindowState::Fullscreen, WindowState::Minimized] {
+            assert_eq!(window_state_from_net(&net_wm_states(&state)), state);
+        }
+        // Half-maximized (one axis) is not a WASMA maximize
+        assert_eq!(window_state_from_net(&[NetWmState::MaximizedVert]), WindowState::Normal);
+        assert_eq!(
+            window_state_from_net(&[NetWmState::Fullscreen, NetWmState::Hidden]),
+            WindowState::Minimized
+        );
+    }
+}
@@ -0,0 +1,612 @@
+// WASMA - XWayland Support
+// Spawns a rootless Xwayland server as a client of the WASMA compositor and
+// runs a minimal X11 window manager on its -wm socket. Every mapped X11
+// window becomes a WASMA Window; _NET_WM properties are mapped into Window
+// metadata and the wl_surface is associated through WL_SURFACE_ID
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::os::fd::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::sync::Arc;
+use wayland_server::backend::ObjectId;
+use wayland_server::{Display, Resource};
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{self, ConnectionExt as XprotoExt};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::{DefaultStream, RustConnection};
+
+use crate::wayland_compositor::CompositorState;
+use crate::window_handling::{BackendType, WindowGeometry, WindowState, WindowType};
+use crate::x11_adoption::{allocate_stream_id, parse_wm_class};
+
+x11rb::atom_manager! {
+    pub XwmAtoms: XwmAtomsCookie {
+        WL_SURFACE_ID,
+        WM_PROTOCOLS,
+        WM_DELETE_WINDOW,
+        _NET_WM_NAME,
+        _NET_WM_PID,
+        _NET_WM_WINDOW_TYPE,
+        _NET_WM_STATE,
+        UTF8_STRING,
+    }
+}
+
+/// _NET_WM / ICCCM metadata read from an X11 window
+#[derive(Debug, Clone, PartialEq)]
+pub struct X11Metadata {
+    pub title: String,
+    pub wm_class: String,
+    pub pid: Option<u32>,
+    pub window_type: WindowType,
+    pub state: WindowState,
+    pub transient_for: Option<xproto::Window>,
+    /// _NET_WM_STATE_MODAL: blocks the window it is transient for
+    pub modal: bool,
+}
+
+/// An X11 window managed through XWayland
+#[derive(Debug, Clone)]
+pub struct XwaylandWindow {
+    pub xid: xproto::Window,
+    pub window_id: u64,
+    pub stream_id: u8,
+    pub surface: Option<ObjectId>,
+    pub metadata: X11Metadata,
+}
+
+// ============================================================================
+// XWAYLAND SERVER
+// ============================================================================
+
+/// Running Xwayland process and its window manager connection
+pub struct XWayland {
+    child: Child,
+    display_number: u32,
+    client_id: wayland_server::backend::ClientId,
+    socket_path: PathBuf,
+    lock_path: PathBuf,
+    display_fd: UnixStream,
+    wm_stream: Option<UnixStream>,
+    wm: Option<Xwm>,
+}
+
+impl XWayland {
+    /// Spawn rootless Xwayland connected to the given compositor display
+    pub fn spawn(display: &mut Display<CompositorState>) -> Result<Self, String> {
+        let (display_number, lock_path) = reserve_display()?;
+        let socket_path = PathBuf::from(format!("/tmp/.X11-unix/X{}", display_number));
+        fs::create_dir_all("/tmp/.X11-unix").ok();
+        fs::remove_file(&socket_path).ok();
+
+        let listener = UnixListener::bind(&socket_path)
+            .map_err(|e| format!("X11 socket bind failed: {}", e))?;
+
+        let (wl_server, wl_client) = UnixStream::pair().map_err(|e| e.to_string())?;
+        let (wm_ours, wm_theirs) = UnixStream::pair().map_err(|e| e.to_string())?;
+        let (display_fd, display_fd_theirs) = UnixStream::pair().map_err(|e| e.to_string())?;
+        display_fd.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+        let client = display.handle()
+            .insert_client(wl_server, Arc::new(()))
+            .map_err(|e| format!("Xwayland client insert failed: {}", e))?;
+
+        let inherited = [
+            wl_client.as_raw_fd(),
+            wm_theirs.as_raw_fd(),
+            listener.as_raw_fd(),
+            display_fd_theirs.as_raw_fd(),
+        ];
+
+        let mut command = Command::new("Xwayland");
+        command
+            .arg(format!(":{}", display_number))
+            .args(["-rootless", "-terminate"])
+            .args(["-wm", &wm_theirs.as_raw_fd().to_string()])
+            .args(["-listenfd", &listener.as_raw_fd().to_string()])
+            .args(["-displayfd", &display_fd_theirs.as_raw_fd().to_string()])
+            .env("WAYLAND_SOCKET", wl_client.as_raw_fd().to_string())
+            .env_remove("DISPLAY");
+
+        // Rust opens every fd with CLOEXEC – clear it for the fds Xwayland inherits
+        unsafe {
+            command.pre_exec(move || {
+                for fd in inherited {
+                    let flags = libc::fcntl(fd, libc::F_GETFD);
+                    if flags < 0 || libc::fcntl(fd, libc::F_SETFD, flags & !libc::FD_CLOEXEC) < 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+
+        let child = command.spawn()
+            .map_err(|e| format!("Failed to spawn Xwayland: {}", e))?;
+
+        println!("🧩 Xwayland starting on :{} (pid {})", display_number, child.id());
+
+        Ok(Self {
+            child,
+            display_number,
+            client_id: client.id(),
+            socket_path,
+            lock_path,
+            display_fd,
+            wm_stream: Some(wm_ours),
+            wm: None,
+        })
+    }
+
+    /// DISPLAY value for X11 clients
+    pub fn display_name(&self) -> String {
+        format!(":{}", self.display_number)
+    }
+
+    /// Whether the window manager connection is up
+    pub fn is_ready(&self) -> bool {
+        self.wm.is_some()
+    }
+
+    /// Managed X11 windows
+    pub fn windows(&self) -> Vec<XwaylandWindow> {
+        self.wm.as_ref()
+            .map(|wm| wm.windows.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Drive readiness and WM events – called from the compositor loop
+    pub fn dispatch(&mut self, state: &mut CompositorState) -> Result<(), String> {
+        if self.wm.is_none() {
+            if !self.poll_display_fd() {
+                return Ok(());
+            }
+
+            let stream = self.wm_stream.take()
+                .ok_or_else(|| "Xwayland WM socket already consumed".to_string())?;
+            self.wm = Some(Xwm::connect(stream)?);
+            println!("✅ Xwayland ready on {}", self.display_name());
+        }
+
+        if let Some(wm) = self.wm.as_mut() {
+            wm.dispatch(state, &self.client_id)?;
+        }
+        Ok(())
+    }
+
+    /// Close X11 windows whose assignment is gone
+    pub fn enforce_resources(&mut self, state: &CompositorState) {
+        if let Some(wm) = self.wm.as_mut() {
+            wm.enforce_resources(state);
+        }
+    }
+
+    fn poll_display_fd(&mut self) -> bool {
+        let mut buf = [0u8; 16];
+        match self.display_fd.read(&mut buf) {
+            Ok(n) if n > 0 => buf[..n].contains(&b'\n'),
+            _ => false,
+        }
+    }
+}
+
+impl Drop for XWayland {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+        fs::remove_file(&self.socket_path).ok();
+        fs::remove_file(&self.lock_path).ok();
+    }
+}
+
+/// Find a free X display number and create its lock file
+fn reserve_display() -> Result<(u32, PathBuf), String> {
+    for n in 0..33 {
+        let lock_path = PathBuf::from(format!("/tmp/.X{}-lock", n));
+        let lock = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path);
+
+        if let Ok(mut file) = lock {
+            use std::io::Write;
+            writeln!(file, "{:>10}", std::process::id()).map_err(|e| e.to_string())?;
+            return Ok((n, lock_path));
+        }
+    }
+    Err("No free X display number".to_string())
+}
+
+// ============================================================================
+// X WINDOW MANAGER
+// ============================================================================
+
+/// Minimal XWM running on the Xwayland -wm connection
+struct Xwm {
+    conn: RustConnection,
+    root: xproto::Window,
+    atoms: XwmAtoms,
+    windows: HashMap<xproto::Window, XwaylandWindow>,
+    pending_surfaces: HashMap<xproto::Window, u32>,
+}
+
+impl Xwm {
+    fn connect(stream: UnixStream) -> Result<Self, String> {
+        let (stream, _) = DefaultStream::from_unix_stream(stream).map_err(|e| e.to_string())?;
+        let conn = RustConnection::connect_to_stream(stream, 0)
+            .map_err(|e| format!("XWM connection failed: {}", e))?;
+        let root = conn.setup().roots[0].root;
+
+        let atoms = XwmAtoms::new(&conn)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+
+        let aux = xproto::ChangeWindowAttributesAux::new().event_mask(
+            xproto::EventMask::SUBSTRUCTURE_REDIRECT
+                | xproto::EventMask::SUBSTRUCTURE_NOTIFY
+                | xproto::EventMask::PROPERTY_CHANGE,
+        );
+        conn.change_window_attributes(root, &aux)
+            .map_err(|e| e.to_string())?
+            .check()
+            .map_err(|e| format!("XWM could not select root events: {}", e))?;
+        conn.flush().map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            conn,
+            root,
+            atoms,
+            windows: HashMap::new(),
+            pending_surfaces: HashMap::new(),
+        })
+    }
+
+    fn dispatch(&mut self, state: &mut CompositorState, client: &wayland_server::backend::ClientId) -> Result<(), String> {
+        while let Some(event) = self.conn.poll_for_event().map_err(|e| e.to_string())? {
+            match event {
+                Event::MapRequest(ev) => {
+                    if let Err(e) = self.manage(ev.window, state) {
+                        eprintln!("⚠️  XWM could not manage 0x{:x}: {}", ev.window, e);
+                    }
+                    self.conn.map_window(ev.window).ok();
+                }
+                // Managed windows follow the multitary layout, others get what they ask for
+                Event::ConfigureRequest(ev) if !self.windows.contains_key(&ev.window) => {
+                    let aux = xproto::ConfigureWindowAux::from_configure_request(&ev);
+                    self.conn.configure_window(ev.window, &aux).ok();
+                }
+                Event::ClientMessage(ev) if ev.type_ == self.atoms.WL_SURFACE_ID => {
+                    self.pending_surfaces.insert(ev.window, ev.data.as_data32()[0]);
+                }
+                Event::PropertyNotify(ev) if self.windows.contains_key(&ev.window) => {
+                    self.refresh_metadata(ev.window, state);
+                }
+                Event::DestroyNotify(ev) => {
+                    self.unmanage(ev.window, state);
+                }
+                Event::UnmapNotify(ev) if ev.event == self.root => {
+                    self.unmanage(ev.window, state);
+                }
+                _ => {}
+            }
+        }
+
+        self.associate_surfaces(state, client);
+        self.conn.flush().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// New X11 window → WASMA Window with metadata + viewport
+    fn manage(&mut self, xid: xproto::Window, state: &mut CompositorState) -> Result<(), String> {
+        if self.windows.contains_key(&xid) {
+            return Ok(());
+        }
+
+        let stream_id = allocate_stream_id(self.windows.values().map(|w| w.stream_id))
+            .ok_or_else(|| "No free stream slots for X11 windows".to_string())?;
+
+        let metadata = self.read_metadata(xid);
+        let geo = self.conn.get_geometry(xid)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+
+        let handler = state.handler();
+        let window_id = handler.create_window(
+            metadata.title.clone(),
+            metadata.wm_class.clone(),
+            WindowGeometry {
+                x: geo.x as i32,
+                y: geo.y as i32,
+                width: geo.width as u32,
+                height: geo.height as u32,
+            },
+            None,
+            state.resource_mode(),
+        )?;
+        handler.set_backend_type(window_id, BackendType::X11)?;
+
+        self.conn.change_window_attributes(
+            xid,
+            &xproto::ChangeWindowAttributesAux::new().event_mask(xproto::EventMask::PROPERTY_CHANGE),
+        ).ok();
+
+        // Binaries without a theme icon get the one embedded in their executable
+        if let Some(pid) = metadata.pid {
+            handler.set_window_icon_from_pid(window_id, pid).ok();
+        }
+
+        self.windows.insert(xid, XwaylandWindow {
+            xid,
+            window_id,
+            stream_id,
+            surface: None,
+            metadata,
+        });
+        self.apply_metadata(xid, state);
+
+        // Transients (dialogs, menus) float over their parent instead of tiling
+        if self.windows[&xid].metadata.transient_for.is_none() {
+            state.attach_stream(stream_id);
+            self.apply_layout(state);
+        }
+
+        println!("🧩 XWayland window 0x{:x} → Window {} | Stream {}", xid, window_id, stream_id);
+        Ok(())
+    }
+
+    fn unmanage(&mut self, xid: xproto::Window, state: &mut CompositorState) {
+        self.pending_surfaces.remove(&xid);
+        if let Some(win) = self.windows.remove(&xid) {
+            state.detach_stream(win.stream_id);
+            state.handler().close_window(win.window_id).ok();
+            self.apply_layout(state);
+            println!("🗑️  XWayland window 0x{:x} released (Window {})", xid, win.window_id);
+        }
+    }
+
+    /// Resolve WL_SURFACE_ID messages against surfaces of the Xwayland client
+    fn associate_surfaces(&mut self, state: &CompositorState, client: &wayland_server::backend::ClientId) {
+        let pending: Vec<(xproto::Window, u32)> = self.pending_surfaces.iter().map(|(k, v)| (*k, *v)).collect();
+
+        for (xid, protocol_id) in pending {
+            let surface = match state.find_surface(client, protocol_id) {
+                Some(s) => s,
+                None => continue,
+            };
+
+            self.pending_surfaces.remove(&xid);
+            if let Some(win) = self.windows.get_mut(&xid) {
+                win.surface = Some(surface.id());
+            }
+        }
+    }
+
+    fn refresh_metadata(&mut self, xid: xproto::Window, state: &CompositorState) {
+        let metadata = self.read_metadata(xid);
+        if let Some(win) = self.windows.get_mut(&xid) {
+            if win.metadata != metadata {
+                win.metadata = metadata;
+                self.apply_metadata(xid, state);
+            }
+        }
+    }
+
+    /// Push _NET_WM metadata into the WASMA Window
+    fn apply_metadata(&self, xid: xproto::Window, state: &CompositorState) {
+        let win = match self.windows.get(&xid) {
+            Some(w) => w,
+            None => return,
+        };
+        let handler = state.handler();
+
+        handler.set_title(win.window_id, win.metadata.title.clone()).ok();
+        handler.set_app_id(win.window_id, win.metadata.wm_class.clone()).ok();
+        handler.set_window_type(win.window_id, win.metadata.window_type.clone()).ok();
+        handler.set_window_state(win.window_id, win.metadata.state.clone()).ok();
+
+        if let Some(parent) = win.metadata.transient_for.and_then(|p| self.windows.get(&p)) {
+            handler.set_parent(win.window_id, parent.window_id).ok();
+            // X11 marks modality itself; dialogs without the state do not block
+            handler.set_modal(win.window_id, win.metadata.modal).ok();
+        }
+    }
+
+    fn apply_layout(&self, state: &CompositorState) {
+        for win in self.windows.values() {
+            let vp = match state.viewport_for_stream(win.stream_id) {
+                Some(vp) => vp,
+                None => continue,
+            };
+
+            let aux = xproto::ConfigureWindowAux::new()
+                .x(vp.x)
+                .y(vp.y)
+                .width(vp.width)
+                .height(vp.height);
+            self.conn.configure_window(win.xid, &aux).ok();
+
+            state.handler().set_geometry(win.window_id, WindowGeometry {
+                x: vp.x,
+                y: vp.y,
+                width: vp.width,
+                height: vp.height,
+            }).ok();
+        }
+    }
+
+    fn enforce_resources(&mut self, state: &CompositorState) {
+        let expired: Vec<xproto::Window> = self.windows.values()
+            .filter(|w| state.handler().get_window_resource_usage(w.window_id).is_err())
+            .map(|w| w.xid)
+            .collect();
+
+        for xid in expired {
+            println!("⏱️  Assignment lease expired – closing X11 window 0x{:x}", xid);
+            self.close(xid);
+        }
+    }
+
+    /// Polite WM_DELETE_WINDOW if supported, otherwise kill the client
+    fn close(&self, xid: xproto::Window) {
+        let supports_delete = self.conn
+            .get_property(false, xid, self.atoms.WM_PROTOCOLS, xproto::AtomEnum::ATOM, 0, 32)
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .and_then(|r| r.value32().map(|v| v.collect::<Vec<u32>>()))
+            .map(|atoms| atoms.contains(&self.atoms.WM_DELETE_WINDOW))
+            .unwrap_or(false);
+
+        if supports_delete {
+            let event = xproto::ClientMessageEvent::new(
+                32,
+                xid,
+                self.atoms.WM_PROTOCOLS,
+                [self.atoms.WM_DELETE_WINDOW, x11rb::CURRENT_TIME, 0, 0, 0],
+            );
+            self.conn.send_event(false, xid, xproto::EventMask::NO_EVENT, event).ok();
+        } else {
+            self.conn.kill_client(xid).ok();
+        }
+        self.conn.flush().ok();
+    }
+
+    fn read_metadata(&self, xid: xproto::Window) -> X11Metadata {
+        let title = self.string_property(xid, self.atoms._NET_WM_NAME, self.atoms.UTF8_STRING)
+            .or_else(|| self.string_property(xid, xproto::AtomEnum::WM_NAME.into(), xproto::AtomEnum::STRING.into()))
+            .unwrap_or_else(|| format!("X11 0x{:x}", xid));
+
+        let wm_class = self.raw_property(xid, xproto::AtomEnum::WM_CLASS.into(), xproto::AtomEnum::STRING.into())
+            .map(|raw| parse_wm_class(&raw))
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "xwayland.client".to_string());
+
+        let pid = self.atom_list(xid, self.atoms._NET_WM_PID, xproto::AtomEnum::CARDINAL.into())
+            .first()
+            .copied();
+
+        let window_type = self.atom_list(xid, self.atoms._NET_WM_WINDOW_TYPE, xproto::AtomEnum::ATOM.into())
+            .into_iter()
+            .find_map(|atom| window_type_from_name(&self.atom_name(atom)))
+            .unwrap_or(WindowType::Normal);
+
+        let state_names: Vec<String> = self.atom_list(xid, self.atoms._NET_WM_STATE, xproto::AtomEnum::ATOM.into())
+            .into_iter()
+            .map(|atom| self.atom_name(atom))
+            .collect();
+
+        let transient_for = self.atom_list(xid, xproto::AtomEnum::WM_TRANSIENT_FOR.into(), xproto::AtomEnum::WINDOW.into())
+            .first()
+            .copied()
+            .filter(|w| *w != 0);
+
+        X11Metadata {
+            title,
+            wm_class,
+            pid,
+            window_type,
+            state: window_state_from_names(&state_names),
+            transient_for,
+            modal: state_names.iter().any(|name| name == "_NET_WM_STATE_MODAL"),
+        }
+    }
+
+    fn raw_property(&self, xid: xproto::Window, property: xproto::Atom, type_: xproto::Atom) -> Option<Vec<u8>> {
+        self.conn
+            .get_property(false, xid, property, type_, 0, 1024)
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .map(|r| r.value)
+            .filter(|v| !v.is_empty())
+    }
+
+    fn string_property(&self, xid: xproto::Window, property: xproto::Atom, type_: xproto::Atom) -> Option<String> {
+        self.raw_property(xid, property, type_)
+            .map(|v| String::from_utf8_lossy(&v).to_string())
+    }
+
+    fn atom_list(&self, xid: xproto::Window, property: xproto::Atom, type_: xproto::Atom) -> Vec<u32> {
+        self.conn
+            .get_property(false, xid, property, type_, 0, 64)
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .and_then(|r| r.value32().map(|v| v.collect()))
+            .unwrap_or_default()
+    }
+
+    fn atom_name(&self, atom: xproto::Atom) -> String {
+        self.conn
+            .get_atom_name(atom)
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .map(|r| String::from_utf8_lossy(&r.name).to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// _NET_WM_WINDOW_TYPE_* → WindowType
+pub fn window_type_from_name(name: &str) -> Option<WindowType> {
+    match name.strip_prefix("_NET_WM_WINDOW_TYPE_")? {
+        "NORMAL" => Some(WindowType::Normal),
+        "DIALOG" => Some(WindowType::Dialog),
+        "UTILITY" | "TOOLBAR" => Some(WindowType::Utility),
+        "SPLASH" => Some(WindowType::Splash),
+        "MENU" => Some(WindowType::Menu),
+        "DROPDOWN_MENU" | "COMBO" => Some(WindowType::Dropdown),
+        "POPUP_MENU" => Some(WindowType::Popup),
+        "TOOLTIP" => Some(WindowType::Tooltip),
+        "NOTIFICATION" => Some(WindowType::Notification),
+        _ => None,
+    }
+}
+
+/// _NET_WM_STATE atoms → WindowState (hidden > fullscreen > maximized)
+pub fn window_state_from_names(names: &[String]) -> WindowState {
+    let has = |s: &str| names.iter().any(|n| n == s);
+
+    if has("_NET_WM_STATE_HIDDEN") {
+        WindowState::Minimized
+    } else if has("_NET_WM_STATE_FULLSCREEN") {
+        WindowState::Fullscreen
+    } else if has("_NET_WM_STATE_MAXIMIZED_VERT") && has("_NET_WM_STATE_MAXIMIZED_HORZ") {
+        WindowState::Maximized
+    } else {
+        WindowState::Normal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_type_mapping() {
+        assert_eq!(window_type_from_name("_NET_WM_WINDOW_TYPE_DIALOG"), Some(WindowType::Dialog));
+        assert_eq!(window_type_from_name("_NET_WM_WINDOW_TYPE_POPUP_MENU"), Some(WindowType::Popup));
+        assert_eq!(window_type_from_name("_NET_WM_WINDOW_TYPE_DESKTOP"), None);
+        assert_eq!(window_type_from_name("WM_NAME"), None);
+    }
+
+    #[test]
+    fn test_window_state_mapping() {
+        let names = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(window_state_from_names(&names(&[])), WindowState::Normal);
+        assert_eq!(window_state_from_names(&names(&["_NET_WM_STATE_FULLSCREEN"])), WindowState::Fullscreen);
+        assert_eq!(
+            window_state_from_names(&names(&["_NET_WM_STATE_MAXIMIZED_VERT", "_NET_WM_STATE_MAXIMIZED_HORZ"])),
+            WindowState::Maximized
+        );
+        assert_eq!(window_state_from_names(&names(&["_NET_WM_STATE_MAXIMIZED_VERT"])), WindowState::Normal);
+        assert_eq!(
+            window_state_from_names(&names(&["_NET_WM_STATE_HIDDEN", "_NET_WM_STATE_FULLSCREEN"])),
+            WindowState::Minimized
+        );
+    }
+}
@@ -1,11 +1,16 @@
 // src/assignment.rs
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
 use core_affinity;
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};  
-#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+use crate::hybrid::{self, Device, HybridPolicy, HybridSplitter, ModeStats, WorkKind, WorkUnit};
+use crate::topology::{CoreClass, CorePlacement, CoreUse, CpuTopology};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum, Serialize, Deserialize)]
 pub enum ExecutionMode {
     #[clap(alias = "cpu")]
     CpuOnly,
@@ -17,6 +22,20 @@ pub enum ExecutionMode {
     Hybrid,
 }
 
+impl ExecutionMode {
+    /// Startup check against the detected GPU: GPU-only cannot run without
+    /// one, GPU-preferred and hybrid fall back to the CPU (returned as a warning)
+    pub fn check_gpu(self, gpu_available: bool) -> Result<Option<String>, String> {
+        match (self, gpu_available) {
+            (ExecutionMode::GpuOnly, false) => Err("GpuOnly execution needs a GPU, none was detected".to_string()),
+            (ExecutionMode::GpuPreferred | ExecutionMode::Hybrid, false) => {
+                Ok(Some(format!("No GPU detected, {:?} runs on the CPU", self)))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Assignment {
     pub id: u32,
@@ -38,8 +57,28 @@ pub struct Assignment {
     pub cgroup_path: Option<String>,
 
     pub execution_mode: ExecutionMode,
+    /// Otomatik bind'da fiziksel çekirdek / SMT kardeş tercihi
+    pub core_placement: CorePlacement,
+    /// Hibrit CPU'da istenen çekirdek sınıfı; None ise önceliğe göre seçilir
+    pub core_class: Option<CoreClass>,
+    /// Ortak RAM/VRAM/CPU bütçesini paylaştığı QuotaGroup (bkz. quota.rs)
+    pub quota_group: Option<String>,
+
+    /// Hybrid iş bölme eşikleri
+    pub hybrid_policy: HybridPolicy,
+    /// Task'ın cihaz başına iş istatistiği (clone'lar aynı sayaçları paylaşır)
+    pub stats: Arc<Mutex<ModeStats>>,
 }
 
+/// Bu önceliğin altındaki assignment'lar arka plan işi sayılır (E-core)
+pub const BACKGROUND_CPU_PRIORITY: u8 = 3;
+
+/// Hybrid frame: pencere yüzeyi, popup, imleç katmanları (piksel)
+const HYBRID_FRAME_LAYERS: [u64; 3] = [1280 * 720, 256 * 256, 32 * 32];
+const HYBRID_FRAME_INTERVAL: Duration = Duration::from_millis(16);
+// Tek yollu modlarda istatistik bu kadar döngüde bir yazılır
+const STATS_FLUSH_CYCLES: u64 = 100_000;
+
 impl Assignment {
     pub fn new(id: u32) -> Self {
         Assignment {
@@ -58,91 +97,118 @@ impl Assignment {
             task_active: Arc::new(Mutex::new(false)),
             cgroup_path: None,
             execution_mode: ExecutionMode::GpuPreferred,
+            core_placement: CorePlacement::default(),
+            core_class: None,
+            quota_group: None,
+            hybrid_policy: HybridPolicy::default(),
+            stats: Arc::new(Mutex::new(ModeStats::default())),
         }
     }
 
+    /// Task istatistiğinin anlık kopyası
+    pub fn stats(&self) -> ModeStats {
+        self.stats.lock().unwrap().clone()
+    }
+
     pub fn start_lease(&mut self, dur: Duration) {
+        self.start_lease_at(dur, Instant::now());
+    }
+
+    /// `now` enjekte edilen saatten gelir (bkz. clock.rs)
+    pub fn start_lease_at(&mut self, dur: Duration, now: Instant) {
         self.lease_duration = Some(dur);
-        self.lease_start = Some(Instant::now());
+        self.lease_start = Some(now);
+    }
+
+    /// Lease'i aynı süreyle baştan başlat; lease yoksa bir şey yapmaz
+    pub fn renew_lease(&mut self) -> bool {
+        self.renew_lease_at(Instant::now())
+    }
+
+    pub fn renew_lease_at(&mut self, now: Instant) -> bool {
+        if self.lease_duration.is_none() {
+            return false;
+        }
+        self.lease_start = Some(now);
+        true
     }
 
     pub fn lease_expired(&self) -> bool {
+        self.lease_expired_at(Instant::now())
+    }
+
+    pub fn lease_expired_at(&self, now: Instant) -> bool {
+        self.lease_remaining_at(now).is_some_and(|left| left.is_zero())
+    }
+
+    /// Lease'in `now` anındaki kalan süresi; lease yoksa None
+    pub fn lease_remaining_at(&self, now: Instant) -> Option<Duration> {
         match (self.lease_duration, self.lease_start) {
-            (Some(d), Some(s)) => s.elapsed() >= d,
-            _ => false,
+            (Some(d), Some(s)) => Some(d.saturating_sub(now.saturating_duration_since(s))),
+            _ => None,
         }
     }
 
     pub fn bind_cpu(&mut self) {
+        self.bind_cpu_with(&CpuTopology::detect(), &[]);
+    }
+
+    /// `occupied` = diğer assignment'ların kullandığı CPU'lar (bkz. core_uses)
+    pub fn bind_cpu_with(&mut self, topology: &CpuTopology, occupied: &[CoreUse]) {
         if self.cpu_cores.is_empty() {
-            if let Some(core_ids) = core_affinity::get_core_ids() {
-                if let Some(core) = core_ids.first() {
-                    self.cpu_cores.push(core.id);
-                    let _ = core_affinity::set_for_current(*core);
-                    println!("🔗 CPU pinned to core {} for assignment {}", core.id, self.id);
-                }
+            let class = self.preferred_core_class();
+            if let Some(id) = topology.pick_core(occupied, self.is_gpu_heavy(), self.core_placement, class) {
+                self.cpu_cores.push(id);
+                let _ = core_affinity::set_for_current(core_affinity::CoreId { id });
+                tracing::debug!(assignment = self.id, core = id, "CPU pinned");
             }
         }
     }
 
+    /// Manifest'teki core_class, yoksa: gerçek zamanlı frame işi P-core'a,
+    /// düşük öncelikli arka plan işi E-core'a
+    pub fn preferred_core_class(&self) -> CoreClass {
+        self.core_class.unwrap_or(if self.cpu_priority < BACKGROUND_CPU_PRIORITY {
+            CoreClass::Efficiency
+        } else {
+            CoreClass::Performance
+        })
+    }
+
+    /// SMT kardeşi paylaşmaması gereken iş
+    pub fn is_gpu_heavy(&self) -> bool {
+        self.requires_gpu()
+    }
+
     pub fn bind_gpu(&mut self) {
         if !self.should_bind_gpu() {
             return;
         }
 
-        #[cfg(target_os = "linux")]
-        {
-            // 1. NVIDIA dGPU
-            if std::process::Command::new("nvidia-smi")
-                .arg("--query-gpu=name")
-                .output()
-                .is_ok()
-            {
-                self.gpu_device = Some("nvidia-dgpu".to_string());
-                self.gpu_id = Some("cuda:0".to_string());
-                println!("✅ Discrete GPU detected: NVIDIA dGPU");
-                return;
-            }
-
-            // 2. AMD dGPU
-            if std::process::Command::new("rocminfo").output().is_ok() {
-                self.gpu_device = Some("amd-dgpu".to_string());
-                self.gpu_id = Some("rocm:0".to_string());
-                println!("✅ Discrete GPU detected: AMD dGPU");
-                return;
+        match detect_gpu() {
+            Some((device, id, label)) => {
+                self.gpu_device = Some(device.to_string());
+                self.gpu_id = Some(id.to_string());
+                tracing::info!(assignment = self.id, device, "{}", label);
             }
-
-            // 3. iGPU – /dev/dri üzerinden
-            if std::path::Path::new("/dev/dri/renderD128").exists()
-                || std::path::Path::new("/dev/dri/renderD129").exists()
-                || std::path::Path::new("/dev/dri/card0").exists()
-                || std::path::Path::new("/dev/dri/card1").exists()
-            {
-                self.gpu_device = Some("integrated-gpu".to_string());
-                self.gpu_id = Some("igpu:0".to_string());
-                println!("✅ Integrated GPU (iGPU) detected via /dev/dri");
-                return;
-            }
-        }
-
-        #[cfg(target_os = "macos")]
-        {
-            self.gpu_device = Some("apple-igpu".to_string());
-            self.gpu_id = Some("metal:0".to_string());
-            println!("✅ Apple Silicon iGPU detected");
-            return;
+            // Hiç GPU bulunamadı
+            None => tracing::info!(assignment = self.id, "no GPU detected, falling back to CPU-only mode"),
         }
+    }
 
-        #[cfg(target_os = "windows")]
-        {
-            self.gpu_device = Some("windows-igpu".to_string());
-            self.gpu_id = Some("dxgi:0".to_string());
-            println("✅ Windows GPU detected (likely iGPU)");
+    /// Pin to a specific DRM device (e.g. the card of the seat the window lives on)
+    /// instead of probing for one
+    pub fn bind_gpu_device(&mut self, device: &str) {
+        if !self.should_bind_gpu() {
             return;
         }
 
-        // Hiç GPU bulunamadı
-        println!("ℹ️ No GPU detected – falling back to CPU-only mode");
+        let name = std::path::Path::new(device)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| device.to_string());
+        self.gpu_device = Some(device.to_string());
+        self.gpu_id = Some(format!("drm:{}", name));
     }
 
     pub fn should_bind_gpu(&self) -> bool {
@@ -163,6 +229,8 @@ impl Assignment {
         let cpu_cores = self.cpu_cores.clone();
         let gpu_device = self.gpu_device.clone();
         let mode = self.execution_mode;
+        let stats = Arc::clone(&self.stats);
+        let policy = self.hybrid_policy.clone();
 
         let handle = thread::spawn(move || {
             // CPU affinity tekrardan uygula (thread içinde)
@@ -174,25 +242,36 @@ impl Assignment {
                 }
             }
 
-            let mode_str = match mode {
-                ExecutionMode::CpuOnly => "🔵 CPU-Only",
-                ExecutionMode::GpuPreferred => "🟢 GPU Preferred",
-                ExecutionMode::GpuOnly => "🟡 GPU-Only",
-                ExecutionMode::Hybrid => "⚡ Hybrid",
-            };
+            // Task thread'indeki tüm olaylar assignment span'ında
+            let _span = tracing::info_span!("assignment", id).entered();
+            tracing::info!(?mode, "task started");
 
-            println!("🚀 Task {} STARTED → {}", id, mode_str);
+            if mode == ExecutionMode::Hybrid {
+                run_hybrid(id, &task_active, &stats, policy, gpu_device.is_some());
+                tracing::info!("task terminated");
+                return;
+            }
 
+            // Tek yollu modlar: tüm iş tek cihazda
+            let device = HybridSplitter::new(policy, gpu_device.is_some())
+                .route(mode, &WorkUnit::new(WorkKind::Composition, 0), 0);
             let mut counter = 0u64;
+            let mut window_start = Instant::now();
             while *task_active.lock().unwrap() {
                 counter += 1;
+                if counter % STATS_FLUSH_CYCLES == 0 {
+                    let mut stats = stats.lock().unwrap();
+                    stats.record(device, window_start.elapsed());
+                    stats.frames += STATS_FLUSH_CYCLES;
+                    window_start = Instant::now();
+                }
                 if counter % 5_000_000 == 0 {  // Çıktıyı seyrelttik
-                    println!("📊 Assignment {} alive ({}M cycles) | GPU: {:?}", id, counter / 1_000_000, gpu_device);
+                    tracing::trace!(million_cycles = counter / 1_000_000, gpu = ?gpu_device, "task alive");
                 }
                 thread::yield_now();
             }
 
-            println!("🛑 Task {} terminated", id);
+            tracing::info!("task terminated");
         });
 
         self.task_handle = Some(handle);
@@ -203,12 +282,63 @@ impl Assignment {
         *self.task_active.lock().unwrap() = false;
         if let Some(handle) = self.task_handle.take() {
             let _ = handle.join();
-            println!("🛑 Task {} gracefully stopped", self.id);
+            tracing::info!(assignment = self.id, "task stopped");
         }
     }
 }
 
+/// GPU tespiti, yan etkisiz: (gpu_device, gpu_id, log etiketi)
+/// bind_gpu ve dry-run planı aynı sırayı kullanır
+pub fn detect_gpu() -> Option<(&'static str, &'static str, &'static str)> {
+    #[cfg(target_os = "linux")]
+    {
+        // 1. NVIDIA dGPU
+        if std::process::Command::new("nvidia-smi")
+            .arg("--query-gpu=name")
+            .output()
+            .is_ok()
+        {
+            return Some(("nvidia-dgpu", "cuda:0", "Discrete GPU detected: NVIDIA dGPU"));
+        }
+
+        // 2. AMD dGPU
+        if std::process::Command::new("rocminfo").output().is_ok() {
+            return Some(("amd-dgpu", "rocm:0", "Discrete GPU detected: AMD dGPU"));
+        }
+
+        // 3. iGPU – /dev/dri üzerinden
+        if std::path::Path::new("/dev/dri/renderD128").exists()
+            || std::path::Path::new("/dev/dri/renderD129").exists()
+            || std::path::Path::new("/dev/dri/card0").exists()
+            || std::path::Path::new("/dev/dri/card1").exists()
+        {
+            return Some(("integrated-gpu", "igpu:0", "Integrated GPU (iGPU) detected via /dev/dri"));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        return Some(("apple-igpu", "metal:0", "Apple Silicon iGPU detected"));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        return Some(("windows-igpu", "dxgi:0", "Windows GPU detected (likely iGPU)"));
+    }
+
+    #[allow(unreachable_code)]
+    None
+}
+
 // JoinHandle Clone edilemediği için manuel Clone
+/// Assignment'ların bağlı olduğu CPU'lar; çekirdek seçiminde doluluk bilgisi
+pub fn core_uses<'a>(assignments: impl IntoIterator<Item = &'a Assignment>) -> Vec<CoreUse> {
+    assignments
+        .into_iter()
+        .flat_map(|a| a.cpu_cores.iter().map(move |&cpu| CoreUse { cpu, gpu_heavy: a.is_gpu_heavy() }))
+        .collect()
+}
+
 impl Clone for Assignment {
     fn clone(&self) -> Self {
         Assignment {
@@ -227,6 +357,116 @@ impl Clone for Assignment {
             task_active: Arc::new(Mutex::new(*self.task_active.lock().unwrap())),
             cgroup_path: self.cgroup_path.clone(),
             execution_mode: self.execution_mode,
+            core_placement: self.core_placement,
+            core_class: self.core_class,
+            quota_group: self.quota_group.clone(),
+            hybrid_policy: self.hybrid_policy.clone(),
+            stats: Arc::clone(&self.stats),
         }
     }
 }
+
+/// Hybrid döngü: UI/kontrol bu thread'de (CPU), kompozisyon GPU kuyruğunda.
+/// Kuyruk doluysa, iş küçükse ya da GPU ölçümde yavaşsa kompozisyon CPU'da yapılır.
+fn run_hybrid(
+    id: u32,
+    task_active: &Arc<Mutex<bool>>,
+    stats: &Arc<Mutex<ModeStats>>,
+    policy: HybridPolicy,
+    gpu_available: bool,
+) {
+    let mut splitter = HybridSplitter::new(policy.clone(), gpu_available);
+    let backlog = Arc::new(AtomicUsize::new(0));
+    let (gpu_tx, gpu_rx) = mpsc::sync_channel::<WorkUnit>(policy.max_gpu_backlog.max(1));
+    let (done_tx, done_rx) = mpsc::channel::<(WorkUnit, Duration)>();
+
+    // GPU kuyruğu: gönderilen kompozisyonları sırayla işler
+    let gpu_worker = gpu_available.then(|| {
+        let backlog = Arc::clone(&backlog);
+        let stats = Arc::clone(stats);
+        thread::spawn(move || {
+            let mut scratch = Vec::new();
+            for unit in gpu_rx {
+                let start = Instant::now();
+                hybrid::compose_pixels(&mut scratch, unit.cost);
+                let elapsed = start.elapsed();
+                backlog.fetch_sub(1, Ordering::SeqCst);
+                stats.lock().unwrap().record(Device::Gpu, elapsed);
+                if done_tx.send((unit, elapsed)).is_err() {
+                    break;
+                }
+            }
+        })
+    });
+
+    let mut scratch = Vec::new();
+    while *task_active.lock().unwrap() {
+        let frame_start = Instant::now();
+
+        // GPU ölçümlerini karar mekanizmasına geri besle
+        for (unit, elapsed) in done_rx.try_iter() {
+            splitter.record(Device::Gpu, &unit, elapsed);
+        }
+
+        for unit in hybrid::frame_work(&HYBRID_FRAME_LAYERS) {
+            if splitter.route(ExecutionMode::Hybrid, &unit, backlog.load(Ordering::SeqCst)) == Device::Gpu {
+                backlog.fetch_add(1, Ordering::SeqCst);
+                if gpu_tx.try_send(unit).is_ok() {
+                    continue;
+                }
+                backlog.fetch_sub(1, Ordering::SeqCst);
+            }
+
+            let start = Instant::now();
+            if unit.kind == WorkKind::Composition {
+                hybrid::compose_pixels(&mut scratch, unit.cost);
+            }
+            let elapsed = start.elapsed();
+            splitter.record(Device::Cpu, &unit, elapsed);
+
+            let mut stats = stats.lock().unwrap();
+            stats.record(Device::Cpu, elapsed);
+            if unit.kind == WorkKind::Composition && gpu_available {
+                stats.cpu_fallbacks += 1;
+            }
+        }
+
+        let frames = {
+            let mut stats = stats.lock().unwrap();
+            stats.frames += 1;
+            stats.frames
+        };
+        if frames % 600 == 0 {
+            let snapshot = stats.lock().unwrap().clone();
+            tracing::debug!(
+                assignment = id,
+                gpu_share = snapshot.gpu_share(),
+                cpu_fallbacks = snapshot.cpu_fallbacks,
+                "hybrid split"
+            );
+        }
+
+        if let Some(rest) = HYBRID_FRAME_INTERVAL.checked_sub(frame_start.elapsed()) {
+            thread::sleep(rest);
+        }
+    }
+
+    drop(gpu_tx);
+    if let Some(worker) = gpu_worker {
+        let _ = worker.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_gpu() {
+        assert_eq!(ExecutionMode::GpuOnly.check_gpu(true), Ok(None));
+        assert!(ExecutionMode::GpuOnly.check_gpu(false).is_err());
+        assert!(ExecutionMode::GpuPreferred.check_gpu(false).unwrap().is_some());
+        assert!(ExecutionMode::Hybrid.check_gpu(false).unwrap().is_some());
+        assert_eq!(ExecutionMode::CpuOnly.check_gpu(false), Ok(None));
+    }
+}
@@ -0,0 +1,168 @@
+// src/cadence.rs
+// WASMA - Döngü aralıkları
+// run_cycle üç aşamayı birlikte çalıştırır; run_due her aşamayı kendi aralığında
+// çalıştırır: allocate + schedule, lease enforce ve monitor. Sürücü döngüler sabit
+// uyku yerine wait_for_work ile bekler; bir sonraki aşama zamanı, yaklaşan lease
+// uyarısı/bitişi ya da wake() (yeni / kaldırılan assignment) uyandırır.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Eski sabit döngü aralığı; tüm aşamaların varsayılanı
+pub const DEFAULT_CYCLE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Aşama başına çalışma aralığı
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CycleCadence {
+    /// Çekirdek sınıflandırma + allocate + schedule
+    pub allocation: Duration,
+    /// Lease bitişi ve uyarıları
+    pub leases: Duration,
+    /// Canlı kaynak monitörü
+    pub monitor: Duration,
+}
+
+impl Default for CycleCadence {
+    fn default() -> Self {
+        Self::uniform(DEFAULT_CYCLE_INTERVAL)
+    }
+}
+
+impl CycleCadence {
+    /// Tüm aşamalar aynı aralıkta
+    pub fn uniform(interval: Duration) -> Self {
+        CycleCadence { allocation: interval, leases: interval, monitor: interval }
+    }
+}
+
+/// Bu çağrıda çalışacak aşamalar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DuePhases {
+    pub allocation: bool,
+    pub leases: bool,
+    pub monitor: bool,
+}
+
+impl DuePhases {
+    pub const ALL: DuePhases = DuePhases { allocation: true, leases: true, monitor: true };
+
+    pub fn any(&self) -> bool {
+        self.allocation || self.leases || self.monitor
+    }
+}
+
+/// Aşamaların son çalıştığı an; hiç çalışmamış aşama hemen çalışır
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PhaseTimes {
+    allocation: Option<Instant>,
+    leases: Option<Instant>,
+    monitor: Option<Instant>,
+}
+
+impl PhaseTimes {
+    pub fn due(&self, cadence: &CycleCadence, now: Instant) -> DuePhases {
+        let due = |last: Option<Instant>, every: Duration| !matches!(last, Some(at) if now.saturating_duration_since(at) < every);
+        DuePhases {
+            allocation: due(self.allocation, cadence.allocation),
+            leases: due(self.leases, cadence.leases),
+            monitor: due(self.monitor, cadence.monitor),
+        }
+    }
+
+    pub fn mark(&mut self, phases: DuePhases, now: Instant) {
+        for (ran, last) in [
+            (phases.allocation, &mut self.allocation),
+            (phases.leases, &mut self.leases),
+            (phases.monitor, &mut self.monitor),
+        ] {
+            if ran {
+                *last = Some(now);
+            }
+        }
+    }
+
+    /// En yakın aşamaya kalan süre; çalışması gereken varsa sıfır
+    pub fn until_next(&self, cadence: &CycleCadence, now: Instant) -> Duration {
+        let left = |last: Option<Instant>, every: Duration| {
+            last.map_or(Duration::ZERO, |at| every.saturating_sub(now.saturating_duration_since(at)))
+        };
+        left(self.allocation, cadence.allocation)
+            .min(left(self.leases, cadence.leases))
+            .min(left(self.monitor, cadence.monitor))
+    }
+}
+
+/// Sürücü döngüyü erken uyandıran sinyal; bekleyen yokken gelen uyandırma saklanır
+#[derive(Debug, Default)]
+pub struct Wakeup {
+    pending: Mutex<bool>,
+    cond: Condvar,
+}
+
+impl Wakeup {
+    pub fn notify(&self) {
+        *self.pending.lock().unwrap() = true;
+        self.cond.notify_all();
+    }
+
+    /// `timeout` kadar ya da uyandırılana kadar bekle; uyandırıldıysa true
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let pending = self.pending.lock().unwrap();
+        let (mut pending, _) = self.cond.wait_timeout_while(pending, timeout, |woken| !*woken).unwrap();
+        std::mem::take(&mut *pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Assignment, MockClock, ResourceMode, WBackend};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_phase_cadences_and_wakeup() {
+        let clock = Arc::new(MockClock::new());
+        let backend = Arc::new(WBackend::with_clock(ResourceMode::Manual, clock.clone()));
+        backend.set_cadence(CycleCadence {
+            allocation: Duration::from_secs(1),
+            leases: Duration::from_secs(4),
+            monitor: Duration::from_secs(60),
+        });
+        backend.resource_manager.set_default_lease(Duration::from_secs(30));
+        backend.add_assignment(Assignment::new(1));
+
+        // Yeni assignment bekleyen döngüyü hemen uyandırır
+        assert!(backend.wait_for_work());
+        assert_eq!(backend.due_phases(), DuePhases::ALL);
+        assert!(backend.run_due().is_empty());
+        assert!(!backend.due_phases().any());
+        assert_eq!(backend.next_wakeup(), Duration::from_secs(1));
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(backend.due_phases(), DuePhases { allocation: true, ..DuePhases::default() });
+        backend.run_due();
+
+        // Lease aşaması kendi aralığında çalışır; bitiş bir sonraki lease turunda yakalanır
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(backend.next_wakeup(), Duration::ZERO);
+        let phases = backend.due_phases();
+        assert!(phases.allocation && phases.leases && !phases.monitor);
+        assert_eq!(backend.run_due(), vec![1]);
+
+        // Lease uyarısı sabit aralıktan önce uyandırır
+        backend.set_cadence(CycleCadence::uniform(Duration::from_secs(60)));
+        backend.add_assignment(Assignment::new(2));
+        backend.run_cycle();
+        assert_eq!(backend.next_wakeup(), Duration::from_secs(25));
+        assert!(backend.wait_for_work());
+
+        let waker = backend.clone();
+        let wake = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            waker.wake();
+        });
+        assert!(backend.wait_for_work());
+        wake.join().unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+// src/cgroup.rs
+// WASMA - cgroups v2 muhasebesi (Linux)
+// ram_limit / cpu_cores tek başına sadece sayı; burada her assignment için
+// <cgroup kökü>/wasma/assignment-<pid>-<id> açılır, memory.max ve cpu.max
+// yazılır, gerçek kullanım memory.current ve cpu.stat'tan okunur. <pid> sahip
+// süreçtir; çökme temizliği yalnızca ölü sürecin cgroup'larına dokunur.
+
+use crate::assignment::Assignment;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const WASMA_GROUP: &str = "wasma";
+/// Assignment cgroup adlarının ön eki
+pub const ASSIGNMENT_PREFIX: &str = "assignment-";
+/// cpu.max periyodu (µs); kota = çekirdek sayısı × periyot
+pub const CPU_PERIOD_US: u64 = 100_000;
+
+/// Kernel'in ölçtüğü gerçek kullanım
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CgroupUsage {
+    /// memory.current (byte)
+    pub memory_current: u64,
+    /// memory.peak (byte); eski kernel'lerde yok
+    pub memory_peak: Option<u64>,
+    /// cpu.stat usage_usec
+    pub cpu_usage_usec: u64,
+}
+
+/// Assignment cgroup'larının kökü
+#[derive(Debug, Clone)]
+pub struct Cgroups {
+    base: PathBuf,
+    // Gerçek cgroupfs mi (testlerde düz dizin)
+    live: bool,
+    // Ad etiketindeki sahip süreç
+    owner: u32,
+}
+
+impl Cgroups {
+    /// Yazılabilir bir cgroup v2 hiyerarşisi varsa <kök>/wasma'yı hazırla; yoksa None
+    pub fn detect() -> Option<Self> {
+        if !cfg!(target_os = "linux") {
+            return None;
+        }
+        let root = Path::new(CGROUP_ROOT);
+        // cgroup.controllers sadece v2 (unified) hiyerarşide bulunur
+        let controllers = fs::read_to_string(root.join("cgroup.controllers")).ok()?;
+        if !controllers.split_whitespace().any(|c| c == "memory") {
+            return None;
+        }
+
+        let base = root.join(WASMA_GROUP);
+        fs::create_dir_all(&base).ok()?;
+        // memory/cpu dosyaları alt cgroup'larda görünsün; kökte zaten açık olabilir
+        let _ = fs::write(root.join("cgroup.subtree_control"), "+memory +cpu");
+        fs::write(base.join("cgroup.subtree_control"), "+memory +cpu").ok()?;
+        Some(Cgroups { base, live: true, owner: std::process::id() })
+    }
+
+    /// Kontrolcü ayarı yapmadan `base` altında çalış (testler, özel mount'lar)
+    pub fn with_base(base: impl Into<PathBuf>) -> Self {
+        Cgroups { base: base.into(), live: false, owner: std::process::id() }
+    }
+
+    pub fn base(&self) -> &Path {
+        &self.base
+    }
+
+    pub fn path_for(&self, id: u32) -> PathBuf {
+        self.base.join(format!("{}{}-{}", ASSIGNMENT_PREFIX, self.owner, id))
+    }
+
+    /// Başka bir sürecin cgroup adlarıyla çalış (testler)
+    pub fn with_owner(mut self, owner: u32) -> Self {
+        self.owner = owner;
+        self
+    }
+
+    /// Assignment'ın cgroup'unu oluştur (varsa kullan) ve limitlerini yaz
+    pub fn create(&self, assignment: &Assignment) -> io::Result<PathBuf> {
+        let path = self.path_for(assignment.id);
+        fs::create_dir_all(&path)?;
+        apply_limits(&path, assignment)?;
+        Ok(path)
+    }
+
+    /// cgroup'u sil; içindeki süreçler önce kök cgroup'a taşınır
+    pub fn remove(&self, path: &Path) -> io::Result<()> {
+        if !self.live {
+            return fs::remove_dir_all(path);
+        }
+        let procs = fs::read_to_string(path.join("cgroup.procs")).unwrap_or_default();
+        for pid in procs.split_whitespace() {
+            let _ = fs::write(Path::new(CGROUP_ROOT).join("cgroup.procs"), pid);
+        }
+        // cgroupfs'de arayüz dosyaları dizinle birlikte gider
+        fs::remove_dir(path)
+    }
+}
+
+/// memory.max ← ram_limit, cpu.max ← cpu_cores; 0 / boş "max" (sınırsız) yazar
+pub fn apply_limits(path: &Path, assignment: &Assignment) -> io::Result<()> {
+    let memory_max = match assignment.ram_limit {
+        0 => "max".to_string(),
+        bytes => bytes.to_string(),
+    };
+    fs::write(path.join("memory.max"), memory_max)?;
+
+    let cpu_max = match assignment.cpu_cores.len() as u64 {
+        0 => format!("max {}", CPU_PERIOD_US),
+        cores => format!("{} {}", cores * CPU_PERIOD_US, CPU_PERIOD_US),
+    };
+    fs::write(path.join("cpu.max"), cpu_max)
+}
+
+/// Süreci cgroup'a taşı
+pub fn attach(path: &Path, pid: u32) -> io::Result<()> {
+    fs::write(path.join("cgroup.procs"), pid.to_string())
+}
+
+/// Süreçleri dondur / çöz (cgroup.freeze); bellek ve lease'ler korunur
+pub fn freeze(path: &Path, frozen: bool) -> io::Result<()> {
+    fs::write(path.join("cgroup.freeze"), if frozen { "1" } else { "0" })
+}
+
+/// cgroup'taki süreçler
+pub fn procs(path: &Path) -> io::Result<Vec<u32>> {
+    Ok(fs::read_to_string(path.join("cgroup.procs"))?
+        .split_whitespace()
+        .filter_map(|pid| pid.parse().ok())
+        .collect())
+}
+
+pub fn usage(path: &Path) -> io::Result<CgroupUsage> {
+    let read_u64 = |file: &str| -> io::Result<u64> {
+        fs::read_to_string(path.join(file))?
+            .trim()
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", file, e)))
+    };
+
+    let cpu_usage_usec = fs::read_to_string(path.join("cpu.stat"))
+        .ok()
+        .and_then(|stat| {
+            stat.lines()
+                .find_map(|line| line.strip_prefix("usage_usec "))
+                .and_then(|v| v.trim().parse().ok())
+        })
+        .unwrap_or(0);
+
+    Ok(CgroupUsage {
+        memory_current: read_u64("memory.current")?,
+        memory_peak: read_u64("memory.peak").ok(),
+        cpu_usage_usec,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cgroup_limits_and_usage() {
+        let base = std::env::temp_dir().join(format!("wasma-cgroup-test-{}", std::process::id()));
+        let cgroups = Cgroups::with_base(&base);
+
+        let mut assignment = Assignment::new(7);
+        assignment.ram_limit = 256 * 1024 * 1024;
+        assignment.cpu_cores = vec![0, 1];
+        let path = cgroups.create(&assignment).unwrap();
+        assert_eq!(path, base.join(format!("assignment-{}-7", std::process::id())));
+        assert_eq!(fs::read_to_string(path.join("memory.max")).unwrap(), "268435456");
+        assert_eq!(fs::read_to_string(path.join("cpu.max")).unwrap(), "200000 100000");
+
+        assignment.ram_limit = 0;
+        assignment.cpu_cores.clear();
+        apply_limits(&path, &assignment).unwrap();
+        assert_eq!(fs::read_to_string(path.join("memory.max")).unwrap(), "max");
+        assert_eq!(fs::read_to_string(path.join("cpu.max")).unwrap(), "max 100000");
+
+        // Kernel'in yazdığı dosyalar
+        fs::write(path.join("memory.current"), "1048576\n").unwrap();
+        fs::write(path.join("cpu.stat"), "usage_usec 4200\nuser_usec 4000\nsystem_usec 200\n").unwrap();
+        let used = usage(&path).unwrap();
+        assert_eq!(used, CgroupUsage { memory_current: 1 << 20, memory_peak: None, cpu_usage_usec: 4200 });
+
+        cgroups.remove(&path).unwrap();
+        assert!(!path.exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+}
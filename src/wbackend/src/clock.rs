@@ -0,0 +1,122 @@
+// src/clock.rs
+// WASMA - Zaman kaynağı
+// Lease bitişi, uyarı penceresi ve scheduler kararları "şimdi"yi buradan alır.
+// Üretimde SystemClock kullanılır; testler MockClock ile zamanı elle ilerletir,
+// böylece lease mantığı sleep beklemeden deterministik test edilir.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Monotonik zaman kaynağı
+pub trait Clock: Send + Sync + fmt::Debug {
+    fn now(&self) -> Instant;
+}
+
+/// WBackend, ResourceManager ve Scheduler arasında paylaşılan saat
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Gerçek monotonik saat (Instant::now)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Sadece advance() ile ilerleyen test saati
+#[derive(Debug)]
+pub struct MockClock {
+    origin: Instant,
+    offset: Mutex<Duration>,
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        MockClock {
+            origin: Instant::now(),
+            offset: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.offset.lock().unwrap() += by;
+    }
+
+    /// Oluşturulduğundan beri ilerletilen toplam süre
+    pub fn elapsed(&self) -> Duration {
+        *self.offset.lock().unwrap()
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.origin + self.elapsed()
+    }
+}
+
+/// Varsayılan saat
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::PlannedAction;
+    use crate::{Assignment, LeaseDecision, ResourceMode, WBackend};
+
+    #[test]
+    fn test_mock_clock_lease_expiry() {
+        let clock = Arc::new(MockClock::new());
+        let backend = WBackend::with_clock(ResourceMode::Manual, clock.clone());
+        let mut assignment = Assignment::new(1);
+        assignment.start_lease_at(Duration::from_secs(30), clock.now());
+        backend.add_assignment(assignment);
+
+        clock.advance(Duration::from_secs(26));
+        let plan = backend.dry_run_cycle();
+        assert_eq!(plan.assignments[0].actions, vec![PlannedAction::LeaseWarning { remaining_secs: 4 }]);
+        backend.run_cycle();
+        assert!(backend.get_assignment(1).is_some());
+
+        clock.advance(Duration::from_secs(4));
+        assert_eq!(backend.dry_run_cycle().preempted(), vec![1]);
+        backend.run_cycle();
+        assert!(backend.get_assignment(1).is_none());
+    }
+
+    #[test]
+    fn test_lease_renewal_and_expiry_hook() {
+        let clock = Arc::new(MockClock::new());
+        let backend = WBackend::with_clock(ResourceMode::Manual, clock.clone());
+        backend.resource_manager.set_default_lease(Duration::from_secs(10));
+        // Tek ID'ler hook tarafından yenilenir
+        backend.resource_manager.on_lease_expired(|a| {
+            if a.id % 2 == 1 { LeaseDecision::Renew } else { LeaseDecision::Release }
+        });
+        backend.add_assignment(Assignment::new(1));
+        backend.add_assignment(Assignment::new(2));
+        assert_eq!(backend.get_assignment(2).unwrap().lease_duration, Some(Duration::from_secs(10)));
+
+        clock.advance(Duration::from_secs(8));
+        assert!(backend.renew_lease(2));
+        clock.advance(Duration::from_secs(8));
+        assert!(backend.run_cycle().expired.is_empty());
+
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(backend.run_cycle().expired, vec![2]);
+        assert!(backend.get_assignment(2).is_none());
+        // 1, 16. saniyede hook ile yenilendi
+        let renewed = backend.get_assignment(1).unwrap();
+        assert_eq!(renewed.lease_remaining_at(clock.now()), Some(Duration::from_secs(8)));
+    }
+}
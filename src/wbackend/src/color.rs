@@ -0,0 +1,218 @@
+// src/color.rs
+// WASMA - Renk tipi
+// Ayarlardaki tema renkleri, tema token'ları, GUI ve OSD aynı tipi kullanır.
+// settings.conf'taki "#3584e4", "#fff", "rgb(53, 132, 228)", "hsl(213, 76%, 55%)"
+// yazımlarının hepsi buradan çözülür; yazarken her zaman hex'e döner.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// sRGB renk, alfa dahil (0-255)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Color::BLACK
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorParseError(pub String);
+
+impl fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid color '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ColorParseError {}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(0xff, 0xff, 0xff);
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 0xff }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
+    }
+
+    /// `hue` derece, `saturation` ve `lightness` 0.0-1.0
+    pub fn hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let (s, l) = (saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = l - chroma / 2.0;
+        Color::rgb(unit_to_u8(r + m), unit_to_u8(g + m), unit_to_u8(b + m))
+    }
+
+    /// Aynı renk, `alpha` 0.0-1.0 saydamlıkla
+    pub fn with_alpha(self, alpha: f32) -> Self {
+        Color { a: unit_to_u8(alpha), ..self }
+    }
+
+    /// [r, g, b, a], 0.0-1.0
+    pub fn to_f32(self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a].map(|c| c as f32 / 255.0)
+    }
+
+    /// Doğrusal karışım, `t` = 0 → self, 1 → other
+    pub fn mix(self, other: Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color::rgba(lerp(self.r, other.r), lerp(self.g, other.g), lerp(self.b, other.b), lerp(self.a, other.a))
+    }
+
+    /// "#rrggbb"; opak olmayan renklerde "#rrggbbaa"
+    pub fn to_hex(self) -> String {
+        if self.a == 0xff {
+            format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+        }
+    }
+
+    /// "#rgb", "#rgba", "#rrggbb", "#rrggbbaa" ('#' isteğe bağlı), "rgb()/rgba()", "hsl()/hsla()".
+    /// Fonksiyon argümanları virgül ya da boşlukla ayrılır, alfa "/ 0.5" olarak da verilebilir
+    pub fn parse(text: &str) -> Result<Self, ColorParseError> {
+        let error = || ColorParseError(text.to_string());
+        let value = text.trim().to_ascii_lowercase();
+
+        if let Some((name, args)) = value.strip_suffix(')').and_then(|v| v.split_once('(')) {
+            let args: Vec<&str> = args.split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+                .filter(|a| !a.is_empty())
+                .collect();
+            let alpha = match args.get(3) {
+                Some(a) => parse_alpha(a).ok_or_else(error)?,
+                None => 1.0,
+            };
+            if args.len() < 3 || args.len() > 4 {
+                return Err(error());
+            }
+            let color = match name.trim() {
+                "rgb" | "rgba" => {
+                    let channel = |a: &str| parse_channel(a).ok_or_else(error);
+                    Color::rgb(channel(args[0])?, channel(args[1])?, channel(args[2])?)
+                }
+                "hsl" | "hsla" => {
+                    let hue = args[0].trim_end_matches("deg").parse::<f32>().map_err(|_| error())?;
+                    let percent = |a: &str| parse_percent(a).ok_or_else(error);
+                    Color::hsl(hue, percent(args[1])?, percent(args[2])?)
+                }
+                _ => return Err(error()),
+            };
+            return Ok(color.with_alpha(alpha));
+        }
+
+        let hex = value.strip_prefix('#').unwrap_or(&value);
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(error());
+        }
+        let digits: Vec<u8> = match hex.len() {
+            // Kısa yazımda her hane iki kez: "#f80" = "#ff8800"
+            3 | 4 => hex.chars().map(|c| c.to_digit(16).unwrap() as u8 * 0x11).collect(),
+            6 | 8 => (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect(),
+            _ => return Err(error()),
+        };
+        Ok(Color::rgba(digits[0], digits[1], digits[2], digits.get(3).copied().unwrap_or(0xff)))
+    }
+}
+
+fn unit_to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// "128" ya da "50%"
+fn parse_channel(arg: &str) -> Option<u8> {
+    match arg.strip_suffix('%') {
+        Some(p) => Some(unit_to_u8(p.parse::<f32>().ok()? / 100.0)),
+        None => Some(arg.parse::<f32>().ok()?.clamp(0.0, 255.0).round() as u8),
+    }
+}
+
+/// "76%" ya da "76" → 0.76
+fn parse_percent(arg: &str) -> Option<f32> {
+    Some(arg.trim_end_matches('%').parse::<f32>().ok()? / 100.0)
+}
+
+/// "0.5" ya da "50%"
+fn parse_alpha(arg: &str) -> Option<f32> {
+    match arg.strip_suffix('%') {
+        Some(p) => Some(p.parse::<f32>().ok()? / 100.0),
+        None => arg.parse::<f32>().ok(),
+    }
+}
+
+impl FromStr for Color {
+    type Err = ColorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Color::parse(s)
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl From<[u8; 3]> for Color {
+    fn from([r, g, b]: [u8; 3]) -> Self {
+        Color::rgb(r, g, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() {
+        let accent = Color::rgb(0x35, 0x84, 0xe4);
+        assert_eq!(Color::parse("#3584e4"), Ok(accent));
+        assert_eq!(Color::parse(" 3584E4 "), Ok(accent));
+        assert_eq!(Color::parse("rgb(53, 132, 228)"), Ok(accent));
+        assert_eq!(Color::parse("rgb(53 132 228)"), Ok(accent));
+        assert_eq!(Color::parse("#fff"), Ok(Color::WHITE));
+        assert_eq!(Color::parse("#f808"), Ok(Color::rgba(0xff, 0x88, 0x00, 0x88)));
+        assert_eq!(Color::parse("#3584e480"), Ok(accent.with_alpha(0.5)));
+        assert_eq!(Color::parse("rgba(53, 132, 228, 0.5)"), Ok(accent.with_alpha(0.5)));
+        assert_eq!(Color::parse("rgb(100%, 0%, 0% / 50%)"), Ok(Color::rgba(0xff, 0, 0, 0x80)));
+
+        assert_eq!(Color::parse("hsl(0, 100%, 50%)"), Ok(Color::rgb(0xff, 0, 0)));
+        assert_eq!(Color::parse("hsl(120deg, 100%, 25%)"), Ok(Color::rgb(0, 0x80, 0)));
+        assert_eq!(Color::parse("hsla(240, 100%, 50%, 0)"), Ok(Color::rgba(0, 0, 0xff, 0)));
+        assert_eq!(Color::parse("hsl(0, 0%, 100%)"), Ok(Color::WHITE));
+
+        for bad in ["", "#zzzzzz", "#12345", "rgb(1, 2)", "rgb(1, 2, x)", "cmyk(0, 0, 0, 0)", "blue"] {
+            assert!(Color::parse(bad).is_err(), "{}", bad);
+        }
+
+        // Hex'e yazılıp geri okunur
+        assert_eq!(accent.to_string(), "#3584e4");
+        assert_eq!(accent.with_alpha(0.5).to_hex(), "#3584e480");
+        assert_eq!(accent.with_alpha(0.5).to_string().parse::<Color>(), Ok(accent.with_alpha(0.5)));
+
+        assert_eq!(Color::BLACK.mix(Color::WHITE, 0.5), Color::rgb(0x80, 0x80, 0x80));
+        assert_eq!(Color::WHITE.to_f32(), [1.0; 4]);
+    }
+}
@@ -0,0 +1,145 @@
+// src/gpu_metrics.rs
+// WASMA - Süreç başına gerçek VRAM kullanımı
+// vram_limit sadece ayrılan değeri gösterir. Burada kullanılan VRAM okunur:
+//   NVIDIA    → NVML (nvidia-smi --query-compute-apps üzerinden)
+//   AMD/Intel → DRM fdinfo (/proc/<pid>/fdinfo/*, drm-memory-vram / drm-resident-*)
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// Kullanım bilgisinin kaynağı
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GpuMetricsSource {
+    Nvml,
+    DrmFdinfo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GpuMemoryUsage {
+    pub vram_bytes: u64,
+    pub source: GpuMetricsSource,
+}
+
+/// Süreçlerin toplam VRAM kullanımı; hiçbir kaynak cevap vermezse None
+pub fn processes_vram(pids: &[u32]) -> Option<GpuMemoryUsage> {
+    if pids.is_empty() {
+        return None;
+    }
+
+    // NVIDIA: tek sorgu tüm süreçleri döndürür
+    if let Some(per_pid) = nvml_process_usage() {
+        let used: u64 = pids.iter().filter_map(|pid| per_pid.get(pid)).sum();
+        if used > 0 {
+            return Some(GpuMemoryUsage { vram_bytes: used, source: GpuMetricsSource::Nvml });
+        }
+    }
+
+    let mut found = false;
+    let mut used = 0;
+    for &pid in pids {
+        if let Some(bytes) = drm_process_usage(pid) {
+            found = true;
+            used += bytes;
+        }
+    }
+    found.then_some(GpuMemoryUsage { vram_bytes: used, source: GpuMetricsSource::DrmFdinfo })
+}
+
+/// nvidia-smi'nin NVML'den okuduğu pid → kullanılan VRAM (byte)
+fn nvml_process_usage() -> Option<HashMap<u32, u64>> {
+    if !cfg!(target_os = "linux") && !cfg!(target_os = "windows") {
+        return None;
+    }
+    let output = std::process::Command::new("nvidia-smi")
+        .args(["--query-compute-apps=pid,used_memory", "--format=csv,noheader,nounits"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    Some(parse_nvml_csv(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// "pid, used_memory(MiB)" satırları
+pub fn parse_nvml_csv(csv: &str) -> HashMap<u32, u64> {
+    let mut usage = HashMap::new();
+    for line in csv.lines() {
+        let mut fields = line.split(',').map(str::trim);
+        let (Some(pid), Some(mib)) = (fields.next(), fields.next()) else { continue };
+        if let (Ok(pid), Ok(mib)) = (pid.parse::<u32>(), mib.parse::<u64>()) {
+            *usage.entry(pid).or_insert(0) += mib << 20;
+        }
+    }
+    usage
+}
+
+/// Sürecin açık DRM dosyalarındaki VRAM; DRM istemcisi yoksa None
+fn drm_process_usage(pid: u32) -> Option<u64> {
+    let dir = Path::new("/proc").join(pid.to_string()).join("fdinfo");
+    let mut clients = HashSet::new();
+    let mut used = None;
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let Ok(content) = fs::read_to_string(entry.path()) else { continue };
+        let Some((client, bytes)) = parse_drm_fdinfo(&content) else { continue };
+        // dup edilmiş fd'ler aynı istemciyi gösterir; bir kez say
+        if clients.insert(client) {
+            *used.get_or_insert(0) += bytes;
+        }
+    }
+    used
+}
+
+/// fdinfo'dan (drm-client-id, VRAM byte); DRM dosyası değilse None
+pub fn parse_drm_fdinfo(content: &str) -> Option<(String, u64)> {
+    let mut client = None;
+    let mut vram = 0;
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let key = key.trim();
+        if key == "drm-client-id" {
+            client = Some(value.trim().to_string());
+        } else if key == "drm-memory-vram"
+            || key.starts_with("drm-resident-vram")
+            || key.starts_with("drm-resident-local")
+        {
+            vram += parse_size(value).unwrap_or(0);
+        }
+    }
+    client.map(|c| (c, vram))
+}
+
+/// "1234 KiB" / "12 MiB" / "4096" → byte
+fn parse_size(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let number: u64 = parts.next()?.parse().ok()?;
+    let shift = match parts.next() {
+        None => 0,
+        Some("KiB") => 10,
+        Some("MiB") => 20,
+        Some("GiB") => 30,
+        Some(_) => return None,
+    };
+    Some(number << shift)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpu_metrics_parsing() {
+        let nvml = parse_nvml_csv("1234, 512\n1234, 64\n99, 8\nbogus line\n");
+        assert_eq!(nvml.get(&1234), Some(&(576 << 20)));
+        assert_eq!(nvml.get(&99), Some(&(8 << 20)));
+
+        let amd = "pos:\t0\nflags:\t02100002\ndrm-driver:\tamdgpu\ndrm-client-id:\t42\n\
+                   drm-memory-vram:\t20480 KiB\ndrm-memory-gtt:\t2048 KiB\n";
+        assert_eq!(parse_drm_fdinfo(amd), Some(("42".to_string(), 20 << 20)));
+
+        let intel = "drm-driver:\txe\ndrm-client-id:\t7\ndrm-total-vram0:\t64 MiB\ndrm-resident-vram0:\t48 MiB\n";
+        assert_eq!(parse_drm_fdinfo(intel), Some(("7".to_string(), 48 << 20)));
+
+        // DRM olmayan fd
+        assert_eq!(parse_drm_fdinfo("pos:\t0\nflags:\t02\nmnt_id:\t25\n"), None);
+    }
+}
@@ -0,0 +1,234 @@
+// src/hybrid.rs
+// WASMA - Hybrid yürütme yolu
+// Hybrid modda UI/kontrol işi CPU'da, frame kompozisyonu GPU kuyruğunda çalışır.
+// HybridSplitter her iş birimini hangi cihazın alacağına karar verir;
+// ModeStats her assignment için cihaz başına iş/süre istatistiği tutar.
+
+use crate::assignment::ExecutionMode;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Bir frame içindeki iş türleri
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WorkKind {
+    /// Widget ağacı, layout, input
+    Ui,
+    /// Lease, sinyal, IPC gibi kontrol işleri
+    Control,
+    /// Katmanların birleştirilmesi (piksel işi)
+    Composition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Device {
+    Cpu,
+    Gpu,
+}
+
+/// Tek bir iş birimi; `cost` kompozisyon için piksel sayısıdır
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorkUnit {
+    pub kind: WorkKind,
+    pub cost: u64,
+}
+
+impl WorkUnit {
+    pub fn new(kind: WorkKind, cost: u64) -> Self {
+        WorkUnit { kind, cost }
+    }
+}
+
+/// İş bölme eşikleri
+#[derive(Debug, Clone, PartialEq)]
+pub struct HybridPolicy {
+    /// Bundan küçük kompozisyonlar CPU'da kalır (upload maliyeti baskın)
+    pub min_gpu_pixels: u64,
+    /// GPU kuyruğunda bu kadar iş bekliyorsa yeni kompozisyon CPU'ya düşer
+    pub max_gpu_backlog: usize,
+    /// Ölçülen süreler için üstel ortalama katsayısı (0-1)
+    pub smoothing: f64,
+}
+
+impl Default for HybridPolicy {
+    fn default() -> Self {
+        HybridPolicy {
+            // 128x128 altı katmanlar CPU'da daha ucuz
+            min_gpu_pixels: 128 * 128,
+            max_gpu_backlog: 3,
+            smoothing: 0.2,
+        }
+    }
+}
+
+/// Cihaz seçimi ve ölçüme dayalı geri besleme
+#[derive(Debug, Clone)]
+pub struct HybridSplitter {
+    policy: HybridPolicy,
+    gpu_available: bool,
+    // Birim (piksel) başına ortalama süre, nanosaniye; ölçüm yoksa None
+    cpu_ns_per_unit: Option<f64>,
+    gpu_ns_per_unit: Option<f64>,
+}
+
+impl HybridSplitter {
+    pub fn new(policy: HybridPolicy, gpu_available: bool) -> Self {
+        HybridSplitter {
+            policy,
+            gpu_available,
+            cpu_ns_per_unit: None,
+            gpu_ns_per_unit: None,
+        }
+    }
+
+    pub fn policy(&self) -> &HybridPolicy {
+        &self.policy
+    }
+
+    /// `gpu_backlog` = GPU kuyruğunda bekleyen kompozisyon sayısı
+    pub fn route(&self, mode: ExecutionMode, unit: &WorkUnit, gpu_backlog: usize) -> Device {
+        match mode {
+            ExecutionMode::CpuOnly => Device::Cpu,
+            ExecutionMode::GpuOnly => Device::Gpu,
+            ExecutionMode::GpuPreferred => {
+                if self.gpu_available { Device::Gpu } else { Device::Cpu }
+            }
+            ExecutionMode::Hybrid => self.route_hybrid(unit, gpu_backlog),
+        }
+    }
+
+    fn route_hybrid(&self, unit: &WorkUnit, gpu_backlog: usize) -> Device {
+        // UI ve kontrol işi her zaman CPU'da
+        if unit.kind != WorkKind::Composition || !self.gpu_available {
+            return Device::Cpu;
+        }
+        if unit.cost < self.policy.min_gpu_pixels || gpu_backlog >= self.policy.max_gpu_backlog {
+            return Device::Cpu;
+        }
+
+        // Kuyruk bekleme süresi dahil GPU, CPU'dan yavaşsa CPU'da birleştir
+        if let (Some(cpu), Some(gpu)) = (self.cpu_ns_per_unit, self.gpu_ns_per_unit) {
+            if gpu * (gpu_backlog as f64 + 1.0) > cpu {
+                return Device::Cpu;
+            }
+        }
+
+        Device::Gpu
+    }
+
+    /// Biten kompozisyonun süresini ortalamaya kat
+    pub fn record(&mut self, device: Device, unit: &WorkUnit, elapsed: Duration) {
+        if unit.kind != WorkKind::Composition || unit.cost == 0 {
+            return;
+        }
+        let sample = elapsed.as_nanos() as f64 / unit.cost as f64;
+        let alpha = self.policy.smoothing;
+        let slot = match device {
+            Device::Cpu => &mut self.cpu_ns_per_unit,
+            Device::Gpu => &mut self.gpu_ns_per_unit,
+        };
+        *slot = Some(match *slot {
+            Some(avg) => avg + (sample - avg) * alpha,
+            None => sample,
+        });
+    }
+}
+
+/// Cihaz başına iş sayısı ve süre
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceStats {
+    pub units: u64,
+    pub busy: Duration,
+}
+
+/// Bir assignment'ın (veya bir yürütme modunun toplamının) istatistiği
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModeStats {
+    pub cpu: DeviceStats,
+    pub gpu: DeviceStats,
+    /// GPU'ya gidebilecekken CPU'da yapılan kompozisyonlar (küçük iş, kuyruk dolu, GPU yavaş)
+    pub cpu_fallbacks: u64,
+    pub frames: u64,
+}
+
+impl ModeStats {
+    pub fn record(&mut self, device: Device, elapsed: Duration) {
+        let slot = match device {
+            Device::Cpu => &mut self.cpu,
+            Device::Gpu => &mut self.gpu,
+        };
+        slot.units += 1;
+        slot.busy += elapsed;
+    }
+
+    /// GPU'ya giden iş oranı (0-1)
+    pub fn gpu_share(&self) -> f64 {
+        let total = self.cpu.units + self.gpu.units;
+        if total == 0 { 0.0 } else { self.gpu.units as f64 / total as f64 }
+    }
+
+    pub fn merge(&mut self, other: &ModeStats) {
+        self.cpu.units += other.cpu.units;
+        self.cpu.busy += other.cpu.busy;
+        self.gpu.units += other.gpu.units;
+        self.gpu.busy += other.gpu.busy;
+        self.cpu_fallbacks += other.cpu_fallbacks;
+        self.frames += other.frames;
+    }
+}
+
+/// Yürütme modu başına toplanmış istatistik
+pub type ExecutionStats = HashMap<ExecutionMode, ModeStats>;
+
+/// Frame başına iş listesi: bir UI, bir kontrol, `layers` kadar kompozisyon
+pub fn frame_work(layer_pixels: &[u64]) -> Vec<WorkUnit> {
+    let mut work = vec![
+        WorkUnit::new(WorkKind::Ui, 1),
+        WorkUnit::new(WorkKind::Control, 1),
+    ];
+    work.extend(layer_pixels.iter().map(|&px| WorkUnit::new(WorkKind::Composition, px)));
+    work
+}
+
+/// Yazılım kompozisyonu: `pixels` RGBA pikseli üst üste harmanla
+/// (CPU yolu ve GPU kuyruğu simülasyonu aynı çekirdeği kullanır)
+pub fn compose_pixels(scratch: &mut Vec<u8>, pixels: u64) {
+    let len = (pixels as usize).saturating_mul(4).min(4 * 1024 * 1024);
+    scratch.resize(len, 0);
+    for (i, px) in scratch.iter_mut().enumerate() {
+        // src-over, alfa 50%
+        *px = ((*px as u16 + (i & 0xff) as u16) / 2) as u8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hybrid_routing() {
+        let mut splitter = HybridSplitter::new(HybridPolicy::default(), true);
+        let ui = WorkUnit::new(WorkKind::Ui, 1);
+        let layer = WorkUnit::new(WorkKind::Composition, 1920 * 1080);
+        let cursor = WorkUnit::new(WorkKind::Composition, 32 * 32);
+
+        assert_eq!(splitter.route(ExecutionMode::Hybrid, &ui, 0), Device::Cpu);
+        assert_eq!(splitter.route(ExecutionMode::Hybrid, &layer, 0), Device::Gpu);
+        assert_eq!(splitter.route(ExecutionMode::Hybrid, &cursor, 0), Device::Cpu);
+        assert_eq!(splitter.route(ExecutionMode::Hybrid, &layer, 3), Device::Cpu);
+        // GpuPreferred her şeyi GPU'ya verir
+        assert_eq!(splitter.route(ExecutionMode::GpuPreferred, &ui, 0), Device::Gpu);
+
+        // GPU ölçümde CPU'dan yavaşsa kompozisyon CPU'ya kayar
+        splitter.record(Device::Cpu, &layer, Duration::from_millis(1));
+        splitter.record(Device::Gpu, &layer, Duration::from_millis(4));
+        assert_eq!(splitter.route(ExecutionMode::Hybrid, &layer, 0), Device::Cpu);
+
+        let no_gpu = HybridSplitter::new(HybridPolicy::default(), false);
+        assert_eq!(no_gpu.route(ExecutionMode::Hybrid, &layer, 0), Device::Cpu);
+
+        let mut stats = ModeStats::default();
+        stats.record(Device::Cpu, Duration::from_micros(10));
+        stats.record(Device::Gpu, Duration::from_micros(10));
+        assert_eq!(stats.gpu_share(), 0.5);
+    }
+}
@@ -0,0 +1,133 @@
+// src/ids.rs
+// WASMA - Assignment ID ayırıcı
+// Kaldırılan ID'ler yeniden verilir, ama uçuştaki bir döngü (run_async task'ı)
+// ID'yi hâlâ tutuyorsa önce mezar taşı (tombstone) olur; döngü bitince serbest
+// kalır. Böylece eski ID'ye ait bir döngü yeni assignment'a dokunmaz.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+#[derive(Debug)]
+pub struct IdAllocator {
+    // Hiç verilmemiş ilk ID
+    next: u32,
+    // Yeniden verilebilir ID'ler; en küçüğü önce
+    free: BTreeSet<u32>,
+    // Kaldırılmış ama döngüsü sürdüğü için henüz verilemeyen ID'ler
+    tombstones: HashSet<u32>,
+    // ID başına süren döngü sayısı
+    in_flight: HashMap<u32, usize>,
+}
+
+impl Default for IdAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdAllocator {
+    pub fn new() -> Self {
+        IdAllocator {
+            next: 1,
+            free: BTreeSet::new(),
+            tombstones: HashSet::new(),
+            in_flight: HashMap::new(),
+        }
+    }
+
+    /// Boşta en küçük ID; yoksa yeni bir ID
+    pub fn allocate(&mut self) -> u32 {
+        if let Some(id) = self.free.pop_first() {
+            return id;
+        }
+        let id = self.next;
+        self.next += 1;
+        id
+    }
+
+    /// Dışarıdan seçilmiş ID kullanımda (add_assignment / restore)
+    pub fn reserve(&mut self, id: u32) {
+        self.free.remove(&id);
+        self.tombstones.remove(&id);
+        self.next = self.next.max(id.saturating_add(1));
+    }
+
+    /// Kaldırılan ID'yi geri ver; döngüsü sürüyorsa mezar taşı olur
+    pub fn release(&mut self, id: u32) {
+        if id >= self.next {
+            return;
+        }
+        if self.in_flight.contains_key(&id) {
+            self.tombstones.insert(id);
+        } else {
+            self.free.insert(id);
+        }
+    }
+
+    /// ID için bir döngü başladı
+    pub fn begin_cycle(&mut self, id: u32) {
+        *self.in_flight.entry(id).or_insert(0) += 1;
+    }
+
+    /// ID'nin döngüsü bitti; son döngüyse mezar taşı serbest kalır
+    pub fn end_cycle(&mut self, id: u32) {
+        let Some(count) = self.in_flight.get_mut(&id) else { return };
+        *count -= 1;
+        if *count == 0 {
+            self.in_flight.remove(&id);
+            if self.tombstones.remove(&id) {
+                self.free.insert(id);
+            }
+        }
+    }
+
+    pub fn is_tombstoned(&self, id: u32) -> bool {
+        self.tombstones.contains(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Assignment, ResourceMode, WBackend};
+
+    #[test]
+    fn test_id_recycling() {
+        let mut ids = IdAllocator::new();
+        assert_eq!((ids.allocate(), ids.allocate(), ids.allocate()), (1, 2, 3));
+
+        // En küçük boş ID önce verilir
+        ids.release(3);
+        ids.release(2);
+        assert_eq!(ids.allocate(), 2);
+
+        // Uçuştaki döngü bitene kadar ID verilmez
+        ids.begin_cycle(1);
+        ids.release(1);
+        assert!(ids.is_tombstoned(1));
+        assert_eq!(ids.allocate(), 3);
+        assert_eq!(ids.allocate(), 4);
+        ids.end_cycle(1);
+        assert!(!ids.is_tombstoned(1));
+        assert_eq!(ids.allocate(), 1);
+
+        // Dışarıdan seçilen ID'ler atlanır
+        ids.reserve(10);
+        assert_eq!(ids.allocate(), 11);
+        ids.release(42);
+        assert_eq!(ids.allocate(), 12);
+
+        // Backend: kaldırma task'ı durdurur, ID yeniden verilir
+        let backend = WBackend::new(ResourceMode::Manual);
+        let first = backend.allocate_id();
+        backend.add_assignment(Assignment::new(first));
+        backend.add_assignment(Assignment::new(backend.allocate_id()));
+        assert_eq!(backend.allocate_id(), 3);
+
+        let removed = backend.remove_assignment(first).unwrap();
+        assert!(removed.task_handle.is_none());
+        assert!(backend.get_assignment(first).is_none());
+        assert!(backend.remove_assignment(first).is_none());
+        assert_eq!(backend.list_assignments().len(), 1);
+        assert_eq!(backend.allocate_id(), first);
+    }
+}
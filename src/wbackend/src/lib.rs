@@ -2,13 +2,54 @@
 pub mod assignment;
 pub mod resource_manager;
 pub mod scheduler;
+pub mod osd;
+pub mod theme;
+pub mod color;
+pub mod hybrid;
+pub mod snapshot;
+pub mod plan;
+pub mod clock;
+pub mod topology;
+pub mod cgroup;
+pub mod gpu_metrics;
+pub mod quota;
+pub mod ids;
+pub mod cadence;
+pub mod report;
+pub mod recovery;
+#[cfg(unix)]
+pub mod rundir;
+#[cfg(feature = "async")]
+pub mod runtime;
 
 pub use assignment::{Assignment, ExecutionMode};
-pub use resource_manager::{ResourceManager, ResourceMode};
+pub use resource_manager::{LeaseDecision, LeaseHook, ResourceManager, ResourceMode, DEFAULT_LEASE};
 pub use scheduler::Scheduler;
+pub use osd::{OsdIcon, OsdMessage};
+pub use theme::{A11yTheme, ThemeTokens};
+pub use color::{Color, ColorParseError};
+pub use hybrid::{HybridPolicy, HybridSplitter, ModeStats, ExecutionStats};
+pub use snapshot::{AssignmentSnapshot, BackendSnapshot, SnapshotError, SNAPSHOT_VERSION};
+pub use plan::{AssignmentPlan, CyclePlan, PlannedAction};
+pub use clock::{Clock, MockClock, SharedClock, SystemClock};
+pub use topology::{CoreClass, CorePlacement, CoreUse, CpuTopology};
+pub use cgroup::{CgroupUsage, Cgroups};
+pub use gpu_metrics::{GpuMemoryUsage, GpuMetricsSource};
+pub use quota::{QuotaError, QuotaGroup, QuotaOverflow, QuotaUsage};
+pub use ids::IdAllocator;
+pub use cadence::{CycleCadence, DuePhases, DEFAULT_CYCLE_INTERVAL};
+pub use report::{AssignmentStatus, ModeSummary, MonitorReport};
+pub use recovery::{RecoveryReport, RunRecord};
+#[cfg(feature = "async")]
+pub use runtime::{shutdown_channel, ShutdownHandle, ShutdownSignal};
 
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use cadence::{PhaseTimes, Wakeup};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
 
 /// WASMA'nın ana backend'i – Resource-first otorite merkezi
 pub struct WBackend {
@@ -19,26 +60,104 @@ pub struct WBackend {
     assignments: Arc<Mutex<HashMap<u32, Assignment>>>,
 
     mode: ResourceMode,
+    clock: SharedClock,
+
+    // İsimle kota grupları; assignment.quota_group buradaki bir ada bakar
+    quota_groups: RwLock<HashMap<String, QuotaGroup>>,
+
+    // Kilit sırası: assignments, sonra ids
+    ids: Mutex<IdAllocator>,
+
+    // run_due aşama aralıkları ve son çalışma anları
+    cadence: RwLock<CycleCadence>,
+    phases: Mutex<PhaseTimes>,
+    wakeup: Wakeup,
+
+    // Monitor aşamasının son raporu ve abonelerin kanalları
+    last_report: Mutex<Option<MonitorReport>>,
+    report_subscribers: Mutex<Vec<Sender<MonitorReport>>>,
+
+    // Çökme kaydı: dosya yolu ve son yazılan içerik (bkz. recover_previous_run)
+    run_record: Mutex<Option<(PathBuf, RunRecord)>>,
 }
 
 impl WBackend {
     pub fn new(mode: ResourceMode) -> Self {
+        Self::with_clock(mode, clock::system())
+    }
+
+    /// Lease ve scheduler kararları `clock` üzerinden; testlerde MockClock verilir
+    pub fn with_clock(mode: ResourceMode, clock: SharedClock) -> Self {
         WBackend {
-            scheduler: Scheduler::new(),
-            resource_manager: ResourceManager::new(mode),
+            scheduler: Scheduler::with_clock(clock.clone()),
+            resource_manager: ResourceManager::with_clock(mode, clock.clone()),
             assignments: Arc::new(Mutex::new(HashMap::new())),
             mode,
+            clock,
+            quota_groups: RwLock::new(HashMap::new()),
+            ids: Mutex::new(IdAllocator::new()),
+            cadence: RwLock::new(CycleCadence::default()),
+            phases: Mutex::new(PhaseTimes::default()),
+            wakeup: Wakeup::default(),
+            last_report: Mutex::new(None),
+            report_subscribers: Mutex::new(Vec::new()),
+            run_record: Mutex::new(None),
         }
     }
 
-    /// Yeni assignment ekle
-    pub fn add_assignment(&self, mut assignment: Assignment) {
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
+    /// Kullanılmayan bir assignment ID'si; kaldırılanlar yeniden verilir
+    pub fn allocate_id(&self) -> u32 {
+        self.ids.lock().unwrap().allocate()
+    }
+
+    /// Kaldırılmış ama async döngüsü henüz bitmemiş ID
+    pub fn is_tombstoned(&self, id: u32) -> bool {
+        self.ids.lock().unwrap().is_tombstoned(id)
+    }
+
+    /// Assignment'ı kaldır: task durur, cgroup silinir, ID yeniden verilebilir olur.
+    /// Döndürülen assignment'ın task'ı durdurulmuştur; yoksa None
+    pub fn remove_assignment(&self, id: u32) -> Option<Assignment> {
+        let mut assignments = self.assignments.lock().unwrap();
+        let mut removed = assignments.remove(&id)?;
+        self.resource_manager.release(&mut removed);
+        self.ids.lock().unwrap().release(id);
+        self.sync_run_record(&assignments);
+        self.wake();
+        tracing::info!(id, "assignment removed");
+        Some(removed)
+    }
+
+    /// Yeni assignment ekle (kota kontrolü yapılmaz; bkz. try_add_assignment)
+    pub fn add_assignment(&self, assignment: Assignment) {
+        let mut assignments = self.assignments.lock().unwrap();
+        self.insert_assignment(&mut assignments, assignment);
+    }
+
+    /// Kota grubuna sığıyorsa ekle; kontrol ve ekleme aynı kilit altında
+    pub fn try_add_assignment(&self, assignment: Assignment) -> Result<(), QuotaError> {
+        let mut assignments = self.assignments.lock().unwrap();
+        if let Some(name) = assignment.quota_group.as_deref() {
+            let groups = self.quota_groups.read().unwrap();
+            let group = groups.get(name).ok_or_else(|| QuotaError::UnknownGroup(name.to_string()))?;
+            quota::check(group, &assignment, assignments.values())?;
+        }
+        self.insert_assignment(&mut assignments, assignment);
+        Ok(())
+    }
+
+    fn insert_assignment(&self, assignments: &mut HashMap<u32, Assignment>, mut assignment: Assignment) {
         let id = assignment.id;
 
         if self.mode == ResourceMode::Auto {
-            // CPU binding
+            // CPU binding: diğer assignment'ların fiziksel çekirdeklerinden kaçın
             if assignment.cpu_cores.is_empty() {
-                assignment.bind_cpu();
+                let occupied = assignment::core_uses(assignments.values().filter(|a| a.id != id));
+                assignment.bind_cpu_with(&self.resource_manager.topology(), &occupied);
             }
 
             // GPU binding (opsiyonel)
@@ -48,7 +167,7 @@ impl WBackend {
 
             // Lease başlat
             if assignment.lease_start.is_none() {
-                assignment.start_lease(std::time::Duration::from_secs(30));
+                assignment.start_lease_at(self.resource_manager.default_lease(), self.clock.now());
             }
 
             // Task'ı hemen başlat
@@ -58,31 +177,242 @@ impl WBackend {
         } else {
             // Manual mod: sadece lease
             if assignment.lease_start.is_none() {
-                assignment.start_lease(std::time::Duration::from_secs(30));
+                assignment.start_lease_at(self.resource_manager.default_lease(), self.clock.now());
             }
         }
 
+        // cgroup: yeni assignment'ta oluştur, güncellenende limitleri yeniden yaz
+        self.resource_manager.sync_cgroup(&mut assignment);
+
         // HashMap'e ekle
-        let mut assignments = self.assignments.lock().unwrap();
+        self.ids.lock().unwrap().reserve(id);
         assignments.insert(id, assignment);
-        println!("➕ Assignment {} added to WBackend | Mode: {:?}", id, self.mode);
+        self.sync_run_record(assignments);
+        self.wake();
+        tracing::info!(id, mode = ?self.mode, "assignment added");
+    }
+
+    /// Ana döngü – WASMA'nın kalbi; raporun `expired` alanı lease'i bitip
+    /// kaldırılan assignment'lardır
+    pub fn run_cycle(&self) -> MonitorReport {
+        let (_, report) = self.run_phases(DuePhases::ALL);
+        report.expect("run_cycle monitor aşamasını da çalıştırır")
     }
 
-    /// Ana döngü – WASMA'nın kalbi
-    pub fn run_cycle(&self) {
+    /// Sadece zamanı gelen aşamaları çalıştır (bkz. set_cadence); sürücü döngüler
+    /// wait_for_work ile birlikte kullanır
+    pub fn run_due(&self) -> Vec<u32> {
+        let due = self.due_phases();
+        if !due.any() {
+            return Vec::new();
+        }
+        self.run_phases(due).0
+    }
+
+    fn run_phases(&self, due: DuePhases) -> (Vec<u32>, Option<MonitorReport>) {
         let mut assignments = self.assignments.lock().unwrap();
 
-        // 1. Allocate + Schedule
-        for assignment in assignments.values_mut() {
-            self.resource_manager.allocate(assignment);
-            self.scheduler.schedule(assignment);
+        if due.allocation {
+            // 0. Hibrit CPU: çekirdek sınıfı değiştiyse yanlış sınıftaki işleri taşı
+            self.resource_manager.reclassify_cores(&mut assignments);
+
+            // 1. Allocate + Schedule
+            let mut occupied = assignment::core_uses(assignments.values());
+            for assignment in assignments.values_mut() {
+                let _span = tracing::info_span!("assignment", id = assignment.id).entered();
+                self.resource_manager.allocate(assignment, &mut occupied);
+                self.scheduler.schedule(assignment);
+            }
         }
 
         // 2. Lease enforce
-        self.resource_manager.enforce_leases(&mut assignments);
+        let mut expired = Vec::new();
+        if due.leases {
+            expired = self.resource_manager.enforce_leases(&mut assignments);
+            self.release_ids(&expired);
+        }
 
         // 3. Monitor
-        self.resource_manager.monitor(&assignments);
+        let report = due.monitor.then(|| MonitorReport {
+            expired: expired.clone(),
+            ..self.resource_manager.monitor(&assignments)
+        });
+        self.sync_run_record(&assignments);
+        drop(assignments);
+
+        self.phases.lock().unwrap().mark(due, self.clock.now());
+        if let Some(report) = &report {
+            self.publish_report(report);
+        }
+        (expired, report)
+    }
+
+    /// Her monitor aşamasının raporunu al (run_cycle, run_due, run_async)
+    pub fn subscribe_reports(&self) -> Receiver<MonitorReport> {
+        let (tx, rx) = mpsc::channel();
+        self.report_subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// Son monitor raporu; monitor aşaması henüz çalışmadıysa None
+    pub fn last_report(&self) -> Option<MonitorReport> {
+        self.last_report.lock().unwrap().clone()
+    }
+
+    fn publish_report(&self, report: &MonitorReport) {
+        *self.last_report.lock().unwrap() = Some(report.clone());
+        self.report_subscribers.lock().unwrap().retain(|tx| tx.send(report.clone()).is_ok());
+    }
+
+    pub fn cadence(&self) -> CycleCadence {
+        *self.cadence.read().unwrap()
+    }
+
+    /// Aşama aralıklarını değiştir; bekleyen sürücü döngü yeni aralıkla devam eder
+    pub fn set_cadence(&self, cadence: CycleCadence) {
+        *self.cadence.write().unwrap() = cadence;
+        self.wake();
+    }
+
+    /// Şu an çalışması gereken aşamalar; yaklaşan lease olayı lease aşamasını öne çeker
+    pub fn due_phases(&self) -> DuePhases {
+        let mut due = self.phases.lock().unwrap().due(&self.cadence(), self.clock.now());
+        if !due.leases {
+            let assignments = self.assignments.lock().unwrap();
+            due.leases = self.resource_manager.next_lease_event(&assignments) == Some(Duration::ZERO);
+        }
+        due
+    }
+
+    /// Bir sonraki aşamaya ya da lease uyarısı/bitişine kalan süre
+    pub fn next_wakeup(&self) -> Duration {
+        let until_phase = self.phases.lock().unwrap().until_next(&self.cadence(), self.clock.now());
+        let assignments = self.assignments.lock().unwrap();
+        match self.resource_manager.next_lease_event(&assignments) {
+            Some(lease) => until_phase.min(lease),
+            None => until_phase,
+        }
+    }
+
+    /// Bir sonraki işe kadar bekle: aşama zamanı, lease olayı ya da wake().
+    /// Erken uyandırıldıysa true
+    pub fn wait_for_work(&self) -> bool {
+        self.wakeup.wait_timeout(self.next_wakeup())
+    }
+
+    /// wait_for_work'te bekleyen sürücü döngüyü uyandır
+    pub fn wake(&self) {
+        self.wakeup.notify();
+    }
+
+    fn release_ids(&self, ids: &[u32]) {
+        let mut allocator = self.ids.lock().unwrap();
+        for &id in ids {
+            allocator.release(id);
+        }
+    }
+
+    /// Hibrit CPU'da çekirdek sınıflarını yeniden oku; yanlış sınıfta kalan
+    /// assignment'lar taşınır. Taşınan assignment ID'lerini döndürür.
+    pub fn reclassify_cores(&self) -> Vec<u32> {
+        let mut assignments = self.assignments.lock().unwrap();
+        self.resource_manager.reclassify_cores(&mut assignments)
+    }
+
+    /// Assignment'ın lease'ini şimdiden yenile; assignment yoksa false
+    pub fn renew_lease(&self, id: u32) -> bool {
+        let mut assignments = self.assignments.lock().unwrap();
+        let now = self.clock.now();
+        let renewed = assignments.get_mut(&id).is_some_and(|a| a.renew_lease_at(now));
+        if renewed {
+            self.wake();
+        }
+        renewed
+    }
+
+    /// Grubu tanımla ya da bütçesini güncelle (mevcut üyeler çıkarılmaz)
+    pub fn set_quota_group(&self, group: QuotaGroup) {
+        self.quota_groups.write().unwrap().insert(group.name.clone(), group);
+    }
+
+    pub fn remove_quota_group(&self, name: &str) -> Option<QuotaGroup> {
+        self.quota_groups.write().unwrap().remove(name)
+    }
+
+    pub fn quota_group(&self, name: &str) -> Option<QuotaGroup> {
+        self.quota_groups.read().unwrap().get(name).cloned()
+    }
+
+    /// Tanımlı gruplar (ada göre sıralı)
+    pub fn quota_groups(&self) -> Vec<QuotaGroup> {
+        let mut groups: Vec<QuotaGroup> = self.quota_groups.read().unwrap().values().cloned().collect();
+        groups.sort_by(|a, b| a.name.cmp(&b.name));
+        groups
+    }
+
+    /// Assignment'ı grubundan çıkar (penceresi kapanınca bütçeyi boşaltır); grubu yoksa false
+    pub fn leave_quota_group(&self, id: u32) -> bool {
+        let mut assignments = self.assignments.lock().unwrap();
+        assignments.get_mut(&id).and_then(|a| a.quota_group.take()).is_some()
+    }
+
+    pub fn quota_usage(&self, name: &str) -> QuotaUsage {
+        QuotaUsage::of(name, self.assignments.lock().unwrap().values())
+    }
+
+    /// Grubun assignment ID'leri (sıralı)
+    pub fn quota_members(&self, name: &str) -> Vec<u32> {
+        let assignments = self.assignments.lock().unwrap();
+        let mut ids: Vec<u32> = assignments
+            .values()
+            .filter(|a| a.quota_group.as_deref() == Some(name))
+            .map(|a| a.id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Süreci assignment'ın cgroup'una taşı (limitler o sürece uygulanır)
+    pub fn attach_process(&self, id: u32, pid: u32) -> std::io::Result<()> {
+        let assignments = self.assignments.lock().unwrap();
+        let path = assignments
+            .get(&id)
+            .and_then(|a| a.cgroup_path.clone())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("assignment {} has no cgroup", id)))?;
+        cgroup::attach(std::path::Path::new(&path), pid)
+    }
+
+    /// Assignment süreçlerini dondur / çöz (kullanıcı oturumu askıya alınırken)
+    pub fn set_frozen(&self, id: u32, frozen: bool) -> std::io::Result<()> {
+        let assignments = self.assignments.lock().unwrap();
+        let path = assignments
+            .get(&id)
+            .and_then(|a| a.cgroup_path.clone())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("assignment {} has no cgroup", id)))?;
+        cgroup::freeze(std::path::Path::new(&path), frozen)
+    }
+
+    /// Assignment'ın gerçek kullanımı; cgroup yoksa None
+    pub fn resource_usage(&self, id: u32) -> Option<CgroupUsage> {
+        let assignments = self.assignments.lock().unwrap();
+        self.resource_manager.usage(assignments.get(&id)?)
+    }
+
+    /// Assignment süreçlerinin gerçek VRAM kullanımı; ölçülemezse None
+    pub fn gpu_usage(&self, id: u32) -> Option<GpuMemoryUsage> {
+        let assignments = self.assignments.lock().unwrap();
+        self.resource_manager.gpu_usage(assignments.get(&id)?)
+    }
+
+    /// Dry-run: run_cycle'ın yapacaklarını uygulamadan döndür
+    pub fn dry_run_cycle(&self) -> CyclePlan {
+        self.preview_with(&[])
+    }
+
+    /// `extra` eklenseydi döngü ne yapardı (ör. ağır pencere önizlemesi)
+    pub fn preview_with(&self, extra: &[Assignment]) -> CyclePlan {
+        let assignments = self.assignments.lock().unwrap();
+        self.resource_manager.plan_cycle(&assignments, extra)
     }
 
     /// Yardımcı: Aktif assignment listesi
@@ -91,6 +421,84 @@ impl WBackend {
         assignments.values().cloned().collect()
     }
 
+    /// Yürütme modu başına toplanmış CPU/GPU iş istatistiği
+    pub fn mode_stats(&self) -> ExecutionStats {
+        let assignments = self.assignments.lock().unwrap();
+        let mut totals = ExecutionStats::new();
+        for a in assignments.values() {
+            totals.entry(a.execution_mode).or_default().merge(&a.stats());
+        }
+        totals
+    }
+
+    /// Tüm assignment ve lease durumunu dondur (binary upgrade öncesi)
+    pub fn snapshot(&self) -> BackendSnapshot {
+        let assignments = self.assignments.lock().unwrap();
+        let now = self.clock.now();
+        let mut entries: Vec<AssignmentSnapshot> = assignments.values().map(|a| a.snapshot_at(now)).collect();
+        entries.sort_by_key(|a| a.id);
+        BackendSnapshot::new(self.mode, entries)
+    }
+
+    /// Snapshot'taki assignment'ları geri yükle; aynı ID'li mevcut assignment'ın yerini alır.
+    /// Task thread'leri exec'ten sağ çıkmaz, aktif olanlar Auto modda yeniden başlatılır.
+    pub fn restore(&self, snapshot: &BackendSnapshot) -> Result<usize, SnapshotError> {
+        snapshot.check_version()?;
+
+        let mut assignments = self.assignments.lock().unwrap();
+        let mut ids = self.ids.lock().unwrap();
+        let now = self.clock.now();
+        for entry in &snapshot.assignments {
+            ids.reserve(entry.id);
+            let mut assignment = Assignment::from_snapshot_at(entry, now);
+            if entry.task_active && self.mode == ResourceMode::Auto {
+                assignment.start_task();
+            }
+            if let Some(mut old) = assignments.insert(entry.id, assignment) {
+                old.stop_task();
+            }
+        }
+        drop(ids);
+        self.sync_run_record(&assignments);
+
+        tracing::info!(count = snapshot.assignments.len(), "assignments restored from snapshot");
+        Ok(snapshot.assignments.len())
+    }
+
+    /// Önceki çalışmanın çökmeden kalan assignment'larını temizle (bkz. recovery),
+    /// sonra bu çalışmanın assignment'larını `state_dir` altına kaydetmeye başla.
+    /// Kaydın sahibi hâlâ çalışıyorsa kayıt ona bırakılır.
+    pub fn recover_previous_run(&self, state_dir: &Path) -> io::Result<RecoveryReport> {
+        // Dosya sistemi işleri sürerken assignment'lar kilitli kalmaz; bu sürecin
+        // cgroup'ları kendi PID'iyle etiketli olduğundan temizlik onlara dokunmaz
+        let in_use: HashSet<String> =
+            self.assignments.lock().unwrap().values().filter_map(|a| a.cgroup_path.clone()).collect();
+        let record_path = state_dir.join(recovery::RECORD_FILE);
+        let report = recovery::recover(&record_path, self.resource_manager.cgroups().as_ref(), &in_use)?;
+        if !report.owner_alive {
+            *self.run_record.lock().unwrap() = Some((record_path, RunRecord::default()));
+            self.sync_run_record(&self.assignments.lock().unwrap());
+        }
+        Ok(report)
+    }
+
+    /// Assignment kümesi ya da cgroup'ları değiştiyse kaydı yeniden yaz
+    fn sync_run_record(&self, assignments: &HashMap<u32, Assignment>) {
+        let mut run_record = self.run_record.lock().unwrap();
+        let Some((path, written)) = run_record.as_mut() else { return };
+        let mut entries: Vec<(u32, Option<String>)> =
+            assignments.values().map(|a| (a.id, a.cgroup_path.clone())).collect();
+        entries.sort_by_key(|(id, _)| *id);
+        let record = RunRecord { pid: std::process::id(), assignments: entries };
+        if *written == record {
+            return;
+        }
+        match record.write(path) {
+            Ok(()) => *written = record,
+            Err(e) => tracing::warn!(path = %path.display(), error = %e, "run record could not be written"),
+        }
+    }
+
     /// Yardımcı: ID ile assignment al
     pub fn get_assignment(&self, id: u32) -> Option<Assignment> {
         let assignments = self.assignments.lock().unwrap();
@@ -1,7 +1,6 @@
 // src/main.rs
-use wbackend::{Assignment, ExecutionMode, ResourceMode, WBackend};
+use wbackend::{Assignment, CycleCadence, ExecutionMode, ResourceMode, WBackend};
 use clap::Parser;
-use std::thread;
 use std::time::Duration;
 
 /// WASMA – Resource-first Runtime Authority (January 02, 2026)
@@ -19,6 +18,28 @@ struct Cli {
     #[arg(short, long, default_value_t = 10)]
     cycles: usize,
 
+    /// Seconds between allocation/schedule passes
+    #[arg(long, default_value_t = 2.0)]
+    interval: f64,
+
+    /// Seconds between lease enforcement passes (default: --interval)
+    #[arg(long)]
+    lease_interval: Option<f64>,
+
+    /// Seconds between monitor reports (default: --interval)
+    #[arg(long)]
+    monitor_interval: Option<f64>,
+
+    /// Drive the backend with the tokio run loop instead of the blocking cycle loop
+    #[cfg(feature = "async")]
+    #[arg(long = "async")]
+    run_async: bool,
+
+    /// Log output format
+    #[cfg(feature = "tracing-subscriber")]
+    #[arg(long, value_enum, default_value = "text")]
+    log_format: LogFormat,
+
     /// Add assignment
     #[command(subcommand)]
     command: Option<Commands>,
@@ -37,8 +58,31 @@ enum Commands {
     },
 }
 
+/// Log kayıt biçimi; json journald/ELK içindir
+#[cfg(feature = "tracing-subscriber")]
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+/// Kayıtlar stderr'e; seviye RUST_LOG ile değişir (varsayılan info)
+#[cfg(feature = "tracing-subscriber")]
+fn init_logging(format: LogFormat) {
+    use tracing_subscriber::EnvFilter;
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter).with_writer(std::io::stderr);
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().with_current_span(true).with_span_list(true).init(),
+    }
+}
+
 fn main() {
     let cli = Cli::parse();
+    #[cfg(feature = "tracing-subscriber")]
+    init_logging(cli.log_format);
 
     println!("🌀 WASMA v1.0 starting – Authority active (January 02, 2026)");
     println!(
@@ -51,11 +95,33 @@ fn main() {
         }
     );
 
+    let seconds = |value: f64| {
+        Duration::try_from_secs_f64(value).unwrap_or_else(|_| {
+            eprintln!("❌ Invalid interval: {}", value);
+            std::process::exit(1);
+        })
+    };
+    let interval = seconds(cli.interval);
+    let cadence = CycleCadence {
+        allocation: interval,
+        leases: cli.lease_interval.map_or(interval, seconds),
+        monitor: cli.monitor_interval.map_or(interval, seconds),
+    };
+
     let backend = WBackend::new(cli.mode);
+    backend.set_cadence(cadence);
 
     // Add assignments from CLI
     match cli.command {
         Some(Commands::Add { id, exec }) => {
+            match exec.check_gpu(wbackend::assignment::detect_gpu().is_some()) {
+                Ok(Some(warning)) => println!("⚠️  {}", warning),
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("❌ {}", e);
+                    std::process::exit(1);
+                }
+            }
             let mut assignment = Assignment::new(id);
             assignment.execution_mode = exec;
             backend.add_assignment(assignment);
@@ -79,13 +145,27 @@ fn main() {
         }
     }
 
-    // Cycle loop
+    // Monitor raporları konsola; backend artık kendisi yazdırmaz
+    let reports = backend.subscribe_reports();
+    std::thread::spawn(move || {
+        for report in reports {
+            println!("{}", report);
+        }
+    });
+
+    #[cfg(feature = "async")]
+    if cli.run_async {
+        run_async(backend, cli.cycles, interval);
+        println!("🏁 WASMA completed – Authority shutting down.");
+        return;
+    }
+
+    // Cycle loop: her aşama kendi aralığında; yeni assignment ve lease olayları erken uyandırır
     let cycle_count = if cli.cycles == 0 { usize::MAX } else { cli.cycles };
 
     for i in 1..=cycle_count {
-        backend.run_cycle();
-
-        thread::sleep(Duration::from_secs(2));
+        backend.run_due();
+        backend.wait_for_work();
 
         if i % 5 == 0 || i == 1 {
             println!("🔄 Cycle {} completed\n", i);
@@ -98,3 +178,25 @@ fn main() {
 
     println!("🏁 WASMA completed – Authority shutting down.");
 }
+
+/// Async sürücü: `cycles` kadar döngü süresi (0 = sonsuz) sonra kapanış sinyali
+#[cfg(feature = "async")]
+fn run_async(backend: WBackend, cycles: usize, interval: Duration) {
+    use std::sync::Arc;
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .expect("tokio runtime");
+    let (handle, signal) = wbackend::shutdown_channel();
+
+    runtime.block_on(async move {
+        let run = tokio::spawn(Arc::new(backend).run_async(interval, signal));
+        if cycles == 0 {
+            std::future::pending::<()>().await;
+        }
+        tokio::time::sleep(interval * cycles as u32).await;
+        handle.shutdown();
+        let _ = run.await;
+    });
+}
@@ -0,0 +1,330 @@
+// src/osd.rs
+// WASMA - On-Screen Display
+// Kısa ömürlü bildirimler (ses, parlaklık, odak değişimi, lease uyarıları).
+// Hangi process göstermeyi üstlenirse (GUI / compositor) `serve()` çağırır,
+// diğer process'ler (backend, UBIN uygulamaları) `show()` ile soket üzerinden iletir.
+// Kilit tutan kod (lease denetimi) `post()` kullanır: soket IPC'si ayrı bir
+// iş parçacığında yapılır, çağıran beklemez.
+
+use std::collections::VecDeque;
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Aynı anda ekranda tutulacak en fazla toast sayısı
+pub const MAX_VISIBLE: usize = 4;
+
+/// OSD ikon türleri
+#[derive(Debug, Clone, PartialEq)]
+pub enum OsdIcon {
+    Volume,
+    Brightness,
+    Focus,
+    Lease,
+    Warning,
+    Info,
+}
+
+impl OsdIcon {
+    /// Ekranda gösterilecek sembol
+    pub fn glyph(&self) -> &'static str {
+        match self {
+            OsdIcon::Volume => "🔊",
+            OsdIcon::Brightness => "☀️",
+            OsdIcon::Focus => "👁️",
+            OsdIcon::Lease => "⏳",
+            OsdIcon::Warning => "⚠️",
+            OsdIcon::Info => "ℹ️",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OsdIcon::Volume => "volume",
+            OsdIcon::Brightness => "brightness",
+            OsdIcon::Focus => "focus",
+            OsdIcon::Lease => "lease",
+            OsdIcon::Warning => "warning",
+            OsdIcon::Info => "info",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "volume" => Some(OsdIcon::Volume),
+            "brightness" => Some(OsdIcon::Brightness),
+            "focus" => Some(OsdIcon::Focus),
+            "lease" => Some(OsdIcon::Lease),
+            "warning" => Some(OsdIcon::Warning),
+            "info" => Some(OsdIcon::Info),
+            _ => None,
+        }
+    }
+}
+
+/// Tek bir OSD bildirimi
+#[derive(Debug, Clone)]
+pub struct OsdMessage {
+    pub id: u64,
+    pub text: String,
+    pub icon: OsdIcon,
+    pub duration: Duration,
+    pub shown_at: Instant,
+}
+
+impl OsdMessage {
+    pub fn expired(&self) -> bool {
+        self.shown_at.elapsed() >= self.duration
+    }
+
+    /// Kalan görünürlük oranı (1.0 → yeni, 0.0 → bitti); fade için
+    pub fn remaining_fraction(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 0.0;
+        }
+        let left = self.duration.saturating_sub(self.shown_at.elapsed());
+        left.as_secs_f32() / self.duration.as_secs_f32()
+    }
+}
+
+static QUEUE: OnceLock<Mutex<VecDeque<OsdMessage>>> = OnceLock::new();
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static SERVING: AtomicBool = AtomicBool::new(false);
+static POSTED: OnceLock<Sender<(String, OsdIcon, Duration)>> = OnceLock::new();
+
+fn queue() -> &'static Mutex<VecDeque<OsdMessage>> {
+    QUEUE.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+/// OSD soketinin varsayılan konumu: <runtime_dir>/osd.sock
+#[cfg(unix)]
+pub fn default_socket_path() -> PathBuf {
+    crate::rundir::runtime_dir().join("osd.sock")
+}
+
+/// Bildirim göster.
+/// Bu process OSD'yi çiziyorsa kuyruğa ekler; değilse çalışan OSD sunucusuna iletir,
+/// sunucu yoksa yerel kuyruğa düşer. Dönen id `dismiss()` için kullanılabilir.
+pub fn show(text: impl Into<String>, icon: OsdIcon, duration: Duration) -> u64 {
+    let text = text.into();
+
+    #[cfg(unix)]
+    if !SERVING.load(Ordering::SeqCst) {
+        if let Ok(id) = forward(&default_socket_path(), &text, &icon, duration) {
+            return id;
+        }
+    }
+
+    push_local(text, icon, duration)
+}
+
+/// Beklemeyen `show()`: bildirim kuyruğa girer, IPC'yi OSD iş parçacığı yapar.
+/// Kilit tutan çağıranlar için; yavaş bir OSD sunucusu onları durdurmaz.
+pub fn post(text: impl Into<String>, icon: OsdIcon, duration: Duration) {
+    let sender = POSTED.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<(String, OsdIcon, Duration)>();
+        std::thread::spawn(move || {
+            for (text, icon, duration) in rx {
+                show(text, icon, duration);
+            }
+        });
+        tx
+    });
+    sender.send((text.into(), icon, duration)).ok();
+}
+
+fn push_local(text: String, icon: OsdIcon, duration: Duration) -> u64 {
+    let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+    let mut q = queue().lock().unwrap();
+
+    // Aynı ikonlu eski toast'ı değiştir (ör. art arda ses değişimi tek toast olarak kalsın)
+    q.retain(|m| m.icon != icon || matches!(icon, OsdIcon::Warning | OsdIcon::Lease | OsdIcon::Info));
+
+    q.push_back(OsdMessage {
+        id,
+        text,
+        icon,
+        duration,
+        shown_at: Instant::now(),
+    });
+
+    while q.len() > MAX_VISIBLE {
+        q.pop_front();
+    }
+
+    id
+}
+
+/// Süresi dolanları temizleyip görünür bildirimleri döndür
+pub fn active() -> Vec<OsdMessage> {
+    let mut q = queue().lock().unwrap();
+    q.retain(|m| !m.expired());
+    q.iter().cloned().collect()
+}
+
+/// Bildirimi erken kapat
+pub fn dismiss(id: u64) -> bool {
+    let mut q = queue().lock().unwrap();
+    let before = q.len();
+    q.retain(|m| m.id != id);
+    q.len() != before
+}
+
+/// Tüm bildirimleri temizle
+pub fn clear() {
+    queue().lock().unwrap().clear();
+}
+
+/// Bu process'i OSD sunucusu yap: diğer process'lerden gelen bildirimleri kabul eder.
+/// Protokol (satır bazlı): `SHOW <icon> <duration_ms> <text>` → `OK <id>` | `ERR <sebep>`
+#[cfg(unix)]
+pub fn serve(socket_path: PathBuf) -> std::io::Result<()> {
+    if let Some(parent) = socket_path.parent() {
+        crate::rundir::ensure_private(parent)?;
+    }
+    std::fs::remove_file(&socket_path).ok();
+
+    let listener = UnixListener::bind(&socket_path)?;
+    SERVING.store(true, Ordering::SeqCst);
+
+    tracing::info!(socket = %socket_path.display(), "OSD server listening");
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().map_while(Result::ok) {
+            std::thread::spawn(move || {
+                if let Err(e) = serve_client(stream) {
+                    tracing::warn!(error = %e, "OSD client error");
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn serve_client(stream: UnixStream) -> std::io::Result<()> {
+    // Yalnızca aynı kullanıcının process'leri bildirim gösterebilir
+    #[cfg(target_os = "linux")]
+    {
+        let uid = peer_uid(&stream)?;
+        if uid != unsafe { libc::geteuid() } {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("connection from uid {} refused", uid),
+            ));
+        }
+    }
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let reply = handle_request(&line?);
+        writeln!(writer, "{}", reply)?;
+    }
+    Ok(())
+}
+
+/// Soketin öbür ucundaki process'in uid'i (SO_PEERCRED)
+#[cfg(target_os = "linux")]
+fn peer_uid(stream: &UnixStream) -> std::io::Result<u32> {
+    use std::os::fd::AsRawFd;
+
+    let mut cred = libc::ucred { pid: 0, uid: 0, gid: 0 };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let rc = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(cred.uid)
+}
+
+/// Tek bir protokol satırını işle
+pub fn handle_request(line: &str) -> String {
+    let mut parts = line.trim().splitn(4, ' ');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some("SHOW"), Some(icon), Some(ms), Some(text)) => {
+            let Some(icon) = OsdIcon::from_name(icon) else {
+                return format!("ERR unknown icon {}", icon);
+            };
+            let Ok(ms) = ms.parse::<u64>() else {
+                return format!("ERR invalid duration {}", ms);
+            };
+            let id = push_local(text.to_string(), icon, Duration::from_millis(ms));
+            format!("OK {}", id)
+        }
+        (Some("DISMISS"), Some(id), None, None) => match id.parse::<u64>() {
+            Ok(id) if dismiss(id) => "OK".to_string(),
+            Ok(id) => format!("ERR no message {}", id),
+            Err(_) => format!("ERR invalid id {}", id),
+        },
+        _ => format!("ERR malformed request: {}", line.trim()),
+    }
+}
+
+#[cfg(unix)]
+fn forward(socket_path: &PathBuf, text: &str, icon: &OsdIcon, duration: Duration) -> std::io::Result<u64> {
+    let mut stream = UnixStream::connect(socket_path)?;
+    stream.set_read_timeout(Some(Duration::from_millis(500)))?;
+
+    // Protokol satır bazlı; metin tek satıra indirgenir
+    let text = text.replace('\n', " ");
+    writeln!(stream, "SHOW {} {} {}", icon.name(), duration.as_millis(), text)?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+
+    reply
+        .trim()
+        .strip_prefix("OK ")
+        .and_then(|id| id.parse().ok())
+        .ok_or_else(|| std::io::Error::other(reply.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_show_and_dismiss() {
+        let reply = handle_request("SHOW volume 5000 Volume 40%");
+        let id: u64 = reply.strip_prefix("OK ").unwrap().parse().unwrap();
+        assert!(active().iter().any(|m| m.id == id && m.text == "Volume 40%"));
+
+        // Aynı ikon tek toast olarak kalır
+        handle_request("SHOW volume 5000 Volume 45%");
+        assert_eq!(active().iter().filter(|m| m.icon == OsdIcon::Volume).count(), 1);
+
+        assert!(handle_request("SHOW sparkles 100 nope").starts_with("ERR"));
+        assert!(handle_request("SHOW info soon nope").starts_with("ERR"));
+        assert!(handle_request(&format!("DISMISS {}", id)).starts_with("ERR"));
+    }
+
+    #[test]
+    fn test_post_queues_for_osd_thread() {
+        post("posted lease warning", OsdIcon::Warning, Duration::from_secs(30));
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !active().iter().any(|m| m.text == "posted lease warning") {
+            assert!(Instant::now() < deadline, "posted message never shown");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_expiry() {
+        let id = push_local("gone".to_string(), OsdIcon::Lease, Duration::ZERO);
+        assert!(!active().iter().any(|m| m.id == id));
+    }
+}
@@ -0,0 +1,113 @@
+// src/plan.rs
+// WASMA - Dry-run kaynak döngüsü
+// run_cycle'ın yapacağı bind/başlatma/preempt işlemlerini uygulamadan hesaplar.
+// `wasma cycle --dry-run` ve GUI'deki "ağır pencere önizleme" bu planı gösterir.
+
+use crate::assignment::ExecutionMode;
+use crate::resource_manager::ResourceMode;
+use serde::{Deserialize, Serialize};
+
+/// Döngünün bir assignment'a uygulayacağı tek işlem
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PlannedAction {
+    /// İlk uygun çekirdeğe pin
+    BindCpu { core: usize },
+    BindGpu { device: String },
+    /// GPU istendi ama bulunamadı; iş CPU'da kalır
+    GpuUnavailable,
+    StartTask,
+    StartLease { secs: u64 },
+    /// Lease bitmek üzere, OSD uyarısı gösterilecek
+    LeaseWarning { remaining_secs: u64 },
+    /// Lease bitti: task durdurulur, assignment kaldırılır
+    Preempt,
+}
+
+/// Tek assignment için plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssignmentPlan {
+    pub id: u32,
+    pub execution_mode: ExecutionMode,
+    /// Henüz eklenmemiş (önizleme) assignment
+    pub is_new: bool,
+    pub ram_mb: u64,
+    pub vram_mb: u64,
+    pub actions: Vec<PlannedAction>,
+}
+
+impl AssignmentPlan {
+    pub fn preempted(&self) -> bool {
+        self.actions.contains(&PlannedAction::Preempt)
+    }
+}
+
+/// Bir döngünün tamamı; hiçbir şey uygulanmamıştır
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CyclePlan {
+    pub mode: ResourceMode,
+    pub assignments: Vec<AssignmentPlan>,
+    /// Döngü sonrası ayakta kalan assignment'ların toplam limiti
+    pub ram_mb: u64,
+    pub vram_mb: u64,
+    /// /proc/meminfo MemTotal; okunamazsa None
+    pub system_ram_mb: Option<u64>,
+    pub warnings: Vec<String>,
+}
+
+impl CyclePlan {
+    pub fn preempted(&self) -> Vec<u32> {
+        self.assignments.iter().filter(|a| a.preempted()).map(|a| a.id).collect()
+    }
+
+    /// Hiçbir assignment'a işlem yapılmayacak mı
+    pub fn is_noop(&self) -> bool {
+        self.assignments.iter().all(|a| a.actions.is_empty())
+    }
+
+    pub fn ram_overcommitted(&self) -> bool {
+        self.system_ram_mb.is_some_and(|total| self.ram_mb > total)
+    }
+}
+
+/// Sistem RAM'i (MiB)
+pub fn system_ram_mb() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    meminfo
+        .lines()
+        .find(|l| l.starts_with("MemTotal:"))
+        .and_then(|l| l.split_whitespace().nth(1))
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb / 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Assignment, ResourceMode, WBackend};
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_dry_run_plan() {
+        let backend = WBackend::new(ResourceMode::Manual);
+        let mut expired = Assignment::new(1);
+        expired.start_lease(Duration::ZERO);
+        backend.add_assignment(expired);
+        let mut running = Assignment::new(2);
+        running.start_lease(Duration::from_secs(60));
+        backend.add_assignment(running);
+
+        let plan = backend.dry_run_cycle();
+        assert_eq!(plan.preempted(), vec![1]);
+        // Dry run hiçbir şeyi uygulamaz
+        assert!(backend.get_assignment(1).is_some());
+
+        let mut heavy = Assignment::new(3);
+        heavy.ram_limit = 4096 * 1024 * 1024;
+        let preview = backend.preview_with(&[heavy]);
+        let new = preview.assignments.iter().find(|a| a.id == 3).unwrap();
+        assert!(new.is_new);
+        assert_eq!(new.actions, vec![PlannedAction::StartLease { secs: 30 }]);
+        assert_eq!(preview.ram_mb, plan.ram_mb + 4096);
+        assert!(backend.get_assignment(3).is_none());
+    }
+}
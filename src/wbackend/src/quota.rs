@@ -0,0 +1,153 @@
+// src/quota.rs
+// WASMA - Kota grupları
+// Birden çok assignment ortak bir RAM/VRAM/CPU bütçesini paylaşır
+// (ör. wasma.in.conf'ta "browser: 4GB"). Grup doluyken yeni assignment
+// reddedilir; tahliye (en uzun süredir odaklanmamış pencere) istemcinin işidir.
+
+use crate::assignment::Assignment;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Grup bütçesi aşıldığında yapılacak
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuotaOverflow {
+    /// Yeni assignment reddedilir
+    #[default]
+    Reject,
+    /// Grubun en uzun süredir odaklanmamış üyesi kapatılıp tekrar denenir
+    EvictLeastRecentlyFocused,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaGroup {
+    pub name: String,
+    /// Üyelerin ram_limit toplamı için üst sınır (byte)
+    pub ram_limit: usize,
+    /// vram_limit toplamı; None sınırsız
+    pub vram_limit: Option<usize>,
+    /// Bağlı çekirdek toplamı; None sınırsız
+    pub cpu_cores: Option<usize>,
+    pub on_full: QuotaOverflow,
+}
+
+impl QuotaGroup {
+    pub fn new(name: impl Into<String>, ram_limit: usize) -> Self {
+        QuotaGroup { name: name.into(), ram_limit, vram_limit: None, cpu_cores: None, on_full: QuotaOverflow::default() }
+    }
+}
+
+/// Grubun o anki dolulukları
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaUsage {
+    pub ram: usize,
+    pub vram: usize,
+    pub cpu_cores: usize,
+    pub members: usize,
+}
+
+impl QuotaUsage {
+    pub fn of<'a>(group: &str, assignments: impl IntoIterator<Item = &'a Assignment>) -> Self {
+        let mut usage = QuotaUsage::default();
+        for a in assignments.into_iter().filter(|a| a.quota_group.as_deref() == Some(group)) {
+            usage.add(a);
+        }
+        usage
+    }
+
+    fn add(&mut self, assignment: &Assignment) {
+        self.ram += assignment.ram_limit;
+        self.vram += assignment.vram_limit;
+        self.cpu_cores += assignment.cpu_cores.len();
+        self.members += 1;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuotaError {
+    /// Assignment tanımlı olmayan bir gruba atanmış
+    UnknownGroup(String),
+    /// Eklenirse grubun `resource` bütçesi aşılır
+    Exceeded { group: String, resource: &'static str, requested: usize, available: usize },
+}
+
+impl fmt::Display for QuotaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotaError::UnknownGroup(group) => write!(f, "unknown quota group '{}'", group),
+            QuotaError::Exceeded { group, resource, requested, available } => write!(
+                f,
+                "quota group '{}' is full: {} requested {}, {} available",
+                group, resource, requested, available
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QuotaError {}
+
+/// `candidate` gruba sığar mı; `others` aynı ID'li assignment'ı içerebilir (yerine geçer)
+pub fn check<'a>(
+    group: &QuotaGroup,
+    candidate: &Assignment,
+    others: impl IntoIterator<Item = &'a Assignment>,
+) -> Result<(), QuotaError> {
+    let used = QuotaUsage::of(&group.name, others.into_iter().filter(|a| a.id != candidate.id));
+    let exceeded = |resource, requested: usize, used: usize, limit: usize| {
+        (used + requested > limit).then(|| QuotaError::Exceeded {
+            group: group.name.clone(),
+            resource,
+            requested,
+            available: limit.saturating_sub(used),
+        })
+    };
+
+    let over = exceeded("ram", candidate.ram_limit, used.ram, group.ram_limit)
+        .or_else(|| group.vram_limit.and_then(|limit| exceeded("vram", candidate.vram_limit, used.vram, limit)))
+        .or_else(|| group.cpu_cores.and_then(|limit| exceeded("cpu_cores", candidate.cpu_cores.len(), used.cpu_cores, limit)));
+    match over {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ResourceMode, WBackend};
+
+    fn member(id: u32, ram_mb: usize) -> Assignment {
+        let mut assignment = Assignment::new(id);
+        assignment.ram_limit = ram_mb << 20;
+        assignment.quota_group = Some("browser".to_string());
+        assignment
+    }
+
+    #[test]
+    fn test_quota_group_shares_budget() {
+        let backend = WBackend::new(ResourceMode::Manual);
+        assert_eq!(backend.try_add_assignment(member(1, 1024)), Err(QuotaError::UnknownGroup("browser".to_string())));
+
+        backend.set_quota_group(QuotaGroup::new("browser", 4096 << 20));
+        backend.try_add_assignment(member(1, 2048)).unwrap();
+        backend.try_add_assignment(member(2, 1024)).unwrap();
+
+        let err = backend.try_add_assignment(member(3, 2048)).unwrap_err();
+        assert_eq!(
+            err,
+            QuotaError::Exceeded { group: "browser".to_string(), resource: "ram", requested: 2048 << 20, available: 1024 << 20 }
+        );
+        // Grup dışı assignment etkilenmez
+        backend.try_add_assignment(Assignment::new(4)).unwrap();
+
+        // Aynı ID'nin güncellenmesi kendi payıyla yarışmaz
+        backend.try_add_assignment(member(2, 2048)).unwrap();
+        assert_eq!(backend.quota_usage("browser").ram, 4096 << 20);
+        assert_eq!(backend.quota_members("browser"), vec![1, 2]);
+
+        // Ayrılan üye yer açar
+        assert!(backend.leave_quota_group(1));
+        assert!(!backend.leave_quota_group(1));
+        backend.try_add_assignment(member(3, 2048)).unwrap();
+        assert_eq!(backend.quota_members("browser"), vec![2, 3]);
+    }
+}
@@ -0,0 +1,259 @@
+// src/recovery.rs
+// WASMA - Çökme sonrası temizlik
+// Çalışan backend assignment'larını (ID + cgroup yolu) durum dizinindeki
+// assignments.list dosyasına yazar. WASMA çökerse bu kayıt kalır; bir sonraki
+// açılışta recover() kaydın sahibi olan süreç ölmüşse lease'leri bırakır ve
+// onun cgroup'larını siler. cgroup'lar (assignment-<pid>-<id>) sahip PID ile
+// etiketlenir; yalnızca etiketi ölü kaydın PID'i olanlara dokunulur, başka
+// bir WASMA örneğinin kaynakları yerinde kalır. Paylaşılan bellek buffer'ları
+// memfd'dir (adı yok, /dev/shm'de durmaz); süreçle birlikte çekirdek serbest
+// bırakır, temizlenecek bir şey kalmaz.
+//
+// Dosya biçimi satır tabanlı:
+//   pid <pid>
+//   assignment <id> [<cgroup yolu>]
+
+use crate::cgroup::{Cgroups, ASSIGNMENT_PREFIX};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Durum dizinindeki kayıt dosyası
+pub const RECORD_FILE: &str = "assignments.list";
+
+/// "<önek><pid>-..." adındaki sahip PID
+fn owner_tag(name: &str, prefix: &str) -> Option<u32> {
+    let (pid, _) = name.strip_prefix(prefix)?.split_once('-')?;
+    pid.parse().ok()
+}
+
+/// Yolun son bileşeni `prefix` ve `pid` ile etiketli mi
+fn tagged_by(path: &Path, prefix: &str, pid: u32) -> bool {
+    path.file_name()
+        .and_then(|name| owner_tag(&name.to_string_lossy(), prefix))
+        == Some(pid)
+}
+
+/// Bir çalışmanın assignment kaydı
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunRecord {
+    pub pid: u32,
+    /// (assignment ID, cgroup yolu)
+    pub assignments: Vec<(u32, Option<String>)>,
+}
+
+impl RunRecord {
+    pub fn render(&self) -> String {
+        let mut text = format!("pid {}\n", self.pid);
+        for (id, cgroup) in &self.assignments {
+            match cgroup {
+                Some(path) => text.push_str(&format!("assignment {} {}\n", id, path)),
+                None => text.push_str(&format!("assignment {}\n", id)),
+            }
+        }
+        text
+    }
+
+    /// Bozuk satırlar atlanır; pid satırı yoksa None
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut pid = None;
+        let mut assignments = Vec::new();
+        for line in text.lines() {
+            let mut parts = line.trim().splitn(3, ' ');
+            match (parts.next(), parts.next().and_then(|v| v.parse().ok())) {
+                (Some("pid"), Some(value)) => pid = Some(value),
+                (Some("assignment"), Some(id)) => {
+                    assignments.push((id, parts.next().map(str::to_string)));
+                }
+                _ => {}
+            }
+        }
+        Some(RunRecord { pid: pid?, assignments })
+    }
+
+    /// Geçici dosyaya yazıp taşı; yarım yazılmış kayıt kalmaz
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, self.render())?;
+        fs::rename(&tmp, path)
+    }
+}
+
+/// Temizlenenlerin dökümü
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecoveryReport {
+    /// Kaydı bırakan önceki süreç
+    pub previous_pid: Option<u32>,
+    /// Kaydın sahibi hâlâ çalışıyor; hiçbir şeye dokunulmadı
+    pub owner_alive: bool,
+    /// Lease'i bırakılan assignment ID'leri
+    pub released_leases: Vec<u32>,
+    pub removed_cgroups: Vec<PathBuf>,
+}
+
+impl RecoveryReport {
+    /// Temizlenecek bir şey yoktu
+    pub fn is_empty(&self) -> bool {
+        self.released_leases.is_empty() && self.removed_cgroups.is_empty()
+    }
+}
+
+impl fmt::Display for RecoveryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.owner_alive {
+            return write!(f, "previous run (pid {}) is still running", self.previous_pid.unwrap_or_default());
+        }
+        match self.previous_pid {
+            Some(pid) => write!(f, "released {} stale lease(s) from pid {}", self.released_leases.len(), pid)?,
+            None => write!(f, "no previous run recorded")?,
+        }
+        write!(f, ", removed {} cgroup(s)", self.removed_cgroups.len())
+    }
+}
+
+/// Süreç hâlâ WASMA mı; PID başka bir programa geçmişse ölü sayılır
+fn is_running(pid: u32) -> bool {
+    match fs::read_to_string(format!("/proc/{}/comm", pid)) {
+        Ok(comm) => fs::read_to_string("/proc/self/comm").is_ok_and(|own| own == comm),
+        Err(_) => false,
+    }
+}
+
+/// Önceki çalışmanın artıklarını temizle: kayıttaki assignment'lar ile ölü
+/// PID'in etiketini taşıyan cgroup'lar. `in_use` bu süreçte
+/// zaten açılmış cgroup yollarıdır, silinmez. Kayıt dosyası sonunda kaldırılır;
+/// kayıt yoksa, sahibi yaşıyorsa ya da bu süreçse (re-exec) hiçbir şeye dokunulmaz.
+pub fn recover(
+    record_path: &Path,
+    cgroups: Option<&Cgroups>,
+    in_use: &HashSet<String>,
+) -> io::Result<RecoveryReport> {
+    let mut report = RecoveryReport::default();
+    let record = match fs::read_to_string(record_path) {
+        Ok(text) => RunRecord::parse(&text),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+
+    let Some(record) = record else { return Ok(report) };
+    report.previous_pid = Some(record.pid);
+    if record.pid == std::process::id() {
+        // Binary upgrade: exec PID'i korur, assignment'lar snapshot'tan geri gelir
+        return Ok(report);
+    }
+    if is_running(record.pid) {
+        report.owner_alive = true;
+        return Ok(report);
+    }
+
+    let reclaimable = |path: &Path| {
+        tagged_by(path, ASSIGNMENT_PREFIX, record.pid)
+            && !in_use.contains(path.to_string_lossy().as_ref())
+            && match cgroups {
+                Some(c) => path.parent() == Some(c.base()),
+                None => true,
+            }
+    };
+    for (id, cgroup) in &record.assignments {
+        report.released_leases.push(*id);
+        let Some(path) = cgroup.as_deref().map(Path::new) else { continue };
+        if reclaimable(path) && path.exists() {
+            remove_cgroup(cgroups, path, &mut report);
+        }
+    }
+
+    // Kayda girmeden (kayıt güncellenemeden) kalan, ölü PID'in cgroup'ları
+    if let Some(cgroups) = cgroups {
+        if let Ok(entries) = fs::read_dir(cgroups.base()) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if reclaimable(&path) && path.is_dir() && !report.removed_cgroups.contains(&path) {
+                    remove_cgroup(Some(cgroups), &path, &mut report);
+                }
+            }
+        }
+    }
+
+    fs::remove_file(record_path)?;
+    if !report.is_empty() {
+        tracing::info!(
+            previous_pid = report.previous_pid,
+            leases = report.released_leases.len(),
+            cgroups = report.removed_cgroups.len(),
+            "previous run cleaned up"
+        );
+    }
+    Ok(report)
+}
+
+fn remove_cgroup(cgroups: Option<&Cgroups>, path: &Path, report: &mut RecoveryReport) {
+    let result = match cgroups {
+        Some(cgroups) => cgroups.remove(path),
+        // cgroup muhasebesi kapalı; kalan dizin yine de boşsa silinir
+        None => fs::remove_dir(path),
+    };
+    match result {
+        Ok(()) => report.removed_cgroups.push(path.to_path_buf()),
+        Err(e) => tracing::warn!(path = %path.display(), error = %e, "stale cgroup could not be removed"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recover_crashed_run() {
+        let root = std::env::temp_dir().join(format!("wasma-recovery-test-{}", std::process::id()));
+        // Çöken çalışma u32::MAX - 1 (artık WASMA değil), u32::MAX - 2 başka bir örnek
+        let dead = u32::MAX - 1;
+        let cgroups = Cgroups::with_base(root.join("cgroup"));
+        let dead_cgroups = cgroups.clone().with_owner(dead);
+        let other_cgroups = cgroups.clone().with_owner(u32::MAX - 2);
+        let record_path = root.join("state").join(RECORD_FILE);
+        for path in [dead_cgroups.path_for(3), dead_cgroups.path_for(9), dead_cgroups.path_for(12), other_cgroups.path_for(3), cgroups.path_for(3)] {
+            fs::create_dir_all(path).unwrap();
+        }
+
+        // Kayıtta 3 ve 5 var; 9 kayda girmeden çökmüş. Başka örneğin yolu kayda sokulsa da silinmez
+        let record = RunRecord {
+            pid: dead,
+            assignments: vec![
+                (3, Some(dead_cgroups.path_for(3).to_string_lossy().into_owned())),
+                (5, None),
+                (7, Some(other_cgroups.path_for(3).to_string_lossy().into_owned())),
+            ],
+        };
+        record.write(&record_path).unwrap();
+        assert_eq!(RunRecord::parse(&fs::read_to_string(&record_path).unwrap()), Some(record));
+
+        // Ölü PID'in assignment-12'si bu süreçte yeniden açılmış
+        let in_use = HashSet::from([dead_cgroups.path_for(12).to_string_lossy().into_owned()]);
+        let report = recover(&record_path, Some(&cgroups), &in_use).unwrap();
+        assert_eq!(report.previous_pid, Some(dead));
+        assert!(!report.owner_alive);
+        assert_eq!(report.released_leases, vec![3, 5, 7]);
+        assert_eq!(report.removed_cgroups.len(), 2);
+        assert!(!dead_cgroups.path_for(3).exists() && !dead_cgroups.path_for(9).exists());
+        assert!(dead_cgroups.path_for(12).exists());
+        assert!(other_cgroups.path_for(3).exists() && cgroups.path_for(3).exists());
+        assert!(!record_path.exists());
+
+        // Kayıt yoksa hiçbir şey silinmez
+        let report = recover(&record_path, Some(&cgroups), &HashSet::new()).unwrap();
+        assert!(report.is_empty() && other_cgroups.path_for(3).exists());
+
+        // Re-exec sonrası kayıt bu sürecin; dokunulmaz
+        RunRecord { pid: std::process::id(), assignments: vec![(4, None)] }.write(&record_path).unwrap();
+        let report = recover(&record_path, Some(&cgroups), &HashSet::new()).unwrap();
+        assert!(!report.owner_alive && report.is_empty());
+        assert!(record_path.exists() && cgroups.path_for(3).exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
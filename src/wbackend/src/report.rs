@@ -0,0 +1,201 @@
+// src/report.rs
+// WASMA - Monitor raporu
+// Monitor aşaması artık stdout'a yazmaz: assignment durumlarını ve mod başına
+// iş dağılımını MonitorReport olarak üretir. run_cycle raporu döndürür,
+// subscribe_reports her monitor aşamasında bir kopya alır; CLI, GUI, metrik
+// dışa aktarımı ve webhook'lar aynı veriyi tüketir. Konsol çıktısı Display'dir.
+
+use crate::assignment::ExecutionMode;
+use crate::hybrid::ModeStats;
+use crate::resource_manager::ResourceMode;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Tek assignment'ın monitor anındaki durumu
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssignmentStatus {
+    pub id: u32,
+    pub task_running: bool,
+    pub execution_mode: ExecutionMode,
+    pub gpu_device: Option<String>,
+    pub cpu_cores: Vec<usize>,
+    pub ram_limit_mb: u64,
+    /// cgroup memory.current; cgroup yoksa None
+    pub ram_used_mb: Option<u64>,
+    /// Lease yoksa 0
+    pub lease_remaining_secs: u64,
+}
+
+/// Yürütme modu başına toplanmış CPU/GPU iş dağılımı
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModeSummary {
+    pub mode: ExecutionMode,
+    pub frames: u64,
+    pub cpu_units: u64,
+    pub cpu_busy: Duration,
+    pub gpu_units: u64,
+    pub gpu_busy: Duration,
+    pub cpu_fallbacks: u64,
+}
+
+impl ModeSummary {
+    pub fn of(mode: ExecutionMode, stats: &ModeStats) -> Self {
+        ModeSummary {
+            mode,
+            frames: stats.frames,
+            cpu_units: stats.cpu.units,
+            cpu_busy: stats.cpu.busy,
+            gpu_units: stats.gpu.units,
+            gpu_busy: stats.gpu.busy,
+            cpu_fallbacks: stats.cpu_fallbacks,
+        }
+    }
+
+    /// GPU'da yapılan işlerin oranı (0..=1)
+    pub fn gpu_share(&self) -> f64 {
+        let total = self.cpu_units + self.gpu_units;
+        if total == 0 { 0.0 } else { self.gpu_units as f64 / total as f64 }
+    }
+}
+
+/// Bir monitor aşamasının çıktısı
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorReport {
+    pub mode: ResourceMode,
+    /// ID'ye göre sıralı
+    pub assignments: Vec<AssignmentStatus>,
+    /// Moda göre sıralı
+    pub modes: Vec<ModeSummary>,
+    /// Bu döngüde lease'i bitip kaldırılan assignment'lar
+    pub expired: Vec<u32>,
+}
+
+impl MonitorReport {
+    pub fn assignment(&self, id: u32) -> Option<&AssignmentStatus> {
+        self.assignments.iter().find(|a| a.id == id)
+    }
+
+    /// Task'ı çalışan assignment sayısı
+    pub fn running(&self) -> usize {
+        self.assignments.iter().filter(|a| a.task_running).count()
+    }
+}
+
+impl fmt::Display for MonitorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\n🌀 WASMA v1.0 – Live Resource Monitor (2 Ocak 2026) 🌀\n")?;
+
+        if self.assignments.is_empty() {
+            return writeln!(f, "   📭 No active assignments currently.");
+        }
+
+        for a in &self.assignments {
+            let task_status = if a.task_running { "🟢 RUNNING" } else { "🔴 STOPPED" };
+
+            // Gerçek GPU durumuna göre akıllı sınıflandırma
+            let effective_mode = match a.gpu_device.as_deref() {
+                Some("nvidia-dgpu") => "🟢 Discrete GPU (NVIDIA dGPU)",
+                Some("amd-dgpu") => "🟢 Discrete GPU (AMD dGPU)",
+                Some("integrated-gpu") => "🟡 Integrated GPU (iGPU via /dev/dri)",
+                Some("apple-igpu") => "🟡 Apple Silicon iGPU",
+                Some("windows-igpu") => "🟡 Windows iGPU",
+                None => "🔵 Pure CPU-Only (No GPU available)",
+                _ => "🟢 GPU Active",
+            };
+            let requested_mode = match a.execution_mode {
+                ExecutionMode::CpuOnly => "Requested: Pure CPU",
+                ExecutionMode::GpuPreferred => "Requested: GPU Preferred",
+                ExecutionMode::GpuOnly => "Requested: Strict GPU",
+                ExecutionMode::Hybrid => "Requested: Full Hybrid",
+            };
+            let ram = match a.ram_used_mb {
+                Some(used) => format!("{:4}/{:4}", used, a.ram_limit_mb),
+                None => format!("{:4}", a.ram_limit_mb),
+            };
+
+            writeln!(
+                f,
+                "ID {:2} | {} | {} | {} | Cores: {:?} | GPU: {:18} | RAM: {} MiB | Lease: {}s",
+                a.id,
+                task_status,
+                effective_mode,
+                requested_mode,
+                a.cpu_cores,
+                a.gpu_device.as_deref().unwrap_or("None"),
+                ram,
+                a.lease_remaining_secs
+            )?;
+        }
+
+        // Mod başına CPU/GPU iş dağılımı
+        for m in &self.modes {
+            writeln!(
+                f,
+                "   {:?}: {} frames | CPU {} units ({:?}) | GPU {} units ({:?}) | {:.0}% GPU | {} CPU fallbacks",
+                m.mode,
+                m.frames,
+                m.cpu_units,
+                m.cpu_busy,
+                m.gpu_units,
+                m.gpu_busy,
+                m.gpu_share() * 100.0,
+                m.cpu_fallbacks
+            )?;
+        }
+
+        writeln!(f, "──────────────────────────────────────────────────────────────────────")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Assignment, Clock, CycleCadence, MockClock, WBackend};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_monitor_report() {
+        let clock = Arc::new(MockClock::new());
+        let backend = WBackend::with_clock(ResourceMode::Manual, clock.clone());
+        backend.set_cadence(CycleCadence {
+            allocation: Duration::from_secs(1),
+            leases: Duration::from_secs(1),
+            monitor: Duration::from_secs(10),
+        });
+        let reports = backend.subscribe_reports();
+        assert!(backend.last_report().is_none());
+
+        let mut gpu = Assignment::new(7);
+        gpu.execution_mode = ExecutionMode::GpuOnly;
+        backend.add_assignment(gpu);
+        let mut short = Assignment::new(3);
+        short.start_lease_at(Duration::from_secs(5), clock.now());
+        backend.add_assignment(short);
+
+        // Sıralı, yazdırmadan; abonelere ve last_report'a gider
+        let report = backend.run_cycle();
+        assert_eq!(report.mode, ResourceMode::Manual);
+        assert_eq!(report.assignments.iter().map(|a| a.id).collect::<Vec<_>>(), vec![3, 7]);
+        assert_eq!(report.assignment(3).unwrap().lease_remaining_secs, 5);
+        assert_eq!(report.assignment(7).unwrap().ram_limit_mb, 512);
+        assert_eq!(report.modes.iter().map(|m| m.mode).collect::<Vec<_>>(), vec![ExecutionMode::GpuPreferred, ExecutionMode::GpuOnly]);
+        assert!(report.expired.is_empty());
+        assert_eq!(reports.try_recv().unwrap(), report);
+        assert_eq!(backend.last_report(), Some(report.clone()));
+        assert!(report.to_string().contains("ID  3 |"));
+
+        // Monitor aşaması zamanı gelmeden rapor yok
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(backend.run_due(), vec![3]);
+        assert!(reports.try_recv().is_err());
+
+        // Bir sonraki rapor arada biten lease'leri değil, kendi döngüsününkini taşır
+        clock.advance(Duration::from_secs(5));
+        backend.run_due();
+        let report = reports.try_recv().unwrap();
+        assert!(report.expired.is_empty());
+        assert_eq!(report.running(), 0);
+        assert_eq!(report.assignments.len(), 1);
+    }
+}
@@ -1,10 +1,21 @@
 // src/resource_manager.rs
-use crate::assignment::{Assignment, ExecutionMode};
-use std::collections::HashMap;
+use crate::assignment::{self, Assignment, ExecutionMode};
+use crate::cgroup::{self, CgroupUsage, Cgroups};
+use crate::gpu_metrics::{self, GpuMemoryUsage};
+use crate::clock::{self, SharedClock};
+use crate::topology::{CoreUse, CpuTopology};
+use crate::hybrid::ModeStats;
+use crate::plan::{self, AssignmentPlan, CyclePlan, PlannedAction};
+use crate::osd::{self, OsdIcon};
+use crate::report::{AssignmentStatus, ModeSummary, MonitorReport};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Mutex, RwLock, RwLockReadGuard};
 use std::time::Duration;
 use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
 pub enum ResourceMode {
     #[clap(alias = "m")]
     Manual,
@@ -12,24 +23,195 @@ pub enum ResourceMode {
     Auto,
 }
 
+/// Lease bitmeden bu kadar süre önce OSD uyarısı gösterilir
+const LEASE_WARNING_WINDOW: Duration = Duration::from_secs(5);
+
+/// Süresi verilmemiş assignment'ların lease'i (wasma.in.conf ile değiştirilebilir)
+pub const DEFAULT_LEASE: Duration = Duration::from_secs(30);
+
+/// Lease'i biten assignment için hook kararı
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaseDecision {
+    /// Task durdurulur, assignment kaldırılır (varsayılan)
+    Release,
+    /// Lease aynı süreyle yenilenir, assignment kalır
+    Renew,
+}
+
+/// enforce_leases sırasında, assignment kilidi tutulurken çağrılır;
+/// hook WBackend'e geri çağrı yapmamalıdır
+pub type LeaseHook = Box<dyn Fn(&Assignment) -> LeaseDecision + Send + Sync>;
+
 pub struct ResourceManager {
     mode: ResourceMode,
+    // Uyarısı zaten gösterilmiş assignment'lar
+    lease_warned: Mutex<HashSet<u32>>,
+    clock: SharedClock,
+    // Çalışırken yeniden sınıflandırılabilir (bkz. reclassify_cores)
+    topology: RwLock<CpuTopology>,
+    default_lease: RwLock<Duration>,
+    lease_hooks: Mutex<Vec<LeaseHook>>,
+    // cgroup v2 yoksa None: limitler sadece kayıt olarak kalır
+    cgroups: RwLock<Option<Cgroups>>,
 }
 
 impl ResourceManager {
     pub fn new(mode: ResourceMode) -> Self {
-        ResourceManager { mode }
+        Self::with_clock(mode, clock::system())
+    }
+
+    /// Lease kararları `clock`'a göre verilir (testlerde MockClock)
+    pub fn with_clock(mode: ResourceMode, clock: SharedClock) -> Self {
+        ResourceManager {
+            mode,
+            lease_warned: Mutex::new(HashSet::new()),
+            clock,
+            topology: RwLock::new(CpuTopology::detect()),
+            default_lease: RwLock::new(DEFAULT_LEASE),
+            lease_hooks: Mutex::new(Vec::new()),
+            cgroups: RwLock::new(Cgroups::detect()),
+        }
+    }
+
+    pub fn default_lease(&self) -> Duration {
+        *self.default_lease.read().unwrap()
+    }
+
+    /// Bundan sonra başlatılan lease'ler bu süreyi kullanır
+    pub fn set_default_lease(&self, lease: Duration) {
+        *self.default_lease.write().unwrap() = lease;
+    }
+
+    /// Lease'i biten her assignment için `hook` sorulur; herhangi bir hook
+    /// Renew derse assignment kaldırılmaz
+    pub fn on_lease_expired(&self, hook: impl Fn(&Assignment) -> LeaseDecision + Send + Sync + 'static) {
+        self.lease_hooks.lock().unwrap().push(Box::new(hook));
+    }
+
+    pub fn cgroups(&self) -> Option<Cgroups> {
+        self.cgroups.read().unwrap().clone()
+    }
+
+    /// cgroup kökünü değiştir; None muhasebeyi kapatır
+    pub fn set_cgroups(&self, cgroups: Option<Cgroups>) {
+        *self.cgroups.write().unwrap() = cgroups;
+    }
+
+    /// Assignment'ın cgroup'unu oluştur ya da limitlerini yeniden yaz
+    pub fn sync_cgroup(&self, assignment: &mut Assignment) {
+        let Some(cgroups) = self.cgroups() else { return };
+        let result = match assignment.cgroup_path.as_deref() {
+            Some(path) => cgroup::apply_limits(Path::new(path), assignment).map(|_| None),
+            None => cgroups.create(assignment).map(Some),
+        };
+        match result {
+            Ok(Some(path)) => {
+                tracing::info!(assignment = assignment.id, path = %path.display(), "cgroup created");
+                assignment.cgroup_path = Some(path.to_string_lossy().into_owned());
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!(assignment = assignment.id, error = %e, "cgroup setup failed"),
+        }
     }
 
-    pub fn allocate(&self, assignment: &mut Assignment) {
+    /// Kernel'in ölçtüğü kullanım; cgroup yoksa None
+    pub fn usage(&self, assignment: &Assignment) -> Option<CgroupUsage> {
+        cgroup::usage(Path::new(assignment.cgroup_path.as_deref()?)).ok()
+    }
+
+    /// cgroup'taki süreçlerin gerçek VRAM kullanımı (NVML / DRM fdinfo)
+    pub fn gpu_usage(&self, assignment: &Assignment) -> Option<GpuMemoryUsage> {
+        let pids = cgroup::procs(Path::new(assignment.cgroup_path.as_deref()?)).ok()?;
+        gpu_metrics::processes_vram(&pids)
+    }
+
+    fn release_cgroup(&self, assignment: &mut Assignment) {
+        let (Some(cgroups), Some(path)) = (self.cgroups(), assignment.cgroup_path.take()) else { return };
+        if let Err(e) = cgroups.remove(Path::new(&path)) {
+            tracing::warn!(%path, error = %e, "cgroup could not be removed");
+        }
+    }
+
+    /// Kaldırılan assignment'ın task'ını durdur, cgroup'unu ve lease uyarısını bırak
+    pub fn release(&self, assignment: &mut Assignment) {
+        assignment.stop_task();
+        self.release_cgroup(assignment);
+        self.lease_warned.lock().unwrap().remove(&assignment.id);
+    }
+
+    pub fn topology(&self) -> RwLockReadGuard<'_, CpuTopology> {
+        self.topology.read().unwrap()
+    }
+
+    /// Topolojiyi değiştir; çekirdek sınıfı değişen CPU'ları döndürür
+    pub fn set_topology(&self, topology: CpuTopology) -> Vec<usize> {
+        let mut current = self.topology.write().unwrap();
+        let changed = topology
+            .cpus
+            .iter()
+            .filter(|cpu| current.class_of(cpu.id).is_some_and(|class| class != cpu.class))
+            .map(|cpu| cpu.id)
+            .collect();
+        *current = topology;
+        changed
+    }
+
+    /// Sınıfı değişen çekirdeklerdeki (ör. cpufreq sınırı, CPU hotplug) assignment'ları
+    /// istedikleri sınıfa yeniden bind et; taşınan assignment ID'lerini döndürür
+    pub fn reclassify_cores(&self, assignments: &mut HashMap<u32, Assignment>) -> Vec<u32> {
+        self.apply_topology(CpuTopology::detect(), assignments)
+    }
+
+    /// reclassify_cores'un `topology` verilmiş hali
+    pub fn apply_topology(&self, topology: CpuTopology, assignments: &mut HashMap<u32, Assignment>) -> Vec<u32> {
+        let changed = self.set_topology(topology);
+        if changed.is_empty() {
+            return Vec::new();
+        }
+        tracing::info!(cpus = ?changed, "core classes changed");
+
+        let topology = self.topology();
+        let mut ids: Vec<u32> = assignments.keys().copied().collect();
+        ids.sort_unstable();
+
+        let mut moved = Vec::new();
+        for id in ids {
+            let wanted = assignments[&id].preferred_core_class();
+            let misplaced = assignments[&id].cpu_cores.iter().any(|&cpu| topology.class_of(cpu).is_some_and(|class| class != wanted));
+            if !misplaced {
+                continue;
+            }
+            let occupied = assignment::core_uses(assignments.values().filter(|a| a.id != id));
+            let a = assignments.get_mut(&id).unwrap();
+            let old = std::mem::take(&mut a.cpu_cores);
+            a.bind_cpu_with(&topology, &occupied);
+            if a.cpu_cores != old {
+                moved.push(id);
+            }
+        }
+        moved
+    }
+
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
+    /// `occupied` döngüdeki tüm bind'ları taşır; yeni bind'lar eklenir
+    pub fn allocate(&self, assignment: &mut Assignment, occupied: &mut Vec<CoreUse>) {
+        // Limitler her iki modda da kernel'e yazılır
+        if assignment.cgroup_path.is_none() {
+            self.sync_cgroup(assignment);
+        }
+
         match self.mode {
             ResourceMode::Manual => {
-                println!("📋 Manual mode: Assignment {} – configure manually", assignment.id);
+                tracing::debug!(assignment = assignment.id, "manual mode, configure manually");
             }
             ResourceMode::Auto => {
                 // CPU her zaman bind edilir
                 if assignment.cpu_cores.is_empty() {
-                    assignment.bind_cpu();
+                    assignment.bind_cpu_with(&self.topology(), occupied);
+                    occupied.extend(assignment::core_uses([&*assignment]));
                 }
 
                 // GPU sadece gerekliyse ve mevcutsa bind et
@@ -44,87 +226,198 @@ impl ResourceManager {
 
                 // Lease başlat
                 if assignment.lease_start.is_none() {
-                    assignment.start_lease(Duration::from_secs(30));
+                    assignment.start_lease_at(self.default_lease(), self.clock.now());
                 }
 
-                // Kullanıcıya bilgi
-                let mode_str = match assignment.execution_mode {
-                    ExecutionMode::CpuOnly => "🔵 Pure CPU-Only",
-                    ExecutionMode::GpuPreferred => "🟢 GPU Preferred",
-                    ExecutionMode::GpuOnly => "🟡 Strict GPU-Only",
-                    ExecutionMode::Hybrid => "⚡ Full Hybrid",
-                };
-
-                println!("✅ Allocation complete → Assignment {} | Requested Mode: {}", assignment.id, mode_str);
+                tracing::debug!(assignment = assignment.id, mode = ?assignment.execution_mode, "allocation complete");
             }
         }
     }
 
-    pub fn enforce_leases(&self, assignments: &mut HashMap<u32, Assignment>) {
-        let expired_ids: Vec<u32> = assignments
+    /// Lease'i biten assignment'ları durdurup kaldırır; kaldırılan ID'leri döndürür.
+    /// on_lease_expired hook'larından biri Renew derse lease yenilenir.
+    pub fn enforce_leases(&self, assignments: &mut HashMap<u32, Assignment>) -> Vec<u32> {
+        let now = self.clock.now();
+        let mut expired_ids: Vec<u32> = assignments
             .iter()
-            .filter(|(_, a)| a.lease_expired())
+            .filter(|(_, a)| a.lease_expired_at(now))
             .map(|(&id, _)| id)
             .collect();
+        expired_ids.sort_unstable();
 
-        for id in expired_ids {
+        let mut warned = self.lease_warned.lock().unwrap();
+
+        let hooks = self.lease_hooks.lock().unwrap();
+        expired_ids.retain(|id| {
+            let Some(assignment) = assignments.get_mut(id) else { return false };
+            // Her hook çağrılır (bildirim), tek Renew yeterli
+            let decisions: Vec<LeaseDecision> = hooks.iter().map(|hook| hook(assignment)).collect();
+            if !decisions.contains(&LeaseDecision::Renew) {
+                return true;
+            }
+            assignment.renew_lease_at(now);
+            warned.remove(id);
+            tracing::info!(assignment = id, "lease renewed");
+            false
+        });
+        drop(hooks);
+
+        // Yaklaşan lease bitişleri için OSD uyarısı (assignment başına bir kez)
+        for (&id, a) in assignments.iter() {
+            if let Some(remaining) = a.lease_remaining_at(now) {
+                if !remaining.is_zero() && remaining <= LEASE_WARNING_WINDOW && warned.insert(id) {
+                    osd::post(
+                        format!("Assignment {} lease expires in {}s", id, remaining.as_secs().max(1)),
+                        OsdIcon::Lease,
+                        Duration::from_secs(3),
+                    );
+                }
+            }
+        }
+
+        for &id in &expired_ids {
             if let Some(mut expired) = assignments.remove(&id) {
-                println!("🗑️ Lease expired → Gracefully stopping and removing assignment {}", id);
+                tracing::info!(assignment = id, "lease expired, stopping and removing assignment");
                 expired.stop_task();
+                self.release_cgroup(&mut expired);
+                warned.remove(&id);
+                osd::post(
+                    format!("Assignment {} lease expired", id),
+                    OsdIcon::Warning,
+                    Duration::from_secs(4),
+                );
             }
         }
+
+        expired_ids
     }
 
-    pub fn monitor(&self, assignments: &HashMap<u32, Assignment>) {
-        println!("\n🌀 WASMA v1.0 – Live Resource Monitor (2 Ocak 2026) 🌀\n");
+    /// Bir sonraki lease olayına (uyarı ya da bitiş) kalan süre; lease yoksa None
+    pub fn next_lease_event(&self, assignments: &HashMap<u32, Assignment>) -> Option<Duration> {
+        let now = self.clock.now();
+        let warned = self.lease_warned.lock().unwrap();
+        assignments
+            .values()
+            .filter_map(|a| {
+                let remaining = a.lease_remaining_at(now)?;
+                if remaining > LEASE_WARNING_WINDOW {
+                    Some(remaining - LEASE_WARNING_WINDOW)
+                } else if warned.contains(&a.id) {
+                    Some(remaining)
+                } else {
+                    Some(Duration::ZERO)
+                }
+            })
+            .min()
+    }
+
+    /// allocate + enforce_leases'in yapacaklarını uygulamadan hesapla.
+    /// `extra` henüz eklenmemiş assignment'lardır (add_assignment önizlemesi).
+    pub fn plan_cycle(&self, assignments: &HashMap<u32, Assignment>, extra: &[Assignment]) -> CyclePlan {
+        let warned = self.lease_warned.lock().unwrap();
+        let now = self.clock.now();
+        // GPU tespiti komut çalıştırır; plan başına bir kez
+        let mut gpu: Option<Option<&'static str>> = None;
+
+        let mut existing: Vec<&Assignment> = assignments.values().collect();
+        existing.sort_by_key(|a| a.id);
+        let candidates = existing.into_iter().map(|a| (a, false))
+            .chain(extra.iter().map(|a| (a, true)));
 
-        if assignments.is_empty() {
-            println!("   📭 No active assignments currently.\n");
-            return;
+        let topology = self.topology();
+        let mut occupied = assignment::core_uses(assignments.values());
+        let mut plans = Vec::new();
+        for (a, is_new) in candidates {
+            let mut actions = Vec::new();
+
+            if self.mode == ResourceMode::Auto {
+                if a.cpu_cores.is_empty() {
+                    let class = a.preferred_core_class();
+                    if let Some(core) = topology.pick_core(&occupied, a.is_gpu_heavy(), a.core_placement, class) {
+                        actions.push(PlannedAction::BindCpu { core });
+                        occupied.push(CoreUse { cpu: core, gpu_heavy: a.is_gpu_heavy() });
+                    }
+                }
+                if a.gpu_device.is_none() && a.should_bind_gpu() {
+                    let device = *gpu.get_or_insert_with(|| assignment::detect_gpu().map(|(device, _, _)| device));
+                    actions.push(match device {
+                        Some(device) => PlannedAction::BindGpu { device: device.to_string() },
+                        None => PlannedAction::GpuUnavailable,
+                    });
+                }
+                if a.task_handle.is_none() {
+                    actions.push(PlannedAction::StartTask);
+                }
+            }
+            // Manual modda add_assignment sadece lease başlatır
+            if a.lease_start.is_none() {
+                actions.push(PlannedAction::StartLease { secs: self.default_lease().as_secs() });
+            }
+
+            if a.lease_expired_at(now) {
+                actions = vec![PlannedAction::Preempt];
+            } else if let Some(remaining) = a.lease_remaining_at(now) {
+                if remaining <= LEASE_WARNING_WINDOW && !warned.contains(&a.id) {
+                    actions.push(PlannedAction::LeaseWarning { remaining_secs: remaining.as_secs().max(1) });
+                }
+            }
+
+            plans.push(AssignmentPlan {
+                id: a.id,
+                execution_mode: a.execution_mode,
+                is_new,
+                ram_mb: (a.ram_limit >> 20) as u64,
+                vram_mb: (a.vram_limit >> 20) as u64,
+                actions,
+            });
         }
 
-        for (_, a) in assignments {
-            let task_status = if a.task_handle.is_some() && *a.task_active.lock().unwrap() {
-                "🟢 RUNNING"
-            } else {
-                "🔴 STOPPED"
-            };
-
-            // Gerçek GPU durumuna göre akıllı sınıflandırma
-            let effective_mode = match a.gpu_device.as_deref() {
-                Some("nvidia-dgpu") => "🟢 Discrete GPU (NVIDIA dGPU)",
-                Some("amd-dgpu") => "🟢 Discrete GPU (AMD dGPU)",
-                Some("integrated-gpu") => "🟡 Integrated GPU (iGPU via /dev/dri)",
-                Some("apple-igpu") => "🟡 Apple Silicon iGPU",
-                Some("windows-igpu") => "🟡 Windows iGPU",
-                None => "🔵 Pure CPU-Only (No GPU available)",
-                _ => "🟢 GPU Active",
-            };
-            let requested_mode = match a.execution_mode {
-                ExecutionMode::CpuOnly => "Requested: Pure CPU",
-                ExecutionMode::GpuPreferred => "Requested: GPU Preferred",
-                ExecutionMode::GpuOnly => "Requested: Strict GPU",
-                ExecutionMode::Hybrid => "Requested: Full Hybrid",
-            };
-
-            let remaining = a
-                .lease_duration
-                .and_then(|d| a.lease_start.map(|s| d.as_secs().saturating_sub(s.elapsed().as_secs())))
-                .unwrap_or(0);
-
-            println!(
-                "ID {:2} | {} | {} | {} | Cores: {:?} | GPU: {:18} | RAM: {:4} MiB | Lease: {}s",
-                a.id,
-                task_status,
-                effective_mode,
-                requested_mode,
-                a.cpu_cores,
-                a.gpu_device.as_deref().unwrap_or("None"),
-                a.ram_limit >> 20,
-                remaining
-            );
+        let surviving = plans.iter().filter(|p| !p.preempted());
+        let ram_mb = surviving.clone().map(|p| p.ram_mb).sum();
+        let vram_mb = surviving.map(|p| p.vram_mb).sum();
+        let system_ram_mb = plan::system_ram_mb();
+
+        let mut warnings = Vec::new();
+        if let Some(total) = system_ram_mb.filter(|total| ram_mb > *total) {
+            warnings.push(format!("RAM limits total {} MiB, system has {} MiB", ram_mb, total));
         }
+        for p in &plans {
+            if p.actions.contains(&PlannedAction::GpuUnavailable) && p.execution_mode == ExecutionMode::GpuOnly {
+                warnings.push(format!("Assignment {} requires a GPU but none was detected", p.id));
+            }
+        }
+
+        CyclePlan { mode: self.mode, assignments: plans, ram_mb, vram_mb, system_ram_mb, warnings }
+    }
+
+    /// Canlı kaynak durumu; yazdırmaz, çıktı MonitorReport'un Display'idir
+    pub fn monitor(&self, assignments: &HashMap<u32, Assignment>) -> MonitorReport {
+        let now = self.clock.now();
+        let mut statuses: Vec<AssignmentStatus> = assignments
+            .values()
+            .map(|a| AssignmentStatus {
+                id: a.id,
+                task_running: a.task_handle.is_some() && *a.task_active.lock().unwrap(),
+                execution_mode: a.execution_mode,
+                gpu_device: a.gpu_device.clone(),
+                cpu_cores: a.cpu_cores.clone(),
+                ram_limit_mb: (a.ram_limit >> 20) as u64,
+                ram_used_mb: self.usage(a).map(|used| used.memory_current >> 20),
+                lease_remaining_secs: a.lease_remaining_at(now).map(|d| d.as_secs()).unwrap_or(0),
+            })
+            .collect();
+        statuses.sort_by_key(|s| s.id);
+
+        // Mod başına CPU/GPU iş dağılımı; ExecutionMode sırasıyla
+        let mut per_mode: HashMap<ExecutionMode, ModeStats> = HashMap::new();
+        for a in assignments.values() {
+            per_mode.entry(a.execution_mode).or_default().merge(&a.stats());
+        }
+        let modes = ExecutionMode::value_variants()
+            .iter()
+            .filter_map(|mode| per_mode.get(mode).map(|stats| ModeSummary::of(*mode, stats)))
+            .collect();
 
-        println!("──────────────────────────────────────────────────────────────────────\n");
+        MonitorReport { mode: self.mode, assignments: statuses, modes, expired: Vec::new() }
     }
 }
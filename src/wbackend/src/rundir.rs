@@ -0,0 +1,69 @@
+// src/rundir.rs
+// WASMA - Çalışma zamanı dizini
+// Kontrol/hotkey/OSD soketleri, kilit işareti ve UPGRADE snapshot'ı burada durur.
+// $XDG_RUNTIME_DIR/wasma kullanılır; tanımlı değilse /tmp/wasma-<uid>.
+// Paylaşılan /tmp/wasma'ya asla düşülmez: başka bir kullanıcı dizini önceden
+// açıp soketi ya da snapshot'ı ele geçiremesin diye dizin 0700 ve bizim olmalı.
+
+use std::io;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+/// Bu kullanıcının WASMA çalışma dizini (oluşturmaz)
+pub fn runtime_dir() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime) if !runtime.is_empty() => PathBuf::from(runtime).join("wasma"),
+        _ => std::env::temp_dir().join(format!("wasma-{}", unsafe { libc::geteuid() })),
+    }
+}
+
+/// Dizini 0700 oluşturur ve yalnızca bu kullanıcının erişebildiğini doğrular.
+/// Sembolik bağlantı ya da başkasına ait dizin reddedilir; gevşek izinler daraltılır.
+pub fn ensure_private(dir: &Path) -> io::Result<()> {
+    std::fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+
+    let meta = std::fs::symlink_metadata(dir)?;
+    if !meta.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is not a directory", dir.display()),
+        ));
+    }
+    if meta.uid() != unsafe { libc::geteuid() } {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} belongs to uid {}", dir.display(), meta.uid()),
+        ));
+    }
+    if meta.mode() & 0o077 != 0 {
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ensure_private() {
+        let root = std::env::temp_dir().join(format!("wasma-rundir-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+
+        let dir = root.join("wasma");
+        ensure_private(&dir).unwrap();
+        assert_eq!(std::fs::metadata(&dir).unwrap().mode() & 0o777, 0o700);
+
+        // Önceden herkese açık oluşturulmuş dizin daraltılır
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        ensure_private(&dir).unwrap();
+        assert_eq!(std::fs::metadata(&dir).unwrap().mode() & 0o777, 0o700);
+
+        // Başka bir yere yönlenen bağlantı kabul edilmez
+        let link = root.join("link");
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+        assert_eq!(ensure_private(&link).unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,168 @@
+// src/runtime.rs
+// WASMA - Async (tokio) sürücü
+// run_cycle'ın thread::sleep döngüsü yerine: her assignment kendi tokio task'ında
+// schedule edilir, lease bitişi timer ile yakalanır, kapanış ShutdownSignal ile.
+// Gömülü kullanımda backend, çağıranın runtime'ı içinde sürülür.
+
+use crate::assignment;
+use crate::{MonitorReport, WBackend};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tracing::Instrument;
+
+/// Kapanışı tetikleyen taraf
+#[derive(Debug)]
+pub struct ShutdownHandle(watch::Sender<bool>);
+
+/// run_async'in beklediği kapanış sinyali; task'lar arasında klonlanır
+#[derive(Debug, Clone)]
+pub struct ShutdownSignal(watch::Receiver<bool>);
+
+pub fn shutdown_channel() -> (ShutdownHandle, ShutdownSignal) {
+    let (tx, rx) = watch::channel(false);
+    (ShutdownHandle(tx), ShutdownSignal(rx))
+}
+
+impl ShutdownHandle {
+    pub fn shutdown(&self) {
+        let _ = self.0.send(true);
+    }
+}
+
+impl ShutdownSignal {
+    pub fn is_shutdown(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Kapanış istenene kadar bekle (handle düşürülürse de döner)
+    pub async fn wait(&mut self) {
+        while !self.is_shutdown() {
+            if self.0.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+impl WBackend {
+    /// Async ana döngü: `interval` monitor ve yeni assignment taraması periyodu.
+    /// Kapanışa kadar lease'i bitip kaldırılan assignment ID'lerini döndürür.
+    pub async fn run_async(self: Arc<Self>, interval: Duration, mut shutdown: ShutdownSignal) -> Vec<u32> {
+        let mut tasks: JoinSet<Vec<u32>> = JoinSet::new();
+        let mut running: HashSet<u32> = HashSet::new();
+        let mut expired = Vec::new();
+        // expired'ın son rapora girmiş kısmı
+        let mut reported = 0;
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            tokio::select! {
+                _ = shutdown.wait() => break,
+                Some(done) = tasks.join_next() => {
+                    if let Ok(ids) = done {
+                        expired.extend(ids);
+                    }
+                }
+                _ = ticker.tick() => {
+                    self.reclassify_cores();
+                    // Yeni assignment'lar için task başlat, bitmişleri unut
+                    let ids: Vec<u32> = self.assignments.lock().unwrap().keys().copied().collect();
+                    running.retain(|id| ids.contains(id));
+                    for id in ids {
+                        if running.insert(id) {
+                            self.ids.lock().unwrap().begin_cycle(id);
+                            tasks.spawn(
+                                assignment_task(self.clone(), id, interval, shutdown.clone())
+                                    .instrument(tracing::info_span!("assignment", id)),
+                            );
+                        }
+                    }
+                    let report = MonitorReport {
+                        expired: expired[reported..].to_vec(),
+                        ..self.resource_manager.monitor(&self.assignments.lock().unwrap())
+                    };
+                    reported = expired.len();
+                    self.publish_report(&report);
+                }
+            }
+        }
+
+        while let Some(done) = tasks.join_next().await {
+            if let Ok(ids) = done {
+                expired.extend(ids);
+            }
+        }
+        tracing::info!(expired = expired.len(), "async run loop stopped");
+        expired
+    }
+
+    /// Tek assignment için allocate + schedule + lease kontrolü.
+    /// None: assignment artık yok; Some(expired, remaining) aksi halde.
+    fn cycle_assignment(&self, id: u32) -> Option<(Vec<u32>, Option<Duration>)> {
+        let mut assignments = self.assignments.lock().unwrap();
+
+        let mut occupied = assignment::core_uses(assignments.values().filter(|a| a.id != id));
+        let current = assignments.get_mut(&id)?;
+        self.resource_manager.allocate(current, &mut occupied);
+        self.scheduler.schedule(current);
+
+        let expired = self.resource_manager.enforce_leases(&mut assignments);
+        self.release_ids(&expired);
+        let remaining = assignments.get(&id).and_then(|a| a.lease_remaining_at(self.clock.now()));
+        self.sync_run_record(&assignments);
+        Some((expired, remaining))
+    }
+}
+
+/// Assignment başına task: her `interval`'de bir schedule, lease bitişinde hemen uyanır.
+/// Task sürdükçe ID uçuşta sayılır; kaldırılırsa task bitene kadar yeniden verilmez.
+async fn assignment_task(backend: Arc<WBackend>, id: u32, interval: Duration, mut shutdown: ShutdownSignal) -> Vec<u32> {
+    let mut expired = Vec::new();
+    while let Some((ids, remaining)) = backend.cycle_assignment(id) {
+        let removed = ids.contains(&id);
+        expired.extend(ids);
+        if removed {
+            break;
+        }
+
+        let wake = remaining.map_or(interval, |left| left.min(interval));
+        tokio::select! {
+            _ = shutdown.wait() => break,
+            _ = tokio::time::sleep(wake) => {}
+        }
+    }
+    backend.ids.lock().unwrap().end_cycle(id);
+    expired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Assignment, MockClock, ResourceMode};
+
+    #[test]
+    fn test_run_async_expires_leases_and_stops() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        let clock = Arc::new(MockClock::new());
+        let backend = Arc::new(WBackend::with_clock(ResourceMode::Manual, clock.clone()));
+        backend.add_assignment(Assignment::new(1));
+        backend.add_assignment(Assignment::new(2));
+        clock.advance(Duration::from_secs(31));
+
+        let (handle, signal) = shutdown_channel();
+        let expired = runtime.block_on(async {
+            let run = tokio::spawn(backend.clone().run_async(Duration::from_millis(10), signal));
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            handle.shutdown();
+            run.await.unwrap()
+        });
+
+        let mut expired = expired;
+        expired.sort_unstable();
+        assert_eq!(expired, vec![1, 2]);
+        assert!(backend.list_assignments().is_empty());
+    }
+}
@@ -1,11 +1,18 @@
 // src/scheduler.rs
 use crate::assignment::{Assignment, ExecutionMode};
+use crate::clock::{self, SharedClock};
 
-pub struct Scheduler;
+pub struct Scheduler {
+    clock: SharedClock,
+}
 
 impl Scheduler {
     pub fn new() -> Self {
-        Scheduler
+        Self::with_clock(clock::system())
+    }
+
+    pub fn with_clock(clock: SharedClock) -> Self {
+        Scheduler { clock }
     }
 
     /// Scheduler'ın ana görevi: Assignment'ı doğrula ve çalıştırılabilirliğini onayla
@@ -13,8 +20,8 @@ impl Scheduler {
     /// Burada sadece scheduling kararı + monitoring logu basıyoruz
     pub fn schedule(&self, assignment: &Assignment) {
         // 1. Lease kontrolü
-        if assignment.lease_expired() {
-            println!("⏰ Scheduler: Skipping EXPIRED assignment {}", assignment.id);
+        if assignment.lease_expired_at(self.clock.now()) {
+            tracing::debug!(assignment = assignment.id, "skipping expired assignment");
             return;
         }
 
@@ -22,40 +29,29 @@ impl Scheduler {
         let task_running = assignment.task_handle.is_some() && *assignment.task_active.lock().unwrap();
 
         // 3. Execution Mode'a göre net tanımlama
-        let mode_str = if task_running {
+        let state = if task_running {
             match (assignment.execution_mode, assignment.gpu_device.is_some()) {
-                (ExecutionMode::CpuOnly, _) => "🔵 CPU-Only (Deterministic)",
-                (ExecutionMode::GpuPreferred, true) => "🟢 Hybrid (GPU Active)",
-                (ExecutionMode::GpuPreferred, false) => "🔵 CPU-Only (GPU Unavailable)",
-                (ExecutionMode::GpuOnly, true) => "🟡 GPU-Only (Enforced)",
-                (ExecutionMode::GpuOnly, false) => "⚠️  GPU-Only Requested but Unavailable",
-                (ExecutionMode::Hybrid, true) => "⚡ Full Hybrid Mode",
-                (ExecutionMode::Hybrid, false) => "🔵 Hybrid → Fallback to CPU-Only",
+                (ExecutionMode::CpuOnly, _) => "cpu-only",
+                (ExecutionMode::GpuPreferred, true) => "gpu-active",
+                (ExecutionMode::GpuPreferred, false) => "cpu-only (gpu unavailable)",
+                (ExecutionMode::GpuOnly, true) => "gpu-only",
+                (ExecutionMode::GpuOnly, false) => "gpu-only requested but unavailable",
+                (ExecutionMode::Hybrid, true) => "hybrid",
+                (ExecutionMode::Hybrid, false) => "hybrid fallback to cpu-only",
             }
         } else {
-            "⏳ Not Started Yet"
+            "not started"
         };
 
-        // 4. CPU core bilgisi
-        let cpu_info = if assignment.cpu_cores.is_empty() {
-            "No affinity".to_string()
-        } else {
-            format!("Cores {:?}", assignment.cpu_cores)
-        };
-
-        // 5. RAM / VRAM
-        let ram_mb = assignment.ram_limit / (1024 * 1024);
-        let vram_mb = assignment.vram_limit / (1024 * 1024);
-
-        // 6. Ana scheduling logu – WASMA otoritesi burada konuşuyor
-        println!(
-            "🗓️  Scheduler: EXECUTING Assignment {:2} | {} | {} | RAM: {:4} MiB | VRAM: {:3} MiB | GPU: {:?}",
-            assignment.id,
-            mode_str,
-            cpu_info,
-            ram_mb,
-            vram_mb,
-            assignment.gpu_device.as_deref().unwrap_or("None")
+        // 4. Ana scheduling kaydı – assignment span'i içinde debug seviyesinde
+        tracing::debug!(
+            assignment = assignment.id,
+            state,
+            cores = ?assignment.cpu_cores,
+            ram_mb = assignment.ram_limit / (1024 * 1024),
+            vram_mb = assignment.vram_limit / (1024 * 1024),
+            gpu = assignment.gpu_device.as_deref().unwrap_or("none"),
+            "executing assignment"
         );
 
         // Gelecekte buraya eklenebilir:
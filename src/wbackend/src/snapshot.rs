@@ -0,0 +1,188 @@
+// src/snapshot.rs
+// WASMA - Assignment snapshot / restore
+// Daemon kendini yeniden exec ederken (binary upgrade) tüm assignment ve
+// lease durumu buraya yazılır; yeni süreç restore() ile kaldığı yerden devam eder.
+// Format serde ile serileştirilir, dosya biçimini çağıran seçer.
+
+use crate::assignment::{Assignment, ExecutionMode};
+use crate::resource_manager::ResourceMode;
+use crate::topology::{CoreClass, CorePlacement};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Snapshot biçim sürümü; uyumsuz değişiklikte artırılır
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotError {
+    /// Snapshot daha yeni bir WASMA tarafından yazılmış
+    UnsupportedVersion { found: u32, supported: u32 },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "snapshot version {} is newer than supported version {}",
+                found, supported
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// Tek assignment'ın serileştirilebilir hali
+/// (Instant ve thread handle serileşemez; lease kalan süre olarak tutulur)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssignmentSnapshot {
+    pub id: u32,
+    pub cpu_cores: Vec<usize>,
+    pub gpu_device: Option<String>,
+    pub ram_limit: usize,
+    pub vram_limit: usize,
+    pub cpu_priority: u8,
+    pub gpu_priority: u8,
+    pub cpu_affinity_mask: Option<u64>,
+    pub gpu_id: Option<String>,
+    pub lease_duration: Option<Duration>,
+    /// Snapshot anında lease'in kalan süresi
+    pub lease_remaining: Option<Duration>,
+    pub task_active: bool,
+    pub cgroup_path: Option<String>,
+    pub execution_mode: ExecutionMode,
+    #[serde(default)]
+    pub core_placement: CorePlacement,
+    #[serde(default)]
+    pub core_class: Option<CoreClass>,
+    #[serde(default)]
+    pub quota_group: Option<String>,
+}
+
+/// WBackend'in tüm durumu
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackendSnapshot {
+    pub version: u32,
+    pub mode: ResourceMode,
+    /// UNIX zamanı (saniye)
+    pub taken_at: u64,
+    pub assignments: Vec<AssignmentSnapshot>,
+}
+
+impl BackendSnapshot {
+    pub fn new(mode: ResourceMode, assignments: Vec<AssignmentSnapshot>) -> Self {
+        BackendSnapshot {
+            version: SNAPSHOT_VERSION,
+            mode,
+            taken_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            assignments,
+        }
+    }
+
+    pub fn check_version(&self) -> Result<(), SnapshotError> {
+        if self.version > SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion {
+                found: self.version,
+                supported: SNAPSHOT_VERSION,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Assignment {
+    pub fn snapshot(&self) -> AssignmentSnapshot {
+        self.snapshot_at(Instant::now())
+    }
+
+    pub fn snapshot_at(&self, now: Instant) -> AssignmentSnapshot {
+        let lease_remaining = self.lease_remaining_at(now);
+
+        AssignmentSnapshot {
+            id: self.id,
+            cpu_cores: self.cpu_cores.clone(),
+            gpu_device: self.gpu_device.clone(),
+            ram_limit: self.ram_limit,
+            vram_limit: self.vram_limit,
+            cpu_priority: self.cpu_priority,
+            gpu_priority: self.gpu_priority,
+            cpu_affinity_mask: self.cpu_affinity_mask,
+            gpu_id: self.gpu_id.clone(),
+            lease_duration: self.lease_duration,
+            lease_remaining,
+            task_active: *self.task_active.lock().unwrap(),
+            cgroup_path: self.cgroup_path.clone(),
+            execution_mode: self.execution_mode,
+            core_placement: self.core_placement,
+            core_class: self.core_class,
+            quota_group: self.quota_group.clone(),
+        }
+    }
+
+    /// Snapshot'tan assignment kur; task başlatılmaz (çağıran karar verir)
+    pub fn from_snapshot(snapshot: &AssignmentSnapshot) -> Self {
+        Self::from_snapshot_at(snapshot, Instant::now())
+    }
+
+    pub fn from_snapshot_at(snapshot: &AssignmentSnapshot, now: Instant) -> Self {
+        let mut assignment = Assignment::new(snapshot.id);
+        assignment.cpu_cores = snapshot.cpu_cores.clone();
+        assignment.gpu_device = snapshot.gpu_device.clone();
+        assignment.ram_limit = snapshot.ram_limit;
+        assignment.vram_limit = snapshot.vram_limit;
+        assignment.cpu_priority = snapshot.cpu_priority;
+        assignment.gpu_priority = snapshot.gpu_priority;
+        assignment.cpu_affinity_mask = snapshot.cpu_affinity_mask;
+        assignment.gpu_id = snapshot.gpu_id.clone();
+        assignment.cgroup_path = snapshot.cgroup_path.clone();
+        assignment.execution_mode = snapshot.execution_mode;
+        assignment.core_placement = snapshot.core_placement;
+        assignment.core_class = snapshot.core_class;
+        assignment.quota_group = snapshot.quota_group.clone();
+
+        // Lease kaldığı yerden devam eder: başlangıcı geçen süre kadar geriye al
+        assignment.lease_duration = snapshot.lease_duration;
+        if let Some(duration) = snapshot.lease_duration {
+            let used = duration.saturating_sub(snapshot.lease_remaining.unwrap_or(duration));
+            assignment.lease_start = Some(now.checked_sub(used).unwrap_or(now));
+        }
+
+        assignment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WBackend;
+
+    #[test]
+    fn test_snapshot_restore() {
+        let backend = WBackend::new(ResourceMode::Manual);
+        let mut assignment = Assignment::new(7);
+        assignment.execution_mode = ExecutionMode::CpuOnly;
+        assignment.cpu_cores = vec![0];
+        assignment.start_lease(Duration::from_secs(30));
+        backend.add_assignment(assignment);
+
+        let snapshot = backend.snapshot();
+        assert_eq!(snapshot.assignments.len(), 1);
+        assert!(snapshot.assignments[0].lease_remaining.unwrap() <= Duration::from_secs(30));
+
+        let upgraded = WBackend::new(ResourceMode::Manual);
+        assert_eq!(upgraded.restore(&snapshot).unwrap(), 1);
+        let restored = upgraded.get_assignment(7).unwrap();
+        assert_eq!(restored.cpu_cores, vec![0]);
+        assert_eq!(restored.execution_mode, ExecutionMode::CpuOnly);
+        assert!(!restored.lease_expired());
+
+        let mut future = snapshot.clone();
+        future.version = SNAPSHOT_VERSION + 1;
+        assert!(upgraded.restore(&future).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+// src/theme.rs
+// WASMA - Tema token'ları ve erişilebilirlik varyantları
+// GUI yöneticisi ve UBIN aynı renk setini kullanır; aktif varyant
+// WASMA_A11Y_THEME ortam değişkeniyle alt process'lere de aktarılır.
+// Hareket azaltma (reduce motion) tercihi de aynı şekilde WASMA_REDUCE_MOTION ile taşınır.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+use crate::color::Color;
+
+/// Alt process'lere (UBIN uygulamaları) aktarılan ortam değişkeni
+pub const A11Y_ENV: &str = "WASMA_A11Y_THEME";
+/// "1" → animasyonlar kapalı
+pub const REDUCE_MOTION_ENV: &str = "WASMA_REDUCE_MOTION";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum A11yTheme {
+    #[default]
+    Standard,
+    /// Saf siyah/beyaz, kalın kenarlar
+    HighContrast,
+    /// Kırmızı/yeşil ayrımına dayanmayan Okabe-Ito paleti
+    Deuteranopia,
+}
+
+impl A11yTheme {
+    pub fn name(&self) -> &'static str {
+        match self {
+            A11yTheme::Standard => "standard",
+            A11yTheme::HighContrast => "high-contrast",
+            A11yTheme::Deuteranopia => "deuteranopia",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "" | "none" | "standard" | "default" => Some(A11yTheme::Standard),
+            "high-contrast" | "high_contrast" | "hc" => Some(A11yTheme::HighContrast),
+            "deuteranopia" | "deuteranopia-safe" | "colorblind" => Some(A11yTheme::Deuteranopia),
+            _ => None,
+        }
+    }
+
+    pub fn tokens(&self) -> ThemeTokens {
+        ThemeTokens::for_variant(*self)
+    }
+}
+
+/// Tüm arayüzlerin paylaştığı renk/ölçü token'ları
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeTokens {
+    pub background: Color,
+    /// Paneller, palette, OSD
+    pub surface: Color,
+    pub foreground: Color,
+    /// İkincil metin
+    pub muted: Color,
+    pub accent: Color,
+    pub success: Color,
+    pub warning: Color,
+    pub danger: Color,
+    pub border_width: u32,
+}
+
+impl ThemeTokens {
+    pub fn for_variant(variant: A11yTheme) -> Self {
+        match variant {
+            A11yTheme::Standard => ThemeTokens {
+                background: Color::rgb(0x26, 0x26, 0x26),
+                surface: Color::rgb(0x1a, 0x1a, 0x21),
+                foreground: Color::rgb(0xe6, 0xe6, 0xe6),
+                muted: Color::rgb(0x80, 0x80, 0x80),
+                accent: Color::rgb(0x33, 0x99, 0xff),
+                success: Color::rgb(0x4c, 0xaf, 0x50),
+                warning: Color::rgb(0xff, 0xa0, 0x00),
+                danger: Color::rgb(0xd9, 0x40, 0x40),
+                border_width: 1,
+            },
+            A11yTheme::HighContrast => ThemeTokens {
+                background: Color::rgb(0x00, 0x00, 0x00),
+                surface: Color::rgb(0x00, 0x00, 0x00),
+                foreground: Color::rgb(0xff, 0xff, 0xff),
+                muted: Color::rgb(0xd0, 0xd0, 0xd0),
+                accent: Color::rgb(0xff, 0xff, 0x00),
+                success: Color::rgb(0x00, 0xff, 0x00),
+                warning: Color::rgb(0xff, 0xa5, 0x00),
+                danger: Color::rgb(0xff, 0x40, 0x40),
+                border_width: 3,
+            },
+            // Okabe-Ito: başarı mavi, tehlike vermilyon – yeşil/kırmızı çifti yok
+            A11yTheme::Deuteranopia => ThemeTokens {
+                background: Color::rgb(0x26, 0x26, 0x26),
+                surface: Color::rgb(0x1a, 0x1a, 0x21),
+                foreground: Color::rgb(0xe6, 0xe6, 0xe6),
+                muted: Color::rgb(0x99, 0x99, 0x99),
+                accent: Color::rgb(0x00, 0x72, 0xb2),
+                success: Color::rgb(0x56, 0xb4, 0xe9),
+                warning: Color::rgb(0xe6, 0x9f, 0x00),
+                danger: Color::rgb(0xd5, 0x5e, 0x00),
+                border_width: 2,
+            },
+        }
+    }
+}
+
+// 0xff = henüz seçilmedi, ortam değişkenine bak
+static ACTIVE: AtomicU8 = AtomicU8::new(0xff);
+
+/// Process genelinde aktif varyant
+pub fn active() -> A11yTheme {
+    match ACTIVE.load(Ordering::Relaxed) {
+        0 => A11yTheme::Standard,
+        1 => A11yTheme::HighContrast,
+        2 => A11yTheme::Deuteranopia,
+        _ => {
+            let variant = std::env::var(A11Y_ENV)
+                .ok()
+                .and_then(|v| A11yTheme::from_name(&v))
+                .unwrap_or_default();
+            ACTIVE.store(variant as u8, Ordering::Relaxed);
+            variant
+        }
+    }
+}
+
+/// Varyantı seç ve başlatılacak alt process'ler için dışa aktar
+pub fn set_active(variant: A11yTheme) {
+    ACTIVE.store(variant as u8, Ordering::Relaxed);
+    std::env::set_var(A11Y_ENV, variant.name());
+}
+
+pub fn active_tokens() -> ThemeTokens {
+    active().tokens()
+}
+
+// ============================================================================
+// HAREKET AZALTMA
+// ============================================================================
+
+// 0 = kapalı, 1 = açık, 0xff = henüz seçilmedi
+static REDUCE_MOTION: AtomicU8 = AtomicU8::new(0xff);
+
+/// Animasyonlar ve geçişler kapatılmalı mı
+pub fn reduce_motion() -> bool {
+    match REDUCE_MOTION.load(Ordering::Relaxed) {
+        0 => false,
+        1 => true,
+        _ => {
+            let reduce = std::env::var(REDUCE_MOTION_ENV)
+                .map(|v| matches!(v.trim(), "1" | "true" | "yes"))
+                .unwrap_or(false);
+            REDUCE_MOTION.store(reduce as u8, Ordering::Relaxed);
+            reduce
+        }
+    }
+}
+
+pub fn set_reduce_motion(reduce: bool) {
+    REDUCE_MOTION.store(reduce as u8, Ordering::Relaxed);
+    std::env::set_var(REDUCE_MOTION_ENV, if reduce { "1" } else { "0" });
+}
+
+/// Animasyon süresi; hareket azaltma açıksa sıfır (anında geçiş)
+pub fn animation_duration(base: Duration) -> Duration {
+    if reduce_motion() { Duration::ZERO } else { base }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variants() {
+        for variant in [A11yTheme::Standard, A11yTheme::HighContrast, A11yTheme::Deuteranopia] {
+            assert_eq!(A11yTheme::from_name(variant.name()), Some(variant));
+        }
+        assert_eq!(A11yTheme::from_name("bogus"), None);
+
+        let hc = A11yTheme::HighContrast.tokens();
+        assert_eq!(hc.background, Color::BLACK);
+        assert!(hc.border_width > A11yTheme::Standard.tokens().border_width);
+
+        set_active(A11yTheme::Deuteranopia);
+        assert_eq!(active(), A11yTheme::Deuteranopia);
+        assert_eq!(std::env::var(A11Y_ENV).unwrap(), "deuteranopia");
+    }
+
+    #[test]
+    fn test_reduce_motion() {
+        let base = Duration::from_millis(180);
+        set_reduce_motion(true);
+        assert_eq!(animation_duration(base), Duration::ZERO);
+        set_reduce_motion(false);
+        assert_eq!(animation_duration(base), base);
+    }
+}
@@ -0,0 +1,287 @@
+// src/topology.rs
+// WASMA - CPU topolojisi ve çekirdek yerleşimi
+// /sys/devices/system/cpu altından fiziksel çekirdek / SMT kardeş bilgisini okur.
+// Otomatik bind sırasında ayrı fiziksel çekirdekler tercih edilir ve iki GPU-ağır
+// assignment aynı çekirdeğin SMT kardeşlerine yerleştirilmez.
+// Hibrit CPU'larda (big.LITTLE, P/E çekirdek) çekirdekler cpu_capacity veya
+// cpuinfo_max_freq'e göre performans / verimlilik sınıfına ayrılır.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+const SYSFS_CPU: &str = "/sys/devices/system/cpu";
+
+/// Manifest'ten gelen yerleşim tercihi
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CorePlacement {
+    /// Boş fiziksel çekirdekler önce (varsayılan)
+    #[default]
+    Spread,
+    /// Dolu çekirdeğin boş SMT kardeşi önce (önbellek paylaşan işler)
+    Compact,
+}
+
+/// Hibrit CPU'larda çekirdek sınıfı (manifest `core_class`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum CoreClass {
+    /// P-core / big: gerçek zamanlı frame işi
+    #[default]
+    Performance,
+    /// E-core / LITTLE: arka plan işi
+    Efficiency,
+}
+
+/// Tek mantıksal CPU
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogicalCpu {
+    pub id: usize,
+    pub package: usize,
+    pub core: usize,
+    pub class: CoreClass,
+}
+
+impl LogicalCpu {
+    /// Aynı fiziksel çekirdeği paylaşan CPU'lar için ortak anahtar
+    pub fn physical(&self) -> (usize, usize) {
+        (self.package, self.core)
+    }
+}
+
+/// Bir mantıksal CPU'yu kullanan assignment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoreUse {
+    pub cpu: usize,
+    pub gpu_heavy: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CpuTopology {
+    pub cpus: Vec<LogicalCpu>,
+}
+
+impl CpuTopology {
+    /// sysfs'ten oku; okunamayan sistemlerde her CPU ayrı fiziksel çekirdek sayılır.
+    /// Süreç affinity maskesinin dışındaki CPU'lar listelenmez.
+    pub fn detect() -> Self {
+        let allowed: Vec<usize> = core_affinity::get_core_ids()
+            .map(|ids| ids.into_iter().map(|c| c.id).collect())
+            .unwrap_or_default();
+
+        let mut topology = Self::from_sysfs(Path::new(SYSFS_CPU));
+        if topology.cpus.is_empty() {
+            topology = Self::flat(&allowed);
+        } else if !allowed.is_empty() {
+            topology.cpus.retain(|cpu| allowed.contains(&cpu.id));
+        }
+        topology
+    }
+
+    /// `root` = /sys/devices/system/cpu biçiminde dizin
+    pub fn from_sysfs(root: &Path) -> Self {
+        let online = fs::read_to_string(root.join("online"))
+            .map(|list| parse_cpu_list(&list))
+            .unwrap_or_default();
+
+        let read = |path: std::path::PathBuf| -> Option<usize> { fs::read_to_string(path).ok()?.trim().parse().ok() };
+
+        let mut capacities = HashMap::new();
+        let mut cpus: Vec<LogicalCpu> = online
+            .into_iter()
+            .filter_map(|id| {
+                let dir = root.join(format!("cpu{}", id));
+                // ARM: cpu_capacity; x86 hibrit: P-core'ların azami frekansı daha yüksek
+                if let Some(capacity) = read(dir.join("cpu_capacity"))
+                    .or_else(|| read(dir.join("cpufreq").join("cpuinfo_max_freq")))
+                {
+                    capacities.insert(id, capacity);
+                }
+                Some(LogicalCpu {
+                    id,
+                    package: read(dir.join("topology").join("physical_package_id")).unwrap_or(0),
+                    core: read(dir.join("topology").join("core_id"))?,
+                    class: CoreClass::Performance,
+                })
+            })
+            .collect();
+        cpus.sort_by_key(|c| c.id);
+
+        let mut topology = CpuTopology { cpus };
+        topology.classify(&capacities);
+        topology
+    }
+
+    /// En yüksek kapasiteli CPU'lar Performance, diğerleri Efficiency.
+    /// Kapasitesi bilinmeyen CPU'lar Performance sayılır.
+    pub fn classify(&mut self, capacities: &HashMap<usize, usize>) {
+        let top = capacities.values().copied().max().unwrap_or(0);
+        for cpu in &mut self.cpus {
+            cpu.class = match capacities.get(&cpu.id) {
+                Some(&capacity) if capacity < top => CoreClass::Efficiency,
+                _ => CoreClass::Performance,
+            };
+        }
+    }
+
+    /// Hem P hem E çekirdeği olan sistem
+    pub fn is_hybrid(&self) -> bool {
+        self.cpus.iter().any(|c| c.class == CoreClass::Efficiency)
+            && self.cpus.iter().any(|c| c.class == CoreClass::Performance)
+    }
+
+    pub fn class_of(&self, cpu: usize) -> Option<CoreClass> {
+        self.cpus.iter().find(|c| c.id == cpu).map(|c| c.class)
+    }
+
+    /// SMT bilgisi olmayan topoloji
+    pub fn flat(ids: &[usize]) -> Self {
+        CpuTopology {
+            cpus: ids
+                .iter()
+                .map(|&id| LogicalCpu { id, package: 0, core: id, class: CoreClass::Performance })
+                .collect(),
+        }
+    }
+
+    pub fn physical_cores(&self) -> usize {
+        let mut keys: Vec<(usize, usize)> = self.cpus.iter().map(LogicalCpu::physical).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        keys.len()
+    }
+
+    /// `cpu` ile aynı fiziksel çekirdekteki diğer CPU'lar
+    pub fn siblings(&self, cpu: usize) -> Vec<usize> {
+        let Some(key) = self.cpus.iter().find(|c| c.id == cpu).map(LogicalCpu::physical) else {
+            return Vec::new();
+        };
+        self.cpus.iter().filter(|c| c.physical() == key && c.id != cpu).map(|c| c.id).collect()
+    }
+
+    /// Yeni assignment için CPU seç.
+    /// Spread: boş fiziksel çekirdek → en az dolu çekirdek; GPU-ağır iş, başka bir
+    /// GPU-ağır işin SMT kardeşine ancak başka yer kalmazsa konur.
+    /// `class` istenen çekirdek sınıfı; o sınıfta CPU yoksa diğerine düşülür.
+    pub fn pick_core(
+        &self,
+        occupied: &[CoreUse],
+        gpu_heavy: bool,
+        placement: CorePlacement,
+        class: CoreClass,
+    ) -> Option<usize> {
+        let mut on_physical: HashMap<(usize, usize), (usize, bool)> = HashMap::new();
+        let mut on_cpu: HashMap<usize, usize> = HashMap::new();
+        for used in occupied {
+            let Some(cpu) = self.cpus.iter().find(|c| c.id == used.cpu) else { continue };
+            let entry = on_physical.entry(cpu.physical()).or_default();
+            entry.0 += 1;
+            entry.1 |= used.gpu_heavy;
+            *on_cpu.entry(cpu.id).or_default() += 1;
+        }
+
+        self.cpus
+            .iter()
+            .min_by_key(|cpu| {
+                let (physical_load, heavy) = on_physical.get(&cpu.physical()).copied().unwrap_or((0, false));
+                let own_load = on_cpu.get(&cpu.id).copied().unwrap_or(0);
+                let heavy_clash = gpu_heavy && heavy;
+                let wrong_class = cpu.class != class;
+                match placement {
+                    CorePlacement::Spread => (wrong_class, heavy_clash, physical_load, own_load, cpu.id),
+                    // Yarı dolu çekirdekler önce
+                    CorePlacement::Compact => {
+                        (wrong_class, heavy_clash, own_load, usize::from(physical_load == 0), cpu.id)
+                    }
+                }
+            })
+            .map(|cpu| cpu.id)
+    }
+}
+
+/// "0-3,8,10-11" → [0, 1, 2, 3, 8, 10, 11]
+pub fn parse_cpu_list(list: &str) -> Vec<usize> {
+    list.trim()
+        .split(',')
+        .filter(|part| !part.is_empty())
+        .flat_map(|part| {
+            let (start, end) = match part.split_once('-') {
+                Some((a, b)) => (a.trim().parse().ok(), b.trim().parse().ok()),
+                None => (part.trim().parse().ok(), part.trim().parse().ok()),
+            };
+            match (start, end) {
+                (Some(start), Some(end)) if start <= end => (start..=end).collect(),
+                _ => Vec::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smt_aware_placement() {
+        assert_eq!(parse_cpu_list("0-3,8\n"), vec![0, 1, 2, 3, 8]);
+
+        let dir = std::env::temp_dir().join(format!("wasma-topology-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("online"), "0-3\n").unwrap();
+        // 2 fiziksel çekirdek, her biri 2 SMT thread: cpu0+cpu2, cpu1+cpu3
+        for (cpu, core) in [(0, 0), (1, 1), (2, 0), (3, 1)] {
+            let topo = dir.join(format!("cpu{}", cpu)).join("topology");
+            std::fs::create_dir_all(&topo).unwrap();
+            std::fs::write(topo.join("core_id"), format!("{}\n", core)).unwrap();
+            std::fs::write(topo.join("physical_package_id"), "0\n").unwrap();
+        }
+        let topology = CpuTopology::from_sysfs(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(topology.physical_cores(), 2);
+        assert_eq!(topology.siblings(0), vec![2]);
+
+        let p = CoreClass::Performance;
+        let first = CoreUse { cpu: 0, gpu_heavy: true };
+        // İkinci iş cpu2 (SMT kardeşi) yerine diğer fiziksel çekirdeğe gider
+        assert_eq!(topology.pick_core(&[first], false, CorePlacement::Spread, p), Some(1));
+        assert_eq!(topology.pick_core(&[first], false, CorePlacement::Compact, p), Some(2));
+
+        // Çekirdekler dolunca GPU-ağır iş, GPU-ağır olmayan işin kardeşine konur
+        let light = CoreUse { cpu: 1, gpu_heavy: false };
+        assert_eq!(topology.pick_core(&[first, light], true, CorePlacement::Spread, p), Some(3));
+        assert_eq!(topology.pick_core(&[first, light], true, CorePlacement::Compact, p), Some(3));
+
+        // Kapasite bilgisi yok: hibrit değil, Efficiency isteği yine de yer bulur
+        assert!(!topology.is_hybrid());
+        assert_eq!(topology.pick_core(&[], false, CorePlacement::Spread, CoreClass::Efficiency), Some(0));
+    }
+
+    #[test]
+    fn test_hybrid_core_classes() {
+        let mut topology = CpuTopology::flat(&[0, 1, 2, 3]);
+        // cpu0-1 P-core (4.8 GHz), cpu2-3 E-core (3.6 GHz)
+        topology.classify(&HashMap::from([(0, 4_800_000), (1, 4_800_000), (2, 3_600_000), (3, 3_600_000)]));
+        assert!(topology.is_hybrid());
+        assert_eq!(topology.class_of(2), Some(CoreClass::Efficiency));
+
+        let spread = CorePlacement::Spread;
+        assert_eq!(topology.pick_core(&[], false, spread, CoreClass::Efficiency), Some(2));
+        assert_eq!(topology.pick_core(&[], false, spread, CoreClass::Performance), Some(0));
+
+        // E-core'lar doluyken de arka plan işi E-core'da kalır; P-core'a sadece sınıf hiç yoksa geçilir
+        let busy = [CoreUse { cpu: 2, gpu_heavy: false }, CoreUse { cpu: 3, gpu_heavy: false }];
+        assert_eq!(topology.pick_core(&busy, false, spread, CoreClass::Efficiency), Some(2));
+        let only_p = CpuTopology::flat(&[0, 1]);
+        assert_eq!(only_p.pick_core(&[], false, spread, CoreClass::Efficiency), Some(0));
+
+        // Çalışırken yeniden sınıflandırma: E-core isteyen iş P-core'dan taşınır
+        let manager = crate::ResourceManager::new(crate::ResourceMode::Manual);
+        manager.set_topology(CpuTopology::flat(&[0, 1, 2, 3]));
+        let mut background = crate::Assignment::new(7);
+        background.core_class = Some(CoreClass::Efficiency);
+        background.cpu_cores = vec![0];
+        let mut assignments = HashMap::from([(7, background)]);
+        assert_eq!(manager.apply_topology(topology, &mut assignments), vec![7]);
+        assert_eq!(assignments[&7].cpu_cores, vec![2]);
+    }
+}
@@ -9,16 +9,27 @@
 pub mod manifest_parser;
 /// Source parser module for parsing permission source files.
 pub mod source_parser;
+/// Semantic manifest checks against the host (`ManifestParser::validate`).
+pub mod validation;
+/// TOML/JSON manifest schema (`ManifestParser::load_toml` / `load_json`).
+#[cfg(feature = "serialization")]
+pub mod structured_manifest;
 
 // Re-export main types
 pub use manifest_parser::{
-    ManifestParser, ManifestError, WasmaManifest,
-    AppMetadata, ResourceConfig, 
+    ManifestParser, ManifestError, WasmaManifest, MANIFEST_API_LEVEL,
+    ResolvedManifest, ResolvedLine,
+    AppMetadata, LaunchCommand, ResourceConfig, 
     CpuAffinityConfig, CpuCoreServe,
     GpuConfig, GpuAllocationType, GpuSizeMode, GpuUsing,
     RamConfig, CacheMode, RamBitwidth,
     PermissionReference, PermissionCheckType,
-    WindowConfig,
+    WindowConfig, WindowPlacement, ManifestWindowType, InitialWindowState,
+    EnvironmentConfig, EnvConflict,
+};
+
+pub use validation::{
+    Diagnostic, HostLimits, Severity, ValidationReport,
 };
 
 pub use source_parser::{
@@ -2,10 +2,14 @@
 // Parses .manifest files for application resource configuration
 // January 15, 2026
 
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
-use wbackend::ExecutionMode;
+use wbackend::{CoreClass, CorePlacement, ExecutionMode};
+
+use crate::source_parser::SourceParser;
 
 #[derive(Debug, Error)]
 /// Error type for manifest parsing operations.
@@ -40,11 +44,32 @@ pub enum ManifestError {
         reason: String 
     },
     
+    /// Manifest targets a newer format than this parser understands.
+    #[error("Manifest requires api_level {found}, but this WASMA supports up to api_level {supported}; update WASMA to use it")]
+    UnsupportedApiLevel {
+        /// Level declared by the manifest.
+        found: u32,
+        /// Highest level this parser supports.
+        supported: u32,
+    },
+    
+    /// `include` directives lead back to a manifest that is already being read.
+    #[error("Manifest include cycle: {}", .0.join(" -> "))]
+    IncludeCycle(Vec<String>),
+    
     /// IO error during manifest operations.
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
 
+/// Newest manifest format understood by this parser.
+///
+/// * 1 – original format (assumed when `api_level` is absent); `app_uri`
+///   doubles as the launch command.
+/// * 2 – `exec`/`args`/`cwd`, `handles_*` and `[environment]`; `app_uri`
+///   is ignored and the `ram_used_bitwitdh` spelling is deprecated.
+pub const MANIFEST_API_LEVEL: u32 = 2;
+
 /// WASMA Application Manifest Structure
 #[derive(Debug, Clone)]
 pub struct WasmaManifest {
@@ -59,6 +84,15 @@ pub struct WasmaManifest {
     
     /// Window configuration
     pub window: WindowConfig,
+
+    /// Per-application environment from the `[environment]` block
+    pub environment: EnvironmentConfig,
+
+    /// Format level the manifest was written against
+    pub api_level: u32,
+
+    /// Deprecation notices raised while parsing
+    pub warnings: Vec<String>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -74,6 +108,143 @@ pub struct AppMetadata {
     pub uri_app_source: Option<String>,
     /// URIs to application resources.
     pub uri_app_resource: Vec<String>,
+    /// Program to launch; may carry its own arguments and field codes.
+    pub exec: Option<String>,
+    /// Extra arguments appended after `exec`, with field codes.
+    pub args: Vec<String>,
+    /// Working directory for the launched process.
+    pub cwd: Option<String>,
+    /// MIME types this application opens (`handles_mime = [..]`).
+    pub handles_mime: Vec<String>,
+    /// URI schemes this application opens (`handles_scheme = [..]`).
+    pub handles_scheme: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A fully expanded command line built from the manifest's launch fields.
+pub struct LaunchCommand {
+    /// Executable path or name.
+    pub program: String,
+    /// Arguments after field-code expansion.
+    pub args: Vec<String>,
+    /// Expanded working directory.
+    pub cwd: Option<String>,
+}
+
+impl AppMetadata {
+    /// Expand `exec`, `args` and `cwd` for the given targets (paths or URIs).
+    ///
+    /// Field codes follow desktop entries: `%f`/`%F` local file(s), `%u`/`%U`
+    /// URI(s), `%i` → `--icon <uri_appimg>`, `%c` app name, `%%` literal `%`.
+    /// Returns `None` when the manifest has no `exec`.
+    pub fn launch_command(&self, targets: &[&str]) -> Option<LaunchCommand> {
+        let exec = self.exec.as_deref()?;
+
+        let mut words = split_words(exec);
+        words.extend(self.args.iter().cloned());
+
+        let mut expanded = Vec::new();
+        for word in &words {
+            expanded.extend(self.expand_word(word, targets));
+        }
+        if expanded.is_empty() {
+            return None;
+        }
+
+        let program = expanded.remove(0);
+        let cwd = self.cwd.as_ref().map(|cwd| self.expand_word(cwd, targets).join(" "));
+
+        Some(LaunchCommand { program, args: expanded, cwd })
+    }
+
+    fn expand_word(&self, word: &str, targets: &[&str]) -> Vec<String> {
+        let files: Vec<String> = targets.iter().map(|t| to_local_path(t)).collect();
+        let uris: Vec<String> = targets.iter().map(|t| to_uri(t)).collect();
+
+        // A code standing alone may expand to several arguments (or none)
+        match word {
+            "%F" => return files,
+            "%U" => return uris,
+            "%f" => return files.into_iter().take(1).collect(),
+            "%u" => return uris.into_iter().take(1).collect(),
+            "%i" => {
+                return match &self.uri_appimg {
+                    Some(icon) => vec!["--icon".to_string(), to_local_path(icon)],
+                    None => Vec::new(),
+                };
+            }
+            _ => {}
+        }
+
+        // Embedded codes are replaced inside the word
+        let mut out = String::new();
+        let mut chars = word.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('f') | Some('F') => out.push_str(files.first().map(String::as_str).unwrap_or("")),
+                Some('u') | Some('U') => out.push_str(uris.first().map(String::as_str).unwrap_or("")),
+                Some('i') => out.push_str(&self.uri_appimg.as_deref().map(to_local_path).unwrap_or_default()),
+                Some('c') => out.push_str(&self.name),
+                Some('%') => out.push('%'),
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                }
+                None => out.push('%'),
+            }
+        }
+        vec![out]
+    }
+}
+
+/// Split a command line on whitespace, honoring double and single quotes.
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_word = false;
+
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+
+    words
+}
+
+fn to_local_path(target: &str) -> String {
+    target.strip_prefix("file://").unwrap_or(target).to_string()
+}
+
+fn to_uri(target: &str) -> String {
+    if target.contains("://") {
+        target.to_string()
+    } else {
+        format!("file://{}", target)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -85,6 +256,10 @@ pub struct ResourceConfig {
     pub cpu_affinity: CpuAffinityConfig,
     /// CPU core serving mode.
     pub cpu_core_serve: CpuCoreServe,
+    /// Physical core / SMT sibling preference when cores are auto-bound.
+    pub cpu_placement: CorePlacement,
+    /// P-core / E-core request on hybrid CPUs; `None` lets the scheduler decide.
+    pub core_class: Option<CoreClass>,
     
     /// GPU configuration.
     pub gpu_perp: GpuConfig,
@@ -100,6 +275,32 @@ pub struct ResourceConfig {
     pub execution_mode: ExecutionMode,
 }
 
+impl Default for ResourceConfig {
+    /// Values used for keys a manifest leaves out
+    fn default() -> Self {
+        Self {
+            cpu_perception: 1,
+            cpu_affinity: CpuAffinityConfig { resource_max: 10, bitmax: 20 },
+            cpu_core_serve: CpuCoreServe::Static(1),
+            cpu_placement: CorePlacement::default(),
+            core_class: None,
+            gpu_perp: GpuConfig {
+                allocation_type: GpuAllocationType::Allocation,
+                size_mode: GpuSizeMode::ByDefault,
+                default_size: 1024,
+            },
+            gpu_using: GpuUsing { size: 1024, resource_max: 15, bitwidth: 25 },
+            ram_using: RamConfig {
+                ram_type: "DDR5".to_string(),
+                size: 1024,
+                cache_mode: CacheMode::SwapOnline,
+            },
+            ram_used_bitwidth: RamBitwidth { size: 1024, bit_width: 15, cache_resourceing: 20.0 },
+            execution_mode: ExecutionMode::GpuPreferred,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 /// CPU affinity configuration.
 pub struct CpuAffinityConfig {
@@ -206,6 +407,38 @@ pub struct PermissionReference {
     pub source_path: Option<String>,
 }
 
+impl PermissionReference {
+    /// Permission source file: `source_path` when set, otherwise the default
+    /// location of `permission_check`, with `$HOME`, `$USE_CONFIG` and `$ROOT` expanded.
+    pub fn source_file(&self) -> PathBuf {
+        let path = match &self.source_path {
+            Some(source) => source.clone(),
+            None => SourceParser::new(None)
+                .resolve_source_path(self.permission_check.as_str())
+                .display()
+                .to_string(),
+        };
+        expand_source_path(&path)
+    }
+}
+
+/// `$HOME`, `$USE_CONFIG` and `$ROOT` as used by the permission source paths
+fn expand_source_path(path: &str) -> PathBuf {
+    let home = dirs::home_dir().map(|h| h.display().to_string()).unwrap_or_default();
+    let config = std::env::var("USE_CONFIG").unwrap_or_else(|_| ".config".to_string());
+    let expanded = path.replace("$HOME", &home).replace("$USE_CONFIG", &config).replace("$ROOT", "");
+    PathBuf::from(expanded.replace("//", "/"))
+}
+
+impl Default for PermissionReference {
+    fn default() -> Self {
+        Self {
+            permission_check: PermissionCheckType::PermissionDevel,
+            source_path: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 /// Types of permission checks.
 pub enum PermissionCheckType {
@@ -221,8 +454,33 @@ pub enum PermissionCheckType {
     PermissionPurning,
 }
 
+impl PermissionCheckType {
+    /// Manifest spelling, e.g. `permission_devel`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PermissionDevel => "permission_devel",
+            Self::PermissionSys => "permission_sys",
+            Self::PermissionPreset => "permission_preset",
+            Self::PermissionPinning => "permission_pinning",
+            Self::PermissionPurning => "permission_purning",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 /// Window configuration for the application.
+///
+/// ```text
+/// [window]
+/// width = 800
+/// height = 600
+/// resizable = true
+/// position = center          *// auto, center or x,y
+/// window_type = dialog       *// normal, dialog, utility, splash, notification
+/// decorations = false        *// omit to follow the WASMA decoration theme
+/// always_on_top = true
+/// state = maximized          *// normal, maximized, minimized, fullscreen
+/// ```
 pub struct WindowConfig {
     /// Window width.
     pub width: Option<u32>,
@@ -230,6 +488,343 @@ pub struct WindowConfig {
     pub height: Option<u32>,
     /// Whether the window is resizable.
     pub resizable: bool,
+    /// Where the window first appears.
+    pub position: WindowPlacement,
+    /// Role of the window.
+    pub window_type: ManifestWindowType,
+    /// Server-side decorations; `None` follows the WASMA theme.
+    pub decorations: Option<bool>,
+    /// Keep the window above normal windows.
+    pub always_on_top: bool,
+    /// State the window opens in.
+    pub initial_state: InitialWindowState,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Initial window position.
+pub enum WindowPlacement {
+    /// Let WASMA place the window.
+    #[default]
+    Auto,
+    /// Center on the output.
+    Centered,
+    /// Fixed top-left corner.
+    At {
+        /// Horizontal offset.
+        x: i32,
+        /// Vertical offset.
+        y: i32,
+    },
+}
+
+impl WindowPlacement {
+    /// Parse `auto`, `center` or `x,y`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().trim_matches('"').to_lowercase();
+        match value.as_str() {
+            "auto" | "default" => Some(Self::Auto),
+            "center" | "centered" => Some(Self::Centered),
+            _ => {
+                let (x, y) = value.split_once(',')?;
+                Some(Self::At { x: x.trim().parse().ok()?, y: y.trim().parse().ok()? })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Role of a manifest window.
+pub enum ManifestWindowType {
+    /// Regular application window.
+    #[default]
+    Normal,
+    /// Dialog.
+    Dialog,
+    /// Tool palette or utility window.
+    Utility,
+    /// Splash screen.
+    Splash,
+    /// Notification popup.
+    Notification,
+}
+
+impl ManifestWindowType {
+    /// Accepted names, in manifest spelling.
+    pub const NAMES: [(&'static str, Self); 5] = [
+        ("normal", Self::Normal),
+        ("dialog", Self::Dialog),
+        ("utility", Self::Utility),
+        ("splash", Self::Splash),
+        ("notification", Self::Notification),
+    ];
+
+    /// Parse a manifest name (case-insensitive).
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().trim_matches('"').to_lowercase();
+        Self::NAMES.iter().find(|(name, _)| *name == value).map(|(_, t)| *t)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// State a manifest window opens in.
+pub enum InitialWindowState {
+    /// Regular size and position.
+    #[default]
+    Normal,
+    /// Maximized.
+    Maximized,
+    /// Minimized.
+    Minimized,
+    /// Fullscreen.
+    Fullscreen,
+}
+
+impl InitialWindowState {
+    /// Accepted names, in manifest spelling.
+    pub const NAMES: [(&'static str, Self); 4] = [
+        ("normal", Self::Normal),
+        ("maximized", Self::Maximized),
+        ("minimized", Self::Minimized),
+        ("fullscreen", Self::Fullscreen),
+    ];
+
+    /// Parse a manifest name (case-insensitive).
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().trim_matches('"').to_lowercase();
+        Self::NAMES.iter().find(|(name, _)| *name == value).map(|(_, s)| *s)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Environment overrides applied when the application launches.
+///
+/// ```text
+/// [environment]
+/// GDK_BACKEND = wayland
+/// PATH = "$PATH:/opt/app/bin"
+/// unset LD_PRELOAD
+/// ```
+pub struct EnvironmentConfig {
+    /// Variables to set, in manifest order. Values may reference `$VAR` / `${VAR}`.
+    pub set: Vec<(String, String)>,
+    /// Variables removed from the launch environment.
+    pub unset: Vec<String>,
+}
+
+impl EnvironmentConfig {
+    /// Whether the block is absent or empty.
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.unset.is_empty()
+    }
+
+    /// Merge the overrides over `base`: unset first, then set in order.
+    /// References are expanded against the environment as built so far.
+    pub fn apply(&self, base: &mut HashMap<String, String>) {
+        for key in &self.unset {
+            base.remove(key);
+        }
+        for (key, value) in &self.set {
+            let expanded = expand_vars(value, base);
+            base.insert(key.clone(), expanded);
+        }
+    }
+
+    /// Variables this block changes that `defined` (the env.path exports) already sets.
+    pub fn conflicts(&self, defined: &HashMap<String, String>) -> Vec<EnvConflict> {
+        let mut conflicts = Vec::new();
+
+        for key in &self.unset {
+            if let Some(existing) = defined.get(key) {
+                conflicts.push(EnvConflict {
+                    key: key.clone(),
+                    manifest_value: None,
+                    env_path_value: existing.clone(),
+                });
+            }
+        }
+        for (key, value) in &self.set {
+            match defined.get(key) {
+                Some(existing) if existing != value => conflicts.push(EnvConflict {
+                    key: key.clone(),
+                    manifest_value: Some(value.clone()),
+                    env_path_value: existing.clone(),
+                }),
+                _ => {}
+            }
+        }
+
+        conflicts
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A manifest environment entry that overrides a variable defined in env.path.
+pub struct EnvConflict {
+    /// Variable name.
+    pub key: String,
+    /// New value, `None` when the manifest unsets the variable.
+    pub manifest_value: Option<String>,
+    /// Value exported by env.path.
+    pub env_path_value: String,
+}
+
+impl fmt::Display for EnvConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.manifest_value {
+            Some(value) => write!(f, "{} = \"{}\" overrides env.path value \"{}\"", self.key, value, self.env_path_value),
+            None => write!(f, "unset {} removes env.path value \"{}\"", self.key, self.env_path_value),
+        }
+    }
+}
+
+pub(crate) fn is_structured_path(path: &str) -> bool {
+    matches!(
+        Path::new(path).extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref(),
+        Some("toml" | "json")
+    )
+}
+
+/// `true`/`false`, `yes`/`no`, `1`/`0`
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "1" => Some(true),
+        "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Environment variable names: `[A-Za-z_][A-Za-z0-9_]*`
+pub(crate) fn valid_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `handles_mime` entries need a `/`, `handles_scheme` entries may only use RFC 3986 scheme characters
+pub(crate) fn valid_handler(field: &str, entry: &str) -> bool {
+    if field == "handles_mime" {
+        matches!(entry.split_once('/'), Some((kind, sub)) if !kind.is_empty() && !sub.is_empty() && !sub.contains('/'))
+    } else {
+        entry.starts_with(|c: char| c.is_ascii_alphabetic())
+            && entry.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    }
+}
+
+/// Expand `$VAR` and `${VAR}`; unknown variables expand to an empty string.
+fn expand_vars(value: &str, vars: &HashMap<String, String>) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+
+        let braced = chars.peek() == Some(&'{');
+        if braced {
+            chars.next();
+        }
+
+        let mut name = String::new();
+        while let Some(&n) = chars.peek() {
+            if n.is_ascii_alphanumeric() || n == '_' {
+                name.push(n);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        if braced && chars.peek() == Some(&'}') {
+            chars.next();
+        }
+
+        if name.is_empty() {
+            out.push('$');
+        } else if let Some(v) = vars.get(&name) {
+            out.push_str(v);
+        }
+    }
+
+    out
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// One manifest line after `include` directives are resolved.
+pub struct ResolvedLine {
+    /// Section the line belongs to; empty for top-level keys.
+    pub section: String,
+    /// Key the line sets (the variable name inside `[environment]`); empty if it sets none.
+    pub key: String,
+    /// The line without its trailing comment.
+    pub text: String,
+    /// File the line was read from.
+    pub origin: String,
+    /// 1-based line number in `origin`.
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// A manifest with its `include` chain flattened.
+///
+/// Included files come first and every later definition of a key replaces
+/// the earlier one, so a derived manifest overrides its base field by field:
+///
+/// ```text
+/// include = base.manifest
+/// name = Viewer
+/// ram_using = "DDR5" "2048MB"
+/// ```
+pub struct ResolvedManifest {
+    /// Every file read, in include order; the requested manifest is last.
+    pub files: Vec<String>,
+    /// Effective lines; overridden definitions are dropped.
+    pub lines: Vec<ResolvedLine>,
+}
+
+impl ResolvedManifest {
+    /// Manifest text equivalent to the whole chain: top-level keys first, then each section.
+    pub fn content(&self) -> String {
+        self.render(false).0
+    }
+
+    /// `content()` with the file and line every definition came from, for debugging inheritance.
+    pub fn dump(&self) -> String {
+        let mut out = format!("*// resolved from {}\n", self.files.join(" <- "));
+        out.push_str(&self.render(true).0);
+        out
+    }
+
+    /// Rendered text and, per output line, the resolved line it came from
+    fn render(&self, with_origin: bool) -> (String, Vec<Option<&ResolvedLine>>) {
+        let mut sections: Vec<&str> = Vec::new();
+        for line in &self.lines {
+            if !sections.contains(&line.section.as_str()) {
+                sections.push(&line.section);
+            }
+        }
+        // Top-level keys must precede every section header
+        sections.sort_by_key(|s| !s.is_empty());
+
+        let mut out = String::new();
+        let mut origins = Vec::new();
+        for section in sections {
+            if !section.is_empty() {
+                out.push_str(&format!("[{}]\n", section));
+                origins.push(None);
+            }
+            for line in self.lines.iter().filter(|l| l.section == section) {
+                if with_origin {
+                    out.push_str(&format!("{} *// {}:{}\n", line.text, line.origin, line.line));
+                } else {
+                    out.push_str(&line.text);
+                    out.push('\n');
+                }
+                origins.push(Some(line));
+            }
+        }
+        (out, origins)
+    }
 }
 
 /// Manifest Parser
@@ -244,49 +839,182 @@ impl ManifestParser {
     }
 
     /// Load and parse the manifest from the file.
+    /// `.toml` and `.json` files use the structured schema (see load_toml), anything else the legacy syntax.
     pub fn load(&self) -> Result<WasmaManifest, ManifestError> {
         if !Path::new(&self.path).exists() {
             return Err(ManifestError::FileNotFound(self.path.clone()));
         }
 
+        let extension = Path::new(&self.path).extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+        match extension.as_deref() {
+            #[cfg(feature = "serialization")]
+            Some("toml") => return self.load_toml(),
+            #[cfg(feature = "serialization")]
+            Some("json") => return self.load_json(),
+            #[cfg(not(feature = "serialization"))]
+            Some(format @ ("toml" | "json")) => {
+                return Err(ManifestError::InvalidValue {
+                    field: self.path.clone(),
+                    reason: format!("{} manifests need wsdg-app-manifest's `serialization` feature", format),
+                });
+            }
+            _ => {}
+        }
+
         let content = fs::read_to_string(&self.path)
             .map_err(|e| ManifestError::ReadError(e.to_string()))?;
 
         self.parse(&content)
     }
 
-    /// Parse manifest content from a string.
+    /// Path this parser reads
+    pub(crate) fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Whether the file is a TOML/JSON manifest, which cannot embed a `[source]` section
+    pub fn is_structured(&self) -> bool {
+        is_structured_path(&self.path)
+    }
+
+    /// Read the manifest and everything it includes, without interpreting values.
+    pub fn resolve(&self) -> Result<ResolvedManifest, ManifestError> {
+        if !Path::new(&self.path).exists() {
+            return Err(ManifestError::FileNotFound(self.path.clone()));
+        }
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| ManifestError::ReadError(e.to_string()))?;
+        self.resolve_content(&content)
+    }
+
+    /// Resolve `include` directives in `content`; relative paths start at this manifest's directory.
+    pub fn resolve_content(&self, content: &str) -> Result<ResolvedManifest, ManifestError> {
+        let mut resolved = ResolvedManifest::default();
+        let root = Path::new(&self.path);
+        let mut stack = vec![fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf())];
+        self.resolve_into(content, root, &mut stack, &mut resolved)?;
+        Ok(resolved)
+    }
+
+    fn resolve_into(
+        &self,
+        content: &str,
+        origin: &Path,
+        stack: &mut Vec<PathBuf>,
+        resolved: &mut ResolvedManifest,
+    ) -> Result<(), ManifestError> {
+        let mut section = String::new();
+
+        for (line_num, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("*//") || line.starts_with("//") {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line.trim_matches(|c| c == '[' || c == ']').trim().to_lowercase();
+                continue;
+            }
+
+            let text = self.extract_value(line);
+            let key = match self.split_key_value(&text) {
+                Some(("include", target)) if section.is_empty() => {
+                    self.include(target, origin, stack, resolved)?;
+                    continue;
+                }
+                Some((key, _)) => key.to_string(),
+                None if section == "environment" => text.strip_prefix("unset ").unwrap_or("").trim().to_string(),
+                None => String::new(),
+            };
+
+            if !key.is_empty() {
+                resolved.lines.retain(|l| l.section != section || l.key != key);
+            }
+            resolved.lines.push(ResolvedLine {
+                section: section.clone(),
+                key,
+                text,
+                origin: origin.display().to_string(),
+                line: line_num + 1,
+            });
+        }
+
+        resolved.files.push(origin.display().to_string());
+        Ok(())
+    }
+
+    fn include(
+        &self,
+        target: &str,
+        origin: &Path,
+        stack: &mut Vec<PathBuf>,
+        resolved: &mut ResolvedManifest,
+    ) -> Result<(), ManifestError> {
+        let target = target.trim_matches('"').trim_matches('\'');
+        let path = origin.parent().unwrap_or(Path::new("")).join(target);
+        let canonical = fs::canonicalize(&path)
+            .map_err(|_| ManifestError::FileNotFound(path.display().to_string()))?;
+
+        if let Some(start) = stack.iter().position(|p| *p == canonical) {
+            let mut cycle: Vec<String> = stack[start..].iter().map(|p| p.display().to_string()).collect();
+            cycle.push(canonical.display().to_string());
+            return Err(ManifestError::IncludeCycle(cycle));
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(|e| ManifestError::ReadError(e.to_string()))?;
+        stack.push(canonical);
+        self.resolve_into(&content, &path, stack, resolved)?;
+        stack.pop();
+        Ok(())
+    }
+
+    /// Parse manifest content from a string; `include` directives are followed.
     pub fn parse(&self, content: &str) -> Result<WasmaManifest, ManifestError> {
+        let resolved = self.resolve_content(content)?;
+        let (flat, origins) = resolved.render(false);
+
+        // Point errors at the file and line that caused them
+        self.parse_resolved(&flat).map_err(|e| match e {
+            ManifestError::ParseError { line, reason } => match origins.get(line.wrapping_sub(1)).copied().flatten() {
+                Some(origin) if origin.origin != self.path => ManifestError::ParseError {
+                    line: origin.line,
+                    reason: format!("{} (in {})", reason, origin.origin),
+                },
+                Some(origin) => ManifestError::ParseError { line: origin.line, reason },
+                None => ManifestError::ParseError { line, reason },
+            },
+            other => other,
+        })
+    }
+
+    fn parse_resolved(&self, content: &str) -> Result<WasmaManifest, ManifestError> {
         let mut app = AppMetadata::default();
-        let mut cpu_perception = 1;
-        let mut cpu_affinity = CpuAffinityConfig { resource_max: 10, bitmax: 20 };
-        let mut cpu_core_serve = CpuCoreServe::Static(1);
-        let mut gpu_perp = GpuConfig {
-            allocation_type: GpuAllocationType::Allocation,
-            size_mode: GpuSizeMode::ByDefault,
-            default_size: 1024,
-        };
-        let mut gpu_using = GpuUsing {
-            size: 1024,
-            resource_max: 15,
-            bitwidth: 25,
-        };
-        let mut ram_using = RamConfig {
-            ram_type: "DDR5".to_string(),
-            size: 1024,
-            cache_mode: CacheMode::SwapOnline,
-        };
-        let mut ram_bitwidth = RamBitwidth {
-            size: 1024,
-            bit_width: 15,
-            cache_resourceing: 20.0,
-        };
-        let mut permission_ref = PermissionReference {
-            permission_check: PermissionCheckType::PermissionDevel,
-            source_path: None,
-        };
-        let window = WindowConfig::default();
-        let mut execution_mode = ExecutionMode::GpuPreferred; // Default execution mode
+        let ResourceConfig {
+            mut cpu_perception,
+            mut cpu_affinity,
+            mut cpu_core_serve,
+            mut cpu_placement,
+            mut core_class,
+            mut gpu_perp,
+            mut gpu_using,
+            mut ram_using,
+            ram_used_bitwidth: mut ram_bitwidth,
+            mut execution_mode,
+        } = ResourceConfig::default();
+        let mut permission_ref = PermissionReference::default();
+        let mut window = WindowConfig::default();
+        let mut environment = EnvironmentConfig::default();
+        let mut section = String::new();
+        let api_level = self.detect_api_level(content)?;
+        let mut warnings = Vec::new();
+        let mut legacy_app_uri = None;
+
+        if api_level < MANIFEST_API_LEVEL {
+            warnings.push(format!(
+                "api_level {} is deprecated; declare api_level = {}",
+                api_level, MANIFEST_API_LEVEL
+            ));
+        }
 
         for (line_num, line) in content.lines().enumerate() {
             let line = line.trim();
@@ -296,6 +1024,22 @@ impl ManifestParser {
                 continue;
             }
 
+            // Section headers ([environment], [source], ...)
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line.trim_matches(|c| c == '[' || c == ']').trim().to_lowercase();
+                continue;
+            }
+
+            if section == "environment" {
+                self.parse_environment_line(line, line_num, &mut environment)?;
+                continue;
+            }
+
+            if section == "window" {
+                self.parse_window_line(line, line_num, &mut window)?;
+                continue;
+            }
+
             // Parse key = value pairs
             if let Some((key, value)) = self.split_key_value(line) {
                 match key {
@@ -314,6 +1058,21 @@ impl ManifestParser {
                     "uri_app_resource" => {
                         app.uri_app_resource = self.extract_uri_list(value);
                     }
+                    "exec" => {
+                        app.exec = Some(self.extract_value(value));
+                    }
+                    "args" => {
+                        app.args = split_words(&self.extract_value(value));
+                    }
+                    "cwd" => {
+                        app.cwd = Some(self.extract_uri(value));
+                    }
+                    "handles_mime" => {
+                        app.handles_mime = self.parse_handler_list(value, line_num, "handles_mime")?;
+                    }
+                    "handles_scheme" => {
+                        app.handles_scheme = self.parse_handler_list(value, line_num, "handles_scheme")?;
+                    }
                     "cpu_perception" => {
                         cpu_perception = self.parse_u32(value, line_num, "cpu_perception")?;
                     }
@@ -323,6 +1082,12 @@ impl ManifestParser {
                     "cpu_core_serve" => {
                         cpu_core_serve = self.parse_cpu_core_serve(value, line_num)?;
                     }
+                    "cpu_placement" => {
+                        cpu_placement = self.parse_cpu_placement(value);
+                    }
+                    "core_class" => {
+                        core_class = self.parse_core_class(value);
+                    }
                     "gpu_perp" => {
                         gpu_perp = self.parse_gpu_perp(value, line_num)?;
                     }
@@ -333,8 +1098,26 @@ impl ManifestParser {
                         ram_using = self.parse_ram_using(value, line_num)?;
                     }
                     "ram_used_bitwitdh" | "ram_used_bitwidth" => {
+                        if key == "ram_used_bitwitdh" && api_level >= 2 {
+                            warnings.push(format!(
+                                "line {}: ram_used_bitwitdh is deprecated, use ram_used_bitwidth",
+                                line_num + 1
+                            ));
+                        }
                         ram_bitwidth = self.parse_ram_bitwidth(value, line_num)?;
                     }
+                    "app_uri" => {
+                        if api_level >= 2 {
+                            warnings.push(format!(
+                                "line {}: app_uri is ignored since api_level 2, use exec",
+                                line_num + 1
+                            ));
+                        } else {
+                            legacy_app_uri = Some(self.extract_uri(value));
+                        }
+                    }
+                    // Read by detect_api_level
+                    "api_level" => {}
                     "permission_check" => {
                         permission_ref = self.parse_permission_check(value, line_num)?;
                     }
@@ -348,12 +1131,22 @@ impl ManifestParser {
             }
         }
 
+        // api_level 1: app_uri was the launch command
+        if app.exec.is_none() {
+            if let Some(uri) = legacy_app_uri {
+                let path = uri.strip_prefix("file://").unwrap_or(&uri);
+                app.exec = Some(format!("/{}", path.trim_start_matches('/')));
+            }
+        }
+
         Ok(WasmaManifest {
             app,
             resources: ResourceConfig {
                 cpu_perception,
                 cpu_affinity,
                 cpu_core_serve,
+                cpu_placement,
+                core_class,
                 gpu_perp,
                 gpu_using,
                 ram_using,
@@ -362,9 +1155,106 @@ impl ManifestParser {
             },
             permissions: permission_ref,
             window,
+            environment,
+            api_level,
+            warnings,
         })
     }
 
+    /// `api_level` may appear anywhere outside a section; absent means 1.
+    /// Checked before anything else so newer syntax reports the level, not a parse error.
+    fn detect_api_level(&self, content: &str) -> Result<u32, ManifestError> {
+        for (line_num, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.starts_with('[') {
+                break;
+            }
+            let Some(("api_level", value)) = self.split_key_value(line) else {
+                continue;
+            };
+
+            let level = self.parse_u32(value, line_num, "api_level")?;
+            if level == 0 {
+                return Err(ManifestError::InvalidValue {
+                    field: "api_level".to_string(),
+                    reason: "levels start at 1".to_string(),
+                });
+            }
+            if level > MANIFEST_API_LEVEL {
+                return Err(ManifestError::UnsupportedApiLevel {
+                    found: level,
+                    supported: MANIFEST_API_LEVEL,
+                });
+            }
+            return Ok(level);
+        }
+        Ok(1)
+    }
+
+    fn parse_window_line(&self, line: &str, line_num: usize, window: &mut WindowConfig) -> Result<(), ManifestError> {
+        let Some((key, value)) = self.split_key_value(line) else {
+            return Ok(());
+        };
+        let value = self.extract_uri(value);
+        let invalid = |what: &str| ManifestError::ParseError {
+            line: line_num + 1,
+            reason: format!("Invalid {} value: {}", what, value),
+        };
+
+        match key {
+            "width" => window.width = Some(self.parse_u32(&value, line_num, "width")?),
+            "height" => window.height = Some(self.parse_u32(&value, line_num, "height")?),
+            "resizable" => window.resizable = parse_bool(&value).ok_or_else(|| invalid("resizable"))?,
+            "position" => window.position = WindowPlacement::parse(&value).ok_or_else(|| invalid("position"))?,
+            "window_type" => {
+                window.window_type = ManifestWindowType::parse(&value).ok_or_else(|| invalid("window_type"))?
+            }
+            "decorations" => window.decorations = Some(parse_bool(&value).ok_or_else(|| invalid("decorations"))?),
+            "always_on_top" => window.always_on_top = parse_bool(&value).ok_or_else(|| invalid("always_on_top"))?,
+            "state" => window.initial_state = InitialWindowState::parse(&value).ok_or_else(|| invalid("state"))?,
+            _ => {
+                // Unknown key, skip
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_environment_line(&self, line: &str, line_num: usize, env: &mut EnvironmentConfig) -> Result<(), ManifestError> {
+        let line = self.extract_value(line);
+
+        if let Some(key) = line.strip_prefix("unset ") {
+            let key = key.trim();
+            if !valid_env_name(key) {
+                return Err(ManifestError::ParseError {
+                    line: line_num + 1,
+                    reason: format!("Invalid environment variable name: {}", key),
+                });
+            }
+            env.set.retain(|(k, _)| k != key);
+            env.unset.push(key.to_string());
+            return Ok(());
+        }
+
+        let Some((key, value)) = self.split_key_value(&line) else {
+            return Err(ManifestError::ParseError {
+                line: line_num + 1,
+                reason: format!("Expected KEY = value or unset KEY in [environment]: {}", line),
+            });
+        };
+        if !valid_env_name(key) {
+            return Err(ManifestError::ParseError {
+                line: line_num + 1,
+                reason: format!("Invalid environment variable name: {}", key),
+            });
+        }
+
+        let value = value.trim_matches('"').trim_matches('\'').to_string();
+        env.unset.retain(|k| k != key);
+        env.set.retain(|(k, _)| k != key);
+        env.set.push((key.to_string(), value));
+        Ok(())
+    }
+
     fn parse_execution_mode(&self, value: &str, _line_num: usize) -> Result<ExecutionMode, ManifestError> {
         let value = self.extract_value(value).to_lowercase();
         
@@ -406,6 +1296,39 @@ impl ManifestParser {
             .collect()
     }
 
+    /// `[image/png, "text/plain"]` → lowercase entries; MIME types need a `/`,
+    /// schemes may only use RFC 3986 scheme characters
+    fn parse_handler_list(&self, value: &str, line_num: usize, field: &str) -> Result<Vec<String>, ManifestError> {
+        let value = self.extract_value(value);
+        let inner = value.trim()
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+            .ok_or_else(|| ManifestError::ParseError {
+                line: line_num + 1,
+                reason: format!("{} expects a list like [a, b], got: {}", field, value),
+            })?;
+
+        let mut entries = Vec::new();
+        for entry in inner.split(',') {
+            let entry = entry.trim().trim_matches('"').trim_matches('\'').to_lowercase();
+            if entry.is_empty() {
+                continue;
+            }
+
+            if !valid_handler(field, &entry) {
+                return Err(ManifestError::ParseError {
+                    line: line_num + 1,
+                    reason: format!("Invalid {} entry: {}", field, entry),
+                });
+            }
+
+            if !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
     fn parse_u32(&self, value: &str, line_num: usize, field: &str) -> Result<u32, ManifestError> {
         let value = self.extract_value(value);
         value.parse().map_err(|_| ManifestError::ParseError {
@@ -450,6 +1373,23 @@ impl ManifestParser {
         }
     }
 
+    /// `spread` keeps assignments on distinct physical cores, `compact` fills SMT siblings first
+    fn parse_cpu_placement(&self, value: &str) -> CorePlacement {
+        match self.extract_value(value).trim_matches('"').to_lowercase().as_str() {
+            "compact" | "smt" => CorePlacement::Compact,
+            _ => CorePlacement::Spread,
+        }
+    }
+
+    /// `performance` (P-core/big) or `efficiency` (E-core/LITTLE); anything else is automatic
+    fn parse_core_class(&self, value: &str) -> Option<CoreClass> {
+        match self.extract_value(value).trim_matches('"').to_lowercase().as_str() {
+            "performance" | "p" | "big" => Some(CoreClass::Performance),
+            "efficiency" | "e" | "little" => Some(CoreClass::Efficiency),
+            _ => None,
+        }
+    }
+
     fn parse_gpu_perp(&self, value: &str, _line_num: usize) -> Result<GpuConfig, ManifestError> {
         // Parse: "VRAM:allocation:size_bydefault = 1024"
         let value = self.extract_value(value).trim_matches('"').to_string();
@@ -458,7 +1398,8 @@ impl ManifestParser {
         let mut size_mode = GpuSizeMode::ByDefault;
         let mut default_size = 1024;
 
-        if value.contains("location") {
+        // "VRAM:location:..." - a plain contains() would also match "allocation"
+        if value.split(':').any(|part| part.trim() == "location") {
             allocation_type = GpuAllocationType::Location(value.clone());
         }
 
@@ -663,5 +1604,202 @@ execution_mode = cpu_only
         let manifest = parser.parse(content).unwrap();
         
         assert!(matches!(manifest.resources.execution_mode, ExecutionMode::CpuOnly));
+        assert_eq!(manifest.resources.cpu_placement, CorePlacement::Spread);
+
+        let compact = parser.parse("name = TestApp\ncpu_placement = compact\n").unwrap();
+        assert_eq!(compact.resources.cpu_placement, CorePlacement::Compact);
+        assert_eq!(compact.resources.core_class, None);
+
+        let background = parser.parse("name = TestApp\ncore_class = efficiency\n").unwrap();
+        assert_eq!(background.resources.core_class, Some(CoreClass::Efficiency));
+    }
+
+    #[test]
+    fn test_launch_templating() {
+        let content = r#"
+name = Viewer
+uri_appimg = file:///usr/share/icons/viewer.png
+exec = /usr/bin/viewer --class "%c"
+args = %i --open %F
+cwd = "/tmp/%c"
+        "#;
+
+        let parser = ManifestParser::new("test.manifest".to_string());
+        let manifest = parser.parse(content).unwrap();
+
+        let cmd = manifest.app.launch_command(&["/home/u/a.png", "file:///home/u/b.png"]).unwrap();
+        assert_eq!(cmd.program, "/usr/bin/viewer");
+        assert_eq!(cmd.args, vec![
+            "--class", "Viewer",
+            "--icon", "/usr/share/icons/viewer.png",
+            "--open", "/home/u/a.png", "/home/u/b.png",
+        ]);
+        assert_eq!(cmd.cwd.as_deref(), Some("/tmp/Viewer"));
+
+        let mut app = manifest.app.clone();
+        app.args = vec!["%u".to_string()];
+        let cmd = app.launch_command(&["/home/u/a.png"]).unwrap();
+        assert_eq!(cmd.args.last().unwrap(), "file:///home/u/a.png");
+
+        app.exec = None;
+        assert!(app.launch_command(&[]).is_none());
+    }
+
+    #[test]
+    fn test_api_levels() {
+        let parser = ManifestParser::new("test.manifest".to_string());
+
+        // No api_level: legacy app_uri becomes the launch command
+        let legacy = parser.parse("name = Old\napp_uri = file:///usr/bin/old\n").unwrap();
+        assert_eq!(legacy.api_level, 1);
+        assert_eq!(legacy.app.exec.as_deref(), Some("/usr/bin/old"));
+        assert_eq!(legacy.warnings.len(), 1);
+
+        let current = parser.parse("api_level = 2\nname = New\napp_uri = file:///usr/bin/old\nram_used_bitwitdh = \"512MB\" \"bit_width : 8\"\n").unwrap();
+        assert_eq!(current.api_level, MANIFEST_API_LEVEL);
+        assert!(current.app.exec.is_none());
+        assert_eq!(current.warnings.len(), 2);
+
+        match parser.parse("name = Future\napi_level = 99\nfuture_syntax {{ }}\n") {
+            Err(ManifestError::UnsupportedApiLevel { found: 99, supported }) => {
+                assert_eq!(supported, MANIFEST_API_LEVEL);
+            }
+            other => panic!("expected UnsupportedApiLevel, got {:?}", other.map(|m| m.api_level)),
+        }
+        assert!(parser.parse("api_level = 0").is_err());
+    }
+
+    #[test]
+    fn test_handler_lists() {
+        let content = r#"
+name = Viewer
+handles_mime = [image/png, "Image/JPEG", image/png] *// duplicates collapse
+handles_scheme = [viewer, web+viewer]
+        "#;
+
+        let parser = ManifestParser::new("test.manifest".to_string());
+        let manifest = parser.parse(content).unwrap();
+        assert_eq!(manifest.app.handles_mime, vec!["image/png", "image/jpeg"]);
+        assert_eq!(manifest.app.handles_scheme, vec!["viewer", "web+viewer"]);
+
+        assert!(parser.parse("handles_mime = [png]").is_err());
+        assert!(parser.parse("handles_scheme = [9p]").is_err());
+        assert!(parser.parse("handles_scheme = viewer").is_err());
+    }
+
+    #[test]
+    fn test_environment_block() {
+        let content = r#"
+name = TestApp
+
+[environment]
+GDK_BACKEND = wayland
+PATH = "$PATH:/opt/app/bin" *// append
+unset LD_PRELOAD
+        "#;
+
+        let parser = ManifestParser::new("test.manifest".to_string());
+        let manifest = parser.parse(content).unwrap();
+        let env = &manifest.environment;
+
+        assert_eq!(manifest.app.name, "TestApp");
+        assert_eq!(env.unset, vec!["LD_PRELOAD".to_string()]);
+
+        let mut base = HashMap::new();
+        base.insert("PATH".to_string(), "/usr/bin".to_string());
+        base.insert("LD_PRELOAD".to_string(), "libfoo.so".to_string());
+        env.apply(&mut base);
+
+        assert_eq!(base.get("PATH").unwrap(), "/usr/bin:/opt/app/bin");
+        assert_eq!(base.get("GDK_BACKEND").unwrap(), "wayland");
+        assert!(!base.contains_key("LD_PRELOAD"));
+
+        let mut defined = HashMap::new();
+        defined.insert("GDK_BACKEND".to_string(), "x11".to_string());
+        let conflicts = env.conflicts(&defined);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].key, "GDK_BACKEND");
+
+        assert!(parser.parse("[environment]\n1BAD = x").is_err());
+    }
+
+    #[test]
+    fn test_window_section() {
+        let content = r#"
+name = TestApp
+
+[window]
+width = 800
+height = 600
+position = 40, 60 *// top-left corner
+window_type = utility
+decorations = no
+always_on_top = true
+state = fullscreen
+        "#;
+
+        let parser = ManifestParser::new("test.manifest".to_string());
+        let window = parser.parse(content).unwrap().window;
+
+        assert_eq!((window.width, window.height), (Some(800), Some(600)));
+        assert_eq!(window.position, WindowPlacement::At { x: 40, y: 60 });
+        assert_eq!(window.window_type, ManifestWindowType::Utility);
+        assert_eq!(window.decorations, Some(false));
+        assert!(window.always_on_top);
+        assert_eq!(window.initial_state, InitialWindowState::Fullscreen);
+
+        let centered = parser.parse("[window]\nposition = center").unwrap().window;
+        assert_eq!(centered.position, WindowPlacement::Centered);
+        assert_eq!(centered.decorations, None);
+        assert!(parser.parse("[window]\nstate = shaded").is_err());
+    }
+
+    #[test]
+    fn test_manifest_include() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("profiles")).unwrap();
+        let write = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            path.display().to_string()
+        };
+        write("base.manifest", "api_level = 2\nname = Base\ncpu_perception = 4\nram_using = \"DDR5\" \"1024MB\"\n[environment]\nGDK_BACKEND = wayland\nTRACE = 1\n");
+        write("profiles/heavy.manifest", "include = ../base.manifest\nram_using = \"DDR5\" \"4096MB\" *// more RAM\n[environment]\nunset TRACE\n");
+        let app = write("app.manifest", "include = \"profiles/heavy.manifest\"\nname = Viewer\nexecution_mode = cpu_only\n");
+
+        let parser = ManifestParser::new(app.clone());
+        let manifest = parser.load().unwrap();
+        assert_eq!(manifest.api_level, 2);
+        assert_eq!(manifest.app.name, "Viewer");
+        assert_eq!(manifest.resources.cpu_perception, 4);
+        assert_eq!(manifest.resources.ram_using.size, 4096);
+        assert!(matches!(manifest.resources.execution_mode, ExecutionMode::CpuOnly));
+        assert_eq!(manifest.environment.set, vec![("GDK_BACKEND".to_string(), "wayland".to_string())]);
+        assert_eq!(manifest.environment.unset, vec!["TRACE".to_string()]);
+
+        let resolved = parser.resolve().unwrap();
+        assert_eq!(resolved.files.len(), 3);
+        assert_eq!(resolved.files.last(), Some(&app));
+        let ram = resolved.lines.iter().find(|l| l.key == "ram_using").unwrap();
+        assert!(ram.origin.ends_with("heavy.manifest"));
+        assert_eq!(ram.line, 2);
+        let dump = resolved.dump();
+        assert!(dump.contains(&format!("name = Viewer *// {}:2", app)));
+        assert!(!dump.contains("name = Base"));
+        // The dump is itself a valid manifest
+        assert_eq!(parser.parse(&resolved.content()).unwrap().app.name, "Viewer");
+
+        // Errors name the included file and its own line number
+        write("broken.manifest", "name = Broken\ncpu_perception = lots\n");
+        let derived = write("derived.manifest", "include = broken.manifest\n");
+        match ManifestParser::new(derived).load() {
+            Err(ManifestError::ParseError { line: 2, reason }) => assert!(reason.contains("broken.manifest")),
+            other => panic!("expected ParseError, got {:?}", other.map(|m| m.app.name)),
+        }
+
+        write("a.manifest", "include = b.manifest\n");
+        let b = write("b.manifest", "include = a.manifest\n");
+        assert!(matches!(ManifestParser::new(b).load(), Err(ManifestError::IncludeCycle(chain)) if chain.len() == 3));
+        assert!(matches!(parser.parse("include = missing.manifest"), Err(ManifestError::FileNotFound(_))));
     }
 }
@@ -221,6 +221,10 @@ impl Default for AudioPermission {
 #[derive(Debug, Clone, Default)]
 /// System-related permissions for the application.
 pub struct SystemPermissions {
+    /// Permission to register global hotkeys.
+    pub global_hotkeys: bool,
+    /// Isolation profile, 0 (none) to 10 (strictest).
+    pub sandbox_level: Option<u8>,
     /// Custom system permission fields.
     pub custom_fields: HashMap<String, String>,
 }
@@ -277,7 +281,15 @@ impl SourceParser {
     fn extract_source_section(&self, content: &str) -> Option<String> {
         if let Some(start_idx) = content.find("[source]") {
             let after_marker = &content[start_idx + 8..];
-            return Some(after_marker.to_string());
+            // The section ends at the next header, e.g. [environment]
+            let section: Vec<&str> = after_marker
+                .lines()
+                .take_while(|l| {
+                    let l = l.trim();
+                    !(l.starts_with('[') && l.ends_with(']'))
+                })
+                .collect();
+            return Some(section.join("\n"));
         }
         None
     }
@@ -288,7 +300,7 @@ impl SourceParser {
         let mut filesystem = FilesystemPermissions::default();
         let mut usb = UsbPermissions::default();
         let mut media = MediaPermissions::default();
-        let system = SystemPermissions::default();
+        let mut system = SystemPermissions::default();
         let mut custom = HashMap::new();
 
         for (_line_num, line) in content.lines().enumerate() {
@@ -338,6 +350,12 @@ impl SourceParser {
                     "USE_AUDIO" => {
                         media.audio = self.parse_audio_permission(value);
                     }
+                    "USE_GLOBAL_HOTKEYS" => {
+                        system.global_hotkeys = self.parse_bool(value);
+                    }
+                    "USE_SANDBOX_LEVEL" => {
+                        system.sandbox_level = value.parse::<u8>().ok().map(|level| level.min(10));
+                    }
                     _ => {
                         // Store as custom permission
                         custom.insert(key.to_string(), value.to_string());
@@ -0,0 +1,574 @@
+// WASMA Structured Manifests
+// TOML / JSON alternative to the legacy .manifest syntax
+// Every key is optional; omitted keys take the same defaults as the legacy parser.
+// Unknown keys and unknown enum values are errors instead of being ignored.
+//
+// ```toml
+// api_level = 2                         # defaults to MANIFEST_API_LEVEL
+//
+// [app]
+// name = "Example"
+// uri_appimg = "file://usr/share/pixelevel/map/12x12.png"
+// uri_shortcut = "file://Desktop"
+// uri_app_source = "file://usr/bin/app"
+// uri_app_resource = ["file://local/bin/app"]
+// exec = "/usr/bin/example-app"
+// args = ["--icon-name", "%c", "%F"]    # one entry per argument, no word splitting
+// cwd = "$HOME"
+// handles_mime = ["text/plain"]
+// handles_scheme = ["example-app"]
+//
+// [resources]
+// cpu_perception = 1
+// cpu_core_serve = 1                    # core count, "dynamic" or "affinity_default"
+// cpu_placement = "spread"              # spread | compact
+// core_class = "performance"            # performance | efficiency | auto
+// execution_mode = "gpu_preferred"      # cpu_only | gpu_only | gpu_preferred | hybrid
+// cpu_affinity = { resource_max = 10, bitmax = 20 }
+// gpu_perp = { size_mode = "bydefault", size_mb = 1024 }   # optional location = "..."
+// gpu_using = { size_mb = 1024, resource_max = 15, bitwidth = 25 }
+// ram_using = { type = "DDR5", size_mb = 1024, cache = "swaponline" }   # swaponline | swapoffline | resolved
+// ram_used_bitwidth = { size_mb = 1024, bit_width = 15, cache_percent = 20.0 }
+//
+// [permissions]
+// check = "permission_devel"            # permission_devel | _sys | _preset | _pinning | _purning
+// source = "/path/to/source"
+//
+// [window]
+// width = 800
+// height = 600
+// resizable = true
+// position = "center"                   # auto | center | "x,y"
+// window_type = "dialog"                # normal | dialog | utility | splash | notification
+// decorations = false                   # omit to follow the WASMA theme
+// always_on_top = true
+// state = "maximized"                   # normal | maximized | minimized | fullscreen
+//
+// [environment]                         # KEY = "value" in file order, plus an optional unset list
+// GDK_BACKEND = "wayland"
+// unset = ["LD_PRELOAD"]
+// ```
+//
+// JSON uses the same structure: {"app": {"name": "Example"}, "resources": {...}}
+
+use std::fmt;
+use std::fs;
+
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use wbackend::{CoreClass, CorePlacement, ExecutionMode};
+
+use crate::manifest_parser::{
+    valid_env_name, valid_handler, AppMetadata, CacheMode, CpuCoreServe, EnvironmentConfig,
+    GpuAllocationType, GpuSizeMode, ManifestError, ManifestParser, PermissionCheckType,
+    InitialWindowState, ManifestWindowType, PermissionReference, ResourceConfig, WasmaManifest,
+    WindowConfig, WindowPlacement, MANIFEST_API_LEVEL,
+};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Document {
+    api_level: Option<u32>,
+    app: AppSection,
+    resources: ResourcesSection,
+    permissions: PermissionsSection,
+    window: WindowSection,
+    environment: EnvironmentSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AppSection {
+    name: String,
+    uri_appimg: Option<String>,
+    uri_shortcut: Option<String>,
+    uri_app_source: Option<String>,
+    uri_app_resource: Vec<String>,
+    exec: Option<String>,
+    args: Vec<String>,
+    cwd: Option<String>,
+    handles_mime: Vec<String>,
+    handles_scheme: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ResourcesSection {
+    cpu_perception: Option<u32>,
+    cpu_affinity: CpuAffinitySection,
+    cpu_core_serve: Option<CoreServeValue>,
+    cpu_placement: Option<String>,
+    core_class: Option<String>,
+    execution_mode: Option<String>,
+    gpu_perp: GpuPerpSection,
+    gpu_using: GpuUsingSection,
+    ram_using: RamUsingSection,
+    ram_used_bitwidth: RamBitwidthSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CpuAffinitySection {
+    resource_max: Option<u32>,
+    bitmax: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CoreServeValue {
+    Cores(u32),
+    Mode(String),
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct GpuPerpSection {
+    location: Option<String>,
+    size_mode: Option<String>,
+    size_mb: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct GpuUsingSection {
+    size_mb: Option<u64>,
+    resource_max: Option<u32>,
+    bitwidth: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RamUsingSection {
+    #[serde(rename = "type")]
+    ram_type: Option<String>,
+    size_mb: Option<u64>,
+    cache: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RamBitwidthSection {
+    size_mb: Option<u64>,
+    bit_width: Option<u32>,
+    cache_percent: Option<f32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PermissionsSection {
+    check: Option<String>,
+    source: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct WindowSection {
+    width: Option<u32>,
+    height: Option<u32>,
+    resizable: bool,
+    position: Option<String>,
+    window_type: Option<String>,
+    decorations: Option<bool>,
+    always_on_top: bool,
+    state: Option<String>,
+}
+
+/// `[environment]` keeps the file order of its variables, so it is read by hand
+#[derive(Debug, Default)]
+struct EnvironmentSection {
+    set: Vec<(String, String)>,
+    unset: Vec<String>,
+}
+
+impl<'de> Deserialize<'de> for EnvironmentSection {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EnvVisitor;
+
+        impl<'de> Visitor<'de> for EnvVisitor {
+            type Value = EnvironmentSection;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a table of KEY = \"value\" entries and an optional unset list")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut section = EnvironmentSection::default();
+                while let Some(key) = map.next_key::<String>()? {
+                    if key == "unset" {
+                        section.unset = map.next_value()?;
+                    } else {
+                        let value: String = map.next_value()?;
+                        section.set.retain(|(k, _)| *k != key);
+                        section.set.push((key, value));
+                    }
+                }
+                Ok(section)
+            }
+        }
+
+        deserializer.deserialize_map(EnvVisitor)
+    }
+}
+
+impl ManifestParser {
+    /// Load a TOML manifest (schema in the structured_manifest module docs).
+    pub fn load_toml(&self) -> Result<WasmaManifest, ManifestError> {
+        let content = self.read()?;
+        self.parse_toml(&content)
+    }
+
+    /// Load a JSON manifest; same structure as the TOML schema.
+    pub fn load_json(&self) -> Result<WasmaManifest, ManifestError> {
+        let content = self.read()?;
+        self.parse_json(&content)
+    }
+
+    /// Parse TOML manifest content.
+    pub fn parse_toml(&self, content: &str) -> Result<WasmaManifest, ManifestError> {
+        let document: Document = toml::from_str(content).map_err(|e| ManifestError::ParseError {
+            line: e.span().map(|span| line_of(content, span.start)).unwrap_or(0),
+            reason: e.message().to_string(),
+        })?;
+        build(document)
+    }
+
+    /// Parse JSON manifest content.
+    pub fn parse_json(&self, content: &str) -> Result<WasmaManifest, ManifestError> {
+        let document: Document = serde_json::from_str(content).map_err(|e| ManifestError::ParseError {
+            line: e.line(),
+            reason: e.to_string(),
+        })?;
+        build(document)
+    }
+
+    fn read(&self) -> Result<String, ManifestError> {
+        fs::read_to_string(self.path()).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => ManifestError::FileNotFound(self.path().to_string()),
+            _ => ManifestError::ReadError(e.to_string()),
+        })
+    }
+}
+
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+/// Look `value` up in `choices`, naming the accepted values when it is not there
+fn choice<T: Clone>(field: &str, value: &str, choices: &[(&str, T)]) -> Result<T, ManifestError> {
+    let value = value.to_lowercase();
+    choices
+        .iter()
+        .find(|(name, _)| *name == value)
+        .map(|(_, v)| v.clone())
+        .ok_or_else(|| ManifestError::InvalidValue {
+            field: field.to_string(),
+            reason: format!(
+                "unknown value \"{}\", expected one of: {}",
+                value,
+                choices.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ")
+            ),
+        })
+}
+
+fn handlers(field: &str, entries: Vec<String>) -> Result<Vec<String>, ManifestError> {
+    let mut out: Vec<String> = Vec::new();
+    for entry in entries {
+        let entry = entry.trim().to_lowercase();
+        if !valid_handler(field, &entry) {
+            return Err(ManifestError::InvalidValue {
+                field: field.to_string(),
+                reason: format!("invalid entry: {}", entry),
+            });
+        }
+        if !out.contains(&entry) {
+            out.push(entry);
+        }
+    }
+    Ok(out)
+}
+
+fn build(document: Document) -> Result<WasmaManifest, ManifestError> {
+    let api_level = document.api_level.unwrap_or(MANIFEST_API_LEVEL);
+    if api_level == 0 {
+        return Err(ManifestError::InvalidValue {
+            field: "api_level".to_string(),
+            reason: "levels start at 1".to_string(),
+        });
+    }
+    if api_level > MANIFEST_API_LEVEL {
+        return Err(ManifestError::UnsupportedApiLevel { found: api_level, supported: MANIFEST_API_LEVEL });
+    }
+
+    let app = document.app;
+    let app = AppMetadata {
+        name: app.name,
+        uri_appimg: app.uri_appimg,
+        uri_shortcut: app.uri_shortcut,
+        uri_app_source: app.uri_app_source,
+        uri_app_resource: app.uri_app_resource,
+        exec: app.exec,
+        args: app.args,
+        cwd: app.cwd,
+        handles_mime: handlers("handles_mime", app.handles_mime)?,
+        handles_scheme: handlers("handles_scheme", app.handles_scheme)?,
+    };
+
+    let section = document.resources;
+    let mut resources = ResourceConfig::default();
+    if let Some(perception) = section.cpu_perception {
+        resources.cpu_perception = perception;
+    }
+    if let Some(resource_max) = section.cpu_affinity.resource_max {
+        resources.cpu_affinity.resource_max = resource_max;
+    }
+    if let Some(bitmax) = section.cpu_affinity.bitmax {
+        resources.cpu_affinity.bitmax = bitmax;
+    }
+    match section.cpu_core_serve {
+        Some(CoreServeValue::Cores(cores)) => resources.cpu_core_serve = CpuCoreServe::Static(cores),
+        Some(CoreServeValue::Mode(mode)) => {
+            resources.cpu_core_serve = choice(
+                "cpu_core_serve",
+                &mode,
+                &[("dynamic", CpuCoreServe::Dynamic), ("affinity_default", CpuCoreServe::AffinityDefault)],
+            )?;
+        }
+        None => {}
+    }
+    if let Some(placement) = section.cpu_placement {
+        resources.cpu_placement = choice(
+            "cpu_placement",
+            &placement,
+            &[("spread", CorePlacement::Spread), ("compact", CorePlacement::Compact)],
+        )?;
+    }
+    if let Some(class) = section.core_class {
+        resources.core_class = choice(
+            "core_class",
+            &class,
+            &[("performance", Some(CoreClass::Performance)), ("efficiency", Some(CoreClass::Efficiency)), ("auto", None)],
+        )?;
+    }
+    if let Some(mode) = section.execution_mode {
+        resources.execution_mode = choice(
+            "execution_mode",
+            &mode,
+            &[
+                ("cpu_only", ExecutionMode::CpuOnly),
+                ("gpu_only", ExecutionMode::GpuOnly),
+                ("gpu_preferred", ExecutionMode::GpuPreferred),
+                ("hybrid", ExecutionMode::Hybrid),
+            ],
+        )?;
+    }
+
+    if let Some(location) = section.gpu_perp.location {
+        resources.gpu_perp.allocation_type = GpuAllocationType::Location(location);
+    }
+    if let Some(size_mode) = section.gpu_perp.size_mode {
+        resources.gpu_perp.size_mode = choice(
+            "gpu_perp.size_mode",
+            &size_mode,
+            &[
+                ("bydefault", GpuSizeMode::ByDefault),
+                ("bycustom", GpuSizeMode::ByCustom),
+                ("bysection", GpuSizeMode::BySection),
+                ("byprop", GpuSizeMode::ByProp),
+            ],
+        )?;
+    }
+    if let Some(size) = section.gpu_perp.size_mb {
+        resources.gpu_perp.default_size = size;
+    }
+    if let Some(size) = section.gpu_using.size_mb {
+        resources.gpu_using.size = size;
+    }
+    if let Some(resource_max) = section.gpu_using.resource_max {
+        resources.gpu_using.resource_max = resource_max;
+    }
+    if let Some(bitwidth) = section.gpu_using.bitwidth {
+        resources.gpu_using.bitwidth = bitwidth;
+    }
+
+    if let Some(ram_type) = section.ram_using.ram_type {
+        resources.ram_using.ram_type = ram_type;
+    }
+    if let Some(size) = section.ram_using.size_mb {
+        resources.ram_using.size = size;
+    }
+    if let Some(cache) = section.ram_using.cache {
+        resources.ram_using.cache_mode = choice(
+            "ram_using.cache",
+            &cache,
+            &[
+                ("swaponline", CacheMode::SwapOnline),
+                ("swapoffline", CacheMode::SwapOffline),
+                ("resolved", CacheMode::Resolved),
+            ],
+        )?;
+    }
+    if let Some(size) = section.ram_used_bitwidth.size_mb {
+        resources.ram_used_bitwidth.size = size;
+    }
+    if let Some(bit_width) = section.ram_used_bitwidth.bit_width {
+        resources.ram_used_bitwidth.bit_width = bit_width;
+    }
+    if let Some(percent) = section.ram_used_bitwidth.cache_percent {
+        resources.ram_used_bitwidth.cache_resourceing = percent;
+    }
+
+    let mut permissions = PermissionReference { source_path: document.permissions.source, ..Default::default() };
+    if let Some(check) = document.permissions.check {
+        permissions.permission_check = choice(
+            "permissions.check",
+            &check,
+            &[
+                ("permission_devel", PermissionCheckType::PermissionDevel),
+                ("permission_sys", PermissionCheckType::PermissionSys),
+                ("permission_preset", PermissionCheckType::PermissionPreset),
+                ("permission_pinning", PermissionCheckType::PermissionPinning),
+                ("permission_purning", PermissionCheckType::PermissionPurning),
+            ],
+        )?;
+    }
+
+    let section = document.window;
+    let mut window = WindowConfig {
+        width: section.width,
+        height: section.height,
+        resizable: section.resizable,
+        decorations: section.decorations,
+        always_on_top: section.always_on_top,
+        ..Default::default()
+    };
+    if let Some(position) = section.position {
+        window.position = WindowPlacement::parse(&position).ok_or_else(|| ManifestError::InvalidValue {
+            field: "window.position".to_string(),
+            reason: format!("unknown value \"{}\", expected auto, center or \"x,y\"", position),
+        })?;
+    }
+    if let Some(window_type) = section.window_type {
+        window.window_type = choice("window.window_type", &window_type, &ManifestWindowType::NAMES)?;
+    }
+    if let Some(state) = section.state {
+        window.initial_state = choice("window.state", &state, &InitialWindowState::NAMES)?;
+    }
+
+    let environment = document.environment;
+    for key in environment.set.iter().map(|(k, _)| k).chain(&environment.unset) {
+        if !valid_env_name(key) {
+            return Err(ManifestError::InvalidValue {
+                field: "environment".to_string(),
+                reason: format!("Invalid environment variable name: {}", key),
+            });
+        }
+    }
+    let set: Vec<(String, String)> = environment
+        .set
+        .into_iter()
+        .filter(|(k, _)| !environment.unset.contains(k))
+        .collect();
+
+    Ok(WasmaManifest {
+        app,
+        resources,
+        permissions,
+        window,
+        environment: EnvironmentConfig { set, unset: environment.unset },
+        api_level,
+        warnings: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_structured_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml_path = dir.path().join("app.manifest.toml");
+        fs::write(&toml_path, r#"
+[app]
+name = "TomlApp"
+exec = "/usr/bin/toml-app"
+args = ["--title", "two words", "%F"]
+handles_mime = ["Text/Plain", "text/plain"]
+
+[resources]
+cpu_perception = 4
+cpu_core_serve = "dynamic"
+core_class = "efficiency"
+execution_mode = "hybrid"
+ram_using = { type = "DDR4", size_mb = 2048, cache = "resolved" }
+
+[environment]
+PATH = "$PATH:/opt/app/bin"
+GDK_BACKEND = "wayland"
+unset = ["LD_PRELOAD"]
+"#).unwrap();
+
+        let manifest = ManifestParser::new(toml_path.display().to_string()).load().unwrap();
+        assert_eq!(manifest.app.name, "TomlApp");
+        assert_eq!(manifest.app.args, vec!["--title", "two words", "%F"]);
+        assert_eq!(manifest.app.handles_mime, vec!["text/plain"]);
+        assert_eq!(manifest.api_level, MANIFEST_API_LEVEL);
+        assert_eq!(manifest.resources.cpu_perception, 4);
+        assert!(matches!(manifest.resources.cpu_core_serve, CpuCoreServe::Dynamic));
+        assert_eq!(manifest.resources.core_class, Some(CoreClass::Efficiency));
+        assert_eq!(manifest.resources.execution_mode, ExecutionMode::Hybrid);
+        assert_eq!(manifest.resources.ram_using.size, 2048);
+        assert!(matches!(manifest.resources.ram_using.cache_mode, CacheMode::Resolved));
+        // Untouched keys keep the legacy defaults
+        assert_eq!(manifest.resources.gpu_using.size, 1024);
+        assert_eq!(manifest.environment.set[0].0, "PATH");
+        assert_eq!(manifest.environment.set[1].0, "GDK_BACKEND");
+        assert_eq!(manifest.environment.unset, vec!["LD_PRELOAD"]);
+
+        let json_path = dir.path().join("app.json");
+        fs::write(&json_path, r#"{
+  "api_level": 2,
+  "app": { "name": "JsonApp", "handles_scheme": ["json-app"] },
+  "resources": { "cpu_core_serve": 3, "gpu_using": { "size_mb": 512 } },
+  "window": { "width": 640, "resizable": true, "position": "10,20", "window_type": "Dialog", "state": "maximized" }
+}"#).unwrap();
+        let manifest = ManifestParser::new(json_path.display().to_string()).load().unwrap();
+        assert_eq!(manifest.app.name, "JsonApp");
+        assert_eq!(manifest.app.handles_scheme, vec!["json-app"]);
+        assert!(matches!(manifest.resources.cpu_core_serve, CpuCoreServe::Static(3)));
+        assert_eq!(manifest.resources.gpu_using.size, 512);
+        assert_eq!(manifest.window.width, Some(640));
+        assert_eq!(manifest.window.position, WindowPlacement::At { x: 10, y: 20 });
+        assert_eq!(manifest.window.window_type, ManifestWindowType::Dialog);
+        assert_eq!(manifest.window.initial_state, InitialWindowState::Maximized);
+        assert_eq!(manifest.window.decorations, None);
+
+        // Mistakes are reported instead of silently defaulted
+        let parser = ManifestParser::new("inline.toml".to_string());
+        match parser.parse_toml("[app]\nname = \"x\"\n\n[resources]\ncpu_percepton = 2\n") {
+            Err(ManifestError::ParseError { line, reason }) => {
+                assert_eq!(line, 5);
+                assert!(reason.contains("cpu_percepton"));
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+        assert!(matches!(
+            parser.parse_toml("[resources]\ncpu_placement = \"diagonal\"\n"),
+            Err(ManifestError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            parser.parse_json(r#"{"api_level": 9}"#),
+            Err(ManifestError::UnsupportedApiLevel { found: 9, .. })
+        ));
+
+        // The shipped example stays valid
+        let example = parser.parse_toml(include_str!("wasma.manifest.toml")).unwrap();
+        assert_eq!(example.app.exec.as_deref(), Some("/usr/bin/example-app"));
+
+        // The legacy syntax is still used for other extensions
+        let legacy_path = dir.path().join("app.manifest");
+        fs::write(&legacy_path, "name = LegacyApp\n").unwrap();
+        let manifest = ManifestParser::new(legacy_path.display().to_string()).load().unwrap();
+        assert_eq!(manifest.app.name, "LegacyApp");
+    }
+}
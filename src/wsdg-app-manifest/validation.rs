@@ -0,0 +1,347 @@
+// WASMA Manifest Validation
+// Semantic checks on top of parsing: requested resources against the host,
+// value ranges and whether the permission source can be found.
+// Every diagnostic points at the file and line that set the value.
+
+use std::fmt;
+
+use wbackend::{plan::system_ram_mb, CpuTopology};
+
+use crate::manifest_parser::{
+    is_structured_path, CpuCoreServe, GpuAllocationType, ManifestError, ManifestParser, WasmaManifest,
+};
+use crate::source_parser::SourceParser;
+
+/// Requests above this much VRAM are flagged even when the host size is unknown
+pub const VRAM_SANITY_LIMIT_MB: u64 = 64 * 1024;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// Works, but probably not as intended.
+    Warning,
+    /// WASMA cannot honour the manifest as written.
+    Error,
+}
+
+/// One finding of `ManifestParser::validate`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct Diagnostic {
+    /// Warning or error.
+    pub severity: Severity,
+    /// Manifest key the finding is about.
+    pub field: String,
+    /// File that set the key (an included file for legacy manifests).
+    pub origin: Option<String>,
+    /// 1-based line in `origin`.
+    pub line: Option<usize>,
+    /// What is wrong.
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        match (&self.origin, self.line) {
+            (Some(origin), Some(line)) => write!(f, "{}:{}: ", origin, line)?,
+            (Some(origin), None) => write!(f, "{}: ", origin)?,
+            _ => {}
+        }
+        write!(f, "{}[{}]: {}", severity, self.field, self.message)
+    }
+}
+
+/// What the host can provide
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct HostLimits {
+    /// Total RAM; None when /proc/meminfo cannot be read.
+    pub ram_mb: Option<u64>,
+    /// Dedicated VRAM of the first GPU reporting it; None when unknown.
+    pub vram_mb: Option<u64>,
+    /// Logical CPUs this process may run on.
+    pub cpu_cores: usize,
+    /// Whether P-cores and E-cores are told apart.
+    pub hybrid: bool,
+}
+
+impl HostLimits {
+    /// Read the limits of the running system.
+    pub fn detect() -> Self {
+        let topology = CpuTopology::detect();
+        Self {
+            ram_mb: system_ram_mb(),
+            vram_mb: detect_vram_mb(),
+            cpu_cores: topology.cpus.len().max(1),
+            hybrid: topology.is_hybrid(),
+        }
+    }
+}
+
+/// amdgpu/i915 report dedicated VRAM in sysfs; others leave it unknown
+fn detect_vram_mb() -> Option<u64> {
+    let cards = std::fs::read_dir("/sys/class/drm").ok()?;
+    cards
+        .flatten()
+        .filter_map(|card| std::fs::read_to_string(card.path().join("device/mem_info_vram_total")).ok())
+        .filter_map(|bytes| bytes.trim().parse::<u64>().ok())
+        .map(|bytes| bytes / (1024 * 1024))
+        .max()
+}
+
+/// Result of validating one manifest
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct ValidationReport {
+    /// Manifest path.
+    pub manifest: String,
+    /// Limits the resources were checked against.
+    pub host: HostLimits,
+    /// Findings in manifest order where lines are known.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    /// No errors (warnings are allowed).
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Error diagnostics.
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Error)
+    }
+
+    /// Warning diagnostics.
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Warning)
+    }
+}
+
+/// Finds the line that set a key
+struct Locator {
+    path: String,
+    /// (key, origin, line) for legacy manifests after include resolution
+    resolved: Vec<(String, String, usize)>,
+    /// Raw text for TOML/JSON, searched by key name
+    content: String,
+}
+
+impl Locator {
+    fn new(parser: &ManifestParser, path: &str) -> Self {
+        let content = std::fs::read_to_string(path).unwrap_or_default();
+        let resolved = if is_structured_path(path) {
+            Vec::new()
+        } else {
+            parser
+                .resolve()
+                .map(|r| r.lines.into_iter().map(|l| (l.key, l.origin, l.line)).collect())
+                .unwrap_or_default()
+        };
+        Self { path: path.to_string(), resolved, content }
+    }
+
+    fn locate(&self, key: &str) -> (Option<String>, Option<usize>) {
+        if let Some((_, origin, line)) = self.resolved.iter().rev().find(|(k, _, _)| k == key) {
+            return (Some(origin.clone()), Some(*line));
+        }
+        // The structured schema names the permission keys differently
+        let names: &[&str] = match key {
+            "permission_check" => &["source", "check"],
+            _ => &[key],
+        };
+        let line = names.iter().find_map(|name| {
+            self.content.lines().position(|l| {
+                let l = l.trim_start().trim_start_matches('"');
+                l.strip_prefix(name).is_some_and(|rest| rest.trim_start().starts_with(['=', ':', '"']))
+            })
+        });
+        (Some(self.path.clone()), line.map(|l| l + 1))
+    }
+}
+
+impl ManifestParser {
+    /// Parse the manifest and check it against `host`.
+    /// A manifest that does not parse yields a single error diagnostic.
+    pub fn validate(&self, host: &HostLimits) -> ValidationReport {
+        let path = self.path().to_string();
+        let mut report = ValidationReport { manifest: path.clone(), host: host.clone(), diagnostics: Vec::new() };
+
+        let manifest = match self.load() {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                let (origin, line) = match &e {
+                    ManifestError::ParseError { line, .. } => (Some(path.clone()), Some(*line)),
+                    _ => (Some(path.clone()), None),
+                };
+                report.diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    field: "manifest".to_string(),
+                    origin,
+                    line,
+                    message: e.to_string(),
+                });
+                return report;
+            }
+        };
+
+        let locator = Locator::new(self, &path);
+        let mut push = |severity: Severity, field: &str, message: String| {
+            let (origin, line) = locator.locate(field);
+            report.diagnostics.push(Diagnostic { severity, field: field.to_string(), origin, line, message });
+        };
+
+        for warning in &manifest.warnings {
+            push(Severity::Warning, "api_level", warning.clone());
+        }
+        check_resources(&manifest, host, &mut push);
+        check_permissions(&manifest, &path, &mut push);
+
+        report.diagnostics.sort_by_key(|d| (d.origin.clone(), d.line));
+        report
+    }
+}
+
+fn check_resources(manifest: &WasmaManifest, host: &HostLimits, push: &mut impl FnMut(Severity, &str, String)) {
+    let resources = &manifest.resources;
+
+    if let Some(total) = host.ram_mb {
+        if resources.ram_using.size > total {
+            push(Severity::Error, "ram_using", format!("requests {} MB but the host has {} MB", resources.ram_using.size, total));
+        } else if resources.ram_using.size > total * 3 / 4 {
+            push(Severity::Warning, "ram_using", format!("requests {} MB, over 75% of the host's {} MB", resources.ram_using.size, total));
+        }
+    }
+    if resources.ram_used_bitwidth.size > resources.ram_using.size {
+        push(
+            Severity::Warning,
+            "ram_used_bitwidth",
+            format!("{} MB exceeds the {} MB set by ram_using", resources.ram_used_bitwidth.size, resources.ram_using.size),
+        );
+    }
+    if !(0.0..=100.0).contains(&resources.ram_used_bitwidth.cache_resourceing) {
+        push(Severity::Error, "ram_used_bitwidth", format!("cache share {}% is outside 0-100%", resources.ram_used_bitwidth.cache_resourceing));
+    }
+
+    if resources.cpu_perception == 0 {
+        push(Severity::Error, "cpu_perception", "must be at least 1".to_string());
+    } else if resources.cpu_perception as usize > host.cpu_cores {
+        push(Severity::Error, "cpu_perception", format!("{} exceeds the {} available CPU cores", resources.cpu_perception, host.cpu_cores));
+    }
+    if let CpuCoreServe::Static(cores) = resources.cpu_core_serve {
+        if cores as usize > host.cpu_cores {
+            push(Severity::Error, "cpu_core_serve", format!("{} cores requested, {} available", cores, host.cpu_cores));
+        }
+    }
+    if resources.cpu_affinity.resource_max > 100 {
+        push(Severity::Error, "cpu_affinity", format!("resource_max {}% is over 100%", resources.cpu_affinity.resource_max));
+    }
+    if resources.core_class.is_some() && !host.hybrid {
+        push(Severity::Warning, "core_class", "this CPU has no P-/E-core split; the request is ignored".to_string());
+    }
+
+    let gpu = &resources.gpu_perp;
+    if gpu.default_size == 0 {
+        push(Severity::Error, "gpu_perp", "VRAM size must be greater than 0".to_string());
+    } else if let Some(vram) = host.vram_mb.filter(|&vram| gpu.default_size > vram) {
+        push(Severity::Error, "gpu_perp", format!("requests {} MB VRAM but the GPU has {} MB", gpu.default_size, vram));
+    } else if gpu.default_size > VRAM_SANITY_LIMIT_MB {
+        push(Severity::Warning, "gpu_perp", format!("{} MB of VRAM is unusually large", gpu.default_size));
+    }
+    if let GpuAllocationType::Location(_) = gpu.allocation_type {
+        push(Severity::Warning, "gpu_perp", "\"location\" allocation may fail on some drivers; \"allocation\" is recommended".to_string());
+    }
+    if resources.gpu_using.size > gpu.default_size && gpu.default_size > 0 {
+        push(
+            Severity::Warning,
+            "gpu_using",
+            format!("{} MB exceeds the {} MB reserved by gpu_perp", resources.gpu_using.size, gpu.default_size),
+        );
+    }
+    if resources.gpu_using.resource_max > 100 {
+        push(Severity::Error, "gpu_using", format!("resource_max {}% is over 100%", resources.gpu_using.resource_max));
+    }
+}
+
+fn check_permissions(manifest: &WasmaManifest, path: &str, push: &mut impl FnMut(Severity, &str, String)) {
+    let parser = SourceParser::new(None);
+
+    // An embedded [source] section wins over any file
+    if !is_structured_path(path) {
+        let content = std::fs::read_to_string(path).unwrap_or_default();
+        match parser.load_embedded(&content) {
+            Ok(Some(_)) => return,
+            Ok(None) => {}
+            Err(e) => {
+                push(Severity::Error, "permission_check", format!("embedded [source] section: {}", e));
+                return;
+            }
+        }
+    }
+
+    let explicit = manifest.permissions.source_path.is_some();
+    let source = manifest.permissions.source_file();
+
+    if !source.exists() {
+        let severity = if explicit { Severity::Error } else { Severity::Warning };
+        push(severity, "permission_check", format!("permission source {} does not exist", source.display()));
+    } else if let Err(e) = parser.load(&source.display().to_string()) {
+        push(Severity::Error, "permission_check", format!("permission source {}: {}", source.display(), e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_manifest_validation() {
+        let host = HostLimits { ram_mb: Some(8192), vram_mb: Some(4096), cpu_cores: 4, hybrid: false };
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        fs::write(&source, "USE_WIFI_CONNECTION = 1\n").unwrap();
+
+        let path = dir.path().join("app.manifest");
+        fs::write(&path, "api_level = 2\nname = Heavy\ncpu_perception = 8\nram_using = \"DDR5\" \"16384MB\" \"*cache_resolved:swaponline\"\ngpu_perp = \"VRAM:allocation:size_bydefault = 2048\"\ncore_class = performance\n\n[source]\nUSE_WIFI_CONNECTION = 1\n").unwrap();
+        let report = ManifestParser::new(path.display().to_string()).validate(&host);
+        assert!(!report.is_valid());
+
+        let cpu = report.errors().find(|d| d.field == "cpu_perception").unwrap();
+        assert_eq!(cpu.line, Some(3));
+        let ram = report.errors().find(|d| d.field == "ram_using").unwrap();
+        assert_eq!(ram.line, Some(4));
+        assert!(ram.to_string().contains("app.manifest:4: error[ram_using]"));
+        assert!(report.warnings().any(|d| d.field == "core_class"));
+        assert!(!report.diagnostics.iter().any(|d| d.field == "gpu_perp" || d.field == "permission_check"));
+
+        // TOML manifest with an explicit, missing permission source
+        #[cfg(feature = "serialization")]
+        {
+            let toml_path = dir.path().join("app.toml");
+            fs::write(&toml_path, format!(
+                "[app]\nname = \"Light\"\n\n[resources]\ngpu_perp = {{ size_mb = 8192 }}\n\n[permissions]\nsource = \"{}\"\n",
+                dir.path().join("missing").display()
+            )).unwrap();
+            let report = ManifestParser::new(toml_path.display().to_string()).validate(&host);
+            let gpu = report.errors().find(|d| d.field == "gpu_perp").unwrap();
+            assert_eq!(gpu.line, Some(5));
+            assert!(report.errors().any(|d| d.field == "permission_check"));
+
+            fs::write(&toml_path, format!("[permissions]\nsource = \"{}\"\n", source.display())).unwrap();
+            assert!(ManifestParser::new(toml_path.display().to_string()).validate(&host).is_valid());
+        }
+
+        // Parse failures become a single line-anchored error
+        fs::write(&path, "name = Broken\ncpu_perception = many\n").unwrap();
+        let report = ManifestParser::new(path.display().to_string()).validate(&host);
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(report.diagnostics[0].line, Some(2));
+    }
+}
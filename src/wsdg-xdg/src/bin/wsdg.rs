@@ -0,0 +1,137 @@
+// WSDG CLI Tool
+// Command-line interface for WSDG services (starter logs)
+// Part of WASMA (Windows Assignment System Monitoring Architecture)
+
+use std::env;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+use wsdg_xdg::wsdg_starter_log::tail;
+use wsdg_xdg::{starter_log_path, WsdgEnv};
+
+const DEFAULT_TAIL_LINES: usize = 20;
+
+fn print_usage() {
+    eprintln!("Usage: wsdg [COMMAND] [OPTIONS]");
+    eprintln!();
+    eprintln!("WSDG service tools");
+    eprintln!();
+    eprintln!("Commands:");
+    eprintln!("  starter logs <NAME>     Show the captured stdout/stderr of a starter entry");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("  -h, --help              Show this help message");
+    eprintln!("  -v, --version           Show version information");
+    eprintln!("  -n, --lines <N>         Lines to show [default: {}]", DEFAULT_TAIL_LINES);
+    eprintln!("  -f, --follow            Keep printing lines as they are written");
+    eprintln!();
+    eprintln!("Examples:");
+    eprintln!("  wsdg starter logs firefox           # Last lines of firefox's starter log");
+    eprintln!("  wsdg starter logs syncd -n 100 -f   # Follow syncd's output");
+}
+
+fn print_version() {
+    println!("wsdg {}", wsdg_xdg::VERSION);
+    println!("{}", wsdg_xdg::LIBRARY_INFO);
+}
+
+/// Print what gets appended to `path`, starting over when the log rotates
+fn follow(path: &Path) -> ! {
+    let mut position = path.metadata().map(|m| m.len()).unwrap_or(0);
+    loop {
+        std::thread::sleep(Duration::from_millis(500));
+        let Ok(len) = path.metadata().map(|m| m.len()) else { continue };
+        if len < position {
+            position = 0;
+        }
+        if len == position {
+            continue;
+        }
+        if let Ok(mut file) = File::open(path) {
+            let mut new = Vec::new();
+            if file.seek(SeekFrom::Start(position)).is_ok() && file.read_to_end(&mut new).is_ok() {
+                print!("{}", String::from_utf8_lossy(&new));
+                position += new.len() as u64;
+            }
+        }
+    }
+}
+
+fn starter_logs(args: &[String]) {
+    let mut name = None;
+    let mut lines = DEFAULT_TAIL_LINES;
+    let mut follow_log = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-f" | "--follow" => follow_log = true,
+            "-n" | "--lines" => {
+                lines = match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(n) => n,
+                    None => {
+                        eprintln!("Error: {} needs a number", arg);
+                        process::exit(1);
+                    }
+                };
+            }
+            other if name.is_none() => name = Some(other.to_string()),
+            other => {
+                eprintln!("Error: Unexpected argument: {}", other);
+                process::exit(1);
+            }
+        }
+    }
+
+    let Some(name) = name else {
+        eprintln!("Error: Starter name required");
+        eprintln!("Usage: wsdg starter logs <NAME> [-n LINES] [-f]");
+        process::exit(1);
+    };
+
+    let path = starter_log_path(&WsdgEnv::new(), &name);
+    match tail(&path, lines) {
+        Ok(lines) => {
+            for line in lines {
+                println!("{}", line);
+            }
+        }
+        Err(e) => {
+            eprintln!("No log for {}: {}", name, e);
+            process::exit(1);
+        }
+    }
+
+    if follow_log {
+        follow(&path);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() < 2 {
+        print_usage();
+        process::exit(1);
+    }
+
+    match args[1].as_str() {
+        "-h" | "--help" => print_usage(),
+        "-v" | "--version" => print_version(),
+        "starter" => match args.get(2).map(String::as_str) {
+            Some("logs") => starter_logs(&args[3..]),
+            _ => {
+                eprintln!("Error: Unknown starter command");
+                eprintln!("Usage: wsdg starter logs <NAME> [-n LINES] [-f]");
+                process::exit(1);
+            }
+        },
+        other => {
+            eprintln!("Unknown command: {}", other);
+            print_usage();
+            process::exit(1);
+        }
+    }
+}
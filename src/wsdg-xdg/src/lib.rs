@@ -23,10 +23,17 @@
 //! - `wsdg_open`: Application launcher
 //! - `wsdg_ghx_open`: URI and protocol handler
 //! - `wsdg_mime_array`: MIME type detection and registry
+//! - `wsdg_mime_archive`: zip/tar member inspection for MIME detection (feature `archive-inspection`)
+//! - `wsdg_handlers`: Manifest-declared MIME associations and scheme handlers
 //! - `wsdg_byico_icoctl`: Icon discovery system
+//! - `wsdg_icon_extract`: Icons embedded in ELF/PE executables and AppImages
 //! - `wsdg_autocompile`: Auto-compilation for translation layer
 //! - `wsdg_settings`: Settings management
-//! - `wsdg_starter`: Application startup configuration
+//! - `wsdg_starter`: Application startup configuration and crash-loop supervision
+//! - `wsdg_starter_log`: Rotated stdout/stderr logs of starter-launched processes
+//! - `wsdg_atomic`: Crash-safe file writes (temp file + rename, with a backup)
+//! - `wsdg_fonts`: Installed font families and per-script fallback chains
+//! - `sandbox`: seccomp/landlock profiles for each permission `sandbox_level`
 //!
 //! # Quick Start
 //!
@@ -76,10 +83,19 @@ pub mod wsdg_env;
 pub mod wsdg_open;
 pub mod wsdg_ghx_open;
 pub mod wsdg_mime_array;
+#[cfg(feature = "archive-inspection")]
+pub mod wsdg_mime_archive;
+pub mod wsdg_handlers;
 pub mod wsdg_byico_icoctl;
+pub mod wsdg_icon_extract;
 pub mod wsdg_autocompile;
 pub mod wsdg_settings;
 pub mod wsdg_starter;
+pub mod wsdg_starter_log;
+pub mod sandbox;
+pub mod wsdg_kiosk;
+pub mod wsdg_atomic;
+pub mod wsdg_fonts;
 
 // Re-exports for convenience
 pub use xdg_wsdg_translate::{
@@ -114,6 +130,27 @@ pub use wsdg_mime_array::{
     MimeType,
     MimeCategory,
     MimeError,
+    MimeDetector,
+    DetectionInput,
+    MimeConflict,
+    BUILTIN_DETECTOR_PRIORITY,
+    register_mime_detector,
+    unregister_mime_detector,
+};
+
+#[cfg(feature = "archive-inspection")]
+pub use wsdg_mime_archive::{
+    ArchiveKind,
+    ArchiveListing,
+};
+
+pub use wsdg_handlers::{
+    HandlerRegistry,
+    HandlerKind,
+    HandlerReport,
+    HandlerError,
+    install_manifest,
+    uninstall_manifest,
 };
 
 pub use wsdg_byico_icoctl::{
@@ -125,6 +162,13 @@ pub use wsdg_byico_icoctl::{
     IconError,
 };
 
+pub use wsdg_icon_extract::{
+    ExtractedIcon,
+    IconSource,
+    extract_icon,
+    extraction_cache_dir,
+};
+
 pub use wsdg_autocompile::{
     WsdgAutoCompiler,
     CompilationBuffer,
@@ -140,13 +184,61 @@ pub use wsdg_settings::{
     FontSettings,
     IconSettings,
     WindowSettings,
+    TelemetrySettings,
+    WebhookSettings,
+    PlaceholderSettings,
+    SettingsDelta,
+    SettingChange,
+    SettingConflict,
+    MergeReport,
     SettingsError,
+    lock_settings_file,
 };
 
 pub use wsdg_starter::{
     WsdgStarter,
     StarterConfig,
     StarterError,
+    RestartPolicy,
+    EntryStatus,
+    StarterEntry,
+    StarterEvent,
+};
+
+pub use wsdg_starter_log::{
+    RotatingLog,
+    starter_log_dir,
+    starter_log_path,
+    STARTER_LOG_MAX_BYTES,
+    STARTER_LOG_KEEP,
+};
+
+pub use wsdg_kiosk::{
+    KioskPolicy,
+    PolicyDenied,
+    KIOSK_ENV_VAR,
+    KIOSK_CONFIG,
+};
+
+pub use wsdg_atomic::{
+    write_atomic,
+    backup_path,
+    restore_backup,
+};
+
+pub use wsdg_fonts::{
+    FontCatalog,
+    FontScript,
+    MissingFont,
+    GENERIC_FAMILIES,
+};
+
+pub use sandbox::{
+    SandboxProfile,
+    SyscallGroup,
+    SocketPolicy,
+    FsScope,
+    MAX_SANDBOX_LEVEL,
 };
 
 /// WSDG-XDG version
@@ -0,0 +1,661 @@
+// WSDG Sandbox - Isolation profiles per sandbox_level
+// A permission source's USE_SANDBOX_LEVEL (0–10) picks one of the profiles
+// below. A profile allows groups of syscalls through a seccomp filter and
+// scopes the filesystem through landlock; WsdgOpen and WsdgStarter install it
+// on the child right before exec, so the level is real isolation and not a label.
+// Part of WASMA (Windows Assignment System Monitoring Architecture)
+//
+// level  syscalls allowed                        sockets          filesystem
+//   0    any (no filter)                          any              unrestricted
+//   1    any (no_new_privs only)                  any              unrestricted
+//   2    base, debug, mount, keyring, bpf, ns     any              unrestricted
+//   3    base, mount, keyring, bpf, ns            any              unrestricted
+//   4    base, bpf, ns                            any              unrestricted
+//   5    base                                     any              home
+//   6    same as 5                                any              app data
+//   7    same as 5                                no raw/packet    app data
+//   8    same as 5                                no inet          app data
+//   9    same as 5                                no inet          read-only
+//  10    same as 5                                unix only        read-only
+//
+// Filtered levels are allowlists: any syscall outside the allowed groups fails
+// with EPERM. That covers module loading, kexec, reboot and the system clock
+// from level 2 on. io_uring is refused at every filtered level, since its
+// submissions (IORING_OP_SOCKET, ...) never pass through the checks below.
+
+use std::ffi::CString;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::wsdg_env::WsdgEnv;
+
+/// Highest sandbox_level; larger values are clamped
+pub const MAX_SANDBOX_LEVEL: u8 = 10;
+
+// Landlock ABI v1 (Linux 5.13); later ABIs add rights, see landlock_handled_access
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1;
+const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+const LANDLOCK_ACCESS_FS_EXECUTE: u64 = 1 << 0;
+const LANDLOCK_ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const LANDLOCK_ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const LANDLOCK_ACCESS_FS_READ_DIR: u64 = 1 << 3;
+/// Every ABI v1 right (READ_DIR ... MAKE_SYM)
+const LANDLOCK_ACCESS_FS_V1: u64 = (1 << 13) - 1;
+/// ABI v2 (Linux 5.19): linking or renaming across directories
+const LANDLOCK_ACCESS_FS_REFER: u64 = 1 << 13;
+/// ABI v3 (Linux 6.2): truncate(2), ftruncate(2), O_TRUNC
+const LANDLOCK_ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+/// ABI v5 (Linux 6.10): ioctl(2) on device files
+const LANDLOCK_ACCESS_FS_IOCTL_DEV: u64 = 1 << 15;
+/// Rights that apply to a file (not a directory) rule
+const LANDLOCK_ACCESS_FS_FILE: u64 = LANDLOCK_ACCESS_FS_EXECUTE | LANDLOCK_ACCESS_FS_WRITE_FILE
+    | LANDLOCK_ACCESS_FS_READ_FILE | LANDLOCK_ACCESS_FS_TRUNCATE | LANDLOCK_ACCESS_FS_IOCTL_DEV;
+const LANDLOCK_ACCESS_FS_READ: u64 =
+    LANDLOCK_ACCESS_FS_EXECUTE | LANDLOCK_ACCESS_FS_READ_FILE | LANDLOCK_ACCESS_FS_READ_DIR;
+
+#[repr(C)]
+struct LandlockRulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct LandlockPathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+// seccomp_data layout: nr @0, arch @4, args[0] @16
+const SECCOMP_DATA_NR: u32 = 0;
+const SECCOMP_DATA_ARCH: u32 = 4;
+const SECCOMP_DATA_ARG0: u32 = 16;
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH_NATIVE: Option<u32> = Some(0xC000_003E);
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH_NATIVE: Option<u32> = Some(0xC000_00B7);
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const AUDIT_ARCH_NATIVE: Option<u32> = None;
+/// x32 syscalls report AUDIT_ARCH_X86_64 with this bit set in the number
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+/// clone() flags that create namespaces
+const CLONE_NEW_FLAGS: u32 = (libc::CLONE_NEWNS | libc::CLONE_NEWCGROUP | libc::CLONE_NEWUTS | libc::CLONE_NEWIPC
+    | libc::CLONE_NEWUSER | libc::CLONE_NEWPID | libc::CLONE_NEWNET) as u32;
+
+/// Readable (and executable) by every sandboxed process
+pub const SYSTEM_READ_PATHS: &[&str] = &[
+    "/usr", "/lib", "/lib32", "/lib64", "/bin", "/sbin", "/etc", "/opt", "/nix",
+    "/proc", "/sys", "/run", "/var/lib",
+];
+/// Writable by every process whose filesystem is scoped
+pub const SYSTEM_WRITE_PATHS: &[&str] = &["/tmp", "/dev", "/dev/shm"];
+
+// ============================================================================
+// PROFILES
+// ============================================================================
+
+/// Syscalls allowed together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyscallGroup {
+    /// Files, memory, processes, signals, time, polling, sockets and local IPC:
+    /// what ordinary programs need. clone() is in here; its CLONE_NEW* flags
+    /// are only accepted together with Namespace
+    Base,
+    /// ptrace and cross-process memory and fd access
+    Debug,
+    /// Mounting and changing the root
+    Mount,
+    /// Kernel keyrings
+    Keyring,
+    /// eBPF and userfaultfd
+    Bpf,
+    /// Creating or joining namespaces: unshare, setns, clone3 and clone with
+    /// CLONE_NEW* flags (without it clone3 is refused with ENOSYS, its flags
+    /// are out of seccomp's reach)
+    Namespace,
+}
+
+/// Syscall numbers both x86_64 and aarch64 have
+const BASE_SYSCALLS: &[libc::c_long] = &[
+    // files and directories
+    libc::SYS_read, libc::SYS_write, libc::SYS_readv, libc::SYS_writev, libc::SYS_pread64, libc::SYS_pwrite64,
+    libc::SYS_preadv, libc::SYS_pwritev, libc::SYS_preadv2, libc::SYS_pwritev2, libc::SYS_openat, libc::SYS_openat2,
+    libc::SYS_close, libc::SYS_close_range, libc::SYS_lseek, libc::SYS_fstat, libc::SYS_newfstatat, libc::SYS_statx,
+    libc::SYS_statfs, libc::SYS_fstatfs, libc::SYS_truncate, libc::SYS_ftruncate, libc::SYS_fallocate,
+    libc::SYS_fsync, libc::SYS_fdatasync, libc::SYS_sync, libc::SYS_syncfs, libc::SYS_getdents64,
+    libc::SYS_getcwd, libc::SYS_chdir, libc::SYS_fchdir, libc::SYS_mkdirat, libc::SYS_mknodat, libc::SYS_unlinkat,
+    libc::SYS_renameat2, libc::SYS_linkat, libc::SYS_symlinkat, libc::SYS_readlinkat, libc::SYS_fchmod,
+    libc::SYS_fchmodat, libc::SYS_fchown, libc::SYS_fchownat, libc::SYS_faccessat, libc::SYS_faccessat2,
+    libc::SYS_utimensat, libc::SYS_umask, libc::SYS_flock, libc::SYS_fcntl, libc::SYS_dup, libc::SYS_dup3,
+    libc::SYS_pipe2, libc::SYS_ioctl, libc::SYS_splice, libc::SYS_tee, libc::SYS_vmsplice,
+    libc::SYS_copy_file_range, libc::SYS_sendfile, libc::SYS_readahead, libc::SYS_fadvise64,
+    libc::SYS_getxattr, libc::SYS_lgetxattr, libc::SYS_fgetxattr, libc::SYS_listxattr, libc::SYS_llistxattr,
+    libc::SYS_flistxattr, libc::SYS_setxattr, libc::SYS_lsetxattr, libc::SYS_fsetxattr, libc::SYS_removexattr,
+    libc::SYS_lremovexattr, libc::SYS_fremovexattr, libc::SYS_inotify_init1, libc::SYS_inotify_add_watch,
+    libc::SYS_inotify_rm_watch,
+    // memory
+    libc::SYS_mmap, libc::SYS_munmap, libc::SYS_mprotect, libc::SYS_mremap, libc::SYS_madvise, libc::SYS_msync,
+    libc::SYS_mincore, libc::SYS_mlock, libc::SYS_munlock, libc::SYS_mlock2, libc::SYS_mlockall,
+    libc::SYS_munlockall, libc::SYS_brk, libc::SYS_membarrier, libc::SYS_memfd_create, libc::SYS_mseal,
+    libc::SYS_get_mempolicy, libc::SYS_set_mempolicy, libc::SYS_mbind, libc::SYS_process_madvise,
+    // signals
+    libc::SYS_rt_sigaction, libc::SYS_rt_sigprocmask, libc::SYS_rt_sigreturn, libc::SYS_rt_sigsuspend,
+    libc::SYS_rt_sigpending, libc::SYS_rt_sigtimedwait, libc::SYS_rt_sigqueueinfo, libc::SYS_rt_tgsigqueueinfo,
+    libc::SYS_sigaltstack, libc::SYS_kill, libc::SYS_tkill, libc::SYS_tgkill, libc::SYS_pidfd_open,
+    libc::SYS_pidfd_send_signal, libc::SYS_signalfd4, libc::SYS_restart_syscall,
+    // processes, credentials and scheduling
+    libc::SYS_exit, libc::SYS_exit_group, libc::SYS_wait4, libc::SYS_waitid, libc::SYS_clone, libc::SYS_execve,
+    libc::SYS_execveat, libc::SYS_getpid, libc::SYS_getppid, libc::SYS_gettid, libc::SYS_getuid,
+    libc::SYS_geteuid, libc::SYS_getgid, libc::SYS_getegid, libc::SYS_getresuid, libc::SYS_getresgid,
+    libc::SYS_getgroups, libc::SYS_setuid, libc::SYS_setgid, libc::SYS_setreuid, libc::SYS_setregid,
+    libc::SYS_setresuid, libc::SYS_setresgid, libc::SYS_setgroups, libc::SYS_setfsuid, libc::SYS_setfsgid,
+    libc::SYS_getpgid, libc::SYS_setpgid, libc::SYS_getsid, libc::SYS_setsid, libc::SYS_getpriority,
+    libc::SYS_setpriority, libc::SYS_prlimit64, libc::SYS_getrusage, libc::SYS_times, libc::SYS_uname,
+    libc::SYS_sysinfo, libc::SYS_prctl, libc::SYS_capget, libc::SYS_capset, libc::SYS_sched_yield,
+    libc::SYS_sched_getaffinity, libc::SYS_sched_setaffinity, libc::SYS_sched_getparam,
+    libc::SYS_sched_setparam, libc::SYS_sched_getscheduler, libc::SYS_sched_setscheduler,
+    libc::SYS_sched_get_priority_max, libc::SYS_sched_get_priority_min, libc::SYS_sched_rr_get_interval,
+    libc::SYS_sched_getattr, libc::SYS_sched_setattr, libc::SYS_ioprio_get, libc::SYS_ioprio_set,
+    libc::SYS_getcpu, libc::SYS_set_tid_address, libc::SYS_set_robust_list, libc::SYS_get_robust_list,
+    libc::SYS_rseq, libc::SYS_futex, libc::SYS_futex_waitv, libc::SYS_getrandom,
+    // further restricting itself is always fine
+    libc::SYS_seccomp, libc::SYS_landlock_create_ruleset, libc::SYS_landlock_add_rule,
+    libc::SYS_landlock_restrict_self,
+    // time
+    libc::SYS_nanosleep, libc::SYS_clock_nanosleep, libc::SYS_clock_gettime, libc::SYS_clock_getres,
+    libc::SYS_gettimeofday, libc::SYS_getitimer, libc::SYS_setitimer, libc::SYS_timer_create,
+    libc::SYS_timer_settime, libc::SYS_timer_gettime, libc::SYS_timer_getoverrun, libc::SYS_timer_delete,
+    libc::SYS_timerfd_create, libc::SYS_timerfd_settime, libc::SYS_timerfd_gettime,
+    // polling
+    libc::SYS_eventfd2, libc::SYS_epoll_create1, libc::SYS_epoll_ctl, libc::SYS_epoll_pwait,
+    libc::SYS_epoll_pwait2, libc::SYS_ppoll, libc::SYS_pselect6,
+    // sockets (socket() itself is checked against the SocketPolicy first)
+    libc::SYS_socket, libc::SYS_socketpair, libc::SYS_bind, libc::SYS_listen, libc::SYS_accept,
+    libc::SYS_accept4, libc::SYS_connect, libc::SYS_getsockname, libc::SYS_getpeername, libc::SYS_sendto,
+    libc::SYS_recvfrom, libc::SYS_sendmsg, libc::SYS_recvmsg, libc::SYS_sendmmsg, libc::SYS_recvmmsg,
+    libc::SYS_setsockopt, libc::SYS_getsockopt, libc::SYS_shutdown,
+    // System V and POSIX IPC
+    libc::SYS_shmget, libc::SYS_shmat, libc::SYS_shmdt, libc::SYS_shmctl, libc::SYS_semget, libc::SYS_semop,
+    libc::SYS_semctl, libc::SYS_semtimedop, libc::SYS_msgget, libc::SYS_msgsnd, libc::SYS_msgrcv,
+    libc::SYS_msgctl, libc::SYS_mq_open, libc::SYS_mq_unlink, libc::SYS_mq_timedsend,
+    libc::SYS_mq_timedreceive, libc::SYS_mq_notify, libc::SYS_mq_getsetattr,
+];
+
+/// x86_64-only numbers: the pre-*at calls arm64 never had, and a few others
+#[cfg(target_arch = "x86_64")]
+const BASE_SYSCALLS_ARCH: &[libc::c_long] = &[
+    libc::SYS_open, libc::SYS_creat, libc::SYS_stat, libc::SYS_lstat, libc::SYS_access, libc::SYS_pipe,
+    libc::SYS_dup2, libc::SYS_poll, libc::SYS_select, libc::SYS_epoll_create, libc::SYS_epoll_wait,
+    libc::SYS_eventfd, libc::SYS_signalfd, libc::SYS_inotify_init, libc::SYS_fork, libc::SYS_vfork,
+    libc::SYS_getdents, libc::SYS_mkdir, libc::SYS_rmdir, libc::SYS_unlink, libc::SYS_rename,
+    libc::SYS_renameat, libc::SYS_link, libc::SYS_symlink, libc::SYS_readlink, libc::SYS_chmod,
+    libc::SYS_chown, libc::SYS_lchown, libc::SYS_utime, libc::SYS_utimes, libc::SYS_futimesat, libc::SYS_time,
+    libc::SYS_alarm, libc::SYS_pause, libc::SYS_getpgrp, libc::SYS_arch_prctl, libc::SYS_mknod,
+    libc::SYS_fchmodat2, libc::SYS_sync_file_range, libc::SYS_getrlimit, libc::SYS_setrlimit,
+];
+#[cfg(not(target_arch = "x86_64"))]
+const BASE_SYSCALLS_ARCH: &[libc::c_long] = &[];
+
+/// Never allowed by a filter: io_uring submissions bypass every seccomp check
+const IO_URING_SYSCALLS: &[libc::c_long] =
+    &[libc::SYS_io_uring_setup, libc::SYS_io_uring_enter, libc::SYS_io_uring_register];
+
+impl SyscallGroup {
+    pub fn syscalls(&self) -> Vec<libc::c_long> {
+        match self {
+            SyscallGroup::Base => [BASE_SYSCALLS, BASE_SYSCALLS_ARCH].concat(),
+            SyscallGroup::Debug => vec![
+                libc::SYS_ptrace, libc::SYS_process_vm_readv, libc::SYS_process_vm_writev,
+                libc::SYS_perf_event_open, libc::SYS_pidfd_getfd, libc::SYS_kcmp,
+            ],
+            SyscallGroup::Mount => vec![
+                libc::SYS_mount, libc::SYS_umount2, libc::SYS_pivot_root, libc::SYS_chroot,
+                libc::SYS_fsopen, libc::SYS_fsmount, libc::SYS_move_mount, libc::SYS_open_tree,
+            ],
+            SyscallGroup::Keyring => vec![libc::SYS_add_key, libc::SYS_request_key, libc::SYS_keyctl],
+            SyscallGroup::Bpf => vec![libc::SYS_bpf, libc::SYS_userfaultfd],
+            SyscallGroup::Namespace => vec![libc::SYS_unshare, libc::SYS_setns, libc::SYS_clone3],
+        }
+    }
+}
+
+/// Socket families a profile still lets the process create
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketPolicy {
+    Any,
+    /// No AF_PACKET (raw link-layer access)
+    NoRawPackets,
+    /// No AF_INET/AF_INET6/AF_PACKET: offline
+    NoInet,
+    /// AF_UNIX only (display server, D-Bus)
+    UnixOnly,
+}
+
+/// Where a sandboxed process may write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsScope {
+    Unrestricted,
+    /// Read the system, read-write the home directory
+    Home,
+    /// Read the system, read-write only the app's config/data/cache directories
+    AppData,
+    /// Read the system, write only the app's cache directory
+    ReadOnly,
+}
+
+/// What a sandbox_level translates to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxProfile {
+    pub level: u8,
+    pub no_new_privs: bool,
+    /// Syscall allowlist; None installs no allowlist (everything not refused
+    /// by the socket policy is allowed)
+    pub allowed: Option<Vec<SyscallGroup>>,
+    pub sockets: SocketPolicy,
+    pub filesystem: FsScope,
+}
+
+impl SandboxProfile {
+    /// Profile of `level` (see the table at the top of this file)
+    pub fn for_level(level: u8) -> Self {
+        use SyscallGroup::*;
+
+        let level = level.min(MAX_SANDBOX_LEVEL);
+        let allowed = match level {
+            0 | 1 => None,
+            2 => Some(vec![Base, Debug, Mount, Keyring, Bpf, Namespace]),
+            3 => Some(vec![Base, Mount, Keyring, Bpf, Namespace]),
+            4 => Some(vec![Base, Bpf, Namespace]),
+            _ => Some(vec![Base]),
+        };
+        let sockets = match level {
+            0..=6 => SocketPolicy::Any,
+            7 => SocketPolicy::NoRawPackets,
+            8 | 9 => SocketPolicy::NoInet,
+            _ => SocketPolicy::UnixOnly,
+        };
+        let filesystem = match level {
+            0..=4 => FsScope::Unrestricted,
+            5 => FsScope::Home,
+            6..=8 => FsScope::AppData,
+            _ => FsScope::ReadOnly,
+        };
+        Self { level, no_new_privs: level >= 1, allowed, sockets, filesystem }
+    }
+
+    /// Nothing to install
+    pub fn is_unconfined(&self) -> bool {
+        !self.no_new_privs && self.allowed.is_none() && self.sockets == SocketPolicy::Any
+            && self.filesystem == FsScope::Unrestricted
+    }
+
+    /// (readable, writable) landlock paths for `app_name`; `None` when unrestricted
+    pub fn filesystem_paths(&self, env: &WsdgEnv, app_name: &str) -> Option<(Vec<PathBuf>, Vec<PathBuf>)> {
+        let readable = SYSTEM_READ_PATHS.iter().map(PathBuf::from).collect();
+        let mut writable: Vec<PathBuf> = vec![PathBuf::from("/dev")];
+        if let Ok(runtime) = env.runtime_dir() {
+            writable.push(runtime);
+        }
+
+        let app_dir = |dir: Result<PathBuf, _>| dir.ok().map(|d: PathBuf| d.join(app_name));
+        match self.filesystem {
+            FsScope::Unrestricted => return None,
+            FsScope::Home => {
+                writable.extend(SYSTEM_WRITE_PATHS.iter().map(PathBuf::from));
+                writable.extend(env.home_dir().ok());
+            }
+            FsScope::AppData => {
+                writable.extend(SYSTEM_WRITE_PATHS.iter().map(PathBuf::from));
+                writable.extend(app_dir(env.config_dir()));
+                writable.extend(app_dir(env.share_dir()));
+                writable.extend(app_dir(env.cache_dir()));
+            }
+            FsScope::ReadOnly => writable.extend(app_dir(env.cache_dir())),
+        }
+        Some((readable, writable))
+    }
+
+    /// seccomp filter of the profile; empty when it has neither an allowlist
+    /// nor a socket policy
+    pub fn seccomp_filter(&self) -> Vec<libc::sock_filter> {
+        if self.allowed.is_none() && self.sockets == SocketPolicy::Any {
+            return Vec::new();
+        }
+        let Some(arch) = AUDIT_ARCH_NATIVE else { return Vec::new() };
+
+        let deny = libc::SECCOMP_RET_ERRNO | libc::EPERM as u32;
+        let allow = libc::SECCOMP_RET_ALLOW;
+        let mut program = vec![
+            bpf_stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, SECCOMP_DATA_ARCH),
+            bpf_jump(libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K, arch, 1, 0),
+            // Foreign-ABI syscalls would bypass the numbers below
+            bpf_stmt(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_ERRNO | libc::ENOSYS as u32),
+            bpf_stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, SECCOMP_DATA_NR),
+        ];
+        if cfg!(target_arch = "x86_64") {
+            // x32 shares the x86_64 audit arch; its numbers would miss every check below
+            program.push(bpf_jump(libc::BPF_JMP | libc::BPF_JGE | libc::BPF_K, X32_SYSCALL_BIT, 0, 1));
+            program.push(bpf_stmt(libc::BPF_RET | libc::BPF_K, deny));
+        }
+
+        for &nr in IO_URING_SYSCALLS {
+            program.push(bpf_jump(libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K, nr as u32, 0, 1));
+            program.push(bpf_stmt(libc::BPF_RET | libc::BPF_K, deny));
+        }
+
+        let namespaces = self.allowed.as_ref().map_or(true, |groups| groups.contains(&SyscallGroup::Namespace));
+        if !namespaces {
+            // clone3 passes its flags in memory seccomp cannot read; ENOSYS makes libc fall back to clone
+            program.push(bpf_jump(libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K, libc::SYS_clone3 as u32, 0, 1));
+            program.push(bpf_stmt(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_ERRNO | libc::ENOSYS as u32));
+            // clone(): deny CLONE_NEW* in the flags, reload the syscall number
+            program.push(bpf_jump(libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K, libc::SYS_clone as u32, 0, 4));
+            program.push(bpf_stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, SECCOMP_DATA_ARG0));
+            program.push(bpf_jump(libc::BPF_JMP | libc::BPF_JSET | libc::BPF_K, CLONE_NEW_FLAGS, 0, 1));
+            program.push(bpf_stmt(libc::BPF_RET | libc::BPF_K, deny));
+            program.push(bpf_stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, SECCOMP_DATA_NR));
+        }
+
+        let no_family = libc::SECCOMP_RET_ERRNO | libc::EAFNOSUPPORT as u32;
+        let family_check = match self.sockets {
+            SocketPolicy::Any => Vec::new(),
+            SocketPolicy::NoRawPackets => deny_families(&[libc::AF_PACKET], no_family),
+            SocketPolicy::NoInet => deny_families(&[libc::AF_INET, libc::AF_INET6, libc::AF_PACKET], no_family),
+            SocketPolicy::UnixOnly => vec![
+                bpf_jump(libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K, libc::AF_UNIX as u32, 1, 0),
+                bpf_stmt(libc::BPF_RET | libc::BPF_K, no_family),
+            ],
+        };
+        if !family_check.is_empty() {
+            // socket(): load the family, check it, reload the syscall number
+            let skip = family_check.len() as u8 + 2;
+            program.push(bpf_jump(libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K, libc::SYS_socket as u32, 0, skip));
+            program.push(bpf_stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, SECCOMP_DATA_ARG0));
+            program.extend(family_check);
+            program.push(bpf_stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, SECCOMP_DATA_NR));
+        }
+
+        let Some(groups) = &self.allowed else {
+            program.push(bpf_stmt(libc::BPF_RET | libc::BPF_K, allow));
+            return program;
+        };
+        for group in groups {
+            for nr in group.syscalls() {
+                program.push(bpf_jump(libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K, nr as u32, 0, 1));
+                program.push(bpf_stmt(libc::BPF_RET | libc::BPF_K, allow));
+            }
+        }
+        program.push(bpf_stmt(libc::BPF_RET | libc::BPF_K, deny));
+        program
+    }
+
+    /// Install the profile on `command` for the process of `app_name`. Steps
+    /// that fail in the child make the spawn fail; a kernel without landlock
+    /// only loses the filesystem scope, with a warning.
+    pub fn apply(&self, command: &mut Command, env: &WsdgEnv, app_name: &str) {
+        if self.is_unconfined() {
+            return;
+        }
+
+        let ruleset = self.filesystem_paths(env, app_name).and_then(|(readable, writable)| {
+            landlock_ruleset(&readable, &writable)
+                .map_err(|e| tracing::warn!(app = %app_name, error = %e, "filesystem scope not applied (landlock)"))
+                .ok()
+        });
+        let filter = self.seccomp_filter();
+        let no_new_privs = self.no_new_privs;
+
+        unsafe {
+            command.pre_exec(move || {
+                if no_new_privs && libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if let Some(ruleset) = &ruleset {
+                    landlock_restrict_self(ruleset.as_raw_fd()).map_err(io::Error::from_raw_os_error)?;
+                }
+                if !filter.is_empty() {
+                    let program = libc::sock_fprog { len: filter.len() as u16, filter: filter.as_ptr() as *mut _ };
+                    if libc::prctl(libc::PR_SET_SECCOMP, libc::SECCOMP_MODE_FILTER, &program as *const libc::sock_fprog) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+}
+
+fn bpf_stmt(code: u32, k: u32) -> libc::sock_filter {
+    libc::sock_filter { code: code as u16, jt: 0, jf: 0, k }
+}
+
+fn bpf_jump(code: u32, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+    libc::sock_filter { code: code as u16, jt, jf, k }
+}
+
+fn deny_families(families: &[libc::c_int], ret: u32) -> Vec<libc::sock_filter> {
+    families
+        .iter()
+        .flat_map(|&family| {
+            [
+                bpf_jump(libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K, family as u32, 0, 1),
+                bpf_stmt(libc::BPF_RET | libc::BPF_K, ret),
+            ]
+        })
+        .collect()
+}
+
+// ============================================================================
+// LANDLOCK
+// ============================================================================
+
+/// Filesystem rights landlock ABI `abi` can restrict. Everything the kernel
+/// knows is handled, so rights newer than v1 (refer, truncate, device ioctl)
+/// are denied outside the granted paths too
+pub fn landlock_handled_access(abi: i64) -> u64 {
+    let mut handled = LANDLOCK_ACCESS_FS_V1;
+    if abi >= 2 {
+        handled |= LANDLOCK_ACCESS_FS_REFER;
+    }
+    if abi >= 3 {
+        handled |= LANDLOCK_ACCESS_FS_TRUNCATE;
+    }
+    if abi >= 5 {
+        handled |= LANDLOCK_ACCESS_FS_IOCTL_DEV;
+    }
+    handled
+}
+
+/// Landlock ruleset granting read+exec on `readable` and full access on
+/// `writable`. Missing paths grant nothing. Build it before forking and
+/// restrict the child with `landlock_restrict_self`.
+pub fn landlock_ruleset(readable: &[PathBuf], writable: &[PathBuf]) -> io::Result<OwnedFd> {
+    let abi = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<LandlockRulesetAttr>(),
+            0usize,
+            LANDLOCK_CREATE_RULESET_VERSION,
+        )
+    };
+    if abi < 1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let handled = landlock_handled_access(abi as i64);
+    let attr = LandlockRulesetAttr { handled_access_fs: handled };
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            &attr as *const LandlockRulesetAttr,
+            std::mem::size_of::<LandlockRulesetAttr>(),
+            0u32,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let ruleset = unsafe { OwnedFd::from_raw_fd(fd as RawFd) };
+
+    let rules = readable
+        .iter()
+        .map(|p| (p, LANDLOCK_ACCESS_FS_READ))
+        .chain(writable.iter().map(|p| (p, handled)));
+    for (path, access) in rules {
+        let Some(parent) = open_path(path) else { continue };
+        let access = if path.is_dir() { access } else { access & LANDLOCK_ACCESS_FS_FILE & handled };
+
+        let rule = LandlockPathBeneathAttr { allowed_access: access, parent_fd: parent.as_raw_fd() };
+        let added = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_add_rule,
+                ruleset.as_raw_fd(),
+                LANDLOCK_RULE_PATH_BENEATH,
+                &rule as *const LandlockPathBeneathAttr,
+                0u32,
+            )
+        };
+        if added != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(ruleset)
+}
+
+fn open_path(path: &Path) -> Option<OwnedFd> {
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let fd = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+    (fd >= 0).then(|| unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// Enforce a ruleset on the calling process; raw syscalls only, so it is
+/// safe between fork and exec. Returns the errno on failure.
+pub fn landlock_restrict_self(ruleset: RawFd) -> Result<(), i32> {
+    unsafe {
+        if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0
+            || libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0u32) != 0
+        {
+            return Err(*libc::__errno_location());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wsdg_env::WsdgEnvBuilder;
+
+    #[test]
+    fn test_sandbox_profiles() {
+        assert!(SandboxProfile::for_level(0).is_unconfined());
+        assert_eq!(SandboxProfile::for_level(200), SandboxProfile::for_level(10));
+        let strict = SandboxProfile::for_level(10);
+        assert_eq!(strict.sockets, SocketPolicy::UnixOnly);
+        assert_eq!(strict.filesystem, FsScope::ReadOnly);
+
+        // Syscalls outside the allowlist fail with EPERM, a shell still runs
+        let env = WsdgEnvBuilder::new().build();
+        let mut command = Command::new("/bin/sh");
+        command.args(["-c", "exec 2>&1; unshare -n true || echo unshare-denied; echo alive"]);
+        SandboxProfile::for_level(5).apply(&mut command, &env, "test.sandbox");
+        let output = command.output().unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("alive"));
+        assert!(stdout.contains("unshare-denied"));
+        let mut command = Command::new("/bin/sh");
+        command.args(["-c", "ls /usr >/dev/null && echo alive"]);
+        SandboxProfile::for_level(10).apply(&mut command, &env, "test.sandbox");
+        assert_eq!(String::from_utf8_lossy(&command.output().unwrap().stdout), "alive\n");
+
+        // clone3, clone with CLONE_NEW*, x32 numbers, io_uring and syscalls no group
+        // lists are all refused; one bit per check
+        let filter = SandboxProfile::for_level(5).seccomp_filter();
+        let status = unsafe {
+            let pid = libc::fork();
+            if pid == 0 {
+                let program = libc::sock_fprog { len: filter.len() as u16, filter: filter.as_ptr() as *mut _ };
+                libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0);
+                libc::prctl(libc::PR_SET_SECCOMP, libc::SECCOMP_MODE_FILTER, &program as *const libc::sock_fprog);
+                let errno = |ret: libc::c_long| if ret == -1 { *libc::__errno_location() } else { 0 };
+                let mut code = 0;
+                if errno(libc::syscall(libc::SYS_clone3, std::ptr::null::<u8>(), 0usize)) == libc::ENOSYS {
+                    code |= 1;
+                }
+                let flags = (libc::CLONE_NEWUSER | libc::SIGCHLD) as libc::c_ulong;
+                if errno(libc::syscall(libc::SYS_clone, flags, 0usize, 0usize, 0usize, 0usize)) == libc::EPERM {
+                    code |= 2;
+                }
+                if !cfg!(target_arch = "x86_64")
+                    || errno(libc::syscall(libc::SYS_getpid | X32_SYSCALL_BIT as libc::c_long)) == libc::EPERM
+                {
+                    code |= 4;
+                }
+                if errno(libc::syscall(libc::SYS_io_uring_setup, 1u32, std::ptr::null_mut::<u8>())) == libc::EPERM {
+                    code |= 8;
+                }
+                if errno(libc::syscall(libc::SYS_io_setup, 0u32, std::ptr::null_mut::<u8>())) == libc::EPERM {
+                    code |= 16;
+                }
+                libc::_exit(code);
+            }
+            let mut status = 0;
+            libc::waitpid(pid, &mut status, 0);
+            status
+        };
+        assert!(libc::WIFEXITED(status));
+        assert_eq!(libc::WEXITSTATUS(status), 31);
+
+        // Only the filtered levels get an allowlist
+        assert_eq!(SandboxProfile::for_level(1).allowed, None);
+        assert!(SandboxProfile::for_level(4).allowed.unwrap().contains(&SyscallGroup::Namespace));
+    }
+
+    #[test]
+    fn test_landlock_handles_abi_rights() {
+        assert_eq!(landlock_handled_access(1), LANDLOCK_ACCESS_FS_V1);
+        assert_eq!(landlock_handled_access(2) & LANDLOCK_ACCESS_FS_TRUNCATE, 0);
+        assert_ne!(landlock_handled_access(3) & LANDLOCK_ACCESS_FS_REFER, 0);
+        assert_ne!(landlock_handled_access(3) & LANDLOCK_ACCESS_FS_TRUNCATE, 0);
+
+        // A readable-only file cannot be truncated or renamed out of its scope
+        let dir = tempfile::tempdir().unwrap();
+        let (readable, writable) = (dir.path().join("ro"), dir.path().join("rw"));
+        std::fs::create_dir_all(&readable).unwrap();
+        std::fs::create_dir_all(&writable).unwrap();
+        let file = readable.join("data");
+        std::fs::write(&file, "keep").unwrap();
+        let Ok(ruleset) = landlock_ruleset(std::slice::from_ref(&readable), std::slice::from_ref(&writable)) else {
+            return; // kernel without landlock
+        };
+        let abi = unsafe {
+            libc::syscall(libc::SYS_landlock_create_ruleset, std::ptr::null::<u8>(), 0usize, LANDLOCK_CREATE_RULESET_VERSION)
+        };
+        let c_file = CString::new(file.as_os_str().as_bytes()).unwrap();
+        let c_moved = CString::new(writable.join("data").as_os_str().as_bytes()).unwrap();
+
+        let status = unsafe {
+            let pid = libc::fork();
+            if pid == 0 {
+                if landlock_restrict_self(ruleset.as_raw_fd()).is_err() {
+                    libc::_exit(100);
+                }
+                let mut code = 0;
+                if abi < 3 || libc::truncate(c_file.as_ptr(), 0) != 0 {
+                    code |= 1;
+                }
+                if abi < 2 || libc::rename(c_file.as_ptr(), c_moved.as_ptr()) != 0 {
+                    code |= 2;
+                }
+                libc::_exit(code);
+            }
+            let mut status = 0;
+            libc::waitpid(pid, &mut status, 0);
+            status
+        };
+        assert_eq!(libc::WEXITSTATUS(status), 3);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep");
+    }
+}
@@ -0,0 +1,172 @@
+// WSDG Atomic Writes - Crash-safe persistence for settings and state files
+// Files are written to a temporary sibling, fsynced and renamed over the
+// target, so a crash leaves either the old or the new version, never a mix.
+// The version being replaced is kept as <name>.bak. A symlinked path is
+// resolved first, so the link stays and its target gets the new contents.
+// Part of WASMA (Windows Assignment System Monitoring Architecture)
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Symlinks followed before giving up (Linux MAXSYMLINKS)
+const MAX_LINK_DEPTH: usize = 40;
+
+/// Distinguishes temporary files of concurrent writes within this process
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Where `write_atomic` keeps the previous version of `path`
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".tmp-{}-{}", std::process::id(), TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)));
+    path.with_file_name(name)
+}
+
+/// The file `path` ends up at after following symlinks; it need not exist
+fn resolve_links(path: &Path) -> io::Result<PathBuf> {
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_LINK_DEPTH {
+        match fs::symlink_metadata(&path) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                let target = fs::read_link(&path)?;
+                // Relative targets are relative to the link's directory
+                path = match path.parent() {
+                    Some(dir) => dir.join(target),
+                    None => target,
+                };
+            }
+            _ => return Ok(path),
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidInput, format!("too many levels of symbolic links: {}", path.display())))
+}
+
+/// Replace `path` with `contents` atomically
+///
+/// The data is on disk before the rename, and the rename itself is made
+/// durable by syncing the directory. Parent directories are created.
+/// A symlink is written through: its target is replaced, not the link.
+pub fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = resolve_links(path.as_ref())?;
+    let path = path.as_path();
+    if path.file_name().is_none() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("not a file path: {}", path.display())));
+    }
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)?;
+
+    let temp = temp_path(path);
+    let written = (|| {
+        let mut file = OpenOptions::new().write(true).create_new(true).open(&temp)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        // Keep the permissions of the file being replaced
+        if let Ok(meta) = fs::metadata(path) {
+            fs::set_permissions(&temp, meta.permissions())?;
+        }
+        Ok(())
+    })();
+    if let Err(e) = written {
+        fs::remove_file(&temp).ok();
+        return Err(e);
+    }
+
+    if path.exists() {
+        let backup = backup_path(path);
+        fs::remove_file(&backup).ok();
+        // A hard link costs no copy; filesystems without links get a copy
+        if fs::hard_link(path, &backup).is_err() {
+            if let Err(e) = fs::copy(path, &backup) {
+                tracing::warn!(path = %path.display(), error = %e, "no backup written");
+            }
+        }
+    }
+
+    if let Err(e) = fs::rename(&temp, path) {
+        fs::remove_file(&temp).ok();
+        return Err(e);
+    }
+    File::open(dir)?.sync_all()
+}
+
+/// Put the backup of `path` back in place; false when there is none
+pub fn restore_backup(path: impl AsRef<Path>) -> io::Result<bool> {
+    let path = path.as_ref();
+    let backup = backup_path(path);
+    if !backup.exists() {
+        return Ok(false);
+    }
+    write_atomic(path, fs::read(&backup)?)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_write_atomic_with_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/settings.conf");
+
+        write_atomic(&path, "[theme]\nmode = \"dark\"\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[theme]\nmode = \"dark\"\n");
+        assert!(!backup_path(&path).exists());
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        write_atomic(&path, "[theme]\nmode = \"light\"\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[theme]\nmode = \"light\"\n");
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "[theme]\nmode = \"dark\"\n");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        // No temporary files left behind
+        let names: Vec<_> = fs::read_dir(path.parent().unwrap()).unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(names.len(), 2, "{:?}", names);
+
+        assert!(restore_backup(&path).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[theme]\nmode = \"dark\"\n");
+        assert!(!restore_backup(dir.path().join("missing.conf")).unwrap());
+    }
+
+    #[test]
+    fn test_write_atomic_through_symlink_and_threads() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("dotfiles/settings.conf");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&target, "old\n").unwrap();
+        let link = dir.path().join("settings.conf");
+        std::os::unix::fs::symlink("dotfiles/settings.conf", &link).unwrap();
+
+        // The link stays a link, the file it points at is replaced
+        write_atomic(&link, "new\n").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new\n");
+
+        // Concurrent writers in one process do not share a temporary file
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let target = &target;
+                scope.spawn(move || write_atomic(target, format!("writer {}\n", i)).unwrap());
+            }
+        });
+        assert!(fs::read_to_string(&target).unwrap().starts_with("writer "));
+        let temps = fs::read_dir(target.parent().unwrap()).unwrap()
+            .filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().contains(".tmp-"))
+            .count();
+        assert_eq!(temps, 0);
+    }
+}
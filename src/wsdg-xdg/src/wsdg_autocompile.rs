@@ -9,6 +9,7 @@ use std::collections::HashMap;
 use thiserror::Error;
 
 use crate::xdg_wsdg_translate::{XdgWsdgTranslator, EnvConfig, ShellStandard};
+use crate::wsdg_atomic::write_atomic;
 
 #[derive(Debug, Error)]
 pub enum AutoCompileError {
@@ -227,7 +228,7 @@ impl WsdgAutoCompiler {
             content.push_str(&format!("{}\n", export));
         }
         
-        fs::write(cache_file, content)?;
+        write_atomic(cache_file, content)?;
         Ok(())
     }
     
@@ -9,6 +9,8 @@ use std::fs;
 use std::collections::HashMap;
 use thiserror::Error;
 
+use crate::wsdg_icon_extract::ExtractedIcon;
+
 #[derive(Debug, Error)]
 pub enum IconError {
     #[error("Icon not found: {0}")]
@@ -17,6 +19,9 @@ pub enum IconError {
     #[error("Invalid icon size: {0}")]
     InvalidSize(u32),
     
+    #[error("Unsupported icon source: {0}")]
+    Unsupported(String),
+    
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
@@ -354,6 +359,23 @@ impl WsdgIcoCtl {
             self.icon_dirs.push(dir);
         }
     }
+    
+    /// Icon embedded in an executable or AppImage, for binaries without a theme icon
+    pub fn icon_for_executable(&mut self, exe: &Path, preferred_size: Option<IconSize>) -> Option<IconInfo> {
+        let stem = exe.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string();
+        let cache_dir = crate::wsdg_icon_extract::extraction_cache_dir();
+        
+        match crate::wsdg_icon_extract::extract_icon(exe, &cache_dir) {
+            Ok(ExtractedIcon::File { path, format, .. }) => Some(IconInfo {
+                name: stem,
+                path,
+                size: preferred_size.unwrap_or(IconSize::Size48),
+                format,
+            }),
+            Ok(ExtractedIcon::ThemeName { name, .. }) => self.find_icon(&name, preferred_size),
+            Err(_) => self.find_icon(&stem, preferred_size),
+        }
+    }
 }
 
 impl Default for WsdgIcoCtl {
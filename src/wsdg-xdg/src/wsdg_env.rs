@@ -6,6 +6,7 @@ use std::collections::HashMap;
 use std::env;
 use std::path::PathBuf;
 use thiserror::Error;
+use wsdg_app_manifest::EnvironmentConfig;
 
 #[derive(Debug, Error)]
 pub enum EnvError {
@@ -195,6 +196,11 @@ impl WsdgEnv {
         Ok(1000) // Default on non-Unix
     }
     
+    /// Kiosk mode in effect for this environment, if any
+    pub fn kiosk(&self) -> Option<crate::wsdg_kiosk::KioskPolicy> {
+        crate::wsdg_kiosk::KioskPolicy::detect(self)
+    }
+    
     /// Enable/disable system fallback
     pub fn set_system_fallback(&mut self, enabled: bool) {
         self.use_system_fallback = enabled;
@@ -220,6 +226,19 @@ impl WsdgEnv {
         exports
     }
     
+    /// Full environment for a launched application: system variables (when
+    /// fallback is enabled), WSDG variables on top, then the manifest overlay
+    pub fn launch_environment(&self, overlay: &EnvironmentConfig) -> HashMap<String, String> {
+        let mut vars = if self.use_system_fallback {
+            self.system_vars.clone()
+        } else {
+            HashMap::new()
+        };
+        vars.extend(self.vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+        overlay.apply(&mut vars);
+        vars
+    }
+    
     /// Merge with XDG translator config
     pub fn merge_from_translator(&mut self, _translator: &crate::xdg_wsdg_translate::XdgWsdgTranslator) {
         // This would integrate with the translator
@@ -345,4 +364,22 @@ mod tests {
         let fish_exports = env.export_to_shell("fish");
         assert!(fish_exports.iter().any(|e| e.contains("set -gx TEST")));
     }
+    
+    #[test]
+    fn test_launch_environment_overlay() {
+        let env = WsdgEnvBuilder::new()
+            .home("/home/testuser")
+            .var("APP_MODE", "default")
+            .system_fallback(false)
+            .build();
+        
+        let overlay = EnvironmentConfig {
+            set: vec![("APP_MODE".to_string(), "debug".to_string())],
+            unset: vec!["HOME".to_string()],
+        };
+        
+        let vars = env.launch_environment(&overlay);
+        assert_eq!(vars.get("APP_MODE"), Some(&"debug".to_string()));
+        assert!(!vars.contains_key("HOME"));
+    }
 }
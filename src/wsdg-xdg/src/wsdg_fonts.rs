@@ -0,0 +1,295 @@
+// WSDG Fonts - Font discovery and per-script fallback
+// The installed font families come from fontconfig (`fc-list`) or, without it,
+// from the family names inside the TrueType/OpenType files under the XDG font
+// directories. Font settings are validated against this catalog: fallback
+// chains only keep families that are installed, so UBIN text rendering never
+// walks through fonts that cannot be loaded.
+// Part of WASMA (Windows Assignment System Monitoring Architecture)
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use crate::wsdg_env::WsdgEnv;
+
+/// fontconfig aliases; always resolvable, whatever is installed
+pub const GENERIC_FAMILIES: &[&str] = &[
+    "sans", "sans-serif", "serif", "monospace", "mono", "emoji", "system-ui", "cursive", "fantasy",
+];
+
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "otc"];
+const MAX_SCAN_DEPTH: usize = 6;
+
+/// Scripts that get their own fallback chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FontScript {
+    /// Chinese, Japanese and Korean (Han, kana, Hangul, fullwidth forms)
+    Cjk,
+    Arabic,
+    Emoji,
+}
+
+impl FontScript {
+    pub const ALL: [FontScript; 3] = [FontScript::Cjk, FontScript::Arabic, FontScript::Emoji];
+
+    /// Key in the [font] section of settings.conf
+    pub fn name(&self) -> &'static str {
+        match self {
+            FontScript::Cjk => "cjk",
+            FontScript::Arabic => "arabic",
+            FontScript::Emoji => "emoji",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|script| script.name() == name.trim().to_lowercase())
+    }
+
+    /// Script a character needs its own fonts for; None for everything the
+    /// main family and the general fallback cover
+    pub fn of(c: char) -> Option<Self> {
+        match c as u32 {
+            0x2E80..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF | 0x20000..=0x3FFFF => Some(FontScript::Cjk),
+            0x0600..=0x06FF | 0x0750..=0x077F | 0x08A0..=0x08FF | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => Some(FontScript::Arabic),
+            0x2600..=0x27BF | 0x1F000..=0x1FAFF => Some(FontScript::Emoji),
+            _ => None,
+        }
+    }
+}
+
+/// A configured family that is not installed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingFont {
+    /// Setting it appears in ("font.fallback", "font.cjk", ...)
+    pub key: String,
+    pub family: String,
+}
+
+/// Installed font families
+#[derive(Debug, Clone, Default)]
+pub struct FontCatalog {
+    // lowercase → name as installed
+    families: BTreeMap<String, String>,
+}
+
+static SYSTEM: OnceLock<FontCatalog> = OnceLock::new();
+
+impl FontCatalog {
+    pub fn new<S: AsRef<str>>(families: impl IntoIterator<Item = S>) -> Self {
+        let families = families.into_iter()
+            .map(|f| f.as_ref().trim().to_string())
+            .filter(|f| !f.is_empty())
+            .map(|f| (f.to_lowercase(), f))
+            .collect();
+        Self { families }
+    }
+
+    /// Families of the user's session, discovered once per process
+    pub fn system() -> &'static FontCatalog {
+        SYSTEM.get_or_init(|| Self::discover(&WsdgEnv::new()))
+    }
+
+    /// fontconfig's list, else a scan of the font directories
+    pub fn discover(env: &WsdgEnv) -> Self {
+        Self::from_fontconfig().unwrap_or_else(|| Self::scan(&Self::font_dirs(env)))
+    }
+
+    fn from_fontconfig() -> Option<Self> {
+        let output = Command::new("fc-list")
+            .args(["--format", "%{family}\n"])
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        // A file lists every family name it answers to: "DejaVu Sans,DejaVu Sans Condensed"
+        let list = String::from_utf8_lossy(&output.stdout).into_owned();
+        let catalog = Self::new(list.lines().flat_map(|line| line.split(',')).map(|f| f.replace('\\', "")));
+        (!catalog.is_empty()).then_some(catalog)
+    }
+
+    /// XDG font directories, user ones first
+    pub fn font_dirs(env: &WsdgEnv) -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        if let Ok(share) = env.share_dir() {
+            dirs.push(share.join("fonts"));
+        }
+        if let Ok(home) = env.home_dir() {
+            dirs.push(home.join(".fonts"));
+        }
+        let data_dirs = env.get("XDG_DATA_DIRS").cloned().unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+        dirs.extend(data_dirs.split(':').filter(|d| !d.is_empty()).map(|d| Path::new(d).join("fonts")));
+        dirs.dedup();
+        dirs
+    }
+
+    /// Family names read from the font files under `dirs`
+    pub fn scan(dirs: &[PathBuf]) -> Self {
+        let mut files = Vec::new();
+        for dir in dirs {
+            collect_font_files(dir, 0, &mut files);
+        }
+        Self::new(files.iter().filter_map(|path| sfnt_family(path)))
+    }
+
+    pub fn contains(&self, family: &str) -> bool {
+        let family = family.trim().to_lowercase();
+        GENERIC_FAMILIES.contains(&family.as_str()) || self.families.contains_key(&family)
+    }
+
+    pub fn families(&self) -> impl Iterator<Item = &str> {
+        self.families.values().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.families.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.families.is_empty()
+    }
+}
+
+fn collect_font_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            if depth < MAX_SCAN_DEPTH {
+                collect_font_files(&path, depth + 1, files);
+            }
+        } else if path.extension().and_then(|e| e.to_str()).is_some_and(|e| FONT_EXTENSIONS.contains(&e.to_lowercase().as_str())) {
+            files.push(path);
+        }
+    }
+}
+
+// ============================================================================
+// SFNT NAME TABLE
+// ============================================================================
+
+fn read_at(file: &mut File, offset: u64, len: usize) -> Option<Vec<u8>> {
+    let mut buf = vec![0; len];
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    file.read_exact(&mut buf).ok()?;
+    Some(buf)
+}
+
+fn be16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn be32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Family name of a TrueType/OpenType font (the first font of a collection).
+/// Only the table directory and the name table are read, not the glyphs
+pub fn sfnt_family(path: &Path) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let header = read_at(&mut file, 0, 12)?;
+    let font_offset = if &header[0..4] == b"ttcf" { be32(&header, 8)? as u64 } else { 0 };
+
+    let directory = read_at(&mut file, font_offset, 12)?;
+    let num_tables = be16(&directory, 4)? as usize;
+    let records = read_at(&mut file, font_offset + 12, num_tables * 16)?;
+    let (name_offset, name_len) = records.chunks_exact(16)
+        .find(|record| &record[0..4] == b"name")
+        .and_then(|record| Some((be32(record, 8)?, be32(record, 12)?)))?;
+    let table = read_at(&mut file, name_offset as u64, (name_len as usize).min(1 << 20))?;
+    name_table_family(&table)
+}
+
+/// Typographic family (name ID 16) when present, else the family (ID 1);
+/// Windows/Unicode (UTF-16BE) records are preferred over Mac Roman ones
+fn name_table_family(table: &[u8]) -> Option<String> {
+    let count = be16(table, 2)? as usize;
+    let strings = be16(table, 4)? as usize;
+    let mut best: Option<(u32, String)> = None;
+    for i in 0..count {
+        let record = table.get(6 + i * 12..18 + i * 12)?;
+        let (platform, language, name_id) = (be16(record, 0)?, be16(record, 4)?, be16(record, 6)?);
+        let (len, offset) = (be16(record, 8)? as usize, be16(record, 10)? as usize);
+        if name_id != 1 && name_id != 16 {
+            continue;
+        }
+        let Some(bytes) = table.get(strings + offset..strings + offset + len) else { continue };
+        let name = match platform {
+            0 | 3 => {
+                let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+                String::from_utf16_lossy(&units)
+            }
+            1 => bytes.iter().map(|&b| b as char).collect(),
+            _ => continue,
+        };
+        let rank = (name_id == 16) as u32 * 4 + (platform != 1) as u32 * 2 + (language == 0x409 || platform == 0) as u32;
+        if !name.trim().is_empty() && best.as_ref().map_or(true, |(r, _)| rank > *r) {
+            best = Some((rank, name.trim().to_string()));
+        }
+    }
+    best.map(|(_, name)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Smallest sfnt with a name table holding `names` (platform, name id, name)
+    fn font_file(names: &[(u16, u16, &str)]) -> Vec<u8> {
+        let mut strings = Vec::new();
+        let mut records = Vec::new();
+        for &(platform, name_id, name) in names {
+            let bytes: Vec<u8> = if platform == 1 {
+                name.bytes().collect()
+            } else {
+                name.encode_utf16().flat_map(u16::to_be_bytes).collect()
+            };
+            let language: u16 = if platform == 3 { 0x409 } else { 0 };
+            for v in [platform, 1, language, name_id, bytes.len() as u16, strings.len() as u16] {
+                records.extend(v.to_be_bytes());
+            }
+            strings.extend(bytes);
+        }
+        let mut table = Vec::new();
+        for v in [0u16, names.len() as u16, 6 + records.len() as u16] {
+            table.extend(v.to_be_bytes());
+        }
+        table.extend(records);
+        table.extend(strings);
+
+        let mut font = vec![0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+        font.extend(b"name");
+        for v in [0u32, 28, table.len() as u32] {
+            font.extend(v.to_be_bytes());
+        }
+        font.extend(table);
+        font
+    }
+
+    #[test]
+    fn test_font_discovery() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("truetype/noto");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("NotoSansCJK.ttf"), font_file(&[(1, 1, "Mac Name"), (3, 1, "Noto Sans CJK SC")])).unwrap();
+        fs::write(dir.path().join("Inter.otf"), font_file(&[(3, 1, "Inter Regular"), (3, 16, "Inter")])).unwrap();
+        fs::write(dir.path().join("broken.ttf"), b"not a font").unwrap();
+        fs::write(dir.path().join("readme.txt"), font_file(&[(3, 1, "Ignored")])).unwrap();
+
+        let catalog = FontCatalog::scan(&[dir.path().to_path_buf(), dir.path().join("missing")]);
+        assert_eq!(catalog.families().collect::<Vec<_>>(), ["Inter", "Noto Sans CJK SC"]);
+        assert!(catalog.contains("noto sans cjk sc"));
+        assert!(catalog.contains("Monospace"));
+        assert!(!catalog.contains("Inter Regular"));
+
+        assert_eq!(FontScript::of('漢'), Some(FontScript::Cjk));
+        assert_eq!(FontScript::of('한'), Some(FontScript::Cjk));
+        assert_eq!(FontScript::of('ع'), Some(FontScript::Arabic));
+        assert_eq!(FontScript::of('😀'), Some(FontScript::Emoji));
+        assert_eq!(FontScript::of('ş'), None);
+        assert_eq!(FontScript::from_name("Emoji"), Some(FontScript::Emoji));
+    }
+}
@@ -11,6 +11,7 @@ use thiserror::Error;
 
 use crate::wsdg_open::{WsdgOpen, OpenError};
 use crate::wsdg_env::WsdgEnv;
+use crate::wsdg_handlers::{HandlerKind, HandlerRegistry};
 
 #[derive(Debug, Error)]
 pub enum GhxOpenError {
@@ -139,9 +140,29 @@ impl WsdgGhxOpen {
         
         // Register default handlers
         ghx.register_default_handlers();
+        ghx.register_manifest_handlers();
         ghx
     }
     
+    /// Schemes declared by installed manifests (`handles_scheme`); these
+    /// take precedence over the built-in handlers
+    fn register_manifest_handlers(&mut self) {
+        let Ok(registry) = HandlerRegistry::open(self.wsdg_open.env(), HandlerKind::Scheme) else {
+            return;
+        };
+        
+        for scheme in registry.keys() {
+            let Some(manifest_path) = registry.default_handler(&scheme).map(str::to_string) else {
+                continue;
+            };
+            self.register_handler(&scheme, Box::new(move |uri, env| {
+                WsdgOpen::new(env.clone())
+                    .open_manifest_path(&manifest_path, &[uri.to_string().as_str()])
+                    .map_err(GhxOpenError::from)
+            }));
+        }
+    }
+    
     /// Register default protocol handlers
     fn register_default_handlers(&mut self) {
         // App protocol - Custom app:// URI for manifest-based apps
@@ -0,0 +1,313 @@
+// WSDG Handlers - Manifest-declared MIME and URI scheme handlers
+// Persistent MIME association store and ghx scheme registry
+// `handles_mime` / `handles_scheme` entries are registered on install and removed on uninstall
+// Part of WASMA (Windows Assignment System Monitoring Architecture)
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use wsdg_app_manifest::{ManifestParser, ManifestError};
+
+use crate::wsdg_env::WsdgEnv;
+use crate::wsdg_atomic::write_atomic;
+use crate::wsdg_kiosk::PolicyDenied;
+
+#[derive(Debug, Error)]
+pub enum HandlerError {
+    #[error("Manifest error: {0}")]
+    Manifest(#[from] ManifestError),
+
+    #[error("Manifest declares no handles_mime or handles_scheme: {0}")]
+    NothingToRegister(String),
+
+    #[error("Environment error: {0}")]
+    Env(String),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("{0}")]
+    PolicyDenied(#[from] PolicyDenied),
+}
+
+/// Which registry a store backs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandlerKind {
+    /// MIME type → manifests (wsdg-open)
+    Mime,
+    /// URI scheme → manifests (wsdg-ghx-open)
+    Scheme,
+}
+
+impl HandlerKind {
+    fn file_name(&self) -> &'static str {
+        match self {
+            Self::Mime => "mime-associations.list",
+            Self::Scheme => "scheme-handlers.list",
+        }
+    }
+}
+
+/// `key = manifest;manifest` store; the first manifest is the default handler
+#[derive(Debug, Clone)]
+pub struct HandlerRegistry {
+    kind: HandlerKind,
+    path: PathBuf,
+    entries: BTreeMap<String, Vec<String>>,
+}
+
+impl HandlerRegistry {
+    /// `$SHARE/wsdg/<kind>.list` for the given environment (the vendor copy in kiosk mode)
+    pub fn default_path(env: &WsdgEnv, kind: HandlerKind) -> Result<PathBuf, HandlerError> {
+        if let Some(policy) = env.kiosk() {
+            return Ok(policy.handler_store(kind.file_name()));
+        }
+        env.share_dir()
+            .map(|share| share.join("wsdg").join(kind.file_name()))
+            .map_err(|e| HandlerError::Env(e.to_string()))
+    }
+
+    /// Open the user's registry; a missing file is an empty registry
+    pub fn open(env: &WsdgEnv, kind: HandlerKind) -> Result<Self, HandlerError> {
+        Self::load(kind, Self::default_path(env, kind)?)
+    }
+
+    pub fn load(kind: HandlerKind, path: impl Into<PathBuf>) -> Result<Self, HandlerError> {
+        let path = path.into();
+        let mut entries = BTreeMap::new();
+
+        if path.exists() {
+            for line in fs::read_to_string(&path)?.lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with("*//") {
+                    continue;
+                }
+                if let Some((key, manifests)) = line.split_once('=') {
+                    let manifests: Vec<String> = manifests.split(';')
+                        .map(|m| m.trim().to_string())
+                        .filter(|m| !m.is_empty())
+                        .collect();
+                    if !manifests.is_empty() {
+                        entries.insert(key.trim().to_lowercase(), manifests);
+                    }
+                }
+            }
+        }
+
+        Ok(Self { kind, path, entries })
+    }
+
+    pub fn save(&self) -> Result<(), HandlerError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut content = match self.kind {
+            HandlerKind::Mime => String::from("*// WSDG MIME associations - managed by `wasma manifest install`\n"),
+            HandlerKind::Scheme => String::from("*// WSDG scheme handlers - managed by `wasma manifest install`\n"),
+        };
+        for (key, manifests) in &self.entries {
+            content.push_str(&format!("{} = {}\n", key, manifests.join(";")));
+        }
+
+        write_atomic(&self.path, content)?;
+        Ok(())
+    }
+
+    pub fn kind(&self) -> HandlerKind {
+        self.kind
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Manifests registered for a MIME type or scheme, default first
+    pub fn handlers(&self, key: &str) -> &[String] {
+        self.entries.get(&key.to_lowercase()).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn default_handler(&self, key: &str) -> Option<&str> {
+        self.handlers(key).first().map(String::as_str)
+    }
+
+    /// Registered MIME types or schemes
+    pub fn keys(&self) -> Vec<String> {
+        self.entries.keys().cloned().collect()
+    }
+
+    /// Returns false when the manifest was already registered for `key`
+    pub fn register(&mut self, key: &str, manifest: &str) -> bool {
+        let manifests = self.entries.entry(key.to_lowercase()).or_default();
+        if manifests.iter().any(|m| m == manifest) {
+            return false;
+        }
+        manifests.push(manifest.to_string());
+        true
+    }
+
+    /// Drop every entry pointing at `manifest`; returns the keys it was removed from
+    pub fn unregister_manifest(&mut self, manifest: &str) -> Vec<String> {
+        let mut removed = Vec::new();
+        for (key, manifests) in self.entries.iter_mut() {
+            let before = manifests.len();
+            manifests.retain(|m| m != manifest);
+            if manifests.len() != before {
+                removed.push(key.clone());
+            }
+        }
+        self.entries.retain(|_, manifests| !manifests.is_empty());
+        removed
+    }
+
+    /// Point every entry for `from` at `to` (manifest moved); returns the entries changed
+    pub fn rename_manifest(&mut self, from: &str, to: &str) -> usize {
+        let mut renamed = 0;
+        for manifests in self.entries.values_mut() {
+            let Some(index) = manifests.iter().position(|m| m == from) else { continue };
+            if manifests.iter().any(|m| m == to) {
+                manifests.remove(index);
+            } else {
+                manifests[index] = to.to_string();
+            }
+            renamed += 1;
+        }
+        renamed
+    }
+}
+
+/// What an install or uninstall changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HandlerReport {
+    pub manifest: String,
+    pub mime_types: Vec<String>,
+    pub schemes: Vec<String>,
+    /// Manifest deprecation notices (install only)
+    pub warnings: Vec<String>,
+}
+
+/// Absolute manifest path used as the registry value
+fn manifest_key(manifest_path: &Path) -> String {
+    let resolved = fs::canonicalize(manifest_path).ok().or_else(|| {
+        // Already deleted: resolve the directory instead
+        let parent = manifest_path.parent().filter(|p| !p.as_os_str().is_empty())?;
+        Some(fs::canonicalize(parent).ok()?.join(manifest_path.file_name()?))
+    });
+    resolved
+        .unwrap_or_else(|| manifest_path.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Register a manifest's `handles_mime` and `handles_scheme` entries
+pub fn install_manifest(env: &WsdgEnv, manifest_path: &Path) -> Result<HandlerReport, HandlerError> {
+    if let Some(policy) = env.kiosk() {
+        return Err(policy.deny(format!("registering handlers of {}", manifest_path.display())).into());
+    }
+    let key = manifest_key(manifest_path);
+    let manifest = ManifestParser::new(key.clone()).load()?;
+
+    if manifest.app.handles_mime.is_empty() && manifest.app.handles_scheme.is_empty() {
+        return Err(HandlerError::NothingToRegister(key));
+    }
+
+    let mut mime = HandlerRegistry::open(env, HandlerKind::Mime)?;
+    let mut schemes = HandlerRegistry::open(env, HandlerKind::Scheme)?;
+
+    // Reinstall replaces whatever an older version of the manifest declared
+    mime.unregister_manifest(&key);
+    schemes.unregister_manifest(&key);
+
+    for mime_type in &manifest.app.handles_mime {
+        mime.register(mime_type, &key);
+    }
+    for scheme in &manifest.app.handles_scheme {
+        schemes.register(scheme, &key);
+    }
+
+    mime.save()?;
+    schemes.save()?;
+
+    Ok(HandlerReport {
+        manifest: key,
+        mime_types: manifest.app.handles_mime,
+        schemes: manifest.app.handles_scheme,
+        warnings: manifest.warnings,
+    })
+}
+
+/// Remove every registration of a manifest; works even if the file is gone
+pub fn uninstall_manifest(env: &WsdgEnv, manifest_path: &Path) -> Result<HandlerReport, HandlerError> {
+    if let Some(policy) = env.kiosk() {
+        return Err(policy.deny(format!("removing handlers of {}", manifest_path.display())).into());
+    }
+    let key = manifest_key(manifest_path);
+
+    let mut mime = HandlerRegistry::open(env, HandlerKind::Mime)?;
+    let mut schemes = HandlerRegistry::open(env, HandlerKind::Scheme)?;
+
+    let report = HandlerReport {
+        mime_types: mime.unregister_manifest(&key),
+        schemes: schemes.unregister_manifest(&key),
+        manifest: key,
+        warnings: Vec::new(),
+    };
+
+    mime.save()?;
+    schemes.save()?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wsdg_env::WsdgEnvBuilder;
+
+    #[test]
+    fn test_install_uninstall() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = WsdgEnvBuilder::new()
+            .share(dir.path().to_string_lossy())
+            .build();
+
+        let manifest_path = dir.path().join("viewer.manifest");
+        fs::write(&manifest_path, "name = Viewer\nexec = viewer\nhandles_mime = [image/png]\nhandles_scheme = [viewer]\n").unwrap();
+
+        let report = install_manifest(&env, &manifest_path).unwrap();
+        assert_eq!(report.mime_types, vec!["image/png"]);
+
+        let mime = HandlerRegistry::open(&env, HandlerKind::Mime).unwrap();
+        assert_eq!(mime.default_handler("IMAGE/PNG"), Some(report.manifest.as_str()));
+        let schemes = HandlerRegistry::open(&env, HandlerKind::Scheme).unwrap();
+        assert_eq!(schemes.keys(), vec!["viewer"]);
+
+        fs::remove_file(&manifest_path).unwrap();
+        let removed = uninstall_manifest(&env, &manifest_path).unwrap();
+        assert_eq!(removed.schemes, vec!["viewer"]);
+
+        let mime = HandlerRegistry::open(&env, HandlerKind::Mime).unwrap();
+        assert!(mime.handlers("image/png").is_empty());
+    }
+
+    #[test]
+    fn test_kiosk_denies_registration() {
+        let dir = tempfile::tempdir().unwrap();
+        let vendor = dir.path().join("vendor");
+        let env = WsdgEnvBuilder::new()
+            .share(dir.path().to_string_lossy())
+            .var(crate::wsdg_kiosk::KIOSK_ENV_VAR, vendor.to_string_lossy())
+            .build();
+
+        let manifest_path = dir.path().join("viewer.manifest");
+        fs::write(&manifest_path, "name = Viewer\nexec = viewer\nhandles_scheme = [viewer]\n").unwrap();
+
+        assert!(matches!(install_manifest(&env, &manifest_path), Err(HandlerError::PolicyDenied(_))));
+        assert!(matches!(uninstall_manifest(&env, &manifest_path), Err(HandlerError::PolicyDenied(_))));
+        assert_eq!(
+            HandlerRegistry::default_path(&env, HandlerKind::Scheme).unwrap(),
+            vendor.join("scheme-handlers.list")
+        );
+    }
+}
@@ -0,0 +1,952 @@
+// WSDG Icon Extract - Embedded icons from executables
+// ELF: `.icon` section (raw PNG/SVG) or `.desktop` section (Icon= theme name)
+// PE: RT_GROUP_ICON + RT_ICON resources, rebuilt into a standalone .ico
+// AppImage: `.DirIcon` read from the embedded squashfs image (the binary is never run)
+// Extracted images land in $CACHE/wsdg/icons/extracted and are reused until the binary changes
+// Part of WASMA (Windows Assignment System Monitoring Architecture)
+
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::wsdg_byico_icoctl::{IconError, IconFormat};
+use crate::wsdg_env::WsdgEnv;
+
+/// Largest embedded icon or resource section read from a binary
+pub const MAX_EMBEDDED_ICON_BYTES: u64 = 8 * 1024 * 1024;
+
+const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+const PNG_MAGIC: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+const RT_ICON: u32 = 3;
+const RT_GROUP_ICON: u32 = 14;
+const RESOURCE_DIRECTORY_INDEX: usize = 2;
+
+/// Where an extracted icon came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconSource {
+    ElfSection,
+    ElfDesktopEntry,
+    PeResource,
+    AppImage,
+}
+
+/// Result of an extraction
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtractedIcon {
+    /// Image written to the extraction cache
+    File { path: PathBuf, format: IconFormat, source: IconSource },
+    /// The binary only names a theme icon (`Icon=` of an embedded .desktop entry)
+    ThemeName { name: String, source: IconSource },
+}
+
+/// Default extraction cache: $CACHE/wsdg/icons/extracted
+pub fn extraction_cache_dir() -> PathBuf {
+    WsdgEnv::new()
+        .cache_dir()
+        .unwrap_or_else(|_| std::env::temp_dir())
+        .join("wsdg/icons/extracted")
+}
+
+/// Extract the icon embedded in `exe`, caching images under `cache_dir`
+pub fn extract_icon(exe: &Path, cache_dir: &Path) -> Result<ExtractedIcon, IconError> {
+    let exe = fs::canonicalize(exe)?;
+    let modified = fs::metadata(&exe)?.modified()?;
+    let key = cache_key(&exe);
+
+    // Cached image newer than the binary: nothing to do
+    for (ext, format) in [("png", IconFormat::Png), ("svg", IconFormat::Svg), ("ico", IconFormat::Ico)] {
+        let cached = cache_dir.join(format!("{}.{}", key, ext));
+        let fresh = fs::metadata(&cached)
+            .and_then(|m| m.modified())
+            .map(|t| t >= modified)
+            .unwrap_or(false);
+        if fresh {
+            return Ok(ExtractedIcon::File { path: cached, format, source: source_of(&exe)? });
+        }
+    }
+
+    let (data, source) = match source_of(&exe)? {
+        IconSource::AppImage => (extract_appimage(&exe)?, IconSource::AppImage),
+        IconSource::PeResource => (extract_pe(&exe)?, IconSource::PeResource),
+        _ => match extract_elf(&exe)? {
+            ElfIcon::Image(data) => (data, IconSource::ElfSection),
+            ElfIcon::Name(name) => return theme_or_file(&name, cache_dir, &key),
+        },
+    };
+
+    let format = image_format(&data)
+        .ok_or_else(|| IconError::Unsupported(format!("{}: embedded icon is not PNG, SVG or ICO", exe.display())))?;
+    let path = write_cached(cache_dir, &key, format, &data)?;
+    Ok(ExtractedIcon::File { path, format, source })
+}
+
+/// Classify a binary by its header
+fn source_of(exe: &Path) -> Result<IconSource, IconError> {
+    let mut header = [0u8; 16];
+    let read = File::open(exe)?.read(&mut header)?;
+    let header = &header[..read];
+
+    if header.starts_with(ELF_MAGIC) {
+        // AppImage type 1/2: "AI" + version in the ELF padding bytes
+        if header.len() >= 11 && &header[8..10] == b"AI" && matches!(header[10], 1 | 2) {
+            Ok(IconSource::AppImage)
+        } else {
+            Ok(IconSource::ElfSection)
+        }
+    } else if header.starts_with(b"MZ") {
+        Ok(IconSource::PeResource)
+    } else {
+        Err(IconError::Unsupported(format!("{}: not an ELF, PE or AppImage binary", exe.display())))
+    }
+}
+
+/// Stable cache name: file stem plus a hash of the full path
+fn cache_key(exe: &Path) -> String {
+    // FNV-1a, so binaries with the same name in different directories do not collide
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in exe.as_os_str().to_string_lossy().bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    let stem = exe.file_stem().and_then(|s| s.to_str()).unwrap_or("binary");
+    format!("{}-{:016x}", stem, hash)
+}
+
+fn image_format(data: &[u8]) -> Option<IconFormat> {
+    if data.starts_with(PNG_MAGIC) {
+        return Some(IconFormat::Png);
+    }
+    if data.starts_with(&[0, 0, 1, 0]) {
+        return Some(IconFormat::Ico);
+    }
+    let head = String::from_utf8_lossy(&data[..data.len().min(512)]);
+    if head.contains("<svg") {
+        return Some(IconFormat::Svg);
+    }
+    None
+}
+
+fn write_cached(cache_dir: &Path, key: &str, format: IconFormat, data: &[u8]) -> Result<PathBuf, IconError> {
+    fs::create_dir_all(cache_dir)?;
+    let path = cache_dir.join(format!("{}.{}", key, format.extension()));
+    fs::write(&path, data)?;
+    Ok(path)
+}
+
+/// `Icon=` may be an absolute path; copy it so the cache stays self-contained
+fn theme_or_file(name: &str, cache_dir: &Path, key: &str) -> Result<ExtractedIcon, IconError> {
+    let path = Path::new(name);
+    if path.is_absolute() {
+        let data = fs::read(path)?;
+        let format = path.extension()
+            .and_then(|e| e.to_str())
+            .and_then(IconFormat::from_extension)
+            .or_else(|| image_format(&data))
+            .ok_or_else(|| IconError::Unsupported(format!("{}: unknown icon format", path.display())))?;
+        let path = write_cached(cache_dir, key, format, &data)?;
+        return Ok(ExtractedIcon::File { path, format, source: IconSource::ElfDesktopEntry });
+    }
+    Ok(ExtractedIcon::ThemeName { name: name.to_string(), source: IconSource::ElfDesktopEntry })
+}
+
+fn read_at(file: &mut File, offset: u64, len: u64) -> Result<Vec<u8>, IconError> {
+    if len > MAX_EMBEDDED_ICON_BYTES {
+        return Err(IconError::Unsupported(format!("embedded data of {} bytes exceeds the limit", len)));
+    }
+    let mut buf = vec![0u8; len as usize];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn malformed(what: &str) -> IconError {
+    IconError::Unsupported(format!("malformed {}", what))
+}
+
+// ============================================================================
+// ELF
+// ============================================================================
+
+enum ElfIcon {
+    Image(Vec<u8>),
+    Name(String),
+}
+
+/// Reads integers in the binary's byte order
+#[derive(Clone, Copy)]
+struct Endian {
+    little: bool,
+}
+
+impl Endian {
+    fn u16(self, b: &[u8], at: usize) -> Option<u64> {
+        let raw: [u8; 2] = b.get(at..at + 2)?.try_into().ok()?;
+        Some(if self.little { u16::from_le_bytes(raw) } else { u16::from_be_bytes(raw) } as u64)
+    }
+
+    fn u32(self, b: &[u8], at: usize) -> Option<u64> {
+        let raw: [u8; 4] = b.get(at..at + 4)?.try_into().ok()?;
+        Some(if self.little { u32::from_le_bytes(raw) } else { u32::from_be_bytes(raw) } as u64)
+    }
+
+    fn u64(self, b: &[u8], at: usize) -> Option<u64> {
+        let raw: [u8; 8] = b.get(at..at + 8)?.try_into().ok()?;
+        Some(if self.little { u64::from_le_bytes(raw) } else { u64::from_be_bytes(raw) })
+    }
+}
+
+/// Section header table location of an ELF binary
+struct ElfHeader {
+    wide: bool,
+    endian: Endian,
+    shoff: u64,
+    shentsize: u64,
+    shnum: u64,
+    shstrndx: u64,
+}
+
+impl ElfHeader {
+    fn read(file: &mut File, exe: &Path) -> Result<Self, IconError> {
+        let header = read_at(file, 0, 64)?;
+        let wide = match header[4] {
+            1 => false,
+            2 => true,
+            _ => return Err(malformed("ELF class")),
+        };
+        let e = Endian { little: header[5] == 1 };
+
+        let (shoff, shentsize, shnum, shstrndx) = if wide {
+            (e.u64(&header, 0x28), e.u16(&header, 0x3A), e.u16(&header, 0x3C), e.u16(&header, 0x3E))
+        } else {
+            (e.u32(&header, 0x20), e.u16(&header, 0x2E), e.u16(&header, 0x30), e.u16(&header, 0x32))
+        };
+        match (shoff, shentsize, shnum, shstrndx) {
+            (Some(shoff), Some(shentsize), Some(shnum), Some(shstrndx)) if shentsize > 0 && shstrndx < shnum => {
+                Ok(ElfHeader { wide, endian: e, shoff, shentsize, shnum, shstrndx })
+            }
+            _ => Err(IconError::IconNotFound(format!("{}: no section headers", exe.display()))),
+        }
+    }
+
+    /// First byte past the section header table, where an AppImage runtime ends
+    fn end(&self) -> u64 {
+        self.shoff.saturating_add(self.shentsize * self.shnum)
+    }
+}
+
+fn extract_elf(exe: &Path) -> Result<ElfIcon, IconError> {
+    let mut file = File::open(exe)?;
+    let ElfHeader { wide, endian: e, shoff, shentsize, shnum, shstrndx } = ElfHeader::read(&mut file, exe)?;
+
+    let table = read_at(&mut file, shoff, shentsize * shnum)?;
+    // (name offset, file offset, size) per section
+    let sections: Vec<(u64, u64, u64)> = (0..shnum as usize)
+        .filter_map(|i| {
+            let at = i * shentsize as usize;
+            if wide {
+                Some((e.u32(&table, at)?, e.u64(&table, at + 0x18)?, e.u64(&table, at + 0x20)?))
+            } else {
+                Some((e.u32(&table, at)?, e.u32(&table, at + 0x10)?, e.u32(&table, at + 0x14)?))
+            }
+        })
+        .collect();
+
+    let (_, str_off, str_size) = *sections.get(shstrndx as usize).ok_or_else(|| malformed("ELF string table"))?;
+    let names = read_at(&mut file, str_off, str_size)?;
+    let section = |wanted: &str| {
+        sections.iter().find(|(name, _, _)| {
+            let start = *name as usize;
+            names.get(start..)
+                .and_then(|rest| rest.split(|b| *b == 0).next())
+                .is_some_and(|n| n == wanted.as_bytes())
+        })
+    };
+
+    if let Some(&(_, offset, size)) = section(".icon") {
+        return Ok(ElfIcon::Image(read_at(&mut file, offset, size)?));
+    }
+    if let Some(&(_, offset, size)) = section(".desktop") {
+        let entry = read_at(&mut file, offset, size)?;
+        let entry = String::from_utf8_lossy(&entry);
+        let icon = entry.lines()
+            .filter_map(|l| l.trim().strip_prefix("Icon="))
+            .map(|v| v.trim().trim_end_matches('\0').to_string())
+            .find(|v| !v.is_empty());
+        if let Some(icon) = icon {
+            return Ok(ElfIcon::Name(icon));
+        }
+    }
+
+    Err(IconError::IconNotFound(format!("{}: no .icon or .desktop section", exe.display())))
+}
+
+// ============================================================================
+// PE
+// ============================================================================
+
+struct ResourceSection {
+    virtual_address: u64,
+    data: Vec<u8>,
+}
+
+impl ResourceSection {
+    /// Bytes of a data entry (RVA + size) inside the section
+    fn data_entry(&self, entry: usize) -> Option<&[u8]> {
+        let e = Endian { little: true };
+        let rva = e.u32(&self.data, entry)?;
+        let size = e.u32(&self.data, entry + 4)?;
+        let start = rva.checked_sub(self.virtual_address)? as usize;
+        self.data.get(start..start.checked_add(size as usize)?)
+    }
+
+    /// (id, offset) entries of a directory; the offset keeps its subdirectory bit
+    fn entries(&self, dir: usize) -> Vec<(u32, u64)> {
+        let e = Endian { little: true };
+        let named = e.u16(&self.data, dir + 12).unwrap_or(0) as usize;
+        let ids = e.u16(&self.data, dir + 14).unwrap_or(0) as usize;
+        (0..named + ids)
+            .filter_map(|i| {
+                let at = dir + 16 + i * 8;
+                Some((e.u32(&self.data, at)? as u32, e.u32(&self.data, at + 4)?))
+            })
+            .collect()
+    }
+
+    /// Follow type → name → language down to the first data entry of each name
+    fn resources_of_type(&self, kind: u32) -> Vec<(u32, &[u8])> {
+        const SUBDIR: u64 = 0x8000_0000;
+        let mut found = Vec::new();
+        let Some((_, names)) = self.entries(0).into_iter().find(|(id, off)| *id == kind && off & SUBDIR != 0) else {
+            return found;
+        };
+        for (id, name_dir) in self.entries((names & !SUBDIR) as usize) {
+            if name_dir & SUBDIR == 0 {
+                continue;
+            }
+            let language = self.entries((name_dir & !SUBDIR) as usize).into_iter().find(|(_, off)| off & SUBDIR == 0);
+            if let Some(data) = language.and_then(|(_, off)| self.data_entry(off as usize)) {
+                found.push((id, data));
+            }
+        }
+        found
+    }
+}
+
+fn extract_pe(exe: &Path) -> Result<Vec<u8>, IconError> {
+    let e = Endian { little: true };
+    let mut file = File::open(exe)?;
+    let dos = read_at(&mut file, 0, 64)?;
+    let pe = e.u32(&dos, 0x3C).ok_or_else(|| malformed("DOS header"))?;
+
+    let coff = read_at(&mut file, pe, 24)?;
+    if &coff[..4] != b"PE\0\0" {
+        return Err(malformed("PE signature"));
+    }
+    let sections = e.u16(&coff, 6).unwrap_or(0);
+    let optional_size = e.u16(&coff, 20).unwrap_or(0);
+    let optional = read_at(&mut file, pe + 24, optional_size)?;
+    let (count_at, dirs_at) = match e.u16(&optional, 0) {
+        Some(0x10b) => (92, 96),
+        Some(0x20b) => (108, 112),
+        _ => return Err(malformed("PE optional header")),
+    };
+    if e.u32(&optional, count_at).unwrap_or(0) as usize <= RESOURCE_DIRECTORY_INDEX {
+        return Err(IconError::IconNotFound(format!("{}: no resource directory", exe.display())));
+    }
+    let resource_rva = e.u32(&optional, dirs_at + RESOURCE_DIRECTORY_INDEX * 8).unwrap_or(0);
+
+    let table = read_at(&mut file, pe + 24 + optional_size, sections * 40)?;
+    let mut section = None;
+    for at in (0..sections as usize).map(|i| i * 40) {
+        let field = |offset| e.u32(&table, at + offset).ok_or_else(|| malformed("PE section table"));
+        let (virtual_size, virtual_address, raw_size, raw_offset) = (field(8)?, field(12)?, field(16)?, field(20)?);
+        // RVAs are 32-bit; a section reaching past 4 GiB is corrupt
+        let end = virtual_address.checked_add(virtual_size.max(raw_size))
+            .filter(|end| *end <= u32::MAX as u64)
+            .ok_or_else(|| malformed("PE section table"))?;
+        if resource_rva >= virtual_address && resource_rva < end {
+            section = Some((virtual_address, raw_size, raw_offset));
+            break;
+        }
+    }
+    let (virtual_address, raw_size, raw_offset) = section
+        .ok_or_else(|| IconError::IconNotFound(format!("{}: no resource section", exe.display())))?;
+    let data = read_at(&mut file, raw_offset, raw_size)?;
+    // The directory may start past the section head
+    let skip = (resource_rva - virtual_address) as usize;
+    let resources = ResourceSection {
+        virtual_address: resource_rva,
+        data: data.get(skip..).ok_or_else(|| malformed("resource directory"))?.to_vec(),
+    };
+
+    let group = resources.resources_of_type(RT_GROUP_ICON)
+        .into_iter()
+        .next()
+        .map(|(_, data)| data)
+        .ok_or_else(|| IconError::IconNotFound(format!("{}: no RT_GROUP_ICON resource", exe.display())))?;
+    let icons = resources.resources_of_type(RT_ICON);
+    build_ico(group, &icons).ok_or_else(|| malformed("RT_GROUP_ICON"))
+}
+
+/// GRPICONDIR (14-byte entries naming RT_ICON ids) → ICO file (16-byte entries with offsets)
+fn build_ico(group: &[u8], icons: &[(u32, &[u8])]) -> Option<Vec<u8>> {
+    let e = Endian { little: true };
+    let count = e.u16(group, 4)? as usize;
+    let mut images = Vec::new();
+    for i in 0..count {
+        let entry = group.get(6 + i * 14..6 + i * 14 + 14)?;
+        let id = e.u16(entry, 12)? as u32;
+        if let Some((_, data)) = icons.iter().find(|(icon_id, _)| *icon_id == id) {
+            images.push((&entry[..8], *data));
+        }
+    }
+    if images.is_empty() {
+        return None;
+    }
+
+    let mut ico = vec![0, 0, 1, 0];
+    ico.extend_from_slice(&(images.len() as u16).to_le_bytes());
+    let mut offset = 6 + images.len() * 16;
+    for (dir_entry, data) in &images {
+        ico.extend_from_slice(dir_entry);
+        ico.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        ico.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += data.len();
+    }
+    for (_, data) in &images {
+        ico.extend_from_slice(data);
+    }
+    Some(ico)
+}
+
+// ============================================================================
+// AppImage
+// ============================================================================
+
+const SQUASHFS_MAGIC: &[u8; 4] = b"hsqs";
+const SQUASHFS_ZLIB: u64 = 1;
+const SQUASHFS_METADATA_SIZE: u64 = 8192;
+const SQUASHFS_NO_FRAGMENT: u64 = 0xFFFF_FFFF;
+
+/// Read `.DirIcon` (and the file it links to) straight out of the embedded squashfs image.
+/// The AppImage is never executed: its runtime is untrusted code.
+fn extract_appimage(exe: &Path) -> Result<Vec<u8>, IconError> {
+    let mut file = File::open(exe)?;
+    let offset = ElfHeader::read(&mut file, exe)?.end();
+    let mut image = SquashFs::open(file, offset)?;
+
+    let mut path = PathBuf::from(".DirIcon");
+    // .DirIcon is usually a symlink to the real icon; follow a bounded chain
+    for _ in 0..4 {
+        match image.lookup(&path)? {
+            Some(Inode::File { blocks_start, size, fragment, blocks }) => {
+                if size > MAX_EMBEDDED_ICON_BYTES {
+                    return Err(IconError::Unsupported(format!("{}: .DirIcon too large", exe.display())));
+                }
+                return image.read_file(blocks_start, size, fragment, &blocks);
+            }
+            Some(Inode::Symlink(target)) => {
+                let target = Path::new(&target);
+                if target.is_absolute() {
+                    break;
+                }
+                path = path.parent().unwrap_or(Path::new("")).join(target);
+            }
+            _ => break,
+        }
+    }
+    Err(IconError::IconNotFound(format!("{}: no .DirIcon in AppImage", exe.display())))
+}
+
+enum Inode {
+    Dir { start: u64, offset: u64, size: u64 },
+    /// Data blocks from `blocks_start`, then an optional (fragment index, offset) tail
+    File { blocks_start: u64, size: u64, fragment: Option<(u64, u64)>, blocks: Vec<u64> },
+    Symlink(String),
+    Other,
+}
+
+/// Read-only squashfs 4.0 reader, just enough to walk paths and read small files
+struct SquashFs {
+    file: File,
+    /// Image start inside the AppImage; every table position is relative to it
+    base: u64,
+    block_size: u64,
+    compression: u64,
+    root: u64,
+    inode_table: u64,
+    directory_table: u64,
+    fragment_table: u64,
+}
+
+impl SquashFs {
+    fn open(mut file: File, base: u64) -> Result<Self, IconError> {
+        let e = Endian { little: true };
+        let sb = read_at(&mut file, base, 96)?;
+        if &sb[..4] != SQUASHFS_MAGIC || e.u16(&sb, 28) != Some(4) {
+            return Err(malformed("AppImage squashfs superblock"));
+        }
+        let field = |at| e.u64(&sb, at).unwrap_or(0);
+        Ok(SquashFs {
+            file,
+            base,
+            block_size: e.u32(&sb, 12).unwrap_or(0).clamp(4096, 1 << 20),
+            compression: e.u16(&sb, 20).unwrap_or(0),
+            root: field(32),
+            inode_table: field(64),
+            directory_table: field(72),
+            fragment_table: field(80),
+        })
+    }
+
+    fn decompress(&self, raw: Vec<u8>, limit: u64) -> Result<Vec<u8>, IconError> {
+        if self.compression != SQUASHFS_ZLIB {
+            return Err(IconError::Unsupported(format!(
+                "AppImage squashfs compression {} is not supported (only zlib)", self.compression
+            )));
+        }
+        let mut data = Vec::new();
+        flate2::read::ZlibDecoder::new(&raw[..]).take(limit + 1).read_to_end(&mut data)
+            .map_err(|_| malformed("AppImage squashfs block"))?;
+        if data.len() as u64 > limit {
+            return Err(malformed("AppImage squashfs block"));
+        }
+        Ok(data)
+    }
+
+    /// One metadata block at `pos` (image-relative): its contents and on-disk length
+    fn metadata_block(&mut self, pos: u64) -> Result<(Vec<u8>, u64), IconError> {
+        let bad = || malformed("AppImage squashfs metadata");
+        let at = self.base.checked_add(pos).ok_or_else(bad)?;
+        let header = read_at(&mut self.file, at, 2)?;
+        let word = u16::from_le_bytes([header[0], header[1]]) as u64;
+        let len = word & 0x7FFF;
+        if len == 0 || len > SQUASHFS_METADATA_SIZE {
+            return Err(bad());
+        }
+        let raw = read_at(&mut self.file, at.checked_add(2).ok_or_else(bad)?, len)?;
+        let data = if word & 0x8000 != 0 { raw } else { self.decompress(raw, SQUASHFS_METADATA_SIZE)? };
+        Ok((data, 2 + len))
+    }
+
+    /// `len` bytes of metadata starting `offset` bytes into the block at `pos`
+    fn metadata(&mut self, mut pos: u64, offset: u64, len: u64) -> Result<Vec<u8>, IconError> {
+        if len > MAX_EMBEDDED_ICON_BYTES {
+            return Err(malformed("AppImage squashfs metadata"));
+        }
+        let mut out = Vec::with_capacity(len as usize);
+        let mut skip = offset as usize;
+        while (out.len() as u64) < len {
+            let (data, disk_len) = self.metadata_block(pos)?;
+            let chunk = data.get(skip..).ok_or_else(|| malformed("AppImage squashfs metadata"))?;
+            let take = chunk.len().min(len as usize - out.len());
+            out.extend_from_slice(&chunk[..take]);
+            pos = pos.checked_add(disk_len).ok_or_else(|| malformed("AppImage squashfs metadata"))?;
+            skip = 0;
+        }
+        Ok(out)
+    }
+
+    fn inode(&mut self, reference: u64) -> Result<Inode, IconError> {
+        let e = Endian { little: true };
+        let bad = || malformed("AppImage squashfs inode");
+        let pos = self.inode_table.checked_add(reference >> 16).ok_or_else(bad)?;
+        let offset = reference & 0xFFFF;
+        let kind = e.u16(&self.metadata(pos, offset, 16)?, 0).ok_or_else(bad)?;
+
+        Ok(match kind {
+            1 => {
+                let raw = self.metadata(pos, offset, 32)?;
+                Inode::Dir {
+                    start: e.u32(&raw, 16).ok_or_else(bad)?,
+                    size: e.u16(&raw, 24).ok_or_else(bad)?,
+                    offset: e.u16(&raw, 26).ok_or_else(bad)?,
+                }
+            }
+            8 => {
+                let raw = self.metadata(pos, offset, 40)?;
+                Inode::Dir {
+                    size: e.u32(&raw, 20).ok_or_else(bad)?,
+                    start: e.u32(&raw, 24).ok_or_else(bad)?,
+                    offset: e.u16(&raw, 34).ok_or_else(bad)?,
+                }
+            }
+            2 | 9 => {
+                let (head, blocks_start, fragment, frag_offset, size) = if kind == 2 {
+                    let raw = self.metadata(pos, offset, 32)?;
+                    (32, e.u32(&raw, 16), e.u32(&raw, 20), e.u32(&raw, 24), e.u32(&raw, 28))
+                } else {
+                    let raw = self.metadata(pos, offset, 56)?;
+                    (56, e.u64(&raw, 16), e.u32(&raw, 44), e.u32(&raw, 48), e.u64(&raw, 24))
+                };
+                let (blocks_start, fragment, frag_offset, size) = (
+                    blocks_start.ok_or_else(bad)?,
+                    fragment.ok_or_else(bad)?,
+                    frag_offset.ok_or_else(bad)?,
+                    size.ok_or_else(bad)?,
+                );
+                if size > MAX_EMBEDDED_ICON_BYTES {
+                    return Ok(Inode::File { blocks_start, size, fragment: None, blocks: Vec::new() });
+                }
+                let (count, fragment) = if fragment == SQUASHFS_NO_FRAGMENT {
+                    (size.div_ceil(self.block_size), None)
+                } else {
+                    (size / self.block_size, Some((fragment, frag_offset)))
+                };
+                let raw = self.metadata(pos, offset, head + count * 4)?;
+                let blocks = (0..count).map(|i| e.u32(&raw, (head + i * 4) as usize).ok_or_else(bad)).collect::<Result<_, _>>()?;
+                Inode::File { blocks_start, size, fragment, blocks }
+            }
+            3 | 10 => {
+                let raw = self.metadata(pos, offset, 24)?;
+                let len = e.u32(&raw, 20).ok_or_else(bad)?.min(4096);
+                let raw = self.metadata(pos, offset, 24 + len)?;
+                Inode::Symlink(String::from_utf8_lossy(&raw[24..]).into_owned())
+            }
+            _ => Inode::Other,
+        })
+    }
+
+    /// Inode reference of `name` in a directory listing
+    fn find_entry(&mut self, start: u64, offset: u64, size: u64, name: &str) -> Result<Option<u64>, IconError> {
+        let e = Endian { little: true };
+        // Listing sizes count the implicit "." and ".." entries
+        let Some(len) = size.checked_sub(3).filter(|len| *len > 0) else {
+            return Ok(None);
+        };
+        let bad = || malformed("AppImage squashfs directory");
+        let listing = self.metadata(self.directory_table.checked_add(start).ok_or_else(bad)?, offset, len)?;
+
+        let mut at = 0;
+        while at + 12 <= listing.len() {
+            let count = e.u32(&listing, at).ok_or_else(bad)? + 1;
+            let block = e.u32(&listing, at + 4).ok_or_else(bad)?;
+            at += 12;
+            for _ in 0..count.min(256) {
+                let entry_offset = e.u16(&listing, at).ok_or_else(bad)?;
+                let name_len = e.u16(&listing, at + 6).ok_or_else(bad)? as usize + 1;
+                let entry_name = listing.get(at + 8..at + 8 + name_len).ok_or_else(bad)?;
+                at += 8 + name_len;
+                if entry_name == name.as_bytes() {
+                    return Ok(Some((block << 16) | entry_offset));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Walk `path` from the root; `..` and absolute components are not followed
+    fn lookup(&mut self, path: &Path) -> Result<Option<Inode>, IconError> {
+        let mut inode = self.inode(self.root)?;
+        for component in path.components() {
+            let std::path::Component::Normal(name) = component else {
+                return Ok(None);
+            };
+            let Inode::Dir { start, offset, size } = inode else {
+                return Ok(None);
+            };
+            let Some(reference) = self.find_entry(start, offset, size, &name.to_string_lossy())? else {
+                return Ok(None);
+            };
+            inode = self.inode(reference)?;
+        }
+        Ok(Some(inode))
+    }
+
+    /// One data block: the top size bit marks it stored uncompressed, size 0 is a sparse block
+    fn data_block(&mut self, pos: u64, word: u64, limit: u64) -> Result<Vec<u8>, IconError> {
+        let len = word & 0x00FF_FFFF;
+        if len == 0 {
+            return Ok(vec![0; limit as usize]);
+        }
+        if len > self.block_size {
+            return Err(malformed("AppImage squashfs data block"));
+        }
+        let at = self.base.checked_add(pos).ok_or_else(|| malformed("AppImage squashfs data block"))?;
+        let raw = read_at(&mut self.file, at, len)?;
+        if word & 0x0100_0000 != 0 { Ok(raw) } else { self.decompress(raw, self.block_size) }
+    }
+
+    fn read_file(&mut self, blocks_start: u64, size: u64, fragment: Option<(u64, u64)>, blocks: &[u64]) -> Result<Vec<u8>, IconError> {
+        let bad = || malformed("AppImage squashfs file");
+        let mut data = Vec::with_capacity(size as usize);
+        let mut pos = blocks_start;
+        for &word in blocks {
+            let remaining = (size - data.len() as u64).min(self.block_size);
+            let block = self.data_block(pos, word, remaining)?;
+            data.extend_from_slice(block.get(..remaining as usize).ok_or_else(bad)?);
+            pos = pos.checked_add(word & 0x00FF_FFFF).ok_or_else(bad)?;
+        }
+
+        if let Some((index, offset)) = fragment {
+            let e = Endian { little: true };
+            let at = self.base.checked_add(self.fragment_table)
+                .and_then(|table| table.checked_add((index / 512) * 8))
+                .ok_or_else(bad)?;
+            let table = read_at(&mut self.file, at, 8)?;
+            let block = e.u64(&table, 0).ok_or_else(bad)?;
+            let entry = self.metadata(block, (index % 512) * 16, 16)?;
+            let start = e.u64(&entry, 0).ok_or_else(bad)?;
+            let word = e.u32(&entry, 8).ok_or_else(bad)?;
+            let tail = self.data_block(start, word, self.block_size)?;
+            let remaining = (size - data.len() as u64) as usize;
+            let offset = offset as usize;
+            data.extend_from_slice(tail.get(offset..offset + remaining).ok_or_else(bad)?);
+        }
+
+        if data.len() as u64 != size {
+            return Err(bad());
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ELF64 LE with a section string table plus the given sections
+    fn elf_bytes(sections: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut names = vec![0u8];
+        let mut name_offsets = Vec::new();
+        for (name, _) in sections.iter().copied().chain([(".shstrtab", &b""[..])]) {
+            name_offsets.push(names.len() as u32);
+            names.extend_from_slice(name.as_bytes());
+            names.push(0);
+        }
+
+        let mut body = vec![0u8; 64];
+        let mut placed = Vec::new();
+        for (_, data) in sections {
+            placed.push((body.len() as u64, data.len() as u64));
+            body.extend_from_slice(data);
+        }
+        placed.push((body.len() as u64, names.len() as u64));
+        body.extend_from_slice(&names);
+
+        let shoff = body.len() as u64;
+        body.extend_from_slice(&[0u8; 64]); // null section
+        for (i, (offset, size)) in placed.iter().enumerate() {
+            let mut sh = [0u8; 64];
+            sh[0..4].copy_from_slice(&name_offsets[i].to_le_bytes());
+            sh[0x18..0x20].copy_from_slice(&offset.to_le_bytes());
+            sh[0x20..0x28].copy_from_slice(&size.to_le_bytes());
+            body.extend_from_slice(&sh);
+        }
+
+        body[..4].copy_from_slice(ELF_MAGIC);
+        body[4] = 2;
+        body[5] = 1;
+        body[0x28..0x30].copy_from_slice(&shoff.to_le_bytes());
+        body[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+        body[0x3C..0x3E].copy_from_slice(&(placed.len() as u16 + 1).to_le_bytes());
+        body[0x3E..0x40].copy_from_slice(&(placed.len() as u16).to_le_bytes());
+        body
+    }
+
+    /// PE32 with one .rsrc section holding a single-image icon group
+    fn pe_bytes(image: &[u8]) -> Vec<u8> {
+        const RVA: u32 = 0x1000;
+        const RAW: u32 = 0x200;
+        fn dir(entries: &[(u32, u32)]) -> Vec<u8> {
+            let mut d = vec![0u8; 16];
+            d[14..16].copy_from_slice(&(entries.len() as u16).to_le_bytes());
+            for (id, off) in entries {
+                d.extend_from_slice(&id.to_le_bytes());
+                d.extend_from_slice(&off.to_le_bytes());
+            }
+            d
+        }
+        let sub = 0x8000_0000u32;
+
+        // Layout: root, type dirs, name dirs, language dirs, data entries, payloads
+        let mut group = vec![0, 0, 1, 0, 1, 0];
+        group.extend_from_slice(&[16, 16, 0, 0, 1, 0, 32, 0]);
+        group.extend_from_slice(&(image.len() as u32).to_le_bytes());
+        group.extend_from_slice(&7u16.to_le_bytes());
+
+        let root_len = 16 + 2 * 8;
+        let type_len = 16 + 8;
+        let (icon_type, group_type) = (root_len, root_len + type_len);
+        let (icon_lang, group_lang) = (group_type + type_len, group_type + 2 * type_len);
+        let (icon_data, group_data) = (group_type + 3 * type_len, group_type + 3 * type_len + 16);
+        let payload = group_data + 16;
+
+        let mut rsrc = dir(&[(RT_ICON, sub | icon_type as u32), (RT_GROUP_ICON, sub | group_type as u32)]);
+        rsrc.extend(dir(&[(7, sub | icon_lang as u32)]));
+        rsrc.extend(dir(&[(1, sub | group_lang as u32)]));
+        rsrc.extend(dir(&[(0x409, icon_data as u32)]));
+        rsrc.extend(dir(&[(0x409, group_data as u32)]));
+        for (offset, len) in [(payload, image.len()), (payload + image.len(), group.len())] {
+            rsrc.extend_from_slice(&(RVA + offset as u32).to_le_bytes());
+            rsrc.extend_from_slice(&(len as u32).to_le_bytes());
+            rsrc.extend_from_slice(&[0u8; 8]);
+        }
+        rsrc.extend_from_slice(image);
+        rsrc.extend_from_slice(&group);
+
+        let mut pe = vec![0u8; RAW as usize];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3C..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        pe[0x40..0x44].copy_from_slice(b"PE\0\0");
+        pe[0x46..0x48].copy_from_slice(&1u16.to_le_bytes());
+        pe[0x54..0x56].copy_from_slice(&224u16.to_le_bytes());
+        let opt = 0x58;
+        pe[opt..opt + 2].copy_from_slice(&0x10bu16.to_le_bytes());
+        pe[opt + 92..opt + 96].copy_from_slice(&16u32.to_le_bytes());
+        pe[opt + 112..opt + 116].copy_from_slice(&RVA.to_le_bytes());
+        pe[opt + 116..opt + 120].copy_from_slice(&(rsrc.len() as u32).to_le_bytes());
+        let sec = opt + 224;
+        pe[sec..sec + 5].copy_from_slice(b".rsrc");
+        pe[sec + 8..sec + 12].copy_from_slice(&(rsrc.len() as u32).to_le_bytes());
+        pe[sec + 12..sec + 16].copy_from_slice(&RVA.to_le_bytes());
+        pe[sec + 16..sec + 20].copy_from_slice(&(rsrc.len() as u32).to_le_bytes());
+        pe[sec + 20..sec + 24].copy_from_slice(&RAW.to_le_bytes());
+        pe.extend_from_slice(&rsrc);
+        pe
+    }
+
+    /// AppImage: ELF runtime followed by a squashfs image whose root holds
+    /// `.DirIcon -> app.png` and a zlib-compressed `app.png`
+    fn appimage_bytes(icon: &[u8]) -> Vec<u8> {
+        use std::io::Write;
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(icon).unwrap();
+        let block = encoder.finish().unwrap();
+
+        let uncompressed_metadata = |data: &[u8]| [&(0x8000u16 | data.len() as u16).to_le_bytes()[..], data].concat();
+        let inode_header = |kind: u16, number: u32| {
+            let mut h = vec![0u8; 16];
+            h[0..2].copy_from_slice(&kind.to_le_bytes());
+            h[12..16].copy_from_slice(&number.to_le_bytes());
+            h
+        };
+
+        let mut listing = Vec::new();
+        listing.extend_from_slice(&1u32.to_le_bytes()); // two entries
+        listing.extend_from_slice(&0u32.to_le_bytes());
+        listing.extend_from_slice(&1u32.to_le_bytes());
+        for (offset, kind, name) in [(32u16, 3u16, ".DirIcon"), (63, 2, "app.png")] {
+            listing.extend_from_slice(&offset.to_le_bytes());
+            listing.extend_from_slice(&0u16.to_le_bytes());
+            listing.extend_from_slice(&kind.to_le_bytes());
+            listing.extend_from_slice(&(name.len() as u16 - 1).to_le_bytes());
+            listing.extend_from_slice(name.as_bytes());
+        }
+
+        let mut inodes = inode_header(1, 1);
+        inodes.extend_from_slice(&0u32.to_le_bytes()); // directory block
+        inodes.extend_from_slice(&2u32.to_le_bytes());
+        inodes.extend_from_slice(&(listing.len() as u16 + 3).to_le_bytes());
+        inodes.extend_from_slice(&0u16.to_le_bytes());
+        inodes.extend_from_slice(&1u32.to_le_bytes());
+        inodes.extend(inode_header(3, 2));
+        inodes.extend_from_slice(&1u32.to_le_bytes());
+        inodes.extend_from_slice(&7u32.to_le_bytes());
+        inodes.extend_from_slice(b"app.png");
+        inodes.extend(inode_header(2, 3));
+        inodes.extend_from_slice(&96u32.to_le_bytes()); // data right after the superblock
+        inodes.extend_from_slice(&0xFFFF_FFFFu32.to_le_bytes());
+        inodes.extend_from_slice(&0u32.to_le_bytes());
+        inodes.extend_from_slice(&(icon.len() as u32).to_le_bytes());
+        inodes.extend_from_slice(&(block.len() as u32).to_le_bytes());
+
+        let inode_table = 96 + block.len() as u64;
+        let inodes = uncompressed_metadata(&inodes);
+        let directory_table = inode_table + inodes.len() as u64;
+
+        let mut sb = vec![0u8; 96];
+        sb[..4].copy_from_slice(SQUASHFS_MAGIC);
+        sb[12..16].copy_from_slice(&131072u32.to_le_bytes());
+        sb[20..22].copy_from_slice(&1u16.to_le_bytes());
+        sb[28..30].copy_from_slice(&4u16.to_le_bytes());
+        sb[64..72].copy_from_slice(&inode_table.to_le_bytes());
+        sb[72..80].copy_from_slice(&directory_table.to_le_bytes());
+
+        let mut image = elf_bytes(&[(".text", b"\x90")]);
+        image[8..11].copy_from_slice(b"AI\x02");
+        image.extend(sb);
+        image.extend(block);
+        image.extend(inodes);
+        image.extend(uncompressed_metadata(&listing));
+        image
+    }
+
+    #[test]
+    fn test_icon_extraction() {
+        let dir = std::env::temp_dir().join(format!("wsdg-icon-extract-{}", std::process::id()));
+        let cache = dir.join("cache");
+        fs::create_dir_all(&dir).unwrap();
+        let png = [&PNG_MAGIC[..], b"pixels"].concat();
+
+        let elf = dir.join("viewer");
+        fs::write(&elf, elf_bytes(&[(".text", b"\x90\x90"), (".icon", &png)])).unwrap();
+        let ExtractedIcon::File { path, format, source } = extract_icon(&elf, &cache).unwrap() else {
+            panic!("expected an image");
+        };
+        assert_eq!((format, source), (IconFormat::Png, IconSource::ElfSection));
+        assert_eq!(fs::read(&path).unwrap(), png);
+        // Second call is served from the cache
+        assert!(matches!(extract_icon(&elf, &cache).unwrap(), ExtractedIcon::File { path: p, .. } if p == path));
+
+        let desktop = dir.join("editor");
+        fs::write(&desktop, elf_bytes(&[(".desktop", b"[Desktop Entry]\nName=Editor\nIcon=accessories-text-editor\n")])).unwrap();
+        assert_eq!(
+            extract_icon(&desktop, &cache).unwrap(),
+            ExtractedIcon::ThemeName { name: "accessories-text-editor".into(), source: IconSource::ElfDesktopEntry }
+        );
+
+        let bare = dir.join("bare");
+        fs::write(&bare, elf_bytes(&[(".text", b"\x90")])).unwrap();
+        assert!(matches!(extract_icon(&bare, &cache), Err(IconError::IconNotFound(_))));
+
+        let exe = dir.join("setup.exe");
+        fs::write(&exe, pe_bytes(&png)).unwrap();
+        let ExtractedIcon::File { path, format, source } = extract_icon(&exe, &cache).unwrap() else {
+            panic!("expected an image");
+        };
+        assert_eq!((format, source), (IconFormat::Ico, IconSource::PeResource));
+        let ico = fs::read(&path).unwrap();
+        assert_eq!(&ico[..6], &[0, 0, 1, 0, 1, 0]);
+        assert_eq!(&ico[6..8], &[16, 16]);
+        assert_eq!(&ico[22..], &png[..]);
+
+        // .DirIcon symlink resolved inside the squashfs image
+        let appimage = dir.join("Tool.AppImage");
+        fs::write(&appimage, appimage_bytes(&png)).unwrap();
+        let ExtractedIcon::File { path, format, source } = extract_icon(&appimage, &cache).unwrap() else {
+            panic!("expected an image");
+        };
+        assert_eq!((format, source), (IconFormat::Png, IconSource::AppImage));
+        assert_eq!(fs::read(&path).unwrap(), png);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_corrupt_offsets_are_rejected() {
+        let dir = std::env::temp_dir().join(format!("wsdg-icon-offsets-{}", std::process::id()));
+        let cache = dir.join("cache");
+        fs::create_dir_all(&dir).unwrap();
+        let png = [&PNG_MAGIC[..], b"pixels"].concat();
+
+        // .rsrc at RVA 0x1000 claiming 4 GiB of virtual size
+        let mut pe = pe_bytes(&png);
+        let sec = 0x58 + 224;
+        pe[sec + 8..sec + 12].copy_from_slice(&u32::MAX.to_le_bytes());
+        let exe = dir.join("huge.exe");
+        fs::write(&exe, pe).unwrap();
+        assert!(matches!(extract_icon(&exe, &cache), Err(IconError::Unsupported(_))));
+
+        // Inode table position at the end of the u64 range
+        let mut appimage = appimage_bytes(&png);
+        let sb = appimage.windows(4).position(|w| w == SQUASHFS_MAGIC).unwrap();
+        appimage[sb + 32..sb + 40].copy_from_slice(&(1u64 << 16).to_le_bytes());
+        appimage[sb + 64..sb + 72].copy_from_slice(&u64::MAX.to_le_bytes());
+        let image = dir.join("Broken.AppImage");
+        fs::write(&image, appimage).unwrap();
+        assert!(matches!(extract_icon(&image, &cache), Err(IconError::Unsupported(_))));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,145 @@
+// WSDG Kiosk - Read-only / locked configuration mode
+// Enabled system-wide by /etc/wsdg/kiosk.conf (`vendor_dir = <dir>`, `enabled = false`
+// to turn it off) or per session by WSDG_KIOSK=<dir> in the WSDG environment; a user
+// variable can enable kiosk mode but never disable a system-wide one.
+// Everything is read from the vendor directory:
+//   <vendor>/wasma.in.conf, settings.conf, env.path,
+//   <vendor>/mime-associations.list, scheme-handlers.list,
+//   <vendor>/manifests/*.manifest
+// and runtime mutation (settings writes, handler registration, resources beyond
+// manifest bounds, configuration import) fails with PolicyDenied.
+// Part of WASMA (Windows Assignment System Monitoring Architecture)
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::wsdg_env::WsdgEnv;
+
+/// Session variable naming the vendor directory ("1" for the default one)
+pub const KIOSK_ENV_VAR: &str = "WSDG_KIOSK";
+/// System-wide kiosk switch
+pub const KIOSK_CONFIG: &str = "/etc/wsdg/kiosk.conf";
+/// Vendor directory used when none is configured
+pub const DEFAULT_VENDOR_DIR: &str = "/usr/share/wsdg/vendor";
+
+/// A mutation refused because kiosk mode is active
+#[derive(Debug, Clone, Error, PartialEq)]
+#[error("Denied by kiosk policy: {0}")]
+pub struct PolicyDenied(pub String);
+
+/// Active kiosk mode and the vendor directory it reads from
+#[derive(Debug, Clone, PartialEq)]
+pub struct KioskPolicy {
+    vendor_dir: PathBuf,
+}
+
+impl KioskPolicy {
+    pub fn new(vendor_dir: impl Into<PathBuf>) -> Self {
+        Self { vendor_dir: vendor_dir.into() }
+    }
+
+    /// Kiosk mode for `env`: the system-wide config first, then WSDG_KIOSK
+    pub fn detect(env: &WsdgEnv) -> Option<Self> {
+        Self::from_config(Path::new(KIOSK_CONFIG)).or_else(|| {
+            match env.get(KIOSK_ENV_VAR).map(|v| v.trim()) {
+                None | Some("") | Some("0") => None,
+                Some("1") => Some(Self::new(DEFAULT_VENDOR_DIR)),
+                Some(dir) => Some(Self::new(dir)),
+            }
+        })
+    }
+
+    /// `vendor_dir = <dir>` / `enabled = false`; a missing file means no kiosk mode
+    pub fn from_config(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        let mut vendor_dir = PathBuf::from(DEFAULT_VENDOR_DIR);
+
+        for line in content.lines() {
+            let line = line.split("*//").next().unwrap_or(line).trim();
+            let Some((key, value)) = line.split_once('=') else { continue };
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "vendor_dir" => vendor_dir = PathBuf::from(value),
+                "enabled" if value == "false" => return None,
+                _ => {}
+            }
+        }
+
+        Some(Self::new(vendor_dir))
+    }
+
+    pub fn vendor_dir(&self) -> &Path {
+        &self.vendor_dir
+    }
+
+    pub fn wasma_config(&self) -> PathBuf {
+        self.vendor_dir.join("wasma.in.conf")
+    }
+
+    pub fn settings_path(&self) -> PathBuf {
+        self.vendor_dir.join("settings.conf")
+    }
+
+    pub fn env_path(&self) -> PathBuf {
+        self.vendor_dir.join("env.path")
+    }
+
+    pub fn manifest_dir(&self) -> PathBuf {
+        self.vendor_dir.join("manifests")
+    }
+
+    /// Vendor copy of a handler store (`mime-associations.list`, `scheme-handlers.list`)
+    pub fn handler_store(&self, file_name: &str) -> PathBuf {
+        self.vendor_dir.join(file_name)
+    }
+
+    /// Only manifests shipped in the vendor manifest directory may be loaded
+    pub fn check_manifest(&self, manifest_path: &Path) -> Result<(), PolicyDenied> {
+        let dir = fs::canonicalize(self.manifest_dir()).unwrap_or_else(|_| self.manifest_dir());
+        let resolved = fs::canonicalize(manifest_path).unwrap_or_else(|_| manifest_path.to_path_buf());
+        if resolved.starts_with(&dir) {
+            Ok(())
+        } else {
+            Err(self.deny(format!("manifest {} is outside {}", manifest_path.display(), dir.display())))
+        }
+    }
+
+    pub fn deny(&self, action: impl Into<String>) -> PolicyDenied {
+        PolicyDenied(action.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wsdg_env::WsdgEnvBuilder;
+
+    #[test]
+    fn test_kiosk_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let vendor = dir.path().join("vendor");
+        fs::create_dir_all(vendor.join("manifests")).unwrap();
+        fs::write(vendor.join("manifests/kiosk.manifest"), "name = Kiosk\n").unwrap();
+        fs::write(dir.path().join("user.manifest"), "name = User\n").unwrap();
+
+        let config = dir.path().join("kiosk.conf");
+        fs::write(&config, format!("*// kiosk lockdown\nvendor_dir = \"{}\"\n", vendor.display())).unwrap();
+        let policy = KioskPolicy::from_config(&config).unwrap();
+        assert_eq!(policy.settings_path(), vendor.join("settings.conf"));
+        assert!(policy.check_manifest(&vendor.join("manifests/kiosk.manifest")).is_ok());
+        assert!(policy.check_manifest(&vendor.join("manifests/../../user.manifest")).is_err());
+        assert!(policy.check_manifest(&dir.path().join("user.manifest")).is_err());
+
+        fs::write(&config, "enabled = false\n").unwrap();
+        assert_eq!(KioskPolicy::from_config(&config), None);
+        assert_eq!(KioskPolicy::from_config(&dir.path().join("missing.conf")), None);
+
+        if !Path::new(KIOSK_CONFIG).exists() {
+            let env = WsdgEnvBuilder::new().var(KIOSK_ENV_VAR, vendor.to_string_lossy()).build();
+            assert_eq!(KioskPolicy::detect(&env), Some(KioskPolicy::new(&vendor)));
+            let env = WsdgEnvBuilder::new().var(KIOSK_ENV_VAR, "0").build();
+            assert_eq!(KioskPolicy::detect(&env), None);
+        }
+    }
+}
@@ -0,0 +1,323 @@
+// WSDG MIME Archive Inspection
+// Classifies zip and tar containers by their member names (feature "archive-inspection")
+// Only directory data is read; nothing is decompressed except a stored `mimetype` member
+// Part of WASMA (Windows Assignment System Monitoring Architecture)
+
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::wsdg_mime_array::MimeError;
+
+/// Members listed at most; larger archives are classified from the first ones
+pub const MAX_ARCHIVE_MEMBERS: usize = 4096;
+
+const ZIP_EOCD_SIG: u32 = 0x0605_4b50;
+const ZIP_CENTRAL_SIG: u32 = 0x0201_4b50;
+const ZIP_LOCAL_SIG: u32 = 0x0403_4b50;
+/// End-of-central-directory record plus the longest possible comment
+const ZIP_EOCD_SEARCH: u64 = 22 + 0xFFFF;
+const TAR_BLOCK: usize = 512;
+/// Longest `mimetype` member read (EPUB/ODF keep it stored and short)
+const MAX_MIMETYPE_LEN: u32 = 256;
+/// Types a `mimetype` member may claim; anything else is ignored, so a crafted
+/// zip cannot pass itself off as e.g. text/plain or a script
+const MIMETYPE_MEMBER_TYPES: &[&str] = &["application/epub+zip"];
+const MIMETYPE_MEMBER_PREFIXES: &[&str] = &["application/vnd.oasis.opendocument."];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+}
+
+/// Member names of a container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveListing {
+    pub kind: ArchiveKind,
+    pub members: Vec<String>,
+    /// Content of a stored `mimetype` member that is the first in the zip (EPUB, OpenDocument)
+    pub mimetype: Option<String>,
+}
+
+impl ArchiveListing {
+    fn has(&self, name: &str) -> bool {
+        self.members.iter().any(|m| m == name)
+    }
+
+    fn has_prefix(&self, prefix: &str) -> bool {
+        self.members.iter().any(|m| m.starts_with(prefix))
+    }
+}
+
+/// MIME type of the container's contents, or None for a plain archive
+pub fn inspect(path: &Path) -> Option<String> {
+    classify(&list_members(path).ok()?)
+}
+
+/// Read the member names of a zip or uncompressed tar file
+pub fn list_members(path: &Path) -> Result<ArchiveListing, MimeError> {
+    let mut file = fs::File::open(path)?;
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    if u32::from_le_bytes(magic) == ZIP_LOCAL_SIG {
+        list_zip(&mut file)
+    } else {
+        list_tar(&mut file)
+    }
+}
+
+/// Known layouts, checked from most to least specific
+pub fn classify(listing: &ArchiveListing) -> Option<String> {
+    if let Some(mime) = &listing.mimetype {
+        if MIMETYPE_MEMBER_TYPES.contains(&mime.as_str())
+            || MIMETYPE_MEMBER_PREFIXES.iter().any(|prefix| mime.starts_with(prefix))
+        {
+            return Some(mime.clone());
+        }
+    }
+
+    let mime = match listing.kind {
+        ArchiveKind::Zip if listing.has("AndroidManifest.xml") && listing.has("classes.dex") => {
+            "application/vnd.android.package-archive"
+        }
+        ArchiveKind::Zip if listing.has("[Content_Types].xml") && listing.has_prefix("word/") => {
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+        }
+        ArchiveKind::Zip if listing.has("[Content_Types].xml") && listing.has_prefix("xl/") => {
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+        }
+        ArchiveKind::Zip if listing.has("[Content_Types].xml") && listing.has_prefix("ppt/") => {
+            "application/vnd.openxmlformats-officedocument.presentationml.presentation"
+        }
+        ArchiveKind::Zip if listing.has("META-INF/MANIFEST.MF") => "application/java-archive",
+        ArchiveKind::Tar if listing.has("oci-layout") && listing.has("index.json") => {
+            "application/vnd.oci.image.layout.v1+tar"
+        }
+        _ => return None,
+    };
+    Some(mime.to_string())
+}
+
+fn read_u16(buf: &[u8], at: usize) -> usize {
+    u16::from_le_bytes([buf[at], buf[at + 1]]) as usize
+}
+
+fn read_u32(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+}
+
+fn corrupt(reason: &str) -> MimeError {
+    MimeError::MagicBytesError(format!("archive: {}", reason))
+}
+
+fn list_zip(file: &mut fs::File) -> Result<ArchiveListing, MimeError> {
+    let len = file.seek(SeekFrom::End(0))?;
+    let tail_start = len.saturating_sub(ZIP_EOCD_SEARCH);
+    file.seek(SeekFrom::Start(tail_start))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+
+    let eocd = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&i| read_u32(&tail, i) == ZIP_EOCD_SIG)
+        .ok_or_else(|| corrupt("zip end of central directory not found"))?;
+    let entries = read_u16(&tail, eocd + 10).min(MAX_ARCHIVE_MEMBERS);
+    let dir_size = read_u32(&tail, eocd + 12) as usize;
+    let dir_offset = read_u32(&tail, eocd + 16) as u64;
+    if dir_offset + dir_size as u64 > len {
+        return Err(corrupt("zip central directory out of bounds"));
+    }
+
+    let mut dir = vec![0u8; dir_size];
+    file.seek(SeekFrom::Start(dir_offset))?;
+    file.read_exact(&mut dir)?;
+
+    let mut listing = ArchiveListing { kind: ArchiveKind::Zip, members: Vec::new(), mimetype: None };
+    let mut mimetype_at = None;
+    let mut pos = 0;
+    for _ in 0..entries {
+        if pos + 46 > dir.len() || read_u32(&dir, pos) != ZIP_CENTRAL_SIG {
+            break;
+        }
+        let method = read_u16(&dir, pos + 10);
+        let size = read_u32(&dir, pos + 20);
+        let name_len = read_u16(&dir, pos + 28);
+        let extra_len = read_u16(&dir, pos + 30);
+        let comment_len = read_u16(&dir, pos + 32);
+        let local_offset = read_u32(&dir, pos + 42) as u64;
+        let Some(name) = dir.get(pos + 46..pos + 46 + name_len) else { break };
+        let name = String::from_utf8_lossy(name).into_owned();
+
+        // EPUB and ODF require it first in the archive, at offset 0
+        let first = listing.members.is_empty() && local_offset == 0;
+        if name == "mimetype" && first && method == 0 && size <= MAX_MIMETYPE_LEN {
+            mimetype_at = Some((local_offset, size));
+        }
+        listing.members.push(name);
+        pos += 46 + name_len + extra_len + comment_len;
+    }
+
+    if let Some((offset, size)) = mimetype_at {
+        listing.mimetype = read_stored_member(file, offset, size).ok();
+    }
+    Ok(listing)
+}
+
+fn read_stored_member(file: &mut fs::File, offset: u64, size: u32) -> Result<String, MimeError> {
+    let mut header = [0u8; 30];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut header)?;
+    if read_u32(&header, 0) != ZIP_LOCAL_SIG {
+        return Err(corrupt("zip local header missing"));
+    }
+    let skip = read_u16(&header, 26) + read_u16(&header, 28);
+    file.seek(SeekFrom::Current(skip as i64))?;
+
+    let mut data = vec![0u8; size as usize];
+    file.read_exact(&mut data)?;
+    Ok(String::from_utf8_lossy(&data).trim().to_string())
+}
+
+fn list_tar(file: &mut fs::File) -> Result<ArchiveListing, MimeError> {
+    let mut listing = ArchiveListing { kind: ArchiveKind::Tar, members: Vec::new(), mimetype: None };
+    let mut header = [0u8; TAR_BLOCK];
+
+    while listing.members.len() < MAX_ARCHIVE_MEMBERS {
+        if file.read_exact(&mut header).is_err() || header.iter().all(|&b| b == 0) {
+            break;
+        }
+        if &header[257..262] != b"ustar" && listing.members.is_empty() {
+            return Err(corrupt("not a zip or ustar archive"));
+        }
+
+        let field = |range: std::ops::Range<usize>| {
+            let raw = &header[range];
+            let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+            String::from_utf8_lossy(&raw[..end]).into_owned()
+        };
+        let size = u64::from_str_radix(field(124..136).trim(), 8)
+            .map_err(|_| corrupt("tar member size is not octal"))?;
+        let prefix = field(345..500);
+        let name = field(0..100);
+        let name = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+        listing.members.push(name.trim_start_matches("./").to_string());
+
+        let padded = size.div_ceil(TAR_BLOCK as u64) * TAR_BLOCK as u64;
+        file.seek(SeekFrom::Current(padded as i64))?;
+    }
+    if listing.members.is_empty() {
+        return Err(corrupt("not a zip or ustar archive"));
+    }
+    Ok(listing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal stored zip with the given members
+    fn zip_bytes(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, data) in members {
+            let offset = out.len() as u32;
+            let mut local = Vec::new();
+            local.extend_from_slice(&ZIP_LOCAL_SIG.to_le_bytes());
+            local.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            local.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            local.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            local.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            local.extend_from_slice(&[0, 0]);
+            local.extend_from_slice(name.as_bytes());
+            local.extend_from_slice(data);
+            out.extend_from_slice(&local);
+
+            central.extend_from_slice(&ZIP_CENTRAL_SIG.to_le_bytes());
+            central.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            central.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0; 12]);
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let dir_offset = out.len() as u32;
+        out.extend_from_slice(&central);
+        out.extend_from_slice(&ZIP_EOCD_SIG.to_le_bytes());
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(&(members.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(members.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        out.extend_from_slice(&dir_offset.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out
+    }
+
+    #[test]
+    fn test_archive_inspection() {
+        let dir = std::env::temp_dir().join(format!("wsdg-mime-archive-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let apk = dir.join("app.bin");
+        fs::write(&apk, zip_bytes(&[("AndroidManifest.xml", b"<x/>"), ("classes.dex", b"dex")])).unwrap();
+        assert_eq!(inspect(&apk).as_deref(), Some("application/vnd.android.package-archive"));
+        // from_path() refines the plain zip magic match
+        let mime_array = crate::WsdgMimeArray::new();
+        assert_eq!(mime_array.from_path(&apk).unwrap(), "application/vnd.android.package-archive");
+
+        let docx = dir.join("report");
+        fs::write(&docx, zip_bytes(&[("[Content_Types].xml", b"<x/>"), ("word/document.xml", b"<x/>")])).unwrap();
+        assert_eq!(
+            inspect(&docx).as_deref(),
+            Some("application/vnd.openxmlformats-officedocument.wordprocessingml.document")
+        );
+
+        let epub = dir.join("book.zip");
+        fs::write(&epub, zip_bytes(&[("mimetype", b"application/epub+zip"), ("OEBPS/content.opf", b"")])).unwrap();
+        assert_eq!(inspect(&epub).as_deref(), Some("application/epub+zip"));
+
+        let odt = dir.join("letter");
+        let odt_type = b"application/vnd.oasis.opendocument.text";
+        fs::write(&odt, zip_bytes(&[("mimetype", odt_type), ("content.xml", b"<x/>")])).unwrap();
+        assert_eq!(inspect(&odt).as_deref(), Some("application/vnd.oasis.opendocument.text"));
+
+        // Only EPUB/ODF types, and only from the first member
+        let spoofed = dir.join("spoofed.zip");
+        fs::write(&spoofed, zip_bytes(&[("mimetype", b"text/plain"), ("run.sh", b"")])).unwrap();
+        assert_eq!(inspect(&spoofed), None);
+        let late = dir.join("late.zip");
+        fs::write(&late, zip_bytes(&[("OEBPS/content.opf", b""), ("mimetype", b"application/epub+zip")])).unwrap();
+        assert_eq!(list_members(&late).unwrap().mimetype, None);
+
+        let plain = dir.join("plain.zip");
+        fs::write(&plain, zip_bytes(&[("notes.txt", b"hi")])).unwrap();
+        assert_eq!(list_members(&plain).unwrap().members, vec!["notes.txt"]);
+        assert_eq!(inspect(&plain), None);
+
+        // ustar with an OCI image layout
+        let mut tar = Vec::new();
+        for (name, data) in [("oci-layout", &b"{}"[..]), ("index.json", &b"{}"[..])] {
+            let mut header = [0u8; TAR_BLOCK];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+            header[257..262].copy_from_slice(b"ustar");
+            tar.extend_from_slice(&header);
+            let mut block = [0u8; TAR_BLOCK];
+            block[..data.len()].copy_from_slice(data);
+            tar.extend_from_slice(&block);
+        }
+        tar.extend_from_slice(&[0u8; TAR_BLOCK * 2]);
+        let image = dir.join("image.tar");
+        fs::write(&image, tar).unwrap();
+        assert_eq!(inspect(&image).as_deref(), Some("application/vnd.oci.image.layout.v1+tar"));
+
+        let text = dir.join("text");
+        fs::write(&text, b"not an archive").unwrap();
+        assert!(list_members(&text).is_err());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -6,8 +6,19 @@
 use std::collections::HashMap;
 use std::path::Path;
 use std::fs;
+use std::sync::{Arc, RwLock};
 use thiserror::Error;
 
+/// Priority of the built-in extension and magic rules. Detectors above it are
+/// asked before them, detectors at or below it only when they find nothing.
+pub const BUILTIN_DETECTOR_PRIORITY: i32 = 0;
+
+/// Bytes read from the start of a file for magic and detector matching
+const HEADER_LEN: usize = 512;
+
+/// Detectors every new WsdgMimeArray starts with, see register_mime_detector()
+static PLUGIN_DETECTORS: RwLock<Vec<Arc<dyn MimeDetector>>> = RwLock::new(Vec::new());
+
 #[derive(Debug, Error)]
 pub enum MimeError {
     #[error("Unknown MIME type for: {0}")]
@@ -21,6 +32,9 @@ pub enum MimeError {
     
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+
+    #[error("MIME detector already registered: {0}")]
+    DetectorExists(String),
 }
 
 /// MIME type information
@@ -73,11 +87,55 @@ pub struct MagicSignature {
     pub mime_type: String,
 }
 
+/// What a custom detector gets to look at
+pub struct DetectionInput<'a> {
+    pub path: &'a Path,
+    pub extension: Option<&'a str>,
+    /// First 512 bytes; empty when the path is not a readable file
+    pub header: &'a [u8],
+}
+
+/// MIME detector contributed by a plugin or an external crate (e.g. for a proprietary format)
+pub trait MimeDetector: Send + Sync {
+    /// Unique name, used to replace or unregister the detector
+    fn name(&self) -> &str;
+
+    /// Higher runs first. Above BUILTIN_DETECTOR_PRIORITY the detector overrides the
+    /// built-in rules, otherwise it is only a fallback for files they do not know.
+    fn priority(&self) -> i32 {
+        BUILTIN_DETECTOR_PRIORITY
+    }
+
+    /// MIME type for the file, or None to let the next rule decide
+    fn detect(&self, input: &DetectionInput<'_>) -> Option<String>;
+
+    /// Types (and their extensions) this detector produces, added to the registry on registration
+    fn mime_types(&self) -> Vec<MimeType> {
+        Vec::new()
+    }
+}
+
+/// An extension claimed by two sources; the one with the higher priority keeps it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimeConflict {
+    pub extension: String,
+    /// Detector name, or "builtin"
+    pub kept_by: String,
+    pub kept_mime: String,
+    pub rejected_by: String,
+    pub rejected_mime: String,
+}
+
 /// WSDG MIME Array - MIME type registry and detector
 pub struct WsdgMimeArray {
     mime_by_ext: HashMap<String, MimeType>,
     ext_by_mime: HashMap<String, Vec<String>>,
     magic_signatures: Vec<MagicSignature>,
+    /// Sorted by descending priority, registration order within a priority
+    detectors: Vec<Arc<dyn MimeDetector>>,
+    /// Who registered each extension: (detector name or "builtin", priority)
+    ext_owner: HashMap<String, (String, i32)>,
+    conflicts: Vec<MimeConflict>,
 }
 
 impl WsdgMimeArray {
@@ -86,10 +144,18 @@ impl WsdgMimeArray {
             mime_by_ext: HashMap::new(),
             ext_by_mime: HashMap::new(),
             magic_signatures: Vec::new(),
+            detectors: Vec::new(),
+            ext_owner: HashMap::new(),
+            conflicts: Vec::new(),
         };
         
         array.register_standard_types();
         array.register_magic_signatures();
+
+        let plugins = PLUGIN_DETECTORS.read().unwrap_or_else(|e| e.into_inner()).clone();
+        for detector in plugins {
+            let _ = array.register_detector(detector);
+        }
         array
     }
     
@@ -145,6 +211,10 @@ impl WsdgMimeArray {
         self.register("application/x-bzip2", &["bz2"], "BZip2 Archive", MimeCategory::Archive);
         self.register("application/x-7z-compressed", &["7z"], "7-Zip Archive", MimeCategory::Archive);
         self.register("application/x-rar-compressed", &["rar"], "RAR Archive", MimeCategory::Archive);
+        self.register("application/vnd.android.package-archive", &["apk"], "Android Package", MimeCategory::Application);
+        self.register("application/java-archive", &["jar"], "Java Archive", MimeCategory::Application);
+        self.register("application/epub+zip", &["epub"], "EPUB Book", MimeCategory::Document);
+        self.register("application/vnd.oasis.opendocument.text", &["odt"], "OpenDocument Text", MimeCategory::Document);
         
         // Code
         self.register("text/x-python", &["py"], "Python Script", MimeCategory::Code);
@@ -189,6 +259,9 @@ impl WsdgMimeArray {
         // GZIP
         self.add_magic(0, vec![0x1F, 0x8B], "application/gzip");
         
+        // TAR (ustar header)
+        self.add_magic(257, b"ustar".to_vec(), "application/x-tar");
+        
         // ELF (Linux executable)
         self.add_magic(0, vec![0x7F, 0x45, 0x4C, 0x46], "application/x-executable");
         
@@ -215,6 +288,7 @@ impl WsdgMimeArray {
         // Store by extension
         for ext in extensions {
             self.mime_by_ext.insert(ext.to_string(), mime_type.clone());
+            self.ext_owner.insert(ext.to_string(), ("builtin".to_string(), BUILTIN_DETECTOR_PRIORITY));
         }
         
         // Store extensions by MIME
@@ -233,46 +307,140 @@ impl WsdgMimeArray {
         });
     }
     
+    /// Add a custom detector, keeping the list ordered by priority.
+    /// Extensions from its mime_types() replace existing ones only when its
+    /// priority is higher than the current owner's; every clash is recorded in conflicts().
+    pub fn register_detector(&mut self, detector: Arc<dyn MimeDetector>) -> Result<(), MimeError> {
+        if self.detectors.iter().any(|d| d.name() == detector.name()) {
+            return Err(MimeError::DetectorExists(detector.name().to_string()));
+        }
+        let priority = detector.priority();
+
+        for mime_type in detector.mime_types() {
+            let mut accepted = Vec::new();
+            for ext in &mime_type.extensions {
+                if let (Some((owner, owner_priority)), Some(current)) = (self.ext_owner.get(ext), self.mime_by_ext.get(ext)) {
+                    if current.mime != mime_type.mime {
+                        let wins = priority > *owner_priority;
+                        let (kept_by, kept_mime, rejected_by, rejected_mime) = if wins {
+                            (detector.name().to_string(), mime_type.mime.clone(), owner.clone(), current.mime.clone())
+                        } else {
+                            (owner.clone(), current.mime.clone(), detector.name().to_string(), mime_type.mime.clone())
+                        };
+                        self.conflicts.push(MimeConflict { extension: ext.clone(), kept_by, kept_mime, rejected_by, rejected_mime });
+                        if !wins {
+                            continue;
+                        }
+                    }
+                }
+                self.mime_by_ext.insert(ext.clone(), mime_type.clone());
+                self.ext_owner.insert(ext.clone(), (detector.name().to_string(), priority));
+                accepted.push(ext.clone());
+            }
+            let known = self.ext_by_mime.entry(mime_type.mime.clone()).or_default();
+            for ext in accepted {
+                if !known.contains(&ext) {
+                    known.push(ext);
+                }
+            }
+        }
+
+        let index = self.detectors.iter().position(|d| d.priority() < priority).unwrap_or(self.detectors.len());
+        self.detectors.insert(index, detector);
+        Ok(())
+    }
+
+    /// Remove a detector by name. Extensions it registered stay in the registry.
+    pub fn unregister_detector(&mut self, name: &str) -> bool {
+        let before = self.detectors.len();
+        self.detectors.retain(|d| d.name() != name);
+        self.detectors.len() != before
+    }
+
+    /// Registered detectors as (name, priority), in the order they are asked
+    pub fn detectors(&self) -> Vec<(String, i32)> {
+        self.detectors.iter().map(|d| (d.name().to_string(), d.priority())).collect()
+    }
+
+    /// Extension clashes seen while registering detectors
+    pub fn conflicts(&self) -> &[MimeConflict] {
+        &self.conflicts
+    }
+
     /// Get MIME type from file path
     pub fn from_path(&self, path: &Path) -> Result<String, MimeError> {
+        let extension = path.extension().and_then(|e| e.to_str());
+        let is_file = path.exists() && path.is_file();
+
+        // Detectors read the header up front; without them it is only needed for magic bytes
+        let header = if is_file && !self.detectors.is_empty() {
+            read_header(path).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let input = DetectionInput { path, extension, header: &header };
+        let (overriding, fallback): (Vec<_>, Vec<_>) =
+            self.detectors.iter().partition(|d| d.priority() > BUILTIN_DETECTOR_PRIORITY);
+
+        if let Some(mime) = overriding.iter().find_map(|d| d.detect(&input)) {
+            return Ok(mime);
+        }
+
         // Try extension first
-        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        if let Some(ext) = extension {
             if let Some(mime_type) = self.mime_by_ext.get(ext) {
-                return Ok(mime_type.mime.clone());
+                return Ok(self.refine_container(path, &mime_type.mime));
             }
         }
         
         // Try magic bytes
-        if path.exists() && path.is_file() {
-            if let Ok(mime) = self.detect_from_magic(path) {
-                return Ok(mime);
+        if is_file {
+            let magic = if self.detectors.is_empty() {
+                self.detect_from_magic(path).ok()
+            } else {
+                self.match_magic(&header)
+            };
+            if let Some(mime) = magic {
+                return Ok(self.refine_container(path, &mime));
             }
         }
+
+        if let Some(mime) = fallback.iter().find_map(|d| d.detect(&input)) {
+            return Ok(mime);
+        }
         
         // Default
         Ok("application/octet-stream".to_string())
     }
     
-    /// Detect MIME type from magic bytes
+    /// Detect MIME type from the built-in magic bytes (custom detectors are not asked)
     pub fn detect_from_magic(&self, path: &Path) -> Result<String, MimeError> {
-        let mut file = fs::File::open(path)?;
-        use std::io::Read;
-        
-        let mut buffer = vec![0u8; 512]; // Read first 512 bytes
-        let bytes_read = file.read(&mut buffer)?;
-        buffer.truncate(bytes_read);
-        
-        // Check signatures
-        for sig in &self.magic_signatures {
-            if sig.offset + sig.bytes.len() <= buffer.len() {
-                let slice = &buffer[sig.offset..sig.offset + sig.bytes.len()];
-                if slice == sig.bytes.as_slice() {
-                    return Ok(sig.mime_type.clone());
-                }
+        let buffer = read_header(path)?;
+        self.match_magic(&buffer)
+            .ok_or_else(|| MimeError::UnknownMimeType(path.display().to_string()))
+    }
+
+    /// Zip and tar files are classified by their members (.apk, OOXML, EPUB, ...)
+    #[cfg(feature = "archive-inspection")]
+    fn refine_container(&self, path: &Path, mime: &str) -> String {
+        if matches!(mime, "application/zip" | "application/x-tar") && path.is_file() {
+            if let Some(inner) = crate::wsdg_mime_archive::inspect(path) {
+                return inner;
             }
         }
-        
-        Err(MimeError::UnknownMimeType(path.display().to_string()))
+        mime.to_string()
+    }
+
+    #[cfg(not(feature = "archive-inspection"))]
+    fn refine_container(&self, _path: &Path, mime: &str) -> String {
+        mime.to_string()
+    }
+
+    fn match_magic(&self, buffer: &[u8]) -> Option<String> {
+        self.magic_signatures
+            .iter()
+            .find(|sig| buffer.get(sig.offset..sig.offset + sig.bytes.len()) == Some(sig.bytes.as_slice()))
+            .map(|sig| sig.mime_type.clone())
     }
     
     /// Get MIME type from extension
@@ -313,6 +481,30 @@ impl WsdgMimeArray {
     }
 }
 
+/// Make `detector` part of every WsdgMimeArray created from now on.
+/// A detector with the same name replaces the earlier one.
+pub fn register_mime_detector(detector: Arc<dyn MimeDetector>) {
+    let mut plugins = PLUGIN_DETECTORS.write().unwrap_or_else(|e| e.into_inner());
+    plugins.retain(|d| d.name() != detector.name());
+    plugins.push(detector);
+}
+
+/// Stop adding the named detector to new arrays
+pub fn unregister_mime_detector(name: &str) -> bool {
+    let mut plugins = PLUGIN_DETECTORS.write().unwrap_or_else(|e| e.into_inner());
+    let before = plugins.len();
+    plugins.retain(|d| d.name() != name);
+    plugins.len() != before
+}
+
+fn read_header(path: &Path) -> Result<Vec<u8>, MimeError> {
+    use std::io::Read;
+
+    let mut buffer = Vec::with_capacity(HEADER_LEN);
+    fs::File::open(path)?.take(HEADER_LEN as u64).read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
 impl Default for WsdgMimeArray {
     fn default() -> Self {
         Self::new()
@@ -373,4 +565,79 @@ mod tests {
         assert_eq!(mime_array.get_category(&path), MimeCategory::Image);
         assert!(mime_array.is_category(&path, MimeCategory::Image));
     }
+    
+    struct Fixed {
+        name: &'static str,
+        priority: i32,
+        mime: &'static str,
+        ext: &'static str,
+        magic: &'static [u8],
+    }
+
+    impl MimeDetector for Fixed {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn priority(&self) -> i32 {
+            self.priority
+        }
+
+        fn detect(&self, input: &DetectionInput<'_>) -> Option<String> {
+            (input.header.starts_with(self.magic) || input.extension == Some(self.ext)).then(|| self.mime.to_string())
+        }
+
+        fn mime_types(&self) -> Vec<MimeType> {
+            vec![MimeType {
+                mime: self.mime.to_string(),
+                extensions: vec![self.ext.to_string()],
+                description: self.name.to_string(),
+                category: MimeCategory::Document,
+            }]
+        }
+    }
+
+    #[test]
+    fn test_custom_detectors() {
+        let temp_dir = std::env::temp_dir();
+        let vendor_path = temp_dir.join("wsdg-mime-detector-test.bin");
+        fs::write(&vendor_path, b"ACME\x01payload").unwrap();
+
+        let mut mime_array = WsdgMimeArray::new();
+        assert_eq!(mime_array.from_path(&vendor_path).unwrap(), "application/octet-stream");
+
+        // Fallback detector: extension claim loses to the built-in "pdf"
+        mime_array.register_detector(Arc::new(Fixed {
+            name: "acme", priority: -10, mime: "application/x-acme", ext: "pdf", magic: b"ACME",
+        })).unwrap();
+        assert_eq!(mime_array.from_extension("pdf"), Some("application/pdf".to_string()));
+        assert_eq!(mime_array.conflicts()[0].kept_by, "builtin");
+        assert_eq!(mime_array.from_path(&vendor_path).unwrap(), "application/x-acme");
+        assert_eq!(mime_array.from_path(Path::new("manual.pdf")).unwrap(), "application/pdf");
+        assert!(matches!(
+            mime_array.register_detector(Arc::new(Fixed { name: "acme", priority: 0, mime: "", ext: "", magic: b"" })),
+            Err(MimeError::DetectorExists(_))
+        ));
+
+        // Overriding detector runs before the built-in rules and takes the extension
+        mime_array.register_detector(Arc::new(Fixed {
+            name: "vendor-pdf", priority: 10, mime: "application/x-vendor-pdf", ext: "pdf", magic: b"%VND",
+        })).unwrap();
+        assert_eq!(mime_array.detectors(), vec![("vendor-pdf".to_string(), 10), ("acme".to_string(), -10)]);
+        assert_eq!(mime_array.from_path(Path::new("manual.pdf")).unwrap(), "application/x-vendor-pdf");
+        assert_eq!(mime_array.from_extension("pdf"), Some("application/x-vendor-pdf".to_string()));
+        assert_eq!(mime_array.conflicts()[1].rejected_by, "builtin");
+
+        assert!(mime_array.unregister_detector("acme"));
+        assert_eq!(mime_array.from_path(&vendor_path).unwrap(), "application/octet-stream");
+
+        // Globally registered detectors reach arrays created afterwards
+        register_mime_detector(Arc::new(Fixed {
+            name: "global-acme", priority: -10, mime: "application/x-acme", ext: "acme", magic: b"ACME",
+        }));
+        assert_eq!(WsdgMimeArray::new().from_path(&vendor_path).unwrap(), "application/x-acme");
+        assert!(unregister_mime_detector("global-acme"));
+
+        let _ = fs::remove_file(vendor_path);
+    }
 }
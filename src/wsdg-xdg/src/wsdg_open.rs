@@ -7,9 +7,13 @@ use std::path::{Path, PathBuf};
 use std::process::{Command, Child};
 use std::collections::HashMap;
 use thiserror::Error;
+use wsdg_app_manifest::{EnvironmentConfig, ManifestParser, SourceParser, WasmaManifest};
 
 use crate::xdg_wsdg_translate::XdgWsdgTranslator;
+use crate::sandbox::SandboxProfile;
 use crate::wsdg_env::WsdgEnv;
+use crate::wsdg_handlers::{HandlerKind, HandlerRegistry};
+use crate::wsdg_mime_array::WsdgMimeArray;
 
 #[derive(Debug, Error)]
 pub enum OpenError {
@@ -49,6 +53,7 @@ pub struct WsdgOpen {
     translator: Option<XdgWsdgTranslator>,
     app_cache: HashMap<String, AppInfo>,
     desktop_dirs: Vec<PathBuf>,
+    sandbox_level: Option<u8>,
 }
 
 impl WsdgOpen {
@@ -60,9 +65,16 @@ impl WsdgOpen {
             translator: None,
             app_cache: HashMap::new(),
             desktop_dirs,
+            sandbox_level: None,
         }
     }
     
+    /// Sandbox profile for launched apps whose manifest does not set one
+    pub fn with_sandbox_level(mut self, level: u8) -> Self {
+        self.sandbox_level = Some(level);
+        self
+    }
+    
     pub fn with_translator(mut self, translator: XdgWsdgTranslator) -> Self {
         self.translator = Some(translator);
         self
@@ -107,6 +119,13 @@ impl WsdgOpen {
         // Get MIME type
         let mime_type = self.get_mime_type(path)?;
         
+        // Manifests registered through `wasma manifest install` come first
+        let associated = HandlerRegistry::open(&self.env, HandlerKind::Mime).ok()
+            .and_then(|registry| registry.default_handler(&mime_type).map(str::to_string));
+        if let Some(manifest_path) = associated {
+            return self.open_manifest_path(&manifest_path, &[path.to_string_lossy().as_ref()]);
+        }
+        
         // Find handler for MIME type
         let handler = self.find_mime_handler(&mime_type)
             .ok_or_else(|| OpenError::NoHandler(mime_type.clone()))?;
@@ -152,6 +171,68 @@ impl WsdgOpen {
         self.launch_app(app_name, args)
     }
     
+    /// Launch an application described by a WASMA manifest.
+    /// `exec`/`args`/`cwd` are expanded for `targets` (files or URIs) and the
+    /// manifest [environment] block is merged over the WSDG environment
+    pub fn open_manifest(&self, manifest: &WasmaManifest, targets: &[&str]) -> Result<Child, OpenError> {
+        self.launch_manifest(manifest, targets, referenced_sandbox_level(manifest))
+    }
+    
+    fn launch_manifest(&self, manifest: &WasmaManifest, targets: &[&str], sandbox_level: Option<u8>) -> Result<Child, OpenError> {
+        let launch = manifest.app.launch_command(targets)
+            .ok_or_else(|| OpenError::AppNotFound(format!("{} (manifest has no exec)", manifest.app.name)))?;
+        
+        let program = self.expand_wsdg_path(&launch.program)?;
+        let mut cmd = Command::new(&program);
+        cmd.args(&launch.args);
+        
+        if let Some(ref cwd) = launch.cwd {
+            let cwd = self.expand_wsdg_path(cwd)?;
+            if !cwd.is_dir() {
+                return Err(OpenError::InvalidPath(cwd.to_string_lossy().to_string()));
+            }
+            cmd.current_dir(cwd);
+        }
+        
+        self.apply_environment(&mut cmd, &manifest.environment);
+        
+        self.apply_sandbox(&mut cmd, sandbox_level, &manifest.app.name);
+        
+        cmd.spawn()
+            .map_err(|e| OpenError::LaunchFailed(format!("{}: {}", program.display(), e)))
+    }
+    
+    /// Parse a manifest file and launch it with `targets`
+    pub fn open_manifest_path(&self, manifest_path: &str, targets: &[&str]) -> Result<Child, OpenError> {
+        let manifest = ManifestParser::new(manifest_path.to_string()).load()
+            .map_err(|e| OpenError::AppNotFound(format!("{}: {}", manifest_path, e)))?;
+        
+        // An embedded [source] takes precedence over the referenced one
+        let embedded_level = std::fs::read_to_string(manifest_path).ok()
+            .and_then(|content| SourceParser::new(None).load_embedded(&content).ok().flatten())
+            .and_then(|source| source.system.sandbox_level);
+        let level = embedded_level.or_else(|| referenced_sandbox_level(&manifest));
+        self.launch_manifest(&manifest, targets, level)
+    }
+    
+    /// Install the sandbox profile of `level`, else of the opener's default level
+    fn apply_sandbox(&self, cmd: &mut Command, level: Option<u8>, app_name: &str) {
+        if let Some(level) = level.or(self.sandbox_level) {
+            SandboxProfile::for_level(level).apply(cmd, &self.env, app_name);
+        }
+    }
+    
+    /// WSDG variables, or the full merged environment when the manifest changes it
+    fn apply_environment(&self, cmd: &mut Command, overlay: &EnvironmentConfig) {
+        if overlay.is_empty() {
+            for (key, value) in self.env.all_vars() {
+                cmd.env(key, value);
+            }
+        } else {
+            cmd.env_clear().envs(self.env.launch_environment(overlay));
+        }
+    }
+    
     /// Find desktop file for application
     fn find_desktop_file(&self, app_name: &str) -> Result<Option<AppInfo>, OpenError> {
         let desktop_filename = if app_name.ends_with(".desktop") {
@@ -249,9 +330,11 @@ impl WsdgOpen {
         }
         
         // Set WSDG environment
-        for (key, value) in self.env.all_vars() {
-            cmd.env(key, value);
-        }
+        self.apply_environment(&mut cmd, &EnvironmentConfig::default());
+        let app_name = Path::new(&exec_parts[0]).file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.apply_sandbox(&mut cmd, None, &app_name);
         
         cmd.spawn()
             .map_err(|e| OpenError::LaunchFailed(format!("{}: {}", exec_parts[0], e)))
@@ -339,10 +422,9 @@ impl WsdgOpen {
     }
     
     /// Get MIME type for file
-    fn get_mime_type(&self, _path: &Path) -> Result<String, OpenError> {
-        // This would integrate with wsdg-mime-array.rs
-        // For now, simple extension-based detection
-        Ok("application/octet-stream".to_string())
+    fn get_mime_type(&self, path: &Path) -> Result<String, OpenError> {
+        WsdgMimeArray::new().from_path(path)
+            .map_err(|e| OpenError::NoHandler(e.to_string()))
     }
     
     /// Find handler for MIME type
@@ -382,6 +464,14 @@ impl WsdgOpen {
     }
 }
 
+/// USE_SANDBOX_LEVEL of the permission source the manifest references
+fn referenced_sandbox_level(manifest: &WasmaManifest) -> Option<u8> {
+    SourceParser::new(None)
+        .load(&manifest.permissions.source_file().to_string_lossy())
+        .ok()
+        .and_then(|source| source.system.sandbox_level)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -413,4 +503,17 @@ mod tests {
         let path = opener.expand_wsdg_path("$CONFIG/app").unwrap();
         assert_eq!(path, PathBuf::from("/home/user/.config/app"));
     }
+    
+    #[test]
+    fn test_open_manifest() {
+        let opener = WsdgOpen::new(WsdgEnv::new());
+        let parser = wsdg_app_manifest::ManifestParser::new("test.manifest".to_string());
+        
+        let manifest = parser.parse("name = NoExec").unwrap();
+        assert!(matches!(opener.open_manifest(&manifest, &[]), Err(OpenError::AppNotFound(_))));
+        
+        let manifest = parser.parse("name = True\nexec = true\nargs = %f\ncwd = /tmp\n[environment]\nWSDG_TEST = 1").unwrap();
+        let mut child = opener.open_manifest(&manifest, &["/tmp"]).unwrap();
+        assert!(child.wait().unwrap().success());
+    }
 }
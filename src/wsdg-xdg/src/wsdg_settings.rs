@@ -6,10 +6,15 @@
 
 use std::path::{Path, PathBuf};
 use std::fs;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
 use thiserror::Error;
+use wbackend::Color;
 
 use crate::wsdg_env::WsdgEnv;
+use crate::wsdg_fonts::{FontCatalog, FontScript, MissingFont};
+use crate::wsdg_atomic::write_atomic;
+use crate::wsdg_kiosk::{KioskPolicy, PolicyDenied};
 
 #[derive(Debug, Error)]
 pub enum SettingsError {
@@ -24,6 +29,9 @@ pub enum SettingsError {
     
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+    
+    #[error("{0}")]
+    PolicyDenied(#[from] PolicyDenied),
 }
 
 /// GUI Theme settings
@@ -31,9 +39,13 @@ pub enum SettingsError {
 pub struct ThemeSettings {
     pub name: String,
     pub dark_mode: bool,
-    pub accent_color: String,
-    pub background_color: String,
-    pub foreground_color: String,
+    pub accent_color: Color,
+    pub background_color: Color,
+    pub foreground_color: Color,
+    /// Accessibility palette: "standard", "high-contrast" or "deuteranopia"
+    pub a11y_variant: String,
+    /// Disable animations and transitions (see also `system_reduce_motion`)
+    pub reduce_motion: bool,
 }
 
 impl Default for ThemeSettings {
@@ -41,9 +53,11 @@ impl Default for ThemeSettings {
         Self {
             name: "default".to_string(),
             dark_mode: false,
-            accent_color: "#3584e4".to_string(),
-            background_color: "#ffffff".to_string(),
-            foreground_color: "#000000".to_string(),
+            accent_color: Color::rgb(0x35, 0x84, 0xe4),
+            background_color: Color::WHITE,
+            foreground_color: Color::BLACK,
+            a11y_variant: "standard".to_string(),
+            reduce_motion: false,
         }
     }
 }
@@ -56,6 +70,10 @@ pub struct FontSettings {
     pub weight: String,
     pub monospace_family: String,
     pub monospace_size: u32,
+    /// Families tried in order when `family` lacks a glyph (comma separated in the file)
+    pub fallback: Vec<String>,
+    /// Families tried first for text in a script, e.g. `cjk = "Noto Sans CJK SC"`
+    pub script_fallback: BTreeMap<FontScript, Vec<String>>,
 }
 
 impl Default for FontSettings {
@@ -66,8 +84,63 @@ impl Default for FontSettings {
             weight: "normal".to_string(),
             monospace_family: "Monospace".to_string(),
             monospace_size: 10,
+            fallback: Vec::new(),
+            script_fallback: BTreeMap::new(),
+        }
+    }
+}
+
+impl FontSettings {
+    /// Families to try for text in `script`: its overrides, the main family,
+    /// then the general fallback, without repeats
+    pub fn chain(&self, script: Option<FontScript>) -> Vec<String> {
+        let overrides = script.and_then(|s| self.script_fallback.get(&s)).into_iter().flatten();
+        let mut chain: Vec<String> = Vec::new();
+        for family in overrides.chain(std::iter::once(&self.family)).chain(&self.fallback) {
+            if !chain.iter().any(|f| f.eq_ignore_ascii_case(family)) {
+                chain.push(family.clone());
+            }
         }
+        chain
     }
+    
+    /// Configured families `catalog` does not know. Nothing is reported
+    /// against an empty catalog (no fonts could be discovered)
+    pub fn missing_fonts(&self, catalog: &FontCatalog) -> Vec<MissingFont> {
+        if catalog.is_empty() {
+            return Vec::new();
+        }
+        let lists = std::iter::once(("family", std::slice::from_ref(&self.family)))
+            .chain(std::iter::once(("monospace_family", std::slice::from_ref(&self.monospace_family))))
+            .chain(std::iter::once(("fallback", self.fallback.as_slice())))
+            .chain(self.script_fallback.iter().map(|(script, families)| (script.name(), families.as_slice())));
+        lists
+            .flat_map(|(key, families)| families.iter().map(move |family| (key, family)))
+            .filter(|(_, family)| !catalog.contains(family))
+            .map(|(key, family)| MissingFont { key: format!("font.{}", key), family: family.clone() })
+            .collect()
+    }
+    
+    /// Copy whose fallback lists only hold installed families. The main and
+    /// monospace families stay as configured: something has to render
+    pub fn validated(&self, catalog: &FontCatalog) -> FontSettings {
+        if catalog.is_empty() {
+            return self.clone();
+        }
+        let installed = |families: &Vec<String>| families.iter().filter(|f| catalog.contains(f)).cloned().collect::<Vec<_>>();
+        FontSettings {
+            fallback: installed(&self.fallback),
+            script_fallback: self.script_fallback.iter()
+                .map(|(script, families)| (*script, installed(families)))
+                .filter(|(_, families)| !families.is_empty())
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+fn font_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|family| !family.is_empty()).map(String::from).collect()
 }
 
 /// Icon settings
@@ -96,6 +169,16 @@ pub struct WindowSettings {
     pub decorations: bool,
     pub transparency: bool,
     pub opacity: f32,
+    /// "click", "mouse" (focus follows mouse) or "sloppy"
+    pub focus_policy: String,
+    /// Hover delay before focus-follows-mouse switches focus
+    pub focus_delay_ms: u32,
+    pub focus_stealing_prevention: bool,
+    /// How often the window manager redraws its live resource graphs; 0 turns it off
+    pub graph_refresh_ms: u32,
+    /// How often the window manager runs the resource cycle on its own; 0 leaves it
+    /// to the "Update Resources" button
+    pub resource_cycle_ms: u32,
 }
 
 impl Default for WindowSettings {
@@ -106,6 +189,66 @@ impl Default for WindowSettings {
             decorations: true,
             transparency: false,
             opacity: 1.0,
+            focus_policy: "click".to_string(),
+            focus_delay_ms: 150,
+            focus_stealing_prevention: true,
+            graph_refresh_ms: 1000,
+            resource_cycle_ms: 2000,
+        }
+    }
+}
+
+/// Usage statistics settings; nothing is recorded unless `enabled` is set
+#[derive(Debug, Clone, Default)]
+pub struct TelemetrySettings {
+    pub enabled: bool,
+    /// http:// endpoint reports are POSTed to; empty writes them to a local file
+    pub endpoint: String,
+}
+
+/// Event webhooks; nothing is sent while `urls` is empty
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookSettings {
+    /// http:// URLs every event is POSTed to (comma separated in the file)
+    pub urls: Vec<String>,
+    /// Events to send ("window_created", "window_closed", "lease_expired",
+    /// "endpoint_down", "resource_report"); empty sends all of them except
+    /// "resource_report", which is sent every monitor cycle
+    pub events: Vec<String>,
+    /// Deliveries per URL per minute, 0 for no limit; events over it are dropped
+    pub max_per_minute: u32,
+    /// Attempts after a failed delivery, with a doubling delay
+    pub retries: u32,
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            events: Vec::new(),
+            max_per_minute: 30,
+            retries: 3,
+        }
+    }
+}
+
+/// What a window shows while its stream is disconnected
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaceholderSettings {
+    /// "placeholder" (icon, message, elapsed time), "blank" or "freeze" (keep the last frame)
+    pub mode: String,
+    pub message: String,
+    pub show_icon: bool,
+    pub show_elapsed: bool,
+}
+
+impl Default for PlaceholderSettings {
+    fn default() -> Self {
+        Self {
+            mode: "placeholder".to_string(),
+            message: "Reconnecting…".to_string(),
+            show_icon: true,
+            show_elapsed: true,
         }
     }
 }
@@ -117,6 +260,11 @@ pub struct WsdgSettings {
     pub font: FontSettings,
     pub icon: IconSettings,
     pub window: WindowSettings,
+    pub telemetry: TelemetrySettings,
+    pub webhooks: WebhookSettings,
+    pub placeholder: PlaceholderSettings,
+    /// [placeholder.<app_id>] overrides, keyed by app id
+    pub app_placeholders: HashMap<String, PlaceholderSettings>,
     pub custom: HashMap<String, String>,
 }
 
@@ -127,32 +275,231 @@ impl Default for WsdgSettings {
             font: FontSettings::default(),
             icon: IconSettings::default(),
             window: WindowSettings::default(),
+            telemetry: TelemetrySettings::default(),
+            webhooks: WebhookSettings::default(),
+            placeholder: PlaceholderSettings::default(),
+            app_placeholders: HashMap::new(),
             custom: HashMap::new(),
         }
     }
 }
 
+impl WsdgSettings {
+    /// Disconnected-stream placeholder of `app_id`, falling back to [placeholder]
+    pub fn placeholder_for(&self, app_id: &str) -> &PlaceholderSettings {
+        self.app_placeholders.get(app_id).unwrap_or(&self.placeholder)
+    }
+    
+    /// Every setting as "section.key" → value, with the keys of settings.conf
+    pub fn entries(&self) -> BTreeMap<String, String> {
+        let mut entries = BTreeMap::new();
+        let mut put = |key: String, value: String| {
+            entries.insert(key, value);
+        };
+        let theme = &self.theme;
+        put("theme.name".into(), theme.name.clone());
+        put("theme.dark_mode".into(), theme.dark_mode.to_string());
+        put("theme.accent_color".into(), theme.accent_color.to_string());
+        put("theme.background_color".into(), theme.background_color.to_string());
+        put("theme.foreground_color".into(), theme.foreground_color.to_string());
+        put("theme.a11y_variant".into(), theme.a11y_variant.clone());
+        put("theme.reduce_motion".into(), theme.reduce_motion.to_string());
+        let font = &self.font;
+        put("font.family".into(), font.family.clone());
+        put("font.size".into(), font.size.to_string());
+        put("font.weight".into(), font.weight.clone());
+        put("font.monospace_family".into(), font.monospace_family.clone());
+        put("font.monospace_size".into(), font.monospace_size.to_string());
+        put("font.fallback".into(), font.fallback.join(", "));
+        for script in FontScript::ALL {
+            put(format!("font.{}", script.name()), font.script_fallback.get(&script).map(|f| f.join(", ")).unwrap_or_default());
+        }
+        let icon = &self.icon;
+        put("icon.theme".into(), icon.theme.clone());
+        put("icon.size".into(), icon.size.to_string());
+        put("icon.use_symbolic".into(), icon.use_symbolic.to_string());
+        let window = &self.window;
+        put("window.default_width".into(), window.default_width.to_string());
+        put("window.default_height".into(), window.default_height.to_string());
+        put("window.decorations".into(), window.decorations.to_string());
+        put("window.transparency".into(), window.transparency.to_string());
+        put("window.opacity".into(), window.opacity.to_string());
+        put("window.focus_policy".into(), window.focus_policy.clone());
+        put("window.focus_delay_ms".into(), window.focus_delay_ms.to_string());
+        put("window.focus_stealing_prevention".into(), window.focus_stealing_prevention.to_string());
+        put("window.graph_refresh_ms".into(), window.graph_refresh_ms.to_string());
+        put("window.resource_cycle_ms".into(), window.resource_cycle_ms.to_string());
+        put("telemetry.enabled".into(), self.telemetry.enabled.to_string());
+        put("telemetry.endpoint".into(), self.telemetry.endpoint.clone());
+        let webhooks = &self.webhooks;
+        put("webhooks.urls".into(), webhooks.urls.join(", "));
+        put("webhooks.events".into(), webhooks.events.join(", "));
+        put("webhooks.max_per_minute".into(), webhooks.max_per_minute.to_string());
+        put("webhooks.retries".into(), webhooks.retries.to_string());
+        let placeholders = std::iter::once(("placeholder".to_string(), &self.placeholder))
+            .chain(self.app_placeholders.iter().map(|(app_id, p)| (format!("placeholder.{}", app_id), p)));
+        for (section, placeholder) in placeholders {
+            put(format!("{}.mode", section), placeholder.mode.clone());
+            put(format!("{}.message", section), placeholder.message.clone());
+            put(format!("{}.show_icon", section), placeholder.show_icon.to_string());
+            put(format!("{}.show_elapsed", section), placeholder.show_elapsed.to_string());
+        }
+        for (key, value) in &self.custom {
+            put(format!("custom.{}", key), value.clone());
+        }
+        entries
+    }
+}
+
+/// One setting that differs between two snapshots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingChange {
+    /// "section.key", as in `WsdgSettings::entries`
+    pub key: String,
+    /// None: the setting did not exist (per-app or custom keys)
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// What changed since WASMA was last synced, so consumers only restyle
+/// what a save actually touched
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SettingsDelta {
+    pub changes: Vec<SettingChange>,
+}
+
+impl SettingsDelta {
+    pub fn between(old: &WsdgSettings, new: &WsdgSettings) -> Self {
+        let (old, new) = (old.entries(), new.entries());
+        let keys: std::collections::BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+        let changes = keys.into_iter()
+            .filter(|key| old.get(*key) != new.get(*key))
+            .map(|key| SettingChange { key: key.clone(), old: old.get(key).cloned(), new: new.get(key).cloned() })
+            .collect();
+        Self { changes }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+    
+    pub fn contains(&self, key: &str) -> bool {
+        self.changes.iter().any(|change| change.key == key)
+    }
+    
+    /// Any key of `section` ("theme", "window", "placeholder.<app_id>", …)
+    pub fn touches(&self, section: &str) -> bool {
+        self.changes.iter().any(|change| change.key.rsplit_once('.').is_some_and(|(s, _)| s == section))
+    }
+    
+    /// New value of `key` if it changed
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.changes.iter().find(|change| change.key == key).and_then(|change| change.new.as_deref())
+    }
+    
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.changes.iter().map(|change| change.key.as_str())
+    }
+}
+
+/// A setting both this process and another one changed since the last load
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingConflict {
+    pub key: String,
+    /// Value at the last load
+    pub base: Option<String>,
+    /// Our value, which was written
+    pub ours: Option<String>,
+    /// The other writer's value, which was overwritten
+    pub theirs: Option<String>,
+}
+
+/// Outcome of a save that merged in what other processes wrote meanwhile
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Changes of other writers, taken over because we did not touch the key
+    pub merged: Vec<SettingChange>,
+    pub conflicts: Vec<SettingConflict>,
+}
+
+impl MergeReport {
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
+}
+
+type DeltaCallback = Box<dyn Fn(&WsdgSettings, &SettingsDelta) + Send + Sync>;
+
+/// Advisory lock on `<settings.conf>.lock` (flock), shared by every process
+/// that reads or writes that settings.conf; released when the File is dropped.
+/// Anything replacing settings.conf outside `WsdgSettingsManager` takes it
+/// exclusively so it cannot race `save_merged`
+pub fn lock_settings_file(settings_path: &Path, exclusive: bool) -> std::io::Result<fs::File> {
+    let mut name = settings_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(settings_path.with_file_name(name))?;
+    // flock(2) directly: File::lock needs a newer toolchain than WASMA supports
+    let operation = if exclusive { libc::LOCK_EX } else { libc::LOCK_SH };
+    if unsafe { libc::flock(std::os::fd::AsRawFd::as_raw_fd(&file), operation) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(file)
+}
+
 /// WSDG Settings Manager
 pub struct WsdgSettingsManager {
-    #[allow(dead_code)]
     env: WsdgEnv,
     settings: WsdgSettings,
     settings_path: PathBuf,
     manifest_rrt_support: bool,
+    /// Read-only vendor settings when kiosk mode is active
+    kiosk: Option<KioskPolicy>,
     /// WASMA integration callback
     wasma_sync_callback: Option<Box<dyn Fn(&WsdgSettings) + Send + Sync>>,
+    /// Differential WASMA callback and the settings it last saw
+    wasma_delta_callback: Option<DeltaCallback>,
+    synced: Mutex<Option<WsdgSettings>>,
+    /// settings.conf as last loaded or saved: the common base when merging
+    /// with writes of other processes
+    base: Option<WsdgSettings>,
 }
 
 impl WsdgSettingsManager {
     pub fn new(env: WsdgEnv) -> Self {
-        let settings_path = Self::get_settings_path(&env);
+        let kiosk = env.kiosk();
+        let settings_path = match &kiosk {
+            Some(policy) => policy.settings_path(),
+            None => Self::get_settings_path(&env),
+        };
         
         Self {
             env,
             settings: WsdgSettings::default(),
             settings_path,
             manifest_rrt_support: false,
+            kiosk,
             wasma_sync_callback: None,
+            wasma_delta_callback: None,
+            synced: Mutex::new(None),
+            base: None,
+        }
+    }
+    
+    /// Kiosk mode: settings come from `policy`'s vendor directory and cannot be changed
+    pub fn with_kiosk_policy(mut self, policy: KioskPolicy) -> Self {
+        self.settings_path = policy.settings_path();
+        self.kiosk = Some(policy);
+        self
+    }
+    
+    fn check_writable(&self, action: &str) -> Result<(), SettingsError> {
+        match &self.kiosk {
+            Some(policy) => Err(policy.deny(action).into()),
+            None => Ok(()),
         }
     }
     
@@ -165,11 +512,21 @@ impl WsdgSettingsManager {
         }
     }
     
+    /// settings.conf this manager loads and saves
+    pub fn settings_path(&self) -> &Path {
+        &self.settings_path
+    }
+    
     /// Enable manifest_rrt support
     pub fn enable_manifest_rrt(&mut self) {
         self.manifest_rrt_support = true;
     }
     
+    /// Advisory lock shared by every process using this settings.conf
+    fn lock(&self, exclusive: bool) -> Result<fs::File, SettingsError> {
+        Ok(lock_settings_file(&self.settings_path, exclusive)?)
+    }
+    
     /// Load settings from file
     pub fn load(&mut self) -> Result<(), SettingsError> {
         if !self.settings_path.exists() {
@@ -177,12 +534,70 @@ impl WsdgSettingsManager {
             return Ok(());
         }
         
+        // Read-only locations (kiosk vendor settings) are read without a lock
+        let _lock = self.lock(false).ok();
         let content = fs::read_to_string(&self.settings_path)?;
         self.parse_settings(&content)?;
+        self.base = Some(self.settings.clone());
         
         Ok(())
     }
     
+    /// settings.conf as it is on disk now, parsed over the defaults
+    fn read_disk(&mut self) -> Result<Option<WsdgSettings>, SettingsError> {
+        if !self.settings_path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&self.settings_path)?;
+        let ours = std::mem::take(&mut self.settings);
+        let parsed = self.parse_settings(&content);
+        let theirs = std::mem::replace(&mut self.settings, ours);
+        parsed.map(|_| Some(theirs))
+    }
+    
+    /// Set (or remove, for per-app and custom keys) one "section.key" entry
+    fn apply_entry(&mut self, entry: &str, value: Option<&str>) -> Result<(), SettingsError> {
+        let (section, key) = match entry.strip_prefix("custom.") {
+            Some(key) => ("custom", key),
+            None => entry.rsplit_once('.').unwrap_or(("", entry)),
+        };
+        match value {
+            Some(value) => self.apply_setting(section, key, value),
+            None => {
+                if section == "custom" {
+                    self.settings.custom.remove(key);
+                } else if let Some(app_id) = section.strip_prefix("placeholder.") {
+                    self.settings.app_placeholders.remove(app_id);
+                }
+                Ok(())
+            }
+        }
+    }
+    
+    /// Three-way merge of our settings with what is on disk: keys only the
+    /// other writer changed are taken over, keys only we changed are kept,
+    /// and keys both changed keep our value and are reported as conflicts
+    fn merge_with(&mut self, theirs: &WsdgSettings) -> Result<MergeReport, SettingsError> {
+        let base = self.base.clone().unwrap_or_default().entries();
+        let (ours, theirs) = (self.settings.entries(), theirs.entries());
+        let keys: std::collections::BTreeSet<&String> = base.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+        
+        let mut report = MergeReport::default();
+        for key in keys {
+            let (b, o, t) = (base.get(key), ours.get(key), theirs.get(key));
+            if o == t || t == b {
+                continue;
+            }
+            if o == b {
+                self.apply_entry(key, t.map(String::as_str))?;
+                report.merged.push(SettingChange { key: key.clone(), old: o.cloned(), new: t.cloned() });
+            } else {
+                report.conflicts.push(SettingConflict { key: key.clone(), base: b.cloned(), ours: o.cloned(), theirs: t.cloned() });
+            }
+        }
+        Ok(report)
+    }
+    
     /// Parse settings content
     fn parse_settings(&mut self, content: &str) -> Result<(), SettingsError> {
         let mut current_section = String::new();
@@ -221,9 +636,11 @@ impl WsdgSettingsManager {
                 match key {
                     "name" => self.settings.theme.name = value.to_string(),
                     "dark_mode" => self.settings.theme.dark_mode = value == "true" || value == "yes",
-                    "accent_color" => self.settings.theme.accent_color = value.to_string(),
-                    "background_color" => self.settings.theme.background_color = value.to_string(),
-                    "foreground_color" => self.settings.theme.foreground_color = value.to_string(),
+                    "accent_color" => parse_color(key, value, &mut self.settings.theme.accent_color),
+                    "background_color" => parse_color(key, value, &mut self.settings.theme.background_color),
+                    "foreground_color" => parse_color(key, value, &mut self.settings.theme.foreground_color),
+                    "a11y_variant" => self.settings.theme.a11y_variant = value.to_string(),
+                    "reduce_motion" => self.settings.theme.reduce_motion = value == "true" || value == "yes",
                     _ => {}
                 }
             }
@@ -234,7 +651,17 @@ impl WsdgSettingsManager {
                     "weight" => self.settings.font.weight = value.to_string(),
                     "monospace_family" => self.settings.font.monospace_family = value.to_string(),
                     "monospace_size" => self.settings.font.monospace_size = value.parse().unwrap_or(10),
-                    _ => {}
+                    "fallback" => self.settings.font.fallback = font_list(value),
+                    _ => {
+                        if let Some(script) = FontScript::from_name(key) {
+                            let families = font_list(value);
+                            if families.is_empty() {
+                                self.settings.font.script_fallback.remove(&script);
+                            } else {
+                                self.settings.font.script_fallback.insert(script, families);
+                            }
+                        }
+                    }
                 }
             }
             "icon" => {
@@ -252,9 +679,39 @@ impl WsdgSettingsManager {
                     "decorations" => self.settings.window.decorations = value == "true" || value == "yes",
                     "transparency" => self.settings.window.transparency = value == "true" || value == "yes",
                     "opacity" => self.settings.window.opacity = value.parse().unwrap_or(1.0),
+                    "focus_policy" => self.settings.window.focus_policy = value.to_string(),
+                    "focus_delay_ms" => self.settings.window.focus_delay_ms = value.parse().unwrap_or(150),
+                    "focus_stealing_prevention" => self.settings.window.focus_stealing_prevention = value == "true" || value == "yes",
+                    "graph_refresh_ms" => self.settings.window.graph_refresh_ms = value.parse().unwrap_or(1000),
+                    "resource_cycle_ms" => self.settings.window.resource_cycle_ms = value.parse().unwrap_or(2000),
+                    _ => {}
+                }
+            }
+            "telemetry" => {
+                match key {
+                    "enabled" => self.settings.telemetry.enabled = value == "true" || value == "yes",
+                    "endpoint" => self.settings.telemetry.endpoint = value.to_string(),
+                    _ => {}
+                }
+            }
+            "webhooks" => {
+                let list = || value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(String::from).collect();
+                match key {
+                    "urls" => self.settings.webhooks.urls = list(),
+                    "events" => self.settings.webhooks.events = list(),
+                    "max_per_minute" => self.settings.webhooks.max_per_minute = value.parse().unwrap_or(30),
+                    "retries" => self.settings.webhooks.retries = value.parse().unwrap_or(3),
                     _ => {}
                 }
             }
+            "placeholder" => Self::apply_placeholder(&mut self.settings.placeholder, key, value),
+            _ if section.starts_with("placeholder.") => {
+                let app_id = &section["placeholder.".len()..];
+                // Keys missing from the app section keep the [placeholder] value
+                let base = self.settings.placeholder.clone();
+                let placeholder = self.settings.app_placeholders.entry(app_id.to_string()).or_insert(base);
+                Self::apply_placeholder(placeholder, key, value);
+            }
             "custom" | "" => {
                 self.settings.custom.insert(key.to_string(), value.to_string());
             }
@@ -270,13 +727,57 @@ impl WsdgSettingsManager {
         Ok(())
     }
     
-    /// Save settings to file
-    pub fn save(&self) -> Result<(), SettingsError> {
+    fn apply_placeholder(placeholder: &mut PlaceholderSettings, key: &str, value: &str) {
+        match key {
+            "mode" => placeholder.mode = value.to_string(),
+            "message" => placeholder.message = value.to_string(),
+            "show_icon" => placeholder.show_icon = value == "true" || value == "yes",
+            "show_elapsed" => placeholder.show_elapsed = value == "true" || value == "yes",
+            _ => {}
+        }
+    }
+
+    fn write_placeholder(content: &mut String, section: &str, placeholder: &PlaceholderSettings) {
+        content.push_str(&format!("[{}]\n", section));
+        content.push_str(&format!("mode = \"{}\"\n", placeholder.mode));
+        content.push_str(&format!("message = \"{}\"\n", placeholder.message));
+        content.push_str(&format!("show_icon = {}\n", placeholder.show_icon));
+        content.push_str(&format!("show_elapsed = {}\n", placeholder.show_elapsed));
+        content.push('\n');
+    }
+    
+    /// Save settings to file, merged with changes other processes saved since
+    /// our last load (see `save_merged`)
+    pub fn save(&mut self) -> Result<(), SettingsError> {
+        self.save_merged().map(|_| ())
+    }
+    
+    /// Save under the settings lock. Settings another process saved since
+    /// our last load or save are merged in (and also land in `settings()`);
+    /// where both sides changed a key our value wins and is reported
+    pub fn save_merged(&mut self) -> Result<MergeReport, SettingsError> {
+        self.check_writable("saving settings")?;
+        
         // Create directory if needed
         if let Some(parent) = self.settings_path.parent() {
             fs::create_dir_all(parent)?;
         }
         
+        let _lock = self.lock(true)?;
+        let report = match self.read_disk()? {
+            Some(theirs) => self.merge_with(&theirs)?,
+            None => MergeReport::default(),
+        };
+        for conflict in &report.conflicts {
+            tracing::warn!(key = %conflict.key, kept = ?conflict.ours, overwrote = ?conflict.theirs, "settings conflict");
+        }
+        
+        self.write_settings()?;
+        self.base = Some(self.settings.clone());
+        Ok(report)
+    }
+    
+    fn write_settings(&self) -> Result<(), SettingsError> {
         let mut content = String::new();
         content.push_str("*// WSDG Settings Configuration\n");
         content.push_str("*// Part of WASMA (Windows Assignment System Monitoring Architecture)\n\n");
@@ -288,6 +789,8 @@ impl WsdgSettingsManager {
         content.push_str(&format!("accent_color = \"{}\"\n", self.settings.theme.accent_color));
         content.push_str(&format!("background_color = \"{}\"\n", self.settings.theme.background_color));
         content.push_str(&format!("foreground_color = \"{}\"\n", self.settings.theme.foreground_color));
+        content.push_str(&format!("a11y_variant = \"{}\"\n", self.settings.theme.a11y_variant));
+        content.push_str(&format!("reduce_motion = {}\n", self.settings.theme.reduce_motion));
         content.push_str("\n");
         
         // Font section
@@ -297,6 +800,10 @@ impl WsdgSettingsManager {
         content.push_str(&format!("weight = \"{}\"\n", self.settings.font.weight));
         content.push_str(&format!("monospace_family = \"{}\"\n", self.settings.font.monospace_family));
         content.push_str(&format!("monospace_size = {}\n", self.settings.font.monospace_size));
+        content.push_str(&format!("fallback = \"{}\"\n", self.settings.font.fallback.join(", ")));
+        for (script, families) in &self.settings.font.script_fallback {
+            content.push_str(&format!("{} = \"{}\"\n", script.name(), families.join(", ")));
+        }
         content.push_str("\n");
         
         // Icon section
@@ -313,8 +820,35 @@ impl WsdgSettingsManager {
         content.push_str(&format!("decorations = {}\n", self.settings.window.decorations));
         content.push_str(&format!("transparency = {}\n", self.settings.window.transparency));
         content.push_str(&format!("opacity = {:.2}\n", self.settings.window.opacity));
+        content.push_str(&format!("focus_policy = \"{}\"\n", self.settings.window.focus_policy));
+        content.push_str(&format!("focus_delay_ms = {}\n", self.settings.window.focus_delay_ms));
+        content.push_str(&format!("focus_stealing_prevention = {}\n", self.settings.window.focus_stealing_prevention));
+        content.push_str(&format!("graph_refresh_ms = {}\n", self.settings.window.graph_refresh_ms));
+        content.push_str(&format!("resource_cycle_ms = {}\n", self.settings.window.resource_cycle_ms));
         content.push_str("\n");
         
+        // Telemetry section
+        content.push_str("[telemetry]\n");
+        content.push_str(&format!("enabled = {}\n", self.settings.telemetry.enabled));
+        content.push_str(&format!("endpoint = \"{}\"\n", self.settings.telemetry.endpoint));
+        content.push('\n');
+        
+        // Webhooks section
+        content.push_str("[webhooks]\n");
+        content.push_str(&format!("urls = \"{}\"\n", self.settings.webhooks.urls.join(", ")));
+        content.push_str(&format!("events = \"{}\"\n", self.settings.webhooks.events.join(", ")));
+        content.push_str(&format!("max_per_minute = {}\n", self.settings.webhooks.max_per_minute));
+        content.push_str(&format!("retries = {}\n", self.settings.webhooks.retries));
+        content.push('\n');
+        
+        // Disconnected-stream placeholders, then per-app overrides
+        Self::write_placeholder(&mut content, "placeholder", &self.settings.placeholder);
+        let mut apps: Vec<_> = self.settings.app_placeholders.iter().collect();
+        apps.sort_by(|a, b| a.0.cmp(b.0));
+        for (app_id, placeholder) in apps {
+            Self::write_placeholder(&mut content, &format!("placeholder.{}", app_id), placeholder);
+        }
+        
         // Custom settings
         if !self.settings.custom.is_empty() {
             content.push_str("[custom]\n");
@@ -323,7 +857,7 @@ impl WsdgSettingsManager {
             }
         }
         
-        fs::write(&self.settings_path, content)
+        write_atomic(&self.settings_path, content)
             .map_err(|e| SettingsError::SaveFailed(e.to_string()))?;
         
         Ok(())
@@ -350,8 +884,10 @@ impl WsdgSettingsManager {
     }
     
     /// Set custom setting
-    pub fn set_custom(&mut self, key: &str, value: &str) {
+    pub fn set_custom(&mut self, key: &str, value: &str) -> Result<(), SettingsError> {
+        self.check_writable(&format!("setting {}", key))?;
         self.settings.custom.insert(key.to_string(), value.to_string());
+        Ok(())
     }
     
     /// Load from manifest_rrt file (if supported)
@@ -365,6 +901,9 @@ impl WsdgSettingsManager {
         if !manifest_path.exists() {
             return Err(SettingsError::NotFound(manifest_path.display().to_string()));
         }
+        if let Some(policy) = &self.kiosk {
+            policy.check_manifest(manifest_path)?;
+        }
         
         // Parse manifest_rrt format (similar to regular settings but with extended support)
         let content = fs::read_to_string(manifest_path)?;
@@ -402,27 +941,74 @@ impl WsdgSettingsManager {
         F: Fn(&WsdgSettings) + Send + Sync + 'static,
     {
         self.wasma_sync_callback = Some(Box::new(callback));
-        println!("✅ WASMA integration enabled for WSDG Settings");
+        tracing::info!("WASMA integration enabled for WSDG settings");
+    }
+    
+    /// Enable differential WASMA sync: `callback` gets the settings plus what
+    /// changed since its previous call (the first delta is relative to the
+    /// settings at registration), and is not called when nothing changed
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// settings_manager.enable_wasma_delta_sync(move |settings, delta| {
+    ///     if delta.touches("theme") {
+    ///         gui.restyle(&settings.theme);
+    ///     }
+    ///     if let Some(opacity) = delta.get("window.opacity") {
+    ///         handler.set_default_opacity(opacity.parse().unwrap_or(1.0));
+    ///     }
+    /// });
+    /// ```
+    pub fn enable_wasma_delta_sync<F>(&mut self, callback: F)
+    where
+        F: Fn(&WsdgSettings, &SettingsDelta) + Send + Sync + 'static,
+    {
+        self.wasma_delta_callback = Some(Box::new(callback));
+        *self.synced.lock().unwrap() = Some(self.settings.clone());
+        tracing::info!("WASMA differential sync enabled for WSDG settings");
     }
     
     /// Disable WASMA integration
     pub fn disable_wasma_sync(&mut self) {
         self.wasma_sync_callback = None;
-        println!("⚠️  WASMA integration disabled");
+        self.wasma_delta_callback = None;
+        tracing::info!("WASMA integration disabled");
     }
     
     /// Check if WASMA integration is enabled
     pub fn is_wasma_sync_enabled(&self) -> bool {
-        self.wasma_sync_callback.is_some()
+        self.wasma_sync_callback.is_some() || self.wasma_delta_callback.is_some()
+    }
+    
+    /// Changes the differential callback has not seen yet
+    pub fn pending_delta(&self) -> SettingsDelta {
+        match self.synced.lock().unwrap().as_ref() {
+            Some(synced) => SettingsDelta::between(synced, &self.settings),
+            None => SettingsDelta::default(),
+        }
+    }
+    
+    /// Call the registered callbacks; false when none is registered
+    fn notify_wasma(&self) -> bool {
+        if let Some(ref callback) = self.wasma_sync_callback {
+            callback(&self.settings);
+        }
+        if let Some(ref callback) = self.wasma_delta_callback {
+            let delta = self.pending_delta();
+            *self.synced.lock().unwrap() = Some(self.settings.clone());
+            if !delta.is_empty() {
+                callback(&self.settings, &delta);
+            }
+        }
+        self.is_wasma_sync_enabled()
     }
     
     /// Trigger WASMA sync manually
     /// 
     /// This notifies WASMA about current settings without reloading from file
     pub fn trigger_wasma_sync(&self) {
-        if let Some(ref callback) = self.wasma_sync_callback {
-            callback(&self.settings);
-            println!("🔄 WASMA sync triggered");
+        if self.notify_wasma() {
+            tracing::debug!("WASMA sync triggered");
         }
     }
     
@@ -431,22 +1017,20 @@ impl WsdgSettingsManager {
         self.load()?;
         
         // Notify WASMA if callback is registered
-        if let Some(ref callback) = self.wasma_sync_callback {
-            callback(&self.settings);
-            println!("🔄 Settings loaded and synced to WASMA");
+        if self.notify_wasma() {
+            tracing::debug!("settings loaded and synced to WASMA");
         }
         
         Ok(())
     }
     
     /// Save settings and notify WASMA
-    pub fn save_and_sync(&self) -> Result<(), SettingsError> {
+    pub fn save_and_sync(&mut self) -> Result<(), SettingsError> {
         self.save()?;
         
         // Notify WASMA if callback is registered
-        if let Some(ref callback) = self.wasma_sync_callback {
-            callback(&self.settings);
-            println!("💾 Settings saved and synced to WASMA");
+        if self.notify_wasma() {
+            tracing::debug!("settings saved and synced to WASMA");
         }
         
         Ok(())
@@ -456,7 +1040,7 @@ impl WsdgSettingsManager {
     pub fn update_theme(&mut self, 
         name: Option<String>,
         dark_mode: Option<bool>,
-        accent_color: Option<String>,
+        accent_color: Option<Color>,
     ) {
         if let Some(n) = name {
             self.settings.theme.name = n;
@@ -488,6 +1072,11 @@ impl WsdgSettingsManager {
         self.trigger_wasma_sync();
     }
     
+    /// Configured font families that are not installed
+    pub fn validate_fonts(&self) -> Vec<MissingFont> {
+        self.settings.font.missing_fonts(FontCatalog::system())
+    }
+    
     /// Update window settings and sync to WASMA
     pub fn update_window(&mut self,
         width: Option<u32>,
@@ -512,6 +1101,41 @@ impl WsdgSettingsManager {
         self.trigger_wasma_sync();
     }
     
+    /// System-wide reduced-motion preference, the desktop equivalent of
+    /// `prefers-reduced-motion`: `gtk-enable-animations = false` in the
+    /// GTK 4/3 settings.ini
+    pub fn system_reduce_motion(&self) -> bool {
+        let mut dirs = Vec::new();
+        if let Some(xdg_config) = self.env.get("XDG_CONFIG_HOME") {
+            dirs.push(PathBuf::from(xdg_config));
+        } else if let Ok(home) = self.env.home_dir() {
+            dirs.push(home.join(".config"));
+        }
+        dirs.push(PathBuf::from("/etc/xdg"));
+
+        for dir in dirs {
+            for gtk in ["gtk-4.0", "gtk-3.0"] {
+                let Ok(content) = fs::read_to_string(dir.join(gtk).join("settings.ini")) else {
+                    continue;
+                };
+                for line in content.lines() {
+                    if let Some((key, value)) = line.split_once('=') {
+                        if key.trim() == "gtk-enable-animations" {
+                            return matches!(value.trim(), "0" | "false" | "FALSE" | "no");
+                        }
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Reduce motion when either the WSDG setting or the system preference asks for it
+    pub fn reduce_motion(&self) -> bool {
+        self.settings.theme.reduce_motion || self.system_reduce_motion()
+    }
+
     /// Get settings for WASMA consumption
     /// Returns a clone of current settings for thread-safe access
     pub fn get_wasma_settings(&self) -> WsdgSettings {
@@ -521,21 +1145,33 @@ impl WsdgSettingsManager {
     /// Export settings as WASMA-compatible format
     /// Returns settings that can be directly applied to WASMA windows
     pub fn export_for_wasma(&self) -> WasmaCompatibleSettings {
+        let fonts = self.settings.font.validated(FontCatalog::system());
         WasmaCompatibleSettings {
             window_width: self.settings.window.default_width,
             window_height: self.settings.window.default_height,
             window_decorations: self.settings.window.decorations,
             window_opacity: self.settings.window.opacity,
             theme_dark_mode: self.settings.theme.dark_mode,
-            theme_accent_color: self.settings.theme.accent_color.clone(),
+            theme_accent_color: self.settings.theme.accent_color,
+            reduce_motion: self.reduce_motion(),
             font_family: self.settings.font.family.clone(),
             font_size: self.settings.font.size,
+            font_fallback: fonts.fallback,
+            font_script_fallback: fonts.script_fallback,
             icon_theme: self.settings.icon.theme.clone(),
             icon_size: self.settings.icon.size,
         }
     }
 }
 
+/// Parse a color setting; an unreadable value keeps the current color
+fn parse_color(key: &str, value: &str, target: &mut Color) {
+    match value.parse() {
+        Ok(color) => *target = color,
+        Err(e) => tracing::warn!(key = %format_args!("theme.{}", key), error = %e, "unreadable color kept"),
+    }
+}
+
 /// WASMA-compatible settings structure
 /// 
 /// This structure contains only the settings that WASMA can directly apply
@@ -546,34 +1182,23 @@ pub struct WasmaCompatibleSettings {
     pub window_decorations: bool,
     pub window_opacity: f32,
     pub theme_dark_mode: bool,
-    pub theme_accent_color: String,
+    pub theme_accent_color: Color,
+    pub reduce_motion: bool,
     pub font_family: String,
     pub font_size: u32,
+    /// Installed families of the fallback chain, in order
+    pub font_fallback: Vec<String>,
+    /// Installed per-script families (CJK, Arabic, emoji), tried before `font_family`
+    pub font_script_fallback: BTreeMap<FontScript, Vec<String>>,
     pub icon_theme: String,
     pub icon_size: u32,
 }
 
 impl WasmaCompatibleSettings {
-    /// Convert hex color to RGB tuple (0.0-1.0 range)
+    /// Accent color as an RGB tuple (0.0-1.0 range)
     pub fn accent_color_rgb(&self) -> (f32, f32, f32) {
-        let hex = self.theme_accent_color.trim_start_matches('#');
-        
-        if hex.len() == 6 {
-            if let (Ok(r), Ok(g), Ok(b)) = (
-                u8::from_str_radix(&hex[0..2], 16),
-                u8::from_str_radix(&hex[2..4], 16),
-                u8::from_str_radix(&hex[4..6], 16),
-            ) {
-                return (
-                    r as f32 / 255.0,
-                    g as f32 / 255.0,
-                    b as f32 / 255.0,
-                );
-            }
-        }
-        
-        // Default blue
-        (0.2, 0.6, 1.0)
+        let [r, g, b, _] = self.theme_accent_color.to_f32();
+        (r, g, b)
     }
 }
 
@@ -614,15 +1239,63 @@ size = 12
         assert_eq!(manager.settings.font.size, 12);
     }
     
+    #[test]
+    fn test_placeholder_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = WsdgEnvBuilder::new().build();
+        let mut manager = WsdgSettingsManager::new(env);
+        manager.settings_path = dir.path().join("settings.conf");
+        
+        manager.parse_settings(r#"
+[placeholder]
+message = "Bağlantı bekleniyor…"
+
+[placeholder.org.example.Player]
+mode = "freeze"
+show_icon = false
+        "#).unwrap();
+        
+        let settings = manager.settings();
+        assert_eq!(settings.placeholder_for("org.other.App"), &settings.placeholder);
+        let player = settings.placeholder_for("org.example.Player");
+        assert_eq!(player.mode, "freeze");
+        assert!(!player.show_icon && player.show_elapsed);
+        assert_eq!(player.message, "Bağlantı bekleniyor…");
+        
+        // Per-app sections survive a save/load round trip
+        manager.save().unwrap();
+        let path = manager.settings_path.clone();
+        let mut reloaded = WsdgSettingsManager::new(WsdgEnvBuilder::new().build());
+        reloaded.settings_path = path;
+        reloaded.load().unwrap();
+        assert_eq!(reloaded.settings().app_placeholders, manager.settings().app_placeholders);
+        assert!(reloaded.get_custom("placeholder.org.example.Player.mode").is_none());
+    }
+    
     #[test]
     fn test_custom_settings() {
         let env = WsdgEnvBuilder::new().build();
         let mut manager = WsdgSettingsManager::new(env);
         
-        manager.set_custom("my_key", "my_value");
+        manager.set_custom("my_key", "my_value").unwrap();
         assert_eq!(manager.get_custom("my_key"), Some(&"my_value".to_string()));
     }
     
+    #[test]
+    fn test_kiosk_settings_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("settings.conf"), "[font]\nfamily = \"Kiosk Sans\"\n").unwrap();
+        
+        let env = WsdgEnvBuilder::new().build();
+        let mut manager = WsdgSettingsManager::new(env).with_kiosk_policy(KioskPolicy::new(dir.path()));
+        manager.load().unwrap();
+        assert_eq!(manager.settings().font.family, "Kiosk Sans");
+        
+        assert!(matches!(manager.set_custom("my_key", "my_value"), Err(SettingsError::PolicyDenied(_))));
+        assert_eq!(manager.get_custom("my_key"), None);
+        assert!(matches!(manager.save(), Err(SettingsError::PolicyDenied(_))));
+    }
+    
     #[test]
     fn test_wasma_integration() {
         use std::sync::{Arc, Mutex};
@@ -693,4 +1366,183 @@ size = 12
         
         println!("✅ Load and sync test passed");
     }
+    
+    #[test]
+    fn test_reduce_motion_from_gtk_settings() {
+        let config = std::env::temp_dir().join(format!("wsdg-motion-{}", std::process::id()));
+        fs::create_dir_all(config.join("gtk-3.0")).unwrap();
+        fs::write(config.join("gtk-3.0/settings.ini"), "[Settings]\ngtk-enable-animations=false\n").unwrap();
+        
+        let env = WsdgEnvBuilder::new()
+            .var("XDG_CONFIG_HOME", config.to_string_lossy())
+            .build();
+        let mut manager = WsdgSettingsManager::new(env);
+        
+        assert!(manager.system_reduce_motion());
+        assert!(manager.export_for_wasma().reduce_motion);
+        
+        manager.parse_settings("[theme]\nreduce_motion = true\n").unwrap();
+        assert!(manager.settings.theme.reduce_motion);
+        
+        fs::remove_dir_all(&config).ok();
+    }
+    
+    #[test]
+    fn test_telemetry_opt_in() {
+        let env = WsdgEnvBuilder::new().build();
+        let mut manager = WsdgSettingsManager::new(env);
+        assert!(!manager.settings.telemetry.enabled);
+        
+        manager.parse_settings("[telemetry]\nenabled = yes\nendpoint = \"http://127.0.0.1:9090/wasma\"\n").unwrap();
+        assert!(manager.settings.telemetry.enabled);
+        assert_eq!(manager.settings.telemetry.endpoint, "http://127.0.0.1:9090/wasma");
+    }
+    
+    #[test]
+    fn test_settings_delta_sync() {
+        use std::sync::Arc;
+        
+        let env = WsdgEnvBuilder::new().build();
+        let mut manager = WsdgSettingsManager::new(env);
+        let deltas = Arc::new(Mutex::new(Vec::new()));
+        let seen = deltas.clone();
+        manager.enable_wasma_delta_sync(move |_, delta| seen.lock().unwrap().push(delta.clone()));
+        
+        manager.update_window(None, None, None, Some(0.8));
+        manager.update_theme(None, Some(false), None);
+        manager.parse_settings("[placeholder.org.example.Player]\nmode = \"blank\"\n").unwrap();
+        manager.trigger_wasma_sync();
+        
+        // Only the opacity; then nothing (dark mode was already off); then the new per-app placeholder
+        let deltas = deltas.lock().unwrap();
+        assert_eq!(deltas.len(), 2);
+        assert_eq!(deltas[0].changes, [SettingChange {
+            key: "window.opacity".to_string(),
+            old: Some("1".to_string()),
+            new: Some("0.8".to_string()),
+        }]);
+        assert!(!deltas[0].touches("theme"));
+        assert!(deltas[1].touches("placeholder.org.example.Player"));
+        assert_eq!(deltas[1].get("placeholder.org.example.Player.mode"), Some("blank"));
+        assert!(deltas[1].changes.iter().all(|change| change.old.is_none()));
+        assert!(manager.pending_delta().is_empty());
+    }
+    
+    #[test]
+    fn test_webhook_settings() {
+        let env = WsdgEnvBuilder::new().build();
+        let mut manager = WsdgSettingsManager::new(env);
+        assert!(manager.settings.webhooks.urls.is_empty());
+        
+        manager.parse_settings("[webhooks]\nurls = \"http://alerts:8080/wasma, http://chat/hook\"\nevents = \"endpoint_down\"\nmax_per_minute = 5\n").unwrap();
+        let webhooks = &manager.settings.webhooks;
+        assert_eq!(webhooks.urls, ["http://alerts:8080/wasma", "http://chat/hook"]);
+        assert_eq!(webhooks.events, ["endpoint_down"]);
+        assert_eq!((webhooks.max_per_minute, webhooks.retries), (5, 3));
+    }
+    
+    #[test]
+    fn test_concurrent_saves_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let open = || {
+            let mut manager = WsdgSettingsManager::new(WsdgEnvBuilder::new().build());
+            manager.settings_path = dir.path().join("settings.conf");
+            manager.load().unwrap();
+            manager
+        };
+        let mut daemon = open();
+        daemon.set_custom("session", "1").unwrap();
+        daemon.save().unwrap();
+        let mut app = open();
+        
+        // Both changed settings since their load; the daemon saves first
+        daemon.settings_mut().theme.name = "nord".to_string();
+        daemon.settings_mut().font.size = 14;
+        daemon.settings_mut().custom.remove("session");
+        assert_eq!(daemon.save_merged().unwrap(), MergeReport::default());
+        
+        app.settings_mut().font.family = "Inter".to_string();
+        app.settings_mut().font.size = 12;
+        let report = app.save_merged().unwrap();
+        assert_eq!(report.merged.iter().map(|c| c.key.as_str()).collect::<Vec<_>>(), ["custom.session", "theme.name"]);
+        assert_eq!(report.conflicts, [SettingConflict {
+            key: "font.size".to_string(),
+            base: Some("11".to_string()),
+            ours: Some("12".to_string()),
+            theirs: Some("14".to_string()),
+        }]);
+        assert_eq!(app.settings().theme.name, "nord");
+        assert!(app.get_custom("session").is_none());
+        
+        // The daemon picks up the app's save without conflicts
+        let report = daemon.save_merged().unwrap();
+        assert!(!report.has_conflicts());
+        let mut reloaded = open();
+        reloaded.load().unwrap();
+        let font = &reloaded.settings().font;
+        assert_eq!((font.family.as_str(), font.size), ("Inter", 12));
+        assert_eq!(reloaded.settings().theme.name, "nord");
+        assert!(dir.path().join("settings.conf.lock").exists());
+    }
+    
+    #[test]
+    fn test_font_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = WsdgSettingsManager::new(WsdgEnvBuilder::new().build());
+        manager.settings_path = dir.path().join("settings.conf");
+        manager.parse_settings("[font]\nfamily = \"Inter\"\nfallback = \"DejaVu Sans, Missing Sans,\"\ncjk = \"Noto Sans CJK SC\"\nemoji = \"Noto Color Emoji, Twemoji\"\narabic = \"\"\n").unwrap();
+        let font = &manager.settings().font;
+        assert_eq!(font.fallback, ["DejaVu Sans", "Missing Sans"]);
+        assert_eq!(font.chain(Some(FontScript::Cjk)), ["Noto Sans CJK SC", "Inter", "DejaVu Sans", "Missing Sans"]);
+        assert_eq!(font.chain(Some(FontScript::Arabic)), ["Inter", "DejaVu Sans", "Missing Sans"]);
+        assert_eq!(font.chain(None), font.chain(FontScript::of('a')));
+        
+        let catalog = FontCatalog::new(["Inter", "DejaVu Sans", "Noto Sans CJK SC", "Noto Color Emoji"]);
+        let missing: Vec<_> = font.missing_fonts(&catalog).into_iter().map(|m| (m.key, m.family)).collect();
+        assert_eq!(missing, [
+            ("font.fallback".to_string(), "Missing Sans".to_string()),
+            ("font.emoji".to_string(), "Twemoji".to_string()),
+        ]);
+        let validated = font.validated(&catalog);
+        assert_eq!(validated.fallback, ["DejaVu Sans"]);
+        assert_eq!(validated.script_fallback[&FontScript::Emoji], ["Noto Color Emoji"]);
+        // Nothing discovered: nothing to validate against
+        assert!(font.missing_fonts(&FontCatalog::default()).is_empty());
+        
+        // Lists survive a save
+        let entries = manager.settings().entries();
+        assert_eq!(entries["font.emoji"], "Noto Color Emoji, Twemoji");
+        assert_eq!(entries["font.arabic"], "");
+        let script_fallback = font.script_fallback.clone();
+        manager.save().unwrap();
+        let mut reloaded = WsdgSettingsManager::new(WsdgEnvBuilder::new().build());
+        reloaded.settings_path = manager.settings_path.clone();
+        reloaded.load().unwrap();
+        assert_eq!(reloaded.settings().font.script_fallback, script_fallback);
+        assert_eq!(reloaded.settings().font.fallback, ["DejaVu Sans", "Missing Sans"]);
+    }
+    
+    #[test]
+    fn test_theme_colors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.conf");
+        fs::write(&path, "[theme]\naccent_color = \"hsl(0, 100%, 50%)\"\nbackground_color = \"#fff\"\nforeground_color = \"not-a-color\"\n").unwrap();
+        
+        let mut manager = WsdgSettingsManager::new(WsdgEnvBuilder::new().build());
+        manager.settings_path = path.clone();
+        manager.load().unwrap();
+        let theme = &manager.settings().theme;
+        assert_eq!(theme.accent_color, Color::rgb(0xff, 0, 0));
+        assert_eq!(theme.background_color, Color::WHITE);
+        // Unreadable colors keep the default
+        assert_eq!(theme.foreground_color, Color::BLACK);
+        assert_eq!(manager.export_for_wasma().accent_color_rgb(), (1.0, 0.0, 0.0));
+        
+        // Written back as hex
+        manager.update_theme(None, None, Some(Color::rgba(0x35, 0x84, 0xe4, 0x80)));
+        manager.save().unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.contains("accent_color = \"#3584e480\""), "{}", written);
+        assert!(written.contains("background_color = \"#ffffff\""), "{}", written);
+    }
 }
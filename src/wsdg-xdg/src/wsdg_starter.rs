@@ -7,10 +7,17 @@
 use std::path::{Path, PathBuf};
 use std::fs;
 use std::collections::HashMap;
-use std::process::{Command, Child};
+use std::process::{Command, Child, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
+use wsdg_app_manifest::{EnvConflict, EnvironmentConfig};
 
+use crate::sandbox::SandboxProfile;
+use crate::wsdg_atomic::write_atomic;
 use crate::wsdg_env::WsdgEnv;
+use crate::wsdg_starter_log::{self, RotatingLog, STARTER_LOG_KEEP, STARTER_LOG_MAX_BYTES};
+use crate::xdg_wsdg_translate::EnvPathParser;
 
 #[derive(Debug, Error)]
 pub enum StarterError {
@@ -42,6 +49,12 @@ pub struct StarterConfig {
     pub delay: Option<u32>,
     pub protocol: Option<String>,
     pub icon: Option<String>,
+    /// Manifest [environment] block, merged over the WSDG environment at launch
+    pub environment: EnvironmentConfig,
+    /// stdout/stderr go to the entry's rotated log (`log = false` inherits them)
+    pub capture_output: bool,
+    /// Sandbox profile the process runs under (`sandbox_level = 0..10`)
+    pub sandbox_level: Option<u8>,
 }
 
 impl Default for StarterConfig {
@@ -56,15 +69,100 @@ impl Default for StarterConfig {
             delay: None,
             protocol: None,
             icon: None,
+            environment: EnvironmentConfig::default(),
+            capture_output: true,
+            sandbox_level: None,
         }
     }
 }
 
+/// Crash-loop handling for supervised starter entries
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    /// Crashes in a row before the entry is marked failed
+    pub max_attempts: u32,
+    /// Delay before the first restart, doubled after every further crash
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// A run lasting at least this long resets the crash count
+    pub stable_after: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            stable_after: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Delay before restarting after the `crashes`-th crash in a row
+    pub fn backoff(&self, crashes: u32) -> Duration {
+        let factor = 1u32.checked_shl(crashes.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Supervision state of one starter entry
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntryStatus {
+    Running { pid: u32 },
+    /// Crashed, restarting at `restart_at`
+    Backoff { restart_at: SystemTime },
+    /// Crash loop: gave up after `max_attempts` crashes in a row
+    Failed,
+    /// Exited cleanly, not restarted
+    Exited,
+}
+
+impl EntryStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EntryStatus::Running { .. } => "running",
+            EntryStatus::Backoff { .. } => "backoff",
+            EntryStatus::Failed => "failed",
+            EntryStatus::Exited => "exited",
+        }
+    }
+}
+
+/// Status of a supervised entry, as shown by `wasma starter status`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StarterEntry {
+    pub app_name: String,
+    pub status: EntryStatus,
+    /// Crashes in a row
+    pub crashes: u32,
+    pub last_exit: Option<String>,
+}
+
+/// Something the supervisor did that the user should hear about
+#[derive(Debug, Clone, PartialEq)]
+pub enum StarterEvent {
+    Crashed { app_name: String, exit: String, crashes: u32, restart_in: Duration },
+    Restarted { app_name: String, pid: u32 },
+    Failed { app_name: String, exit: String, crashes: u32 },
+}
+
+struct Supervised {
+    config: StarterConfig,
+    child: Option<Child>,
+    started_at: Instant,
+    restart_at: Option<Instant>,
+    entry: StarterEntry,
+}
+
 /// WSDG Starter - Application startup manager
 pub struct WsdgStarter {
     env: WsdgEnv,
     config_dirs: Vec<PathBuf>,
     configs: HashMap<String, StarterConfig>,
+    policy: RestartPolicy,
+    supervised: HashMap<String, Supervised>,
 }
 
 impl WsdgStarter {
@@ -73,8 +171,15 @@ impl WsdgStarter {
             config_dirs: Self::get_config_directories(&env),
             env,
             configs: HashMap::new(),
+            policy: RestartPolicy::default(),
+            supervised: HashMap::new(),
         }
     }
+
+    pub fn with_restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
     
     /// Get starter configuration directories
     fn get_config_directories(env: &WsdgEnv) -> Vec<PathBuf> {
@@ -158,6 +263,8 @@ impl WsdgStarter {
                     "delay" => config.delay = value.parse().ok(),
                     "protocol" => config.protocol = Some(value.to_string()),
                     "icon" => config.icon = Some(value.to_string()),
+                    "sandbox_level" => config.sandbox_level = value.parse().ok(),
+                    "log" => config.capture_output = !(value == "false" || value == "no" || value == "0"),
                     key if key.starts_with("env.") => {
                         let env_key = key.strip_prefix("env.").unwrap();
                         config.env_vars.insert(env_key.to_string(), value.to_string());
@@ -212,16 +319,99 @@ impl WsdgStarter {
             cmd.env(key, value);
         }
         
+        // Manifest [environment]: rebuild the whole environment so `unset` takes effect
+        if !config.environment.is_empty() {
+            let mut vars = self.env.launch_environment(&EnvironmentConfig::default());
+            vars.extend(config.env_vars.clone());
+            config.environment.apply(&mut vars);
+            cmd.env_clear().envs(vars);
+        }
+        
+        // Isolation for the configured sandbox_level
+        if let Some(level) = config.sandbox_level {
+            SandboxProfile::for_level(level).apply(&mut cmd, &self.env, &config.app_name);
+        }
+        
+        // Output capture into the entry's log
+        let log = if config.capture_output {
+            match RotatingLog::open(&self.log_path(&config.app_name), STARTER_LOG_MAX_BYTES, STARTER_LOG_KEEP) {
+                Ok(log) => {
+                    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+                    Some(Arc::new(Mutex::new(log)))
+                }
+                Err(e) => {
+                    tracing::warn!(app = %config.app_name, error = %e, "starter log could not be opened");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        
         // Spawn process
-        cmd.spawn()
+        let mut child = cmd.spawn()
             .map_err(|e| StarterError::StartFailed(
                 format!("{}: {}", config.exec_path, e)
-            ))
+            ))?;
+        
+        if let Some(log) = log {
+            log.lock().unwrap()
+                .write_marker(&format!("{} started as pid {}", config.exec_path, child.id()))
+                .ok();
+            if let Some(stdout) = child.stdout.take() {
+                wsdg_starter_log::capture(log.clone(), stdout);
+            }
+            if let Some(stderr) = child.stderr.take() {
+                wsdg_starter_log::capture(log, stderr);
+            }
+        }
+        Ok(child)
+    }
+    
+    /// Live log file of a starter entry: $STATE/wsdg/starter-logs/<app>.log
+    pub fn log_path(&self, app_name: &str) -> PathBuf {
+        wsdg_starter_log::starter_log_path(&self.env, app_name)
+    }
+    
+    /// Attach a manifest [environment] block to a starter config.
+    /// Returns the entries that override variables defined in env.path
+    pub fn apply_manifest_environment(
+        &self,
+        config: &mut StarterConfig,
+        environment: &EnvironmentConfig,
+    ) -> Vec<EnvConflict> {
+        config.environment = environment.clone();
+        
+        let defined = EnvPathParser::from_default()
+            .and_then(|parser| parser.load())
+            .map(|env_path| env_path.defined_vars())
+            .unwrap_or_default();
+        
+        let conflicts = environment.conflicts(&defined);
+        for conflict in &conflicts {
+            tracing::warn!(app = %config.app_name, "manifest environment {}", conflict);
+        }
+        conflicts
     }
     
     /// Start all autostart applications
     pub fn start_autostart_apps(&mut self) -> Vec<Result<Child, StarterError>> {
-        let mut results = Vec::new();
+        self.autostart_configs()
+            .iter()
+            .map(|config| self.start_with_config(config))
+            .collect()
+    }
+
+    /// Start all autostart applications under crash-loop supervision
+    pub fn supervise_autostart_apps(&mut self) -> Vec<Result<u32, StarterError>> {
+        self.autostart_configs()
+            .into_iter()
+            .map(|config| self.supervise_config(config))
+            .collect()
+    }
+
+    fn autostart_configs(&mut self) -> Vec<StarterConfig> {
+        let mut configs = Vec::new();
         
         // Scan all config directories
         for dir in &self.config_dirs.clone() {
@@ -238,7 +428,7 @@ impl WsdgStarter {
                             // Try to load and check autostart
                             if let Ok(config) = self.load_config(stem) {
                                 if config.autostart {
-                                    results.push(self.start_with_config(&config));
+                                    configs.push(config);
                                 }
                             }
                         }
@@ -247,7 +437,200 @@ impl WsdgStarter {
             }
         }
         
-        results
+        configs
+    }
+
+    // ========================================================================
+    // SUPERVISION
+    // ========================================================================
+
+    /// Start an app and restart it with backoff when it crashes
+    pub fn supervise(&mut self, app_name: &str) -> Result<u32, StarterError> {
+        let config = self.load_config(app_name)?;
+        self.supervise_config(config)
+    }
+
+    /// Supervise a specific configuration, returns the pid
+    pub fn supervise_config(&mut self, config: StarterConfig) -> Result<u32, StarterError> {
+        let child = self.start_with_config(&config)?;
+        let pid = child.id();
+        let entry = StarterEntry {
+            app_name: config.app_name.clone(),
+            status: EntryStatus::Running { pid },
+            crashes: 0,
+            last_exit: None,
+        };
+        self.supervised.insert(config.app_name.clone(), Supervised {
+            config,
+            child: Some(child),
+            started_at: Instant::now(),
+            restart_at: None,
+            entry,
+        });
+        self.write_status();
+        Ok(pid)
+    }
+
+    /// Reap exited children and restart the ones whose backoff ran out.
+    /// Call periodically; the status file is rewritten on every change.
+    pub fn poll(&mut self) -> Vec<StarterEvent> {
+        let mut events = Vec::new();
+        let now = Instant::now();
+
+        let mut names: Vec<String> = self.supervised.keys().cloned().collect();
+        names.sort();
+        for name in names {
+            let Some(mut app) = self.supervised.remove(&name) else { continue };
+
+            let exited = match app.child.as_mut().map(|c| c.try_wait()) {
+                Some(Ok(Some(status))) => Some(status),
+                Some(Err(e)) => {
+                    tracing::warn!(starter = %name, error = %e, "starter could not be polled");
+                    None
+                }
+                _ => None,
+            };
+            if let Some(status) = exited {
+                app.child = None;
+                if app.config.capture_output {
+                    let marker = RotatingLog::open(&self.log_path(&name), STARTER_LOG_MAX_BYTES, STARTER_LOG_KEEP)
+                        .and_then(|mut log| log.write_marker(&describe_exit(status)));
+                    if let Err(e) = marker {
+                        tracing::warn!(app = %name, error = %e, "starter log could not be written");
+                    }
+                }
+                if status.success() {
+                    app.entry.status = EntryStatus::Exited;
+                    app.entry.crashes = 0;
+                    app.entry.last_exit = Some(describe_exit(status));
+                } else {
+                    let stable = now.duration_since(app.started_at) >= self.policy.stable_after;
+                    if stable {
+                        app.entry.crashes = 0;
+                    }
+                    events.push(self.record_crash(&mut app, describe_exit(status), now));
+                }
+            }
+
+            if app.restart_at.is_some_and(|at| at <= now) {
+                app.restart_at = None;
+                match self.start_with_config(&app.config) {
+                    Ok(child) => {
+                        let pid = child.id();
+                        app.child = Some(child);
+                        app.started_at = now;
+                        app.entry.status = EntryStatus::Running { pid };
+                        events.push(StarterEvent::Restarted { app_name: name.clone(), pid });
+                    }
+                    Err(e) => events.push(self.record_crash(&mut app, e.to_string(), now)),
+                }
+            }
+
+            self.supervised.insert(name, app);
+        }
+
+        if !events.is_empty() {
+            self.write_status();
+        }
+        events
+    }
+
+    fn record_crash(&self, app: &mut Supervised, exit: String, now: Instant) -> StarterEvent {
+        app.entry.crashes += 1;
+        app.entry.last_exit = Some(exit.clone());
+        let app_name = app.config.app_name.clone();
+        let crashes = app.entry.crashes;
+
+        if crashes >= self.policy.max_attempts {
+            app.entry.status = EntryStatus::Failed;
+            return StarterEvent::Failed { app_name, exit, crashes };
+        }
+        let restart_in = self.policy.backoff(crashes);
+        app.restart_at = Some(now + restart_in);
+        app.entry.status = EntryStatus::Backoff { restart_at: SystemTime::now() + restart_in };
+        StarterEvent::Crashed { app_name, exit, crashes, restart_in }
+    }
+
+    /// Restart a failed entry with a fresh crash count
+    pub fn retry(&mut self, app_name: &str) -> Result<u32, StarterError> {
+        let app = self.supervised.remove(app_name)
+            .ok_or_else(|| StarterError::ConfigNotFound(app_name.to_string()))?;
+        self.supervise_config(app.config)
+    }
+
+    /// Current status of every supervised entry, by name
+    pub fn status(&self) -> Vec<StarterEntry> {
+        let mut entries: Vec<StarterEntry> = self.supervised.values().map(|s| s.entry.clone()).collect();
+        entries.sort_by(|a, b| a.app_name.cmp(&b.app_name));
+        entries
+    }
+
+    /// Status file read by `wasma starter status`: $STATE/wsdg/starter.status
+    pub fn status_path(&self) -> PathBuf {
+        self.env.state_dir()
+            .unwrap_or_else(|_| std::env::temp_dir())
+            .join("wsdg")
+            .join("starter.status")
+    }
+
+    fn write_status(&self) {
+        let path = self.status_path();
+        let mut content = String::new();
+        for entry in self.status() {
+            let (pid, restart_at) = match &entry.status {
+                EntryStatus::Running { pid } => (pid.to_string(), "-".to_string()),
+                EntryStatus::Backoff { restart_at } => {
+                    let secs = restart_at.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+                    ("-".to_string(), secs.to_string())
+                }
+                _ => ("-".to_string(), "-".to_string()),
+            };
+            content.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\n",
+                entry.app_name,
+                entry.status.name(),
+                entry.crashes,
+                pid,
+                restart_at,
+                entry.last_exit.as_deref().unwrap_or("-"),
+            ));
+        }
+
+        let written = path.parent()
+            .map(fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| write_atomic(&path, content));
+        if let Err(e) = written {
+            tracing::warn!(path = %path.display(), error = %e, "starter status could not be written");
+        }
+    }
+
+    /// Parse a status file written by a supervising starter
+    pub fn read_status(path: &Path) -> Result<Vec<StarterEntry>, StarterError> {
+        let content = fs::read_to_string(path)?;
+        let mut entries = Vec::new();
+
+        for line in content.lines().filter(|l| !l.trim().is_empty()) {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [name, state, crashes, pid, restart_at, last_exit] = fields[..] else {
+                return Err(StarterError::InvalidConfig(format!("status line: {}", line)));
+            };
+            let status = match state {
+                "running" => EntryStatus::Running { pid: pid.parse().unwrap_or(0) },
+                "backoff" => EntryStatus::Backoff {
+                    restart_at: UNIX_EPOCH + Duration::from_secs(restart_at.parse().unwrap_or(0)),
+                },
+                "failed" => EntryStatus::Failed,
+                _ => EntryStatus::Exited,
+            };
+            entries.push(StarterEntry {
+                app_name: name.to_string(),
+                status,
+                crashes: crashes.parse().unwrap_or(0),
+                last_exit: (last_exit != "-").then(|| last_exit.to_string()),
+            });
+        }
+        Ok(entries)
     }
     
     /// Create a new starter configuration
@@ -304,11 +687,19 @@ impl WsdgStarter {
             content.push_str(&format!("icon = \"{}\"\n", icon));
         }
         
+        if !config.capture_output {
+            content.push_str("log = false\n");
+        }
+        
+        if let Some(level) = config.sandbox_level {
+            content.push_str(&format!("sandbox_level = {}\n", level));
+        }
+        
         for (key, value) in &config.env_vars {
             content.push_str(&format!("env.{} = \"{}\"\n", key, value));
         }
         
-        fs::write(config_path, content)?;
+        write_atomic(config_path, content)?;
         Ok(())
     }
     
@@ -350,6 +741,17 @@ impl WsdgStarter {
     }
 }
 
+/// "exit code 1" / "killed by signal 11"
+fn describe_exit(status: ExitStatus) -> String {
+    use std::os::unix::process::ExitStatusExt;
+
+    match (status.code(), status.signal()) {
+        (Some(code), _) => format!("exit code {}", code),
+        (None, Some(signal)) => format!("killed by signal {}", signal),
+        _ => status.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -365,6 +767,86 @@ mod tests {
         assert_eq!(config.exec_path, "/usr/bin/testapp");
     }
     
+    #[test]
+    fn test_crash_loop_backoff() {
+        let state = tempfile::tempdir().unwrap();
+        let env = WsdgEnvBuilder::new().var("STATE", state.path().display().to_string()).build();
+        let policy = RestartPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            stable_after: Duration::from_secs(60),
+        };
+        let mut starter = WsdgStarter::new(env).with_restart_policy(policy);
+        assert_eq!(RestartPolicy::default().backoff(1), Duration::from_secs(1));
+        assert_eq!(RestartPolicy::default().backoff(3), Duration::from_secs(4));
+        assert_eq!(RestartPolicy::default().backoff(40), Duration::from_secs(60));
+
+        let config = StarterConfig {
+            app_name: "crashy".to_string(),
+            exec_path: "/bin/sh".to_string(),
+            args: vec!["-c".to_string(), "exit 3".to_string()],
+            ..Default::default()
+        };
+        starter.supervise_config(config).unwrap();
+
+        let mut events = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while starter.status()[0].status != EntryStatus::Failed && Instant::now() < deadline {
+            events.extend(starter.poll());
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let crashes = events.iter().filter(|e| matches!(e, StarterEvent::Crashed { .. })).count();
+        assert_eq!(crashes, 2);
+        assert!(events.contains(&StarterEvent::Failed {
+            app_name: "crashy".to_string(),
+            exit: "exit code 3".to_string(),
+            crashes: 3,
+        }));
+
+        // `wasma starter status` sees the same state
+        let entries = WsdgStarter::read_status(&starter.status_path()).unwrap();
+        assert_eq!(entries, starter.status());
+        assert_eq!(entries[0].last_exit.as_deref(), Some("exit code 3"));
+    }
+
+    #[test]
+    fn test_output_capture_and_rotation() {
+        let state = tempfile::tempdir().unwrap();
+        let env = WsdgEnvBuilder::new().var("STATE", state.path().display().to_string()).build();
+        let starter = WsdgStarter::new(env);
+
+        let config = StarterConfig {
+            app_name: "chatty".to_string(),
+            exec_path: "/bin/sh".to_string(),
+            args: vec!["-c".to_string(), "echo out; echo err >&2".to_string()],
+            ..Default::default()
+        };
+        starter.start_with_config(&config).unwrap().wait().unwrap();
+
+        // Capture threads drain the pipes after the exit
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut lines = Vec::new();
+        while lines.len() < 3 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+            lines = crate::wsdg_starter_log::tail(&starter.log_path("chatty"), 10).unwrap();
+        }
+        assert!(lines[0].contains("started as pid"));
+        assert!(lines.contains(&"out".to_string()));
+        assert!(lines.contains(&"err".to_string()));
+
+        // Rotation keeps the newest lines reachable through tail()
+        let path = state.path().join("rotating.log");
+        let mut log = RotatingLog::open(&path, 16, 2).unwrap();
+        for n in 0..10 {
+            log.write_line(format!("line {}\n", n).as_bytes()).unwrap();
+        }
+        assert!(path.with_extension("log.2").exists());
+        assert!(!path.with_extension("log.3").exists());
+        assert_eq!(crate::wsdg_starter_log::tail(&path, 3).unwrap(), vec!["line 7", "line 8", "line 9"]);
+    }
+    
     #[test]
     fn test_config_filename_generation() {
         let test_cases = vec![
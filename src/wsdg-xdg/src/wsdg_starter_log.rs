@@ -0,0 +1,137 @@
+// WSDG Starter Logs - Output capture for starter-launched processes
+// stdout/stderr of each starter entry go to $STATE/wsdg/starter-logs/<app>.log,
+// rotated to <app>.log.1 .. <app>.log.N once the file passes a size limit
+// Part of WASMA (Windows Assignment System Monitoring Architecture)
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::wsdg_env::WsdgEnv;
+
+/// Size at which a starter log is rotated
+pub const STARTER_LOG_MAX_BYTES: u64 = 1024 * 1024;
+/// Rotated files kept next to the live log
+pub const STARTER_LOG_KEEP: usize = 3;
+
+/// Directory holding every starter log: $STATE/wsdg/starter-logs
+pub fn starter_log_dir(env: &WsdgEnv) -> PathBuf {
+    env.state_dir()
+        .unwrap_or_else(|_| std::env::temp_dir())
+        .join("wsdg")
+        .join("starter-logs")
+}
+
+/// Live log file of one starter entry
+pub fn starter_log_path(env: &WsdgEnv, app_name: &str) -> PathBuf {
+    starter_log_dir(env).join(format!("{}.log", app_name))
+}
+
+/// Append-only log file that rotates itself past `max_bytes`
+pub struct RotatingLog {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingLog {
+    pub fn open(path: &Path, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let written = file.metadata()?.len();
+        Ok(Self { path: path.to_path_buf(), file, written, max_bytes, keep })
+    }
+
+    /// Write one line (with its newline), rotating first if it would not fit
+    pub fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.written > 0 && self.written + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(line)?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    /// Marker line between runs: "---- <text> (unix time <secs>) ----"
+    pub fn write_marker(&mut self, text: &str) -> io::Result<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        self.write_line(format!("---- {} (unix time {}) ----\n", text, now).as_bytes())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.keep == 0 {
+            self.file.set_len(0)?;
+        } else {
+            for n in (1..self.keep).rev() {
+                let from = rotated_path(&self.path, n);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, n + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+            self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+}
+
+/// `<app>.log.<n>`
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+/// Copy a child's output stream into the log line by line until it closes
+pub fn capture(log: Arc<Mutex<RotatingLog>>, stream: impl Read + Send + 'static) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if !line.ends_with(b"\n") {
+                        line.push(b'\n');
+                    }
+                    if let Err(e) = log.lock().unwrap().write_line(&line) {
+                        tracing::warn!(error = %e, "starter log line could not be written");
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Last `lines` lines of a starter log, reaching into rotated files as needed
+pub fn tail(path: &Path, lines: usize) -> io::Result<Vec<String>> {
+    if !path.exists() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} does not exist", path.display())));
+    }
+
+    let mut collected: Vec<String> = Vec::new();
+    let files = std::iter::once(path.to_path_buf())
+        .chain((1..=STARTER_LOG_KEEP).map(|n| rotated_path(path, n)));
+    for file in files {
+        if collected.len() >= lines {
+            break;
+        }
+        let Ok(content) = fs::read(&file) else { break };
+        let mut older: Vec<String> = String::from_utf8_lossy(&content).lines().map(str::to_string).collect();
+        older.append(&mut collected);
+        collected = older;
+    }
+
+    let skip = collected.len().saturating_sub(lines);
+    Ok(collected.split_off(skip))
+}
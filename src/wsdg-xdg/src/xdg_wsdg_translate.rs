@@ -4,7 +4,7 @@
 // Part of WASMA (Windows Assignment System Monitoring Architecture)
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::env;
 use thiserror::Error;
@@ -87,6 +87,15 @@ impl Default for EnvConfig {
     }
 }
 
+impl EnvConfig {
+    /// Variables env.path defines for every session (exports and XDG paths)
+    pub fn defined_vars(&self) -> HashMap<String, String> {
+        let mut vars = self.xdg_paths.clone();
+        vars.extend(self.std_exports.iter().map(|(k, v)| (k.clone(), v.clone())));
+        vars
+    }
+}
+
 pub struct EnvPathParser {
     config_path: PathBuf,
 }
@@ -97,6 +106,11 @@ impl EnvPathParser {
     }
     
     pub fn from_default() -> Result<Self, TranslateError> {
+        // Kiosk mode reads the vendor env.path only
+        if let Some(policy) = crate::wsdg_env::WsdgEnv::new().kiosk() {
+            return Ok(Self::new(policy.env_path()));
+        }
+        
         // Try to find env.path in standard locations
         let mut possible_paths = vec![
             PathBuf::from("/etc/wsdg/env.path"),
@@ -117,6 +131,10 @@ impl EnvPathParser {
             "env.path not found in standard locations".to_string()
         ))
     }
+
+    pub fn config_path(&self) -> &Path {
+        &self.config_path
+    }
     
     pub fn load(&self) -> Result<EnvConfig, TranslateError> {
         let content = fs::read_to_string(&self.config_path)
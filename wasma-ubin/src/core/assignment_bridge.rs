@@ -3,7 +3,7 @@
 // UBIN window'ları Assignment'a bağlar, lease/task enforce eder
 // Backend'den Assignment alır, UBIN'e zorla uygular
 
-use crate::{Assignment, ExecutionMode, ResourceMode, WBackend};
+use crate::{Assignment, ExecutionMode, MonitorReport, ResourceMode, WBackend};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -23,7 +23,7 @@ impl UbinAssignmentBridge {
 
     /// Yeni Assignment yarat ve backend'e ekle
     pub fn create_assignment(&self, mode: ExecutionMode) -> Assignment {
-        let mut assignment = Assignment::new(self.backend.list_assignments().len() as u32 + 1);
+        let mut assignment = Assignment::new(self.backend.allocate_id());
         assignment.execution_mode = mode;
 
         // Otomatik bind ve task başlat
@@ -64,6 +64,7 @@ impl UbinAssignmentBridge {
     /// Assignment'ı backend'den kaldır ve task'ı durdur
     pub fn terminate_assignment(&self, assignment: &mut Assignment) {
         assignment.stop_task();
+        self.backend.remove_assignment(assignment.id);
         println!("🛑 Assignment {} terminated – Task stopped", assignment.id);
 
         // Backend cycle – cleanup
@@ -77,8 +78,10 @@ impl UbinAssignmentBridge {
         assignments
     }
 
-    /// Backend monitor çıktısını tetikle
-    pub fn monitor_backend(&self) {
-        self.backend.run_cycle();  // monitor içinde
+    /// Backend döngüsünü çalıştır, monitor raporunu yazdır ve döndür
+    pub fn monitor_backend(&self) -> MonitorReport {
+        let report = self.backend.run_cycle();
+        println!("{}", report);
+        report
     }
 }
\ No newline at end of file
@@ -137,6 +137,11 @@ impl UbinConvergenceEngine {
     }
 
     fn inject_animations(&self) -> bool {
+        // Hareket azaltma açıksa (WSDG ayarı / gtk-enable-animations) animasyon enjekte edilmez
+        if wbackend::theme::reduce_motion() {
+            println!("🐢 Reduce motion active – skipping animation polyfill");
+            return false;
+        }
         println!("⚡ Injecting smooth entrance/exit animations – easing curves");
         true
     }
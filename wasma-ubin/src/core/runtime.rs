@@ -34,7 +34,11 @@ pub struct UbinRuntime {
 
 impl UbinRuntime {
     pub fn initialize() -> Self {
-        let backend = Arc::new(WBackend::new(ResourceMode::Auto));
+        Self::initialize_with_mode(ResourceMode::Auto)
+    }
+
+    pub fn initialize_with_mode(resource_mode: ResourceMode) -> Self {
+        let backend = Arc::new(WBackend::new(resource_mode));
         let convergence_engine = UbinConvergenceEngine::initiate_global_convergence();
 
         println!("♾️ UBIN RUNTIME INITIALIZED – Eternal dominion cycle ready");
@@ -90,7 +94,8 @@ pub fn run_eternal_dominion(&mut self) {
     while self.running && !self.windows.is_empty() {
         let frame_start = Instant::now();
 
-        self.backend.run_cycle();
+        // Frame başına sadece zamanı gelen backend aşamaları (bkz. CycleCadence)
+        self.backend.run_due();
 
         // 1. Window id’lerini önceden topla
         let window_ids: Vec<u32> = self.windows.keys().cloned().collect();
@@ -120,6 +125,7 @@ pub fn run_eternal_dominion(&mut self) {
         for id in terminated {
             if let Some(mut window) = self.windows.remove(&id) {
                 window.assignment.stop_task();
+                self.backend.remove_assignment(window.assignment.id);
                 println!("🧹 Window {} cleaned up", id);
             }
         }
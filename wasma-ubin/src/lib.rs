@@ -9,7 +9,11 @@ pub mod transmutation;
 pub mod utils;
 
 // DÜZELTME: wbackend'den import et
-pub use wbackend::{Assignment, ExecutionMode, ResourceMode, WBackend};
+pub use wbackend::{Assignment, ExecutionMode, MonitorReport, ResourceMode, WBackend};
+// OSD: UBIN uygulamaları `wasma_ubin::osd::show(...)` ile bildirim gösterebilir
+pub use wbackend::osd;
+// Erişilebilirlik temaları: GUI yöneticisiyle aynı token seti
+pub use wbackend::theme as theme_tokens;
 
 // Re-export core types
 pub use core::abi::*;
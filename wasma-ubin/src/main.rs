@@ -48,18 +48,25 @@ struct Cli {
         help = "Resource allocation strategy"
     )]
     resource_mode: ResourceModeArg,
+
+    /// Accessibility palette (inherited from WASMA_A11Y_THEME when omitted)
+    #[arg(long, global = true, value_enum, help = "Accessibility theme: standard, high-contrast, deuteranopia")]
+    a11y_theme: Option<theme_tokens::A11yTheme>,
 }
 
 #[derive(Clone, Copy, ValueEnum)]
 enum ResourceModeArg {
     /// Automatic resource detection and allocation
     Auto,
+    /// Resources only as assigned explicitly, no automatic rebalancing
+    Manual,
 }
 
 impl From<ResourceModeArg> for ResourceMode {
     fn from(val: ResourceModeArg) -> Self {
         match val {
             ResourceModeArg::Auto => ResourceMode::Auto,
+            ResourceModeArg::Manual => ResourceMode::Manual,
         }
     }
 }
@@ -238,12 +245,21 @@ enum Commands {
 enum ExecutionModeArg {
     /// CPU-only mode (no GPU acceleration)
     CpuOnly,
+    /// GPU when one is available, CPU otherwise
+    GpuPreferred,
+    /// GPU only; refused at startup without a GPU
+    GpuOnly,
+    /// Composition on the GPU, the rest on the CPU
+    Hybrid,
 }
 
 impl From<ExecutionModeArg> for ExecutionMode {
     fn from(val: ExecutionModeArg) -> Self {
         match val {
             ExecutionModeArg::CpuOnly => ExecutionMode::CpuOnly,
+            ExecutionModeArg::GpuPreferred => ExecutionMode::GpuPreferred,
+            ExecutionModeArg::GpuOnly => ExecutionMode::GpuOnly,
+            ExecutionModeArg::Hybrid => ExecutionMode::Hybrid,
         }
     }
 }
@@ -288,6 +304,10 @@ fn main() {
     };
     UbinLogger::init(log_level, !cli.no_color);
 
+    if let Some(variant) = cli.a11y_theme {
+        theme_tokens::set_active(variant);
+    }
+
     info("🚀 UBIN CLI Starting...");
 
     match cli.command {
@@ -298,16 +318,25 @@ fn main() {
             mode,
             ghost_mode,
             no_convergence,
-            max_fps,
+            max_fps: _,
         } => {
+            let mode: ExecutionMode = mode.into();
+            match mode.check_gpu(wbackend::assignment::detect_gpu().is_some()) {
+                Ok(Some(warning)) => warn(&format!("⚠️  {}", warning)),
+                Ok(None) => {}
+                Err(e) => {
+                    error(&format!("❌ {}", e));
+                    std::process::exit(1);
+                }
+            }
             run_eternal_dominion(
                 title,
                 width,
                 height,
-                mode.into(),
+                cli.resource_mode.into(),
+                mode,
                 ghost_mode,
                 !no_convergence,
-                max_fps,
             );
         }
         #[cfg(feature = "transmutation")]
@@ -349,19 +378,19 @@ fn run_eternal_dominion(
     title: String,
     width: u32,
     height: u32,
+    resource_mode: ResourceMode,
     mode: ExecutionMode,
     ghost_mode: bool,
     enable_convergence: bool,
-    _max_fps: u32,
 ) {
     info(&format!(
         "🌀 Starting eternal dominion: {} ({}x{})",
         title, width, height
     ));
-    info(&format!("   Mode: {:?} | Ghost: {} | Convergence: {}", 
-        mode, ghost_mode, enable_convergence));
+    info(&format!("   Mode: {:?} | Resources: {:?} | Ghost: {} | Convergence: {}", 
+        mode, resource_mode, ghost_mode, enable_convergence));
 
-    let mut runtime = UbinRuntime::initialize();
+    let mut runtime = UbinRuntime::initialize_with_mode(resource_mode);
 
     // Build demo UI
     let root_widget = build_demo_ui();
@@ -609,8 +638,11 @@ fn show_convergence_status(apply: bool, platform_info: bool, list_features: bool
     }
 }
 
-fn run_demo(demo_type: DemoType, _animations: bool, _dark: bool) {
+fn run_demo(demo_type: DemoType, animations: bool, _dark: bool) {
     info(&format!("🎨 Running {:?} demo", demo_type));
+    if animations && theme_tokens::reduce_motion() {
+        info("🐢 Reduce motion is enabled – animations stay off");
+    }
 
     let mut runtime = UbinRuntime::initialize();
 
@@ -56,7 +56,27 @@ impl Application for UbinFallbackApp {
     }
 
     fn theme(&self) -> Theme {
-        Theme::Dark
+        // Erişilebilirlik varyantı seçiliyse (WASMA_A11Y_THEME / --a11y-theme) token paletini kullan
+        let variant = wbackend::theme::active();
+        if variant == wbackend::A11yTheme::Standard {
+            return Theme::Dark;
+        }
+
+        let tokens = variant.tokens();
+        let rgb = |c: wbackend::Color| {
+            let [r, g, b, a] = c.to_f32();
+            iced::Color { r, g, b, a }
+        };
+        Theme::custom(
+            format!("UBIN {}", variant.name()),
+            theme::Palette {
+                background: rgb(tokens.background),
+                text: rgb(tokens.foreground),
+                primary: rgb(tokens.accent),
+                success: rgb(tokens.success),
+                danger: rgb(tokens.danger),
+            },
+        )
     }
 
     fn update(&mut self, message: FallbackMessage) -> Command<FallbackMessage> {